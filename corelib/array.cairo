@@ -1,7 +1,23 @@
 extern type Array<T>;
 extern func array_new<T>() -> Array::<T> nopanic;
+// Same as `array_new`, with a hint of the number of elements the array will hold.
+extern func array_new_with_capacity<T>(capacity: u128) -> Array::<T> nopanic;
+// Creates the panic data array holding `data`, by a routine shared by all the calls.
+extern func panic_array_new(data: felt) -> Array::<felt> nopanic;
 extern func array_append<T>(ref arr: Array::<T>, value: T) nopanic;
 extern func array_at<T>(
     ref arr: Array::<T>, index: u128
 ) -> Option::<T> implicits(RangeCheck) nopanic;
+// Same as `array_at`, returning the element as a pointer into the array rather than a copy.
+extern func array_get<T>(
+    ref arr: Array::<T>, index: u128
+) -> Option::<Box::<T>> implicits(RangeCheck) nopanic;
 extern func array_len<T>(ref arr: Array::<T>) -> u128 nopanic;
+// Same as `array_len`, range checking the length. Used in place of `array_len` when the compiler is
+// configured to check array views.
+extern func array_len_checked<T>(ref arr: Array::<T>) -> u128 implicits(RangeCheck) nopanic;
+// Returns a copy of the array, with its elements copied into a new array, or None if there is not
+// enough gas to copy them.
+extern func array_clone<T>(
+    ref arr: Array::<T>
+) -> Option::<Array::<T>> implicits(RangeCheck, GasBuiltin) nopanic;
@@ -0,0 +1 @@
+extern func debug_print(ref message: Array::<felt>) nopanic;
@@ -1,5 +1,6 @@
 extern type BuiltinCosts;
 extern type GasBuiltin;
+extern type GasReserve;
 
 impl BuiltinCostsCopy of Copy::<BuiltinCosts>;
 impl BuiltinCostsDrop of Drop::<BuiltinCosts>;
@@ -8,3 +9,16 @@ extern func get_gas() -> Option::<()> implicits(RangeCheck, GasBuiltin) nopanic;
 extern func get_gas_all(
     costs: BuiltinCosts
 ) -> Option::<()> implicits(RangeCheck, GasBuiltin) nopanic;
+// Withdraws the gas required by the rest of the function, and returns the remaining gas, draining
+// the gas counter.
+extern func withdraw_gas_all(
+    costs: BuiltinCosts
+) -> Option::<felt> implicits(RangeCheck, GasBuiltin) nopanic;
+
+// Withdraws `amount` gas from the gas counter into a reserve, which must be released back into the
+// counter by the same function, before it ends. A reserve cannot be passed to other functions, nor
+// be part of an enum or a struct, so the result must be matched directly.
+extern func gas_reserve_create<amount>() -> Option::<GasReserve> implicits(
+    RangeCheck, GasBuiltin
+) nopanic;
+extern func gas_reserve_release(reserve: GasReserve) implicits(GasBuiltin) nopanic;
@@ -92,13 +92,7 @@ func u128_checked_sub(a: u128, b: u128) -> Option::<u128> implicits(RangeCheck)
 }
 
 #[panic_with('u128_mul OF', u128_mul)]
-func u128_checked_mul(a: u128, b: u128) -> Option::<u128> implicits(RangeCheck) nopanic {
-    let (bottom_word, top_word) = u128_wide_mul(a, b);
-    match u128_to_felt(top_word) {
-        0 => Option::<u128>::Some(bottom_word),
-        _ => Option::<u128>::None(()),
-    }
-}
+extern func u128_checked_mul(a: u128, b: u128) -> Option::<u128> implicits(RangeCheck) nopanic;
 
 impl NonZeroU128Copy of Copy::<NonZero::<u128>>;
 impl NonZeroU128Drop of Drop::<NonZero::<u128>>;
@@ -120,8 +120,11 @@ use box::unbox;
 mod array;
 use array::Array;
 use array::array_new;
+use array::array_new_with_capacity;
+use array::panic_array_new;
 use array::array_append;
 use array::array_at;
+use array::array_get;
 use array::array_len;
 
 // Result.
@@ -170,8 +173,12 @@ use integer::Bitwise;
 mod gas;
 use gas::BuiltinCosts;
 use gas::GasBuiltin;
+use gas::GasReserve;
 use gas::get_gas;
 use gas::get_gas_all;
+use gas::gas_reserve_create;
+use gas::gas_reserve_release;
+use gas::withdraw_gas_all;
 
 // Panics.
 enum PanicResult<T> { Ok: T, Err: Array::<felt>, }
@@ -180,9 +187,7 @@ extern func panic(data: Array::<felt>) -> never;
 
 func assert(cond: bool, err_code: felt) {
     if !cond {
-        let mut data = array_new::<felt>();
-        array_append::<felt>(data, err_code);
-        panic(data);
+        panic(panic_array_new(err_code));
     }
 }
 
@@ -196,4 +201,7 @@ use hash::Pedersen;
 mod starknet;
 use starknet::System;
 
+// Debug.
+mod debug;
+
 mod test;
@@ -2,7 +2,113 @@ extern type System;
 extern type StorageAddress;
 
 extern func storage_address_const<address>() -> StorageAddress nopanic;
-extern func storage_read_syscall(ref system: System, address: StorageAddress) -> felt nopanic;
+extern type StorageBaseAddress;
+extern func storage_base_addr_const<address>() -> StorageBaseAddress nopanic;
+// Reduces `addr` modulo `2**251 - 256` into a storage base address.
+extern func storage_base_from_felt(addr: felt) -> StorageBaseAddress implicits(RangeCheck) nopanic;
+// Returns the address `offset` cells after `base`. `offset` is expected to be below 256.
+extern func storage_addr_from_base(
+    base: StorageBaseAddress, offset: felt
+) -> StorageAddress nopanic;
+// The storage syscalls take the domain of the storage address - currently only the domain 0.
+extern func storage_read_syscall(
+    ref system: System, address_domain: felt, address: StorageAddress
+) -> Result::<felt, felt> implicits(GasBuiltin) nopanic;
+// Reads a value of up to 256 felts, from consecutive addresses starting at `address`. On failure,
+// returns the index of the read that failed, and its revert reason. Only allowed for functions of
+// trusted contracts configured in the compiler.
+extern func storage_read_multi<T>(
+    ref system: System, address_domain: felt, address: StorageAddress
+) -> Result::<T, (felt, felt)> implicits(GasBuiltin) nopanic;
 extern func storage_write_syscall(
-    ref system: System, address: StorageAddress, value: felt
+    ref system: System, address_domain: felt, address: StorageAddress, value: felt
 ) -> Result::<(), felt> implicits(GasBuiltin) nopanic;
+// Storage access checking the address at runtime, used in place of the above when the compiler is
+// configured to check the storage addresses.
+extern func storage_read_checked(
+    ref system: System, address_domain: felt, address: StorageAddress
+) -> Result::<felt, felt> implicits(RangeCheck, GasBuiltin) nopanic;
+extern func storage_write_checked(
+    ref system: System, address_domain: felt, address: StorageAddress, value: felt
+) -> Result::<(), felt> implicits(RangeCheck, GasBuiltin) nopanic;
+// Unchecked storage access, only allowed for functions of trusted contracts configured in the
+// compiler.
+extern func storage_read_unchecked(
+    ref system: System, address_domain: felt, address: StorageAddress
+) -> felt implicits(GasBuiltin) nopanic;
+extern func storage_write_unchecked(
+    ref system: System, address_domain: felt, address: StorageAddress, value: felt
+) implicits(GasBuiltin) nopanic;
+
+// Emits an event with the given keys and data.
+extern func emit_event_syscall(
+    ref system: System, keys: Array::<felt>, data: Array::<felt>
+) -> Result::<(), felt> implicits(GasBuiltin) nopanic;
+
+extern type ContractAddress;
+extern func contract_address_const<address>() -> ContractAddress nopanic;
+extern func contract_addr_to_felt(address: ContractAddress) -> felt nopanic;
+extern func contract_addr_from_felt(
+    address: felt
+) -> Option::<ContractAddress> implicits(RangeCheck) nopanic;
+
+// Returns the address of the contract that called the current one.
+extern func get_caller_address(ref system: System) -> Result::<ContractAddress, felt> implicits(
+    GasBuiltin
+) nopanic;
+// Returns the address of the current contract.
+extern func get_contract_address(ref system: System) -> Result::<ContractAddress, felt> implicits(
+    GasBuiltin
+) nopanic;
+// Returns the address of the sequencer of the current block.
+extern func get_sequencer_address(ref system: System) -> Result::<ContractAddress, felt> implicits(
+    GasBuiltin
+) nopanic;
+
+// Returns the number of the current block.
+extern func get_block_number(ref system: System) -> Result::<felt, felt> implicits(
+    GasBuiltin
+) nopanic;
+// Returns the timestamp of the current block.
+extern func get_block_timestamp(ref system: System) -> Result::<felt, felt> implicits(
+    GasBuiltin
+) nopanic;
+
+extern type TxInfo;
+// Returns a box of the information of the current transaction.
+extern func get_tx_info(ref system: System) -> Result::<Box::<TxInfo>, felt> implicits(
+    GasBuiltin
+) nopanic;
+
+// Replaces the class of the current contract with the class of the given hash.
+extern func replace_class_syscall(
+    ref system: System, class_hash: felt
+) -> Result::<(), felt> implicits(GasBuiltin) nopanic;
+
+// Computes the keccak of the input, given as 64-bit words, returning the low and high 128 bits of
+// the result.
+extern func keccak_syscall(
+    ref system: System, input: Array::<felt>
+) -> Result::<(felt, felt), felt> implicits(GasBuiltin) nopanic;
+
+// A point on the secp256k1 curve, held by the syscall handler.
+extern type Secp256k1Point;
+impl Secp256k1PointCopy of Copy::<Secp256k1Point>;
+impl Secp256k1PointDrop of Drop::<Secp256k1Point>;
+
+// Creates a point from its coordinates, given as their low and high 128 bits. Returns a flag that
+// is nonzero if the coordinates are of a point on the curve, and the point, which must not be used
+// otherwise.
+extern func secp256k1_new(
+    ref system: System, x_low: u128, x_high: u128, y_low: u128, y_high: u128
+) -> Result::<(felt, Secp256k1Point), felt> implicits(GasBuiltin) nopanic;
+extern func secp256k1_add(
+    ref system: System, p0: Secp256k1Point, p1: Secp256k1Point
+) -> Result::<Secp256k1Point, felt> implicits(GasBuiltin) nopanic;
+extern func secp256k1_mul(
+    ref system: System, point: Secp256k1Point, scalar_low: u128, scalar_high: u128
+) -> Result::<Secp256k1Point, felt> implicits(GasBuiltin) nopanic;
+// Returns the coordinates of the point, as the low and high 128 bits of each.
+extern func secp256k1_get_xy(
+    ref system: System, point: Secp256k1Point
+) -> Result::<(u128, u128, u128, u128), felt> implicits(GasBuiltin) nopanic;
@@ -295,3 +295,18 @@ func test_array_out_of_bound_1() {
 func test_array_out_of_bound_2() {
     test_array_helper(11_u128);
 }
+
+#[test]
+func test_array_with_capacity() {
+    let mut arr = array_new_with_capacity::<felt>(2_u128);
+    assert(array_len::<felt>(arr) == 0_u128, 'array_len(arr) == 0');
+    array_append::<felt>(arr, 10);
+    array_append::<felt>(arr, 11);
+    // Appending beyond the capacity hint is allowed.
+    array_append::<felt>(arr, 12);
+    assert(array_len::<felt>(arr) == 3_u128, 'array_len(arr) == 3');
+    match array_at::<felt>(arr, 2_u128) {
+        Option::Some(x) => assert(x == 12, 'arr[2] == 12'),
+        Option::None(()) => assert(false, 'arr[2] should exist'),
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt::Display;
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use utils::casts::usize_as_i16;
 
@@ -9,7 +10,7 @@ use crate::operand::{BinOpOperand, CellRef, DerefOrImmediate, Register, ResOpera
 #[path = "ap_change_test.rs"]
 mod test;
 
-#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum ApChange {
     Known(usize),
     Unknown,
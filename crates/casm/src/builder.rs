@@ -1,15 +1,15 @@
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use num_bigint::BigInt;
 use utils::extract_matches;
 
-use crate::ap_change::ApplyApChange;
+use crate::ap_change::{ApChange, ApplyApChange};
 use crate::deref_or_immediate;
 use crate::hints::Hint;
 use crate::instructions::{
-    AddApInstruction, AssertEqInstruction, Instruction, InstructionBody, JnzInstruction,
-    JumpInstruction,
+    AddApInstruction, AssertEqInstruction, CallInstruction, Instruction, InstructionBody,
+    JnzInstruction, JumpInstruction, RetInstruction,
 };
 use crate::operand::{BinOpOperand, CellRef, DerefOrImmediate, Operation, Register, ResOperand};
 
@@ -30,6 +30,16 @@ pub struct State {
     allocated: i16,
     /// The AP change since the beginging of the run.
     pub ap_change: usize,
+    /// The number of cells each buffer variable was advanced by since the begining of the run.
+    buffer_uses: HashMap<Var, usize>,
+    /// Whether ap was advanced by an unknown amount since the begining of the run, in which case
+    /// `ap_change` and the allocations are counted from the last such advance.
+    unknown_ap_change: bool,
+    /// The local routine the code at this state is a part of, if any.
+    routine: Option<String>,
+    /// The aliases of buffers that may no longer be read, as the buffer they alias was advanced -
+    /// with the advanced buffer and the index of the statement it was advanced before.
+    stale_vars: HashMap<Var, (Var, usize)>,
 }
 impl State {
     /// Returns the value, in relation to the initial ap value.
@@ -47,6 +57,18 @@ impl State {
         extract_matches!(self.get_adjusted(var), ResOperand::Deref)
     }
 
+    /// Returns the ap change since the beginning of the run, which is unknown after a loop or a
+    /// call to a routine with an unknown ap change.
+    pub fn full_ap_change(&self) -> ApChange {
+        if self.unknown_ap_change { ApChange::Unknown } else { ApChange::Known(self.ap_change) }
+    }
+
+    /// Returns the number of cells the buffer `var` was advanced by, such as by writes through
+    /// `*(var++)`. Useful for reporting the usage of builtins.
+    pub fn buffer_uses(&self, var: Var) -> usize {
+        self.buffer_uses.get(&var).copied().unwrap_or_default()
+    }
+
     /// Validates that the state is valid, as it had enough ap change.
     fn validate_finality(&self) {
         assert!(
@@ -63,6 +85,11 @@ impl State {
             self.allocated, other.allocated,
             "Merged branches not aligned on number of allocations."
         );
+        assert_eq!(
+            self.unknown_ap_change, other.unknown_ap_change,
+            "Merged branches not aligned on AP tracking."
+        );
+        assert_eq!(self.routine, other.routine, "Merged branches of different routines.");
         self.vars.retain(|var, value| {
             other
                 .vars
@@ -70,6 +97,16 @@ impl State {
                 .map(|x| assert_eq!(x, value, "Var mismatch between branches."))
                 .is_some()
         });
+        self.buffer_uses.retain(|var, uses| {
+            other
+                .buffer_uses
+                .get(var)
+                .map(|x| assert_eq!(x, uses, "Buffer uses mismatch between branches."))
+                .is_some()
+        });
+        for (var, invalidation) in &other.stale_vars {
+            self.stale_vars.entry(*var).or_insert(*invalidation);
+        }
     }
 }
 
@@ -77,7 +114,7 @@ impl State {
 enum Statement {
     /// A final instruction, no need for further editting.
     Final(Instruction),
-    /// A jump command, requires fixing the actual target label.
+    /// A jump or call command, requires fixing the actual target label.
     Jump(String, Instruction),
     /// A target label for jumps.
     Label(String),
@@ -87,12 +124,14 @@ enum Statement {
 pub struct CasmBuildResult {
     /// The actual casm code.
     pub instructions: Vec<Instruction>,
-    /// The set of instructions still requiring relocations.
-    pub awaiting_relocations: Vec<usize>,
+    /// The set of instructions still requiring relocations, with the labels they jump to.
+    pub awaiting_relocations: Vec<(usize, String)>,
     /// The state at a point of jumping into a label, per label.
     pub label_state: HashMap<String, State>,
     /// The state at the last added statement.
     pub fallthrough_state: State,
+    /// The comments added to the instructions, with the index of the instruction each comments on.
+    pub comments: Vec<(usize, String)>,
 }
 
 /// Builder to more easily write casm code without specifically thinking about ap changes and the
@@ -107,11 +146,26 @@ pub struct CasmBuilder {
     statements: Vec<Statement>,
     /// The current set of added hints.
     current_hints: Vec<Hint>,
+    /// The current set of added comments, to be attached to the next added instruction.
+    current_comments: Vec<String>,
+    /// The added comments, with the index of the statement each comments on.
+    comments: Vec<(usize, String)>,
     /// The number of vars created. Used to not reuse var names.
     var_count: usize,
     /// Is the current state reachable.
     /// Example for unreachable state is after a unconditional jump, before any label is stated.
     reachable: bool,
+    /// The ap change of the frame of each local routine, per routine, as known from its returns
+    /// added so far.
+    routine_ap_changes: HashMap<String, ApChange>,
+    /// The value of each named constant, per variable.
+    consts: HashMap<Var, BigInt>,
+    /// The named constants already used inline.
+    used_inline_consts: HashSet<Var>,
+    /// The variable each variable created by `duplicate_var` aliases, transitively.
+    alias_roots: HashMap<Var, Var>,
+    /// The names of the variables, for reporting invalid usages.
+    var_names: HashMap<Var, String>,
 }
 impl CasmBuilder {
     /// Finalizes the builder.
@@ -124,8 +178,15 @@ impl CasmBuilder {
         let label_offsets = self.compute_label_offsets();
         let mut instructions = vec![];
         let mut awaiting_relocations = vec![];
+        let mut comments = vec![];
+        let mut statement_comments = self.comments.into_iter().peekable();
         let mut offset = 0;
-        for statement in self.statements {
+        for (statement_idx, statement) in self.statements.into_iter().enumerate() {
+            while let Some((_, comment)) =
+                statement_comments.next_if(|(idx, _)| *idx == statement_idx)
+            {
+                comments.push((instructions.len(), comment));
+            }
             match statement {
                 Statement::Final(inst) => {
                     offset += inst.body.op_size();
@@ -141,6 +202,10 @@ impl CasmBuilder {
                             | InstructionBody::Jump(JumpInstruction {
                                 target: DerefOrImmediate::Immediate(value),
                                 ..
+                            })
+                            | InstructionBody::Call(CallInstruction {
+                                target: DerefOrImmediate::Immediate(value),
+                                ..
                             }) => {
                                 // Updating the value, instead of assigning into it, to avoid
                                 // allocating a BigInt since it is already 0.
@@ -150,7 +215,7 @@ impl CasmBuilder {
                             _ => unreachable!("Only jump statements should be here."),
                         },
                         None => {
-                            awaiting_relocations.push(instructions.len());
+                            awaiting_relocations.push((instructions.len(), label));
                         }
                     }
                     offset += inst.body.op_size();
@@ -169,6 +234,7 @@ impl CasmBuilder {
             awaiting_relocations,
             label_state: self.label_state,
             fallthrough_state: self.main_state,
+            comments,
         }
     }
 
@@ -197,6 +263,46 @@ impl CasmBuilder {
         var
     }
 
+    /// Adds a named constant with the value `value`.
+    /// The constant is used inline, until it is used inline a second time or is required to be a
+    /// cell reference - at which point it is materialized into a single cell, used by all the later
+    /// statements instead. A cell asserted to be equal to the constant is used the same way.
+    pub fn add_const(&mut self, value: BigInt) -> Var {
+        let var = self.add_var(ResOperand::Immediate(value.clone()));
+        self.consts.insert(var, value);
+        var
+    }
+
+    /// Returns whether `var` is a named constant that is not yet materialized into a cell.
+    fn is_inline_const(&self, var: Var) -> bool {
+        self.consts.contains_key(&var)
+            && matches!(self.main_state.vars.get(&var), Some(ResOperand::Immediate(_)))
+    }
+
+    /// Materializes `var` into a new cell if it is a named constant not yet materialized.
+    fn materialize_const(&mut self, var: Var) {
+        if self.is_inline_const(var) {
+            let cell = self.alloc_var();
+            self.add_assert_eq_statement(cell, var);
+            let value = self.get_value(cell, false);
+            self.main_state.vars.insert(var, value);
+        }
+    }
+
+    /// Records an inline usage of `var`, materializing it first if it is a named constant that was
+    /// already used inline.
+    fn use_inline_const(&mut self, var: Var) {
+        if self.is_inline_const(var) && !self.used_inline_consts.insert(var) {
+            self.materialize_const(var);
+        }
+    }
+
+    /// Adds a comment, describing the next added instruction. Comments added after the last
+    /// instruction are dropped.
+    pub fn add_comment(&mut self, comment: String) {
+        self.current_comments.push(comment);
+    }
+
     /// Allocates a new variable in memory.
     pub fn alloc_var(&mut self) -> Var {
         let var = self.add_var(ResOperand::Deref(CellRef {
@@ -207,9 +313,63 @@ impl CasmBuilder {
         var
     }
 
+    /// Returns the cell of `var`, in relation to the initial ap value - or `None` if ap was
+    /// advanced by an unknown amount since the beginning of the run.
+    /// `var` must be a cell reference.
+    pub fn initial_cell_ref(&self, var: Var) -> Option<CellRef> {
+        if self.main_state.unknown_ap_change { None } else { Some(self.as_cell_ref(var, false)) }
+    }
+
     /// Returns an additional variable pointing to the same value.
+    /// If `var` is a buffer, the new variable shares its uses so far, and is an alias of it - once
+    /// either of them is advanced, the other may no longer be read, until rebound by `rebind_var`.
     pub fn duplicate_var(&mut self, var: Var) -> Var {
-        self.add_var(self.get_value(var, false))
+        let new_var = self.snapshot_var(var);
+        self.alias_roots.insert(new_var, self.alias_root(var));
+        new_var
+    }
+
+    /// Returns an additional variable pointing to the same value, which is not an alias of `var`,
+    /// so it remains readable after `var` is advanced - such as for keeping the start of a buffer
+    /// for a hint.
+    /// If `var` is a buffer, the new variable shares its uses so far.
+    pub fn snapshot_var(&mut self, var: Var) -> Var {
+        let new_var = self.add_var(self.get_value(var, false));
+        if let Some(uses) = self.main_state.buffer_uses.get(&var).copied() {
+            self.main_state.buffer_uses.insert(new_var, uses);
+        }
+        new_var
+    }
+
+    /// Points `var` to the current value of `source`, making it an alias of `source` which may be
+    /// read again, even if it was invalidated by advancing `source`.
+    pub fn rebind_var(&mut self, var: Var, source: Var) {
+        let value = self.get_value(source, false);
+        self.main_state.vars.insert(var, value);
+        match self.main_state.buffer_uses.get(&source).copied() {
+            Some(uses) => self.main_state.buffer_uses.insert(var, uses),
+            None => self.main_state.buffer_uses.remove(&var),
+        };
+        self.main_state.stale_vars.remove(&var);
+        let root = self.alias_root(source);
+        if root != var {
+            self.alias_roots.insert(var, root);
+        }
+    }
+
+    /// Sets the name of `var`, used for reporting its invalid usages.
+    pub fn set_var_name(&mut self, var: Var, name: &str) {
+        self.var_names.insert(var, name.to_owned());
+    }
+
+    /// Returns the variable the alias group of `var` originates from.
+    fn alias_root(&self, var: Var) -> Var {
+        self.alias_roots.get(&var).copied().unwrap_or(var)
+    }
+
+    /// Returns the name of `var` for reporting, if it was named.
+    fn var_name(&self, var: Var) -> String {
+        self.var_names.get(&var).cloned().unwrap_or_else(|| format!("{var:?}"))
     }
 
     /// Adds a hint, generated from `inputs` which are cell refs or immediates and `outputs` which
@@ -239,6 +399,19 @@ impl CasmBuilder {
     /// Adds an assertion that `dst = res`.
     /// `dst` must be a cell reference.
     pub fn assert_vars_eq(&mut self, dst: Var, res: Var) {
+        self.materialize_const(dst);
+        let inline_const = self.is_inline_const(res);
+        self.add_assert_eq_statement(dst, res);
+        if inline_const {
+            // The constant is held in the cell of `dst` from now on, so this usage does not count
+            // as an inline usage.
+            let value = self.get_value(dst, false);
+            self.main_state.vars.insert(res, value);
+        }
+    }
+
+    /// Adds the statement of an assertion that `dst` equals `res`.
+    fn add_assert_eq_statement(&mut self, dst: Var, res: Var) {
         let a = self.as_cell_ref(dst, true);
         let b = self.get_value(res, true);
         let instruction =
@@ -256,9 +429,45 @@ impl CasmBuilder {
         self.assert_vars_eq(value, location);
     }
 
+    /// Writes `value` to the cell `offset` cells after the address in `ptr`.
+    /// Unlike buffer writes, `ptr` is left unchanged.
+    /// `ptr` must be a cell reference, or a cell reference with a small added constant - which is
+    /// folded into the offset of the write, so it does not need to be materialized first.
+    /// `value` must be a cell reference.
+    pub fn write_at_offset(&mut self, ptr: Var, offset: i16, value: Var) {
+        let location = self.double_deref(ptr, offset);
+        self.assert_vars_eq(value, location);
+    }
+
+    /// Advances a buffer by `count` cells, without accessing them.
+    /// Useful for skipping past a whole record, which is then read at negative offsets.
+    /// `buffer` must be a cell reference, or a cell reference with a small added constant.
+    pub fn buffer_advance(&mut self, buffer: Var, count: i16) {
+        let (base, offset) = self.as_cell_ref_plus_const(buffer, count, false);
+        self.add_buffer_uses(buffer, count.try_into().expect("Buffers may only advance forward."));
+        self.main_state.vars.insert(
+            buffer,
+            ResOperand::BinOp(BinOpOperand {
+                op: Operation::Add,
+                a: base,
+                b: deref_or_immediate!(offset),
+            }),
+        );
+    }
+
+    /// Returns a variable that is the cell `distance` cells before the current position of
+    /// `buffer`, leaving the buffer unchanged.
+    /// The read cell must be one the buffer was already advanced past, so `distance` must be
+    /// positive - such reads require no additional slack from the buffer.
+    pub fn buffer_read_back(&mut self, buffer: Var, distance: i16) -> Var {
+        assert!(distance > 0, "Reading back from a buffer requires a positive distance.");
+        self.double_deref(buffer, -distance)
+    }
+
     /// Increments a buffer and allocates and returns variable pointing to its previous value.
     pub fn get_ref_and_inc(&mut self, buffer: Var) -> Var {
         let (cell, offset) = self.as_cell_ref_plus_const(buffer, 0, false);
+        self.add_buffer_uses(buffer, 1);
         self.main_state.vars.insert(
             buffer,
             ResOperand::BinOp(BinOpOperand {
@@ -283,6 +492,7 @@ impl CasmBuilder {
             }) => (a, imm.try_into().expect("Too many buffer writes.")),
             _ => panic!("Not a valid buffer."),
         };
+        self.add_buffer_uses(buffer, 1);
         self.main_state.vars.insert(
             buffer,
             ResOperand::BinOp(BinOpOperand {
@@ -294,6 +504,20 @@ impl CasmBuilder {
         (base, offset)
     }
 
+    /// Records that `buffer` was advanced by `count` additional cells, invalidating its aliases.
+    fn add_buffer_uses(&mut self, buffer: Var, count: usize) {
+        *self.main_state.buffer_uses.entry(buffer).or_default() += count;
+        let root = self.alias_root(buffer);
+        let aliases: Vec<Var> = std::iter::once(root)
+            .chain(self.alias_roots.iter().filter(|(_, r)| **r == root).map(|(alias, _)| *alias))
+            .filter(|alias| *alias != buffer)
+            .collect();
+        let statement_idx = self.statements.len();
+        for alias in aliases {
+            self.main_state.stale_vars.insert(alias, (buffer, statement_idx));
+        }
+    }
+
     /// Increases AP by `size`.
     pub fn add_ap(&mut self, size: usize) {
         let instruction = self.get_instruction(
@@ -307,13 +531,49 @@ impl CasmBuilder {
     }
 
     /// Returns a variable that is the `op` of `lhs` and `rhs`.
-    /// `lhs` must be a cell reference and `rhs` must be deref or immediate.
+    /// `lhs` must be a cell reference and `rhs` must be deref or immediate, unless the operation is
+    /// folded at build time:
+    /// * The operation of two immediates is the immediate result.
+    /// * Adding 0 or multiplying by 1 is an alias of the other operand.
+    /// * An immediate `lhs` is swapped with a cell reference `rhs`.
+    /// * Adding an immediate to a cell reference plus an immediate sums the immediates.
     pub fn bin_op(&mut self, op: Operation, lhs: Var, rhs: Var) -> Var {
-        self.add_var(ResOperand::BinOp(BinOpOperand {
-            op,
-            a: self.as_cell_ref(lhs, false),
-            b: self.as_deref_or_imm(rhs, false),
-        }))
+        let value = match (self.get_value(lhs, false), self.get_value(rhs, false)) {
+            (ResOperand::Immediate(a), ResOperand::Immediate(b)) => {
+                ResOperand::Immediate(match op {
+                    Operation::Add => a + b,
+                    Operation::Mul => a * b,
+                })
+            }
+            (value, ResOperand::Immediate(imm)) | (ResOperand::Immediate(imm), value)
+                if is_identity(&op, &imm) =>
+            {
+                value
+            }
+            (ResOperand::Immediate(_), ResOperand::Deref(_)) => return self.bin_op(op, rhs, lhs),
+            (
+                ResOperand::BinOp(BinOpOperand {
+                    op: Operation::Add,
+                    a,
+                    b: DerefOrImmediate::Immediate(lhs_imm),
+                }),
+                ResOperand::Immediate(rhs_imm),
+            ) if op == Operation::Add => ResOperand::BinOp(BinOpOperand {
+                op,
+                a,
+                b: DerefOrImmediate::Immediate(lhs_imm + rhs_imm),
+            }),
+            _ => {
+                self.materialize_const(lhs);
+                self.use_inline_const(rhs);
+                ResOperand::BinOp(BinOpOperand {
+                    op,
+                    a: self.as_cell_ref(lhs, false),
+                    b: self.as_deref_or_imm(rhs, false),
+                })
+            }
+        };
+        self.add_var(value)
     }
 
     /// Returns a variable that is `[[var] + offset]`.
@@ -325,9 +585,20 @@ impl CasmBuilder {
 
     /// Sets the label to have the set states, otherwise tests if the state matches the existing one
     /// by merging.
-    fn set_or_test_label_state(&mut self, label: String, state: State) {
+    fn set_or_test_label_state(&mut self, label: String, mut state: State) {
         match self.label_state.entry(label) {
             Entry::Occupied(mut e) => {
+                // Named constants held in different cells by the merged branches are used inline.
+                for (var, value) in &self.consts {
+                    if let (Some(existing), Some(new)) =
+                        (e.get().vars.get(var), state.vars.get(var))
+                    {
+                        if existing != new {
+                            e.get_mut().vars.insert(*var, ResOperand::Immediate(value.clone()));
+                            state.vars.insert(*var, ResOperand::Immediate(value.clone()));
+                        }
+                    }
+                }
                 e.get_mut().intersect(&state);
             }
             Entry::Vacant(e) => {
@@ -338,6 +609,15 @@ impl CasmBuilder {
 
     /// Add a statement to jump to `label`.
     pub fn jump(&mut self, label: String) {
+        self.add_jump_statement(label.clone());
+        let mut state = State::default();
+        std::mem::swap(&mut state, &mut self.main_state);
+        self.label_state.insert(label, state);
+        self.reachable = false;
+    }
+
+    /// Adds the statement of a jump to `label`, without tracking the state at the label.
+    fn add_jump_statement(&mut self, label: String) {
         let instruction = self.get_instruction(
             InstructionBody::Jump(JumpInstruction {
                 target: deref_or_immediate!(0),
@@ -345,16 +625,19 @@ impl CasmBuilder {
             }),
             true,
         );
-        self.statements.push(Statement::Jump(label.clone(), instruction));
-        let mut state = State::default();
-        std::mem::swap(&mut state, &mut self.main_state);
-        self.label_state.insert(label, state);
-        self.reachable = false;
+        self.statements.push(Statement::Jump(label, instruction));
     }
 
     /// Add a statement to jump to `label` if `condition != 0`.
     /// `condition` must be a cell reference.
     pub fn jump_nz(&mut self, condition: Var, label: String) {
+        self.add_jump_nz_statement(condition, label.clone());
+        self.set_or_test_label_state(label, self.main_state.clone());
+    }
+
+    /// Adds the statement of a jump to `label` if `condition != 0`, without tracking the state at
+    /// the label.
+    fn add_jump_nz_statement(&mut self, condition: Var, label: String) {
         let cell = self.as_cell_ref(condition, true);
         let instruction = self.get_instruction(
             InstructionBody::Jnz(JnzInstruction {
@@ -363,14 +646,281 @@ impl CasmBuilder {
             }),
             true,
         );
-        self.statements.push(Statement::Jump(label.clone(), instruction));
-        self.set_or_test_label_state(label, self.main_state.clone());
+        self.statements.push(Statement::Jump(label, instruction));
+    }
+
+    /// Copies the cells from `src_start` up to `src_end` into the cells starting at `dst`, using a
+    /// loop copying a cell per iteration. Returns a variable pointing right after the copied cells
+    /// at the destination.
+    ///
+    /// As every iteration advances ap, the ap change of the loop is not known at compile time. The
+    /// state is therefore rescoped to the end of the loop - `ap_change` counts from there on, and
+    /// the variables based on ap are dropped, other than those in `carried`, which are passed
+    /// through the iterations of the loop so they remain usable after it.
+    /// `src_start`, `src_end` and `dst` must be cell references, and all the allocated variables
+    /// must be behind ap.
+    pub fn copy_range(&mut self, src_start: Var, src_end: Var, dst: Var, carried: &[Var]) -> Var {
+        self.validate_no_pending_allocations();
+        let loop_label = format!("CopyRangeLoop{}", self.var_count);
+        let end_label = format!("CopyRangeEnd{}", self.var_count);
+        // The loop state - the carried values, the current source and destination pointers and
+        // the number of cells left to copy - is written before the loop and by every iteration of
+        // it, so it is always at the last cells before ap.
+        let loop_state_size = carried.len() + 3;
+        let mut outer_vars: HashMap<Var, ResOperand> = self
+            .main_state
+            .vars
+            .iter()
+            .filter(|(_, value)| value.can_apply_unknown())
+            .map(|(var, value)| (*var, value.clone()))
+            .collect();
+        restore_dropped_consts(&self.consts, &mut outer_vars);
+        let one = self.add_var(ResOperand::Immediate(BigInt::from(1)));
+        let buffer_uses = self.main_state.buffer_uses.clone();
+        let mut loop_state = vec![];
+        for var in carried {
+            let cell = self.alloc_var();
+            self.assert_vars_eq(cell, *var);
+            loop_state.push(cell);
+        }
+        let src = self.alloc_var();
+        self.assert_vars_eq(src, src_start);
+        let dst_ptr = self.alloc_var();
+        self.assert_vars_eq(dst_ptr, dst);
+        let remaining = self.alloc_var();
+        let remaining_plus_start = self.bin_op(Operation::Add, remaining, src_start);
+        self.assert_vars_eq(src_end, remaining_plus_start);
+        loop_state.extend([src, dst_ptr, remaining]);
+        self.add_jump_nz_statement(remaining, loop_label.clone());
+        self.add_jump_statement(end_label.clone());
+
+        // A single iteration, copying the cell at `src` and writing the next loop state.
+        self.statements.push(Statement::Label(loop_label.clone()));
+        self.main_state = State {
+            vars: loop_state_vars(&loop_state, &outer_vars),
+            allocated: 0,
+            ap_change: 0,
+            buffer_uses: buffer_uses.clone(),
+            unknown_ap_change: true,
+            routine: self.main_state.routine.clone(),
+            stale_vars: self.main_state.stale_vars.clone(),
+        };
+        self.main_state.vars.insert(one, ResOperand::Immediate(BigInt::from(1)));
+        let value = self.alloc_var();
+        let src_cell = self.double_deref(src, 0);
+        self.assert_vars_eq(value, src_cell);
+        let dst_cell = self.double_deref(dst_ptr, 0);
+        self.assert_vars_eq(value, dst_cell);
+        let mut next_loop_state = vec![];
+        for prev in &loop_state[..carried.len()] {
+            let cell = self.alloc_var();
+            self.assert_vars_eq(cell, *prev);
+            next_loop_state.push(cell);
+        }
+        let next_src = self.alloc_var();
+        let src_plus_one = self.bin_op(Operation::Add, src, one);
+        self.assert_vars_eq(next_src, src_plus_one);
+        let next_dst = self.alloc_var();
+        let dst_plus_one = self.bin_op(Operation::Add, dst_ptr, one);
+        self.assert_vars_eq(next_dst, dst_plus_one);
+        let next_remaining = self.alloc_var();
+        let next_remaining_plus_one = self.bin_op(Operation::Add, next_remaining, one);
+        self.assert_vars_eq(remaining, next_remaining_plus_one);
+        next_loop_state.extend([next_src, next_dst, next_remaining]);
+        assert_eq!(next_loop_state.len(), loop_state_size);
+        self.add_jump_nz_statement(next_remaining, loop_label);
+
+        // Both the skipping of the loop and its last iteration end with the loop state at the last
+        // cells before ap.
+        self.statements.push(Statement::Label(end_label));
+        self.main_state = State {
+            vars: loop_state_vars(&[carried, &[src, dst_ptr, remaining]].concat(), &outer_vars),
+            allocated: 0,
+            ap_change: 0,
+            buffer_uses,
+            unknown_ap_change: true,
+            routine: self.main_state.routine.clone(),
+            stale_vars: self.main_state.stale_vars.clone(),
+        };
+        dst_ptr
+    }
+
+    /// Returns variables holding the quotient and the remainder of the division of `lhs` by `rhs`.
+    /// The values are supplied by a hint, and verified by writing 4 values to `range_check`: the
+    /// quotient, the remainder, `rhs - remainder - 1` - verifying the remainder is smaller than
+    /// `rhs`, so a zero `rhs` fails the verification - and a bound on the smaller of `rhs` and the
+    /// quotient, verifying their product does not overflow.
+    /// `lhs` and `rhs` must be cell references to values in the range [0, 2**128), and
+    /// `range_check` must be a buffer.
+    pub fn div_mod(&mut self, lhs: Var, rhs: Var, range_check: Var) -> (Var, Var) {
+        let q_is_small_label = format!("DivModQIsSmall{}", self.var_count);
+        let verify_bq_label = format!("DivModVerifyBQ{}", self.var_count);
+        let builder = self;
+        let u128_bound_minus_u64_bound = builder
+            .add_var(ResOperand::Immediate(BigInt::from(u128::MAX) - BigInt::from(u64::MAX)));
+        let u64_bound = builder.add_var(ResOperand::Immediate(BigInt::from(u64::MAX) + 1));
+        let one = builder.add_var(ResOperand::Immediate(BigInt::from(1)));
+        crate::casm_build_extend! {builder,
+            tempvar r_plus_1;
+            tempvar b_minus_r_minus_1;
+            tempvar q_is_small;
+            tempvar b_or_q_bound_rc_value;
+            tempvar bq;
+            tempvar q;
+            tempvar r;
+            hint DivMod { lhs: lhs, rhs: rhs } into { quotient: q, remainder: r };
+            // Both `q` and `r` must be in the range [0, 2**128).
+            // `r` must be checked explicitly: `0 <= b - (r + 1)` and `b * q + r = a` are later
+            // checked, however if `r = -1` both checks may pass (say, if `b = a + 1` and `q = 1`).
+            // `q` must also be checked explicitly, as the only arithmetic constraint on it is
+            // `b * q + r = a`, which for `b = 2`, `a = 1` and `r = 0` passes with the inverse of 2
+            // (`(PRIME + 1) / 2`, much larger than 2**128) as `q`.
+            assert *(range_check++) = q;
+            assert *(range_check++) = r;
+            // Verify `r < b` by constraining `0 <= b - (r + 1)`.
+            assert r_plus_1 = r + one;
+            assert rhs = b_minus_r_minus_1 + r_plus_1;
+            assert *(range_check++) = b_minus_r_minus_1;
+            // As both `b` and `q` may be up to 2**128 - 1, `b * q` may overflow. Since it must be
+            // less than 2**128 on success, `min(b, q)` must be less than 2**64 - the smaller one is
+            // guessed and verified.
+            hint TestLessThan { lhs: q, rhs: u64_bound } into { dst: q_is_small };
+        };
+        builder.jump_nz(q_is_small, q_is_small_label.clone());
+        // `q >= 2**64`, so `b < 2**64` is verified by range checking `2**128 - 2**64 + b`.
+        crate::casm_build_extend! {builder,
+            assert b_or_q_bound_rc_value = rhs + u128_bound_minus_u64_bound;
+        };
+        builder.jump(verify_bq_label.clone());
+        builder.label(q_is_small_label);
+        // `q < 2**64`, verified by range checking `2**128 - 2**64 + q`.
+        crate::casm_build_extend! {builder,
+            assert b_or_q_bound_rc_value = q + u128_bound_minus_u64_bound;
+        };
+        builder.label(verify_bq_label);
+        crate::casm_build_extend! {builder,
+            assert *(range_check++) = b_or_q_bound_rc_value;
+            // Range validations done - verify `b * q + r = a`.
+            assert bq = rhs * q;
+            assert lhs = bq + r;
+        };
+        (q, r)
+    }
+
+    /// Adds a call to the routine at `label`, which is expected to be outside of the built code, so
+    /// the call awaits relocation.
+    /// The routine is expected to advance ap by `ap_change` in its frame, and to return
+    /// `outputs_count` values in the last cells it advanced over. Returns variables pointing to the
+    /// returned values.
+    pub fn call(&mut self, label: String, ap_change: usize, outputs_count: usize) -> Vec<Var> {
+        self.validate_no_pending_allocations();
+        self.add_call_statement(label);
+        // The call frame starts with the previous fp and the return address.
+        self.main_state.ap_change += 2 + ap_change;
+        self.main_state.allocated = self.main_state.ap_change as i16;
+        let first_output = self.main_state.allocated - outputs_count as i16;
+        (first_output..self.main_state.allocated)
+            .map(|offset| {
+                self.add_var(ResOperand::Deref(CellRef { offset, register: Register::AP }))
+            })
+            .collect()
+    }
+
+    /// Adds a call to the local routine `label`, started by `routine` either before or after the
+    /// call, which is expected to return `outputs_count` values in the last cells it advanced over.
+    /// Returns variables pointing to the returned values.
+    /// If the ap change of the routine is not known at the call - as it is started after the call,
+    /// or its returns do not agree on it - the ap change of the state becomes unknown, and only the
+    /// variables not based on ap are kept.
+    pub fn call_routine(&mut self, label: String, outputs_count: usize) -> Vec<Var> {
+        if let Some(ApChange::Known(ap_change)) = self.routine_ap_changes.get(&label).copied() {
+            return self.call(label, ap_change, outputs_count);
+        }
+        self.validate_no_pending_allocations();
+        self.add_call_statement(label);
+        self.main_state.vars.retain(|_, value| value.can_apply_unknown());
+        restore_dropped_consts(&self.consts, &mut self.main_state.vars);
+        self.main_state.ap_change = 0;
+        self.main_state.allocated = 0;
+        self.main_state.unknown_ap_change = true;
+        (-(outputs_count as i16)..0)
+            .map(|offset| {
+                self.add_var(ResOperand::Deref(CellRef { offset, register: Register::AP }))
+            })
+            .collect()
+    }
+
+    /// Adds the statement of a call to `label`.
+    fn add_call_statement(&mut self, label: String) {
+        let instruction = self.get_instruction(
+            InstructionBody::Call(CallInstruction {
+                target: deref_or_immediate!(0),
+                relative: true,
+            }),
+            false,
+        );
+        self.statements.push(Statement::Jump(label, instruction));
+    }
+
+    /// Starts the local routine `name`, to be called using `call_routine`. The routine runs in a
+    /// frame of its own, so only immediate variables are kept, and its arguments should be added as
+    /// variables relative to fp. The code before the routine must not fall through into it.
+    pub fn routine(&mut self, name: String) {
+        assert!(!self.reachable, "Routine {name} is reachable from the code before it.");
+        let mut vars = self
+            .main_state
+            .vars
+            .iter()
+            .filter(|(_, value)| matches!(value, ResOperand::Immediate(_)))
+            .map(|(var, value)| (*var, value.clone()))
+            .collect();
+        restore_dropped_consts(&self.consts, &mut vars);
+        self.main_state = State { vars, routine: Some(name.clone()), ..Default::default() };
+        self.statements.push(Statement::Label(name));
+        self.reachable = true;
+    }
+
+    /// Adds a return from the current function.
+    /// If the current function is a local routine, its ap change is recorded for later calls.
+    pub fn ret(&mut self) {
+        self.validate_no_pending_allocations();
+        if let Some(routine) = self.main_state.routine.clone() {
+            let ap_change = self.main_state.full_ap_change();
+            match self.routine_ap_changes.entry(routine) {
+                Entry::Occupied(mut e) => {
+                    if *e.get() != ap_change {
+                        e.insert(ApChange::Unknown);
+                    }
+                }
+                Entry::Vacant(e) => {
+                    e.insert(ap_change);
+                }
+            }
+        }
+        let instruction = self.get_instruction(InstructionBody::Ret(RetInstruction {}), false);
+        self.statements.push(Statement::Final(instruction));
+        self.reachable = false;
+    }
+
+    /// Validates that all the allocated variables are behind ap, as required before a call or a
+    /// return.
+    fn validate_no_pending_allocations(&self) {
+        assert_eq!(
+            self.main_state.ap_change, self.main_state.allocated as usize,
+            "Allocated variables must be behind ap, add `add_ap` calls."
+        );
     }
 
     /// Adds a label here named `name`.
     pub fn label(&mut self, name: String) {
         if self.reachable {
             self.set_or_test_label_state(name.clone(), self.main_state.clone());
+            let merged_vars = &self.label_state[&name].vars;
+            for var in self.consts.keys() {
+                if let Some(value) = merged_vars.get(var) {
+                    self.main_state.vars.insert(*var, value.clone());
+                }
+            }
         } else {
             self.main_state = self
                 .label_state
@@ -384,6 +934,15 @@ impl CasmBuilder {
 
     /// Returns `var`s value, with fixed ap if `adjust_ap` is true.
     fn get_value(&self, var: Var, adjust_ap: bool) -> ResOperand {
+        if let Some((buffer, statement_idx)) = self.main_state.stale_vars.get(&var) {
+            panic!(
+                "`{}` is a stale alias of `{}`, which was advanced before statement {}. Use \
+                 `rebind` to read the current value, or `snapshot` to keep the original one.",
+                self.var_name(var),
+                self.var_name(*buffer),
+                statement_idx
+            );
+        }
         if adjust_ap { self.main_state.get_adjusted(var) } else { self.main_state.get_value(var) }
     }
 
@@ -430,10 +989,44 @@ impl CasmBuilder {
         }
         let mut hints = vec![];
         std::mem::swap(&mut hints, &mut self.current_hints);
+        // The instruction is the next added statement.
+        let statement_idx = self.statements.len();
+        self.comments
+            .extend(self.current_comments.drain(..).map(|comment| (statement_idx, comment)));
         Instruction { body, inc_ap, hints }
     }
 }
 
+/// Adds the named constants of `consts` that were dropped from `vars` back as immediates, as the
+/// cells that held them are no longer accessible.
+fn restore_dropped_consts(consts: &HashMap<Var, BigInt>, vars: &mut HashMap<Var, ResOperand>) {
+    for (var, value) in consts {
+        vars.entry(*var).or_insert_with(|| ResOperand::Immediate(value.clone()));
+    }
+}
+
+/// Returns whether `op` with `imm` as an operand results in the other operand.
+fn is_identity(op: &Operation, imm: &BigInt) -> bool {
+    match op {
+        Operation::Add => *imm == BigInt::from(0),
+        Operation::Mul => *imm == BigInt::from(1),
+    }
+}
+
+/// Returns the variables of a state whose last cells before ap hold `loop_state`, in order, along
+/// with `outer_vars`.
+fn loop_state_vars(
+    loop_state: &[Var],
+    outer_vars: &HashMap<Var, ResOperand>,
+) -> HashMap<Var, ResOperand> {
+    let loop_state_size = loop_state.len() as i16;
+    let mut vars = outer_vars.clone();
+    for (offset, var) in (-loop_state_size..).zip(loop_state) {
+        vars.insert(*var, ResOperand::Deref(CellRef { register: Register::AP, offset }));
+    }
+    vars
+}
+
 impl Default for CasmBuilder {
     fn default() -> Self {
         Self {
@@ -441,8 +1034,15 @@ impl Default for CasmBuilder {
             main_state: Default::default(),
             statements: Default::default(),
             current_hints: Default::default(),
+            current_comments: Default::default(),
+            comments: Default::default(),
             var_count: Default::default(),
             reachable: true,
+            routine_ap_changes: Default::default(),
+            consts: Default::default(),
+            used_inline_consts: Default::default(),
+            alias_roots: Default::default(),
+            var_names: Default::default(),
         }
     }
 }
@@ -450,6 +1050,29 @@ impl Default for CasmBuilder {
 #[macro_export]
 macro_rules! casm_build_extend {
     ($builder:ident,) => {};
+    ($builder:ident, #[doc = $comment:literal] $($tok:tt)*) => {
+        $builder.add_comment($comment.trim().to_owned());
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, tempvar ( $q:ident, $r:ident ) = divmod($a:ident, $b:ident)
+        with $range_check:ident; $($tok:tt)*) => {
+        let ($q, $r) = $builder.div_mod($a, $b, $range_check);
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, tempvar $q:ident = $a:ident / $b:ident with $range_check:ident;
+        $($tok:tt)*) => {
+        let ($q, _) = $builder.div_mod($a, $b, $range_check);
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, tempvar $r:ident = $a:ident % $b:ident with $range_check:ident;
+        $($tok:tt)*) => {
+        let (_, $r) = $builder.div_mod($a, $b, $range_check);
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, const $name:ident = $value:expr; $($tok:tt)*) => {
+        let $name = $builder.add_const($value);
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
     ($builder:ident, tempvar $var:ident; $($tok:tt)*) => {
         let $var = $builder.alloc_var();
         $crate::casm_build_extend!($builder, $($tok)*)
@@ -480,6 +1103,14 @@ macro_rules! casm_build_extend {
         $builder.buffer_write_and_inc($buffer, $value);
         $crate::casm_build_extend!($builder, $($tok)*)
     };
+    ($builder:ident, assert * ( $buffer:ident ++ ) = ( $($value:ident),+ ); $($tok:tt)*) => {
+        $($builder.buffer_write_and_inc($buffer, $value);)+
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, assert * ( $ptr:ident + $offset:expr ) = $value:ident; $($tok:tt)*) => {
+        $builder.write_at_offset($ptr, $offset, $value);
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
     ($builder:ident, let $dst:ident = $a:ident + $b:ident; $($tok:tt)*) => {
         let $dst = $builder.bin_op($crate::operand::Operation::Add, $a, $b);
         $crate::casm_build_extend!($builder, $($tok)*)
@@ -492,12 +1123,40 @@ macro_rules! casm_build_extend {
         let $dst = $builder.get_ref_and_inc($buffer);
         $crate::casm_build_extend!($builder, $($tok)*)
     };
+    ($builder:ident, let ( $($dst:ident),+ ) = * ( $buffer:ident ++ ); $($tok:tt)*) => {
+        $(let $dst = $builder.get_ref_and_inc($buffer);)+
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, let $dst:ident = * ( $buffer:ident - $distance:expr ); $($tok:tt)*) => {
+        let $dst = $builder.buffer_read_back($buffer, $distance);
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
     ($builder:ident, let $dst:ident = $buffer:ident [ $offset:ident ] ; $($tok:tt)*) => {
         let $dst = $builder.double_deref($buffer, $offset);
         $crate::casm_build_extend!($builder, $($tok)*)
     };
+    ($builder:ident, let $dst:ident = copy_range($src_start:ident, $src_end:ident, $dst_start:ident)
+        carrying ($($carried:ident),*); $($tok:tt)*) => {
+        let $dst = $builder.copy_range($src_start, $src_end, $dst_start, &[$($carried),*]);
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, let $dst:ident = copy_range($src_start:ident, $src_end:ident, $dst_start:ident);
+        $($tok:tt)*) => {
+        let $dst = $builder.copy_range($src_start, $src_end, $dst_start, &[]);
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, let $dst:ident = snapshot $src:ident; $($tok:tt)*) => {
+        let $dst = $builder.snapshot_var($src);
+        $builder.set_var_name($dst, std::stringify!($dst));
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
     ($builder:ident, let $dst:ident = $src:ident; $($tok:tt)*) => {
         let $dst = $builder.duplicate_var($src);
+        $builder.set_var_name($dst, std::stringify!($dst));
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, rebind $dst:ident = $src:ident; $($tok:tt)*) => {
+        $builder.rebind_var($dst, $src);
         $crate::casm_build_extend!($builder, $($tok)*)
     };
     ($builder:ident, jump $target:ident; $($tok:tt)*) => {
@@ -508,6 +1167,29 @@ macro_rules! casm_build_extend {
         $builder.jump_nz($condition, std::stringify!($target).to_owned());
         $crate::casm_build_extend!($builder, $($tok)*)
     };
+    ($builder:ident, call $target:ident; $($tok:tt)*) => {
+        $builder.call_routine(std::stringify!($target).to_owned(), 0);
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, let ( $($dst:ident),+ ) = call $target:ident; $($tok:tt)*) => {
+        let mut __outputs = $builder
+            .call_routine(std::stringify!($target).to_owned(), [$(std::stringify!($dst)),+].len())
+            .into_iter();
+        $(let $dst = __outputs.next().unwrap();)+
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, ret; $($tok:tt)*) => {
+        $builder.ret();
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, $buffer:ident += $count:expr; $($tok:tt)*) => {
+        $builder.buffer_advance($buffer, $count);
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
+    ($builder:ident, routine $name:ident: $($tok:tt)*) => {
+        $builder.routine(std::stringify!($name).to_owned());
+        $crate::casm_build_extend!($builder, $($tok)*)
+    };
     ($builder:ident, $label:ident: $($tok:tt)*) => {
         $builder.label(std::stringify!($label).to_owned());
         $crate::casm_build_extend!($builder, $($tok)*)
@@ -1,7 +1,11 @@
 use indoc::indoc;
 use itertools::join;
+use num_bigint::BigInt;
 
-use super::CasmBuilder;
+use super::{CasmBuildResult, CasmBuilder, Var};
+use crate::ap_change::ApChange;
+use crate::operand::{CellRef, ResOperand};
+use crate::run::run_function;
 use crate::{casm_build_extend, res};
 
 #[test]
@@ -47,7 +51,7 @@ fn test_awaiting_relocations() {
     let result = builder.build();
     assert_eq!(result.label_state.len(), 1);
     assert_eq!(result.label_state["Target"].ap_change, 5);
-    assert_eq!(result.awaiting_relocations, [1]);
+    assert_eq!(result.awaiting_relocations, [(1, "Target".to_owned())]);
     assert_eq!(
         join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
         indoc! {"
@@ -159,3 +163,744 @@ fn test_unaligned_branch_intersect() {
     };
     builder.build();
 }
+
+#[test]
+fn test_call() {
+    let mut builder = CasmBuilder::default();
+    let arg = builder.add_var(res!([fp - 3]));
+    casm_build_extend! {builder,
+        tempvar stored_arg;
+        assert stored_arg = arg;
+    };
+    let outputs = builder.call("Routine".into(), 3, 2);
+    let result = builder.build();
+    assert_eq!(result.fallthrough_state.ap_change, 6);
+    assert_eq!(result.awaiting_relocations, [(1, "Routine".to_owned())]);
+    assert_eq!(
+        outputs.iter().map(|var| result.fallthrough_state.get_adjusted(*var)).collect::<Vec<_>>(),
+        [res!([ap - 2]), res!([ap - 1])]
+    );
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = [fp + -3], ap++;
+            call rel 0;
+        "}
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_call_with_pending_allocations() {
+    let mut builder = CasmBuilder::default();
+    casm_build_extend! {builder,
+        tempvar a;
+    };
+    builder.call("Routine".into(), 0, 0);
+}
+
+#[test]
+fn test_ret() {
+    let mut builder = CasmBuilder::default();
+    let arg = builder.add_var(res!([fp - 3]));
+    casm_build_extend! {builder,
+        tempvar doubled;
+        assert doubled = arg + arg;
+        ret;
+    };
+    let result = builder.build();
+    assert!(result.awaiting_relocations.is_empty());
+    assert_eq!(result.fallthrough_state.ap_change, 1);
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = [fp + -3] + [fp + -3], ap++;
+            ret;
+        "}
+    );
+}
+
+#[test]
+fn test_write_at_offset() {
+    let mut builder = CasmBuilder::default();
+    let seven = builder.add_var(res!(7));
+    let five = builder.add_var(res!(5));
+    casm_build_extend! {builder,
+        tempvar value;
+        assert value = seven;
+        tempvar ptr;
+        hint AllocSegment {} into {dst: ptr};
+        ap += 1;
+        // A pointer with an added constant, allowing a write at a negative offset.
+        let mid = ptr + five;
+        assert *(mid + 0) = value;
+        assert *(mid + 3) = value;
+        assert *(mid + -2) = value;
+    };
+    let ap_change_before_ret = builder.main_state.ap_change;
+    casm_build_extend! {builder,
+        ret;
+    };
+    let result = builder.build();
+    // The writes do not change ap.
+    assert_eq!(ap_change_before_ret, 2);
+    assert_eq!(result.fallthrough_state.get_adjusted(mid), res!([ap - 1] + 5));
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = 7, ap++;
+            %{ memory[ap + 0] = segments.add() %}
+            ap += 1;
+            [ap + -2] = [[ap + -1] + 5];
+            [ap + -2] = [[ap + -1] + 8];
+            [ap + -2] = [[ap + -1] + 3];
+            ret;
+        "}
+    );
+
+    let (memory, ap) = run_function(result.instructions).expect("Running the program failed.");
+    let cell = |address: usize| memory[address].clone().expect("Missing value.");
+    let ptr = usize::try_from(cell(ap - 1)).unwrap();
+    for offset in [5, 8, 3] {
+        assert_eq!(cell(ptr + offset), BigInt::from(7));
+    }
+}
+
+#[test]
+fn test_buffer_read_back() {
+    let mut builder = CasmBuilder::default();
+    let one = builder.add_var(res!(1));
+    let two = builder.add_var(res!(2));
+    casm_build_extend! {builder,
+        tempvar first;
+        assert first = one;
+        tempvar second;
+        assert second = two;
+        tempvar buffer;
+        hint AllocSegment {} into {dst: buffer};
+        ap += 1;
+        assert *(buffer++) = first;
+        assert *(buffer++) = second;
+        // Skipping a cell, and reading the written cells backwards.
+        buffer += 1;
+        let first_back = *(buffer - 3);
+        let second_back = *(buffer - 2);
+        tempvar first_copy;
+        assert first_copy = first_back;
+        tempvar second_copy;
+        assert second_copy = second_back;
+    };
+    let ap_change_before_ret = builder.main_state.ap_change;
+    casm_build_extend! {builder,
+        ret;
+    };
+    let result = builder.build();
+    assert_eq!(ap_change_before_ret, 5);
+    assert_eq!(result.fallthrough_state.get_adjusted(buffer), res!([ap - 3] + 3));
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = 1, ap++;
+            [ap + 0] = 2, ap++;
+            %{ memory[ap + 0] = segments.add() %}
+            ap += 1;
+            [ap + -3] = [[ap + -1] + 0];
+            [ap + -2] = [[ap + -1] + 1];
+            [ap + 0] = [[ap + -1] + 0], ap++;
+            [ap + 0] = [[ap + -2] + 1], ap++;
+            ret;
+        "}
+    );
+
+    let (memory, ap) = run_function(result.instructions).expect("Running the program failed.");
+    let cell = |address: usize| memory[address].clone().expect("Missing value.");
+    assert_eq!(cell(ap - 2), BigInt::from(1));
+    assert_eq!(cell(ap - 1), BigInt::from(2));
+}
+
+#[test]
+fn test_buffer_tuple_access() {
+    let mut builder = CasmBuilder::default();
+    let one = builder.add_var(res!(1));
+    let two = builder.add_var(res!(2));
+    casm_build_extend! {builder,
+        tempvar first;
+        assert first = one;
+        tempvar second;
+        assert second = two;
+        tempvar buffer;
+        hint AllocSegment {} into {dst: buffer};
+        ap += 1;
+        let start = snapshot buffer;
+        assert *(buffer++) = (first, second);
+        let (first_back, second_back) = *(start++);
+        tempvar first_copy;
+        assert first_copy = first_back;
+        tempvar second_copy;
+        assert second_copy = second_back;
+    };
+    let ap_change_before_ret = builder.main_state.ap_change;
+    casm_build_extend! {builder,
+        ret;
+    };
+    let result = builder.build();
+    assert_eq!(ap_change_before_ret, 5);
+    assert_eq!(result.fallthrough_state.get_adjusted(buffer), res!([ap - 3] + 2));
+    assert_eq!(result.fallthrough_state.get_adjusted(start), res!([ap - 3] + 2));
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = 1, ap++;
+            [ap + 0] = 2, ap++;
+            %{ memory[ap + 0] = segments.add() %}
+            ap += 1;
+            [ap + -3] = [[ap + -1] + 0];
+            [ap + -2] = [[ap + -1] + 1];
+            [ap + 0] = [[ap + -1] + 0], ap++;
+            [ap + 0] = [[ap + -2] + 1], ap++;
+            ret;
+        "}
+    );
+
+    let (memory, ap) = run_function(result.instructions).expect("Running the program failed.");
+    let cell = |address: usize| memory[address].clone().expect("Missing value.");
+    assert_eq!(cell(ap - 2), BigInt::from(1));
+    assert_eq!(cell(ap - 1), BigInt::from(2));
+}
+
+#[test]
+fn test_copy_range() {
+    let mut builder = CasmBuilder::default();
+    let one = builder.add_var(res!(1));
+    let two = builder.add_var(res!(2));
+    let three = builder.add_var(res!(3));
+    casm_build_extend! {builder,
+        tempvar first;
+        assert first = one;
+        tempvar second;
+        assert second = two;
+        tempvar third;
+        assert third = three;
+        tempvar start;
+        hint AllocSegment {} into {dst: start};
+        ap += 1;
+        let buffer = snapshot start;
+        assert *(buffer++) = (first, second, third);
+        tempvar end;
+        assert end = buffer;
+        tempvar dst;
+        hint AllocSegment {} into {dst: dst};
+        ap += 1;
+        let dst_end = copy_range(start, end, dst) carrying (end);
+    };
+    let state_after_copy = builder.main_state.clone();
+    casm_build_extend! {builder,
+        ret;
+    };
+    let result = builder.build();
+    // The state is rescoped to the end of the loop.
+    assert_eq!(state_after_copy.ap_change, 0);
+    assert_eq!(state_after_copy.full_ap_change(), ApChange::Unknown);
+    assert_eq!(state_after_copy.get_adjusted(dst_end), res!([ap - 2]));
+    assert_eq!(state_after_copy.get_adjusted(end), res!([ap - 4]));
+    assert_eq!(state_after_copy.get_adjusted(one), res!(1));
+    assert!(result.label_state.is_empty());
+    assert!(result.awaiting_relocations.is_empty());
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = 1, ap++;
+            [ap + 0] = 2, ap++;
+            [ap + 0] = 3, ap++;
+            %{ memory[ap + 0] = segments.add() %}
+            ap += 1;
+            [ap + -4] = [[ap + -1] + 0];
+            [ap + -3] = [[ap + -1] + 1];
+            [ap + -2] = [[ap + -1] + 2];
+            [ap + 0] = [ap + -1] + 3, ap++;
+            %{ memory[ap + 0] = segments.add() %}
+            ap += 1;
+            [ap + 0] = [ap + -2], ap++;
+            [ap + 0] = [ap + -4], ap++;
+            [ap + 0] = [ap + -3], ap++;
+            [ap + -5] = [ap + 0] + [ap + -6], ap++;
+            jmp rel 4 if [ap + -1] != 0;
+            jmp rel 13;
+            [ap + 0] = [[ap + -3] + 0], ap++;
+            [ap + -1] = [[ap + -3] + 0];
+            [ap + 0] = [ap + -5], ap++;
+            [ap + 0] = [ap + -5] + 1, ap++;
+            [ap + 0] = [ap + -5] + 1, ap++;
+            [ap + -5] = [ap + 0] + 1, ap++;
+            jmp rel -9 if [ap + -1] != 0;
+            ret;
+        "}
+    );
+
+    let (memory, ap) = run_function(result.instructions).expect("Running the program failed.");
+    let cell = |address: usize| memory[address].clone().expect("Missing value.");
+    let dst_end = usize::try_from(cell(ap - 2)).unwrap();
+    for (i, value) in [1, 2, 3].into_iter().enumerate() {
+        assert_eq!(cell(dst_end - 3 + i), BigInt::from(value));
+    }
+    assert_eq!(cell(ap - 1), BigInt::from(0));
+}
+
+/// Returns a function computing the division with remainder of `a` by `b`, returning the quotient
+/// and the remainder, along with the cells they are at and the range check buffer after the run.
+fn build_div_mod(a: i64, b: i64) -> (CasmBuildResult, CellRef, CellRef, ResOperand) {
+    let mut builder = CasmBuilder::default();
+    let a = builder.add_var(res!(a));
+    let b = builder.add_var(res!(b));
+    casm_build_extend! {builder,
+        tempvar lhs;
+        assert lhs = a;
+        tempvar rhs;
+        assert rhs = b;
+        tempvar range_check;
+        hint AllocSegment {} into {dst: range_check};
+        ap += 1;
+        tempvar (q, r) = divmod(lhs, rhs) with range_check;
+        ret;
+    };
+    let result = builder.build();
+    let q = result.fallthrough_state.get_adjusted_as_cell_ref(q);
+    let r = result.fallthrough_state.get_adjusted_as_cell_ref(r);
+    let range_check = result.fallthrough_state.get_adjusted(range_check);
+    (result, q, r, range_check)
+}
+
+#[test]
+fn test_div_mod_exact() {
+    let (result, q, r, range_check) = build_div_mod(12, 4);
+    // Every division uses 4 range checks.
+    assert_eq!(range_check, res!([ap - 8] + 4));
+    assert_eq!(result.fallthrough_state.ap_change, 10);
+    assert!(result.label_state.is_empty());
+    assert!(result.awaiting_relocations.is_empty());
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = 12, ap++;
+            [ap + 0] = 4, ap++;
+            %{ memory[ap + 0] = segments.add() %}
+            ap += 1;
+            %{ (memory[ap + 5], memory[ap + 6]) = divmod(memory[ap + -3], memory[ap + -2]) %}
+            [ap + 5] = [[ap + -1] + 0], ap++;
+            [ap + 5] = [[ap + -2] + 1], ap++;
+            [ap + -2] = [ap + 4] + 1, ap++;
+            [ap + -5] = [ap + -2] + [ap + -3], ap++;
+            [ap + -3] = [[ap + -5] + 2], ap++;
+            %{ memory[ap + -3] = memory[ap + 0] < 18446744073709551616 %}
+            jmp rel 6 if [ap + -3] != 0, ap++;
+            [ap + -3] = [ap + -8] + 340282366920938463444927863358058659840, ap++;
+            jmp rel 4;
+            [ap + -3] = [ap + -1] + 340282366920938463444927863358058659840, ap++;
+            [ap + -4] = [[ap + -8] + 3];
+            [ap + -3] = [ap + -9] * [ap + -2];
+            [ap + -10] = [ap + -3] + [ap + -1];
+            ret;
+        "}
+    );
+
+    let (memory, ap) = run_function(result.instructions).expect("Running the program failed.");
+    let cell = |cell_ref: CellRef| {
+        memory[(ap as isize + cell_ref.offset as isize) as usize].clone().expect("Missing value.")
+    };
+    assert_eq!(cell(q), BigInt::from(3));
+    assert_eq!(cell(r), BigInt::from(0));
+}
+
+#[test]
+fn test_div_mod_with_remainder() {
+    let (result, q, r, _) = build_div_mod(14, 4);
+    let (memory, ap) = run_function(result.instructions).expect("Running the program failed.");
+    let cell = |cell_ref: CellRef| {
+        memory[(ap as isize + cell_ref.offset as isize) as usize].clone().expect("Missing value.")
+    };
+    assert_eq!(cell(q), BigInt::from(3));
+    assert_eq!(cell(r), BigInt::from(2));
+}
+
+#[test]
+fn test_div_mod_by_zero() {
+    let (result, ..) = build_div_mod(12, 0);
+    assert!(run_function(result.instructions).is_err());
+}
+
+#[test]
+fn test_div_and_mod_statements() {
+    let mut builder = CasmBuilder::default();
+    casm_build_extend! {builder,
+        tempvar a;
+        tempvar b;
+        tempvar range_check;
+        ap += 3;
+        tempvar q = a / b with range_check;
+        tempvar r = a % b with range_check;
+    };
+    let result = builder.build();
+    // Each statement performs a whole division.
+    assert_eq!(result.fallthrough_state.get_adjusted(range_check), res!([ap - 15] + 8));
+    assert_eq!(result.fallthrough_state.get_adjusted(q), res!([ap - 9]));
+    assert_eq!(result.fallthrough_state.get_adjusted(r), res!([ap - 1]));
+}
+
+#[test]
+fn test_buffer_uses_per_branch() {
+    let mut builder = CasmBuilder::default();
+    let range_check = builder.add_var(res!([fp - 3]));
+    let value = builder.add_var(res!([fp - 4]));
+    casm_build_extend! {builder,
+        assert *(range_check++) = value;
+        let range_check_copy = range_check;
+        jump Failure if value != 0;
+        assert *(range_check++) = value;
+        range_check += 2;
+    };
+    let result = builder.build();
+    assert_eq!(result.label_state["Failure"].buffer_uses(range_check), 1);
+    assert_eq!(result.label_state["Failure"].buffer_uses(range_check_copy), 1);
+    assert_eq!(result.fallthrough_state.buffer_uses(range_check), 4);
+    assert_eq!(result.fallthrough_state.buffer_uses(range_check_copy), 1);
+    assert_eq!(result.fallthrough_state.buffer_uses(value), 0);
+}
+
+/// Adds a routine named `Double`, returning its argument doubled.
+fn add_double_routine(builder: &mut CasmBuilder) {
+    casm_build_extend! {builder,
+        routine Double:
+    };
+    let value = builder.add_var(res!([fp - 3]));
+    casm_build_extend! {builder,
+        tempvar doubled;
+        assert doubled = value + value;
+        ret;
+    };
+}
+
+/// Adds two calls to the routine `Double`, the second on the result of the first, and a jump to
+/// `Done`. Returns the result of the second call.
+fn add_double_calls(builder: &mut CasmBuilder) -> Var {
+    let arg = builder.add_var(res!([fp - 3]));
+    casm_build_extend! {builder,
+        tempvar first_arg;
+        assert first_arg = arg;
+        let (first) = call Double;
+        tempvar second_arg;
+        assert second_arg = first;
+        let (second) = call Double;
+        jump Done;
+    };
+    second
+}
+
+#[test]
+fn test_call_routine_defined_later() {
+    let mut builder = CasmBuilder::default();
+    let result_var = add_double_calls(&mut builder);
+    add_double_routine(&mut builder);
+    let result = builder.build();
+    // The ap change of the routine is not known at the calls.
+    let done_state = &result.label_state["Done"];
+    assert_eq!(done_state.full_ap_change(), ApChange::Unknown);
+    assert_eq!(done_state.get_adjusted(result_var), res!([ap - 1]));
+    assert_eq!(result.awaiting_relocations, [(4, "Done".to_owned())]);
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = [fp + -3], ap++;
+            call rel 7;
+            [ap + 0] = [ap + -1], ap++;
+            call rel 4;
+            jmp rel 0;
+            [ap + 0] = [fp + -3] + [fp + -3], ap++;
+            ret;
+        "}
+    );
+}
+
+#[test]
+fn test_call_routine_defined_earlier() {
+    let mut builder = CasmBuilder::default();
+    casm_build_extend! {builder,
+        jump Main;
+    };
+    add_double_routine(&mut builder);
+    casm_build_extend! {builder,
+        Main:
+    };
+    let result_var = add_double_calls(&mut builder);
+    let result = builder.build();
+    // Each call advances ap by the frame of the routine - the previous fp, the return address and
+    // the returned value.
+    let done_state = &result.label_state["Done"];
+    assert_eq!(done_state.full_ap_change(), ApChange::Known(8));
+    assert_eq!(done_state.get_adjusted(result_var), res!([ap - 1]));
+    assert_eq!(result.awaiting_relocations, [(7, "Done".to_owned())]);
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            jmp rel 4;
+            [ap + 0] = [fp + -3] + [fp + -3], ap++;
+            ret;
+            [ap + 0] = [fp + -3], ap++;
+            call rel -3;
+            [ap + 0] = [ap + -1], ap++;
+            call rel -6;
+            jmp rel 0;
+        "}
+    );
+}
+
+#[test]
+fn test_constant_folding() {
+    let mut builder = CasmBuilder::default();
+    let three = builder.add_var(res!(3));
+    let four = builder.add_var(res!(4));
+    let zero = builder.add_var(res!(0));
+    let one = builder.add_var(res!(1));
+    let x = builder.add_var(res!([fp - 3]));
+    let buffer = builder.add_var(res!([fp - 4]));
+    casm_build_extend! {builder,
+        tempvar sum;
+        assert sum = three + four;
+        let x_plus_zero = x + zero;
+        let one_times_x = one * x;
+        assert *(buffer++) = x_plus_zero;
+        assert *(buffer++) = one_times_x;
+        let three_times_x = three * x;
+        tempvar scaled;
+        assert scaled = three_times_x;
+        let skipped = buffer + four;
+    };
+    let result = builder.build();
+    let state = &result.fallthrough_state;
+    assert_eq!(state.ap_change, 2);
+    assert_eq!(state.get_adjusted(x_plus_zero), res!([fp - 3]));
+    assert_eq!(state.get_adjusted(one_times_x), res!([fp - 3]));
+    assert_eq!(state.get_adjusted(skipped), res!([fp - 4] + 6));
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = 7, ap++;
+            [fp + -3] = [[fp + -4] + 0];
+            [fp + -3] = [[fp + -4] + 1];
+            [ap + 0] = [fp + -3] * 3, ap++;
+        "}
+    );
+}
+
+/// Builds code writing `x + offset` to a buffer, where `offset` is an immediate 0, first storing
+/// the sum in a temporary variable if `materialize` is true.
+fn build_offset_write(materialize: bool) -> CasmBuildResult {
+    let mut builder = CasmBuilder::default();
+    let x = builder.add_var(res!([fp - 3]));
+    let offset = builder.add_var(res!(0));
+    let buffer = builder.add_var(res!([fp - 4]));
+    casm_build_extend! {builder,
+        let value = x + offset;
+    };
+    let value = if materialize {
+        casm_build_extend! {builder,
+            tempvar stored;
+            assert stored = value;
+        };
+        stored
+    } else {
+        value
+    };
+    casm_build_extend! {builder,
+        assert *(buffer++) = value;
+    };
+    builder.build()
+}
+
+#[test]
+fn test_constant_folding_instruction_count() {
+    let materialized = build_offset_write(true);
+    let folded = build_offset_write(false);
+    // The folded sum is an alias of `x`, so it is written directly, without a temporary variable.
+    assert_eq!(materialized.instructions.len(), 2);
+    assert_eq!(materialized.fallthrough_state.ap_change, 1);
+    assert_eq!(folded.instructions.len(), 1);
+    assert_eq!(folded.fallthrough_state.ap_change, 0);
+}
+
+#[test]
+fn test_const_materialized_once() {
+    let mut builder = CasmBuilder::default();
+    let buffer = builder.add_var(res!([fp - 3]));
+    let x = builder.add_var(res!([fp - 4]));
+    casm_build_extend! {builder,
+        const small = BigInt::from(5);
+        const big = BigInt::from(1000);
+        // Used inline once.
+        let x_plus_small = x + small;
+        tempvar y;
+        assert y = x_plus_small;
+        // Required to be a cell, so materialized on the first write, and reused by the rest.
+        assert *(buffer++) = big;
+        assert *(buffer++) = big;
+        let x_plus_big = x + big;
+        tempvar z;
+        assert z = x_plus_big;
+    };
+    let result = builder.build();
+    assert_eq!(result.fallthrough_state.ap_change, 3);
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = [fp + -4] + 5, ap++;
+            [ap + 0] = 1000, ap++;
+            [ap + -1] = [[fp + -3] + 0];
+            [ap + -1] = [[fp + -3] + 1];
+            [ap + 0] = [fp + -4] + [ap + -1], ap++;
+        "}
+    );
+}
+
+#[test]
+fn test_const_used_inline() {
+    let mut builder = CasmBuilder::default();
+    let x = builder.add_var(res!([fp - 3]));
+    casm_build_extend! {builder,
+        const seven = BigInt::from(7);
+        const nine = BigInt::from(9);
+        let x_plus_seven = x + seven;
+        tempvar a;
+        assert a = x_plus_seven;
+        // The second inline usage materializes the constant.
+        let x_times_seven = x * seven;
+        tempvar b;
+        assert b = x_times_seven;
+        // The cell assigned the constant is used by later statements.
+        tempvar c;
+        assert c = nine;
+        tempvar d;
+        assert d = nine;
+    };
+    let result = builder.build();
+    assert_eq!(result.fallthrough_state.ap_change, 5);
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = [fp + -3] + 7, ap++;
+            [ap + 0] = 7, ap++;
+            [ap + 0] = [fp + -3] * [ap + -1], ap++;
+            [ap + 0] = 9, ap++;
+            [ap + 0] = [ap + -1], ap++;
+        "}
+    );
+}
+
+#[test]
+fn test_const_merged_branches() {
+    let mut builder = CasmBuilder::default();
+    let x = builder.add_var(res!([fp - 3]));
+    casm_build_extend! {builder,
+        const nine = BigInt::from(9);
+        jump Other if x != 0;
+        tempvar a;
+        assert a = nine;
+        jump End;
+    Other:
+        tempvar b;
+        assert b = x;
+    End:
+        // Only one of the branches holds the constant in a cell, so it is used inline again.
+        tempvar c;
+        assert c = nine;
+    };
+    let result = builder.build();
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            jmp rel 5 if [fp + -3] != 0;
+            [ap + 0] = 9, ap++;
+            jmp rel 3;
+            [ap + 0] = [fp + -3], ap++;
+            [ap + 0] = 9, ap++;
+        "}
+    );
+}
+
+#[test]
+fn test_comments() {
+    let mut builder = CasmBuilder::default();
+    let x = builder.add_var(res!([fp - 3]));
+    casm_build_extend! {builder,
+        /// Copy x.
+        tempvar a;
+        assert a = x;
+        /// Jump if x is zero.
+        /// Otherwise fall through.
+        jump Target if x != 0;
+        tempvar b;
+        assert b = a;
+        Target:
+        /// Never attached to an instruction.
+    };
+    let CasmBuildResult { instructions, comments, .. } = builder.build();
+    assert_eq!(
+        join(instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [ap + 0] = [fp + -3], ap++;
+            jmp rel 3 if [fp + -3] != 0;
+            [ap + 0] = [ap + -1], ap++;
+        "}
+    );
+    assert_eq!(
+        comments,
+        vec![
+            (0, "Copy x.".to_owned()),
+            (1, "Jump if x is zero.".to_owned()),
+            (1, "Otherwise fall through.".to_owned()),
+        ]
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "`start` is a stale alias of `buffer`, which was advanced before statement 0."
+)]
+fn test_stale_buffer_alias() {
+    let mut builder = CasmBuilder::default();
+    let buffer = builder.add_var(res!([fp - 3]));
+    builder.set_var_name(buffer, "buffer");
+    let value = builder.add_var(res!([fp - 4]));
+    casm_build_extend! {builder,
+        let start = buffer;
+        assert *(buffer++) = value;
+        assert *(start++) = value;
+    };
+}
+
+#[test]
+fn test_buffer_snapshot_and_rebind() {
+    let mut builder = CasmBuilder::default();
+    let buffer = builder.add_var(res!([fp - 3]));
+    let value = builder.add_var(res!([fp - 4]));
+    casm_build_extend! {builder,
+        let start = snapshot buffer;
+        let alias = buffer;
+        assert *(buffer++) = value;
+        hint SystemCall { system: start };
+        rebind alias = buffer;
+        assert *(alias++) = value;
+        tempvar head;
+        assert head = start;
+    };
+    let result = builder.build();
+    assert_eq!(result.fallthrough_state.get_adjusted(alias), res!([fp - 3] + 2));
+    assert_eq!(result.fallthrough_state.buffer_uses(alias), 2);
+    assert_eq!(
+        join(result.instructions.iter().map(|inst| format!("{inst};\n")), ""),
+        indoc! {"
+            [fp + -4] = [[fp + -3] + 0];
+            %{ syscall_handler.syscall(syscall_ptr=memory[fp + -3]) %}
+            [fp + -4] = [[fp + -3] + 1];
+            [ap + 0] = [fp + -3], ap++;
+        "}
+    );
+}
@@ -0,0 +1,114 @@
+use std::cmp::Ordering;
+
+use num_bigint::BigInt;
+
+use crate::instructions::{Instruction, InstructionBody};
+use crate::operand::DerefOrImmediate;
+
+#[cfg(test)]
+#[path = "cost_test.rs"]
+mod test;
+
+/// The cost of a sequence of instructions.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct InstructionsCost {
+    /// The number of felts encoding the sequence.
+    pub size: usize,
+    /// The number of steps executing the sequence.
+    pub steps: usize,
+}
+impl InstructionsCost {
+    /// Returns the cost of a sequence of instructions that is executed from start to end. See
+    /// [InstructionsCost::of_code] for code with jumps.
+    pub fn of_straight_line(instructions: &[Instruction]) -> Self {
+        Self {
+            size: instructions.iter().map(Instruction::encoded_len).sum(),
+            steps: instructions.len(),
+        }
+    }
+
+    /// Returns the cost of a loop-free code sequence, where `steps` is the largest number of steps
+    /// of a run of the code, from its first instruction until it leaves the code.
+    /// Relative jumps by an immediate are followed, conditional jumps may go either way, and a
+    /// relative jump by a cell (a jump table dispatch) may land on any later instruction. A run
+    /// leaves the code on `ret` or on a jump outside of it. Calls are counted as a single step.
+    pub fn of_code(instructions: &[Instruction]) -> Self {
+        let offsets: Vec<usize> = instructions
+            .iter()
+            .scan(0, |offset, instruction| {
+                let current = *offset;
+                *offset += instruction.encoded_len();
+                Some(current)
+            })
+            .collect();
+        // The largest number of steps of a run starting at each instruction. As jumps only go
+        // forward, it is computed from the last instruction backwards.
+        let mut steps_from = vec![0; instructions.len()];
+        for idx in (0..instructions.len()).rev() {
+            let next = steps_from.get(idx + 1).copied().unwrap_or(0);
+            let any_later = steps_from[idx + 1..].iter().copied().max().unwrap_or(0);
+            let target = |jump_offset: &DerefOrImmediate| match jump_offset {
+                DerefOrImmediate::Deref(_) => any_later,
+                DerefOrImmediate::Immediate(value) => {
+                    let target_idx = usize::try_from(BigInt::from(offsets[idx]) + value)
+                        .ok()
+                        .and_then(|target_offset| offsets.binary_search(&target_offset).ok());
+                    // A jump that does not land on an instruction leaves the code.
+                    target_idx.map_or(0, |target_idx| {
+                        assert!(target_idx > idx, "Loops are not supported.");
+                        steps_from[target_idx]
+                    })
+                }
+            };
+            let following = match &instructions[idx].body {
+                InstructionBody::AddAp(_)
+                | InstructionBody::AssertEq(_)
+                | InstructionBody::Call(_) => next,
+                InstructionBody::Jump(insn) if insn.relative => target(&insn.target),
+                InstructionBody::Jump(_) | InstructionBody::Ret(_) => 0,
+                InstructionBody::Jnz(insn) => next.max(target(&insn.jump_offset)),
+            };
+            steps_from[idx] = 1 + following;
+        }
+        Self {
+            size: instructions.iter().map(Instruction::encoded_len).sum(),
+            steps: steps_from.first().copied().unwrap_or(0),
+        }
+    }
+
+    /// Returns the cost weighted by `weights`.
+    pub fn weighted(&self, weights: &CostWeights) -> usize {
+        self.size * weights.size + self.steps * weights.steps
+    }
+}
+
+/// The relative importance of code size and execution steps.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CostWeights {
+    /// The weight of a single felt of code.
+    pub size: usize,
+    /// The weight of a single execution step.
+    pub steps: usize,
+}
+impl Default for CostWeights {
+    fn default() -> Self {
+        Self { size: 1, steps: 1 }
+    }
+}
+
+/// Compares the weighted costs of two alternatives.
+/// Returns `Ordering::Less` if `a` is cheaper than `b`.
+pub fn compare(a: &InstructionsCost, b: &InstructionsCost, weights: &CostWeights) -> Ordering {
+    a.weighted(weights).cmp(&b.weighted(weights))
+}
+
+/// Returns the index of the cheapest of the alternatives' costs by `weights`. On ties, the
+/// earliest alternative is chosen.
+pub fn cheapest(costs: &[InstructionsCost], weights: &CostWeights) -> usize {
+    costs
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, cost)| cost.weighted(weights))
+        .map(|(idx, _)| idx)
+        .expect("No alternatives to choose from.")
+}
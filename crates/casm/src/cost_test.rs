@@ -0,0 +1,139 @@
+use std::cmp::Ordering;
+
+use test_case::test_case;
+
+use crate::cost::{cheapest, compare, CostWeights, InstructionsCost};
+use crate::instructions::Instruction;
+use crate::{casm, casm_extend, deref};
+
+/// Returns a jump table over 3 targets, selected by `[ap - 1]`.
+fn jump_table() -> Vec<Instruction> {
+    let selector = deref!([ap - 1]);
+    casm! {
+        jmp rel selector;
+        jmp rel 10;
+        jmp rel 20;
+        jmp rel 30;
+    }
+    .instructions
+}
+
+/// Returns a chain of conditional jumps over 3 targets, selected by the first nonzero flag.
+fn jump_chain() -> Vec<Instruction> {
+    casm! {
+        jmp rel 10 if [ap - 1] != 0;
+        jmp rel 20 if [ap - 2] != 0;
+        jmp rel 30;
+    }
+    .instructions
+}
+
+#[test]
+fn test_encoded_len() {
+    let selector = deref!([ap - 1]);
+    let instructions = casm! {
+        jmp rel selector;
+        [ap + 0] = [ap - 1] + 5, ap++;
+        ret;
+    }
+    .instructions;
+    assert_eq!(instructions.iter().map(Instruction::encoded_len).collect::<Vec<_>>(), [1, 2, 1]);
+}
+
+#[test]
+fn test_straight_line_cost() {
+    assert_eq!(
+        InstructionsCost::of_straight_line(&jump_table()),
+        InstructionsCost { size: 7, steps: 4 }
+    );
+    assert_eq!(
+        InstructionsCost::of_straight_line(&jump_chain()),
+        InstructionsCost { size: 6, steps: 3 }
+    );
+}
+
+#[test]
+fn test_code_cost() {
+    // The dispatch and a single jump out of the table.
+    assert_eq!(InstructionsCost::of_code(&jump_table()), InstructionsCost { size: 7, steps: 2 });
+    // The longest run goes through both conditional jumps.
+    assert_eq!(InstructionsCost::of_code(&jump_chain()), InstructionsCost { size: 6, steps: 3 });
+    // Only the steps of the taken jumps are counted, and calls count as a single step.
+    let instructions = casm! {
+        jmp rel 4 if [ap - 1] != 0;
+        [ap + 0] = [ap - 1] + 5, ap++;
+        [ap + 0] = [ap - 1], ap++;
+        call rel 30;
+        ret;
+    }
+    .instructions;
+    assert_eq!(InstructionsCost::of_code(&instructions), InstructionsCost { size: 8, steps: 5 });
+}
+
+/// A jump table is a single felt larger than a chain, but always takes 2 steps, while the chain
+/// takes up to 3 steps.
+#[test_case(CostWeights { size: 1, steps: 0 }, Ordering::Greater; "size_only")]
+#[test_case(CostWeights { size: 1, steps: 1 }, Ordering::Equal; "tie")]
+#[test_case(CostWeights { size: 1, steps: 2 }, Ordering::Less; "steps_weighted")]
+#[test_case(CostWeights { size: 0, steps: 1 }, Ordering::Less; "steps_only")]
+fn test_compare(weights: CostWeights, expected: Ordering) {
+    let table = InstructionsCost::of_code(&jump_table());
+    let chain = InstructionsCost::of_code(&jump_chain());
+    assert_eq!(compare(&table, &chain, &weights), expected);
+}
+
+/// Returns the jump table of an `enum_match` over `n_variants` variants, selected by `[fp - 3]`.
+fn enum_match_table(n_variants: usize) -> Vec<Instruction> {
+    let selector = deref!([fp - 3]);
+    let mut ctx = casm! { jmp rel selector; };
+    for _ in 0..n_variants {
+        casm_extend!(ctx, jmp rel 100;);
+    }
+    ctx.instructions
+}
+
+/// Returns a chain of conditional jumps equivalent to [enum_match_table], comparing the selector
+/// to each of the variant encodings in turn.
+fn enum_match_chain(n_variants: usize) -> Vec<Instruction> {
+    let mut ctx = casm! {};
+    for i in 0..n_variants - 1 {
+        let encoding = -(2 * i as i128 + 1);
+        casm_extend!(ctx,
+            [ap + 0] = [fp - 3] + encoding, ap++;
+            jmp rel 4 if [ap - 1] != 0;
+            jmp rel 100;
+        );
+    }
+    casm_extend!(ctx, jmp rel 100;);
+    ctx.instructions
+}
+
+/// The jump table of `enum_match` is no more costly than a chain of conditional jumps in both size
+/// and steps, so it is chosen under any weights.
+#[test_case(3, InstructionsCost { size: 7, steps: 2 }, InstructionsCost { size: 14, steps: 5 })]
+#[test_case(4, InstructionsCost { size: 9, steps: 2 }, InstructionsCost { size: 20, steps: 7 })]
+#[test_case(8, InstructionsCost { size: 17, steps: 2 }, InstructionsCost { size: 44, steps: 15 })]
+fn test_enum_match_lowerings(
+    n_variants: usize,
+    expected_table: InstructionsCost,
+    expected_chain: InstructionsCost,
+) {
+    let table = InstructionsCost::of_code(&enum_match_table(n_variants));
+    let chain = InstructionsCost::of_code(&enum_match_chain(n_variants));
+    assert_eq!((table, chain), (expected_table, expected_chain));
+    for weights in [CostWeights { size: 1, steps: 0 }, CostWeights { size: 0, steps: 1 }] {
+        assert_eq!(cheapest(&[chain, table], &weights), 1);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Loops are not supported.")]
+fn test_code_cost_loop() {
+    let back = -2;
+    let instructions = casm! {
+        [ap + 0] = [ap - 1] + 1, ap++;
+        jmp rel back;
+    }
+    .instructions;
+    InstructionsCost::of_code(&instructions);
+}
@@ -0,0 +1,154 @@
+//! A static check that the cells written by hints are constrained by the instructions following
+//! them.
+//!
+//! A hint is not part of the proven execution, so a value it writes into memory may be replaced by
+//! any value a malicious prover chooses. Code using hints is therefore only sound if every such
+//! value is later constrained by an instruction - otherwise the hint is effectively a free input.
+
+use std::collections::{HashMap, HashSet};
+
+use thiserror::Error;
+
+use crate::instructions::{Instruction, InstructionBody, JnzInstruction, JumpInstruction};
+use crate::operand::{CellRef, DerefOrImmediate, Register, ResOperand};
+
+#[cfg(test)]
+#[path = "hint_soundness_test.rs"]
+mod test;
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum HintSoundnessError {
+    #[error("The `{hint}` hint at pc {pc} writes {cell}, which may be left unconstrained.")]
+    UnconstrainedOutput { hint: &'static str, pc: usize, cell: CellRef },
+}
+
+/// Checks that every cell declared as an output by a hint in `instructions` is constrained along
+/// every path from the hint to an exit of the code - a `ret`, a `call`, or a jump that cannot be
+/// followed statically.
+///
+/// A cell is considered constrained once an instruction asserts on it, or branches on it - as
+/// hints such as `TestLessThan` only guide the flow, and each of the branches verifies its own
+/// assumption. Only the outputs returned by [crate::hints::Hint::constrained_outputs] are
+/// checked, so the check applies to hints outside of the core ones as well, as long as they
+/// declare their outputs.
+pub fn check_hint_soundness(instructions: &[Instruction]) -> Result<(), HintSoundnessError> {
+    let mut pcs = Vec::with_capacity(instructions.len());
+    let mut pc = 0;
+    for instruction in instructions {
+        pcs.push(pc);
+        pc += instruction.body.op_size();
+    }
+    let pc_to_idx: HashMap<usize, usize> =
+        pcs.iter().enumerate().map(|(idx, pc)| (*pc, idx)).collect();
+    for (idx, instruction) in instructions.iter().enumerate() {
+        for hint in &instruction.hints {
+            for cell in hint.constrained_outputs() {
+                if !is_constrained(instructions, &pcs, &pc_to_idx, idx, cell) {
+                    return Err(HintSoundnessError::UnconstrainedOutput {
+                        hint: hint.name(),
+                        pc: pcs[idx],
+                        cell,
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether `cell`, as seen by the instruction at index `start`, is constrained along every
+/// path starting at that instruction.
+fn is_constrained(
+    instructions: &[Instruction],
+    pcs: &[usize],
+    pc_to_idx: &HashMap<usize, usize>,
+    start: usize,
+    cell: CellRef,
+) -> bool {
+    // The pending paths, as the index of their next instruction and the offset of the cell
+    // relative to the register at that instruction.
+    let mut pending = vec![(start, cell.offset)];
+    let mut visited = HashSet::new();
+    while let Some((idx, offset)) = pending.pop() {
+        // Paths looping back to an already visited state add no new information.
+        if !visited.insert((idx, offset)) {
+            continue;
+        }
+        let Some(instruction) = instructions.get(idx) else {
+            return false;
+        };
+        let cell = CellRef { register: cell.register, offset };
+        if mentions(&instruction.body, cell) {
+            continue;
+        }
+        let ap_change = match &instruction.body {
+            InstructionBody::AddAp(add_ap) => match &add_ap.operand {
+                ResOperand::Immediate(value) => i16::try_from(value).ok(),
+                _ => None,
+            },
+            _ => Some(0),
+        }
+        .and_then(|ap_change| ap_change.checked_add(i16::from(instruction.inc_ap)));
+        let next_offset = match (cell.register, ap_change) {
+            (Register::AP, Some(ap_change)) => offset.checked_sub(ap_change),
+            (Register::AP, None) => None,
+            (Register::FP, _) => Some(offset),
+        };
+        // An ap based cell cannot be followed once ap changes by an unknown amount.
+        let Some(next_offset) = next_offset else {
+            return false;
+        };
+        let relative_target = |jump_offset: &DerefOrImmediate| match jump_offset {
+            DerefOrImmediate::Immediate(jump_offset) => {
+                let target = i128::try_from(jump_offset).ok()? + pcs[idx] as i128;
+                pc_to_idx.get(&usize::try_from(target).ok()?).copied()
+            }
+            DerefOrImmediate::Deref(_) => None,
+        };
+        match &instruction.body {
+            InstructionBody::AddAp(_) | InstructionBody::AssertEq(_) => {
+                pending.push((idx + 1, next_offset));
+            }
+            InstructionBody::Jump(JumpInstruction { target, relative: true }) => {
+                let Some(target) = relative_target(target) else {
+                    return false;
+                };
+                pending.push((target, next_offset));
+            }
+            InstructionBody::Jnz(JnzInstruction { jump_offset, .. }) => {
+                let Some(target) = relative_target(jump_offset) else {
+                    return false;
+                };
+                pending.push((idx + 1, next_offset));
+                pending.push((target, next_offset));
+            }
+            InstructionBody::Jump(_) | InstructionBody::Call(_) | InstructionBody::Ret(_) => {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Returns whether the instruction asserts on `cell`, or branches on it.
+fn mentions(body: &InstructionBody, cell: CellRef) -> bool {
+    match body {
+        InstructionBody::AssertEq(assert_eq) => {
+            assert_eq.a == cell
+                || match &assert_eq.b {
+                    ResOperand::Deref(operand) | ResOperand::DoubleDeref(operand, _) => {
+                        *operand == cell
+                    }
+                    ResOperand::BinOp(bin_op) => {
+                        bin_op.a == cell || bin_op.b == DerefOrImmediate::Deref(cell)
+                    }
+                    ResOperand::Immediate(_) => false,
+                }
+        }
+        InstructionBody::Jnz(jnz) => jnz.condition == cell,
+        InstructionBody::AddAp(_)
+        | InstructionBody::Call(_)
+        | InstructionBody::Jump(_)
+        | InstructionBody::Ret(_) => false,
+    }
+}
@@ -0,0 +1,70 @@
+use pretty_assertions::assert_eq;
+
+use crate::casm;
+use crate::hint_soundness::{check_hint_soundness, HintSoundnessError};
+use crate::operand::{CellRef, Register};
+
+#[test]
+fn test_constrained_div_mod() {
+    let instructions = casm! {
+        %{ (memory[ap + 0], memory[ap + 1]) = divmod(memory[fp - 4], memory[fp - 3]) %}
+        [ap + 0] = [[fp - 5] + 0], ap++;
+        [ap + 0] = [[fp - 5] + 1], ap++;
+        [ap + 0] = [ap - 2] * [fp - 3], ap++;
+        [fp - 4] = [ap - 1] + [ap - 2];
+        ret;
+    }
+    .instructions;
+    assert_eq!(check_hint_soundness(&instructions), Ok(()));
+}
+
+#[test]
+fn test_unconstrained_div_mod_remainder() {
+    let instructions = casm! {
+        ap += 1;
+        %{ (memory[ap + 0], memory[ap + 1]) = divmod(memory[fp - 4], memory[fp - 3]) %}
+        [ap + 0] = [[fp - 5] + 0], ap++;
+        ret;
+    }
+    .instructions;
+    assert_eq!(
+        check_hint_soundness(&instructions),
+        Err(HintSoundnessError::UnconstrainedOutput {
+            hint: "div_mod",
+            pc: 2,
+            cell: CellRef { register: Register::AP, offset: 1 }
+        })
+    );
+}
+
+#[test]
+fn test_branched_on_test_less_than() {
+    let instructions = casm! {
+        %{ memory[ap + 0] = memory[fp - 3] < 45 %}
+        jmp rel 3 if [ap + 0] != 0;
+        ret;
+        ret;
+    }
+    .instructions;
+    assert_eq!(check_hint_soundness(&instructions), Ok(()));
+}
+
+#[test]
+fn test_constrained_on_a_single_branch() {
+    let instructions = casm! {
+        %{ (memory[ap + 1], memory[ap + 2]) = divmod(memory[fp - 4], memory[fp - 3]) %}
+        jmp rel 4 if [fp - 5] != 0;
+        [ap + 1] = [[fp - 5] + 0];
+        [ap + 2] = [[fp - 5] + 1];
+        ret;
+    }
+    .instructions;
+    assert_eq!(
+        check_hint_soundness(&instructions),
+        Err(HintSoundnessError::UnconstrainedOutput {
+            hint: "div_mod",
+            pc: 0,
+            cell: CellRef { register: Register::AP, offset: 1 }
+        })
+    );
+}
@@ -2,12 +2,14 @@ use std::fmt::{Display, Formatter};
 
 use indoc::writedoc;
 
+use self::structured::StructuredHint;
 use crate::operand::{CellRef, DerefOrImmediate, ResOperand};
 
 #[cfg(test)]
 mod test;
 
 pub mod dict_squash;
+pub mod structured;
 
 // Represents a cairo hint.
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -15,6 +17,14 @@ pub enum Hint {
     AllocSegment {
         dst: CellRef,
     },
+    /// Allocates a new segment, which is expected to grow to `capacity` elements of `elem_size`
+    /// cells each. The capacity is only a hint for the allocator, and does not affect the
+    /// semantics of the segment.
+    AllocSegmentWithCapacity {
+        capacity: DerefOrImmediate,
+        elem_size: u16,
+        dst: CellRef,
+    },
     AllocDictFeltTo {
         dst: CellRef,
         default_value: CellRef,
@@ -63,6 +73,51 @@ pub enum Hint {
     Bitwise {
         ptr: ResOperand,
     },
+    /// Prints the values from start to end.
+    /// Both must be pointers.
+    DebugPrint {
+        start: ResOperand,
+        end: ResOperand,
+    },
+}
+
+impl Hint {
+    /// Returns a wrapper displaying the hint in the parsable structured format.
+    pub fn structured(&self) -> StructuredHint<'_> {
+        StructuredHint(self)
+    }
+
+    /// Returns the name of the kind of the hint, as used in the structured format.
+    pub fn name(&self) -> &'static str {
+        structured::hint_signature(self).0
+    }
+
+    /// Returns whether running the hint may write to memory cells, and thus affect the values the
+    /// instructions assert. Hints that do not are safe to remove from a program.
+    pub fn writes_memory(&self) -> bool {
+        !matches!(self, Hint::EnterScope | Hint::ExitScope | Hint::DebugPrint { .. })
+    }
+
+    /// Returns the cells the hint writes, which the instructions following it are expected to
+    /// constrain. The cells written by the rest of the hints - allocated segments, dict accesses
+    /// and system call responses - are validated by other means.
+    pub fn constrained_outputs(&self) -> Vec<CellRef> {
+        match self {
+            Hint::TestLessThan { dst, .. } | Hint::TestLessThanOrEqual { dst, .. } => vec![*dst],
+            Hint::DivMod { quotient, remainder, .. } => vec![*quotient, *remainder],
+            Hint::AllocSegment { .. }
+            | Hint::AllocSegmentWithCapacity { .. }
+            | Hint::AllocDictFeltTo { .. }
+            | Hint::DictFeltToRead { .. }
+            | Hint::DictFeltToWrite { .. }
+            | Hint::EnterScope
+            | Hint::ExitScope
+            | Hint::DictSquashHints { .. }
+            | Hint::SystemCall { .. }
+            | Hint::Bitwise { .. }
+            | Hint::DebugPrint { .. } => vec![],
+        }
+    }
 }
 
 impl Display for Hint {
@@ -83,6 +138,11 @@ impl Display for Hint {
         write!(f, "%{{")?;
         match self {
             Hint::AllocSegment { dst } => write!(f, " memory{dst} = segments.add() ")?,
+            Hint::AllocSegmentWithCapacity { capacity, elem_size, dst } => {
+                write!(f, " memory{dst} = segments.add() # capacity: ")?;
+                fmt_access_or_const(f, capacity)?;
+                write!(f, " * {elem_size} ")?;
+            }
             Hint::AllocDictFeltTo { dst, default_value } => writedoc!(
                 f,
                 "
@@ -148,6 +208,21 @@ impl Display for Hint {
             }
             // TODO(Dori): Implement bitwise_handler in python.
             Hint::Bitwise { ptr } => write!(f, " bitwise_handler(bitwise_ptr={}) ", ptr)?,
+            Hint::DebugPrint { start, end } => {
+                write!(f, "\ncurr = ")?;
+                fmt_res_operand(f, start)?;
+                write!(f, "\nend = ")?;
+                fmt_res_operand(f, end)?;
+                writedoc!(
+                    f,
+                    "
+
+                        while curr != end:
+                            print(memory[curr])
+                            curr += 1
+                    "
+                )?;
+            }
         }
         write!(f, "%}}")
     }
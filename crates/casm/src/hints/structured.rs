@@ -0,0 +1,418 @@
+//! A structured textual format of hints, which, unlike the python code of the hints, can be parsed
+//! back into a [Hint].
+//!
+//! A hint is rendered as `%{ name(arg=value, ...) -> (output=cell, ...) %}`, where the outputs part
+//! is omitted for hints without outputs. Values are rendered as in the casm instructions - cells as
+//! `[ap + 1]`, double derefs as `[[fp + -3] + 2]`, binary operations as `[ap + 1] + 5` and
+//! immediates as plain integers.
+
+use std::fmt::{Display, Formatter};
+
+use num_bigint::BigInt;
+use thiserror::Error;
+
+use super::Hint;
+use crate::operand::{BinOpOperand, CellRef, DerefOrImmediate, Operation, Register, ResOperand};
+
+#[cfg(test)]
+#[path = "structured_test.rs"]
+mod test;
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum HintParseError {
+    #[error("Expected {expected} at position {position}.")]
+    Expected { expected: String, position: usize },
+    #[error("Unknown hint `{0}`.")]
+    UnknownHint(String),
+    #[error("Hint `{hint}` expected argument `{expected}`, found `{found}`.")]
+    UnexpectedArgument { hint: String, expected: String, found: String },
+    #[error("Hint `{hint}` is missing argument `{arg}`.")]
+    MissingArgument { hint: String, arg: String },
+    #[error("Hint `{hint}` got an unexpected extra argument `{arg}`.")]
+    ExtraArgument { hint: String, arg: String },
+    #[error("Invalid value for argument `{arg}` of hint `{hint}`.")]
+    InvalidValue { hint: String, arg: String },
+}
+
+/// Displays a [Hint] in the structured format.
+pub struct StructuredHint<'a>(pub &'a Hint);
+
+impl Display for StructuredHint<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (name, inputs, outputs) = hint_signature(self.0);
+        let fmt_args = |args: Vec<(&str, String)>| {
+            args.into_iter()
+                .map(|(arg, value)| format!("{arg}={value}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        write!(f, "%{{ {name}({})", fmt_args(inputs))?;
+        if !outputs.is_empty() {
+            write!(f, " -> ({})", fmt_args(outputs))?;
+        }
+        write!(f, " %}}")
+    }
+}
+
+/// The arguments of a hint, as pairs of argument name and rendered value.
+type HintArgs = Vec<(&'static str, String)>;
+
+/// Returns the name of the hint, its input arguments and its output arguments.
+pub(crate) fn hint_signature(hint: &Hint) -> (&'static str, HintArgs, HintArgs) {
+    match hint {
+        Hint::AllocSegment { dst } => ("alloc_segment", vec![], vec![("dst", dst.to_string())]),
+        Hint::AllocSegmentWithCapacity { capacity, elem_size, dst } => (
+            "alloc_segment_with_capacity",
+            vec![("capacity", capacity.to_string()), ("elem_size", elem_size.to_string())],
+            vec![("dst", dst.to_string())],
+        ),
+        Hint::AllocDictFeltTo { dst, default_value } => (
+            "alloc_dict_felt_to",
+            vec![("default_value", default_value.to_string())],
+            vec![("dst", dst.to_string())],
+        ),
+        Hint::DictFeltToRead { dict_ptr, dict_offset, key, value_dst } => (
+            "dict_felt_to_read",
+            vec![
+                ("dict_ptr", dict_ptr.to_string()),
+                ("dict_offset", dict_offset.to_string()),
+                ("key", key.to_string()),
+            ],
+            vec![("value_dst", value_dst.to_string())],
+        ),
+        Hint::DictFeltToWrite { dict_ptr, dict_offset, key, value, prev_value_dst } => (
+            "dict_felt_to_write",
+            vec![
+                ("dict_ptr", dict_ptr.to_string()),
+                ("dict_offset", dict_offset.to_string()),
+                ("key", key.to_string()),
+                ("value", value.to_string()),
+            ],
+            vec![("prev_value_dst", prev_value_dst.to_string())],
+        ),
+        Hint::TestLessThan { lhs, rhs, dst } => (
+            "test_less_than",
+            vec![("lhs", lhs.to_string()), ("rhs", rhs.to_string())],
+            vec![("dst", dst.to_string())],
+        ),
+        Hint::TestLessThanOrEqual { lhs, rhs, dst } => (
+            "test_less_than_or_equal",
+            vec![("lhs", lhs.to_string()), ("rhs", rhs.to_string())],
+            vec![("dst", dst.to_string())],
+        ),
+        Hint::DivMod { lhs, rhs, quotient, remainder } => (
+            "div_mod",
+            vec![("lhs", lhs.to_string()), ("rhs", rhs.to_string())],
+            vec![("quotient", quotient.to_string()), ("remainder", remainder.to_string())],
+        ),
+        Hint::EnterScope => ("enter_scope", vec![], vec![]),
+        Hint::ExitScope => ("exit_scope", vec![], vec![]),
+        Hint::DictSquashHints { hint_index } => {
+            ("dict_squash", vec![("hint_index", hint_index.to_string())], vec![])
+        }
+        Hint::SystemCall { system } => {
+            ("system_call", vec![("system", system.to_string())], vec![])
+        }
+        Hint::Bitwise { ptr } => ("bitwise", vec![("ptr", ptr.to_string())], vec![]),
+        Hint::DebugPrint { start, end } => {
+            ("debug_print", vec![("start", start.to_string()), ("end", end.to_string())], vec![])
+        }
+    }
+}
+
+/// Parses a hint in the structured format.
+/// The arguments must appear exactly as rendered by [StructuredHint], up to whitespace.
+pub fn parse_hint(text: &str) -> Result<Hint, HintParseError> {
+    let mut parser = Parser { text, position: 0 };
+    parser.consume("%{")?;
+    let name = parser.identifier()?;
+    parser.consume("(")?;
+    let inputs = parser.args()?;
+    let outputs = if parser.try_consume("->") {
+        parser.consume("(")?;
+        parser.args()?
+    } else {
+        vec![]
+    };
+    parser.consume("%}")?;
+    parser.skip_whitespace();
+    if !parser.rest().is_empty() {
+        return Err(parser.expected("end of hint"));
+    }
+
+    let mut args =
+        ParsedArgs { hint: name, inputs: inputs.into_iter(), outputs: outputs.into_iter() };
+    let hint = match name {
+        "alloc_segment" => Hint::AllocSegment { dst: args.output("dst", as_cell)? },
+        "alloc_segment_with_capacity" => Hint::AllocSegmentWithCapacity {
+            capacity: args.input("capacity", as_deref_or_immediate)?,
+            elem_size: args.input("elem_size", as_integer)?,
+            dst: args.output("dst", as_cell)?,
+        },
+        "alloc_dict_felt_to" => Hint::AllocDictFeltTo {
+            default_value: args.input("default_value", as_cell)?,
+            dst: args.output("dst", as_cell)?,
+        },
+        "dict_felt_to_read" => Hint::DictFeltToRead {
+            dict_ptr: args.input("dict_ptr", as_cell)?,
+            dict_offset: args.input("dict_offset", as_integer)?,
+            key: args.input("key", as_cell)?,
+            value_dst: args.output("value_dst", as_cell)?,
+        },
+        "dict_felt_to_write" => Hint::DictFeltToWrite {
+            dict_ptr: args.input("dict_ptr", as_cell)?,
+            dict_offset: args.input("dict_offset", as_integer)?,
+            key: args.input("key", as_cell)?,
+            value: args.input("value", as_cell)?,
+            prev_value_dst: args.output("prev_value_dst", as_cell)?,
+        },
+        "test_less_than" => Hint::TestLessThan {
+            lhs: args.input("lhs", as_deref_or_immediate)?,
+            rhs: args.input("rhs", as_deref_or_immediate)?,
+            dst: args.output("dst", as_cell)?,
+        },
+        "test_less_than_or_equal" => Hint::TestLessThanOrEqual {
+            lhs: args.input("lhs", as_deref_or_immediate)?,
+            rhs: args.input("rhs", as_deref_or_immediate)?,
+            dst: args.output("dst", as_cell)?,
+        },
+        "div_mod" => Hint::DivMod {
+            lhs: args.input("lhs", as_deref_or_immediate)?,
+            rhs: args.input("rhs", as_deref_or_immediate)?,
+            quotient: args.output("quotient", as_cell)?,
+            remainder: args.output("remainder", as_cell)?,
+        },
+        "enter_scope" => Hint::EnterScope,
+        "exit_scope" => Hint::ExitScope,
+        "dict_squash" => {
+            Hint::DictSquashHints { hint_index: args.input("hint_index", as_integer)? }
+        }
+        "system_call" => Hint::SystemCall { system: args.input("system", Some)? },
+        "bitwise" => Hint::Bitwise { ptr: args.input("ptr", Some)? },
+        "debug_print" => {
+            Hint::DebugPrint { start: args.input("start", Some)?, end: args.input("end", Some)? }
+        }
+        _ => return Err(HintParseError::UnknownHint(name.into())),
+    };
+    args.finish()?;
+    Ok(hint)
+}
+
+/// A parsed argument - its name and value.
+type ParsedArg<'a> = (&'a str, ResOperand);
+
+/// The parsed arguments of a hint, consumed in order while constructing the hint.
+struct ParsedArgs<'a> {
+    hint: &'a str,
+    inputs: std::vec::IntoIter<ParsedArg<'a>>,
+    outputs: std::vec::IntoIter<ParsedArg<'a>>,
+}
+impl ParsedArgs<'_> {
+    /// Takes the next input argument, which must be named `arg`.
+    fn input<T>(
+        &mut self,
+        arg: &str,
+        convert: fn(ResOperand) -> Option<T>,
+    ) -> Result<T, HintParseError> {
+        Self::take(self.hint, &mut self.inputs, arg, convert)
+    }
+
+    /// Takes the next output argument, which must be named `arg`.
+    fn output<T>(
+        &mut self,
+        arg: &str,
+        convert: fn(ResOperand) -> Option<T>,
+    ) -> Result<T, HintParseError> {
+        Self::take(self.hint, &mut self.outputs, arg, convert)
+    }
+
+    fn take<T>(
+        hint: &str,
+        args: &mut std::vec::IntoIter<ParsedArg<'_>>,
+        arg: &str,
+        convert: fn(ResOperand) -> Option<T>,
+    ) -> Result<T, HintParseError> {
+        match args.next() {
+            Some((name, value)) if name == arg => convert(value)
+                .ok_or_else(|| HintParseError::InvalidValue { hint: hint.into(), arg: arg.into() }),
+            Some((name, _)) => Err(HintParseError::UnexpectedArgument {
+                hint: hint.into(),
+                expected: arg.into(),
+                found: name.into(),
+            }),
+            None => Err(HintParseError::MissingArgument { hint: hint.into(), arg: arg.into() }),
+        }
+    }
+
+    /// Verifies all the arguments were consumed.
+    fn finish(mut self) -> Result<(), HintParseError> {
+        match self.inputs.next().or_else(|| self.outputs.next()) {
+            Some((name, _)) => {
+                Err(HintParseError::ExtraArgument { hint: self.hint.into(), arg: name.into() })
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+fn as_cell(value: ResOperand) -> Option<CellRef> {
+    match value {
+        ResOperand::Deref(cell) => Some(cell),
+        _ => None,
+    }
+}
+
+fn as_deref_or_immediate(value: ResOperand) -> Option<DerefOrImmediate> {
+    match value {
+        ResOperand::Deref(cell) => Some(DerefOrImmediate::Deref(cell)),
+        ResOperand::Immediate(value) => Some(DerefOrImmediate::Immediate(value)),
+        _ => None,
+    }
+}
+
+fn as_integer<T: TryFrom<BigInt>>(value: ResOperand) -> Option<T> {
+    match value {
+        ResOperand::Immediate(value) => value.try_into().ok(),
+        _ => None,
+    }
+}
+
+/// A recursive descent parser of the structured hint format.
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn expected(&self, expected: &str) -> HintParseError {
+        HintParseError::Expected { expected: expected.into(), position: self.position }
+    }
+
+    /// Returns whether `token` is next, ignoring whitespace.
+    fn peek(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        self.rest().starts_with(token)
+    }
+
+    /// Consumes `token` if it is next, ignoring whitespace, and returns whether it was consumed.
+    fn try_consume(&mut self, token: &str) -> bool {
+        let is_next = self.peek(token);
+        if is_next {
+            self.position += token.len();
+        }
+        is_next
+    }
+
+    fn consume(&mut self, token: &str) -> Result<(), HintParseError> {
+        if self.try_consume(token) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("`{token}`")))
+        }
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, HintParseError> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len =
+            rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.expected("an identifier"));
+        }
+        self.position += len;
+        Ok(&rest[..len])
+    }
+
+    fn integer(&mut self) -> Result<BigInt, HintParseError> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let sign_len = usize::from(rest.starts_with('-'));
+        let digits_len =
+            rest[sign_len..].find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len() - sign_len);
+        if digits_len == 0 {
+            return Err(self.expected("an integer"));
+        }
+        let len = sign_len + digits_len;
+        self.position += len;
+        Ok(rest[..len].parse().expect("Only sign and digits were consumed."))
+    }
+
+    fn offset(&mut self) -> Result<i16, HintParseError> {
+        let position = self.position;
+        i16::try_from(self.integer()?)
+            .map_err(|_| HintParseError::Expected { expected: "an offset".into(), position })
+    }
+
+    fn cell_ref(&mut self) -> Result<CellRef, HintParseError> {
+        self.consume("[")?;
+        let register = if self.try_consume("ap") {
+            Register::AP
+        } else if self.try_consume("fp") {
+            Register::FP
+        } else {
+            return Err(self.expected("a register"));
+        };
+        self.consume("+")?;
+        let offset = self.offset()?;
+        self.consume("]")?;
+        Ok(CellRef { register, offset })
+    }
+
+    fn deref_or_immediate(&mut self) -> Result<DerefOrImmediate, HintParseError> {
+        if self.peek("[") {
+            Ok(DerefOrImmediate::Deref(self.cell_ref()?))
+        } else {
+            Ok(DerefOrImmediate::Immediate(self.integer()?))
+        }
+    }
+
+    fn res_operand(&mut self) -> Result<ResOperand, HintParseError> {
+        if !self.peek("[") {
+            return Ok(ResOperand::Immediate(self.integer()?));
+        }
+        let start = self.position;
+        self.consume("[")?;
+        if self.peek("[") {
+            let cell = self.cell_ref()?;
+            self.consume("+")?;
+            let offset = self.offset()?;
+            self.consume("]")?;
+            return Ok(ResOperand::DoubleDeref(cell, offset));
+        }
+        self.position = start;
+        let a = self.cell_ref()?;
+        let op = if self.try_consume("+") {
+            Operation::Add
+        } else if self.try_consume("*") {
+            Operation::Mul
+        } else {
+            return Ok(ResOperand::Deref(a));
+        };
+        Ok(ResOperand::BinOp(BinOpOperand { op, a, b: self.deref_or_immediate()? }))
+    }
+
+    /// Parses a list of `name=value` arguments, including the closing parenthesis.
+    fn args(&mut self) -> Result<Vec<ParsedArg<'a>>, HintParseError> {
+        let mut args = vec![];
+        if self.try_consume(")") {
+            return Ok(args);
+        }
+        loop {
+            let name = self.identifier()?;
+            self.consume("=")?;
+            args.push((name, self.res_operand()?));
+            if self.try_consume(")") {
+                return Ok(args);
+            }
+            self.consume(",")?;
+        }
+    }
+}
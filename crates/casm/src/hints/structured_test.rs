@@ -0,0 +1,219 @@
+use num_bigint::BigInt;
+use pretty_assertions::assert_eq;
+use test_case::test_case;
+use test_log::test;
+
+use super::{parse_hint, HintParseError};
+use crate::hints::Hint;
+use crate::operand::{BinOpOperand, CellRef, DerefOrImmediate, Operation, Register, ResOperand};
+
+fn cell(register: Register, offset: i16) -> CellRef {
+    CellRef { register, offset }
+}
+
+#[test_case(
+    Hint::AllocSegment { dst: cell(Register::AP, 0) },
+    "%{ alloc_segment() -> (dst=[ap + 0]) %}";
+    "alloc_segment"
+)]
+#[test_case(
+    Hint::DivMod {
+        lhs: DerefOrImmediate::Deref(cell(Register::AP, 1)),
+        rhs: DerefOrImmediate::Deref(cell(Register::FP, -3)),
+        quotient: cell(Register::AP, 2),
+        remainder: cell(Register::AP, 3),
+    },
+    "%{ div_mod(lhs=[ap + 1], rhs=[fp + -3]) -> (quotient=[ap + 2], remainder=[ap + 3]) %}";
+    "div_mod"
+)]
+#[test_case(
+    Hint::SystemCall {
+        system: ResOperand::BinOp(BinOpOperand {
+            op: Operation::Add,
+            a: cell(Register::FP, -3),
+            b: DerefOrImmediate::Immediate(BigInt::from(5)),
+        }),
+    },
+    "%{ system_call(system=[fp + -3] + 5) %}";
+    "system_call"
+)]
+#[test_case(Hint::EnterScope, "%{ enter_scope() %}"; "enter_scope")]
+fn test_format(hint: Hint, expected: &str) {
+    assert_eq!(hint.structured().to_string(), expected);
+    assert_eq!(parse_hint(expected), Ok(hint));
+}
+
+#[test]
+fn test_parse_whitespace() {
+    assert_eq!(
+        parse_hint("%{test_less_than( lhs = [ap+1] , rhs = -7 )->(dst=[fp+0])%}"),
+        Ok(Hint::TestLessThan {
+            lhs: DerefOrImmediate::Deref(cell(Register::AP, 1)),
+            rhs: DerefOrImmediate::Immediate(BigInt::from(-7)),
+            dst: cell(Register::FP, 0),
+        })
+    );
+}
+
+#[test_case(
+    "%{ alloc_segment() -> (dst=[ap + 0])",
+    HintParseError::Expected { expected: "`%}`".into(), position: 36 };
+    "unterminated"
+)]
+#[test_case(
+    "%{ alloc_segment() -> (dst=[sp + 0]) %}",
+    HintParseError::Expected { expected: "a register".into(), position: 28 };
+    "bad_register"
+)]
+#[test_case(
+    "%{ alloc_segment() -> (dst=[ap + 40000]) %}",
+    HintParseError::Expected { expected: "an offset".into(), position: 32 };
+    "offset_overflow"
+)]
+#[test_case(
+    "%{ no_such_hint() %}",
+    HintParseError::UnknownHint("no_such_hint".into());
+    "unknown_hint"
+)]
+#[test_case(
+    "%{ alloc_segment(dst=[ap + 0]) %}",
+    HintParseError::MissingArgument { hint: "alloc_segment".into(), arg: "dst".into() };
+    "input_instead_of_output"
+)]
+#[test_case(
+    "%{ bitwise(pointer=[ap + 0]) %}",
+    HintParseError::UnexpectedArgument {
+        hint: "bitwise".into(),
+        expected: "ptr".into(),
+        found: "pointer".into(),
+    };
+    "wrong_name"
+)]
+#[test_case(
+    "%{ enter_scope(depth=1) %}",
+    HintParseError::ExtraArgument { hint: "enter_scope".into(), arg: "depth".into() };
+    "extra_argument"
+)]
+#[test_case(
+    "%{ dict_squash(hint_index=[ap + 0]) %}",
+    HintParseError::InvalidValue { hint: "dict_squash".into(), arg: "hint_index".into() };
+    "invalid_value"
+)]
+fn test_parse_error(text: &str, expected: HintParseError) {
+    assert_eq!(parse_hint(text), Err(expected));
+}
+
+/// A deterministic pseudo random generator, for generating hint operands.
+struct Rng(u64);
+impl Rng {
+    fn next(&mut self) -> u64 {
+        // Constants of Knuth's MMIX linear congruential generator.
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn cell(&mut self) -> CellRef {
+        let register = if self.below(2) == 0 { Register::AP } else { Register::FP };
+        CellRef { register, offset: self.next() as i16 }
+    }
+
+    fn immediate(&mut self) -> BigInt {
+        let value = BigInt::from(self.next()) << self.below(250);
+        if self.below(2) == 0 {
+            value
+        } else {
+            -value
+        }
+    }
+
+    fn deref_or_immediate(&mut self) -> DerefOrImmediate {
+        if self.below(2) == 0 {
+            DerefOrImmediate::Deref(self.cell())
+        } else {
+            DerefOrImmediate::Immediate(self.immediate())
+        }
+    }
+
+    fn res_operand(&mut self) -> ResOperand {
+        match self.below(4) {
+            0 => ResOperand::Deref(self.cell()),
+            1 => ResOperand::DoubleDeref(self.cell(), self.next() as i16),
+            2 => ResOperand::Immediate(self.immediate()),
+            _ => ResOperand::BinOp(BinOpOperand {
+                op: if self.below(2) == 0 { Operation::Add } else { Operation::Mul },
+                a: self.cell(),
+                b: self.deref_or_immediate(),
+            }),
+        }
+    }
+
+    /// Returns a hint of the variant matching `variant`, with random operands.
+    fn hint(&mut self, variant: usize) -> Hint {
+        match variant {
+            0 => Hint::AllocSegment { dst: self.cell() },
+            1 => Hint::AllocDictFeltTo { dst: self.cell(), default_value: self.cell() },
+            2 => Hint::DictFeltToRead {
+                dict_ptr: self.cell(),
+                dict_offset: self.next() as u16,
+                key: self.cell(),
+                value_dst: self.cell(),
+            },
+            3 => Hint::DictFeltToWrite {
+                dict_ptr: self.cell(),
+                dict_offset: self.next() as u16,
+                key: self.cell(),
+                value: self.cell(),
+                prev_value_dst: self.cell(),
+            },
+            4 => Hint::TestLessThan {
+                lhs: self.deref_or_immediate(),
+                rhs: self.deref_or_immediate(),
+                dst: self.cell(),
+            },
+            5 => Hint::TestLessThanOrEqual {
+                lhs: self.deref_or_immediate(),
+                rhs: self.deref_or_immediate(),
+                dst: self.cell(),
+            },
+            6 => Hint::DivMod {
+                lhs: self.deref_or_immediate(),
+                rhs: self.deref_or_immediate(),
+                quotient: self.cell(),
+                remainder: self.cell(),
+            },
+            7 => Hint::EnterScope,
+            8 => Hint::ExitScope,
+            9 => Hint::DictSquashHints { hint_index: self.next() as usize },
+            10 => Hint::SystemCall { system: self.res_operand() },
+            11 => Hint::Bitwise { ptr: self.res_operand() },
+            12 => Hint::DebugPrint { start: self.res_operand(), end: self.res_operand() },
+            13 => Hint::AllocSegmentWithCapacity {
+                capacity: self.deref_or_immediate(),
+                elem_size: self.next() as u16,
+                dst: self.cell(),
+            },
+            _ => unreachable!("Unexpected variant index."),
+        }
+    }
+}
+
+/// Number of variants of [Hint]. Must be updated when adding a variant, along with `Rng::hint`.
+const HINT_VARIANTS: usize = 14;
+
+#[test]
+fn test_round_trip() {
+    let mut rng = Rng(0);
+    for _ in 0..100 {
+        for variant in 0..HINT_VARIANTS {
+            let hint = rng.hint(variant);
+            let text = hint.structured().to_string();
+            let parsed = parse_hint(&text).unwrap_or_else(|err| panic!("Parsing `{text}`: {err}"));
+            assert_eq!(parsed.structured().to_string(), text);
+            assert_eq!(parsed, hint);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use indoc::indoc;
 use test_log::test;
 
 use crate::hints::Hint;
@@ -11,6 +12,18 @@ fn test_alloc_segment_format() {
     assert_eq!(hint.to_string(), "%{ memory[ap + 5] = segments.add() %}");
 }
 
+#[test]
+fn test_alloc_segment_with_capacity_format() {
+    let dst = CellRef { register: Register::AP, offset: 0 };
+    let capacity = DerefOrImmediate::Deref(CellRef { register: Register::FP, offset: -3 });
+    let hint = Hint::AllocSegmentWithCapacity { capacity, elem_size: 2, dst };
+
+    assert_eq!(
+        hint.to_string(),
+        "%{ memory[ap + 0] = segments.add() # capacity: memory[fp + -3] * 2 %}"
+    );
+}
+
 #[test]
 fn test_less_than_format() {
     let ap_based = DerefOrImmediate::Deref(CellRef { register: Register::AP, offset: 6 });
@@ -94,3 +107,26 @@ fn test_syscall_hint_format() {
         "%{ syscall_handler.syscall(syscall_ptr=memory[fp + -3] + 3) %}"
     );
 }
+
+#[test]
+fn test_debug_print_hint_format() {
+    let start = ResOperand::Deref(CellRef { register: Register::FP, offset: 1 });
+    let end = ResOperand::BinOp(BinOpOperand {
+        op: Operation::Add,
+        a: CellRef { register: Register::AP, offset: -1 },
+        b: DerefOrImmediate::from(2),
+    });
+
+    assert_eq!(
+        Hint::DebugPrint { start, end }.to_string(),
+        indoc! {"
+            %{
+            curr = memory[fp + 1]
+            end = memory[ap + -1] + 2
+            while curr != end:
+                print(memory[curr])
+                curr += 1
+            %}"
+        }
+    );
+}
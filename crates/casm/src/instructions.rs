@@ -55,6 +55,12 @@ impl Instruction {
     pub fn new(body: InstructionBody, inc_ap: bool) -> Self {
         Self { body, inc_ap, hints: vec![] }
     }
+
+    /// Returns the number of felts encoding the instruction: 2 if it has an immediate, 1
+    /// otherwise.
+    pub fn encoded_len(&self) -> usize {
+        if self.assemble().imm.is_some() { 2 } else { 1 }
+    }
 }
 
 impl Display for Instruction {
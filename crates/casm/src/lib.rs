@@ -3,7 +3,9 @@
 pub mod ap_change;
 pub mod assembler;
 pub mod builder;
+pub mod cost;
 pub mod encoder;
+pub mod hint_soundness;
 pub mod hints;
 pub mod inline;
 pub mod instructions;
@@ -1,5 +1,7 @@
 use std::any::Any;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::io::Write;
 
 use cairo_rs::hint_processor::hint_processor_definition::{HintProcessor, HintReference};
 use cairo_rs::serde::deserialize_program::{
@@ -12,6 +14,7 @@ use cairo_rs::vm::errors::vm_errors::VirtualMachineError;
 use cairo_rs::vm::runners::cairo_runner::CairoRunner;
 use cairo_rs::vm::vm_core::VirtualMachine;
 use num_bigint::BigInt;
+use utils::short_string::format_for_debug;
 
 use crate::hints::Hint;
 use crate::instructions::Instruction;
@@ -39,38 +42,56 @@ fn hint_to_hint_params(hint: &Hint) -> HintParams {
 }
 
 /// HintProcessor for Cairo compiler hints.
-struct CairoHintProcessor {
+struct CairoHintProcessor<'a> {
     // A dict from instruction offset to hint vector.
     pub hints_dict: HashMap<usize, Vec<HintParams>>,
     // A mapping from a string that represents a hint to the hint object.
     pub string_to_hint: HashMap<String, Hint>,
+    // The sink for the output of debug print hints.
+    pub debug_output: RefCell<&'a mut dyn Write>,
+    // The capacity hints of the segments allocated by hints, in allocation order.
+    pub segment_capacities: RefCell<SegmentCapacities>,
+    // The handler of the system call hints, if system calls are supported by the run.
+    pub syscall_handler: Option<RefCell<&'a mut dyn SyscallHintHandler>>,
 }
 
-impl CairoHintProcessor {
-    pub fn new(program: Vec<Instruction>) -> Self {
+impl<'a> CairoHintProcessor<'a> {
+    pub fn new(
+        hints: &[(usize, Vec<Hint>)],
+        debug_output: &'a mut dyn Write,
+        syscall_handler: Option<&'a mut dyn SyscallHintHandler>,
+    ) -> Self {
         let mut hints_dict: HashMap<usize, Vec<HintParams>> = HashMap::new();
         let mut string_to_hint: HashMap<String, Hint> = HashMap::new();
 
-        let mut hint_offset = 0;
-
-        for instruction in program.iter() {
-            if !instruction.hints.is_empty() {
-                // Register hint with string for the hint processor.
-                for hint in instruction.hints.iter() {
-                    string_to_hint.insert(hint.to_string(), hint.clone());
-                }
-                // Add hint, associated with the instruction offset.
-                hints_dict.insert(
-                    hint_offset,
-                    instruction.hints.iter().map(hint_to_hint_params).collect(),
-                );
+        for (hint_offset, offset_hints) in hints {
+            // Register hint with string for the hint processor.
+            for hint in offset_hints {
+                string_to_hint.insert(hint.to_string(), hint.clone());
             }
-            hint_offset += instruction.body.op_size();
+            // Add hint, associated with the instruction offset.
+            hints_dict.insert(*hint_offset, offset_hints.iter().map(hint_to_hint_params).collect());
+        }
+        CairoHintProcessor {
+            hints_dict,
+            string_to_hint,
+            debug_output: RefCell::new(debug_output),
+            segment_capacities: RefCell::new(vec![]),
+            syscall_handler: syscall_handler.map(RefCell::new),
         }
-        CairoHintProcessor { hints_dict, string_to_hint }
     }
 }
 
+/// A handler of the system calls triggered by [Hint::SystemCall] hints.
+pub trait SyscallHintHandler {
+    /// Executes the system call written at `system_ptr`, writing its response into the memory.
+    fn execute_syscall(
+        &mut self,
+        vm: &mut VirtualMachine,
+        system_ptr: Relocatable,
+    ) -> Result<(), VirtualMachineError>;
+}
+
 fn cell_ref_to_relocatable(cell_ref: CellRef, vm: &VirtualMachine) -> Relocatable {
     let base = match cell_ref.register {
         Register::AP => vm.get_ap(),
@@ -79,7 +100,7 @@ fn cell_ref_to_relocatable(cell_ref: CellRef, vm: &VirtualMachine) -> Relocatabl
     base + (cell_ref.offset as i32)
 }
 
-impl HintProcessor for CairoHintProcessor {
+impl HintProcessor for CairoHintProcessor<'_> {
     /// Trait function to execute a given hint in the hint processor.
     fn execute_hint(
         &self,
@@ -89,6 +110,7 @@ impl HintProcessor for CairoHintProcessor {
         _constants: &HashMap<String, BigInt>,
     ) -> Result<(), VirtualMachineError> {
         let hint = hint_data.downcast_ref::<Hint>().unwrap();
+        let prime = get_prime();
         // Retrieve a value located at memory[x].
         let get_relocatable_from_cellref =
             |c: CellRef| -> Result<Relocatable, VirtualMachineError> {
@@ -102,10 +124,28 @@ impl HintProcessor for CairoHintProcessor {
                 DerefOrImmediate::Immediate(i) => Ok(i),
             }
         };
+        // Retrieve a pointer given by a cell ref, possibly with an added value.
+        let get_ptr = |x: &ResOperand| -> Result<Relocatable, VirtualMachineError> {
+            match x {
+                ResOperand::Deref(val) => get_relocatable_from_cellref(*val),
+                ResOperand::BinOp(BinOpOperand { op: Operation::Add, a, b }) => {
+                    get_relocatable_from_cellref(*a)?.add_int_mod(&get_val(b.clone())?, &prime)
+                }
+                _ => panic!("Unexpected pointer operand {x}."),
+            }
+        };
         match hint {
             Hint::AllocSegment { dst } => {
                 let segment = vm.add_memory_segment();
                 vm.insert_value(&cell_ref_to_relocatable(*dst, vm), segment)?;
+                self.segment_capacities.borrow_mut().push(None);
+            }
+            Hint::AllocSegmentWithCapacity { capacity, elem_size, dst } => {
+                // The VM grows segments on demand, so the capacity is only recorded, in cells.
+                let capacity = get_val(capacity.clone())? * BigInt::from(*elem_size);
+                let segment = vm.add_memory_segment();
+                vm.insert_value(&cell_ref_to_relocatable(*dst, vm), segment)?;
+                self.segment_capacities.borrow_mut().push(Some(capacity));
             }
             Hint::TestLessThan { lhs, rhs, dst } => {
                 let lhs_val = get_val(lhs.clone())?;
@@ -126,6 +166,9 @@ impl HintProcessor for CairoHintProcessor {
             Hint::DivMod { lhs, rhs, quotient, remainder } => {
                 let lhs_val = get_val(lhs.clone())?;
                 let rhs_val = get_val(rhs.clone())?;
+                if rhs_val == BigInt::from(0) {
+                    return Err(VirtualMachineError::DividedByZero);
+                }
                 vm.insert_value(
                     &cell_ref_to_relocatable(*quotient, vm),
                     lhs_val.clone() / rhs_val.clone(),
@@ -138,17 +181,18 @@ impl HintProcessor for CairoHintProcessor {
             Hint::EnterScope => todo!(),
             Hint::ExitScope => todo!(),
             Hint::DictSquashHints { .. } => todo!(),
-            Hint::SystemCall { .. } => todo!(),
-            Hint::Bitwise { ptr } => {
-                let prime = get_prime();
-                let bitwise_ptr = match ptr {
-                    ResOperand::Deref(val) => get_relocatable_from_cellref(*val)?,
-                    ResOperand::BinOp(BinOpOperand { op: Operation::Add, a, b }) => {
-                        get_relocatable_from_cellref(*a)?
-                            .add_int_mod(&get_val(b.clone())?, &prime)?
+            Hint::SystemCall { system } => {
+                let system_ptr = get_ptr(system)?;
+                match &self.syscall_handler {
+                    Some(syscall_handler) => {
+                        syscall_handler.borrow_mut().execute_syscall(vm, system_ptr)?
                     }
-                    _ => panic!("Unexpected param type for Bitwise hint {ptr}."),
-                };
+                    // System calls are only supported by runs with a syscall handler.
+                    None => return Err(VirtualMachineError::UnknownHint(hint.to_string())),
+                }
+            }
+            Hint::Bitwise { ptr } => {
+                let bitwise_ptr = get_ptr(ptr)?;
                 let x = vm.get_integer(&bitwise_ptr)?.as_ref().clone();
                 let y = vm
                     .get_integer(&bitwise_ptr.add_int_mod(&BigInt::from(1), &prime)?)?
@@ -164,6 +208,26 @@ impl HintProcessor for CairoHintProcessor {
                 )?;
                 vm.insert_value(&bitwise_ptr.add_int_mod(&BigInt::from(4), &prime)?, x ^ y)?;
             }
+            Hint::DebugPrint { start, end } => {
+                let start = get_ptr(start)?;
+                let end = get_ptr(end)?;
+                let values = (0..(end.offset - start.offset))
+                    .map(|i| {
+                        Ok(vm
+                            .get_integer(&start.add_int_mod(&BigInt::from(i), &prime)?)?
+                            .as_ref()
+                            .clone())
+                    })
+                    .collect::<Result<Vec<_>, VirtualMachineError>>()?;
+                self.debug_output
+                    .borrow_mut()
+                    .write_all(format_for_debug(&values).as_bytes())
+                    .map_err(|error| {
+                        VirtualMachineError::CustomHint(format!(
+                            "Failed writing debug output: {error}"
+                        ))
+                    })?;
+            }
         };
         Ok(())
     }
@@ -184,13 +248,124 @@ impl HintProcessor for CairoHintProcessor {
 pub fn run_function(
     function: Vec<Instruction>,
 ) -> Result<(Vec<Option<BigInt>>, usize), Box<VirtualMachineError>> {
-    let data: Vec<MaybeRelocatable> = function
-        .iter()
-        .flat_map(|inst| inst.assemble().encode())
-        .map(MaybeRelocatable::from)
-        .collect();
+    run_function_with_debug_output(function, &mut std::io::stdout())
+}
 
-    let hint_processor = CairoHintProcessor::new(function);
+/// Runs `program` on layout with prime, and returns the memory layout and ap value.
+/// The output of debug print hints is written into `debug_output`.
+pub fn run_function_with_debug_output(
+    function: Vec<Instruction>,
+    debug_output: &mut dyn Write,
+) -> Result<(Vec<Option<BigInt>>, usize), Box<VirtualMachineError>> {
+    let output = run_function_ex(function, debug_output).map_err(|failure| failure.error)?;
+    Ok((output.memory, output.ap))
+}
+
+/// The capacity hints of the segments allocated during a run, in cells and in allocation order -
+/// `None` for segments allocated without a capacity hint.
+pub type SegmentCapacities = Vec<Option<BigInt>>;
+
+/// Runs `program` on layout with prime, and returns the memory layout, the ap value and the
+/// capacity hints of the segments allocated during the run.
+pub fn run_function_with_segment_capacities(
+    function: Vec<Instruction>,
+) -> Result<(Vec<Option<BigInt>>, usize, SegmentCapacities), Box<VirtualMachineError>> {
+    let output =
+        run_function_ex(function, &mut std::io::stdout()).map_err(|failure| failure.error)?;
+    Ok((output.memory, output.ap, output.segment_capacities))
+}
+
+/// A single step of a run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TraceEntry {
+    /// The offset of the executed instruction within the code.
+    pub pc: usize,
+    /// The value of ap before the instruction was executed.
+    pub ap: usize,
+    /// The value of fp before the instruction was executed.
+    pub fp: usize,
+}
+
+/// The output of a successful run.
+#[derive(Debug)]
+pub struct RunOutput {
+    /// The relocated memory of the run.
+    pub memory: Vec<Option<BigInt>>,
+    /// The final value of ap.
+    pub ap: usize,
+    /// The capacity hints of the segments allocated during the run.
+    pub segment_capacities: SegmentCapacities,
+    /// The executed steps, in execution order.
+    pub trace: Vec<TraceEntry>,
+}
+
+/// A failed run.
+#[derive(Debug)]
+pub struct RunFailure {
+    /// The offset within the code of the instruction the run failed on, if it failed during the
+    /// execution of the code.
+    pub pc: Option<usize>,
+    pub error: Box<VirtualMachineError>,
+}
+impl From<Box<VirtualMachineError>> for RunFailure {
+    fn from(error: Box<VirtualMachineError>) -> Self {
+        Self { pc: None, error }
+    }
+}
+impl From<VirtualMachineError> for RunFailure {
+    fn from(error: VirtualMachineError) -> Self {
+        Box::new(error).into()
+    }
+}
+
+/// Runs `function` on layout with prime, and returns the output of the run, including its trace.
+/// The output of debug print hints is written into `debug_output`.
+pub fn run_function_with_trace(
+    function: Vec<Instruction>,
+    debug_output: &mut dyn Write,
+) -> Result<RunOutput, RunFailure> {
+    run_function_ex(function, debug_output)
+}
+
+fn run_function_ex(
+    function: Vec<Instruction>,
+    debug_output: &mut dyn Write,
+) -> Result<RunOutput, RunFailure> {
+    let mut bytecode = vec![];
+    let mut hints = vec![];
+    for instruction in function {
+        if !instruction.hints.is_empty() {
+            hints.push((bytecode.len(), instruction.hints.clone()));
+        }
+        bytecode.extend(instruction.assemble().encode());
+    }
+    run_bytecode_ex(bytecode, &hints, debug_output, None)
+}
+
+/// Runs `bytecode`, with `hints` by the code offset they are attached to, and returns the memory
+/// layout and ap value.
+/// The system calls of the run are executed by `syscall_handler`, and the output of debug print
+/// hints is written into `debug_output`.
+pub fn run_bytecode(
+    bytecode: Vec<BigInt>,
+    hints: &[(usize, Vec<Hint>)],
+    syscall_handler: &mut dyn SyscallHintHandler,
+    debug_output: &mut dyn Write,
+) -> Result<(Vec<Option<BigInt>>, usize), Box<VirtualMachineError>> {
+    let output = run_bytecode_ex(bytecode, hints, debug_output, Some(syscall_handler))
+        .map_err(|failure| failure.error)?;
+    Ok((output.memory, output.ap))
+}
+
+fn run_bytecode_ex(
+    bytecode: Vec<BigInt>,
+    hints: &[(usize, Vec<Hint>)],
+    debug_output: &mut dyn Write,
+    syscall_handler: Option<&mut dyn SyscallHintHandler>,
+) -> Result<RunOutput, RunFailure> {
+    let data: Vec<MaybeRelocatable> = bytecode.into_iter().map(MaybeRelocatable::from).collect();
+
+    let hint_processor = CairoHintProcessor::new(hints, debug_output, syscall_handler);
 
     let program = Program {
         builtins: Vec::new(),
@@ -213,12 +388,28 @@ pub fn run_function(
 
     let end = runner.initialize(&mut vm).map_err(VirtualMachineError::from).map_err(Box::new)?;
 
-    runner.run_until_pc(end, &mut vm, &hint_processor)?;
+    // The program is the first segment, and its main is at offset 0, so the offset of pc is the
+    // offset of the instruction within the code.
+    runner
+        .run_until_pc(end, &mut vm, &hint_processor)
+        .map_err(|error| RunFailure { pc: Some(vm.get_pc().offset), error: Box::new(error) })?;
     // TODO(alont) Remove this hack once the VM no longer squashes Nones at the end of segments.
     vm.insert_value(&vm.get_ap().add_int_mod(&1.into(), &get_prime())?, BigInt::from(0))?;
-    runner.end_run(true, false, &mut vm, &hint_processor).map_err(Box::new)?;
-    runner.relocate(&mut vm).map_err(VirtualMachineError::from).map_err(Box::new)?;
-    Ok((runner.relocated_memory, runner.relocated_trace.unwrap().last().unwrap().ap))
+    runner.end_run(true, false, &mut vm, &hint_processor)?;
+    runner.relocate(&mut vm).map_err(VirtualMachineError::from)?;
+    let relocated_trace = runner.relocated_trace.unwrap();
+    // The run starts at the first instruction of the code.
+    let code_start = relocated_trace[0].pc;
+    let trace = relocated_trace
+        .iter()
+        .map(|entry| TraceEntry { pc: entry.pc - code_start, ap: entry.ap, fp: entry.fp })
+        .collect();
+    Ok(RunOutput {
+        memory: runner.relocated_memory,
+        ap: relocated_trace.last().unwrap().ap,
+        segment_capacities: hint_processor.segment_capacities.into_inner(),
+        trace,
+    })
 }
 
 /// Runs `function` and returns `n_returns` return values.
@@ -1,9 +1,19 @@
+use std::io::Write;
+
+use cairo_rs::types::relocatable::Relocatable;
+use cairo_rs::vm::errors::vm_errors::VirtualMachineError;
+use cairo_rs::vm::vm_core::VirtualMachine;
 use itertools::Itertools;
 use num_bigint::{BigInt, Sign};
 use test_case::test_case;
 
+use crate::hints::Hint;
 use crate::inline::CasmContext;
-use crate::run::{run_function, run_function_return_values};
+use crate::operand::{BinOpOperand, DerefOrImmediate, Operation, ResOperand};
+use crate::run::{
+    run_bytecode, run_function, run_function_return_values, run_function_with_debug_output,
+    run_function_with_segment_capacities, run_function_with_trace, SyscallHintHandler,
+};
 use crate::{casm, deref};
 
 #[test_case(
@@ -111,3 +121,160 @@ fn test_allocate_segment() {
     let [ptr] = &digits[..] else {panic!("Number not in index range.");};
     assert_eq!(memory[*ptr as usize], Some(BigInt::from(1337)));
 }
+
+#[test]
+fn test_allocate_segment_with_capacity() {
+    let mut function = casm! {
+        [ap] = 1337, ap++;
+        [ap] = 8, ap++;
+        [ap - 2] = [[&deref!([ap])]];
+        ret;
+    };
+    function.instructions[2].hints.push(Hint::AllocSegmentWithCapacity {
+        capacity: deref!([ap - 1]).into(),
+        elem_size: 3,
+        dst: deref!([ap]),
+    });
+    let (memory, ap, segment_capacities) =
+        run_function_with_segment_capacities(function.instructions).expect("Running code failed.");
+    assert_eq!(segment_capacities, vec![Some(BigInt::from(24))]);
+    let ptr = memory[ap].as_ref().expect("Uninitialized value.");
+    let (Sign::Plus, digits) = ptr.to_u64_digits() else {panic!("Negative number.");};
+    let [ptr] = &digits[..] else {panic!("Number not in index range.");};
+    assert_eq!(memory[*ptr as usize], Some(BigInt::from(1337)));
+}
+
+/// A syscall handler responding to a request of a single value with the value plus one.
+#[derive(Default)]
+struct IncrementingSyscallHandler {
+    n_calls: usize,
+}
+impl SyscallHintHandler for IncrementingSyscallHandler {
+    fn execute_syscall(
+        &mut self,
+        vm: &mut VirtualMachine,
+        system_ptr: Relocatable,
+    ) -> Result<(), VirtualMachineError> {
+        self.n_calls += 1;
+        let request = vm.get_integer(&system_ptr)?.as_ref().clone();
+        vm.insert_value(&(system_ptr + 1_i32), request + 1)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_run_bytecode_with_syscall() {
+    let mut function = casm! {
+        %{ memory[ap] = segments.add() %}
+        ap += 1;
+        [ap] = 5, ap++;
+        [ap - 1] = [[ap - 2] + 0];
+        [ap] = [[ap - 2] + 1], ap++;
+        ret;
+    };
+    function.instructions[3]
+        .hints
+        .push(Hint::SystemCall { system: ResOperand::Deref(deref!([ap - 2])) });
+    let mut bytecode = vec![];
+    let mut hints = vec![];
+    for instruction in function.instructions {
+        if !instruction.hints.is_empty() {
+            hints.push((bytecode.len(), instruction.hints.clone()));
+        }
+        bytecode.extend(instruction.assemble().encode());
+    }
+    let mut syscall_handler = IncrementingSyscallHandler::default();
+    let (memory, ap) = run_bytecode(bytecode, &hints, &mut syscall_handler, &mut vec![])
+        .expect("Running code failed.");
+    assert_eq!(syscall_handler.n_calls, 1);
+    assert_eq!(memory[ap - 1], Some(BigInt::from(6)));
+}
+
+#[test]
+fn test_run_function_with_syscall_fails() {
+    // A run without a syscall handler can not execute system calls.
+    let mut function = casm! {
+        %{ memory[ap] = segments.add() %}
+        ap += 1;
+        [ap] = 5, ap++;
+        [ap - 1] = [[ap - 2] + 0];
+        ret;
+    };
+    function.instructions[3]
+        .hints
+        .push(Hint::SystemCall { system: ResOperand::Deref(deref!([ap - 2])) });
+    assert!(run_function(function.instructions).is_err());
+}
+
+/// A writer failing every write.
+struct FailingWriter;
+impl Write for FailingWriter {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::new(std::io::ErrorKind::Other, "closed"))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_run_function_with_failing_debug_output() {
+    // Prints an array holding the single value 5.
+    let function = || {
+        let mut function = casm! {
+            %{ memory[ap] = segments.add() %}
+            ap += 1;
+            [ap] = 5, ap++;
+            [ap - 1] = [[ap - 2] + 0];
+            ret;
+        };
+        function.instructions[3].hints.push(Hint::DebugPrint {
+            start: ResOperand::Deref(deref!([ap - 2])),
+            end: ResOperand::BinOp(BinOpOperand {
+                op: Operation::Add,
+                a: deref!([ap - 2]),
+                b: DerefOrImmediate::Immediate(BigInt::from(1)),
+            }),
+        });
+        function.instructions
+    };
+    let mut debug_output = vec![];
+    run_function_with_debug_output(function(), &mut debug_output).expect("Running code failed.");
+    assert_eq!(String::from_utf8(debug_output).unwrap(), "[DEBUG]\t5\n");
+    assert!(run_function_with_debug_output(function(), &mut FailingWriter).is_err());
+}
+
+#[test]
+fn test_run_function_with_trace() {
+    let output = run_function_with_trace(
+        casm! {
+            [ap] = 1, ap++;
+            ap += 2;
+            [ap] = [ap - 3], ap++;
+            ret;
+        }
+        .instructions,
+        &mut std::io::stdout(),
+    )
+    .expect("Running code failed.");
+    assert_eq!(
+        output.trace.iter().map(|entry| (entry.pc, entry.ap - entry.fp)).collect_vec(),
+        vec![(0, 0), (2, 1), (4, 3), (5, 4)]
+    );
+}
+
+#[test]
+fn test_run_function_with_trace_failure() {
+    let failure = run_function_with_trace(
+        casm! {
+            [ap] = 1, ap++;
+            [ap - 1] = 2;
+            ret;
+        }
+        .instructions,
+        &mut std::io::stdout(),
+    )
+    .expect_err("Running code should fail.");
+    assert_eq!(failure.pc, Some(2));
+}
@@ -1,12 +1,17 @@
-use std::collections::HashSet;
 use std::sync::Arc;
 
 use db_utils::Upcast;
-use defs::ids::{FreeFunctionId, ModuleId, ModuleItemId};
-use diagnostics::{Diagnostics, DiagnosticsBuilder, Maybe};
+use defs::ids::{
+    ExternFunctionId, FreeFunctionId, LanguageElementId, ModuleId, ModuleItemId,
+    TopLevelLanguageElementId,
+};
+use diagnostics::{skip_diagnostic, Diagnostics, DiagnosticsBuilder, Maybe};
 use filesystem::ids::FileId;
 use semantic::db::SemanticGroup;
 use semantic::TypeId;
+use smol_str::SmolStr;
+use utils::ordered_hash_set::OrderedHashSet;
+use utils::unordered_hash_map::UnorderedHashMap;
 
 use crate::diagnostic::LoweringDiagnostic;
 use crate::lower::{lower, Lowered};
@@ -42,7 +47,7 @@ pub trait LoweringGroup: SemanticGroup + Upcast<dyn SemanticGroup> {
     fn function_scc_explicit_implicits(
         &self,
         function: SCCRepresentative,
-    ) -> Maybe<HashSet<TypeId>>;
+    ) -> Maybe<OrderedHashSet<TypeId>>;
 
     /// Returns all the implicit parameters that the function requires (according to both its
     /// signature and the functions it calls). The items in the returned vector are unique and the
@@ -54,7 +59,10 @@ pub trait LoweringGroup: SemanticGroup + Upcast<dyn SemanticGroup> {
     /// Returns all the implicit parameters that the free function requires (according to both its
     /// signature and the functions it calls).
     #[salsa::invoke(crate::lower::implicits::free_function_all_implicits)]
-    fn free_function_all_implicits(&self, function: FreeFunctionId) -> Maybe<HashSet<TypeId>>;
+    fn free_function_all_implicits(
+        &self,
+        function: FreeFunctionId,
+    ) -> Maybe<OrderedHashSet<TypeId>>;
 
     /// Returns all the implicit parameters that the free function requires (according to both its
     /// signature and the functions it calls). The items in the returned vector are unique and the
@@ -78,11 +86,27 @@ pub trait LoweringGroup: SemanticGroup + Upcast<dyn SemanticGroup> {
     /// An array that sets the precedence of implicit types.
     #[salsa::input]
     fn implicit_precedence(&self) -> Arc<Vec<TypeId>>;
+
+    // --- Queries related to extern function substitutions ---
+
+    /// A map from the name of an extern function to the name of a sibling extern function, declared
+    /// in the same module, to be used in its place. Allows the configuration of the compilation to
+    /// select between variants of a libfunc, which may differ in their implicits.
+    #[salsa::input]
+    fn extern_function_substitutions(&self) -> Arc<UnorderedHashMap<SmolStr, SmolStr>>;
+
+    /// Returns the extern function to be used in place of the given extern function, according to
+    /// [Self::extern_function_substitutions]. Returns the given function if it is not substituted.
+    fn substituted_extern_function(
+        &self,
+        extern_function: ExternFunctionId,
+    ) -> Maybe<ExternFunctionId>;
 }
 
 pub fn init_lowering_group(db: &mut (dyn LoweringGroup + 'static)) {
     // Initialize inputs.
     db.set_implicit_precedence(Arc::new(vec![]));
+    db.set_extern_function_substitutions(Arc::new(UnorderedHashMap::default()));
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
@@ -134,3 +158,17 @@ fn file_lowering_diagnostics(
     }
     Ok(diagnostics.build())
 }
+
+fn substituted_extern_function(
+    db: &dyn LoweringGroup,
+    extern_function: ExternFunctionId,
+) -> Maybe<ExternFunctionId> {
+    let substitutions = db.extern_function_substitutions();
+    let Some(substitute) = substitutions.get(&extern_function.name(db.upcast())) else {
+        return Ok(extern_function);
+    };
+    match db.module_item_by_name(extern_function.module(db.upcast()), substitute.clone())? {
+        Some(ModuleItemId::ExternFunction(substitute)) => Ok(substitute),
+        _ => Err(skip_diagnostic()),
+    }
+}
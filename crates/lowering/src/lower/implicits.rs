@@ -1,9 +1,8 @@
-use std::collections::HashSet;
-
 use defs::ids::{FreeFunctionId, GenericFunctionId};
 use diagnostics::Maybe;
 use itertools::Itertools;
 use semantic::TypeId;
+use utils::ordered_hash_set::OrderedHashSet;
 use utils::strongly_connected_components::{compute_scc, GraphNode};
 
 use crate::db::{LoweringGroup, SCCRepresentative};
@@ -20,13 +19,13 @@ pub fn function_scc_representative(
 pub fn function_scc_explicit_implicits(
     db: &dyn LoweringGroup,
     function: SCCRepresentative,
-) -> Maybe<HashSet<TypeId>> {
+) -> Maybe<OrderedHashSet<TypeId>> {
     let scc = function_scc(db, function.0);
-    let mut explicit_implicits = HashSet::new();
+    let mut explicit_implicits = OrderedHashSet::default();
     for func in scc {
-        let current_implicits: HashSet<TypeId> =
-            db.free_function_declaration_implicits(func)?.into_iter().collect();
-        explicit_implicits.extend(current_implicits);
+        for ty in db.free_function_declaration_implicits(func)? {
+            explicit_implicits.insert(ty);
+        }
     }
     Ok(explicit_implicits)
 }
@@ -38,9 +37,9 @@ pub fn function_all_implicits(
 ) -> Maybe<Vec<TypeId>> {
     match db.lookup_intern_function(function).function.generic_function {
         GenericFunctionId::Free(free_function) => db.free_function_all_implicits_vec(free_function),
-        GenericFunctionId::Extern(extern_function) => {
-            db.extern_function_declaration_implicits(extern_function)
-        }
+        GenericFunctionId::Extern(extern_function) => db.extern_function_declaration_implicits(
+            db.substituted_extern_function(extern_function)?,
+        ),
         GenericFunctionId::TraitFunction(_) | GenericFunctionId::ImplFunction(_) => todo!(),
     }
 }
@@ -49,7 +48,7 @@ pub fn function_all_implicits(
 pub fn free_function_all_implicits(
     db: &dyn LoweringGroup,
     function: FreeFunctionId,
-) -> Maybe<HashSet<TypeId>> {
+) -> Maybe<OrderedHashSet<TypeId>> {
     // Find the SCC representative.
     let scc_representative = db.function_scc_representative(function);
 
@@ -73,11 +72,17 @@ pub fn free_function_all_implicits(
                 }
                 GenericFunctionId::Extern(extern_function) => {
                     // All implicits of a libfunc are explicit implicits.
-                    db.extern_function_declaration_implicits(extern_function)?.into_iter().collect()
+                    db.extern_function_declaration_implicits(
+                        db.substituted_extern_function(extern_function)?,
+                    )?
+                    .into_iter()
+                    .collect()
                 }
                 GenericFunctionId::TraitFunction(_) | GenericFunctionId::ImplFunction(_) => todo!(),
             };
-        all_implicits.extend(&current_implicits);
+        for ty in current_implicits {
+            all_implicits.insert(ty);
+        }
     }
     Ok(all_implicits)
 }
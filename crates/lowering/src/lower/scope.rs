@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
 use diagnostics::Maybe;
 use itertools::chain;
@@ -30,7 +30,7 @@ pub struct BlockScope {
     /// A store for semantic variables, owning their OwnedVariable instances.
     semantic_variables: SemanticVariablesMap,
     /// A store for implicit variables, owning their OwnedVariable instances.
-    implicits: HashMap<semantic::TypeId, LivingVar>,
+    implicits: OrderedHashMap<semantic::TypeId, LivingVar>,
     // The implicits that are used/changed in this block.
     changed_implicits: HashSet<semantic::TypeId>,
     /// Current sequence of lowered statements emitted.
@@ -106,7 +106,7 @@ impl BlockScope {
     /// Marks the implicit as changed and moves it.
     pub fn take_implicit(&mut self, ty: semantic::TypeId) -> Option<LivingVar> {
         self.mark_implicit_changed(ty);
-        self.implicits.remove(&ty)
+        self.implicits.shift_remove(&ty)
     }
 
     pub fn mark_implicit_changed(&mut self, ty: semantic::TypeId) {
@@ -149,7 +149,7 @@ impl BlockScope {
 
     /// Pull the living implicit variables into the given merger.
     fn pull_implicits(&mut self, merger: &mut BlockFlowMerger) {
-        for (ty, var) in self.implicits.drain() {
+        for (ty, var) in std::mem::take(&mut self.implicits) {
             let usable_var = self.living_variables.take_var(var);
             let living_var = merger.splitter.add(usable_var);
             merger.implicit_pulls.insert(ty, living_var);
@@ -167,7 +167,7 @@ pub struct BlockSealed {
     /// The semantic variables and their state in the end of the block.
     semantic_variables: SemanticVariablesMap,
     /// All the implicits available in this block.
-    implicits: HashMap<semantic::TypeId, LivingVar>,
+    implicits: OrderedHashMap<semantic::TypeId, LivingVar>,
     /// The implicits that were used/changed by this block.
     changed_implicits: HashSet<semantic::TypeId>,
     /// The lowered statements of this block.
@@ -246,7 +246,7 @@ impl BlockSealed {
                         // This should not panic as implicits are always alive (may only change, but
                         // not drop).
                         let var = implicits
-                            .remove(ty)
+                            .shift_remove(ty)
                             .expect("Implicit removed from main map before finalize()");
                         living_variables.take_var(var).var_id()
                     })
@@ -539,7 +539,7 @@ impl BlockFlowMerger {
         // TODO(spapini): extra_outputs might not be alive. Currently, this panics.
         pushes.extend(extra_outputs.iter().copied());
 
-        let mut unchanged_implicits = HashMap::new();
+        let mut unchanged_implicits = OrderedHashMap::default();
         if self.parent_scope.is_some() {
             // The unchanged implicits are all the ones existing in the parent scope minus the
             // changed ones. Note that in an optimized match, the ones existing in
@@ -604,7 +604,7 @@ pub struct OuterImplicitInfo {
     /// Implicits that are returned from current scope to the calling scope via block outputs.
     pub pushes: Vec<semantic::TypeId>,
     /// Implicits that are returned from current scope to the calling scope by not changing them.
-    pub unchanged: HashMap<semantic::TypeId, LivingVar>,
+    pub unchanged: OrderedHashMap<semantic::TypeId, LivingVar>,
 }
 
 /// Used to finalize blocks. Generated after calling [`BlockFlowMerger::finalize()`].
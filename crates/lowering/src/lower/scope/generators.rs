@@ -151,8 +151,13 @@ impl MatchExtern {
                 .function
                 .try_get_extern_function_id(ctx.db.upcast())
                 .expect("Expected an extern function");
-            let num_implicits =
-                ctx.db.extern_function_declaration_implicits(extern_function_id).unwrap().len();
+            let num_implicits = ctx
+                .db
+                .extern_function_declaration_implicits(
+                    ctx.db.substituted_extern_function(extern_function_id).unwrap(),
+                )
+                .unwrap()
+                .len();
             let num_refs =
                 ctx.db.extern_function_declaration_refs(extern_function_id).unwrap().len();
             let total_extra_inputs = num_implicits + num_refs;
@@ -8,6 +8,7 @@ use compiler::db::RootDatabase;
 use compiler::diagnostics::check_and_eprint_diagnostics;
 use compiler::project::setup_project;
 use diagnostics::ToOption;
+use itertools::Itertools;
 use runner::SierraCasmRunner;
 use sierra_generator::db::SierraGenGroup;
 use sierra_generator::replace_ids::replace_sierra_ids_in_program;
@@ -26,6 +27,9 @@ struct Args {
     /// Whether to print the memory.
     #[arg(long, default_value_t = false)]
     print_full_memory: bool,
+    /// Whether to print the number of steps executed in each function.
+    #[arg(long, default_value_t = false)]
+    profile: bool,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -73,5 +77,13 @@ fn main() -> anyhow::Result<()> {
         }
         println!("]");
     }
+    if args.profile {
+        println!("Steps per function:");
+        for (function, steps) in
+            result.function_steps.iter().sorted_by_key(|(_, steps)| std::cmp::Reverse(**steps))
+        {
+            println!("  {function}: {steps}");
+        }
+    }
     Ok(())
 }
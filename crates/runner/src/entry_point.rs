@@ -0,0 +1,568 @@
+//! Running the entry points of compiled contract classes.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use cairo_rs::types::relocatable::Relocatable;
+use cairo_rs::vm::errors::vm_errors::VirtualMachineError;
+use cairo_rs::vm::vm_core::VirtualMachine;
+use casm::hints::structured::parse_hint;
+use casm::instructions::Instruction;
+use casm::run::{run_bytecode, SyscallHintHandler};
+use casm::{casm, casm_extend};
+use num_bigint::{BigInt, BigUint};
+use sierra_to_casm::syscall_records::{
+    execute_syscall, CallContractRequest, CallContractResponse, DeployRequest, DeployResponse,
+    EmitEventRequest, EmitEventResponse, GetBlockNumberRequest, GetBlockNumberResponse,
+    GetBlockTimestampRequest, GetBlockTimestampResponse, GetCallerAddressRequest,
+    GetCallerAddressResponse, GetContractAddressRequest, GetContractAddressResponse,
+    GetSequencerAddressRequest, GetSequencerAddressResponse, GetTxInfoRequest, GetTxInfoResponse,
+    KeccakRequest, KeccakResponse, LibraryCallRequest, LibraryCallResponse, ReplaceClassRequest,
+    ReplaceClassResponse, Secp256k1AddRequest, Secp256k1AddResponse, Secp256k1GetXyRequest,
+    Secp256k1GetXyResponse, Secp256k1MulRequest, Secp256k1MulResponse, Secp256k1NewRequest,
+    Secp256k1NewResponse, SendMessageToL1Request, SendMessageToL1Response, StorageReadRequest,
+    StorageReadResponse, StorageWriteRequest, StorageWriteResponse, SyscallHandler, SyscallMemory,
+};
+use starknet::casm_contract_class::{CasmContractClass, CasmContractEntryPoint};
+use utils::short_string;
+
+use crate::starknet_state::{L2ToL1Message, StarknetState};
+use crate::{RunResultValue, RunnerError};
+
+#[cfg(test)]
+#[path = "entry_point_test.rs"]
+mod test;
+
+/// The configuration of an entry point run.
+#[derive(Debug, Default)]
+pub struct EntryPointRunConfig {
+    /// The gas available for the run, given to entry points using the gas builtin.
+    pub available_gas: usize,
+    /// The storage of the contract at the beginning of the run.
+    pub storage: HashMap<BigInt, BigInt>,
+}
+
+/// The result of an entry point run.
+#[derive(Debug)]
+pub struct EntryPointRunResult {
+    /// The gas left at the end of the run, for entry points using the gas builtin.
+    pub gas_counter: Option<BigInt>,
+    /// The storage of the contract at the end of the run.
+    pub storage: HashMap<BigInt, BigInt>,
+    /// The returned data of the run, or the panic data if the run reverted.
+    pub value: RunResultValue,
+    /// The events emitted by the run, in the order of emission.
+    pub events: Vec<EmittedEvent>,
+}
+
+/// An event emitted by an entry point run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmittedEvent {
+    pub keys: Vec<BigInt>,
+    pub data: Vec<BigInt>,
+}
+
+/// Runs the external entry point of `casm_class` with the given selector, with `calldata` as its
+/// input.
+///
+/// The builtin segments, the system segment and the calldata segment are allocated by an entry
+/// code preceding the class bytecode. The entry point runs as the contract at address 0, whose
+/// storage syscalls are served from the storage of `config`, and emitted events are recorded in
+/// the result. Contract calls are not supported yet, and revert with [UNSUPPORTED_SYSCALL].
+/// Debug prints are written to the standard output.
+/// The run fails if any of the values written to the range check segment is out of range.
+pub fn run_entry_point(
+    casm_class: &CasmContractClass,
+    selector: &BigUint,
+    calldata: &[BigInt],
+    config: EntryPointRunConfig,
+) -> Result<EntryPointRunResult, RunnerError> {
+    run_entry_point_with_debug_output(
+        casm_class,
+        selector,
+        calldata,
+        config,
+        &mut std::io::stdout(),
+    )
+}
+
+/// Runs the external entry point of `casm_class` as [run_entry_point] does, writing the debug
+/// prints into `debug_output`.
+pub fn run_entry_point_with_debug_output(
+    casm_class: &CasmContractClass,
+    selector: &BigUint,
+    calldata: &[BigInt],
+    config: EntryPointRunConfig,
+    debug_output: &mut dyn Write,
+) -> Result<EntryPointRunResult, RunnerError> {
+    let contract_address = BigInt::from(0);
+    let mut state = StarknetState::default();
+    state.set_contract_storage(&contract_address, config.storage);
+    let (gas_counter, value) = run_entry_point_in_state(
+        casm_class,
+        selector,
+        calldata,
+        config.available_gas,
+        &mut state,
+        &contract_address,
+        debug_output,
+    )?;
+    Ok(EntryPointRunResult {
+        gas_counter,
+        storage: state.take_contract_storage(&contract_address),
+        value,
+        events: state.take_events(),
+    })
+}
+
+/// Runs the external entry point of `casm_class` with the given selector as the contract at
+/// `contract_address`, with its syscalls served from and recorded into `state`.
+/// Returns the gas left at the end of the run, and the returned or panic data.
+pub(crate) fn run_entry_point_in_state(
+    casm_class: &CasmContractClass,
+    selector: &BigUint,
+    calldata: &[BigInt],
+    available_gas: usize,
+    state: &mut StarknetState,
+    contract_address: &BigInt,
+    debug_output: &mut dyn Write,
+) -> Result<(Option<BigInt>, RunResultValue), RunnerError> {
+    let entry_point = casm_class
+        .entry_points_by_type
+        .external
+        .iter()
+        .find(|entry_point| entry_point.selector == *selector)
+        .ok_or_else(|| RunnerError::MissingEntryPoint { selector: selector.clone() })?;
+    let entry_code = create_entry_code(entry_point, calldata, available_gas)?;
+
+    let mut bytecode = vec![];
+    let mut hints = vec![];
+    for instruction in entry_code {
+        if !instruction.hints.is_empty() {
+            hints.push((bytecode.len(), instruction.hints.clone()));
+        }
+        bytecode.extend(instruction.assemble().encode());
+    }
+    let entry_code_size = bytecode.len();
+    bytecode.extend(casm_class.bytecode.iter().map(|value| BigInt::from(value.value.clone())));
+    for (offset, structured_hints) in &casm_class.structured_hints {
+        hints.push((
+            entry_code_size + offset,
+            structured_hints.iter().map(|hint| parse_hint(hint)).collect::<Result<_, _>>()?,
+        ));
+    }
+
+    let mut syscall_handler = EntryPointSyscallHandler {
+        state: std::mem::take(state),
+        contract_address: contract_address.clone(),
+        ..Default::default()
+    };
+    let run_result = run_bytecode(bytecode, &hints, &mut syscall_handler, debug_output);
+    *state = syscall_handler.state;
+    let (cells, ap) = run_result?;
+    let cell = |address: usize| cells[address].clone().expect("Uninitialized return value.");
+
+    // The entry code copies the start of the range check segment right after the returned values.
+    let range_check_idx = range_check_idx(entry_point);
+    let ap = if range_check_idx.is_some() { ap - 1 } else { ap };
+    // The returned values are the builtins, the system pointer and the panic result - the variant
+    // of the result, followed by the start and end of the returned data array.
+    let builtin_value = |idx: usize| cell(ap - 4 - entry_point.builtins.len() + idx);
+    if let Some(idx) = range_check_idx {
+        // The range check builtin of the vm is not used, so the range checked values are validated
+        // after the run.
+        let start = usize::try_from(cell(ap)).unwrap();
+        let end = usize::try_from(builtin_value(idx)).unwrap();
+        let bound = BigInt::from(1) << 128;
+        if let Some(value) = (start..end).map(cell).find(|value| *value >= bound) {
+            return Err(RunnerError::RangeCheckViolation { value });
+        }
+    }
+    let gas_counter =
+        entry_point.builtins.iter().position(|builtin| builtin == "GasBuiltin").map(builtin_value);
+    let data_start = usize::try_from(cell(ap - 2)).unwrap();
+    let data_end = usize::try_from(cell(ap - 1)).unwrap();
+    let data = (data_start..data_end).map(cell).collect();
+    let value = if cell(ap - 3) != BigInt::from(0) {
+        RunResultValue::Panic(data)
+    } else {
+        RunResultValue::Success(data)
+    };
+    Ok((gas_counter, value))
+}
+
+/// Returns the instructions calling `entry_point`, to be placed right before the class bytecode.
+fn create_entry_code(
+    entry_point: &CasmContractEntryPoint,
+    calldata: &[BigInt],
+    available_gas: usize,
+) -> Result<Vec<Instruction>, RunnerError> {
+    // Writing the calldata into its own segment, which starts at the first allocated cell.
+    let mut ctx = casm! {
+        %{ memory[ap + 0] = segments.add() %}
+        ap += 1;
+    };
+    for (i, value) in calldata.iter().enumerate() {
+        let ptr_distance = i as i16 + 2;
+        let value_offset = i as i16;
+        casm_extend! {ctx,
+            [ap + 0] = (value.clone()), ap++;
+            [ap - 1] = [[ap - ptr_distance] + value_offset];
+        }
+    }
+    // The arguments of the entry point - the builtins, the system pointer and the calldata array.
+    for builtin in &entry_point.builtins {
+        match builtin.as_str() {
+            "GasBuiltin" => {
+                casm_extend! {ctx,
+                    [ap + 0] = available_gas, ap++;
+                }
+            }
+            "RangeCheck" | "Pedersen" | "Bitwise" => {
+                // TODO(orizi): Use the vm's builtin segments.
+                casm_extend! {ctx,
+                    %{ memory[ap + 0] = segments.add() %}
+                    ap += 1;
+                }
+            }
+            _ => return Err(RunnerError::UnsupportedBuiltin(builtin.clone())),
+        }
+    }
+    let calldata_ptr_distance = calldata.len() as i16 + entry_point.builtins.len() as i16 + 2;
+    let calldata_len = calldata.len();
+    casm_extend! {ctx,
+        %{ memory[ap + 0] = segments.add() %}
+        ap += 1;
+        [ap + 0] = [ap - calldata_ptr_distance], ap++;
+        [ap + 0] = [ap - 1] + calldata_len, ap++;
+    }
+    let before_final_call = ctx.current_code_offset;
+    let final_call_size = if range_check_idx(entry_point).is_some() { 4 } else { 3 };
+    let offset = final_call_size + entry_point.offset;
+    casm_extend! {ctx,
+        call rel offset;
+    }
+    if let Some(idx) = range_check_idx(entry_point) {
+        // Copying the start of the range check segment, for validating its values after the run.
+        let range_check_start_offset = (1 + calldata.len() + idx) as i16;
+        casm_extend! {ctx,
+            [ap + 0] = [fp + range_check_start_offset], ap++;
+        }
+    }
+    casm_extend! {ctx,
+        ret;
+    }
+    assert_eq!(before_final_call + final_call_size, ctx.current_code_offset);
+    Ok(ctx.instructions)
+}
+
+/// Returns the index of the range check builtin among the builtins of `entry_point`, if used.
+fn range_check_idx(entry_point: &CasmContractEntryPoint) -> Option<usize> {
+    entry_point.builtins.iter().position(|builtin| builtin == "RangeCheck")
+}
+
+/// The revert reason of the syscalls that are not supported by the entry point runner.
+pub const UNSUPPORTED_SYSCALL: &str = "Unsupported syscall";
+
+/// Returns the revert reason of a syscall that is not supported by the entry point runner.
+fn unsupported_syscall() -> BigInt {
+    short_string::from_str(UNSUPPORTED_SYSCALL).unwrap()
+}
+
+/// The revert reason of the syscalls whose call data costs more gas than was passed to them.
+pub const OUT_OF_GAS: &str = "OOG";
+
+/// Returns the gas counter and revert reason of a syscall that charges its call data per word.
+///
+/// The charge is deducted from the gas counter before the syscall, so a counter that does not fit
+/// in 128 bits has wrapped around the field, and the syscall runs out of gas.
+fn charged_call_data_result(gas_counter: BigInt) -> (BigInt, BigInt) {
+    if gas_counter.bits() > 128 {
+        (0.into(), short_string::from_str(OUT_OF_GAS).unwrap())
+    } else {
+        (gas_counter, unsupported_syscall())
+    }
+}
+
+/// Serves the syscalls of an entry point run.
+#[derive(Default)]
+struct EntryPointSyscallHandler {
+    /// The state the syscalls are served from, and the emitted events and messages are recorded
+    /// into.
+    state: StarknetState,
+    /// The address of the contract whose entry point is run.
+    contract_address: BigInt,
+    /// The request of an `emit_event` syscall being executed, whose keys and data are read from
+    /// the vm memory once the syscall is handled.
+    pending_event: Option<EmitEventRequest>,
+    /// The request of a `send_message_to_l1` syscall being executed, whose payload is read from
+    /// the vm memory once the syscall is handled.
+    pending_message: Option<SendMessageToL1Request>,
+}
+impl SyscallHandler for EntryPointSyscallHandler {
+    fn storage_read(&mut self, request: StorageReadRequest) -> StorageReadResponse {
+        StorageReadResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: 0.into(),
+            value: self.state.storage_read(&self.contract_address, &request.address),
+        }
+    }
+
+    fn storage_write(&mut self, request: StorageWriteRequest) -> StorageWriteResponse {
+        self.state.storage_write(&self.contract_address, request.address, request.value);
+        StorageWriteResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: 0.into(),
+            reserved: 0.into(),
+        }
+    }
+
+    fn call_contract(&mut self, request: CallContractRequest) -> CallContractResponse {
+        // There are no other contracts to call. The returned data is an empty array at the end of
+        // the call data, which is consumed by the call.
+        let (gas_counter, revert_reason) = charged_call_data_result(request.gas_counter);
+        CallContractResponse {
+            gas_counter,
+            revert_reason,
+            retdata_start: request.calldata_end.clone(),
+            retdata_end: request.calldata_end,
+        }
+    }
+
+    fn library_call(&mut self, request: LibraryCallRequest) -> LibraryCallResponse {
+        // There are no declared classes to call. As with contract calls, the returned data is an
+        // empty array at the end of the call data.
+        let (gas_counter, revert_reason) = charged_call_data_result(request.gas_counter);
+        LibraryCallResponse {
+            gas_counter,
+            revert_reason,
+            retdata_start: request.calldata_end.clone(),
+            retdata_end: request.calldata_end,
+        }
+    }
+
+    fn deploy(&mut self, request: DeployRequest) -> DeployResponse {
+        // There are no declared classes to deploy. As with contract calls, the returned data is an
+        // empty array at the end of the call data.
+        let (gas_counter, revert_reason) = charged_call_data_result(request.gas_counter);
+        DeployResponse {
+            gas_counter,
+            revert_reason,
+            contract_address: 0.into(),
+            retdata_start: request.calldata_end.clone(),
+            retdata_end: request.calldata_end,
+        }
+    }
+
+    fn send_message_to_l1(&mut self, request: SendMessageToL1Request) -> SendMessageToL1Response {
+        let gas_counter = request.gas_counter.clone();
+        self.pending_message = Some(request);
+        SendMessageToL1Response { gas_counter, revert_reason: 0.into() }
+    }
+
+    fn emit_event(&mut self, request: EmitEventRequest) -> EmitEventResponse {
+        let gas_counter = request.gas_counter.clone();
+        self.pending_event = Some(request);
+        EmitEventResponse { gas_counter, revert_reason: 0.into() }
+    }
+
+    fn get_caller_address(&mut self, request: GetCallerAddressRequest) -> GetCallerAddressResponse {
+        // Entry points are run directly, rather than called by another contract.
+        GetCallerAddressResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: 0.into(),
+            caller_address: 0.into(),
+        }
+    }
+
+    fn get_contract_address(
+        &mut self,
+        request: GetContractAddressRequest,
+    ) -> GetContractAddressResponse {
+        GetContractAddressResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: 0.into(),
+            contract_address: self.contract_address.clone(),
+        }
+    }
+
+    fn get_sequencer_address(
+        &mut self,
+        request: GetSequencerAddressRequest,
+    ) -> GetSequencerAddressResponse {
+        GetSequencerAddressResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: 0.into(),
+            sequencer_address: 0.into(),
+        }
+    }
+
+    fn get_block_number(&mut self, request: GetBlockNumberRequest) -> GetBlockNumberResponse {
+        // Entry points are not run as part of a block.
+        GetBlockNumberResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: 0.into(),
+            block_number: 0.into(),
+        }
+    }
+
+    fn get_block_timestamp(
+        &mut self,
+        request: GetBlockTimestampRequest,
+    ) -> GetBlockTimestampResponse {
+        GetBlockTimestampResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: 0.into(),
+            block_timestamp: 0.into(),
+        }
+    }
+
+    fn get_tx_info(&mut self, request: GetTxInfoRequest) -> GetTxInfoResponse {
+        // Entry points are not run as part of a transaction.
+        GetTxInfoResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: unsupported_syscall(),
+            tx_info: 0.into(),
+        }
+    }
+
+    fn replace_class(&mut self, request: ReplaceClassRequest) -> ReplaceClassResponse {
+        // There are no declared classes to replace the class of the contract with.
+        ReplaceClassResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: unsupported_syscall(),
+        }
+    }
+
+    fn keccak(&mut self, request: KeccakRequest) -> KeccakResponse {
+        // The input array can not be read through the request.
+        KeccakResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: unsupported_syscall(),
+            result_low: 0.into(),
+            result_high: 0.into(),
+        }
+    }
+
+    fn secp256k1_new(&mut self, request: Secp256k1NewRequest) -> Secp256k1NewResponse {
+        // Points are not supported, as there is no memory to keep them in.
+        Secp256k1NewResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: unsupported_syscall(),
+            is_on_curve: 0.into(),
+            point: 0.into(),
+        }
+    }
+
+    fn secp256k1_add(&mut self, request: Secp256k1AddRequest) -> Secp256k1AddResponse {
+        Secp256k1AddResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: unsupported_syscall(),
+            point: 0.into(),
+        }
+    }
+
+    fn secp256k1_mul(&mut self, request: Secp256k1MulRequest) -> Secp256k1MulResponse {
+        Secp256k1MulResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: unsupported_syscall(),
+            point: 0.into(),
+        }
+    }
+
+    fn secp256k1_get_xy(&mut self, request: Secp256k1GetXyRequest) -> Secp256k1GetXyResponse {
+        Secp256k1GetXyResponse {
+            gas_counter: request.gas_counter,
+            revert_reason: unsupported_syscall(),
+            x_low: 0.into(),
+            x_high: 0.into(),
+            y_low: 0.into(),
+            y_high: 0.into(),
+        }
+    }
+}
+impl SyscallHintHandler for EntryPointSyscallHandler {
+    fn execute_syscall(
+        &mut self,
+        vm: &mut VirtualMachine,
+        system_ptr: Relocatable,
+    ) -> Result<(), VirtualMachineError> {
+        let mut memory = VmSegmentMemory { vm, base: system_ptr };
+        execute_syscall(self, &mut memory, 0).map_err(|error| {
+            VirtualMachineError::CustomHint(format!("Failed executing a syscall: {error}"))
+        })?;
+        if let Some(request) = self.pending_event.take() {
+            let keys = read_array(vm, &request.keys_start, &request.keys_end)?;
+            let data = read_array(vm, &request.data_start, &request.data_end)?;
+            self.state.emit_event(EmittedEvent { keys, data });
+        }
+        if let Some(request) = self.pending_message.take() {
+            let payload = read_array(vm, &request.payload_start, &request.payload_end)?;
+            self.state
+                .send_message_to_l1(L2ToL1Message { to_address: request.to_address, payload });
+        }
+        Ok(())
+    }
+}
+
+/// Reads the felts of the array between the pointers `start` and `end`, as passed to a syscall.
+fn read_array(
+    vm: &VirtualMachine,
+    start: &BigInt,
+    end: &BigInt,
+) -> Result<Vec<BigInt>, VirtualMachineError> {
+    let (Some(start), Some(end)) = (decode_pointer(start), decode_pointer(end)) else {
+        return Err(VirtualMachineError::CustomHint("Invalid syscall array pointers.".into()));
+    };
+    if start.segment_index != end.segment_index || start.offset > end.offset {
+        return Err(VirtualMachineError::CustomHint("Invalid syscall array bounds.".into()));
+    }
+    (start.offset..end.offset)
+        .map(|offset| {
+            let address = Relocatable { segment_index: start.segment_index, offset };
+            vm.get_integer(&address).map(|value| value.into_owned())
+        })
+        .collect()
+}
+
+/// A view of the vm memory starting at `base`, with addresses relative to it.
+/// Pointers are viewed as integers out of the range of felts, see [encode_pointer].
+struct VmSegmentMemory<'a> {
+    vm: &'a mut VirtualMachine,
+    base: Relocatable,
+}
+impl SyscallMemory for VmSegmentMemory<'_> {
+    fn get(&self, address: usize) -> Option<BigInt> {
+        let address = self.base + address as i32;
+        match self.vm.get_integer(&address) {
+            Ok(value) => Some(value.into_owned()),
+            Err(_) => self.vm.get_relocatable(&address).ok().map(|ptr| encode_pointer(&ptr)),
+        }
+    }
+
+    fn set(&mut self, address: usize, value: BigInt) {
+        let address = self.base + address as i32;
+        let result = match decode_pointer(&value) {
+            Some(ptr) => self.vm.insert_value(&address, ptr),
+            None => self.vm.insert_value(&address, value),
+        };
+        result.expect("Syscall responses are written to unused cells.");
+    }
+}
+
+/// Returns the integer viewing `ptr` in a [VmSegmentMemory] - `2**256` plus the segment index
+/// shifted by 64 bits plus the offset, so that syscall handlers can pass pointers back unchanged.
+fn encode_pointer(ptr: &Relocatable) -> BigInt {
+    (BigInt::from(1) << 256) + (BigInt::from(ptr.segment_index) << 64) + ptr.offset
+}
+
+/// Returns the pointer viewed as `value` by [encode_pointer], if it views one.
+fn decode_pointer(value: &BigInt) -> Option<Relocatable> {
+    let encoded = value - (BigInt::from(1) << 256);
+    if encoded < BigInt::from(0) || encoded >= BigInt::from(1) << 128 {
+        return None;
+    }
+    let segment_index = isize::try_from(&encoded >> 64).ok()?;
+    let offset = usize::try_from(encoded & BigInt::from(u64::MAX)).ok()?;
+    Some(Relocatable { segment_index, offset })
+}
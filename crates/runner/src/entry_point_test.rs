@@ -0,0 +1,148 @@
+use cairo_rs::types::relocatable::Relocatable;
+use num_bigint::BigInt;
+use sierra_to_casm::syscall_records::{
+    CallContractRequest, DeployRequest, GetTxInfoRequest, KeccakRequest, LibraryCallRequest,
+    ReplaceClassRequest, Secp256k1AddRequest, Secp256k1GetXyRequest, Secp256k1MulRequest,
+    Secp256k1NewRequest, SyscallHandler,
+};
+use utils::short_string;
+
+use super::{
+    decode_pointer, encode_pointer, EntryPointSyscallHandler, OUT_OF_GAS, UNSUPPORTED_SYSCALL,
+};
+
+fn handler() -> EntryPointSyscallHandler {
+    EntryPointSyscallHandler::default()
+}
+
+#[test]
+fn pointer_encoding() {
+    let ptr = Relocatable { segment_index: 3, offset: 17 };
+    assert_eq!(decode_pointer(&encode_pointer(&ptr)), Some(ptr));
+    // Felts are never viewed as pointers.
+    assert_eq!(decode_pointer(&BigInt::from(17)), None);
+    assert_eq!(decode_pointer(&((BigInt::from(1) << 251) + 17 * (BigInt::from(1) << 192))), None);
+}
+
+#[test]
+fn call_contract_unsupported() {
+    let response = handler().call_contract(CallContractRequest {
+        gas_counter: BigInt::from(1000),
+        contract_address: BigInt::from(1),
+        calldata_start: BigInt::from(10),
+        calldata_end: BigInt::from(12),
+    });
+    assert_eq!(response.gas_counter, BigInt::from(1000));
+    assert_eq!(short_string::to_str(&response.revert_reason).as_deref(), Some(UNSUPPORTED_SYSCALL));
+    assert_eq!(response.retdata_start, BigInt::from(12));
+    assert_eq!(response.retdata_end, BigInt::from(12));
+}
+
+#[test]
+fn call_contract_out_of_gas() {
+    // The call data charge underflowed the gas counter, wrapping it around the field.
+    let prime = (BigInt::from(1) << 251) + 17 * (BigInt::from(1) << 192) + 1;
+    let response = handler().call_contract(CallContractRequest {
+        gas_counter: prime - 10,
+        contract_address: BigInt::from(1),
+        calldata_start: BigInt::from(10),
+        calldata_end: BigInt::from(12),
+    });
+    assert_eq!(response.gas_counter, BigInt::from(0));
+    assert_eq!(short_string::to_str(&response.revert_reason).as_deref(), Some(OUT_OF_GAS));
+    assert_eq!(response.retdata_start, BigInt::from(12));
+    assert_eq!(response.retdata_end, BigInt::from(12));
+}
+
+#[test]
+fn deploy_unsupported() {
+    let response = handler().deploy(DeployRequest {
+        gas_counter: BigInt::from(1000),
+        class_hash: BigInt::from(1),
+        contract_address_salt: BigInt::from(2),
+        calldata_start: BigInt::from(10),
+        calldata_end: BigInt::from(12),
+    });
+    assert_eq!(response.gas_counter, BigInt::from(1000));
+    assert_eq!(short_string::to_str(&response.revert_reason).as_deref(), Some(UNSUPPORTED_SYSCALL));
+    assert_eq!(response.retdata_start, BigInt::from(12));
+    assert_eq!(response.retdata_end, BigInt::from(12));
+}
+
+#[test]
+fn library_call_unsupported() {
+    let response = handler().library_call(LibraryCallRequest {
+        gas_counter: BigInt::from(1000),
+        class_hash: BigInt::from(1),
+        calldata_start: BigInt::from(10),
+        calldata_end: BigInt::from(12),
+    });
+    assert_eq!(response.gas_counter, BigInt::from(1000));
+    assert_eq!(short_string::to_str(&response.revert_reason).as_deref(), Some(UNSUPPORTED_SYSCALL));
+    assert_eq!(response.retdata_start, BigInt::from(12));
+    assert_eq!(response.retdata_end, BigInt::from(12));
+}
+
+#[test]
+fn replace_class_unsupported() {
+    let response = handler().replace_class(ReplaceClassRequest {
+        gas_counter: BigInt::from(1000),
+        class_hash: BigInt::from(1),
+    });
+    assert_eq!(response.gas_counter, BigInt::from(1000));
+    assert_eq!(short_string::to_str(&response.revert_reason).as_deref(), Some(UNSUPPORTED_SYSCALL));
+}
+
+#[test]
+fn get_tx_info_unsupported() {
+    let response = handler().get_tx_info(GetTxInfoRequest { gas_counter: BigInt::from(1000) });
+    assert_eq!(response.gas_counter, BigInt::from(1000));
+    assert_eq!(short_string::to_str(&response.revert_reason).as_deref(), Some(UNSUPPORTED_SYSCALL));
+}
+
+#[test]
+fn keccak_unsupported() {
+    let response = handler().keccak(KeccakRequest {
+        gas_counter: BigInt::from(1000),
+        input_start: BigInt::from(10),
+        input_end: BigInt::from(12),
+    });
+    assert_eq!(response.gas_counter, BigInt::from(1000));
+    assert_eq!(short_string::to_str(&response.revert_reason).as_deref(), Some(UNSUPPORTED_SYSCALL));
+}
+
+#[test]
+fn secp256k1_unsupported() {
+    let gas_counter = BigInt::from(1000);
+    let [x_low, x_high, y_low, y_high] = [1, 2, 3, 4].map(BigInt::from);
+    let response = handler().secp256k1_new(Secp256k1NewRequest {
+        gas_counter: gas_counter.clone(),
+        x_low,
+        x_high,
+        y_low,
+        y_high,
+    });
+    assert_eq!(response.gas_counter, gas_counter);
+    assert_eq!(short_string::to_str(&response.revert_reason).as_deref(), Some(UNSUPPORTED_SYSCALL));
+    let response = handler().secp256k1_add(Secp256k1AddRequest {
+        gas_counter: gas_counter.clone(),
+        p0: BigInt::from(10),
+        p1: BigInt::from(12),
+    });
+    assert_eq!(response.gas_counter, gas_counter);
+    assert_eq!(short_string::to_str(&response.revert_reason).as_deref(), Some(UNSUPPORTED_SYSCALL));
+    let response = handler().secp256k1_mul(Secp256k1MulRequest {
+        gas_counter: gas_counter.clone(),
+        point: BigInt::from(10),
+        scalar_low: BigInt::from(5),
+        scalar_high: BigInt::from(0),
+    });
+    assert_eq!(response.gas_counter, gas_counter);
+    assert_eq!(short_string::to_str(&response.revert_reason).as_deref(), Some(UNSUPPORTED_SYSCALL));
+    let response = handler().secp256k1_get_xy(Secp256k1GetXyRequest {
+        gas_counter: gas_counter.clone(),
+        point: BigInt::from(10),
+    });
+    assert_eq!(response.gas_counter, gas_counter);
+    assert_eq!(short_string::to_str(&response.revert_reason).as_deref(), Some(UNSUPPORTED_SYSCALL));
+}
@@ -1,23 +1,34 @@
 use std::collections::HashMap;
+use std::io::Write;
 
 use cairo_rs::vm::errors::vm_errors::VirtualMachineError;
+use casm::cost::CostWeights;
+use casm::hints::structured::HintParseError;
 use casm::instructions::Instruction;
+use casm::operand::{CellRef, Register};
+use casm::run::{RunFailure, TraceEntry};
 use casm::{casm, casm_extend};
 use itertools::chain;
-use num_bigint::BigInt;
+use num_bigint::{BigInt, BigUint};
 use sierra::extensions::builtin_cost::CostTokenType;
 use sierra::extensions::core::{CoreLibFunc, CoreType};
 use sierra::extensions::ConcreteType;
-use sierra::program::{Function, GenericArg};
+use sierra::ids::FunctionId;
+use sierra::program::{Function, GenericArg, StatementLocation};
 use sierra::program_registry::{ProgramRegistry, ProgramRegistryError};
-use sierra_ap_change::{calc_ap_changes, ApChangeError};
-use sierra_gas::calc_gas_info;
+use sierra_ap_change::{calc_ap_changes_with_stack_layout, ApChangeError, StackLayout};
+use sierra_gas::calc_gas_info_with_stack_layout;
 use sierra_gas::gas_info::GasInfo;
-use sierra_to_casm::compiler::{CairoProgram, CompilationError};
+use sierra_to_casm::compiler::{CairoProgram, CairoProgramDebugInfo, CompilationError};
 use sierra_to_casm::metadata::Metadata;
 use thiserror::Error;
 use utils::extract_matches;
 
+pub mod entry_point;
+pub mod revert_reason;
+pub mod starknet_state;
+pub mod test_env;
+
 #[derive(Debug, Error)]
 pub enum RunnerError {
     #[error("Not enough gas to call function.")]
@@ -30,6 +41,16 @@ pub enum RunnerError {
     MissingFunction { suffix: String },
     #[error("Function expects arguments of size {expected} and received {actual} instead.")]
     ArgumentsSizeMismatch { expected: usize, actual: usize },
+    #[error("Entry point with selector {selector:#x} to run not found.")]
+    MissingEntryPoint { selector: BigUint },
+    #[error("No contract is deployed at address {address}.")]
+    MissingContract { address: BigInt },
+    #[error("Builtin `{0}` is not supported by the runner.")]
+    UnsupportedBuiltin(String),
+    #[error("Range checked value {value} is not smaller than 2**128.")]
+    RangeCheckViolation { value: BigInt },
+    #[error(transparent)]
+    HintParseError(#[from] HintParseError),
     #[error(transparent)]
     ProgramRegistryError(#[from] Box<ProgramRegistryError>),
     #[error(transparent)]
@@ -38,6 +59,21 @@ pub enum RunnerError {
     ApChangeError(#[from] ApChangeError),
     #[error(transparent)]
     VirtualMachineError(#[from] Box<VirtualMachineError>),
+    #[error(
+        "Run failed at code offset {code_offset} of function `{function}`{}: {error}",
+        display_location(.location)
+    )]
+    RunFailure {
+        code_offset: usize,
+        function: FunctionId,
+        location: Option<StatementLocation>,
+        error: Box<VirtualMachineError>,
+    },
+}
+
+/// Returns the description of the source location of a failure, if it has one.
+fn display_location(location: &Option<StatementLocation>) -> String {
+    location.map(|location| format!(" ({location})")).unwrap_or_default()
 }
 
 /// The full result of a run.
@@ -45,6 +81,12 @@ pub struct RunResult {
     pub gas_counter: Option<BigInt>,
     pub memory: Vec<Option<BigInt>>,
     pub value: RunResultValue,
+    /// The steps of the run executed in the code of the program, with the pc relative to the start
+    /// of the code.
+    pub trace: Vec<TraceEntry>,
+    /// The number of steps executed in the code of each function that ran. Steps executed in the
+    /// runtime routines are not attributed to any function.
+    pub function_steps: HashMap<FunctionId, usize>,
 }
 
 /// The ran function return value.
@@ -72,28 +114,98 @@ impl SierraCasmRunner {
         sierra_program: sierra::program::Program,
         calc_gas: bool,
     ) -> Result<Self, RunnerError> {
-        let metadata = create_metadata(&sierra_program, calc_gas)?;
+        Self::new_with_stack_layout(sierra_program, calc_gas, StackLayout::default())
+    }
+
+    /// Creates a runner for a program compiled with values stored on the stack according to
+    /// `stack_layout`.
+    pub fn new_with_stack_layout(
+        sierra_program: sierra::program::Program,
+        calc_gas: bool,
+        stack_layout: StackLayout,
+    ) -> Result<Self, RunnerError> {
+        let metadata = create_metadata(&sierra_program, calc_gas, stack_layout)?;
         let sierra_program_registry =
             ProgramRegistry::<CoreType, CoreLibFunc>::new(&sierra_program)?;
-        let casm_program = sierra_to_casm::compiler::compile(&sierra_program, &metadata, calc_gas)?;
+        let casm_program =
+            sierra_to_casm::compiler::compile(&sierra_program, &metadata, calc_gas, false)?;
         Ok(Self { sierra_program, metadata, sierra_program_registry, casm_program })
     }
 
+    /// Returns the debug information of the casm program run by the runner.
+    pub fn debug_info(&self) -> &CairoProgramDebugInfo {
+        &self.casm_program.debug_info
+    }
+
     /// Runs the vm starting from a function. Function may have implicits, but no other ref params.
     /// The cost of the function is deducted from available_gas before the execution begins.
+    /// Debug prints are written to the standard output.
+    /// The run fails if any of the values written to the range check segment is out of range.
     pub fn run_function(
+        self,
+        name_suffix: &str,
+        args: &[BigInt],
+        available_gas: &Option<usize>,
+    ) -> Result<RunResult, RunnerError> {
+        self.run_function_with_debug_output(
+            name_suffix,
+            args,
+            available_gas,
+            &mut std::io::stdout(),
+        )
+    }
+
+    /// Runs the vm starting from a function, similarly to `run_function`, writing the output of
+    /// debug prints into `debug_output`.
+    pub fn run_function_with_debug_output(
         mut self,
         name_suffix: &str,
         args: &[BigInt],
         available_gas: &Option<usize>,
+        debug_output: &mut dyn Write,
     ) -> Result<RunResult, RunnerError> {
         // Extracting instructions before since `self` becomes borrowed later.
         let instructions = self.casm_program.instructions;
         self.casm_program.instructions = vec![];
         let func = self.find_function(name_suffix)?;
         let entry_code = self.create_entry_code(func, args, available_gas)?;
-        let (cells, ap) = casm::run::run_function(chain!(entry_code, instructions).collect())?;
+        let entry_code_size: usize =
+            entry_code.iter().map(|instruction| instruction.body.op_size()).sum();
+        let output = casm::run::run_function_with_trace(
+            chain!(entry_code, instructions).collect(),
+            debug_output,
+        )
+        .map_err(|failure| self.run_failure_error(failure, entry_code_size))?;
+        let (cells, ap) = (output.memory, output.ap);
+        let trace: Vec<TraceEntry> = output
+            .trace
+            .into_iter()
+            .filter_map(|entry| {
+                Some(TraceEntry { pc: entry.pc.checked_sub(entry_code_size)?, ..entry })
+            })
+            .collect();
+        let function_steps = self.function_steps(&trace);
+        // The entry code copies the start of the range check segment right after the returned
+        // values.
+        let range_check_start = uses_range_check(func).then(|| cells[ap - 1].clone());
+        let ap = if range_check_start.is_some() { ap - 1 } else { ap };
         let mut results_data = self.get_results_data(func, &cells, ap)?;
+        if let Some(range_check_start) = range_check_start {
+            // The range check builtin of the vm is not used, so the range checked values are
+            // validated after the run.
+            let (_, values) = results_data
+                .iter()
+                .find(|(ty, _)| *ty == "RangeCheck".into())
+                .expect("The range check builtin must be returned.");
+            let start = usize::try_from(range_check_start.unwrap()).unwrap();
+            let end = usize::try_from(&values[0]).unwrap();
+            let bound = BigInt::from(1) << 128;
+            if let Some(value) =
+                cells[start..end].iter().map(|cell| cell.clone().unwrap()).find(|v| *v >= bound)
+            {
+                return Err(RunnerError::RangeCheckViolation { value });
+            }
+        }
         // Handling implicits.
         let mut gas_counter = None;
         results_data.retain_mut(|(ty, values)| {
@@ -114,7 +226,43 @@ impl SierraCasmRunner {
             let [(ty, values)] = <[_; 1]>::try_from(results_data).ok().unwrap();
             self.handle_main_return_value(ty, values, &cells)?
         };
-        Ok(RunResult { gas_counter, memory: cells, value })
+        Ok(RunResult { gas_counter, memory: cells, value, trace, function_steps })
+    }
+
+    /// Returns the error of a failed run, locating the failure in the program when it failed in
+    /// the code of a function.
+    fn run_failure_error(&self, failure: RunFailure, entry_code_size: usize) -> RunnerError {
+        let debug_info = &self.casm_program.debug_info;
+        let Some(code_offset) = failure.pc.and_then(|pc| pc.checked_sub(entry_code_size)) else {
+            return failure.error.into();
+        };
+        if code_offset >= debug_info.routines_code_offset {
+            return failure.error.into();
+        }
+        let Some(function) = debug_info.function_at(code_offset) else {
+            return failure.error.into();
+        };
+        RunnerError::RunFailure {
+            code_offset,
+            function: function.id.clone(),
+            location: debug_info.location_at(code_offset),
+            error: failure.error,
+        }
+    }
+
+    /// Returns the number of steps of `trace` executed in the code of each function.
+    fn function_steps(&self, trace: &[TraceEntry]) -> HashMap<FunctionId, usize> {
+        let debug_info = &self.casm_program.debug_info;
+        let mut function_steps = HashMap::new();
+        for entry in trace {
+            if entry.pc >= debug_info.routines_code_offset {
+                continue;
+            }
+            if let Some(function) = debug_info.function_at(entry.pc) {
+                *function_steps.entry(function.id.clone()).or_insert(0) += 1;
+            }
+        }
+        function_steps
     }
 
     /// Handling the main return value to create a `RunResultValue`.
@@ -165,12 +313,19 @@ impl SierraCasmRunner {
         cells: &[Option<BigInt>],
         mut ap: usize,
     ) -> Result<Vec<(sierra::ids::ConcreteTypeId, Vec<BigInt>)>, RunnerError> {
+        let stack_layout = &self.metadata.ap_change_info.stack_layout;
         let mut results_data = vec![];
         for ty in func.signature.ret_types.iter().rev() {
             let size = self.sierra_program_registry.get_type(ty)?.info().size as usize;
+            // Values returned by pointer are read through the last cell of their stack slot.
+            let start = if stack_layout.is_by_pointer(size) {
+                usize::try_from(cells[ap - 1].as_ref().unwrap()).unwrap()
+            } else {
+                ap - size
+            };
             let values: Vec<BigInt> =
-                cells[(ap - size)..ap].iter().cloned().map(|cell| cell.unwrap()).collect();
-            ap -= size;
+                cells[start..(start + size)].iter().cloned().map(|cell| cell.unwrap()).collect();
+            ap -= stack_layout.slot_size(size);
             results_data.push((ty.clone(), values));
         }
         Ok(results_data)
@@ -198,13 +353,20 @@ impl SierraCasmRunner {
         let mut arg_iter = args.iter();
         let mut expected_arguments_size = 0;
         let mut ctx = casm! {};
+        // The number of cells written by the entry code, which starts at its frame.
+        let mut stack_size = 0;
+        let mut range_check_start_offset = None;
         for ty in func.signature.param_types.iter() {
             if ty == &"RangeCheck".into() || ty == &"Bitwise".into() || ty == &"Pedersen".into() {
+                if ty == &"RangeCheck".into() {
+                    range_check_start_offset.get_or_insert(stack_size);
+                }
                 // TODO(orizi): Use the vm's range check segment.
                 casm_extend! {ctx,
                     %{ memory[ap + 0] = segments.add() %}
                     ap += 1;
                 }
+                stack_size += 1;
             } else if ty == &"GasBuiltin".into() {
                 if let Some(available_gas) = available_gas {
                     // TODO(lior): Handle the other token types.
@@ -216,6 +378,7 @@ impl SierraCasmRunner {
                         casm_extend! {ctx,
                             [ap + 0] = initial_gas, ap++;
                         }
+                        stack_size += 1;
                     } else {
                         return Err(RunnerError::NotEnoughGasToCall);
                     }
@@ -225,11 +388,29 @@ impl SierraCasmRunner {
             } else {
                 let arg_size = self.sierra_program_registry.get_type(ty)?.info().size;
                 expected_arguments_size += arg_size as usize;
+                if self.metadata.ap_change_info.stack_layout.is_by_pointer(arg_size as usize) {
+                    // Values passed by pointer are copied into a new segment, pointed to by the
+                    // last cell of their stack slot.
+                    let pointer = CellRef { register: Register::AP, offset: arg_size };
+                    casm_extend! {ctx, %{ memory pointer = segments.add() %} }
+                    for (i, value) in (0..arg_size).zip(arg_iter.by_ref()) {
+                        casm_extend! {ctx,
+                            [ap + i] = (value.clone());
+                            [ap + i] = [[&pointer] + i];
+                        }
+                    }
+                    casm_extend! {ctx,
+                        ap += ((arg_size + 1) as i128);
+                    }
+                    stack_size += arg_size + 1;
+                    continue;
+                }
                 for _ in 0..arg_size {
                     if let Some(value) = arg_iter.next() {
                         casm_extend! {ctx,
                             [ap + 0] = (value.clone()), ap++;
                         }
+                        stack_size += 1;
                     }
                 }
             }
@@ -241,11 +422,20 @@ impl SierraCasmRunner {
             });
         }
         let before_final_call = ctx.current_code_offset;
-        let final_call_size = 3;
+        let final_call_size = if range_check_start_offset.is_some() { 4 } else { 3 };
         let offset = final_call_size
             + self.casm_program.debug_info.sierra_statement_info[func.entry_point.0].code_offset;
         casm_extend! {ctx,
             call rel offset;
+        }
+        if let Some(range_check_start_offset) = range_check_start_offset {
+            // Copying the start of the range check segment, for validating its values after the
+            // run.
+            casm_extend! {ctx,
+                [ap + 0] = [fp + range_check_start_offset], ap++;
+            }
+        }
+        casm_extend! {ctx,
             ret;
         }
         assert_eq!(before_final_call + final_call_size, ctx.current_code_offset);
@@ -253,16 +443,31 @@ impl SierraCasmRunner {
     }
 }
 
+/// Returns whether `func` uses the range check builtin.
+fn uses_range_check(func: &Function) -> bool {
+    func.signature.param_types.contains(&"RangeCheck".into())
+}
+
 /// Creates the metadata required for a Sierra program lowering to casm.
 fn create_metadata(
     sierra_program: &sierra::program::Program,
     calc_gas: bool,
+    stack_layout: StackLayout,
 ) -> Result<Metadata, RunnerError> {
     let gas_info = if calc_gas {
-        calc_gas_info(sierra_program).map_err(|_| RunnerError::FailedGasCalculation)?
+        calc_gas_info_with_stack_layout(sierra_program, stack_layout)
+            .map_err(|_| RunnerError::FailedGasCalculation)?
     } else {
-        GasInfo { variable_values: HashMap::new(), function_costs: HashMap::new() }
+        GasInfo {
+            variable_values: HashMap::new(),
+            function_costs: HashMap::new(),
+            store_temp_costs: HashMap::new(),
+        }
+    };
+    let metadata = Metadata {
+        ap_change_info: calc_ap_changes_with_stack_layout(sierra_program, stack_layout)?,
+        gas_info,
+        cost_weights: CostWeights::default(),
     };
-    let metadata = Metadata { ap_change_info: calc_ap_changes(sierra_program)?, gas_info };
     Ok(metadata)
 }
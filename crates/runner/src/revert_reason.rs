@@ -0,0 +1,76 @@
+//! Decoding of the felts a run reverted with into human-readable errors.
+
+use std::fmt::{Display, Formatter};
+
+use num_bigint::BigInt;
+use starknet::contract_class::ErrorCode;
+use utils::short_string;
+
+use crate::RunResultValue;
+
+#[cfg(test)]
+#[path = "revert_reason_test.rs"]
+mod test;
+
+/// The panic data values used by the compiler itself, and their meaning.
+const KNOWN_PANIC_VALUES: [(&str, &str); 1] = [("OOG", "Out of gas")];
+
+/// The reason a run reverted, as the raw felts it reverted with and their best-effort decoding.
+#[derive(Debug, Eq, PartialEq)]
+pub struct RevertReason {
+    pub raw: Vec<BigInt>,
+    /// The decoding of each of the raw felts: its symbolic name if the contract provides one, the
+    /// meaning of panics of the compiler, its short string if printable, or its decimal value.
+    pub decoded: Vec<String>,
+}
+impl RevertReason {
+    /// Decodes `raw`, using `error_codes` for the felts that have symbolic names.
+    pub fn new(raw: Vec<BigInt>, error_codes: &[ErrorCode]) -> Self {
+        let decoded = raw.iter().map(|value| decode_felt(value, error_codes)).collect();
+        Self { raw, decoded }
+    }
+}
+impl Display for RevertReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.decoded.join(", "))
+    }
+}
+
+/// Decodes a single felt of a revert reason.
+fn decode_felt(value: &BigInt, error_codes: &[ErrorCode]) -> String {
+    if let Some(error_code) =
+        error_codes.iter().find(|error_code| BigInt::from(error_code.code.clone()) == *value)
+    {
+        return error_code.name.clone();
+    }
+    match short_string::to_str(value) {
+        Some(s) => KNOWN_PANIC_VALUES
+            .iter()
+            .find(|(panic_value, _)| *panic_value == s)
+            .map_or(s, |(_, meaning)| meaning.to_string()),
+        None => value.to_string(),
+    }
+}
+
+impl RunResultValue {
+    /// Returns the reason of the revert if the run panicked.
+    pub fn revert_reason(&self, error_codes: &[ErrorCode]) -> Option<RevertReason> {
+        match self {
+            RunResultValue::Success(_) => None,
+            RunResultValue::Panic(data) => Some(RevertReason::new(data.clone(), error_codes)),
+        }
+    }
+}
+
+/// Asserts that a run reverted, and that one of the decoded felts of its revert reason, or the
+/// whole decoded reason, is `expected`.
+pub fn assert_reverted_with(revert_reason: Option<RevertReason>, expected: &str) {
+    let Some(revert_reason) = revert_reason else {
+        panic!("Expected the run to revert with `{expected}`, but it succeeded.");
+    };
+    assert!(
+        revert_reason.decoded.iter().any(|decoded| decoded == expected)
+            || revert_reason.to_string() == expected,
+        "Expected the run to revert with `{expected}`, but it reverted with `{revert_reason}`."
+    );
+}
@@ -0,0 +1,46 @@
+use num_bigint::{BigInt, BigUint};
+use starknet::contract_class::ErrorCode;
+use utils::short_string;
+
+use crate::revert_reason::{assert_reverted_with, RevertReason};
+use crate::RunResultValue;
+
+fn felt(s: &str) -> BigInt {
+    short_string::from_str(s).unwrap()
+}
+
+#[test]
+fn raw_decoding() {
+    let reason = RevertReason::new(vec![BigInt::from(0), BigInt::from(17)], &[]);
+    assert_eq!(reason.decoded, vec!["0", "17"]);
+    assert_eq!(reason.to_string(), "0, 17");
+}
+
+#[test]
+fn short_string_decoding() {
+    let reason =
+        RunResultValue::Panic(vec![felt("u128_from OF"), felt("OOG")]).revert_reason(&[]).unwrap();
+    assert_eq!(reason.raw, vec![felt("u128_from OF"), felt("OOG")]);
+    assert_eq!(reason.decoded, vec!["u128_from OF", "Out of gas"]);
+    assert_reverted_with(Some(reason), "u128_from OF");
+    assert_eq!(RunResultValue::Success(vec![felt("OOG")]).revert_reason(&[]), None);
+}
+
+#[test]
+fn table_decoding() {
+    let error_codes =
+        [ErrorCode { code: BigUint::from(1001u32), name: "ERC20: insufficient balance".into() }];
+    let value = RunResultValue::Panic(vec![BigInt::from(1001), felt("transfer")]);
+    assert_reverted_with(value.revert_reason(&error_codes), "ERC20: insufficient balance");
+    assert_reverted_with(
+        value.revert_reason(&error_codes),
+        "ERC20: insufficient balance, transfer",
+    );
+    assert_eq!(value.revert_reason(&[]).unwrap().decoded, vec!["1001", "transfer"]);
+}
+
+#[test]
+#[should_panic(expected = "but it succeeded")]
+fn assert_reverted_with_success() {
+    assert_reverted_with(RunResultValue::Success(vec![]).revert_reason(&[]), "OOG");
+}
@@ -0,0 +1,131 @@
+//! The StarkNet state observed and changed by the runs of contract entry points.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use starknet::casm_contract_class::CasmContractClass;
+
+use crate::entry_point::EmittedEvent;
+
+#[cfg(test)]
+#[path = "starknet_state_test.rs"]
+mod test;
+
+/// A message sent from L2 to L1 by a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L2ToL1Message {
+    pub to_address: BigInt,
+    pub payload: Vec<BigInt>,
+}
+
+/// The StarkNet state - the storage, deployed classes and nonces of the contracts, and the events
+/// and messages emitted so far.
+///
+/// Every part of the state is shared behind an `Rc`, and is copied only when changed while shared.
+/// So cloning the state is O(1), and a change after a clone copies only the changed part - for
+/// storage, the storage of the changed contract.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StarknetState {
+    /// The storage of each contract, by the contract address.
+    storage: Rc<HashMap<BigInt, Rc<HashMap<BigInt, BigInt>>>>,
+    /// The class of each deployed contract, by the contract address.
+    classes: Rc<HashMap<BigInt, Rc<CasmContractClass>>>,
+    /// The nonce of each contract, by the contract address.
+    nonces: Rc<HashMap<BigInt, BigInt>>,
+    /// The events emitted so far, in the order of emission.
+    events: Rc<Vec<EmittedEvent>>,
+    /// The messages sent to L1 so far, in the order of sending.
+    messages: Rc<Vec<L2ToL1Message>>,
+}
+impl StarknetState {
+    /// Returns the value stored at `key` in the storage of the contract at `contract_address`.
+    pub fn storage_read(&self, contract_address: &BigInt, key: &BigInt) -> BigInt {
+        self.storage
+            .get(contract_address)
+            .and_then(|storage| storage.get(key))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Stores `value` at `key` in the storage of the contract at `contract_address`.
+    pub fn storage_write(&mut self, contract_address: &BigInt, key: BigInt, value: BigInt) {
+        let storage = Rc::make_mut(&mut self.storage).entry(contract_address.clone()).or_default();
+        Rc::make_mut(storage).insert(key, value);
+    }
+
+    /// Returns the storage of the contract at `contract_address`.
+    pub fn contract_storage(&self, contract_address: &BigInt) -> Option<&HashMap<BigInt, BigInt>> {
+        self.storage.get(contract_address).map(|storage| storage.as_ref())
+    }
+
+    /// Replaces the storage of the contract at `contract_address` with `storage`.
+    pub fn set_contract_storage(
+        &mut self,
+        contract_address: &BigInt,
+        storage: HashMap<BigInt, BigInt>,
+    ) {
+        Rc::make_mut(&mut self.storage).insert(contract_address.clone(), Rc::new(storage));
+    }
+
+    /// Removes the storage of the contract at `contract_address` from the state, and returns it.
+    /// The storage is copied only if it is shared with a clone of the state.
+    pub fn take_contract_storage(&mut self, contract_address: &BigInt) -> HashMap<BigInt, BigInt> {
+        Rc::make_mut(&mut self.storage)
+            .remove(contract_address)
+            .map(|storage| Rc::try_unwrap(storage).unwrap_or_else(|storage| (*storage).clone()))
+            .unwrap_or_default()
+    }
+
+    /// Returns the class of the contract at `contract_address`, if one was deployed there.
+    pub fn class_of(&self, contract_address: &BigInt) -> Option<&Rc<CasmContractClass>> {
+        self.classes.get(contract_address)
+    }
+
+    /// Records the deployment of a contract of class `class` at `contract_address`.
+    pub fn deploy(&mut self, contract_address: BigInt, class: Rc<CasmContractClass>) {
+        Rc::make_mut(&mut self.classes).insert(contract_address, class);
+    }
+
+    /// Returns the number of deployed contracts.
+    pub fn n_deployed_contracts(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// Returns the nonce of the contract at `contract_address`.
+    pub fn nonce(&self, contract_address: &BigInt) -> BigInt {
+        self.nonces.get(contract_address).cloned().unwrap_or_default()
+    }
+
+    /// Increments the nonce of the contract at `contract_address`.
+    pub fn increment_nonce(&mut self, contract_address: &BigInt) {
+        *Rc::make_mut(&mut self.nonces).entry(contract_address.clone()).or_default() += 1;
+    }
+
+    /// Returns the events emitted so far, in the order of emission.
+    pub fn events(&self) -> &[EmittedEvent] {
+        &self.events
+    }
+
+    /// Records an emitted event.
+    pub fn emit_event(&mut self, event: EmittedEvent) {
+        Rc::make_mut(&mut self.events).push(event);
+    }
+
+    /// Removes the events emitted so far from the state, and returns them.
+    /// The events are copied only if they are shared with a clone of the state.
+    pub fn take_events(&mut self) -> Vec<EmittedEvent> {
+        let events = std::mem::take(&mut self.events);
+        Rc::try_unwrap(events).unwrap_or_else(|events| (*events).clone())
+    }
+
+    /// Returns the messages sent to L1 so far, in the order of sending.
+    pub fn messages(&self) -> &[L2ToL1Message] {
+        &self.messages
+    }
+
+    /// Records a message sent to L1.
+    pub fn send_message_to_l1(&mut self, message: L2ToL1Message) {
+        Rc::make_mut(&mut self.messages).push(message);
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+
+use super::{L2ToL1Message, StarknetState};
+use crate::entry_point::EmittedEvent;
+
+#[test]
+fn storage_read_write() {
+    let mut state = StarknetState::default();
+    let (contract, other_contract) = (BigInt::from(1), BigInt::from(2));
+    state.storage_write(&contract, 10.into(), 100.into());
+    assert_eq!(state.storage_read(&contract, &10.into()), BigInt::from(100));
+    // Unwritten storage, and the storage of other contracts, is zero.
+    assert_eq!(state.storage_read(&contract, &11.into()), BigInt::from(0));
+    assert_eq!(state.storage_read(&other_contract, &10.into()), BigInt::from(0));
+    assert_eq!(
+        state.take_contract_storage(&contract),
+        HashMap::from([(BigInt::from(10), BigInt::from(100))])
+    );
+    assert_eq!(state.contract_storage(&contract), None);
+}
+
+#[test]
+fn clone_isolation() {
+    let mut state = StarknetState::default();
+    let (contract, other_contract) = (BigInt::from(1), BigInt::from(2));
+    state.storage_write(&contract, 10.into(), 100.into());
+    state.storage_write(&other_contract, 10.into(), 200.into());
+    let snapshot = state.clone();
+    // The clone shares all the state.
+    assert!(Rc::ptr_eq(&state.storage, &snapshot.storage));
+    assert!(Rc::ptr_eq(&state.events, &snapshot.events));
+
+    state.storage_write(&contract, 10.into(), 101.into());
+    state.increment_nonce(&contract);
+    state.emit_event(EmittedEvent { keys: vec![1.into()], data: vec![] });
+    state.send_message_to_l1(L2ToL1Message { to_address: 5.into(), payload: vec![6.into()] });
+    assert_eq!(state.storage_read(&contract, &10.into()), BigInt::from(101));
+    assert_eq!(state.nonce(&contract), BigInt::from(1));
+    assert_eq!(state.events().len(), 1);
+    assert_eq!(state.messages().len(), 1);
+
+    // The changes are not visible through the clone.
+    assert_eq!(snapshot.storage_read(&contract, &10.into()), BigInt::from(100));
+    assert_eq!(snapshot.nonce(&contract), BigInt::from(0));
+    assert!(snapshot.events().is_empty());
+    assert!(snapshot.messages().is_empty());
+    // Only the storage of the changed contract was copied.
+    assert!(Rc::ptr_eq(&state.storage[&other_contract], &snapshot.storage[&other_contract]));
+    assert!(!Rc::ptr_eq(&state.storage[&contract], &snapshot.storage[&contract]));
+}
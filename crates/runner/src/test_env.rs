@@ -0,0 +1,115 @@
+//! An environment for testing contracts, running their entry points on a shared StarkNet state.
+
+use std::io::Write;
+use std::rc::Rc;
+
+use num_bigint::{BigInt, BigUint};
+use starknet::casm_contract_class::CasmContractClass;
+
+use crate::entry_point::run_entry_point_in_state;
+use crate::starknet_state::StarknetState;
+use crate::{RunResultValue, RunnerError};
+
+/// A snapshot of the state of a [TestEnv], to restore the environment to with
+/// [TestEnv::restore].
+#[derive(Clone, Debug)]
+pub struct StateHandle(StarknetState);
+
+/// The result of invoking an entry point in a [TestEnv].
+#[derive(Debug)]
+pub struct InvokeResult {
+    /// The gas left at the end of the run, for entry points using the gas builtin.
+    pub gas_counter: Option<BigInt>,
+    /// The returned data of the run, or the panic data if the run reverted.
+    pub value: RunResultValue,
+}
+
+/// An environment for testing contracts - deploys contracts, and invokes their entry points on a
+/// StarkNet state kept across the invocations.
+///
+/// Taking a snapshot of the state is O(1), so test suites can deploy and initialize their contracts
+/// once, and restore the snapshot before each test case instead of repeating the setup.
+#[derive(Debug, Default)]
+pub struct TestEnv {
+    state: StarknetState,
+}
+impl TestEnv {
+    /// Returns the current state of the environment.
+    pub fn state(&self) -> &StarknetState {
+        &self.state
+    }
+
+    /// Deploys a contract of class `casm_class`, and returns its address.
+    /// Contracts are deployed at consecutive addresses, starting at 1.
+    pub fn deploy(&mut self, casm_class: Rc<CasmContractClass>) -> BigInt {
+        let contract_address = BigInt::from(self.state.n_deployed_contracts() + 1);
+        self.state.deploy(contract_address.clone(), casm_class);
+        contract_address
+    }
+
+    /// Invokes the external entry point with the given selector of the contract at
+    /// `contract_address`, with `calldata` as its input, and increments the nonce of the contract.
+    /// Debug prints are written to the standard output.
+    ///
+    /// The changes the entry point makes to the state, including the emitted events and sent
+    /// messages, are kept only if it runs successfully.
+    pub fn invoke(
+        &mut self,
+        contract_address: &BigInt,
+        selector: &BigUint,
+        calldata: &[BigInt],
+        available_gas: usize,
+    ) -> Result<InvokeResult, RunnerError> {
+        self.invoke_with_debug_output(
+            contract_address,
+            selector,
+            calldata,
+            available_gas,
+            &mut std::io::stdout(),
+        )
+    }
+
+    /// Invokes an entry point as [TestEnv::invoke] does, writing the debug prints into
+    /// `debug_output`.
+    pub fn invoke_with_debug_output(
+        &mut self,
+        contract_address: &BigInt,
+        selector: &BigUint,
+        calldata: &[BigInt],
+        available_gas: usize,
+        debug_output: &mut dyn Write,
+    ) -> Result<InvokeResult, RunnerError> {
+        let casm_class =
+            self.state.class_of(contract_address).cloned().ok_or_else(|| {
+                RunnerError::MissingContract { address: contract_address.clone() }
+            })?;
+        self.state.increment_nonce(contract_address);
+        let before_run = self.snapshot();
+        let run_result = run_entry_point_in_state(
+            &casm_class,
+            selector,
+            calldata,
+            available_gas,
+            &mut self.state,
+            contract_address,
+            debug_output,
+        );
+        if !matches!(run_result, Ok((_, RunResultValue::Success(_)))) {
+            self.restore(&before_run);
+        }
+        let (gas_counter, value) = run_result?;
+        Ok(InvokeResult { gas_counter, value })
+    }
+
+    /// Returns a snapshot of the current state of the environment. O(1).
+    pub fn snapshot(&self) -> StateHandle {
+        StateHandle(self.state.clone())
+    }
+
+    /// Restores the environment to the state of `handle`, discarding every change made since the
+    /// snapshot was taken - including the deployed contracts, and the emitted events and sent
+    /// messages. O(1).
+    pub fn restore(&mut self, handle: &StateHandle) {
+        self.state = handle.0.clone();
+    }
+}
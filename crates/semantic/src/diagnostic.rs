@@ -14,6 +14,7 @@ use diagnostics::{
 use smol_str::SmolStr;
 use syntax::node::ids::SyntaxStablePtrId;
 use syntax::node::TypedSyntaxNode;
+use utils::short_string::MAX_SHORT_STRING_LEN;
 
 use crate::db::SemanticGroup;
 use crate::semantic;
@@ -356,6 +357,9 @@ impl DiagnosticEntry for SemanticDiagnostic {
             SemanticDiagnosticKind::ShortStringMustBeAscii => {
                 "Short strings can only include ASCII characters.".into()
             }
+            SemanticDiagnosticKind::ShortStringTooLong => {
+                format!("Short strings are limited to {MAX_SHORT_STRING_LEN} characters.")
+            }
             SemanticDiagnosticKind::IllegalStringEscaping(err) => {
                 format!("Invalid string escaping:\n{err}")
             }
@@ -568,6 +572,7 @@ pub enum SemanticDiagnosticKind {
         actual_enum: EnumId,
     },
     ShortStringMustBeAscii,
+    ShortStringTooLong,
     IllegalStringEscaping(String),
     InvalidCopyTraitImpl,
     InvalidDropTraitImpl,
@@ -9,7 +9,6 @@ use defs::ids::{GenericFunctionId, LocalVarLongId, MemberId};
 use diagnostics::{skip_diagnostic, Maybe, ToMaybe, ToOption};
 use id_arena::Arena;
 use itertools::zip_eq;
-use num_bigint::{BigInt, Sign};
 use smol_str::SmolStr;
 use syntax::node::ast::{BlockOrIf, PatternStructParam};
 use syntax::node::db::SyntaxGroup;
@@ -17,6 +16,7 @@ use syntax::node::helpers::{GetIdentifier, PathSegmentEx};
 use syntax::node::{ast, Terminal, TypedSyntaxNode};
 use unescaper::unescape;
 use utils::ordered_hash_map::OrderedHashMap;
+use utils::short_string::{self, ShortStringError};
 use utils::unordered_hash_map::UnorderedHashMap;
 use utils::unordered_hash_set::UnorderedHashSet;
 use utils::{try_extract_matches, OptionHelper};
@@ -891,15 +891,16 @@ fn short_string_to_semantic(
         let unescaped_literal = unescape(literal).map_err(|err| {
             ctx.diagnostics.report(short_string_syntax, IllegalStringEscaping(format!("{}", err)))
         })?;
-        if unescaped_literal.is_ascii() {
-            Ok(ExprLiteral {
-                value: BigInt::from_bytes_be(Sign::Plus, unescaped_literal.as_bytes()),
-                ty,
-                stable_ptr: short_string_syntax.stable_ptr().into(),
-            })
-        } else {
-            Err(ctx.diagnostics.report(short_string_syntax, ShortStringMustBeAscii))
-        }
+        let value = short_string::from_str(&unescaped_literal).map_err(|err| {
+            ctx.diagnostics.report(
+                short_string_syntax,
+                match err {
+                    ShortStringError::NotAscii => ShortStringMustBeAscii,
+                    ShortStringError::TooLong(_) => ShortStringTooLong,
+                },
+            )
+        })?;
+        Ok(ExprLiteral { value, ty, stable_ptr: short_string_syntax.stable_ptr().into() })
     } else {
         unreachable!();
     }
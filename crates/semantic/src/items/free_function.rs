@@ -1,4 +1,3 @@
-use std::collections::HashSet;
 use std::sync::Arc;
 
 use db_utils::Upcast;
@@ -7,6 +6,7 @@ use diagnostics::{Diagnostics, Maybe, ToMaybe};
 use diagnostics_proc_macros::DebugWithDb;
 use id_arena::Arena;
 use syntax::node::ast;
+use utils::ordered_hash_set::OrderedHashSet;
 use utils::try_extract_matches;
 use utils::unordered_hash_map::UnorderedHashMap;
 
@@ -257,7 +257,7 @@ pub fn priv_free_function_definition_data(
     let body = ctx.exprs.alloc(expr);
     let ComputationContext { exprs, statements, resolver, .. } = ctx;
 
-    let direct_callees: HashSet<FunctionId> = exprs
+    let direct_callees: OrderedHashSet<FunctionId> = exprs
         .iter()
         .filter_map(|(_id, expr)| try_extract_matches!(expr, Expr::FunctionCall))
         .map(|f| f.function)
@@ -4,6 +4,7 @@ use super::bitwise::{BitwiseLibFunc, BitwiseType};
 use super::boolean::BoolLibFunc;
 use super::branch_align::BranchAlignLibFunc;
 use super::builtin_cost::{BuiltinCostLibFunc, BuiltinCostsType};
+use super::debug::DebugLibFunc;
 use super::dict_felt_to::{DictFeltToLibFunc, DictFeltToType};
 use super::drop::DropLibFunc;
 use super::duplicate::DupLibFunc;
@@ -11,7 +12,7 @@ use super::enm::{EnumLibFunc, EnumType};
 use super::modules::boxing::{BoxLibFunc, BoxType};
 use super::modules::felt::{FeltLibFunc, FeltType};
 use super::modules::function_call::FunctionCallLibFunc;
-use super::modules::gas::{GasBuiltinType, GasLibFunc};
+use super::modules::gas::{GasBuiltinType, GasLibFunc, GasReserveType};
 use super::modules::mem::MemLibFunc;
 use super::modules::non_zero::{NonZeroType, UnwrapNonZeroLibFunc};
 use super::modules::uint128::{Uint128LibFunc, Uint128Type};
@@ -31,6 +32,7 @@ define_type_hierarchy! {
         Box(BoxType),
         Felt(FeltType),
         GasBuiltin(GasBuiltinType),
+        GasReserve(GasReserveType),
         BuiltinCosts(BuiltinCostsType),
         Uint128(Uint128Type),
         NonZero(NonZeroType),
@@ -54,6 +56,7 @@ define_libfunc_hierarchy! {
         Bool(BoolLibFunc),
         Box(BoxLibFunc),
         BuiltinCost(BuiltinCostLibFunc),
+        Debug(DebugLibFunc),
         Drop(DropLibFunc),
         Dup(DupLibFunc),
         Felt(FeltLibFunc),
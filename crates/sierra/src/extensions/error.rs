@@ -1,5 +1,7 @@
+use itertools::Itertools;
 use num_bigint::BigInt;
 use thiserror::Error;
+use utils::diagnostic::Diagnostic;
 
 use crate::ids::{ConcreteTypeId, FunctionId, GenericLibFuncId, GenericTypeId};
 use crate::program::GenericArg;
@@ -11,8 +13,11 @@ pub enum SpecializationError {
     UnsupportedId,
     #[error("Expected a different number of generic arguments")]
     WrongNumberOfGenericArgs,
-    #[error("Provided generic arg is unsupported")]
-    UnsupportedGenericArg,
+    #[error("Provided generic arg #{index} is unsupported")]
+    UnsupportedGenericArg {
+        /// The index of the unsupported argument in the generic args list.
+        index: usize,
+    },
     #[error("index is out of a relevant range")]
     IndexOutOfRange {
         index: BigInt,
@@ -37,3 +42,58 @@ pub enum ExtensionError {
     #[error("The requested functionality is not implemented yet")]
     NotImplemented,
 }
+
+impl From<SpecializationError> for Diagnostic {
+    fn from(error: SpecializationError) -> Self {
+        let code = match &error {
+            SpecializationError::UnsupportedId => "E0001",
+            SpecializationError::WrongNumberOfGenericArgs => "E0002",
+            SpecializationError::UnsupportedGenericArg { .. } => "E0003",
+            SpecializationError::IndexOutOfRange { .. } => "E0004",
+            SpecializationError::MissingFunction(_) => "E0005",
+            SpecializationError::TypeWasNotDeclared(_, _) => "E0006",
+            SpecializationError::MissingTypeInfo(_) => "E0007",
+        };
+        let details = match &error {
+            SpecializationError::IndexOutOfRange { index, range_size } => {
+                format!(" (index {index}, range size {range_size})")
+            }
+            SpecializationError::MissingFunction(function_id) => format!(" (`{function_id}`)"),
+            SpecializationError::TypeWasNotDeclared(type_id, args) => {
+                format!(" (`{type_id}<{}>`)", args.iter().join(", "))
+            }
+            SpecializationError::MissingTypeInfo(type_id) => format!(" (`{type_id}`)"),
+            _ => String::new(),
+        };
+        Diagnostic::error(code, format!("{error}{details}."))
+    }
+}
+
+impl From<ExtensionError> for Diagnostic {
+    fn from(error: ExtensionError) -> Self {
+        let message = format!("{error}.");
+        match error {
+            ExtensionError::TypeSpecialization { type_id, error } => {
+                let diagnostic = Diagnostic::from(error);
+                Diagnostic {
+                    message: format!(
+                        "Could not specialize type `{type_id}`: {}",
+                        diagnostic.message
+                    ),
+                    ..diagnostic
+                }
+            }
+            ExtensionError::LibFuncSpecialization { libfunc_id, error } => {
+                let diagnostic = Diagnostic::from(error);
+                Diagnostic {
+                    message: format!(
+                        "Could not specialize libfunc `{libfunc_id}`: {}",
+                        diagnostic.message
+                    ),
+                    ..diagnostic
+                }
+            }
+            ExtensionError::NotImplemented => Diagnostic::error("E0008", message),
+        }
+    }
+}
@@ -80,6 +80,9 @@ pub trait SpecializationContext: SignatureSpecializationContext {
 pub trait GenericLibFunc: Sized {
     type Concrete: ConcreteLibFunc;
 
+    /// Returns the ids the libfunc can be instantiated by.
+    fn supported_ids() -> Vec<GenericLibFuncId>;
+
     /// Instantiates the libfunc by id.
     fn by_id(id: &GenericLibFuncId) -> Option<Self>;
 
@@ -170,6 +173,10 @@ pub trait NamedLibFunc: Default {
 impl<TNamedLibFunc: NamedLibFunc> GenericLibFunc for TNamedLibFunc {
     type Concrete = <Self as NamedLibFunc>::Concrete;
 
+    fn supported_ids() -> Vec<GenericLibFuncId> {
+        vec![Self::ID]
+    }
+
     fn by_id(id: &GenericLibFuncId) -> Option<Self> {
         if &Self::ID == id { Some(Self::default()) } else { None }
     }
@@ -322,6 +329,10 @@ impl From<ConcreteTypeId> for ParamSignature {
 pub enum OutputVarReferenceInfo {
     /// The output value is exactly the same as one of the parameters.
     SameAsParam { param_idx: usize },
+    /// The output has the value of one of the parameters, as a single cell dereference: the
+    /// parameter itself if it is already a dereference, or a new temporary variable holding its
+    /// value otherwise.
+    StoredParam { param_idx: usize },
     /// The output was allocated as a temporary variable.
     /// For the outputs that are at the top of the stack (contiguously), contains the index of the
     /// temporary variable in the stack (0 is the lowest variable).
@@ -561,6 +572,13 @@ macro_rules! define_libfunc_hierarchy {
 
         impl $crate::extensions::GenericLibFunc for $name {
             type Concrete = $concrete_name;
+            fn supported_ids() -> Vec<$crate::ids::GenericLibFuncId> {
+                [
+                    $(
+                        <$variant as $crate::extensions::GenericLibFunc>::supported_ids()
+                    ),*
+                ].concat()
+            }
             fn by_id(id: &$crate::ids::GenericLibFuncId) -> Option<Self> {
                 $(
                     if let Some(res) = <$variant>::by_id(id){
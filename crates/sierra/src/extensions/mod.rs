@@ -7,6 +7,8 @@ pub mod modules;
 pub mod type_specialization_context;
 pub mod types;
 
+use num_bigint::BigInt;
+
 pub use self::error::{ExtensionError, SpecializationError};
 pub use self::lib_func::{
     ConcreteLibFunc, GenericLibFunc, GenericLibFuncEx, NamedLibFunc, NoGenericArgsGenericLibFunc,
@@ -20,10 +22,43 @@ use crate::ids::ConcreteTypeId;
 use crate::program::GenericArg;
 
 /// Helper for extracting the type from the template arguments.
-fn args_as_single_type(args: &[GenericArg]) -> Result<ConcreteTypeId, SpecializationError> {
+pub fn args_as_single_type(args: &[GenericArg]) -> Result<ConcreteTypeId, SpecializationError> {
     match args {
         [GenericArg::Type(ty)] => Ok(ty.clone()),
-        [_] => Err(SpecializationError::UnsupportedGenericArg),
+        [_] => Err(SpecializationError::UnsupportedGenericArg { index: 0 }),
+        _ => Err(SpecializationError::WrongNumberOfGenericArgs),
+    }
+}
+
+/// Helper for extracting the value from the template arguments.
+pub fn args_as_single_value(args: &[GenericArg]) -> Result<BigInt, SpecializationError> {
+    match args {
+        [GenericArg::Value(value)] => Ok(value.clone()),
+        [_] => Err(SpecializationError::UnsupportedGenericArg { index: 0 }),
+        _ => Err(SpecializationError::WrongNumberOfGenericArgs),
+    }
+}
+
+/// Helper for extracting two types from the template arguments.
+pub fn args_as_two_types(
+    args: &[GenericArg],
+) -> Result<(ConcreteTypeId, ConcreteTypeId), SpecializationError> {
+    match args {
+        [GenericArg::Type(ty0), GenericArg::Type(ty1)] => Ok((ty0.clone(), ty1.clone())),
+        [GenericArg::Type(_), _] => Err(SpecializationError::UnsupportedGenericArg { index: 1 }),
+        [_, _] => Err(SpecializationError::UnsupportedGenericArg { index: 0 }),
+        _ => Err(SpecializationError::WrongNumberOfGenericArgs),
+    }
+}
+
+/// Helper for extracting a type and a value from the template arguments.
+pub fn args_as_type_and_value(
+    args: &[GenericArg],
+) -> Result<(ConcreteTypeId, BigInt), SpecializationError> {
+    match args {
+        [GenericArg::Type(ty), GenericArg::Value(value)] => Ok((ty.clone(), value.clone())),
+        [GenericArg::Type(_), _] => Err(SpecializationError::UnsupportedGenericArg { index: 1 }),
+        [_, _] => Err(SpecializationError::UnsupportedGenericArg { index: 0 }),
         _ => Err(SpecializationError::WrongNumberOfGenericArgs),
     }
 }
@@ -1,3 +1,6 @@
+use super::boxing::BoxType;
+use super::felt::FeltType;
+use super::gas::GasBuiltinType;
 use super::range_check::RangeCheckType;
 use super::uint128::Uint128Type;
 use crate::define_libfunc_hierarchy;
@@ -9,7 +12,8 @@ use crate::extensions::lib_func::{
 use crate::extensions::type_specialization_context::TypeSpecializationContext;
 use crate::extensions::types::TypeInfo;
 use crate::extensions::{
-    args_as_single_type, ConcreteType, NamedType, OutputVarReferenceInfo, SpecializationError,
+    args_as_single_type, ConcreteType, NamedType, NoGenericArgsGenericLibFunc,
+    OutputVarReferenceInfo, SpecializationError,
 };
 use crate::ids::{ConcreteTypeId, GenericLibFuncId, GenericTypeId};
 use crate::program::GenericArg;
@@ -40,7 +44,7 @@ impl NamedType for ArrayType {
                 ty,
             })
         } else {
-            Err(SpecializationError::UnsupportedGenericArg)
+            Err(SpecializationError::UnsupportedGenericArg { index: 0 })
         }
     }
 }
@@ -58,9 +62,14 @@ impl ConcreteType for ArrayConcreteType {
 define_libfunc_hierarchy! {
     pub enum ArrayLibFunc {
         New(ArrayNewLibFunc),
+        NewWithCapacity(ArrayNewWithCapacityLibFunc),
+        PanicArrayNew(PanicArrayNewLibFunc),
         Append(ArrayAppendLibFunc),
         At(ArrayAtLibFunc),
+        Get(ArrayGetLibFunc),
         Len(ArrayLenLibFunc),
+        LenChecked(ArrayLenCheckedLibFunc),
+        Clone(ArrayCloneLibFunc),
     }, ArrayConcreteLibFunc
 }
 
@@ -87,6 +96,64 @@ impl SignatureOnlyGenericLibFunc for ArrayNewLibFunc {
     }
 }
 
+/// LibFunc for creating a new empty array, given a hint of the number of elements it will hold.
+/// The capacity is passed to the runner as a hint of the size of the array's segment, and does not
+/// affect the behavior of the array.
+#[derive(Default)]
+pub struct ArrayNewWithCapacityLibFunc {}
+impl SignatureOnlyGenericLibFunc for ArrayNewWithCapacityLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("array_new_with_capacity");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+        args: &[GenericArg],
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let ty = args_as_single_type(args)?;
+        Ok(LibFuncSignature::new_non_branch_ex(
+            vec![ParamSignature {
+                ty: context.get_concrete_type(Uint128Type::id(), &[])?,
+                allow_deferred: false,
+                allow_add_const: false,
+                allow_const: true,
+            }],
+            vec![OutputVarInfo {
+                ty: context.get_wrapped_concrete_type(ArrayType::id(), ty)?,
+                ref_info: OutputVarReferenceInfo::NewTempVar { idx: None },
+            }],
+            SierraApChange::Known { new_vars_only: false },
+        ))
+    }
+}
+
+/// LibFunc for creating the panic data of a panic - an array holding the given felt.
+/// The array is constructed by a runtime routine shared by all the invocations of the libfunc.
+#[derive(Default)]
+pub struct PanicArrayNewLibFunc {}
+impl NoGenericArgsGenericLibFunc for PanicArrayNewLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("panic_array_new");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+        Ok(LibFuncSignature::new_non_branch_ex(
+            vec![ParamSignature {
+                ty: felt_ty.clone(),
+                allow_deferred: false,
+                allow_add_const: false,
+                allow_const: true,
+            }],
+            vec![OutputVarInfo {
+                ty: context.get_wrapped_concrete_type(ArrayType::id(), felt_ty)?,
+                ref_info: OutputVarReferenceInfo::NewTempVar { idx: None },
+            }],
+            SierraApChange::Known { new_vars_only: false },
+        ))
+    }
+}
+
 /// LibFunc for getting the length of the array.
 #[derive(Default)]
 pub struct ArrayLenLibFuncWrapped {}
@@ -117,6 +184,134 @@ impl SignatureAndTypeGenericLibFunc for ArrayLenLibFuncWrapped {
 }
 pub type ArrayLenLibFunc = WrapSignatureAndTypeGenericLibFunc<ArrayLenLibFuncWrapped>;
 
+/// LibFunc for getting the length of the array, range checking it to catch corrupted arrays whose
+/// end is before their start. Used in place of `array_len` when checking array views.
+#[derive(Default)]
+pub struct ArrayLenCheckedLibFuncWrapped {}
+impl SignatureAndTypeGenericLibFunc for ArrayLenCheckedLibFuncWrapped {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("array_len_checked");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+        ty: ConcreteTypeId,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let arr_type = context.get_wrapped_concrete_type(ArrayType::id(), ty)?;
+        let range_check_type = context.get_concrete_type(RangeCheckType::id(), &[])?;
+        Ok(LibFuncSignature::new_non_branch_ex(
+            vec![
+                ParamSignature {
+                    ty: range_check_type.clone(),
+                    allow_deferred: false,
+                    allow_add_const: true,
+                    allow_const: false,
+                },
+                ParamSignature::new(arr_type.clone()),
+            ],
+            vec![
+                OutputVarInfo {
+                    ty: range_check_type,
+                    ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                        param_idx: 0,
+                    }),
+                },
+                OutputVarInfo {
+                    ty: arr_type,
+                    ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 1 },
+                },
+                OutputVarInfo {
+                    ty: context.get_concrete_type(Uint128Type::id(), &[])?,
+                    ref_info: OutputVarReferenceInfo::NewTempVar { idx: None },
+                },
+            ],
+            SierraApChange::Known { new_vars_only: false },
+        ))
+    }
+}
+pub type ArrayLenCheckedLibFunc = WrapSignatureAndTypeGenericLibFunc<ArrayLenCheckedLibFuncWrapped>;
+
+/// The cost of cloning an array per copied cell, in steps - an iteration of the copy loop of
+/// `array_clone`. As the length of the array is not statically known, it is charged at runtime from
+/// the gas counter.
+pub const ARRAY_CLONE_CELL_STEPS: i64 = 11;
+
+/// LibFunc for cloning an array, by copying its elements into a new array.
+/// Charges [ARRAY_CLONE_CELL_STEPS] per copied cell from the gas counter, returning the original
+/// array followed by its clone, or just the original array if the gas counter can not cover the
+/// charge.
+#[derive(Default)]
+pub struct ArrayCloneLibFuncWrapped {}
+impl SignatureAndTypeGenericLibFunc for ArrayCloneLibFuncWrapped {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("array_clone");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+        ty: ConcreteTypeId,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        // Value type must be duplicatable, as the elements are copied into the clone.
+        if !context.get_type_info(ty.clone())?.duplicatable {
+            return Err(SpecializationError::UnsupportedGenericArg { index: 0 });
+        }
+        let arr_type = context.get_wrapped_concrete_type(ArrayType::id(), ty)?;
+        let range_check_type = context.get_concrete_type(RangeCheckType::id(), &[])?;
+        let gas_builtin_type = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+        Ok(LibFuncSignature {
+            param_signatures: vec![
+                ParamSignature::new(range_check_type.clone()),
+                ParamSignature::new(gas_builtin_type.clone()),
+                ParamSignature::new(arr_type.clone()),
+            ],
+            branch_signatures: vec![
+                // Success - the copy loop leaves all the outputs in new temporary variables.
+                BranchSignature {
+                    vars: vec![
+                        OutputVarInfo {
+                            ty: range_check_type.clone(),
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: None },
+                        },
+                        OutputVarInfo {
+                            ty: gas_builtin_type.clone(),
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: None },
+                        },
+                        OutputVarInfo {
+                            ty: arr_type.clone(),
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: None },
+                        },
+                        OutputVarInfo {
+                            ty: arr_type.clone(),
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: None },
+                        },
+                    ],
+                    ap_change: SierraApChange::Unknown,
+                },
+                // Failure - not enough gas to copy the array.
+                BranchSignature {
+                    vars: vec![
+                        OutputVarInfo {
+                            ty: range_check_type,
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 0 },
+                            ),
+                        },
+                        OutputVarInfo {
+                            ty: gas_builtin_type,
+                            ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 1 },
+                        },
+                        OutputVarInfo {
+                            ty: arr_type,
+                            ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 2 },
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+            ],
+            fallthrough: Some(0),
+        })
+    }
+}
+pub type ArrayCloneLibFunc = WrapSignatureAndTypeGenericLibFunc<ArrayCloneLibFuncWrapped>;
+
 /// LibFunc for pushing a value into the end of an array.
 #[derive(Default)]
 pub struct ArrayAppendLibFuncWrapped {}
@@ -162,57 +357,86 @@ impl SignatureAndTypeGenericLibFunc for ArrayAtLibFuncWrapped {
         context: &dyn SignatureSpecializationContext,
         ty: ConcreteTypeId,
     ) -> Result<LibFuncSignature, SpecializationError> {
-        // Value type must be duplicatable.
-        if !context.get_type_info(ty.clone())?.duplicatable {
-            return Err(SpecializationError::UnsupportedGenericArg);
-        }
-        let arr_type = context.get_wrapped_concrete_type(ArrayType::id(), ty.clone())?;
-        let range_check_type = context.get_concrete_type(RangeCheckType::id(), &[])?;
-        let uint128_type = context.get_concrete_type(Uint128Type::id(), &[])?;
-        let param_signatures = vec![
-            ParamSignature::new(range_check_type.clone()),
-            ParamSignature::new(arr_type.clone()),
-            ParamSignature::new(uint128_type),
-        ];
-        let branch_signatures = vec![
-            // First (success) branch returns rc, array and element; failure branch does not return
-            // an element.
-            BranchSignature {
-                vars: vec![
-                    OutputVarInfo {
-                        ty: range_check_type.clone(),
-                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
-                            param_idx: 0,
-                        }),
-                    },
-                    OutputVarInfo {
-                        ty: arr_type.clone(),
-                        ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 1 },
-                    },
-                    OutputVarInfo {
-                        ty,
-                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
-                    },
-                ],
-                ap_change: SierraApChange::Known { new_vars_only: false },
-            },
-            BranchSignature {
-                vars: vec![
-                    OutputVarInfo {
-                        ty: range_check_type,
-                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
-                            param_idx: 0,
-                        }),
-                    },
-                    OutputVarInfo {
-                        ty: arr_type,
-                        ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 1 },
-                    },
-                ],
-                ap_change: SierraApChange::Known { new_vars_only: false },
-            },
-        ];
-        Ok(LibFuncSignature { param_signatures, branch_signatures, fallthrough: Some(0) })
+        array_access_signature(context, ty.clone(), ty)
     }
 }
 pub type ArrayAtLibFunc = WrapSignatureAndTypeGenericLibFunc<ArrayAtLibFuncWrapped>;
+
+/// LibFunc for fetching a boxed value from a specific array index.
+/// Unlike `array_at`, the success branch returns a `Box<T>` - a pointer to the element within the
+/// array - leaving it to the following statements to unbox the parts they use.
+#[derive(Default)]
+pub struct ArrayGetLibFuncWrapped {}
+impl SignatureAndTypeGenericLibFunc for ArrayGetLibFuncWrapped {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("array_get");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+        ty: ConcreteTypeId,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let box_ty = context.get_wrapped_concrete_type(BoxType::id(), ty.clone())?;
+        array_access_signature(context, ty, box_ty)
+    }
+}
+pub type ArrayGetLibFunc = WrapSignatureAndTypeGenericLibFunc<ArrayGetLibFuncWrapped>;
+
+/// Returns the signature of a libfunc fetching an element of type `ty` from an array, where the
+/// success branch returns the element as `element_output_ty`.
+fn array_access_signature(
+    context: &dyn SignatureSpecializationContext,
+    ty: ConcreteTypeId,
+    element_output_ty: ConcreteTypeId,
+) -> Result<LibFuncSignature, SpecializationError> {
+    // Value type must be duplicatable.
+    if !context.get_type_info(ty.clone())?.duplicatable {
+        return Err(SpecializationError::UnsupportedGenericArg { index: 0 });
+    }
+    let arr_type = context.get_wrapped_concrete_type(ArrayType::id(), ty)?;
+    let range_check_type = context.get_concrete_type(RangeCheckType::id(), &[])?;
+    let uint128_type = context.get_concrete_type(Uint128Type::id(), &[])?;
+    let param_signatures = vec![
+        ParamSignature::new(range_check_type.clone()),
+        ParamSignature::new(arr_type.clone()),
+        ParamSignature::new(uint128_type),
+    ];
+    let branch_signatures = vec![
+        // First (success) branch returns rc, array and element output; failure branch does not
+        // return an element.
+        BranchSignature {
+            vars: vec![
+                OutputVarInfo {
+                    ty: range_check_type.clone(),
+                    ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                        param_idx: 0,
+                    }),
+                },
+                OutputVarInfo {
+                    ty: arr_type.clone(),
+                    ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 1 },
+                },
+                OutputVarInfo {
+                    ty: element_output_ty,
+                    ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                },
+            ],
+            ap_change: SierraApChange::Known { new_vars_only: false },
+        },
+        BranchSignature {
+            vars: vec![
+                OutputVarInfo {
+                    ty: range_check_type,
+                    ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                        param_idx: 0,
+                    }),
+                },
+                OutputVarInfo {
+                    ty: arr_type,
+                    ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 1 },
+                },
+            ],
+            ap_change: SierraApChange::Known { new_vars_only: false },
+        },
+    ];
+    Ok(LibFuncSignature { param_signatures, branch_signatures, fallthrough: Some(0) })
+}
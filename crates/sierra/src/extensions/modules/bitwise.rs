@@ -1,32 +1,22 @@
 use super::uint128::Uint128Type;
+use crate::define_info_only_type;
 use crate::extensions::lib_func::{
     DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature, SierraApChange,
     SignatureSpecializationContext,
 };
-use crate::extensions::types::{InfoOnlyConcreteType, TypeInfo};
 use crate::extensions::{
-    NamedType, NoGenericArgsGenericLibFunc, NoGenericArgsGenericType, OutputVarReferenceInfo,
-    SpecializationError,
+    NamedType, NoGenericArgsGenericLibFunc, OutputVarReferenceInfo, SpecializationError,
 };
-use crate::ids::{GenericLibFuncId, GenericTypeId};
+use crate::ids::GenericLibFuncId;
 
-/// Type representing the Bitwise builtin.
-#[derive(Default)]
-pub struct BitwiseType {}
-impl NoGenericArgsGenericType for BitwiseType {
-    type Concrete = InfoOnlyConcreteType;
-    const ID: GenericTypeId = GenericTypeId::new_inline("Bitwise");
-
-    fn specialize(&self) -> Self::Concrete {
-        InfoOnlyConcreteType {
-            info: TypeInfo {
-                long_id: Self::concrete_type_long_id(&[]),
-                storable: true,
-                droppable: false,
-                duplicatable: false,
-                size: 1,
-            },
-        }
+define_info_only_type! {
+    /// Type representing the Bitwise builtin.
+    pub struct BitwiseType {
+        id: "Bitwise",
+        storable: true,
+        droppable: false,
+        duplicatable: false,
+        size: 1,
     }
 }
 
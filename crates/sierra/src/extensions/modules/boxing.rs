@@ -24,7 +24,12 @@ impl NamedType for BoxType {
         args: &[GenericArg],
     ) -> Result<Self::Concrete, SpecializationError> {
         let ty = args_as_single_type(args)?;
-        Ok(BoxConcreteType { info: context.get_type_info(ty.clone())?, ty })
+        let inner_info = context.get_type_info(ty.clone())?;
+        // A box is a single pointer to its value.
+        Ok(BoxConcreteType {
+            info: TypeInfo { long_id: Self::concrete_type_long_id(args), size: 1, ..inner_info },
+            ty,
+        })
     }
 }
 
@@ -1,18 +1,17 @@
 use convert_case::Casing;
 
+use super::felt::FeltType;
 use super::gas::GasBuiltinType;
 use super::range_check::RangeCheckType;
-use crate::define_libfunc_hierarchy;
 use crate::extensions::lib_func::{
     BranchSignature, DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature,
     SierraApChange, SignatureSpecializationContext,
 };
-use crate::extensions::types::{InfoOnlyConcreteType, TypeInfo};
 use crate::extensions::{
-    NamedType, NoGenericArgsGenericLibFunc, NoGenericArgsGenericType, OutputVarReferenceInfo,
-    SpecializationError,
+    NamedType, NoGenericArgsGenericLibFunc, OutputVarReferenceInfo, SpecializationError,
 };
-use crate::ids::{GenericLibFuncId, GenericTypeId};
+use crate::ids::GenericLibFuncId;
+use crate::{define_info_only_type, define_libfunc_hierarchy};
 
 /// Represents different type of costs.
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
@@ -48,29 +47,21 @@ impl CostTokenType {
     }
 }
 
-/// Represents the cost of a single invocation of a builtin.
-#[derive(Default)]
-pub struct BuiltinCostsType {}
-impl NoGenericArgsGenericType for BuiltinCostsType {
-    type Concrete = InfoOnlyConcreteType;
-    const ID: GenericTypeId = GenericTypeId::new_inline("BuiltinCosts");
-
-    fn specialize(&self) -> Self::Concrete {
-        InfoOnlyConcreteType {
-            info: TypeInfo {
-                long_id: Self::concrete_type_long_id(&[]),
-                storable: true,
-                droppable: true,
-                duplicatable: true,
-                size: 1,
-            },
-        }
+define_info_only_type! {
+    /// Represents the cost of a single invocation of a builtin.
+    pub struct BuiltinCostsType {
+        id: "BuiltinCosts",
+        storable: true,
+        droppable: true,
+        duplicatable: true,
+        size: 1,
     }
 }
 
 define_libfunc_hierarchy! {
     pub enum BuiltinCostLibFunc {
         BuiltinGetGas(BuiltinCostGetGasLibFunc),
+        WithdrawGasAll(BuiltinCostWithdrawGasAllLibFunc),
     }, BuiltinCostConcreteLibFunc
 }
 
@@ -144,3 +135,74 @@ impl NoGenericArgsGenericLibFunc for BuiltinCostGetGasLibFunc {
         })
     }
 }
+
+/// LibFunc for withdrawing the gas required by the rest of the function, and draining the rest of
+/// the gas counter into a felt - for example, in order to forward it to an external call.
+#[derive(Default)]
+pub struct BuiltinCostWithdrawGasAllLibFunc {}
+impl NoGenericArgsGenericLibFunc for BuiltinCostWithdrawGasAllLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("withdraw_gas_all");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let gas_builtin_type = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+        let range_check_type = context.get_concrete_type(RangeCheckType::id(), &[])?;
+        let builtin_costs_type = context.get_concrete_type(BuiltinCostsType::id(), &[])?;
+        let felt_type = context.get_concrete_type(FeltType::id(), &[])?;
+        Ok(LibFuncSignature {
+            param_signatures: vec![
+                ParamSignature {
+                    ty: range_check_type.clone(),
+                    allow_deferred: false,
+                    allow_add_const: true,
+                    allow_const: false,
+                },
+                ParamSignature::new(gas_builtin_type.clone()),
+                ParamSignature::new(builtin_costs_type),
+            ],
+            branch_signatures: vec![
+                // Success:
+                BranchSignature {
+                    vars: vec![
+                        OutputVarInfo {
+                            ty: range_check_type.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 0 },
+                            ),
+                        },
+                        // The drained gas counter.
+                        OutputVarInfo {
+                            ty: gas_builtin_type.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Const),
+                        },
+                        // The excess gas.
+                        OutputVarInfo {
+                            ty: felt_type,
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+                // Failure:
+                BranchSignature {
+                    vars: vec![
+                        OutputVarInfo {
+                            ty: range_check_type,
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 0 },
+                            ),
+                        },
+                        OutputVarInfo {
+                            ty: gas_builtin_type,
+                            ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 1 },
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+            ],
+            fallthrough: Some(0),
+        })
+    }
+}
@@ -5,7 +5,8 @@ use crate::extensions::lib_func::{
     SignatureSpecializationContext, SpecializationContext,
 };
 use crate::extensions::{
-    NamedLibFunc, OutputVarReferenceInfo, SignatureBasedConcreteLibFunc, SpecializationError,
+    args_as_single_value, NamedLibFunc, OutputVarReferenceInfo, SignatureBasedConcreteLibFunc,
+    SpecializationError,
 };
 use crate::ids::{GenericLibFuncId, GenericTypeId};
 use crate::program::GenericArg;
@@ -16,6 +17,12 @@ pub trait ConstGenLibFunc: Default {
     const ID: GenericLibFuncId;
     /// The id of the generic type to implement the library functions for.
     const GENERIC_TYPE_ID: GenericTypeId;
+
+    /// Returns whether `c` is a valid value of the type. Invalid constants are rejected at
+    /// specialization.
+    fn is_valid_const(_c: &BigInt) -> bool {
+        true
+    }
 }
 
 /// Wrapper to prevent implementation collisions for `NamedLibFunc`.
@@ -46,17 +53,14 @@ impl<T: ConstGenLibFunc> NamedLibFunc for WrapConstGenLibFunc<T> {
         context: &dyn SpecializationContext,
         args: &[GenericArg],
     ) -> Result<Self::Concrete, SpecializationError> {
-        match args {
-            [GenericArg::Value(c)] => Ok(SignatureAndConstConcreteLibFunc {
-                c: c.clone(),
-                signature: <Self as NamedLibFunc>::specialize_signature(
-                    self,
-                    context.upcast(),
-                    args,
-                )?,
-            }),
-            _ => Err(SpecializationError::UnsupportedGenericArg),
+        let c = args_as_single_value(args)?;
+        if !T::is_valid_const(&c) {
+            return Err(SpecializationError::UnsupportedGenericArg { index: 0 });
         }
+        Ok(SignatureAndConstConcreteLibFunc {
+            c,
+            signature: <Self as NamedLibFunc>::specialize_signature(self, context.upcast(), args)?,
+        })
     }
 }
 
@@ -0,0 +1,40 @@
+use super::array::ArrayType;
+use super::felt::FeltType;
+use crate::define_libfunc_hierarchy;
+use crate::extensions::lib_func::{
+    LibFuncSignature, OutputVarInfo, SierraApChange, SignatureSpecializationContext,
+};
+use crate::extensions::{
+    NamedType, NoGenericArgsGenericLibFunc, OutputVarReferenceInfo, SpecializationError,
+};
+use crate::ids::GenericLibFuncId;
+
+define_libfunc_hierarchy! {
+    pub enum DebugLibFunc {
+        Print(PrintLibFunc),
+    }, DebugConcreteLibFunc
+}
+
+/// LibFunc for printing the felts of an array, for debugging purposes.
+/// Returns the array unchanged.
+#[derive(Default)]
+pub struct PrintLibFunc {}
+impl NoGenericArgsGenericLibFunc for PrintLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("debug_print");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+        let arr_type = context.get_wrapped_concrete_type(ArrayType::id(), felt_ty)?;
+        Ok(LibFuncSignature::new_non_branch(
+            vec![arr_type.clone()],
+            vec![OutputVarInfo {
+                ty: arr_type,
+                ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 0 },
+            }],
+            SierraApChange::Known { new_vars_only: true },
+        ))
+    }
+}
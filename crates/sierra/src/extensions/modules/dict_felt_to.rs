@@ -42,7 +42,7 @@ impl NamedType for DictFeltToType {
                 ty,
             })
         } else {
-            Err(SpecializationError::UnsupportedGenericArg)
+            Err(SpecializationError::UnsupportedGenericArg { index: 0 })
         }
     }
 }
@@ -25,7 +25,7 @@ impl SignatureOnlyGenericLibFunc for DropLibFunc {
                 SierraApChange::Known { new_vars_only: true },
             ))
         } else {
-            Err(SpecializationError::UnsupportedGenericArg)
+            Err(SpecializationError::UnsupportedGenericArg { index: 0 })
         }
     }
 }
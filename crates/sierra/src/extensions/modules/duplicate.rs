@@ -20,7 +20,7 @@ impl SignatureOnlyGenericLibFunc for DupLibFunc {
         let ty = args_as_single_type(generic_args)?;
         let info = context.get_type_info(ty.clone())?;
         if !info.duplicatable {
-            return Err(SpecializationError::UnsupportedGenericArg);
+            return Err(SpecializationError::UnsupportedGenericArg { index: 0 });
         }
 
         Ok(LibFuncSignature::new_non_branch(
@@ -30,8 +30,8 @@ use crate::extensions::lib_func::{
 use crate::extensions::type_specialization_context::TypeSpecializationContext;
 use crate::extensions::types::TypeInfo;
 use crate::extensions::{
-    args_as_single_type, ConcreteType, NamedLibFunc, NamedType, OutputVarReferenceInfo,
-    SignatureBasedConcreteLibFunc, SpecializationError,
+    args_as_single_type, args_as_type_and_value, ConcreteType, NamedLibFunc, NamedType,
+    OutputVarReferenceInfo, SignatureBasedConcreteLibFunc, SpecializationError,
 };
 use crate::ids::{ConcreteTypeId, GenericLibFuncId, GenericTypeId};
 use crate::program::{ConcreteTypeLongId, GenericArg};
@@ -61,22 +61,20 @@ impl EnumConcreteType {
         context: &dyn TypeSpecializationContext,
         args: &[GenericArg],
     ) -> Result<Self, SpecializationError> {
-        let mut args_iter = args.iter();
-        args_iter
-            .next()
+        args.first()
             .and_then(|arg| try_extract_matches!(arg, GenericArg::UserType))
-            .ok_or(SpecializationError::UnsupportedGenericArg)?;
+            .ok_or(SpecializationError::UnsupportedGenericArg { index: 0 })?;
         let mut duplicatable = true;
         let mut droppable = true;
         let mut variants: Vec<ConcreteTypeId> = Vec::new();
         let mut variant_max_size = 0;
-        for arg in args_iter {
+        for (index, arg) in args.iter().enumerate().skip(1) {
             let ty = try_extract_matches!(arg, GenericArg::Type)
-                .ok_or(SpecializationError::UnsupportedGenericArg)?
+                .ok_or(SpecializationError::UnsupportedGenericArg { index })?
                 .clone();
             let info = context.get_type_info(ty.clone())?;
             if !info.storable {
-                return Err(SpecializationError::UnsupportedGenericArg);
+                return Err(SpecializationError::UnsupportedGenericArg { index });
             }
             if !info.duplicatable {
                 duplicatable = false;
@@ -89,10 +87,7 @@ impl EnumConcreteType {
         }
         Ok(EnumConcreteType {
             info: TypeInfo {
-                long_id: ConcreteTypeLongId {
-                    generic_id: "Enum".into(),
-                    generic_args: args.to_vec(),
-                },
+                long_id: ConcreteTypeLongId::new("Enum".into(), args.to_vec()),
                 duplicatable,
                 droppable,
                 storable: true,
@@ -138,13 +133,7 @@ impl EnumInitLibFunc {
         context: &dyn SignatureSpecializationContext,
         args: &[GenericArg],
     ) -> Result<EnumInitConcreteLibFunc, SpecializationError> {
-        let (enum_type, index) = match args {
-            [GenericArg::Type(enum_type), GenericArg::Value(index)] => {
-                (enum_type.clone(), index.clone())
-            }
-            [_, _] => return Err(SpecializationError::UnsupportedGenericArg),
-            _ => return Err(SpecializationError::WrongNumberOfGenericArgs),
-        };
+        let (enum_type, index) = args_as_type_and_value(args)?;
         let generic_args = context.get_type_info(enum_type.clone())?.long_id.generic_args;
         let variant_types =
             EnumConcreteType::new(context.as_type_specialization_context(), &generic_args)?
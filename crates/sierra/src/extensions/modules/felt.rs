@@ -7,33 +7,23 @@ use crate::extensions::lib_func::{
     DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature, SierraApChange,
     SignatureSpecializationContext, SpecializationContext,
 };
-use crate::extensions::types::{InfoOnlyConcreteType, TypeInfo};
 use crate::extensions::{
-    GenericLibFunc, NamedLibFunc, NamedType, NoGenericArgsGenericType, OutputVarReferenceInfo,
+    args_as_single_value, GenericLibFunc, NamedLibFunc, NamedType, OutputVarReferenceInfo,
     SignatureBasedConcreteLibFunc, SpecializationError,
 };
 use crate::ids::{GenericLibFuncId, GenericTypeId};
 use crate::program::GenericArg;
-use crate::{define_concrete_libfunc_hierarchy, define_libfunc_hierarchy};
+use crate::{define_concrete_libfunc_hierarchy, define_info_only_type, define_libfunc_hierarchy};
 
-/// Type for felt.
-/// The native type of the Cairo architecture.
-#[derive(Default)]
-pub struct FeltType {}
-impl NoGenericArgsGenericType for FeltType {
-    type Concrete = InfoOnlyConcreteType;
-    const ID: GenericTypeId = GenericTypeId::new_inline("felt");
-
-    fn specialize(&self) -> Self::Concrete {
-        InfoOnlyConcreteType {
-            info: TypeInfo {
-                long_id: Self::concrete_type_long_id(&[]),
-                storable: true,
-                droppable: true,
-                duplicatable: true,
-                size: 1,
-            },
-        }
+define_info_only_type! {
+    /// Type for felt.
+    /// The native type of the Cairo architecture.
+    pub struct FeltType {
+        id: "felt",
+        storable: true,
+        droppable: true,
+        duplicatable: true,
+        size: 1,
     }
 }
 
@@ -69,6 +59,11 @@ pub enum FeltBinaryOperator {
     Div,
 }
 
+const FELT_ADD: GenericLibFuncId = GenericLibFuncId::new_inline("felt_add");
+const FELT_SUB: GenericLibFuncId = GenericLibFuncId::new_inline("felt_sub");
+const FELT_MUL: GenericLibFuncId = GenericLibFuncId::new_inline("felt_mul");
+const FELT_DIV: GenericLibFuncId = GenericLibFuncId::new_inline("felt_div");
+
 /// Libfunc for felt binary operations.
 pub struct FeltBinaryOperationLibFunc {
     pub operator: FeltBinaryOperator,
@@ -81,16 +76,16 @@ impl FeltBinaryOperationLibFunc {
 impl GenericLibFunc for FeltBinaryOperationLibFunc {
     type Concrete = FeltBinaryOperationConcreteLibFunc;
 
+    fn supported_ids() -> Vec<GenericLibFuncId> {
+        vec![FELT_ADD, FELT_SUB, FELT_MUL, FELT_DIV]
+    }
+
     fn by_id(id: &GenericLibFuncId) -> Option<Self> {
-        const ADD: GenericLibFuncId = GenericLibFuncId::new_inline("felt_add");
-        const SUB: GenericLibFuncId = GenericLibFuncId::new_inline("felt_sub");
-        const MUL: GenericLibFuncId = GenericLibFuncId::new_inline("felt_mul");
-        const DIV: GenericLibFuncId = GenericLibFuncId::new_inline("felt_div");
         match id {
-            id if id == &ADD => Some(Self::new(FeltBinaryOperator::Add)),
-            id if id == &SUB => Some(Self::new(FeltBinaryOperator::Sub)),
-            id if id == &MUL => Some(Self::new(FeltBinaryOperator::Mul)),
-            id if id == &DIV => Some(Self::new(FeltBinaryOperator::Div)),
+            id if id == &FELT_ADD => Some(Self::new(FeltBinaryOperator::Add)),
+            id if id == &FELT_SUB => Some(Self::new(FeltBinaryOperator::Sub)),
+            id if id == &FELT_MUL => Some(Self::new(FeltBinaryOperator::Mul)),
+            id if id == &FELT_DIV => Some(Self::new(FeltBinaryOperator::Div)),
             _ => None,
         }
     }
@@ -124,7 +119,7 @@ impl GenericLibFunc for FeltBinaryOperationLibFunc {
             )),
             [GenericArg::Value(c)] => {
                 if matches!(self.operator, FeltBinaryOperator::Div) && c.is_zero() {
-                    Err(SpecializationError::UnsupportedGenericArg)
+                    Err(SpecializationError::UnsupportedGenericArg { index: 0 })
                 } else {
                     Ok(LibFuncSignature::new_non_branch(
                         vec![ty.clone()],
@@ -136,7 +131,8 @@ impl GenericLibFunc for FeltBinaryOperationLibFunc {
                     ))
                 }
             }
-            _ => Err(SpecializationError::UnsupportedGenericArg),
+            [_] => Err(SpecializationError::UnsupportedGenericArg { index: 0 }),
+            _ => Err(SpecializationError::WrongNumberOfGenericArgs),
         }
     }
 
@@ -154,7 +150,7 @@ impl GenericLibFunc for FeltBinaryOperationLibFunc {
             }),
             [GenericArg::Value(c)] => {
                 if matches!(self.operator, FeltBinaryOperator::Div) && c.is_zero() {
-                    Err(SpecializationError::UnsupportedGenericArg)
+                    Err(SpecializationError::UnsupportedGenericArg { index: 0 })
                 } else {
                     Ok(FeltBinaryOperationConcreteLibFunc::Const(
                         FeltOperationWithConstConcreteLibFunc {
@@ -165,11 +161,14 @@ impl GenericLibFunc for FeltBinaryOperationLibFunc {
                     ))
                 }
             }
-            _ => Err(SpecializationError::UnsupportedGenericArg),
+            [_] => Err(SpecializationError::UnsupportedGenericArg { index: 0 }),
+            _ => Err(SpecializationError::WrongNumberOfGenericArgs),
         }
     }
 }
 
+const FELT_NEG: GenericLibFuncId = GenericLibFuncId::new_inline("felt_neg");
+
 /// Libfunc for felt unary operations.
 pub struct FeltUnaryOperationLibFunc {
     pub operator: FeltUnaryOperator,
@@ -182,10 +181,13 @@ impl FeltUnaryOperationLibFunc {
 impl GenericLibFunc for FeltUnaryOperationLibFunc {
     type Concrete = FeltUnaryOperationConcreteLibFunc;
 
+    fn supported_ids() -> Vec<GenericLibFuncId> {
+        vec![FELT_NEG]
+    }
+
     fn by_id(id: &GenericLibFuncId) -> Option<Self> {
-        const NEG: GenericLibFuncId = GenericLibFuncId::new_inline("felt_neg");
         match id {
-            id if id == &NEG => Some(Self::new(FeltUnaryOperator::Neg)),
+            id if id == &FELT_NEG => Some(Self::new(FeltUnaryOperator::Neg)),
             _ => None,
         }
     }
@@ -212,7 +214,7 @@ impl GenericLibFunc for FeltUnaryOperationLibFunc {
                     SierraApChange::Known { new_vars_only: true },
                 ))
             }
-            _ => Err(SpecializationError::UnsupportedGenericArg),
+            _ => Err(SpecializationError::WrongNumberOfGenericArgs),
         }
     }
 
@@ -228,7 +230,7 @@ impl GenericLibFunc for FeltUnaryOperationLibFunc {
                     signature: self.specialize_signature(context.upcast(), args)?,
                 })
             }),
-            _ => Err(SpecializationError::UnsupportedGenericArg),
+            _ => Err(SpecializationError::WrongNumberOfGenericArgs),
         }
     }
 }
@@ -305,17 +307,10 @@ impl NamedLibFunc for FeltConstLibFunc {
         context: &dyn SpecializationContext,
         args: &[GenericArg],
     ) -> Result<Self::Concrete, SpecializationError> {
-        match args {
-            [GenericArg::Value(c)] => Ok(FeltConstConcreteLibFunc {
-                c: c.clone(),
-                signature: <Self as NamedLibFunc>::specialize_signature(
-                    self,
-                    context.upcast(),
-                    args,
-                )?,
-            }),
-            _ => Err(SpecializationError::UnsupportedGenericArg),
-        }
+        Ok(FeltConstConcreteLibFunc {
+            c: args_as_single_value(args)?,
+            signature: <Self as NamedLibFunc>::specialize_signature(self, context.upcast(), args)?,
+        })
     }
 }
 
@@ -22,6 +22,7 @@ impl NamedLibFunc for FunctionCallLibFunc {
             [GenericArg::UserFunc(function_id)] => {
                 let signature = context.get_function_signature(function_id)?;
                 let ap_change = context.get_function_ap_change(function_id)?;
+                // The results are the top variables of the stack after the call, in order.
                 Ok(LibFuncSignature::new_non_branch(
                     signature.param_types.clone(),
                     signature
@@ -36,7 +37,8 @@ impl NamedLibFunc for FunctionCallLibFunc {
                     ap_change,
                 ))
             }
-            _ => Err(SpecializationError::UnsupportedGenericArg),
+            [_] => Err(SpecializationError::UnsupportedGenericArg { index: 0 }),
+            _ => Err(SpecializationError::WrongNumberOfGenericArgs),
         }
     }
 
@@ -50,7 +52,8 @@ impl NamedLibFunc for FunctionCallLibFunc {
                 function: context.get_function(function_id)?,
                 signature: self.specialize_signature(context.upcast(), args)?,
             }),
-            _ => Err(SpecializationError::UnsupportedGenericArg),
+            [_] => Err(SpecializationError::UnsupportedGenericArg { index: 0 }),
+            _ => Err(SpecializationError::WrongNumberOfGenericArgs),
         }
     }
 }
@@ -1,34 +1,42 @@
 // Module providing the gas related extensions.
+use num_traits::Signed;
+
+use super::consts::SignatureAndConstConcreteLibFunc;
 use super::range_check::RangeCheckType;
-use crate::define_libfunc_hierarchy;
 use crate::extensions::lib_func::{
     BranchSignature, DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature,
-    SierraApChange, SignatureSpecializationContext,
+    SierraApChange, SignatureSpecializationContext, SpecializationContext,
 };
-use crate::extensions::types::{InfoOnlyConcreteType, TypeInfo};
 use crate::extensions::{
-    NamedType, NoGenericArgsGenericLibFunc, NoGenericArgsGenericType, OutputVarReferenceInfo,
-    SpecializationError,
+    args_as_single_value, NamedLibFunc, NamedType, NoGenericArgsGenericLibFunc,
+    OutputVarReferenceInfo, SpecializationError,
 };
-use crate::ids::{GenericLibFuncId, GenericTypeId};
+use crate::ids::GenericLibFuncId;
+use crate::program::GenericArg;
+use crate::{define_info_only_type, define_libfunc_hierarchy};
 
-/// Type for gas actions.
-#[derive(Default)]
-pub struct GasBuiltinType {}
-impl NoGenericArgsGenericType for GasBuiltinType {
-    type Concrete = InfoOnlyConcreteType;
-    const ID: GenericTypeId = GenericTypeId::new_inline("GasBuiltin");
+define_info_only_type! {
+    /// Type for gas actions.
+    pub struct GasBuiltinType {
+        id: "GasBuiltin",
+        storable: true,
+        droppable: false,
+        duplicatable: false,
+        size: 1,
+    }
+}
 
-    fn specialize(&self) -> Self::Concrete {
-        InfoOnlyConcreteType {
-            info: TypeInfo {
-                long_id: Self::concrete_type_long_id(&[]),
-                storable: true,
-                droppable: false,
-                duplicatable: false,
-                size: 1,
-            },
-        }
+define_info_only_type! {
+    /// Type for gas reserved out of the gas counter, which is returned to it when released.
+    pub struct GasReserveType {
+        id: "GasReserve",
+        // Cannot be part of an array, an enum or a struct, so that a reserve can only be created
+        // by `gas_reserve_create`, and not from external data.
+        storable: false,
+        // Must be released, so that the reserved gas is not lost.
+        droppable: false,
+        duplicatable: false,
+        size: 1,
     }
 }
 
@@ -36,6 +44,8 @@ define_libfunc_hierarchy! {
     pub enum GasLibFunc {
         GetGas(GetGasLibFunc),
         RefundGas(RefundGasLibFunc),
+        ReserveCreate(GasReserveCreateLibFunc),
+        ReserveRelease(GasReserveReleaseLibFunc),
     }, GasConcreteLibFunc
 }
 
@@ -121,3 +131,119 @@ impl NoGenericArgsGenericLibFunc for RefundGasLibFunc {
         ))
     }
 }
+
+/// LibFunc for reserving a constant amount of gas out of the gas counter, branching on whether the
+/// counter holds enough gas.
+#[derive(Default)]
+pub struct GasReserveCreateLibFunc {}
+impl NamedLibFunc for GasReserveCreateLibFunc {
+    type Concrete = SignatureAndConstConcreteLibFunc;
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("gas_reserve_create");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+        _args: &[GenericArg],
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let gas_builtin_type = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+        let range_check_type = context.get_concrete_type(RangeCheckType::id(), &[])?;
+        let gas_reserve_type = context.get_concrete_type(GasReserveType::id(), &[])?;
+        Ok(LibFuncSignature {
+            param_signatures: vec![
+                ParamSignature {
+                    ty: range_check_type.clone(),
+                    allow_deferred: false,
+                    allow_add_const: true,
+                    allow_const: false,
+                },
+                ParamSignature::new(gas_builtin_type.clone()),
+            ],
+            branch_signatures: vec![
+                // Success:
+                BranchSignature {
+                    vars: vec![
+                        OutputVarInfo {
+                            ty: range_check_type.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 0 },
+                            ),
+                        },
+                        OutputVarInfo {
+                            ty: gas_builtin_type.clone(),
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                        },
+                        OutputVarInfo {
+                            ty: gas_reserve_type,
+                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Const),
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+                // Failure:
+                BranchSignature {
+                    vars: vec![
+                        OutputVarInfo {
+                            ty: range_check_type,
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 0 },
+                            ),
+                        },
+                        OutputVarInfo {
+                            ty: gas_builtin_type,
+                            ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 1 },
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+            ],
+            fallthrough: Some(0),
+        })
+    }
+
+    fn specialize(
+        &self,
+        context: &dyn SpecializationContext,
+        args: &[GenericArg],
+    ) -> Result<Self::Concrete, SpecializationError> {
+        let amount = args_as_single_value(args)?;
+        // The amount is a constant of the gas reserve equations of the gas solver.
+        if amount.is_negative() || i32::try_from(&amount).is_err() {
+            return Err(SpecializationError::UnsupportedGenericArg { index: 0 });
+        }
+        Ok(SignatureAndConstConcreteLibFunc {
+            c: amount,
+            signature: <Self as NamedLibFunc>::specialize_signature(self, context.upcast(), args)?,
+        })
+    }
+}
+
+/// LibFunc for returning reserved gas to the gas counter.
+#[derive(Default)]
+pub struct GasReserveReleaseLibFunc {}
+impl NoGenericArgsGenericLibFunc for GasReserveReleaseLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("gas_reserve_release");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let gas_builtin_type = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+        let gas_reserve_type = context.get_concrete_type(GasReserveType::id(), &[])?;
+        Ok(LibFuncSignature::new_non_branch_ex(
+            vec![
+                ParamSignature::new(gas_builtin_type.clone()),
+                ParamSignature {
+                    ty: gas_reserve_type,
+                    allow_deferred: false,
+                    allow_add_const: false,
+                    allow_const: true,
+                },
+            ],
+            vec![OutputVarInfo {
+                ty: gas_builtin_type,
+                ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+            }],
+            SierraApChange::Known { new_vars_only: true },
+        ))
+    }
+}
@@ -36,19 +36,21 @@ impl<TJumpNotZeroTraits: JumpNotZeroTraits> NoGenericArgsGenericLibFunc
         let ty = context.get_concrete_type(TJumpNotZeroTraits::GENERIC_TYPE_ID, &[])?;
         Ok(LibFuncSignature {
             param_signatures: vec![ParamSignature::new(ty.clone())],
+            // A value that is not a dereference is first stored into a temporary variable, which is
+            // not an output of the zero branch.
             branch_signatures: vec![
                 // Zero.
                 BranchSignature {
                     vars: vec![],
-                    ap_change: SierraApChange::Known { new_vars_only: true },
+                    ap_change: SierraApChange::Known { new_vars_only: false },
                 },
                 // NonZero.
                 BranchSignature {
                     vars: vec![OutputVarInfo {
                         ty: context.get_wrapped_concrete_type(NonZeroType::id(), ty)?,
-                        ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 0 },
+                        ref_info: OutputVarReferenceInfo::StoredParam { param_idx: 0 },
                     }],
-                    ap_change: SierraApChange::Known { new_vars_only: true },
+                    ap_change: SierraApChange::Known { new_vars_only: false },
                 },
             ],
             fallthrough: Some(0),
@@ -13,6 +13,7 @@ pub mod boxing;
 pub mod branch_align;
 pub mod builtin_cost;
 pub mod consts;
+pub mod debug;
 pub mod dict_felt_to;
 pub mod drop;
 pub mod duplicate;
@@ -51,8 +52,8 @@ fn get_bool_type(
         EnumType::id(),
         &[
             GenericArg::UserType(UserTypeId::from_string("core::bool")),
-            GenericArg::Type(unit_type.clone()),
-            GenericArg::Type(unit_type),
+            GenericArg::ty(unit_type.clone()),
+            GenericArg::ty(unit_type),
         ],
     )
 }
@@ -1,33 +1,22 @@
 use super::felt::FeltType;
-use crate::define_libfunc_hierarchy;
 use crate::extensions::lib_func::{
     DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature, SierraApChange,
     SignatureSpecializationContext,
 };
-use crate::extensions::types::{InfoOnlyConcreteType, TypeInfo};
 use crate::extensions::{
-    NamedType, NoGenericArgsGenericLibFunc, NoGenericArgsGenericType, OutputVarReferenceInfo,
-    SpecializationError,
+    NamedType, NoGenericArgsGenericLibFunc, OutputVarReferenceInfo, SpecializationError,
 };
-use crate::ids::{GenericLibFuncId, GenericTypeId};
+use crate::ids::GenericLibFuncId;
+use crate::{define_info_only_type, define_libfunc_hierarchy};
 
-/// Type representing the Pedersen hash builtin.
-#[derive(Default)]
-pub struct PedersenType {}
-impl NoGenericArgsGenericType for PedersenType {
-    type Concrete = InfoOnlyConcreteType;
-    const ID: GenericTypeId = GenericTypeId::new_inline("Pedersen");
-
-    fn specialize(&self) -> Self::Concrete {
-        InfoOnlyConcreteType {
-            info: TypeInfo {
-                long_id: Self::concrete_type_long_id(&[]),
-                storable: true,
-                droppable: false,
-                duplicatable: false,
-                size: 1,
-            },
-        }
+define_info_only_type! {
+    /// Type representing the Pedersen hash builtin.
+    pub struct PedersenType {
+        id: "Pedersen",
+        storable: true,
+        droppable: false,
+        duplicatable: false,
+        size: 1,
     }
 }
 
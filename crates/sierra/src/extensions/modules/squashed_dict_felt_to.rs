@@ -32,7 +32,7 @@ impl NamedType for SquashedDictFeltToType {
                 ty,
             })
         } else {
-            Err(SpecializationError::UnsupportedGenericArg)
+            Err(SpecializationError::UnsupportedGenericArg { index: 0 })
         }
     }
 }
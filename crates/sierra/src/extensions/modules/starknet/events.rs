@@ -0,0 +1,92 @@
+use super::syscalls::SystemType;
+use crate::extensions::array::ArrayType;
+use crate::extensions::felt::FeltType;
+use crate::extensions::gas::GasBuiltinType;
+use crate::extensions::lib_func::{
+    BranchSignature, DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature,
+    SierraApChange, SignatureSpecializationContext,
+};
+use crate::extensions::{
+    NamedType, NoGenericArgsGenericLibFunc, OutputVarReferenceInfo, SpecializationError,
+};
+use crate::ids::GenericLibFuncId;
+use crate::program::GenericArg;
+
+/// LibFunc for an emit event system call.
+#[derive(Default)]
+pub struct EmitEventLibFunc {}
+impl NoGenericArgsGenericLibFunc for EmitEventLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("emit_event_syscall");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let gas_builtin_ty = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+        let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
+        let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+        let felt_array_ty =
+            context.get_concrete_type(ArrayType::id(), &[GenericArg::ty(felt_ty.clone())])?;
+        Ok(LibFuncSignature {
+            param_signatures: vec![
+                // Gas builtin
+                ParamSignature::new(gas_builtin_ty.clone()),
+                // System
+                ParamSignature {
+                    ty: system_ty.clone(),
+                    allow_deferred: false,
+                    allow_add_const: true,
+                    allow_const: false,
+                },
+                // Keys
+                ParamSignature::new(felt_array_ty.clone()),
+                // Data
+                ParamSignature::new(felt_array_ty),
+            ],
+            branch_signatures: vec![
+                // Success branch
+                BranchSignature {
+                    vars: vec![
+                        // Gas builtin
+                        OutputVarInfo {
+                            ty: gas_builtin_ty.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                        },
+                        // System
+                        OutputVarInfo {
+                            ty: system_ty.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 1 },
+                            ),
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+                // Failure branch
+                BranchSignature {
+                    vars: vec![
+                        // Gas builtin
+                        OutputVarInfo {
+                            ty: gas_builtin_ty,
+                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                        },
+                        // System
+                        OutputVarInfo {
+                            ty: system_ty,
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 1 },
+                            ),
+                        },
+                        // Revert reason
+                        OutputVarInfo {
+                            ty: felt_ty,
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+            ],
+            fallthrough: Some(0),
+        })
+    }
+}
@@ -1,37 +1,39 @@
+use num_bigint::BigInt;
+
+use super::selector_value;
 use super::syscalls::SystemType;
+use crate::define_info_only_type;
 use crate::extensions::array::ArrayType;
-use crate::extensions::consts::{ConstGenLibFunc, WrapConstGenLibFunc};
+use crate::extensions::consts::{
+    ConstGenLibFunc, SignatureAndConstConcreteLibFunc, WrapConstGenLibFunc,
+};
 use crate::extensions::felt::FeltType;
 use crate::extensions::gas::GasBuiltinType;
 use crate::extensions::lib_func::{
     BranchSignature, DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature,
-    SierraApChange, SignatureSpecializationContext,
+    SierraApChange, SignatureSpecializationContext, SpecializationContext,
 };
-use crate::extensions::types::{InfoOnlyConcreteType, TypeInfo};
+use crate::extensions::range_check::RangeCheckType;
 use crate::extensions::{
-    NamedType, NoGenericArgsGenericLibFunc, NoGenericArgsGenericType, OutputVarReferenceInfo,
-    SpecializationError,
+    NamedLibFunc, NamedType, NoGenericArgsGenericLibFunc, NoGenericArgsGenericType,
+    OutputVarReferenceInfo, SpecializationError,
 };
-use crate::ids::{GenericLibFuncId, GenericTypeId};
+use crate::ids::{ConcreteTypeId, GenericLibFuncId, GenericTypeId};
 use crate::program::GenericArg;
 
-/// Type for StarkNet storage address, a value in the range [0, 2 ** 250).
-#[derive(Default)]
-pub struct ContractAddressType {}
-impl NoGenericArgsGenericType for ContractAddressType {
-    type Concrete = InfoOnlyConcreteType;
-    const ID: GenericTypeId = GenericTypeId::new_inline("ContractAddress");
-
-    fn specialize(&self) -> Self::Concrete {
-        InfoOnlyConcreteType {
-            info: TypeInfo {
-                long_id: Self::concrete_type_long_id(&[]),
-                storable: true,
-                droppable: true,
-                duplicatable: true,
-                size: 1,
-            },
-        }
+/// The gas charged per word of the call data of the syscalls calling a contract or a library or
+/// deploying a contract. The charge is deducted at runtime, from the gas counter passed to the
+/// syscall, so it is not part of the costs of the libfuncs.
+pub const CALL_DATA_WORD_GAS: i64 = 5;
+
+define_info_only_type! {
+    /// Type for StarkNet storage address, a value in the range [0, 2 ** 250).
+    pub struct ContractAddressType {
+        id: "ContractAddress",
+        storable: true,
+        droppable: true,
+        duplicatable: true,
+        size: 1,
     }
 }
 
@@ -41,15 +43,245 @@ pub struct ContractAddressConstLibFuncWrapped {}
 impl ConstGenLibFunc for ContractAddressConstLibFuncWrapped {
     const ID: GenericLibFuncId = GenericLibFuncId::new_inline("contract_address_const");
     const GENERIC_TYPE_ID: GenericTypeId = <ContractAddressType as NoGenericArgsGenericType>::ID;
+
+    fn is_valid_const(c: &BigInt) -> bool {
+        *c < BigInt::from(1) << 251
+    }
 }
 
 pub type ContractAddressConstLibFunc = WrapConstGenLibFunc<ContractAddressConstLibFuncWrapped>;
 
-/// LibFunc for a storage call contract system call.
+/// LibFunc for converting a contract address into a felt.
+#[derive(Default)]
+pub struct ContractAddressToFeltLibFunc {}
+impl NoGenericArgsGenericLibFunc for ContractAddressToFeltLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("contract_addr_to_felt");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        Ok(LibFuncSignature::new_non_branch(
+            vec![context.get_concrete_type(ContractAddressType::id(), &[])?],
+            vec![OutputVarInfo {
+                ty: context.get_concrete_type(FeltType::id(), &[])?,
+                ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 0 },
+            }],
+            SierraApChange::Known { new_vars_only: true },
+        ))
+    }
+}
+
+/// LibFunc for converting a felt into a contract address, or failing if the felt is not in the
+/// range [0, 2 ** 251).
+#[derive(Default)]
+pub struct ContractAddressTryFromFeltLibFunc {}
+impl NoGenericArgsGenericLibFunc for ContractAddressTryFromFeltLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("contract_addr_from_felt");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let range_check_type = context.get_concrete_type(RangeCheckType::id(), &[])?;
+        Ok(LibFuncSignature {
+            param_signatures: vec![
+                ParamSignature {
+                    ty: range_check_type.clone(),
+                    allow_deferred: false,
+                    allow_add_const: true,
+                    allow_const: false,
+                },
+                ParamSignature::new(context.get_concrete_type(FeltType::id(), &[])?),
+            ],
+            branch_signatures: vec![
+                // Success branch
+                BranchSignature {
+                    vars: vec![
+                        OutputVarInfo {
+                            ty: range_check_type.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 0 },
+                            ),
+                        },
+                        OutputVarInfo {
+                            ty: context.get_concrete_type(ContractAddressType::id(), &[])?,
+                            ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 1 },
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+                // Failure branch
+                BranchSignature {
+                    vars: vec![OutputVarInfo {
+                        ty: range_check_type,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                            param_idx: 0,
+                        }),
+                    }],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+            ],
+            fallthrough: Some(0),
+        })
+    }
+}
+
+/// Returns the signature of a system call that calls a function of `target_ty` - the address of the
+/// called contract, or the hash of its class - with the call data array, and returns its result
+/// array.
+fn call_signature(
+    context: &dyn SignatureSpecializationContext,
+    target_ty: ConcreteTypeId,
+) -> Result<LibFuncSignature, SpecializationError> {
+    let gas_builtin_ty = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+    let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
+    let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+    let felt_array_ty =
+        context.get_concrete_type(ArrayType::id(), &[GenericArg::ty(felt_ty.clone())])?;
+    Ok(LibFuncSignature {
+        param_signatures: vec![
+            // Gas builtin
+            ParamSignature::new(gas_builtin_ty.clone()),
+            // System
+            ParamSignature {
+                ty: system_ty.clone(),
+                allow_deferred: false,
+                allow_add_const: true,
+                allow_const: false,
+            },
+            // Target address or class hash
+            ParamSignature::new(target_ty),
+            // Call data
+            ParamSignature::new(felt_array_ty.clone()),
+        ],
+        branch_signatures: vec![
+            // Success branch
+            BranchSignature {
+                vars: vec![
+                    // Gas builtin
+                    OutputVarInfo {
+                        ty: gas_builtin_ty.clone(),
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                    },
+                    // System
+                    OutputVarInfo {
+                        ty: system_ty.clone(),
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                            param_idx: 1,
+                        }),
+                    },
+                    // result
+                    OutputVarInfo {
+                        ty: felt_array_ty.clone(),
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                    },
+                ],
+                ap_change: SierraApChange::Known { new_vars_only: false },
+            },
+            BranchSignature {
+                vars: vec![
+                    // Gas builtin
+                    OutputVarInfo {
+                        ty: gas_builtin_ty,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                    },
+                    // System
+                    OutputVarInfo {
+                        ty: system_ty,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                            param_idx: 1,
+                        }),
+                    },
+                    // Revert reason
+                    OutputVarInfo {
+                        ty: felt_ty,
+                        ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                    },
+                    // result
+                    OutputVarInfo {
+                        ty: felt_array_ty,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                    },
+                ],
+                ap_change: SierraApChange::Known { new_vars_only: false },
+            },
+        ],
+        fallthrough: Some(0),
+    })
+}
+
+/// Trait for implementing a library function calling a function of a contract or a class through a
+/// system call - with the selector of the system call as the const of the concrete library
+/// function.
+pub trait CallSyscallGenLibFunc: Default {
+    /// The library function id.
+    const ID: GenericLibFuncId;
+    /// The name of the system call, encoded into its selector by [selector_value].
+    const SELECTOR: &'static str;
+    /// The id of the generic type of the call target.
+    const TARGET_TYPE_ID: GenericTypeId;
+}
+
+/// Wrapper to prevent implementation collisions for `NamedLibFunc`.
 #[derive(Default)]
-pub struct CallContractLibFunc {}
-impl NoGenericArgsGenericLibFunc for CallContractLibFunc {
+pub struct WrapCallSyscallGenLibFunc<T: CallSyscallGenLibFunc>(T);
+
+impl<T: CallSyscallGenLibFunc> NamedLibFunc for WrapCallSyscallGenLibFunc<T> {
+    const ID: GenericLibFuncId = <T as CallSyscallGenLibFunc>::ID;
+    type Concrete = SignatureAndConstConcreteLibFunc;
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+        args: &[GenericArg],
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        if !args.is_empty() {
+            return Err(SpecializationError::WrongNumberOfGenericArgs);
+        }
+        call_signature(context, context.get_concrete_type(T::TARGET_TYPE_ID, &[])?)
+    }
+
+    fn specialize(
+        &self,
+        context: &dyn SpecializationContext,
+        args: &[GenericArg],
+    ) -> Result<Self::Concrete, SpecializationError> {
+        Ok(SignatureAndConstConcreteLibFunc {
+            c: selector_value(T::SELECTOR),
+            signature: <Self as NamedLibFunc>::specialize_signature(self, context.upcast(), args)?,
+        })
+    }
+}
+
+/// LibFunc for a call contract system call.
+#[derive(Default)]
+pub struct CallContractLibFuncWrapped {}
+impl CallSyscallGenLibFunc for CallContractLibFuncWrapped {
     const ID: GenericLibFuncId = GenericLibFuncId::new_inline("call_contract_syscall");
+    const SELECTOR: &'static str = "call_contract";
+    const TARGET_TYPE_ID: GenericTypeId = <ContractAddressType as NoGenericArgsGenericType>::ID;
+}
+
+pub type CallContractLibFunc = WrapCallSyscallGenLibFunc<CallContractLibFuncWrapped>;
+
+/// LibFunc for a library call system call, running a function of a declared class in the context of
+/// the calling contract.
+#[derive(Default)]
+pub struct LibraryCallLibFuncWrapped {}
+impl CallSyscallGenLibFunc for LibraryCallLibFuncWrapped {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("library_call_syscall");
+    const SELECTOR: &'static str = "library_call";
+    const TARGET_TYPE_ID: GenericTypeId = <FeltType as NoGenericArgsGenericType>::ID;
+}
+
+pub type LibraryCallLibFunc = WrapCallSyscallGenLibFunc<LibraryCallLibFuncWrapped>;
+
+/// LibFunc for a deploy system call.
+#[derive(Default)]
+pub struct DeployLibFunc {}
+impl NoGenericArgsGenericLibFunc for DeployLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("deploy_syscall");
 
     fn specialize_signature(
         &self,
@@ -60,7 +292,7 @@ impl NoGenericArgsGenericLibFunc for CallContractLibFunc {
         let addr_ty = context.get_concrete_type(ContractAddressType::id(), &[])?;
         let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
         let felt_array_ty =
-            context.get_concrete_type(ArrayType::id(), &[GenericArg::Type(felt_ty.clone())])?;
+            context.get_concrete_type(ArrayType::id(), &[GenericArg::ty(felt_ty.clone())])?;
         Ok(LibFuncSignature {
             param_signatures: vec![
                 // Gas builtin
@@ -72,9 +304,11 @@ impl NoGenericArgsGenericLibFunc for CallContractLibFunc {
                     allow_add_const: true,
                     allow_const: false,
                 },
-                // Address
-                ParamSignature::new(addr_ty),
-                // Call data
+                // Class hash
+                ParamSignature::new(felt_ty.clone()),
+                // Contract address salt
+                ParamSignature::new(felt_ty.clone()),
+                // Constructor call data
                 ParamSignature::new(felt_array_ty.clone()),
             ],
             branch_signatures: vec![
@@ -93,7 +327,12 @@ impl NoGenericArgsGenericLibFunc for CallContractLibFunc {
                                 DeferredOutputKind::AddConst { param_idx: 1 },
                             ),
                         },
-                        // result
+                        // Deployed contract address
+                        OutputVarInfo {
+                            ty: addr_ty,
+                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                        },
+                        // Constructor result
                         OutputVarInfo {
                             ty: felt_array_ty.clone(),
                             ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
@@ -101,6 +340,7 @@ impl NoGenericArgsGenericLibFunc for CallContractLibFunc {
                     ],
                     ap_change: SierraApChange::Known { new_vars_only: false },
                 },
+                // Failure branch
                 BranchSignature {
                     vars: vec![
                         // Gas builtin
@@ -120,7 +360,7 @@ impl NoGenericArgsGenericLibFunc for CallContractLibFunc {
                             ty: felt_ty,
                             ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
                         },
-                        // result
+                        // Constructor result
                         OutputVarInfo {
                             ty: felt_array_ty,
                             ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
@@ -133,3 +373,82 @@ impl NoGenericArgsGenericLibFunc for CallContractLibFunc {
         })
     }
 }
+
+/// LibFunc for a send message to L1 system call.
+#[derive(Default)]
+pub struct SendMessageToL1LibFunc {}
+impl NoGenericArgsGenericLibFunc for SendMessageToL1LibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("send_message_to_l1");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let gas_builtin_ty = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+        let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
+        let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+        let felt_array_ty =
+            context.get_concrete_type(ArrayType::id(), &[GenericArg::ty(felt_ty.clone())])?;
+        Ok(LibFuncSignature {
+            param_signatures: vec![
+                // Gas builtin
+                ParamSignature::new(gas_builtin_ty.clone()),
+                // System
+                ParamSignature {
+                    ty: system_ty.clone(),
+                    allow_deferred: false,
+                    allow_add_const: true,
+                    allow_const: false,
+                },
+                // L1 address
+                ParamSignature::new(felt_ty.clone()),
+                // Payload
+                ParamSignature::new(felt_array_ty),
+            ],
+            branch_signatures: vec![
+                // Success branch
+                BranchSignature {
+                    vars: vec![
+                        // Gas builtin
+                        OutputVarInfo {
+                            ty: gas_builtin_ty.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                        },
+                        // System
+                        OutputVarInfo {
+                            ty: system_ty.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 1 },
+                            ),
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+                // Failure branch
+                BranchSignature {
+                    vars: vec![
+                        // Gas builtin
+                        OutputVarInfo {
+                            ty: gas_builtin_ty,
+                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                        },
+                        // System
+                        OutputVarInfo {
+                            ty: system_ty,
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 1 },
+                            ),
+                        },
+                        // Revert reason
+                        OutputVarInfo {
+                            ty: felt_ty,
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+            ],
+            fallthrough: Some(0),
+        })
+    }
+}
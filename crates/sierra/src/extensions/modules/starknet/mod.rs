@@ -1,30 +1,89 @@
+use num_bigint::BigInt;
+use utils::short_string;
+
 use crate::{define_libfunc_hierarchy, define_type_hierarchy};
 
 pub mod storage;
 use storage::{
-    StorageAddressConstLibFunc, StorageAddressType, StorageReadLibFunc, StorageWriteLibFunc,
+    StorageAddressConstLibFunc, StorageAddressFromBaseAndOffsetLibFunc, StorageAddressType,
+    StorageBaseAddressConstLibFunc, StorageBaseAddressFromFeltLibFunc, StorageBaseAddressType,
+    StorageReadCheckedLibFunc, StorageReadLibFunc, StorageReadMultiLibFunc,
+    StorageReadUncheckedLibFunc, StorageWriteCheckedLibFunc, StorageWriteLibFunc,
+    StorageWriteUncheckedLibFunc,
 };
 
 mod syscalls;
-use syscalls::SystemType;
+use syscalls::{
+    GetBlockNumberLibFunc, GetBlockTimestampLibFunc, GetCallerAddressLibFunc,
+    GetContractAddressLibFunc, GetSequencerAddressLibFunc, GetTxInfoLibFunc, KeccakLibFunc,
+    ReplaceClassLibFunc, SystemType, TxInfoType,
+};
 
 pub mod interoperability;
-use interoperability::{CallContractLibFunc, ContractAddressConstLibFunc, ContractAddressType};
+use interoperability::{
+    CallContractLibFunc, ContractAddressConstLibFunc, ContractAddressToFeltLibFunc,
+    ContractAddressTryFromFeltLibFunc, ContractAddressType, DeployLibFunc, LibraryCallLibFunc,
+    SendMessageToL1LibFunc,
+};
+
+pub mod events;
+use events::EmitEventLibFunc;
+
+pub mod secp256k1;
+use secp256k1::{
+    Secp256k1AddLibFunc, Secp256k1GetXyLibFunc, Secp256k1MulLibFunc, Secp256k1NewLibFunc,
+    Secp256k1PointType,
+};
+
+/// Returns the value of the selector cell of the system call with the given name - its name encoded
+/// as a little-endian short string.
+pub fn selector_value(name: &str) -> BigInt {
+    short_string::from_str_le(name).expect("Syscall names are valid short strings.")
+}
 
 define_type_hierarchy! {
     pub enum StarkNetType {
         ContractAddress(ContractAddressType),
         StorageAddress(StorageAddressType),
+        StorageBaseAddress(StorageBaseAddressType),
+        Secp256k1Point(Secp256k1PointType),
         System(SystemType),
+        TxInfo(TxInfoType),
     }, StarkNetTypeConcrete
 }
 
 define_libfunc_hierarchy! {
     pub enum StarkNetLibFunc {
          CallContract(CallContractLibFunc),
+         Deploy(DeployLibFunc),
+         LibraryCall(LibraryCallLibFunc),
+         SendMessageToL1(SendMessageToL1LibFunc),
          ContractAddressConst(ContractAddressConstLibFunc),
+         ContractAddressToFelt(ContractAddressToFeltLibFunc),
+         ContractAddressTryFromFelt(ContractAddressTryFromFeltLibFunc),
+         EmitEvent(EmitEventLibFunc),
+         GetCallerAddress(GetCallerAddressLibFunc),
+         GetContractAddress(GetContractAddressLibFunc),
+         GetSequencerAddress(GetSequencerAddressLibFunc),
+         GetBlockNumber(GetBlockNumberLibFunc),
+         GetBlockTimestamp(GetBlockTimestampLibFunc),
+         GetTxInfo(GetTxInfoLibFunc),
+         ReplaceClass(ReplaceClassLibFunc),
+         Keccak(KeccakLibFunc),
+         Secp256k1New(Secp256k1NewLibFunc),
+         Secp256k1Add(Secp256k1AddLibFunc),
+         Secp256k1Mul(Secp256k1MulLibFunc),
+         Secp256k1GetXy(Secp256k1GetXyLibFunc),
          StorageRead(StorageReadLibFunc),
          StorageWrite(StorageWriteLibFunc),
+         StorageReadChecked(StorageReadCheckedLibFunc),
+         StorageWriteChecked(StorageWriteCheckedLibFunc),
+         StorageReadUnchecked(StorageReadUncheckedLibFunc),
+         StorageReadMulti(StorageReadMultiLibFunc),
+         StorageWriteUnchecked(StorageWriteUncheckedLibFunc),
          StorageAddressConst(StorageAddressConstLibFunc),
+         StorageBaseAddressConst(StorageBaseAddressConstLibFunc),
+         StorageBaseAddressFromFelt(StorageBaseAddressFromFeltLibFunc),
+         StorageAddressFromBaseAndOffset(StorageAddressFromBaseAndOffsetLibFunc),
     }, StarkNetConcreteLibFunc
 }
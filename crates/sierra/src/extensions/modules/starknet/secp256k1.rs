@@ -0,0 +1,187 @@
+use super::syscalls::SystemType;
+use crate::define_info_only_type;
+use crate::extensions::felt::FeltType;
+use crate::extensions::gas::GasBuiltinType;
+use crate::extensions::lib_func::{
+    BranchSignature, DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature,
+    SierraApChange, SignatureSpecializationContext,
+};
+use crate::extensions::uint128::Uint128Type;
+use crate::extensions::{
+    NamedType, NoGenericArgsGenericLibFunc, OutputVarReferenceInfo, SpecializationError,
+};
+use crate::ids::{ConcreteTypeId, GenericLibFuncId};
+
+define_info_only_type! {
+    /// Type for a point on the secp256k1 curve.
+    ///
+    /// Represented by a single cell pointing to the point in the memory of the syscall handler. As
+    /// the pointed point is never modified, the type may be freely dropped and duplicated.
+    pub struct Secp256k1PointType {
+        id: "Secp256k1Point",
+        storable: true,
+        droppable: true,
+        duplicatable: true,
+        size: 1,
+    }
+}
+
+/// Returns the signature of a secp256k1 system call, taking the gas builtin, the system and
+/// `param_tys`, and returning new temporary variables of `result_tys` on success.
+fn secp256k1_syscall_signature(
+    context: &dyn SignatureSpecializationContext,
+    param_tys: Vec<ConcreteTypeId>,
+    result_tys: Vec<ConcreteTypeId>,
+) -> Result<LibFuncSignature, SpecializationError> {
+    let gas_builtin_ty = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+    let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
+    let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+    let builtin_outputs = || {
+        [
+            // Gas builtin
+            OutputVarInfo {
+                ty: gas_builtin_ty.clone(),
+                ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+            },
+            // System
+            OutputVarInfo {
+                ty: system_ty.clone(),
+                ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                    param_idx: 1,
+                }),
+            },
+        ]
+    };
+    Ok(LibFuncSignature {
+        param_signatures: [
+            // Gas builtin
+            ParamSignature::new(gas_builtin_ty.clone()),
+            // System
+            ParamSignature {
+                ty: system_ty.clone(),
+                allow_deferred: false,
+                allow_add_const: true,
+                allow_const: false,
+            },
+        ]
+        .into_iter()
+        .chain(param_tys.into_iter().map(ParamSignature::new))
+        .collect(),
+        branch_signatures: vec![
+            // Success branch
+            BranchSignature {
+                vars: builtin_outputs()
+                    .into_iter()
+                    .chain(result_tys.into_iter().enumerate().map(|(idx, ty)| OutputVarInfo {
+                        ty,
+                        ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(idx) },
+                    }))
+                    .collect(),
+                ap_change: SierraApChange::Known { new_vars_only: false },
+            },
+            // Failure branch
+            BranchSignature {
+                vars: builtin_outputs()
+                    .into_iter()
+                    .chain([
+                        // Revert reason
+                        OutputVarInfo {
+                            ty: felt_ty,
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                        },
+                    ])
+                    .collect(),
+                ap_change: SierraApChange::Known { new_vars_only: false },
+            },
+        ],
+        fallthrough: Some(0),
+    })
+}
+
+/// LibFunc for creating a secp256k1 point from its coordinates, given as the low and high 128 bits
+/// of each.
+///
+/// On success, returns a flag that is nonzero if the coordinates are of a point on the curve,
+/// followed by the point, which must not be used if the flag is zero.
+#[derive(Default)]
+pub struct Secp256k1NewLibFunc {}
+impl NoGenericArgsGenericLibFunc for Secp256k1NewLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("secp256k1_new");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let u128_ty = context.get_concrete_type(Uint128Type::id(), &[])?;
+        let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+        let point_ty = context.get_concrete_type(Secp256k1PointType::id(), &[])?;
+        secp256k1_syscall_signature(
+            context,
+            // x low, x high, y low, y high
+            vec![u128_ty.clone(), u128_ty.clone(), u128_ty.clone(), u128_ty],
+            // Is on curve, point
+            vec![felt_ty, point_ty],
+        )
+    }
+}
+
+/// LibFunc for adding two secp256k1 points.
+#[derive(Default)]
+pub struct Secp256k1AddLibFunc {}
+impl NoGenericArgsGenericLibFunc for Secp256k1AddLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("secp256k1_add");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let point_ty = context.get_concrete_type(Secp256k1PointType::id(), &[])?;
+        secp256k1_syscall_signature(
+            context,
+            vec![point_ty.clone(), point_ty.clone()],
+            vec![point_ty],
+        )
+    }
+}
+
+/// LibFunc for multiplying a secp256k1 point by a scalar, given as its low and high 128 bits.
+#[derive(Default)]
+pub struct Secp256k1MulLibFunc {}
+impl NoGenericArgsGenericLibFunc for Secp256k1MulLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("secp256k1_mul");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let u128_ty = context.get_concrete_type(Uint128Type::id(), &[])?;
+        let point_ty = context.get_concrete_type(Secp256k1PointType::id(), &[])?;
+        secp256k1_syscall_signature(
+            context,
+            // Point, scalar low, scalar high
+            vec![point_ty.clone(), u128_ty.clone(), u128_ty],
+            vec![point_ty],
+        )
+    }
+}
+
+/// LibFunc for getting the coordinates of a secp256k1 point, as the low and high 128 bits of each.
+#[derive(Default)]
+pub struct Secp256k1GetXyLibFunc {}
+impl NoGenericArgsGenericLibFunc for Secp256k1GetXyLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("secp256k1_get_xy");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let u128_ty = context.get_concrete_type(Uint128Type::id(), &[])?;
+        let point_ty = context.get_concrete_type(Secp256k1PointType::id(), &[])?;
+        secp256k1_syscall_signature(
+            context,
+            vec![point_ty],
+            // x low, x high, y low, y high
+            vec![u128_ty.clone(), u128_ty.clone(), u128_ty.clone(), u128_ty],
+        )
+    }
+}
@@ -1,35 +1,34 @@
+use num_bigint::BigInt;
+use num_traits::Signed;
+
 use super::syscalls::SystemType;
+use crate::define_info_only_type;
 use crate::extensions::consts::{ConstGenLibFunc, WrapConstGenLibFunc};
 use crate::extensions::felt::FeltType;
 use crate::extensions::gas::GasBuiltinType;
 use crate::extensions::lib_func::{
     BranchSignature, DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature,
-    SierraApChange, SignatureSpecializationContext,
+    SierraApChange, SignatureBasedConcreteLibFunc, SignatureSpecializationContext,
+    SpecializationContext,
 };
-use crate::extensions::types::{InfoOnlyConcreteType, TypeInfo};
+use crate::extensions::range_check::RangeCheckType;
+use crate::extensions::strct::StructType;
+use crate::extensions::type_specialization_context::TypeSpecializationContext;
 use crate::extensions::{
-    NamedType, NoGenericArgsGenericLibFunc, NoGenericArgsGenericType, OutputVarReferenceInfo,
-    SpecializationError,
+    NamedLibFunc, NamedType, NoGenericArgsGenericLibFunc, NoGenericArgsGenericType,
+    OutputVarReferenceInfo, SpecializationError,
 };
-use crate::ids::{GenericLibFuncId, GenericTypeId};
+use crate::ids::{ConcreteTypeId, GenericLibFuncId, GenericTypeId};
+use crate::program::GenericArg;
 
-/// Type for StarkNet storage address, a value in the range [0, 2 ** 251 - 256).
-#[derive(Default)]
-pub struct StorageAddressType {}
-impl NoGenericArgsGenericType for StorageAddressType {
-    type Concrete = InfoOnlyConcreteType;
-    const ID: GenericTypeId = GenericTypeId::new_inline("StorageAddress");
-
-    fn specialize(&self) -> Self::Concrete {
-        InfoOnlyConcreteType {
-            info: TypeInfo {
-                long_id: Self::concrete_type_long_id(&[]),
-                storable: true,
-                droppable: true,
-                duplicatable: true,
-                size: 1,
-            },
-        }
+define_info_only_type! {
+    /// Type for StarkNet storage address, a value in the range [0, 2 ** 251 - 256).
+    pub struct StorageAddressType {
+        id: "StorageAddress",
+        storable: true,
+        droppable: true,
+        duplicatable: true,
+        size: 1,
     }
 }
 
@@ -43,39 +42,66 @@ impl ConstGenLibFunc for StorageAddressConstLibFuncWrapped {
 
 pub type StorageAddressConstLibFunc = WrapConstGenLibFunc<StorageAddressConstLibFuncWrapped>;
 
-/// LibFunc for a storage read system call.
+define_info_only_type! {
+    /// Type for StarkNet storage base address, a value in the range [0, 2 ** 251 - 256).
+    /// Storage variables are laid out at small offsets from their base address, so these offsets
+    /// can not overflow the storage address range.
+    pub struct StorageBaseAddressType {
+        id: "StorageBaseAddress",
+        storable: true,
+        droppable: true,
+        duplicatable: true,
+        size: 1,
+    }
+}
+
+/// LibFunc for creating a constant storage base address.
 #[derive(Default)]
-pub struct StorageReadLibFunc {}
-impl NoGenericArgsGenericLibFunc for StorageReadLibFunc {
-    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("storage_read_syscall");
+pub struct StorageBaseAddressConstLibFuncWrapped {}
+impl ConstGenLibFunc for StorageBaseAddressConstLibFuncWrapped {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("storage_base_addr_const");
+    const GENERIC_TYPE_ID: GenericTypeId = <StorageBaseAddressType as NoGenericArgsGenericType>::ID;
+
+    fn is_valid_const(c: &BigInt) -> bool {
+        !c.is_negative() && *c < (BigInt::from(1) << 251) - 256
+    }
+}
+
+pub type StorageBaseAddressConstLibFunc =
+    WrapConstGenLibFunc<StorageBaseAddressConstLibFuncWrapped>;
+
+/// LibFunc for converting a felt into a storage base address, by reducing it modulo
+/// `2 ** 251 - 256`.
+#[derive(Default)]
+pub struct StorageBaseAddressFromFeltLibFunc {}
+impl NoGenericArgsGenericLibFunc for StorageBaseAddressFromFeltLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("storage_base_from_felt");
 
     fn specialize_signature(
         &self,
         context: &dyn SignatureSpecializationContext,
     ) -> Result<LibFuncSignature, SpecializationError> {
-        let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
-        let addr_ty = context.get_concrete_type(StorageAddressType::id(), &[])?;
-        let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+        let range_check_type = context.get_concrete_type(RangeCheckType::id(), &[])?;
         Ok(LibFuncSignature::new_non_branch_ex(
             vec![
                 ParamSignature {
-                    ty: system_ty.clone(),
+                    ty: range_check_type.clone(),
                     allow_deferred: false,
                     allow_add_const: true,
                     allow_const: false,
                 },
-                ParamSignature::new(addr_ty),
+                ParamSignature::new(context.get_concrete_type(FeltType::id(), &[])?),
             ],
             vec![
                 OutputVarInfo {
-                    ty: system_ty,
+                    ty: range_check_type,
                     ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
                         param_idx: 0,
                     }),
                 },
                 OutputVarInfo {
-                    ty: felt_ty,
-                    ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                    ty: context.get_concrete_type(StorageBaseAddressType::id(), &[])?,
+                    ref_info: OutputVarReferenceInfo::NewTempVar { idx: None },
                 },
             ],
             SierraApChange::Known { new_vars_only: false },
@@ -83,12 +109,457 @@ impl NoGenericArgsGenericLibFunc for StorageReadLibFunc {
     }
 }
 
+/// LibFunc for computing the storage address at an offset from a storage base address.
+/// The offset is expected to be below 256, so the address is within the storage address range.
+#[derive(Default)]
+pub struct StorageAddressFromBaseAndOffsetLibFunc {}
+impl NoGenericArgsGenericLibFunc for StorageAddressFromBaseAndOffsetLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("storage_addr_from_base");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        Ok(LibFuncSignature::new_non_branch_ex(
+            vec![
+                // Base address
+                ParamSignature::new(context.get_concrete_type(StorageBaseAddressType::id(), &[])?),
+                // Offset
+                ParamSignature {
+                    ty: context.get_concrete_type(FeltType::id(), &[])?,
+                    allow_deferred: false,
+                    allow_add_const: false,
+                    allow_const: true,
+                },
+            ],
+            vec![OutputVarInfo {
+                ty: context.get_concrete_type(StorageAddressType::id(), &[])?,
+                ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+            }],
+            SierraApChange::Known { new_vars_only: true },
+        ))
+    }
+}
+
+/// LibFunc for a storage read system call.
+#[derive(Default)]
+pub struct StorageReadLibFunc {}
+impl NoGenericArgsGenericLibFunc for StorageReadLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("storage_read_syscall");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        storage_read_signature(context)
+    }
+}
+
+/// LibFunc for a storage read system call, for trusted contracts only.
+/// Unlike [StorageReadLibFunc], does not branch on the revert reason of the syscall, and may only
+/// be used by functions the compiler is configured to allow.
+#[derive(Default)]
+pub struct StorageReadUncheckedLibFunc {}
+impl NoGenericArgsGenericLibFunc for StorageReadUncheckedLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("storage_read_unchecked");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+        storage_read_value_signature(context, felt_ty)
+    }
+}
+
+/// LibFunc for a storage read system call, checking that the address is within the storage bounds
+/// before the call. Used in place of [StorageReadLibFunc] when the compiler is configured to check
+/// the storage addresses at runtime.
+#[derive(Default)]
+pub struct StorageReadCheckedLibFunc {}
+impl NoGenericArgsGenericLibFunc for StorageReadCheckedLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("storage_read_checked");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        with_range_check(context, storage_read_signature(context)?)
+    }
+}
+
+/// The maximal number of cells read by [StorageReadMultiLibFunc]. Storage addresses are below
+/// `2**251 - 256`, so offsets of up to 255 from a valid address can not overflow the address range.
+pub const STORAGE_READ_MULTI_MAX_SIZE: usize = 256;
+
+/// LibFunc for reading a value of a multi-cell type from storage, with a storage read system call
+/// for each of its cells - read from consecutive addresses starting at the given address.
+/// Branches on the revert reasons of the syscalls - if a read reverts, no further reads are made,
+/// and its index and revert reason are returned.
+/// The read type may only be made of felts, as other types - such as builtins, gas or validated
+/// values - may not be created from arbitrary storage values.
+#[derive(Default)]
+pub struct StorageReadMultiLibFunc {}
+impl StorageReadMultiLibFunc {
+    /// Returns the read type, and its size.
+    fn read_type(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+        args: &[GenericArg],
+    ) -> Result<(ConcreteTypeId, usize), SpecializationError> {
+        match args {
+            [GenericArg::Type(ty)] => {
+                match felts_count(context.as_type_specialization_context(), ty.clone())? {
+                    Some(size) if (1..=STORAGE_READ_MULTI_MAX_SIZE).contains(&size) => {
+                        Ok((ty.clone(), size))
+                    }
+                    _ => Err(SpecializationError::UnsupportedGenericArg { index: 0 }),
+                }
+            }
+            [_] => Err(SpecializationError::UnsupportedGenericArg { index: 0 }),
+            _ => Err(SpecializationError::WrongNumberOfGenericArgs),
+        }
+    }
+}
+impl NamedLibFunc for StorageReadMultiLibFunc {
+    type Concrete = StorageReadMultiConcreteLibFunc;
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("storage_read_multi");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+        args: &[GenericArg],
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let (ty, _) = self.read_type(context, args)?;
+        storage_read_multi_signature(context, ty)
+    }
+
+    fn specialize(
+        &self,
+        context: &dyn SpecializationContext,
+        args: &[GenericArg],
+    ) -> Result<Self::Concrete, SpecializationError> {
+        let (ty, size) = self.read_type(context.upcast(), args)?;
+        Ok(StorageReadMultiConcreteLibFunc {
+            size,
+            signature: storage_read_multi_signature(context.upcast(), ty)?,
+        })
+    }
+}
+
+pub struct StorageReadMultiConcreteLibFunc {
+    /// The number of cells read.
+    pub size: usize,
+    pub signature: LibFuncSignature,
+}
+impl SignatureBasedConcreteLibFunc for StorageReadMultiConcreteLibFunc {
+    fn signature(&self) -> &LibFuncSignature {
+        &self.signature
+    }
+}
+
+/// Returns the number of felts of `ty`, if it is a felt or a struct made of felts only - and `None`
+/// otherwise.
+fn felts_count(
+    context: &dyn TypeSpecializationContext,
+    ty: ConcreteTypeId,
+) -> Result<Option<usize>, SpecializationError> {
+    let long_id = context.get_type_info(ty)?.long_id;
+    if long_id.generic_id == FeltType::id() {
+        return Ok(Some(1));
+    }
+    if long_id.generic_id != StructType::ID {
+        return Ok(None);
+    }
+    let mut count = 0;
+    // The first generic arg of a struct is its user type, followed by its members.
+    for arg in long_id.generic_args.iter().skip(1) {
+        let GenericArg::Type(member) = arg else {
+            return Ok(None);
+        };
+        let Some(member_count) = felts_count(context, member.clone())? else {
+            return Ok(None);
+        };
+        count += member_count;
+    }
+    Ok(Some(count))
+}
+
+/// Returns the signature of the single felt storage read libfuncs, branching on the revert reason
+/// of the syscall.
+fn storage_read_signature(
+    context: &dyn SignatureSpecializationContext,
+) -> Result<LibFuncSignature, SpecializationError> {
+    let gas_builtin_ty = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+    let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
+    let addr_ty = context.get_concrete_type(StorageAddressType::id(), &[])?;
+    let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+    Ok(LibFuncSignature {
+        param_signatures: vec![
+            // Gas builtin
+            ParamSignature::new(gas_builtin_ty.clone()),
+            // System
+            ParamSignature {
+                ty: system_ty.clone(),
+                allow_deferred: false,
+                allow_add_const: true,
+                allow_const: false,
+            },
+            // Address domain
+            ParamSignature::new(felt_ty.clone()),
+            // Address
+            ParamSignature::new(addr_ty),
+        ],
+        branch_signatures: vec![
+            // Success branch
+            BranchSignature {
+                vars: vec![
+                    // Gas builtin
+                    OutputVarInfo {
+                        ty: gas_builtin_ty.clone(),
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                    },
+                    // System
+                    OutputVarInfo {
+                        ty: system_ty.clone(),
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                            param_idx: 1,
+                        }),
+                    },
+                    // Read value
+                    OutputVarInfo {
+                        ty: felt_ty.clone(),
+                        ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                    },
+                ],
+                ap_change: SierraApChange::Known { new_vars_only: false },
+            },
+            // Failure branch
+            BranchSignature {
+                vars: vec![
+                    // Gas builtin
+                    OutputVarInfo {
+                        ty: gas_builtin_ty,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                    },
+                    // System
+                    OutputVarInfo {
+                        ty: system_ty,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                            param_idx: 1,
+                        }),
+                    },
+                    // Revert reason
+                    OutputVarInfo {
+                        ty: felt_ty,
+                        ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                    },
+                ],
+                ap_change: SierraApChange::Known { new_vars_only: false },
+            },
+        ],
+        fallthrough: Some(0),
+    })
+}
+
+/// Returns the signature of the storage read libfuncs that do not branch on the revert reason of
+/// the syscall, reading a value of type `value_ty`.
+fn storage_read_value_signature(
+    context: &dyn SignatureSpecializationContext,
+    value_ty: ConcreteTypeId,
+) -> Result<LibFuncSignature, SpecializationError> {
+    let gas_builtin_ty = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+    let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
+    let addr_ty = context.get_concrete_type(StorageAddressType::id(), &[])?;
+    let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+    Ok(LibFuncSignature::new_non_branch_ex(
+        vec![
+            // Gas builtin
+            ParamSignature::new(gas_builtin_ty.clone()),
+            // System
+            ParamSignature {
+                ty: system_ty.clone(),
+                allow_deferred: false,
+                allow_add_const: true,
+                allow_const: false,
+            },
+            // Address domain
+            ParamSignature::new(felt_ty),
+            // Address
+            ParamSignature::new(addr_ty),
+        ],
+        vec![
+            // Gas builtin
+            OutputVarInfo {
+                ty: gas_builtin_ty,
+                ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+            },
+            // System
+            OutputVarInfo {
+                ty: system_ty,
+                ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                    param_idx: 1,
+                }),
+            },
+            // Read value
+            OutputVarInfo {
+                ty: value_ty,
+                ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+            },
+        ],
+        SierraApChange::Known { new_vars_only: false },
+    ))
+}
+
+/// Returns the signature of [StorageReadMultiLibFunc], reading a value of type `value_ty`.
+fn storage_read_multi_signature(
+    context: &dyn SignatureSpecializationContext,
+    value_ty: ConcreteTypeId,
+) -> Result<LibFuncSignature, SpecializationError> {
+    let gas_builtin_ty = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+    let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
+    let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+    let mut signature = storage_read_value_signature(context, value_ty)?;
+    // The reads may fail at different positions of the system buffer, so the outputs of the
+    // failure branch are all copied into new temporary variables.
+    signature.branch_signatures.push(BranchSignature {
+        vars: vec![
+            // Gas builtin
+            OutputVarInfo {
+                ty: gas_builtin_ty,
+                ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+            },
+            // System
+            OutputVarInfo {
+                ty: system_ty,
+                ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(1) },
+            },
+            // Index of the failed read
+            OutputVarInfo {
+                ty: felt_ty.clone(),
+                ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(2) },
+            },
+            // Revert reason
+            OutputVarInfo {
+                ty: felt_ty,
+                ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(3) },
+            },
+        ],
+        ap_change: SierraApChange::Known { new_vars_only: false },
+    });
+    Ok(signature)
+}
+
 /// LibFunc for a storage write system call.
 #[derive(Default)]
 pub struct StorageWriteLibFunc {}
 impl NoGenericArgsGenericLibFunc for StorageWriteLibFunc {
     const ID: GenericLibFuncId = GenericLibFuncId::new_inline("storage_write_syscall");
 
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        storage_write_signature(context)
+    }
+}
+
+/// LibFunc for a storage write system call, checking that the address is within the storage bounds
+/// before the call. Used in place of [StorageWriteLibFunc] when the compiler is configured to check
+/// the storage addresses at runtime.
+#[derive(Default)]
+pub struct StorageWriteCheckedLibFunc {}
+impl NoGenericArgsGenericLibFunc for StorageWriteCheckedLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("storage_write_checked");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        with_range_check(context, storage_write_signature(context)?)
+    }
+}
+
+/// Returns the signature of the storage write libfunc.
+fn storage_write_signature(
+    context: &dyn SignatureSpecializationContext,
+) -> Result<LibFuncSignature, SpecializationError> {
+    let gas_builtin_ty = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+    let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
+    let addr_ty = context.get_concrete_type(StorageAddressType::id(), &[])?;
+    let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+    Ok(LibFuncSignature {
+        param_signatures: vec![
+            // Gas builtin
+            ParamSignature::new(gas_builtin_ty.clone()),
+            // System
+            ParamSignature {
+                ty: system_ty.clone(),
+                allow_deferred: false,
+                allow_add_const: true,
+                allow_const: false,
+            },
+            // Address domain
+            ParamSignature::new(felt_ty.clone()),
+            // Address
+            ParamSignature::new(addr_ty),
+            // Value
+            ParamSignature::new(felt_ty.clone()),
+        ],
+        branch_signatures: vec![
+            // Success branch
+            BranchSignature {
+                vars: vec![
+                    // Gas builtin
+                    OutputVarInfo {
+                        ty: gas_builtin_ty.clone(),
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                    },
+                    // System
+                    OutputVarInfo {
+                        ty: system_ty.clone(),
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                            param_idx: 1,
+                        }),
+                    },
+                ],
+                ap_change: SierraApChange::Known { new_vars_only: false },
+            },
+            BranchSignature {
+                vars: vec![
+                    // Gas builtin
+                    OutputVarInfo {
+                        ty: gas_builtin_ty,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                    },
+                    // System
+                    OutputVarInfo {
+                        ty: system_ty,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                            param_idx: 1,
+                        }),
+                    },
+                    // Revert reason
+                    OutputVarInfo {
+                        ty: felt_ty,
+                        ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                    },
+                ],
+                ap_change: SierraApChange::Known { new_vars_only: false },
+            },
+        ],
+        fallthrough: Some(0),
+    })
+}
+
+/// LibFunc for a storage write system call, for trusted contracts only.
+/// Unlike [StorageWriteLibFunc], does not branch on the revert reason of the syscall, and may only
+/// be used by functions the compiler is configured to allow.
+#[derive(Default)]
+pub struct StorageWriteUncheckedLibFunc {}
+impl NoGenericArgsGenericLibFunc for StorageWriteUncheckedLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("storage_write_unchecked");
+
     fn specialize_signature(
         &self,
         context: &dyn SignatureSpecializationContext,
@@ -97,8 +568,8 @@ impl NoGenericArgsGenericLibFunc for StorageWriteLibFunc {
         let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
         let addr_ty = context.get_concrete_type(StorageAddressType::id(), &[])?;
         let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
-        Ok(LibFuncSignature {
-            param_signatures: vec![
+        Ok(LibFuncSignature::new_non_branch_ex(
+            vec![
                 // Gas builtin
                 ParamSignature::new(gas_builtin_ty.clone()),
                 // System
@@ -108,54 +579,63 @@ impl NoGenericArgsGenericLibFunc for StorageWriteLibFunc {
                     allow_add_const: true,
                     allow_const: false,
                 },
+                // Address domain
+                ParamSignature::new(felt_ty.clone()),
                 // Address
                 ParamSignature::new(addr_ty),
                 // Value
-                ParamSignature::new(felt_ty.clone()),
+                ParamSignature::new(felt_ty),
             ],
-            branch_signatures: vec![
-                // Success branch
-                BranchSignature {
-                    vars: vec![
-                        // Gas builtin
-                        OutputVarInfo {
-                            ty: gas_builtin_ty.clone(),
-                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
-                        },
-                        // System
-                        OutputVarInfo {
-                            ty: system_ty.clone(),
-                            ref_info: OutputVarReferenceInfo::Deferred(
-                                DeferredOutputKind::AddConst { param_idx: 1 },
-                            ),
-                        },
-                    ],
-                    ap_change: SierraApChange::Known { new_vars_only: false },
+            vec![
+                // Gas builtin
+                OutputVarInfo {
+                    ty: gas_builtin_ty,
+                    ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
                 },
-                BranchSignature {
-                    vars: vec![
-                        // Gas builtin
-                        OutputVarInfo {
-                            ty: gas_builtin_ty,
-                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
-                        },
-                        // System
-                        OutputVarInfo {
-                            ty: system_ty,
-                            ref_info: OutputVarReferenceInfo::Deferred(
-                                DeferredOutputKind::AddConst { param_idx: 1 },
-                            ),
-                        },
-                        // Revert reason
-                        OutputVarInfo {
-                            ty: felt_ty,
-                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
-                        },
-                    ],
-                    ap_change: SierraApChange::Known { new_vars_only: false },
+                // System
+                OutputVarInfo {
+                    ty: system_ty,
+                    ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                        param_idx: 1,
+                    }),
                 },
             ],
-            fallthrough: Some(0),
-        })
+            SierraApChange::Known { new_vars_only: false },
+        ))
+    }
+}
+
+/// Returns the signature of the checked variant of a storage libfunc, given the signature of the
+/// libfunc - with a range check added as the first param and as the first output of every branch.
+fn with_range_check(
+    context: &dyn SignatureSpecializationContext,
+    mut signature: LibFuncSignature,
+) -> Result<LibFuncSignature, SpecializationError> {
+    let range_check_ty = context.get_concrete_type(RangeCheckType::id(), &[])?;
+    signature.param_signatures.insert(0, ParamSignature::new(range_check_ty.clone()));
+    for branch_signature in &mut signature.branch_signatures {
+        for var in &mut branch_signature.vars {
+            match &mut var.ref_info {
+                OutputVarReferenceInfo::SameAsParam { param_idx }
+                | OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst { param_idx }) => {
+                    *param_idx += 1;
+                }
+                OutputVarReferenceInfo::NewTempVar { .. }
+                | OutputVarReferenceInfo::NewLocalVar
+                | OutputVarReferenceInfo::Deferred(
+                    DeferredOutputKind::Const | DeferredOutputKind::Generic,
+                ) => {}
+            }
+        }
+        branch_signature.vars.insert(
+            0,
+            OutputVarInfo {
+                ty: range_check_ty.clone(),
+                ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                    param_idx: 0,
+                }),
+            },
+        );
     }
+    Ok(signature)
 }
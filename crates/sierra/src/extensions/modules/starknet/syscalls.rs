@@ -1,24 +1,377 @@
-use crate::extensions::types::{InfoOnlyConcreteType, TypeInfo};
-use crate::extensions::{NamedType, NoGenericArgsGenericType};
-use crate::ids::GenericTypeId;
-
-/// Type for StarkNet system object.
-/// Used to make system calls.
-#[derive(Default)]
-pub struct SystemType {}
-impl NoGenericArgsGenericType for SystemType {
-    type Concrete = InfoOnlyConcreteType;
-    const ID: GenericTypeId = GenericTypeId::new_inline("System");
-
-    fn specialize(&self) -> Self::Concrete {
-        InfoOnlyConcreteType {
-            info: TypeInfo {
-                long_id: Self::concrete_type_long_id(&[]),
-                storable: true,
-                droppable: false,
-                duplicatable: false,
-                size: 1,
+use super::interoperability::ContractAddressType;
+use crate::define_info_only_type;
+use crate::extensions::array::ArrayType;
+use crate::extensions::boxing::BoxType;
+use crate::extensions::felt::FeltType;
+use crate::extensions::gas::GasBuiltinType;
+use crate::extensions::lib_func::{
+    BranchSignature, DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature,
+    SierraApChange, SignatureSpecializationContext,
+};
+use crate::extensions::{
+    NamedType, NoGenericArgsGenericLibFunc, OutputVarReferenceInfo, SpecializationError,
+};
+use crate::ids::{ConcreteTypeId, GenericLibFuncId};
+use crate::program::GenericArg;
+
+define_info_only_type! {
+    /// Type for StarkNet system object.
+    /// Used to make system calls.
+    pub struct SystemType {
+        id: "System",
+        storable: true,
+        droppable: false,
+        duplicatable: false,
+        size: 1,
+    }
+}
+
+define_info_only_type! {
+    /// Type for the information of the current transaction, pointed to by the result of
+    /// `get_tx_info`.
+    ///
+    /// Laid out in memory as the version, the account contract address, the max fee, the start and
+    /// end of the signature array, the transaction hash, the chain id and the nonce.
+    pub struct TxInfoType {
+        id: "TxInfo",
+        storable: true,
+        droppable: true,
+        duplicatable: true,
+        size: 8,
+    }
+}
+
+/// Returns the signature of a system call that takes no arguments besides the gas builtin and the
+/// system, and returns a single value of type `result_ty` on success.
+fn empty_args_syscall_signature(
+    context: &dyn SignatureSpecializationContext,
+    result_ty: ConcreteTypeId,
+) -> Result<LibFuncSignature, SpecializationError> {
+    let gas_builtin_ty = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+    let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
+    Ok(LibFuncSignature {
+        param_signatures: vec![
+            // Gas builtin
+            ParamSignature::new(gas_builtin_ty.clone()),
+            // System
+            ParamSignature {
+                ty: system_ty.clone(),
+                allow_deferred: false,
+                allow_add_const: true,
+                allow_const: false,
+            },
+        ],
+        branch_signatures: vec![
+            // Success branch
+            BranchSignature {
+                vars: vec![
+                    // Gas builtin
+                    OutputVarInfo {
+                        ty: gas_builtin_ty.clone(),
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                    },
+                    // System
+                    OutputVarInfo {
+                        ty: system_ty.clone(),
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                            param_idx: 1,
+                        }),
+                    },
+                    // Result
+                    OutputVarInfo {
+                        ty: result_ty,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                    },
+                ],
+                ap_change: SierraApChange::Known { new_vars_only: false },
+            },
+            // Failure branch
+            BranchSignature {
+                vars: vec![
+                    // Gas builtin
+                    OutputVarInfo {
+                        ty: gas_builtin_ty,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                    },
+                    // System
+                    OutputVarInfo {
+                        ty: system_ty,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                            param_idx: 1,
+                        }),
+                    },
+                    // Revert reason
+                    OutputVarInfo {
+                        ty: context.get_concrete_type(FeltType::id(), &[])?,
+                        ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                    },
+                ],
+                ap_change: SierraApChange::Known { new_vars_only: false },
             },
-        }
+        ],
+        fallthrough: Some(0),
+    })
+}
+
+/// LibFunc for a get caller address system call.
+#[derive(Default)]
+pub struct GetCallerAddressLibFunc {}
+impl NoGenericArgsGenericLibFunc for GetCallerAddressLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("get_caller_address");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        empty_args_syscall_signature(
+            context,
+            context.get_concrete_type(ContractAddressType::id(), &[])?,
+        )
+    }
+}
+
+/// LibFunc for a get contract address system call, returning the address of the current contract.
+#[derive(Default)]
+pub struct GetContractAddressLibFunc {}
+impl NoGenericArgsGenericLibFunc for GetContractAddressLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("get_contract_address");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        empty_args_syscall_signature(
+            context,
+            context.get_concrete_type(ContractAddressType::id(), &[])?,
+        )
+    }
+}
+
+/// LibFunc for a get sequencer address system call.
+#[derive(Default)]
+pub struct GetSequencerAddressLibFunc {}
+impl NoGenericArgsGenericLibFunc for GetSequencerAddressLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("get_sequencer_address");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        empty_args_syscall_signature(
+            context,
+            context.get_concrete_type(ContractAddressType::id(), &[])?,
+        )
+    }
+}
+
+/// LibFunc for a get block number system call.
+#[derive(Default)]
+pub struct GetBlockNumberLibFunc {}
+impl NoGenericArgsGenericLibFunc for GetBlockNumberLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("get_block_number");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        empty_args_syscall_signature(context, context.get_concrete_type(FeltType::id(), &[])?)
+    }
+}
+
+/// LibFunc for a get block timestamp system call.
+#[derive(Default)]
+pub struct GetBlockTimestampLibFunc {}
+impl NoGenericArgsGenericLibFunc for GetBlockTimestampLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("get_block_timestamp");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        empty_args_syscall_signature(context, context.get_concrete_type(FeltType::id(), &[])?)
+    }
+}
+
+/// LibFunc for a get transaction info system call. Returns a box of the transaction info, as the
+/// system call only writes a pointer to it.
+#[derive(Default)]
+pub struct GetTxInfoLibFunc {}
+impl NoGenericArgsGenericLibFunc for GetTxInfoLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("get_tx_info");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let tx_info_ty = context.get_concrete_type(TxInfoType::id(), &[])?;
+        empty_args_syscall_signature(
+            context,
+            context.get_wrapped_concrete_type(BoxType::id(), tx_info_ty)?,
+        )
+    }
+}
+
+/// LibFunc for a replace class system call, replacing the class of the calling contract.
+#[derive(Default)]
+pub struct ReplaceClassLibFunc {}
+impl NoGenericArgsGenericLibFunc for ReplaceClassLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("replace_class_syscall");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let gas_builtin_ty = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+        let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
+        let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+        Ok(LibFuncSignature {
+            param_signatures: vec![
+                // Gas builtin
+                ParamSignature::new(gas_builtin_ty.clone()),
+                // System
+                ParamSignature {
+                    ty: system_ty.clone(),
+                    allow_deferred: false,
+                    allow_add_const: true,
+                    allow_const: false,
+                },
+                // Class hash
+                ParamSignature::new(felt_ty.clone()),
+            ],
+            branch_signatures: vec![
+                // Success branch
+                BranchSignature {
+                    vars: vec![
+                        // Gas builtin
+                        OutputVarInfo {
+                            ty: gas_builtin_ty.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                        },
+                        // System
+                        OutputVarInfo {
+                            ty: system_ty.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 1 },
+                            ),
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+                // Failure branch
+                BranchSignature {
+                    vars: vec![
+                        // Gas builtin
+                        OutputVarInfo {
+                            ty: gas_builtin_ty,
+                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                        },
+                        // System
+                        OutputVarInfo {
+                            ty: system_ty,
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 1 },
+                            ),
+                        },
+                        // Revert reason
+                        OutputVarInfo {
+                            ty: felt_ty,
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+            ],
+            fallthrough: Some(0),
+        })
+    }
+}
+
+/// LibFunc for a keccak system call, hashing an array of 64-bit words.
+///
+/// Each felt of the input array holds a single 64-bit word of the hashed data. On
+/// success, returns the 256-bit result split into its low and high 128-bit halves.
+#[derive(Default)]
+pub struct KeccakLibFunc {}
+impl NoGenericArgsGenericLibFunc for KeccakLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("keccak_syscall");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let gas_builtin_ty = context.get_concrete_type(GasBuiltinType::id(), &[])?;
+        let system_ty = context.get_concrete_type(SystemType::id(), &[])?;
+        let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+        let felt_array_ty =
+            context.get_concrete_type(ArrayType::id(), &[GenericArg::ty(felt_ty.clone())])?;
+        Ok(LibFuncSignature {
+            param_signatures: vec![
+                // Gas builtin
+                ParamSignature::new(gas_builtin_ty.clone()),
+                // System
+                ParamSignature {
+                    ty: system_ty.clone(),
+                    allow_deferred: false,
+                    allow_add_const: true,
+                    allow_const: false,
+                },
+                // Input
+                ParamSignature::new(felt_array_ty),
+            ],
+            branch_signatures: vec![
+                // Success branch
+                BranchSignature {
+                    vars: vec![
+                        // Gas builtin
+                        OutputVarInfo {
+                            ty: gas_builtin_ty.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                        },
+                        // System
+                        OutputVarInfo {
+                            ty: system_ty.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 1 },
+                            ),
+                        },
+                        // Result low
+                        OutputVarInfo {
+                            ty: felt_ty.clone(),
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                        },
+                        // Result high
+                        OutputVarInfo {
+                            ty: felt_ty.clone(),
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(1) },
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+                // Failure branch
+                BranchSignature {
+                    vars: vec![
+                        // Gas builtin
+                        OutputVarInfo {
+                            ty: gas_builtin_ty,
+                            ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::Generic),
+                        },
+                        // System
+                        OutputVarInfo {
+                            ty: system_ty,
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 1 },
+                            ),
+                        },
+                        // Revert reason
+                        OutputVarInfo {
+                            ty: felt_ty,
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+            ],
+            fallthrough: Some(0),
+        })
     }
 }
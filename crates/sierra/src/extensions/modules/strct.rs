@@ -10,18 +10,28 @@
 //! tuple_construct(felt0, felt1) -> (tup);
 //! tuple_deconstruct(tup) -> (felt0, felt1);
 //! ```
+//!
+//! A single member can be read without deconstructing the struct:
+//! ```ignore
+//! libfunc tuple_get_1 = struct_member_get<Tuple<felt, felt>, 1>;
+//! tuple_get_1(tup) -> (tup, felt1);
+//! ```
 
+use num_bigint::ToBigInt;
+use num_traits::Signed;
 use utils::try_extract_matches;
 
 use crate::define_libfunc_hierarchy;
 use crate::extensions::lib_func::{
     DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature, SierraApChange,
-    SignatureOnlyGenericLibFunc, SignatureSpecializationContext,
+    SignatureBasedConcreteLibFunc, SignatureOnlyGenericLibFunc, SignatureSpecializationContext,
+    SpecializationContext,
 };
 use crate::extensions::type_specialization_context::TypeSpecializationContext;
 use crate::extensions::types::TypeInfo;
 use crate::extensions::{
-    args_as_single_type, ConcreteType, NamedType, OutputVarReferenceInfo, SpecializationError,
+    args_as_single_type, args_as_type_and_value, ConcreteType, NamedLibFunc, NamedType,
+    OutputVarReferenceInfo, SpecializationError,
 };
 use crate::ids::{ConcreteTypeId, GenericLibFuncId, GenericTypeId};
 use crate::program::{ConcreteTypeLongId, GenericArg};
@@ -51,22 +61,20 @@ impl StructConcreteType {
         context: &dyn TypeSpecializationContext,
         args: &[GenericArg],
     ) -> Result<Self, SpecializationError> {
-        let mut args_iter = args.iter();
-        args_iter
-            .next()
+        args.first()
             .and_then(|arg| try_extract_matches!(arg, GenericArg::UserType))
-            .ok_or(SpecializationError::UnsupportedGenericArg)?;
+            .ok_or(SpecializationError::UnsupportedGenericArg { index: 0 })?;
         let mut duplicatable = true;
         let mut droppable = true;
         let mut members: Vec<ConcreteTypeId> = Vec::new();
         let mut size = 0;
-        for arg in args_iter {
+        for (index, arg) in args.iter().enumerate().skip(1) {
             let ty = try_extract_matches!(arg, GenericArg::Type)
-                .ok_or(SpecializationError::UnsupportedGenericArg)?
+                .ok_or(SpecializationError::UnsupportedGenericArg { index })?
                 .clone();
             let info = context.get_type_info(ty.clone())?;
             if !info.storable {
-                return Err(SpecializationError::UnsupportedGenericArg);
+                return Err(SpecializationError::UnsupportedGenericArg { index });
             }
             if !info.duplicatable {
                 duplicatable = false;
@@ -79,10 +87,7 @@ impl StructConcreteType {
         }
         Ok(StructConcreteType {
             info: TypeInfo {
-                long_id: ConcreteTypeLongId {
-                    generic_id: "Struct".into(),
-                    generic_args: args.to_vec(),
-                },
+                long_id: ConcreteTypeLongId::new("Struct".into(), args.to_vec()),
                 duplicatable,
                 droppable,
                 storable: true,
@@ -102,6 +107,7 @@ define_libfunc_hierarchy! {
     pub enum StructLibFunc {
         Construct(StructConstructLibFunc),
         Deconstruct(StructDeconstructLibFunc),
+        MemberGet(StructMemberGetLibFunc),
     }, StructConcreteLibFunc
 }
 
@@ -171,3 +177,85 @@ impl SignatureOnlyGenericLibFunc for StructDeconstructLibFunc {
         ))
     }
 }
+
+pub struct StructMemberGetConcreteLibFunc {
+    pub signature: LibFuncSignature,
+    /// The types of the members of the struct.
+    pub members: Vec<ConcreteTypeId>,
+    /// The index of the relevant member in the struct.
+    pub index: usize,
+}
+impl SignatureBasedConcreteLibFunc for StructMemberGetConcreteLibFunc {
+    fn signature(&self) -> &LibFuncSignature {
+        &self.signature
+    }
+}
+
+/// LibFunc for reading a single member of a struct, without deconstructing it.
+/// Returns the struct itself, and a copy of the member - so the member must be duplicatable.
+#[derive(Default)]
+pub struct StructMemberGetLibFunc {}
+impl StructMemberGetLibFunc {
+    /// Creates the specialization of the member-get libfunc with the given template arguments.
+    fn specialize_concrete_lib_func(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+        args: &[GenericArg],
+    ) -> Result<StructMemberGetConcreteLibFunc, SpecializationError> {
+        let (struct_type, index) = args_as_type_and_value(args)?;
+        let long_id = context.get_type_info(struct_type.clone())?.long_id;
+        if long_id.generic_id != StructType::ID {
+            return Err(SpecializationError::UnsupportedGenericArg { index: 0 });
+        }
+        let type_context = context.as_type_specialization_context();
+        let member_types = StructConcreteType::new(type_context, &long_id.generic_args)?.members;
+        let num_members = member_types.len();
+        if index.is_negative() || index >= num_members.to_bigint().unwrap() {
+            return Err(SpecializationError::IndexOutOfRange { index, range_size: num_members });
+        }
+        let index: usize = index.try_into().unwrap();
+        let member_type = member_types[index].clone();
+        if !context.get_type_info(member_type.clone())?.duplicatable {
+            return Err(SpecializationError::UnsupportedGenericArg { index: 1 });
+        }
+        Ok(StructMemberGetConcreteLibFunc {
+            signature: LibFuncSignature::new_non_branch(
+                vec![struct_type.clone()],
+                vec![
+                    OutputVarInfo {
+                        ty: struct_type,
+                        ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 0 },
+                    },
+                    OutputVarInfo {
+                        ty: member_type,
+                        // The member is a slice of the memory of the struct.
+                        ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 0 },
+                    },
+                ],
+                SierraApChange::Known { new_vars_only: true },
+            ),
+            members: member_types,
+            index,
+        })
+    }
+}
+impl NamedLibFunc for StructMemberGetLibFunc {
+    type Concrete = StructMemberGetConcreteLibFunc;
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("struct_member_get");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+        args: &[GenericArg],
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        Ok(self.specialize_concrete_lib_func(context, args)?.signature)
+    }
+
+    fn specialize(
+        &self,
+        context: &dyn SpecializationContext,
+        args: &[GenericArg],
+    ) -> Result<Self::Concrete, SpecializationError> {
+        self.specialize_concrete_lib_func(context.upcast(), args)
+    }
+}
@@ -2,42 +2,33 @@ use super::felt::FeltType;
 use super::jump_not_zero::{JumpNotZeroLibFunc, JumpNotZeroTraits};
 use super::non_zero::NonZeroType;
 use super::range_check::RangeCheckType;
-use crate::define_libfunc_hierarchy;
 use crate::extensions::lib_func::{
     BranchSignature, DeferredOutputKind, LibFuncSignature, OutputVarInfo, ParamSignature,
     SierraApChange, SignatureSpecializationContext, SpecializationContext,
 };
-use crate::extensions::types::{InfoOnlyConcreteType, TypeInfo};
 use crate::extensions::{
-    GenericLibFunc, NamedLibFunc, NamedType, NoGenericArgsGenericLibFunc, NoGenericArgsGenericType,
+    args_as_single_value, GenericLibFunc, NamedLibFunc, NamedType, NoGenericArgsGenericLibFunc,
     OutputVarReferenceInfo, SignatureBasedConcreteLibFunc, SpecializationError,
 };
 use crate::ids::{ConcreteTypeId, GenericLibFuncId, GenericTypeId};
 use crate::program::GenericArg;
+use crate::{define_info_only_type, define_libfunc_hierarchy};
 
-/// Type for u128.
-#[derive(Default)]
-pub struct Uint128Type {}
-impl NoGenericArgsGenericType for Uint128Type {
-    type Concrete = InfoOnlyConcreteType;
-    const ID: GenericTypeId = GenericTypeId::new_inline("u128");
-
-    fn specialize(&self) -> Self::Concrete {
-        InfoOnlyConcreteType {
-            info: TypeInfo {
-                long_id: Self::concrete_type_long_id(&[]),
-                storable: true,
-                droppable: true,
-                duplicatable: true,
-                size: 1,
-            },
-        }
+define_info_only_type! {
+    /// Type for u128.
+    pub struct Uint128Type {
+        id: "u128",
+        storable: true,
+        droppable: true,
+        duplicatable: true,
+        size: 1,
     }
 }
 
 define_libfunc_hierarchy! {
     pub enum Uint128LibFunc {
         Operation(Uint128OperationLibFunc),
+        CheckedMul(Uint128CheckedMulLibFunc),
         LessThan(Uint128LessThanLibFunc),
         Equal(Uint128EqualLibFunc),
         LessThanOrEqual(Uint128LessThanOrEqualLibFunc),
@@ -65,6 +56,11 @@ pub enum IntOperator {
     DivMod,
 }
 
+const U128_OVERFLOWING_ADD: GenericLibFuncId = GenericLibFuncId::new_inline("u128_overflow_add");
+const U128_OVERFLOWING_SUB: GenericLibFuncId = GenericLibFuncId::new_inline("u128_overflow_sub");
+const U128_OVERFLOWING_MUL: GenericLibFuncId = GenericLibFuncId::new_inline("u128_overflow_mul");
+const U128_DIVMOD: GenericLibFuncId = GenericLibFuncId::new_inline("u128_safe_divmod");
+
 /// Libfunc for u128 operations.
 pub struct Uint128OperationLibFunc {
     pub operator: IntOperator,
@@ -77,16 +73,16 @@ impl Uint128OperationLibFunc {
 impl GenericLibFunc for Uint128OperationLibFunc {
     type Concrete = Uint128OperationConcreteLibFunc;
 
+    fn supported_ids() -> Vec<GenericLibFuncId> {
+        vec![U128_OVERFLOWING_ADD, U128_OVERFLOWING_SUB, U128_OVERFLOWING_MUL, U128_DIVMOD]
+    }
+
     fn by_id(id: &GenericLibFuncId) -> Option<Self> {
-        const OVERFLOWING_ADD: GenericLibFuncId = GenericLibFuncId::new_inline("u128_overflow_add");
-        const OVERFLOWING_SUB: GenericLibFuncId = GenericLibFuncId::new_inline("u128_overflow_sub");
-        const OVERFLOWING_MUL: GenericLibFuncId = GenericLibFuncId::new_inline("u128_overflow_mul");
-        const DIVMOD: GenericLibFuncId = GenericLibFuncId::new_inline("u128_safe_divmod");
         match id {
-            id if id == &OVERFLOWING_ADD => Some(Self::new(IntOperator::OverflowingAdd)),
-            id if id == &OVERFLOWING_SUB => Some(Self::new(IntOperator::OverflowingSub)),
-            id if id == &OVERFLOWING_MUL => Some(Self::new(IntOperator::OverflowingMul)),
-            id if id == &DIVMOD => Some(Self::new(IntOperator::DivMod)),
+            id if id == &U128_OVERFLOWING_ADD => Some(Self::new(IntOperator::OverflowingAdd)),
+            id if id == &U128_OVERFLOWING_SUB => Some(Self::new(IntOperator::OverflowingSub)),
+            id if id == &U128_OVERFLOWING_MUL => Some(Self::new(IntOperator::OverflowingMul)),
+            id if id == &U128_DIVMOD => Some(Self::new(IntOperator::DivMod)),
             _ => None,
         }
     }
@@ -205,6 +201,54 @@ impl SignatureBasedConcreteLibFunc for Uint128OperationConcreteLibFunc {
     }
 }
 
+/// LibFunc for multiplying u128s, branching on whether the product overflows.
+/// Only the product is returned on success, saving the handling of the high limb of the wide
+/// product.
+#[derive(Default)]
+pub struct Uint128CheckedMulLibFunc {}
+impl NoGenericArgsGenericLibFunc for Uint128CheckedMulLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("u128_checked_mul");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let (u128_type, range_check_type) = get_u128_comparison_types(context)?;
+        Ok(LibFuncSignature {
+            param_signatures: get_u128_comparison_param_signatures(context)?,
+            branch_signatures: vec![
+                // No overflow.
+                BranchSignature {
+                    vars: vec![
+                        OutputVarInfo {
+                            ty: range_check_type.clone(),
+                            ref_info: OutputVarReferenceInfo::Deferred(
+                                DeferredOutputKind::AddConst { param_idx: 0 },
+                            ),
+                        },
+                        OutputVarInfo {
+                            ty: u128_type,
+                            ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+                        },
+                    ],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+                // Overflow.
+                BranchSignature {
+                    vars: vec![OutputVarInfo {
+                        ty: range_check_type,
+                        ref_info: OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst {
+                            param_idx: 0,
+                        }),
+                    }],
+                    ap_change: SierraApChange::Known { new_vars_only: false },
+                },
+            ],
+            fallthrough: Some(0),
+        })
+    }
+}
+
 /// LibFunc for creating a constant u128.
 #[derive(Default)]
 pub struct Uint128ConstLibFunc {}
@@ -232,17 +276,11 @@ impl NamedLibFunc for Uint128ConstLibFunc {
         context: &dyn SpecializationContext,
         args: &[GenericArg],
     ) -> Result<Self::Concrete, SpecializationError> {
-        match args {
-            [GenericArg::Value(c)] => Ok(Uint128ConstConcreteLibFunc {
-                c: u128::try_from(c).unwrap(),
-                signature: <Self as NamedLibFunc>::specialize_signature(
-                    self,
-                    context.upcast(),
-                    args,
-                )?,
-            }),
-            _ => Err(SpecializationError::UnsupportedGenericArg),
-        }
+        Ok(Uint128ConstConcreteLibFunc {
+            c: u128::try_from(args_as_single_value(args)?)
+                .map_err(|_| SpecializationError::UnsupportedGenericArg { index: 0 })?,
+            signature: <Self as NamedLibFunc>::specialize_signature(self, context.upcast(), args)?,
+        })
     }
 }
 
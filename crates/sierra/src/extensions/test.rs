@@ -2,21 +2,26 @@ use bimap::BiMap;
 use num_bigint::BigInt;
 use test_case::test_case;
 
-use super::core::{CoreLibFunc, CoreType};
+use super::core::{CoreConcreteLibFunc, CoreLibFunc, CoreType};
 use super::lib_func::{SierraApChange, SignatureSpecializationContext, SpecializationContext};
+use super::starknet::StarkNetConcreteLibFunc;
 use super::types::TypeInfo;
 use super::SpecializationError::{
     self, IndexOutOfRange, MissingFunction, UnsupportedGenericArg, UnsupportedId,
     WrongNumberOfGenericArgs,
 };
+use crate::define_info_only_type;
 use crate::extensions::type_specialization_context::TypeSpecializationContext;
-use crate::extensions::{GenericLibFunc, GenericType};
+use crate::extensions::{
+    args_as_single_type, args_as_single_value, args_as_two_types, args_as_type_and_value,
+    ConcreteType, GenericLibFunc, GenericType,
+};
 use crate::ids::{ConcreteTypeId, FunctionId, GenericTypeId};
 use crate::program::{ConcreteTypeLongId, Function, FunctionSignature, GenericArg, StatementIdx};
 use crate::test_utils::build_bijective_mapping;
 
 fn type_arg(name: &str) -> GenericArg {
-    GenericArg::Type(name.into())
+    GenericArg::ty(name.into())
 }
 
 fn user_type_arg(name: &str) -> GenericArg {
@@ -24,7 +29,7 @@ fn user_type_arg(name: &str) -> GenericArg {
 }
 
 fn value_arg(v: i64) -> GenericArg {
-    GenericArg::Value(BigInt::from(v))
+    GenericArg::value(v)
 }
 
 struct MockSpecializationContext {
@@ -47,7 +52,9 @@ impl TypeSpecializationContext for MockSpecializationContext {
             || id == "Tuple<>".into()
             || id == "U128AndFelt".into()
             || id == "StorageAddress".into()
+            || id == "StorageBaseAddress".into()
             || id == "ContractAddress".into()
+            || id == "Secp256k1Point".into()
         {
             Some(TypeInfo {
                 long_id: self.mapping.get_by_left(&id)?.clone(),
@@ -56,7 +63,7 @@ impl TypeSpecializationContext for MockSpecializationContext {
                 duplicatable: true,
                 size: 1,
             })
-        } else if id == "ArrayFelt".into() || id == "ArrayU128".into() {
+        } else if id == "ArrayFelt".into() || id == "ArrayU128".into() || id == "FeltPair".into() {
             Some(TypeInfo {
                 long_id: self.mapping.get_by_left(&id)?.clone(),
                 storable: true,
@@ -130,6 +137,8 @@ impl SpecializationContext for MockSpecializationContext {
 #[test_case("NoneExistent", vec![] => Err(UnsupportedId); "NoneExistent")]
 #[test_case("GasBuiltin", vec![] => Ok(()); "GasBuiltin")]
 #[test_case("GasBuiltin", vec![type_arg("T")] => Err(WrongNumberOfGenericArgs); "GasBuiltin<T>")]
+#[test_case("GasReserve", vec![] => Ok(()); "GasReserve")]
+#[test_case("GasReserve", vec![type_arg("T")] => Err(WrongNumberOfGenericArgs); "GasReserve<T>")]
 #[test_case("RangeCheck", vec![] => Ok(()); "RangeCheck")]
 #[test_case("RangeCheck", vec![type_arg("T")] => Err(WrongNumberOfGenericArgs); "RangeCheck<T>")]
 #[test_case("felt", vec![] => Ok(()); "felt")]
@@ -138,42 +147,47 @@ impl SpecializationContext for MockSpecializationContext {
 #[test_case("u128", vec![type_arg("T")] => Err(WrongNumberOfGenericArgs); "u128<T>")]
 #[test_case("Array", vec![type_arg("u128")] => Ok(()); "Array<u128>")]
 #[test_case("Array", vec![] => Err(WrongNumberOfGenericArgs); "Array")]
-#[test_case("Array", vec![value_arg(5)] => Err(UnsupportedGenericArg); "Array<5>")]
-#[test_case("Array", vec![type_arg("UninitializedFelt")] => Err(UnsupportedGenericArg);
+#[test_case("Array", vec![value_arg(5)] => Err(UnsupportedGenericArg { index: 0 }); "Array<5>")]
+#[test_case("Array", vec![type_arg("UninitializedFelt")] => Err(UnsupportedGenericArg { index: 0 });
             "Array<UninitializedFelt>")]
+#[test_case("Array", vec![type_arg("GasReserve")] => Err(UnsupportedGenericArg { index: 0 });
+            "Array<GasReserve>")]
 #[test_case("NonZero", vec![type_arg("T")] => Ok(()); "NonZero<T>")]
 #[test_case("NonZero", vec![] => Err(WrongNumberOfGenericArgs); "NonZero")]
-#[test_case("NonZero", vec![value_arg(5)] => Err(UnsupportedGenericArg); "NonZero<5>")]
+#[test_case("NonZero", vec![value_arg(5)] => Err(UnsupportedGenericArg { index: 0 }); "NonZero<5>")]
 #[test_case("Box", vec![type_arg("T")] => Ok(()); "Box<T>")]
 #[test_case("Box", vec![] => Err(WrongNumberOfGenericArgs); "Box<>")]
-#[test_case("Box", vec![value_arg(5)] => Err(UnsupportedGenericArg); "Box<5>")]
+#[test_case("Box", vec![value_arg(5)] => Err(UnsupportedGenericArg { index: 0 }); "Box<5>")]
 #[test_case("Uninitialized", vec![type_arg("T")] => Ok(()); "Uninitialized<T>")]
 #[test_case("Enum", vec![user_type_arg("name")] => Ok(()); "Enum<name>")]
 #[test_case("Enum", vec![user_type_arg("name"), type_arg("u128")] => Ok(());
             "Enum<name, u128>")]
 #[test_case("Enum", vec![user_type_arg("name"), type_arg("u128"), type_arg("felt")] => Ok(());
             "Enum<name, u128, felt>")]
-#[test_case("Enum", vec![user_type_arg("name"), value_arg(5)] => Err(UnsupportedGenericArg);
+#[test_case("Enum", vec![user_type_arg("name"), value_arg(5)] => Err(UnsupportedGenericArg { index: 1 });
             "Enum<name, 5>")]
 #[test_case("Enum", vec![user_type_arg("name"), type_arg("UninitializedFelt")]
-            => Err(UnsupportedGenericArg);
+            => Err(UnsupportedGenericArg { index: 1 });
             "Enum<name, UninitializedFelt>")]
-#[test_case("Enum", vec![type_arg("u128"), type_arg("felt")] => Err(UnsupportedGenericArg);
+#[test_case("Enum", vec![type_arg("u128"), type_arg("felt")] => Err(UnsupportedGenericArg { index: 0 });
             "Enum<u128, felt>")]
 #[test_case("Struct", vec![user_type_arg("Unit")] => Ok(()); "Struct<Unit>")]
 #[test_case("Struct", vec![user_type_arg("Wrap"), type_arg("u128")] => Ok(());
             "Struct<Wrap, u128>")]
 #[test_case("Struct", vec![user_type_arg("Pair"), type_arg("u128"), type_arg("felt")] => Ok(());
             "Struct<Pair, u128, felt>")]
-#[test_case("Struct", vec![user_type_arg("name"), value_arg(5)] => Err(UnsupportedGenericArg);
+#[test_case("Struct", vec![user_type_arg("name"), value_arg(5)] => Err(UnsupportedGenericArg { index: 1 });
             "Struct<name, 5>")]
 #[test_case("Struct", vec![user_type_arg("name"), type_arg("UninitializedFelt")]
-            => Err(UnsupportedGenericArg);
+            => Err(UnsupportedGenericArg { index: 1 });
             "Struct<name, UninitializedFelt>")]
-#[test_case("Struct", vec![type_arg("u128"), type_arg("felt")] => Err(UnsupportedGenericArg);
+#[test_case("Struct", vec![type_arg("u128"), type_arg("felt")] => Err(UnsupportedGenericArg { index: 0 });
             "Struct<u128, felt>")]
 #[test_case("System", vec![] => Ok(()); "System")]
+#[test_case("TxInfo", vec![] => Ok(()); "TxInfo")]
+#[test_case("Secp256k1Point", vec![] => Ok(()); "Secp256k1Point")]
 #[test_case("StorageAddress", vec![] => Ok(()); "StorageAddress")]
+#[test_case("StorageBaseAddress", vec![] => Ok(()); "StorageBaseAddress")]
 fn find_type_specialization(
     id: &str,
     generic_args: Vec<GenericArg>,
@@ -184,25 +198,83 @@ fn find_type_specialization(
         .map(|_| ())
 }
 
+define_info_only_type! {
+    /// Type for testing `define_info_only_type!`.
+    pub struct InfoOnlyTestType {
+        id: "InfoOnlyTest",
+        storable: false,
+        droppable: true,
+        duplicatable: false,
+        size: 3,
+    }
+}
+
+#[test]
+fn info_only_type_round_trip() {
+    let id = GenericTypeId::from("InfoOnlyTest");
+    let ty = InfoOnlyTestType::by_id(&id).unwrap();
+    assert_eq!(
+        ty.specialize(&MockSpecializationContext::new(), &[])
+            .map(|concrete| concrete.info().clone()),
+        Ok(TypeInfo {
+            long_id: ConcreteTypeLongId::new(id, vec![]),
+            storable: false,
+            droppable: true,
+            duplicatable: false,
+            size: 3,
+        })
+    );
+    assert_eq!(
+        ty.specialize(&MockSpecializationContext::new(), &[type_arg("T")]).err(),
+        Some(WrongNumberOfGenericArgs)
+    );
+    assert!(InfoOnlyTestType::by_id(&"felt".into()).is_none());
+}
+
 #[test_case("NoneExistent", vec![] => Err(UnsupportedId); "NoneExistent")]
 #[test_case("function_call", vec![GenericArg::UserFunc("UnregisteredFunction".into())]
             => Err(MissingFunction("UnregisteredFunction".into()));
             "function_call<&UnregisteredFunction>")]
 #[test_case("function_call", vec![GenericArg::UserFunc("RegisteredFunction".into())]
             => Ok(()); "function_call<&RegisteredFunction>")]
-#[test_case("function_call", vec![] => Err(UnsupportedGenericArg); "function_call")]
+#[test_case("function_call", vec![] => Err(WrongNumberOfGenericArgs); "function_call")]
 #[test_case("array_new", vec![] => Err(WrongNumberOfGenericArgs); "array_new")]
 #[test_case("array_new", vec![type_arg("u128")] => Ok(()); "array_new<u128>")]
+#[test_case("array_new_with_capacity", vec![] => Err(WrongNumberOfGenericArgs);
+            "array_new_with_capacity")]
+#[test_case("array_new_with_capacity", vec![type_arg("u128")] => Ok(());
+            "array_new_with_capacity<u128>")]
+#[test_case("panic_array_new", vec![] => Ok(()); "panic_array_new")]
+#[test_case("panic_array_new", vec![type_arg("felt")] => Err(WrongNumberOfGenericArgs);
+            "panic_array_new<felt>")]
 #[test_case("array_append", vec![] => Err(WrongNumberOfGenericArgs); "array_append")]
 #[test_case("array_append", vec![type_arg("u128")] => Ok(()); "array_append<u128>")]
 #[test_case("array_at", vec![] => Err(WrongNumberOfGenericArgs); "array_at")]
 #[test_case("array_at", vec![type_arg("u128")] => Ok(()); "array_at<u128>")]
+#[test_case("array_get", vec![] => Err(WrongNumberOfGenericArgs); "array_get")]
+#[test_case("array_get", vec![type_arg("u128")] => Ok(()); "array_get<u128>")]
 #[test_case("array_len", vec![] => Err(WrongNumberOfGenericArgs); "array_len")]
 #[test_case("array_len", vec![type_arg("u128")] => Ok(()); "array_len<u128>")]
+#[test_case("array_len_checked", vec![] => Err(WrongNumberOfGenericArgs); "array_len_checked")]
+#[test_case("array_len_checked", vec![type_arg("u128")] => Ok(()); "array_len_checked<u128>")]
+#[test_case("array_clone", vec![] => Err(WrongNumberOfGenericArgs); "array_clone")]
+#[test_case("array_clone", vec![type_arg("u128")] => Ok(()); "array_clone<u128>")]
 #[test_case("get_gas", vec![value_arg(0)] => Err(WrongNumberOfGenericArgs); "get_gas<0>")]
 #[test_case("get_gas", vec![] => Ok(()); "get_gas")]
+#[test_case("withdraw_gas_all", vec![value_arg(0)] => Err(WrongNumberOfGenericArgs);
+            "withdraw_gas_all<0>")]
+#[test_case("withdraw_gas_all", vec![] => Ok(()); "withdraw_gas_all")]
 #[test_case("refund_gas", vec![value_arg(0)] => Err(WrongNumberOfGenericArgs); "refund_gas<0>")]
 #[test_case("refund_gas", vec![] => Ok(()); "refund_gas")]
+#[test_case("gas_reserve_create", vec![] => Err(WrongNumberOfGenericArgs); "gas_reserve_create")]
+#[test_case("gas_reserve_create", vec![value_arg(100)] => Ok(()); "gas_reserve_create<100>")]
+#[test_case("gas_reserve_create", vec![value_arg(-1)] => Err(UnsupportedGenericArg { index: 0 });
+            "gas_reserve_create<-1>")]
+#[test_case("gas_reserve_create", vec![value_arg(1 << 31)]
+             => Err(UnsupportedGenericArg { index: 0 }); "gas_reserve_create<2**31>")]
+#[test_case("gas_reserve_release", vec![] => Ok(()); "gas_reserve_release")]
+#[test_case("gas_reserve_release", vec![value_arg(0)] => Err(WrongNumberOfGenericArgs);
+            "gas_reserve_release<0>")]
 #[test_case("felt_add", vec![] => Ok(()); "felt_add")]
 #[test_case("felt_add", vec![value_arg(0)] =>  Ok(()); "felt_add<0>")]
 #[test_case("felt_mul", vec![] => Ok(()); "felt_mul")]
@@ -214,21 +286,54 @@ fn find_type_specialization(
 #[test_case("u128_overflow_sub", vec![] => Ok(()); "u128_overflow_sub")]
 #[test_case("u128_overflow_mul", vec![] => Ok(()); "u128_overflow_mul")]
 #[test_case("u128_safe_divmod", vec![] => Ok(()); "u128_safe_divmod")]
+#[test_case("u128_checked_mul", vec![] => Ok(()); "u128_checked_mul")]
 #[test_case("u128_const", vec![value_arg(8)] => Ok(()); "u128_const<8>")]
-#[test_case("u128_const", vec![] => Err(UnsupportedGenericArg); "u128_const")]
+#[test_case("u128_const", vec![] => Err(WrongNumberOfGenericArgs); "u128_const")]
+#[test_case("u128_const", vec![value_arg(-1)] => Err(UnsupportedGenericArg { index: 0 }); "u128_const<-1>")]
+#[test_case("contract_address_const", vec![value_arg(8)] => Ok(()); "contract_address_const<8>")]
+#[test_case("contract_address_const", vec![GenericArg::Value(BigInt::from(1) << 251)]
+             => Err(UnsupportedGenericArg { index: 0 });
+             "contract_address_const<2**251>")]
+#[test_case("contract_addr_to_felt", vec![] => Ok(()); "contract_addr_to_felt")]
+#[test_case("contract_addr_from_felt", vec![] => Ok(()); "contract_addr_from_felt")]
 #[test_case("storage_address_const", vec![value_arg(8)] => Ok(()); "storage_address_const<8>")]
-#[test_case("storage_address_const", vec![] => Err(UnsupportedGenericArg);
+#[test_case("storage_address_const", vec![] => Err(WrongNumberOfGenericArgs);
 "storage_address_const")]
+#[test_case("storage_base_addr_const", vec![value_arg(8)] => Ok(()); "storage_base_addr_const<8>")]
+#[test_case("storage_base_addr_const", vec![GenericArg::Value((BigInt::from(1) << 251) - 257)]
+             => Ok(());
+             "storage_base_addr_const<2**251-257>")]
+#[test_case("storage_base_addr_const", vec![GenericArg::Value((BigInt::from(1) << 251) - 256)]
+             => Err(UnsupportedGenericArg { index: 0 });
+             "storage_base_addr_const<2**251-256>")]
+#[test_case("storage_base_addr_const", vec![value_arg(-1)] => Err(UnsupportedGenericArg { index: 0 });
+"storage_base_addr_const<-1>")]
+#[test_case("storage_addr_from_base", vec![] => Ok(()); "storage_addr_from_base")]
+#[test_case("storage_base_from_felt", vec![] => Ok(()); "storage_base_from_felt")]
+#[test_case("storage_read_multi", vec![type_arg("felt")] => Ok(()); "storage_read_multi<felt>")]
+#[test_case("storage_read_multi", vec![type_arg("FeltPair")] => Ok(());
+            "storage_read_multi<FeltPair>")]
+#[test_case("storage_read_multi", vec![type_arg("Tuple<>")]
+            => Err(UnsupportedGenericArg { index: 0 }); "storage_read_multi<Tuple<>>")]
+#[test_case("storage_read_multi", vec![type_arg("U128AndFelt")]
+            => Err(UnsupportedGenericArg { index: 0 }); "storage_read_multi<U128AndFelt>")]
+#[test_case("storage_read_multi", vec![type_arg("ArrayFelt")]
+            => Err(UnsupportedGenericArg { index: 0 }); "storage_read_multi<ArrayFelt>")]
+#[test_case("storage_read_multi", vec![type_arg("GasBuiltin")]
+            => Err(UnsupportedGenericArg { index: 0 }); "storage_read_multi<GasBuiltin>")]
+#[test_case("storage_read_multi", vec![type_arg("UninitializedFelt")]
+            => Err(UnsupportedGenericArg { index: 0 }); "storage_read_multi<UninitializedFelt>")]
+#[test_case("storage_read_multi", vec![] => Err(WrongNumberOfGenericArgs); "storage_read_multi")]
 #[test_case("contract_address_const", vec![value_arg(8)] => Ok(()); "contract_address_const<8>")]
-#[test_case("contract_address_const", vec![] => Err(UnsupportedGenericArg);
+#[test_case("contract_address_const", vec![] => Err(WrongNumberOfGenericArgs);
 "contract_address_const")]
 #[test_case("drop", vec![type_arg("u128")] => Ok(()); "drop<u128>")]
 #[test_case("drop", vec![] => Err(WrongNumberOfGenericArgs); "drop<>")]
-#[test_case("drop", vec![type_arg("GasBuiltin")] => Err(UnsupportedGenericArg);
+#[test_case("drop", vec![type_arg("GasBuiltin")] => Err(UnsupportedGenericArg { index: 0 });
 "drop<GasBuiltin>")]
 #[test_case("dup", vec![type_arg("u128")] => Ok(()); "dup<u128>")]
 #[test_case("dup", vec![] => Err(WrongNumberOfGenericArgs); "dup<>")]
-#[test_case("dup", vec![type_arg("GasBuiltin")] => Err(UnsupportedGenericArg);
+#[test_case("dup", vec![type_arg("GasBuiltin")] => Err(UnsupportedGenericArg { index: 0 });
 "dup<GasBuiltin>")]
 #[test_case("u128_jump_nz", vec![] => Ok(()); "u128_jump_nz<>")]
 #[test_case("u128_jump_nz", vec![type_arg("u128")]
@@ -238,7 +343,7 @@ fn find_type_specialization(
 #[test_case("store_temp", vec![type_arg("u128")] => Ok(()); "store_temp<u128>")]
 #[test_case("store_temp", vec![] => Err(WrongNumberOfGenericArgs); "store_temp")]
 #[test_case("align_temps", vec![type_arg("u128")] => Ok(()); "align_temps<u128>")]
-#[test_case("align_temps", vec![value_arg(3)] => Err(UnsupportedGenericArg); "align_temps<3>")]
+#[test_case("align_temps", vec![value_arg(3)] => Err(UnsupportedGenericArg { index: 0 }); "align_temps<3>")]
 #[test_case("align_temps", vec![] => Err(WrongNumberOfGenericArgs); "align_temps")]
 #[test_case("store_local", vec![type_arg("u128")] => Ok(()); "store_local<u128>")]
 #[test_case("store_local", vec![] => Err(WrongNumberOfGenericArgs); "store_local")]
@@ -267,25 +372,61 @@ Ok(());"enum_init<Option,1>")]
 #[test_case("enum_init", vec![value_arg(0)] => Err(WrongNumberOfGenericArgs); "enum_init<0>")]
 #[test_case("enum_init", vec![] => Err(WrongNumberOfGenericArgs); "enum_init")]
 #[test_case("enum_init", vec![value_arg(0),type_arg("Option")]
-            => Err(UnsupportedGenericArg); "enum_init<0,Option>")]
+            => Err(UnsupportedGenericArg { index: 0 }); "enum_init<0,Option>")]
 #[test_case("enum_init", vec![type_arg("Option"), type_arg("Option")]
-            => Err(UnsupportedGenericArg); "enum_init<Option,Option>")]
+            => Err(UnsupportedGenericArg { index: 1 }); "enum_init<Option,Option>")]
 #[test_case("enum_init", vec![value_arg(0), value_arg(0)]
-            => Err(UnsupportedGenericArg); "enum_init<0,0>")]
+            => Err(UnsupportedGenericArg { index: 0 }); "enum_init<0,0>")]
 #[test_case("enum_match", vec![type_arg("Option")] => Ok(()); "enum_match<Option>")]
-#[test_case("enum_match", vec![value_arg(4)] => Err(UnsupportedGenericArg); "enum_match<4>")]
+#[test_case("enum_match", vec![value_arg(4)] => Err(UnsupportedGenericArg { index: 0 }); "enum_match<4>")]
 #[test_case("enum_match", vec![] => Err(WrongNumberOfGenericArgs); "enum_match")]
 #[test_case("struct_construct", vec![type_arg("U128AndFelt")] => Ok(());
             "struct_construct<U128AndFelt>")]
-#[test_case("struct_construct", vec![value_arg(4)] => Err(UnsupportedGenericArg);
+#[test_case("struct_construct", vec![value_arg(4)] => Err(UnsupportedGenericArg { index: 0 });
             "struct_construct<4>")]
 #[test_case("struct_deconstruct", vec![type_arg("U128AndFelt")] => Ok(());
             "struct_deconstruct<U128AndFelt>")]
-#[test_case("struct_deconstruct", vec![value_arg(4)] => Err(UnsupportedGenericArg);
+#[test_case("struct_deconstruct", vec![value_arg(4)] => Err(UnsupportedGenericArg { index: 0 });
             "struct_deconstruct<4>")]
+#[test_case("struct_member_get", vec![type_arg("U128AndFelt"), value_arg(0)] => Ok(());
+            "struct_member_get<U128AndFelt,0>")]
+#[test_case("struct_member_get", vec![type_arg("U128AndFelt"), value_arg(1)] => Ok(());
+            "struct_member_get<U128AndFelt,1>")]
+#[test_case("struct_member_get", vec![type_arg("U128AndFelt"), value_arg(2)]
+            => Err(IndexOutOfRange{index: BigInt::from(2), range_size: 2});
+            "struct_member_get<U128AndFelt,2>")]
+#[test_case("struct_member_get", vec![type_arg("felt"), value_arg(0)]
+            => Err(UnsupportedGenericArg { index: 0 }); "struct_member_get<felt,0>")]
+#[test_case("struct_member_get", vec![type_arg("U128AndFelt")]
+            => Err(WrongNumberOfGenericArgs); "struct_member_get<U128AndFelt>")]
 #[test_case("storage_read_syscall", vec![] => Ok(()); "storage_read_syscall")]
 #[test_case("storage_write_syscall", vec![] => Ok(()); "storage_write_syscall")]
+#[test_case("storage_read_unchecked", vec![] => Ok(()); "storage_read_unchecked")]
+#[test_case("storage_write_unchecked", vec![] => Ok(()); "storage_write_unchecked")]
+#[test_case("storage_read_checked", vec![] => Ok(()); "storage_read_checked")]
+#[test_case("storage_write_checked", vec![] => Ok(()); "storage_write_checked")]
 #[test_case("call_contract_syscall", vec![] => Ok(()); "call_contract_syscall")]
+#[test_case("call_contract_syscall", vec![type_arg("felt")] => Err(WrongNumberOfGenericArgs);
+            "call_contract_syscall<felt>")]
+#[test_case("deploy_syscall", vec![] => Ok(()); "deploy_syscall")]
+#[test_case("library_call_syscall", vec![] => Ok(()); "library_call_syscall")]
+#[test_case("send_message_to_l1", vec![] => Ok(()); "send_message_to_l1")]
+#[test_case("emit_event_syscall", vec![] => Ok(()); "emit_event_syscall")]
+#[test_case("get_caller_address", vec![] => Ok(()); "get_caller_address")]
+#[test_case("get_contract_address", vec![] => Ok(()); "get_contract_address")]
+#[test_case("get_sequencer_address", vec![] => Ok(()); "get_sequencer_address")]
+#[test_case("get_block_number", vec![] => Ok(()); "get_block_number")]
+#[test_case("get_block_timestamp", vec![] => Ok(()); "get_block_timestamp")]
+#[test_case("get_tx_info", vec![] => Ok(()); "get_tx_info")]
+#[test_case("replace_class_syscall", vec![] => Ok(()); "replace_class_syscall")]
+#[test_case("keccak_syscall", vec![] => Ok(()); "keccak_syscall")]
+#[test_case("secp256k1_new", vec![] => Ok(()); "secp256k1_new")]
+#[test_case("secp256k1_add", vec![] => Ok(()); "secp256k1_add")]
+#[test_case("secp256k1_mul", vec![] => Ok(()); "secp256k1_mul")]
+#[test_case("secp256k1_get_xy", vec![] => Ok(()); "secp256k1_get_xy")]
+#[test_case("debug_print", vec![] => Ok(()); "debug_print")]
+#[test_case("debug_print", vec![type_arg("felt")] => Err(WrongNumberOfGenericArgs);
+            "debug_print<felt>")]
 fn find_libfunc_specialization(
     id: &str,
     generic_args: Vec<GenericArg>,
@@ -295,3 +436,75 @@ fn find_libfunc_specialization(
         .specialize(&MockSpecializationContext::new(), &generic_args)
         .map(|_| ())
 }
+
+#[test_case("call_contract_syscall" => b"call_contract".to_vec(); "call_contract_syscall")]
+#[test_case("library_call_syscall" => b"library_call".to_vec(); "library_call_syscall")]
+fn call_syscall_selector(id: &str) -> Vec<u8> {
+    let libfunc = CoreLibFunc::by_id(&id.into())
+        .unwrap()
+        .specialize(&MockSpecializationContext::new(), &[])
+        .unwrap();
+    match libfunc {
+        CoreConcreteLibFunc::StarkNet(
+            StarkNetConcreteLibFunc::CallContract(libfunc)
+            | StarkNetConcreteLibFunc::LibraryCall(libfunc),
+        ) => libfunc.c.to_bytes_be().1,
+        _ => panic!("Expected a call system call."),
+    }
+}
+
+#[test_case(vec![type_arg("T")] => Ok("T".into()); "<T>")]
+#[test_case(vec![value_arg(5)] => Err(UnsupportedGenericArg { index: 0 }); "<5>")]
+#[test_case(vec![] => Err(WrongNumberOfGenericArgs); "<>")]
+#[test_case(vec![type_arg("T"), type_arg("S")] => Err(WrongNumberOfGenericArgs); "<T, S>")]
+fn single_type_arg(generic_args: Vec<GenericArg>) -> Result<ConcreteTypeId, SpecializationError> {
+    args_as_single_type(&generic_args)
+}
+
+#[test_case(vec![value_arg(5)] => Ok(BigInt::from(5)); "<5>")]
+#[test_case(vec![type_arg("T")] => Err(UnsupportedGenericArg { index: 0 }); "<T>")]
+#[test_case(vec![] => Err(WrongNumberOfGenericArgs); "<>")]
+#[test_case(vec![value_arg(5), value_arg(6)] => Err(WrongNumberOfGenericArgs); "<5, 6>")]
+fn single_value_arg(generic_args: Vec<GenericArg>) -> Result<BigInt, SpecializationError> {
+    args_as_single_value(&generic_args)
+}
+
+#[test_case(vec![type_arg("T"), type_arg("S")] => Ok(("T".into(), "S".into())); "<T, S>")]
+#[test_case(vec![value_arg(5), type_arg("S")] => Err(UnsupportedGenericArg { index: 0 });
+            "<5, S>")]
+#[test_case(vec![type_arg("T"), value_arg(5)] => Err(UnsupportedGenericArg { index: 1 });
+            "<T, 5>")]
+#[test_case(vec![type_arg("T")] => Err(WrongNumberOfGenericArgs); "<T>")]
+fn two_type_args(
+    generic_args: Vec<GenericArg>,
+) -> Result<(ConcreteTypeId, ConcreteTypeId), SpecializationError> {
+    args_as_two_types(&generic_args)
+}
+
+#[test_case(vec![type_arg("T"), value_arg(5)] => Ok(("T".into(), BigInt::from(5))); "<T, 5>")]
+#[test_case(vec![value_arg(5), value_arg(5)] => Err(UnsupportedGenericArg { index: 0 });
+            "<5, 5>")]
+#[test_case(vec![type_arg("T"), type_arg("S")] => Err(UnsupportedGenericArg { index: 1 });
+            "<T, S>")]
+#[test_case(vec![value_arg(5)] => Err(WrongNumberOfGenericArgs); "<5>")]
+fn type_and_value_args(
+    generic_args: Vec<GenericArg>,
+) -> Result<(ConcreteTypeId, BigInt), SpecializationError> {
+    args_as_type_and_value(&generic_args)
+}
+
+#[test]
+fn unsupported_generic_arg_message() {
+    assert_eq!(
+        CoreType::by_id(&"Enum".into())
+            .unwrap()
+            .specialize(
+                &MockSpecializationContext::new(),
+                &[user_type_arg("name"), type_arg("u128"), value_arg(5)]
+            )
+            .err()
+            .unwrap()
+            .to_string(),
+        "Provided generic arg #2 is unsupported"
+    );
+}
@@ -54,7 +54,7 @@ pub trait NamedType: Default {
     }
     /// Returns the long ID of the concrete type with `ID` as the generic ID and the given args.
     fn concrete_type_long_id(generic_args: &[GenericArg]) -> ConcreteTypeLongId {
-        ConcreteTypeLongId { generic_id: Self::id(), generic_args: generic_args.to_vec() }
+        ConcreteTypeLongId::new(Self::id(), generic_args.to_vec())
     }
     /// Creates the specialization with the template arguments.
     fn specialize(
@@ -134,6 +134,53 @@ impl ConcreteType for InfoOnlyConcreteType {
     }
 }
 
+/// Defines a type with no generic arguments, specialized into an [InfoOnlyConcreteType] with
+/// constant type info.
+/// Usage example:
+/// ```ignore
+/// define_info_only_type! {
+///     /// Type for felt.
+///     pub struct FeltType {
+///         id: "felt",
+///         storable: true,
+///         droppable: true,
+///         duplicatable: true,
+///         size: 1,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! define_info_only_type {
+    ($(#[$attr:meta])* pub struct $name:ident {
+        id: $id:literal,
+        storable: $storable:expr,
+        droppable: $droppable:expr,
+        duplicatable: $duplicatable:expr,
+        size: $size:expr $(,)?
+    }) => {
+        $(#[$attr])*
+        #[derive(Default)]
+        pub struct $name {}
+        impl $crate::extensions::NoGenericArgsGenericType for $name {
+            type Concrete = $crate::extensions::types::InfoOnlyConcreteType;
+            const ID: $crate::ids::GenericTypeId = $crate::ids::GenericTypeId::new_inline($id);
+
+            fn specialize(&self) -> Self::Concrete {
+                use $crate::extensions::NamedType;
+                $crate::extensions::types::InfoOnlyConcreteType {
+                    info: $crate::extensions::types::TypeInfo {
+                        long_id: Self::concrete_type_long_id(&[]),
+                        storable: $storable,
+                        droppable: $droppable,
+                        duplicatable: $duplicatable,
+                        size: $size,
+                    },
+                }
+            }
+        }
+    };
+}
+
 /// Forms a Sierra type used by extensions type from an enum of such types.
 /// The new enum implements GenericType.
 /// All the variant types must also implement GenericType.
@@ -0,0 +1,244 @@
+//! Extraction of the part of a program reachable from one of its functions, as a standalone
+//! program.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use thiserror::Error;
+
+use crate::extensions::core::{CoreLibFunc, CoreType};
+use crate::extensions::ConcreteLibFunc;
+use crate::ids::{ConcreteLibFuncId, ConcreteTypeId, FunctionId};
+use crate::program::{
+    BranchTarget, Function, GenericArg, LibFuncDeclaration, Program, Statement, StatementIdx,
+    TypeDeclaration,
+};
+use crate::program_registry::{ProgramRegistry, ProgramRegistryError};
+
+#[cfg(test)]
+#[path = "extraction_test.rs"]
+mod test;
+
+/// Error occurring while extracting the program of a function.
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum ExtractionError {
+    #[error("error from the program registry")]
+    ProgramRegistryError(#[from] Box<ProgramRegistryError>),
+    #[error("function `{0}` was not found in the program")]
+    MissingFunction(FunctionId),
+    #[error("libfunc `{0}` was not declared in the program")]
+    MissingLibFunc(ConcreteLibFuncId),
+    #[error("type `{0}` was not declared in the program")]
+    MissingType(ConcreteTypeId),
+    #[error("found an illegal statement index during extraction")]
+    StatementOutOfBounds(StatementIdx),
+}
+
+/// The parts of a program reachable from one of its functions.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Reachable {
+    /// The reachable functions - the extracted function and the functions it calls, directly or
+    /// indirectly.
+    pub functions: HashSet<FunctionId>,
+    /// The reachable statements - the statements of the reachable functions that may run.
+    pub statements: HashSet<StatementIdx>,
+    /// The libfuncs used by the reachable statements, or by the generic arguments of other
+    /// reachable libfuncs.
+    pub libfuncs: HashSet<ConcreteLibFuncId>,
+    /// The types used by the signatures of the reachable functions and libfuncs, or by the generic
+    /// arguments of reachable libfuncs and types.
+    pub types: HashSet<ConcreteTypeId>,
+}
+
+/// Helper for collecting the parts of a program reachable from a function.
+struct ReachabilityCollector<'a> {
+    registry: ProgramRegistry<CoreType, CoreLibFunc>,
+    functions: HashMap<&'a FunctionId, &'a Function>,
+    libfuncs: HashMap<&'a ConcreteLibFuncId, &'a LibFuncDeclaration>,
+    types: HashMap<&'a ConcreteTypeId, &'a TypeDeclaration>,
+    reachable: Reachable,
+    /// Statements reached but not yet handled.
+    pending_statements: VecDeque<StatementIdx>,
+}
+impl<'a> ReachabilityCollector<'a> {
+    fn new(program: &'a Program) -> Result<Self, ExtractionError> {
+        Ok(Self {
+            registry: ProgramRegistry::new(program)?,
+            functions: program.funcs.iter().map(|func| (&func.id, func)).collect(),
+            libfuncs: program
+                .libfunc_declarations
+                .iter()
+                .map(|declaration| (&declaration.id, declaration))
+                .collect(),
+            types: program
+                .type_declarations
+                .iter()
+                .map(|declaration| (&declaration.id, declaration))
+                .collect(),
+            reachable: Reachable::default(),
+            pending_statements: VecDeque::new(),
+        })
+    }
+
+    /// Marks a function as reachable, along with its signature types and entry point.
+    fn add_function(&mut self, id: &FunctionId) -> Result<(), ExtractionError> {
+        if self.reachable.functions.contains(id) {
+            return Ok(());
+        }
+        let func =
+            *self.functions.get(id).ok_or_else(|| ExtractionError::MissingFunction(id.clone()))?;
+        self.reachable.functions.insert(id.clone());
+        for ty in func.signature.param_types.iter().chain(&func.signature.ret_types) {
+            self.add_type(ty)?;
+        }
+        self.add_statement(func.entry_point);
+        Ok(())
+    }
+
+    /// Marks a libfunc as reachable, along with its generic arguments and signature types.
+    /// The signature types are required as well, since the libfunc may use types not appearing in
+    /// its generic arguments (such as the `NonZero<felt>` output of `felt_jump_nz`).
+    fn add_libfunc(&mut self, id: &ConcreteLibFuncId) -> Result<(), ExtractionError> {
+        if self.reachable.libfuncs.contains(id) {
+            return Ok(());
+        }
+        let declaration =
+            *self.libfuncs.get(id).ok_or_else(|| ExtractionError::MissingLibFunc(id.clone()))?;
+        self.reachable.libfuncs.insert(id.clone());
+        let libfunc = self.registry.get_libfunc(id)?;
+        let signature_types: Vec<_> = libfunc
+            .param_signatures()
+            .iter()
+            .map(|param_signature| param_signature.ty.clone())
+            .chain(libfunc.output_types().into_iter().flatten())
+            .collect();
+        for ty in &signature_types {
+            self.add_type(ty)?;
+        }
+        self.add_generic_args(&declaration.long_id.generic_args)
+    }
+
+    /// Marks a type as reachable, along with its generic arguments.
+    fn add_type(&mut self, id: &ConcreteTypeId) -> Result<(), ExtractionError> {
+        if self.reachable.types.contains(id) {
+            return Ok(());
+        }
+        let declaration =
+            *self.types.get(id).ok_or_else(|| ExtractionError::MissingType(id.clone()))?;
+        self.reachable.types.insert(id.clone());
+        self.add_generic_args(&declaration.long_id.generic_args)
+    }
+
+    /// Marks the objects referenced by generic arguments as reachable.
+    fn add_generic_args(&mut self, generic_args: &[GenericArg]) -> Result<(), ExtractionError> {
+        for generic_arg in generic_args {
+            match generic_arg {
+                GenericArg::Type(ty) => self.add_type(ty)?,
+                GenericArg::LibFunc(libfunc) => self.add_libfunc(libfunc)?,
+                GenericArg::UserFunc(function) => self.add_function(function)?,
+                GenericArg::UserType(_) | GenericArg::Value(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks a statement as reachable, to be handled if it was not reached before.
+    fn add_statement(&mut self, idx: StatementIdx) {
+        if self.reachable.statements.insert(idx) {
+            self.pending_statements.push_back(idx);
+        }
+    }
+
+    /// Handles the pending statements, until all the reachable statements are handled.
+    fn handle_pending_statements(&mut self, program: &Program) -> Result<(), ExtractionError> {
+        while let Some(idx) = self.pending_statements.pop_front() {
+            match program.get_statement(&idx) {
+                Some(Statement::Return(_)) => {}
+                Some(Statement::Invocation(invocation)) => {
+                    self.add_libfunc(&invocation.libfunc_id)?;
+                    for branch in &invocation.branches {
+                        self.add_statement(idx.next(&branch.target));
+                    }
+                }
+                None => return Err(ExtractionError::StatementOutOfBounds(idx)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the parts of `program` reachable from the function `function_id`, following both the
+/// branches of the statements and the calls to other functions.
+/// The program is validated by the program registry, used for the signatures of the libfuncs.
+pub fn reachable_from_function(
+    program: &Program,
+    function_id: &FunctionId,
+) -> Result<Reachable, ExtractionError> {
+    let mut collector = ReachabilityCollector::new(program)?;
+    collector.add_function(function_id)?;
+    collector.handle_pending_statements(program)?;
+    Ok(collector.reachable)
+}
+
+/// Returns a standalone program containing only the parts of `program` reachable from the function
+/// `function_id`.
+/// The order of the declarations, statements and functions, as well as the debug names of the ids
+/// and the statement locations, are preserved. The extracted program is validated by the program
+/// registry as well.
+pub fn extract_entry_point_program(
+    program: &Program,
+    function_id: &FunctionId,
+) -> Result<Program, ExtractionError> {
+    let reachable = reachable_from_function(program, function_id)?;
+    let mut kept_statements: Vec<_> = reachable.statements.iter().map(|idx| idx.0).collect();
+    kept_statements.sort_unstable();
+    let new_indices: HashMap<usize, StatementIdx> = kept_statements
+        .iter()
+        .enumerate()
+        .map(|(new_idx, old_idx)| (*old_idx, StatementIdx(new_idx)))
+        .collect();
+    // Since all the successors of a kept statement are kept, the statement following a kept
+    // statement in the original program follows it in the extracted program as well, so
+    // fallthrough targets remain valid.
+    let statements = kept_statements
+        .iter()
+        .map(|idx| match program.statements[*idx].clone() {
+            Statement::Invocation(mut invocation) => {
+                for branch in &mut invocation.branches {
+                    if let BranchTarget::Statement(target) = &mut branch.target {
+                        *target = new_indices[&target.0];
+                    }
+                }
+                Statement::Invocation(invocation)
+            }
+            statement @ Statement::Return(_) => statement,
+        })
+        .collect();
+    let extracted = Program {
+        type_declarations: program
+            .type_declarations
+            .iter()
+            .filter(|declaration| reachable.types.contains(&declaration.id))
+            .cloned()
+            .collect(),
+        libfunc_declarations: program
+            .libfunc_declarations
+            .iter()
+            .filter(|declaration| reachable.libfuncs.contains(&declaration.id))
+            .cloned()
+            .collect(),
+        statements,
+        funcs: program
+            .funcs
+            .iter()
+            .filter(|func| reachable.functions.contains(&func.id))
+            .map(|func| Function { entry_point: new_indices[&func.entry_point.0], ..func.clone() })
+            .collect(),
+        statement_locations: program
+            .statement_locations
+            .iter()
+            .filter_map(|(idx, location)| Some((*new_indices.get(&idx.0)?, *location)))
+            .collect(),
+    };
+    ProgramRegistry::<CoreType, CoreLibFunc>::new(&extracted)?;
+    Ok(extracted)
+}
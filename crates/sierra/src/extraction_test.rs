@@ -0,0 +1,123 @@
+use indoc::indoc;
+use test_log::test;
+
+use super::{extract_entry_point_program, reachable_from_function, ExtractionError};
+use crate::program::{Program, StatementIdx, StatementLocation};
+use crate::ProgramParser;
+
+/// Returns a program with three functions - `square`, `square_plus`, calling `square`, and
+/// `or_one`, replacing zero with one.
+fn three_functions_program() -> Program {
+    ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type NonZeroFelt = NonZero<felt>;
+
+            libfunc dup_felt = dup<felt>;
+            libfunc felt_mul = felt_mul;
+            libfunc felt_add = felt_add;
+            libfunc felt_const_1 = felt_const<1>;
+            libfunc felt_jump_nz = felt_jump_nz;
+            libfunc felt_unwrap_nz = unwrap_nz<felt>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc call_square = function_call<user@square>;
+
+            dup_felt([0]) -> ([0], [1]);
+            felt_mul([0], [1]) -> ([0]);
+            store_temp_felt([0]) -> ([0]);
+            return([0]);
+            store_temp_felt([0]) -> ([0]);
+            call_square([0]) -> ([0]);
+            felt_add([0], [1]) -> ([0]);
+            store_temp_felt([0]) -> ([0]);
+            return([0]);
+            felt_jump_nz([0]) { fallthrough() 13([0]) };
+            felt_const_1() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            return([1]);
+            felt_unwrap_nz([0]) -> ([0]);
+            store_temp_felt([0]) -> ([0]);
+            return([0]);
+
+            square@0([0]: felt) -> (felt);
+            square_plus@4([0]: felt, [1]: felt) -> (felt);
+            or_one@9([0]: felt) -> (felt);
+        "})
+        .unwrap()
+}
+
+#[test]
+fn extract_with_callee() {
+    let program = three_functions_program();
+    let reachable = reachable_from_function(&program, &"square_plus".into()).unwrap();
+    assert_eq!(reachable.functions, ["square".into(), "square_plus".into()].into_iter().collect());
+    assert_eq!(reachable.statements, (0..9).map(StatementIdx).collect());
+    assert_eq!(
+        extract_entry_point_program(&program, &"square_plus".into()),
+        Ok(ProgramParser::new()
+            .parse(indoc! {"
+                type felt = felt;
+
+                libfunc dup_felt = dup<felt>;
+                libfunc felt_mul = felt_mul;
+                libfunc felt_add = felt_add;
+                libfunc store_temp_felt = store_temp<felt>;
+                libfunc call_square = function_call<user@square>;
+
+                dup_felt([0]) -> ([0], [1]);
+                felt_mul([0], [1]) -> ([0]);
+                store_temp_felt([0]) -> ([0]);
+                return([0]);
+                store_temp_felt([0]) -> ([0]);
+                call_square([0]) -> ([0]);
+                felt_add([0], [1]) -> ([0]);
+                store_temp_felt([0]) -> ([0]);
+                return([0]);
+
+                square@0([0]: felt) -> (felt);
+                square_plus@4([0]: felt, [1]: felt) -> (felt);
+            "})
+            .unwrap())
+    );
+}
+
+#[test]
+fn extract_with_jump() {
+    let mut program = three_functions_program();
+    let location = StatementLocation { file_id: 0, start: 5, end: 8 };
+    program.statement_locations =
+        [(StatementIdx(2), location), (StatementIdx(13), location)].into();
+    let mut expected = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type NonZeroFelt = NonZero<felt>;
+
+            libfunc felt_const_1 = felt_const<1>;
+            libfunc felt_jump_nz = felt_jump_nz;
+            libfunc felt_unwrap_nz = unwrap_nz<felt>;
+            libfunc store_temp_felt = store_temp<felt>;
+
+            felt_jump_nz([0]) { fallthrough() 4([0]) };
+            felt_const_1() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            return([1]);
+            felt_unwrap_nz([0]) -> ([0]);
+            store_temp_felt([0]) -> ([0]);
+            return([0]);
+
+            or_one@0([0]: felt) -> (felt);
+        "})
+        .unwrap();
+    // `NonZeroFelt` is kept, as it is used by the signatures of the libfuncs, and the location of
+    // the jump target is moved along with it.
+    expected.statement_locations = [(StatementIdx(4), location)].into();
+    assert_eq!(extract_entry_point_program(&program, &"or_one".into()), Ok(expected));
+}
+
+#[test]
+fn extract_missing_function() {
+    assert_eq!(
+        extract_entry_point_program(&three_functions_program(), &"cube".into()),
+        Err(ExtractionError::MissingFunction("cube".into()))
+    );
+}
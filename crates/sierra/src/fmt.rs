@@ -9,7 +9,7 @@ use crate::ids::{
 use crate::program::{
     ConcreteLibFuncLongId, ConcreteTypeLongId, Function, GenBranchInfo, GenBranchTarget,
     GenInvocation, GenStatement, GenericArg, LibFuncDeclaration, Param, Program, StatementIdx,
-    TypeDeclaration,
+    StatementLocation, TypeDeclaration,
 };
 
 impl fmt::Display for Program {
@@ -22,8 +22,13 @@ impl fmt::Display for Program {
             writeln!(f, "{declaration};")?;
         }
         writeln!(f)?;
-        for statement in &self.statements {
-            writeln!(f, "{statement};")?;
+        for (idx, statement) in self.statements.iter().enumerate() {
+            write!(f, "{statement};")?;
+            // Locations are written as comments, so that the result is still parsable.
+            if let Some(location) = self.get_statement_location(&StatementIdx(idx)) {
+                write!(f, " // {location}")?;
+            }
+            writeln!(f)?;
         }
         writeln!(f)?;
         for func in &self.funcs {
@@ -156,6 +161,12 @@ impl<StatementId: fmt::Display> fmt::Display for GenBranchTarget<StatementId> {
     }
 }
 
+impl fmt::Display for StatementLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "@{}:{}..{}", self.file_id, self.start, self.end)
+    }
+}
+
 impl fmt::Display for StatementIdx {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -1,5 +1,6 @@
 use derivative::Derivative;
 use salsa;
+use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 
 const fn id_from_string(s: &str) -> u64 {
@@ -10,7 +11,7 @@ const fn id_from_string(s: &str) -> u64 {
 macro_rules! define_identity {
     ($doc:literal, $type_name:ident) => {
         #[doc=$doc]
-        #[derive(Clone, Debug, Derivative)]
+        #[derive(Clone, Debug, Derivative, Serialize, Deserialize)]
         #[derivative(Eq, Hash, PartialEq)]
         pub struct $type_name {
             pub id: u64,
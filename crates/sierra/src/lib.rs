@@ -13,6 +13,7 @@ use lalrpop_util::lalrpop_mod;
 
 pub mod edit_state;
 pub mod extensions;
+pub mod extraction;
 pub mod fmt;
 pub mod ids;
 pub mod program;
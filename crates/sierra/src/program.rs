@@ -1,12 +1,16 @@
+use std::collections::HashMap;
+
 use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
 
 use crate::ids::{
     ConcreteLibFuncId, ConcreteTypeId, FunctionId, GenericLibFuncId, GenericTypeId, UserTypeId,
     VarId,
 };
+use crate::serialization::{deserialize_big_int, serialize_big_int};
 
 /// A full Sierra program.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Program {
     /// Declarations for all the used types.
     pub type_declarations: Vec<TypeDeclaration>,
@@ -16,15 +20,35 @@ pub struct Program {
     pub statements: Vec<Statement>,
     /// Descriptions of the functions - signatures and entry points.
     pub funcs: Vec<Function>,
+    /// The source locations of the statements, for statements that have one.
+    /// Only used for debugging - written as comments in the textual representation of the program,
+    /// and therefore not restored by parsing it.
+    pub statement_locations: HashMap<StatementIdx, StatementLocation>,
 }
 impl Program {
     pub fn get_statement(&self, id: &StatementIdx) -> Option<&Statement> {
         self.statements.get(id.0)
     }
+
+    pub fn get_statement_location(&self, id: &StatementIdx) -> Option<&StatementLocation> {
+        self.statement_locations.get(id)
+    }
+}
+
+/// The source location a statement originated from.
+/// Opaque to Sierra - the meaning of the file id is determined by the generator of the program.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct StatementLocation {
+    /// The id of the source file.
+    pub file_id: usize,
+    /// The offset of the start of the span within the file.
+    pub start: usize,
+    /// The offset of the end of the span within the file.
+    pub end: usize,
 }
 
 /// Declaration of a concrete type.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TypeDeclaration {
     /// The id of the declared concrete type.
     pub id: ConcreteTypeId,
@@ -32,16 +56,21 @@ pub struct TypeDeclaration {
 }
 
 /// A concrete type (the generic parent type and the generic arguments).
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct ConcreteTypeLongId {
     /// The id of the used generic type.
     pub generic_id: GenericTypeId,
     /// The arguments for the generic type.
     pub generic_args: Vec<GenericArg>,
 }
+impl ConcreteTypeLongId {
+    pub fn new(generic_id: GenericTypeId, generic_args: Vec<GenericArg>) -> Self {
+        Self { generic_id, generic_args }
+    }
+}
 
 /// Declaration of a concrete library function.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct LibFuncDeclaration {
     /// The id of the declared concrete libfunc.
     pub id: ConcreteLibFuncId,
@@ -49,7 +78,7 @@ pub struct LibFuncDeclaration {
 }
 
 /// A concrete library function (the generic parent function and the generic arguments).
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct ConcreteLibFuncLongId {
     /// The id of the used generic libfunc.
     pub generic_id: GenericLibFuncId,
@@ -58,7 +87,7 @@ pub struct ConcreteLibFuncLongId {
 }
 
 /// Represents the signature of a function.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct FunctionSignature {
     /// The types of the parameters of the function.
     pub param_types: Vec<ConcreteTypeId>,
@@ -67,7 +96,7 @@ pub struct FunctionSignature {
 }
 
 /// Represents a function (its name, signature and entry point).
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct GenFunction<StatementId> {
     /// The name of the function.
     pub id: FunctionId,
@@ -99,14 +128,15 @@ impl<StatementId> GenFunction<StatementId> {
 }
 
 /// Descriptor of a variable.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Param {
     pub id: VarId,
     pub ty: ConcreteTypeId,
 }
 
 /// Represents the index of a Sierra statement in the Program::statements vector.
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct StatementIdx(pub usize);
 impl StatementIdx {
     pub fn next(&self, target: &BranchTarget) -> StatementIdx {
@@ -118,24 +148,38 @@ impl StatementIdx {
 }
 
 /// Possible arguments for generic type.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum GenericArg {
     UserType(UserTypeId),
     Type(ConcreteTypeId),
-    Value(BigInt),
+    Value(
+        #[serde(serialize_with = "serialize_big_int", deserialize_with = "deserialize_big_int")]
+        BigInt,
+    ),
     UserFunc(FunctionId),
     LibFunc(ConcreteLibFuncId),
 }
+impl GenericArg {
+    /// Returns a concrete type argument.
+    pub fn ty(id: ConcreteTypeId) -> Self {
+        GenericArg::Type(id)
+    }
+
+    /// Returns a value argument.
+    pub fn value(value: impl Into<BigInt>) -> Self {
+        GenericArg::Value(value.into())
+    }
+}
 
 /// A possible statement.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum GenStatement<StatementId> {
     Invocation(GenInvocation<StatementId>),
     Return(Vec<VarId>),
 }
 
 /// An invocation statement.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct GenInvocation<StatementId> {
     /// The called libfunc.
     pub libfunc_id: ConcreteLibFuncId,
@@ -147,7 +191,7 @@ pub struct GenInvocation<StatementId> {
 }
 
 /// Describes the flow of a chosen libfunc's branch.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct GenBranchInfo<StatementId> {
     /// The target the branch continues the run through.
     pub target: GenBranchTarget<StatementId>,
@@ -155,7 +199,7 @@ pub struct GenBranchInfo<StatementId> {
     pub results: Vec<VarId>,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum GenBranchTarget<StatementId> {
     /// Continues a run to the next statement.
     Fallthrough,
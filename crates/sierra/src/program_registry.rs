@@ -1,7 +1,10 @@
+#[cfg(any(feature = "testing", test))]
+use std::cell::Cell;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
 use thiserror::Error;
+use utils::diagnostic::Diagnostic;
 
 use crate::extensions::lib_func::{
     SierraApChange, SignatureSpecializationContext, SpecializationContext,
@@ -11,7 +14,7 @@ use crate::extensions::types::TypeInfo;
 use crate::extensions::{
     ConcreteType, ExtensionError, GenericLibFunc, GenericLibFuncEx, GenericType, GenericTypeEx,
 };
-use crate::ids::{ConcreteLibFuncId, ConcreteTypeId, FunctionId, GenericTypeId};
+use crate::ids::{ConcreteLibFuncId, ConcreteTypeId, FunctionId, GenericLibFuncId, GenericTypeId};
 use crate::program::{Function, FunctionSignature, GenericArg, Program, TypeDeclaration};
 
 #[cfg(test)]
@@ -41,14 +44,65 @@ pub enum ProgramRegistryError {
     MissingLibFunc(ConcreteLibFuncId),
 }
 
+impl From<ProgramRegistryError> for Diagnostic {
+    fn from(error: ProgramRegistryError) -> Self {
+        let message = error.to_string();
+        let (code, id) = match error {
+            ProgramRegistryError::TypeSpecialization { concrete_id, error } => {
+                let diagnostic = Diagnostic::from(error);
+                return Diagnostic {
+                    message: format!("Type `{concrete_id}`: {}", diagnostic.message),
+                    ..diagnostic
+                };
+            }
+            ProgramRegistryError::LibFuncSpecialization { concrete_id, error } => {
+                let diagnostic = Diagnostic::from(error);
+                return Diagnostic {
+                    message: format!("Libfunc `{concrete_id}`: {}", diagnostic.message),
+                    ..diagnostic
+                };
+            }
+            ProgramRegistryError::FunctionIdAlreadyExists(id) => ("E0010", id.to_string()),
+            ProgramRegistryError::MissingFunction(id) => ("E0011", id.to_string()),
+            ProgramRegistryError::TypeConcreteIdAlreadyExists(id) => ("E0012", id.to_string()),
+            ProgramRegistryError::TypeAlreadyDeclared(declaration) => {
+                ("E0013", declaration.long_id.to_string())
+            }
+            ProgramRegistryError::MissingType(id) => ("E0014", id.to_string()),
+            ProgramRegistryError::LibFuncConcreteIdAlreadyExists(id) => ("E0015", id.to_string()),
+            ProgramRegistryError::MissingLibFunc(id) => ("E0016", id.to_string()),
+        };
+        Diagnostic::error(code, format!("{message}: `{id}`."))
+    }
+}
+
 type TypeMap<TType> = HashMap<ConcreteTypeId, TType>;
 type LibFuncMap<TLibFunc> = HashMap<ConcreteLibFuncId, TLibFunc>;
+/// Mapping from the ids of libfunc declarations identical to an earlier declaration, to the id of
+/// that earlier declaration.
+type LibFuncAliasMap = HashMap<ConcreteLibFuncId, ConcreteLibFuncId>;
 type FunctionMap = HashMap<FunctionId, Function>;
 /// Mapping from the arguments for generating a concrete type (the generic-id and the arguments) to
 /// the concrete-id that points to it.
+/// The arguments are borrowed from the program's type declarations, so looking up a concrete type
+/// while specializing libfuncs requires no allocations.
 type ConcreteTypeIdMap<'a> = HashMap<(GenericTypeId, &'a [GenericArg]), ConcreteTypeId>;
 
+/// Statistics of the concrete type lookups performed while specializing the libfuncs of a program.
+#[cfg(any(feature = "testing", test))]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TypeLookupStats {
+    /// The number of lookups of a declared concrete type.
+    pub hits: usize,
+    /// The number of lookups of a type missing from the type declarations.
+    pub misses: usize,
+}
+
 /// Registry for the data of the compiler, for all program specific data.
+///
+/// Libfuncs are specialized by `TLibFunc`, so libfuncs defined outside of this crate can be
+/// registered by wrapping them together with the core libfuncs in a hierarchy defined by
+/// [define_libfunc_hierarchy](crate::define_libfunc_hierarchy).
 pub struct ProgramRegistry<TType: GenericType, TLibFunc: GenericLibFunc> {
     /// Mapping ids to the corresponding user function declaration from the program.
     functions: FunctionMap,
@@ -56,6 +110,12 @@ pub struct ProgramRegistry<TType: GenericType, TLibFunc: GenericLibFunc> {
     concrete_types: TypeMap<TType::Concrete>,
     /// Mapping ids to the concrete libfuncs reperesented by them.
     concrete_libfuncs: LibFuncMap<TLibFunc::Concrete>,
+    /// Mapping ids of repeated libfunc declarations to the id of their first declaration, which
+    /// holds the shared concrete libfunc.
+    libfunc_aliases: LibFuncAliasMap,
+    /// The concrete type lookups performed while specializing the libfuncs.
+    #[cfg(any(feature = "testing", test))]
+    type_lookup_stats: TypeLookupStats,
 }
 impl<TType: GenericType, TLibFunc: GenericLibFunc> ProgramRegistry<TType, TLibFunc> {
     /// Create a registry for the program.
@@ -63,18 +123,38 @@ impl<TType: GenericType, TLibFunc: GenericLibFunc> ProgramRegistry<TType, TLibFu
         program: &Program,
         function_ap_change: HashMap<FunctionId, usize>,
     ) -> Result<ProgramRegistry<TType, TLibFunc>, Box<ProgramRegistryError>> {
-        let functions = get_functions(program)?;
-        let (concrete_types, concrete_type_ids) = get_concrete_types_maps::<TType>(program)?;
-        let concrete_libfuncs = get_concrete_libfuncs::<TType, TLibFunc>(
-            program,
-            &SpecializationContextForRegistry {
-                functions: &functions,
-                concrete_type_ids: &concrete_type_ids,
-                concrete_types: &concrete_types,
-                function_ap_change,
-            },
-        )?;
-        Ok(ProgramRegistry { functions, concrete_types, concrete_libfuncs })
+        Self::with_ap_change_collecting_errors(program, function_ap_change)
+            .map_err(|errors| Box::new(errors.into_iter().next().unwrap()))
+    }
+
+    /// Create a registry for the program, returning all the errors found on failure.
+    /// Function and type errors are still reported one at a time, as the later declarations may
+    /// depend on the failing ones, but every libfunc declaration is specialized independently.
+    pub fn with_ap_change_collecting_errors(
+        program: &Program,
+        function_ap_change: HashMap<FunctionId, usize>,
+    ) -> Result<ProgramRegistry<TType, TLibFunc>, Vec<ProgramRegistryError>> {
+        let functions = get_functions(program).map_err(|error| vec![*error])?;
+        let (concrete_types, concrete_type_ids) =
+            get_concrete_types_maps::<TType>(program).map_err(|error| vec![*error])?;
+        let context = SpecializationContextForRegistry {
+            functions: &functions,
+            concrete_type_ids: &concrete_type_ids,
+            concrete_types: &concrete_types,
+            function_ap_change,
+            #[cfg(any(feature = "testing", test))]
+            type_lookup_stats: Cell::default(),
+        };
+        let (concrete_libfuncs, libfunc_aliases) =
+            get_concrete_libfuncs::<TType, TLibFunc>(program, &context)?;
+        Ok(ProgramRegistry {
+            #[cfg(any(feature = "testing", test))]
+            type_lookup_stats: context.type_lookup_stats.get(),
+            functions,
+            concrete_types,
+            concrete_libfuncs,
+            libfunc_aliases,
+        })
     }
 
     pub fn new(
@@ -106,9 +186,14 @@ impl<TType: GenericType, TLibFunc: GenericLibFunc> ProgramRegistry<TType, TLibFu
         id: &ConcreteLibFuncId,
     ) -> Result<&'a TLibFunc::Concrete, Box<ProgramRegistryError>> {
         self.concrete_libfuncs
-            .get(id)
+            .get(self.libfunc_aliases.get(id).unwrap_or(id))
             .ok_or_else(|| Box::new(ProgramRegistryError::MissingLibFunc(id.clone())))
     }
+    /// Get the statistics of the concrete type lookups performed while specializing the libfuncs.
+    #[cfg(any(feature = "testing", test))]
+    pub fn type_lookup_stats(&self) -> TypeLookupStats {
+        self.type_lookup_stats
+    }
 }
 
 /// Creates the functions map.
@@ -180,6 +265,9 @@ pub struct SpecializationContextForRegistry<'a, TType: GenericType> {
     pub concrete_types: &'a TypeMap<TType::Concrete>,
     /// AP changes information for Sierra user functions.
     pub function_ap_change: HashMap<FunctionId, usize>,
+    /// The concrete type lookups performed so far.
+    #[cfg(any(feature = "testing", test))]
+    type_lookup_stats: Cell<TypeLookupStats>,
 }
 impl<TType: GenericType> TypeSpecializationContext for SpecializationContextForRegistry<'_, TType> {
     fn try_get_type_info(&self, id: ConcreteTypeId) -> Option<TypeInfo> {
@@ -194,7 +282,18 @@ impl<TType: GenericType> SignatureSpecializationContext
         id: GenericTypeId,
         generic_args: &[GenericArg],
     ) -> Option<ConcreteTypeId> {
-        self.concrete_type_ids.get(&(id, generic_args)).cloned()
+        let concrete_id = self.concrete_type_ids.get(&(id, generic_args)).cloned();
+        #[cfg(any(feature = "testing", test))]
+        {
+            let mut stats = self.type_lookup_stats.get();
+            if concrete_id.is_some() {
+                stats.hits += 1;
+            } else {
+                stats.misses += 1;
+            }
+            self.type_lookup_stats.set(stats);
+        }
+        concrete_id
     }
 
     fn try_get_function_signature(&self, function_id: &FunctionId) -> Option<FunctionSignature> {
@@ -223,28 +322,53 @@ impl<TType: GenericType> SpecializationContext for SpecializationContextForRegis
     }
 }
 
-/// Creates the libfuncs map.
+/// Creates the libfuncs map, and the alias map of repeated declarations. Returns the errors of all
+/// the failing declarations on failure.
+///
+/// Specialization is pure given the context, so a declaration identical to an earlier successfully
+/// specialized one is not specialized again, and is instead aliased to the earlier declaration.
 fn get_concrete_libfuncs<TType: GenericType, TLibFunc: GenericLibFunc>(
     program: &Program,
     context: &SpecializationContextForRegistry<'_, TType>,
-) -> Result<LibFuncMap<TLibFunc::Concrete>, Box<ProgramRegistryError>> {
+) -> Result<(LibFuncMap<TLibFunc::Concrete>, LibFuncAliasMap), Vec<ProgramRegistryError>> {
     let mut concrete_libfuncs = HashMap::new();
+    let mut libfunc_aliases = LibFuncAliasMap::new();
+    let mut specialized_ids =
+        HashMap::<(GenericLibFuncId, &[GenericArg]), ConcreteLibFuncId>::new();
+    let mut errors = vec![];
     for declaration in &program.libfunc_declarations {
-        let concrete_libfunc = TLibFunc::specialize_by_id(
+        if concrete_libfuncs.contains_key(&declaration.id)
+            || libfunc_aliases.contains_key(&declaration.id)
+        {
+            errors
+                .push(ProgramRegistryError::LibFuncConcreteIdAlreadyExists(declaration.id.clone()));
+            continue;
+        }
+        let key = (declaration.long_id.generic_id.clone(), &declaration.long_id.generic_args[..]);
+        if let Some(specialized_id) = specialized_ids.get(&key) {
+            libfunc_aliases.insert(declaration.id.clone(), specialized_id.clone());
+            continue;
+        }
+        let concrete_libfunc = match TLibFunc::specialize_by_id(
             context,
             &declaration.long_id.generic_id,
             &declaration.long_id.generic_args,
-        )
-        .map_err(|error| ProgramRegistryError::LibFuncSpecialization {
-            concrete_id: declaration.id.clone(),
-            error,
-        })?;
-        match concrete_libfuncs.entry(declaration.id.clone()) {
-            Entry::Occupied(_) => {
-                Err(ProgramRegistryError::LibFuncConcreteIdAlreadyExists(declaration.id.clone()))
+        ) {
+            Ok(concrete_libfunc) => concrete_libfunc,
+            Err(error) => {
+                errors.push(ProgramRegistryError::LibFuncSpecialization {
+                    concrete_id: declaration.id.clone(),
+                    error,
+                });
+                continue;
             }
-            Entry::Vacant(entry) => Ok(entry.insert(concrete_libfunc)),
-        }?;
+        };
+        concrete_libfuncs.insert(declaration.id.clone(), concrete_libfunc);
+        specialized_ids.insert(key, declaration.id.clone());
+    }
+    if errors.is_empty() {
+        Ok((concrete_libfuncs, libfunc_aliases))
+    } else {
+        Err(errors)
     }
-    Ok(concrete_libfuncs)
 }
@@ -1,10 +1,20 @@
 use indoc::indoc;
+use test_case::test_case;
 use test_log::test;
 
-use crate::extensions::core::{CoreLibFunc, CoreType};
+use crate::extensions::core::{CoreConcreteLibFunc, CoreLibFunc, CoreType};
+use crate::extensions::felt::FeltType;
+use crate::extensions::lib_func::{
+    LibFuncSignature, OutputVarInfo, SierraApChange, SignatureSpecializationContext,
+};
+use crate::extensions::{
+    ConcreteLibFunc, ExtensionError, GenericLibFunc, NamedType, NoGenericArgsGenericLibFunc,
+    OutputVarReferenceInfo, SpecializationError,
+};
+use crate::ids::{ConcreteTypeId, GenericLibFuncId};
 use crate::program::{ConcreteTypeLongId, TypeDeclaration};
-use crate::program_registry::{ProgramRegistry, ProgramRegistryError};
-use crate::ProgramParser;
+use crate::program_registry::{ProgramRegistry, ProgramRegistryError, TypeLookupStats};
+use crate::{define_libfunc_hierarchy, ProgramParser};
 
 #[test]
 fn basic_insertion() {
@@ -95,3 +105,163 @@ fn libfunc_id_double_declaration() {
         Err(Box::new(ProgramRegistryError::LibFuncConcreteIdAlreadyExists("used_id".into())))
     );
 }
+
+#[test]
+fn libfunc_signatures_use_declared_type_ids() {
+    let registry = ProgramRegistry::<CoreType, CoreLibFunc>::new(
+        &ProgramParser::new()
+            .parse(indoc! {"
+                type felt = felt;
+                type ArrayFelt = Array<felt>;
+                type RangeCheck = RangeCheck;
+                type GasBuiltin = GasBuiltin;
+                libfunc array_append_felt = array_append<felt>;
+                libfunc get_gas = get_gas;
+            "})
+            .unwrap(),
+    )
+    .unwrap();
+    let param_types = |libfunc: &str| -> Vec<ConcreteTypeId> {
+        registry
+            .get_libfunc(&libfunc.into())
+            .unwrap()
+            .param_signatures()
+            .iter()
+            .map(|param| param.ty.clone())
+            .collect()
+    };
+    assert_eq!(param_types("array_append_felt"), vec!["ArrayFelt".into(), "felt".into()]);
+    assert_eq!(param_types("get_gas"), vec!["RangeCheck".into(), "GasBuiltin".into()]);
+}
+
+#[test]
+fn repeated_libfunc_declarations_are_specialized_once() {
+    let program = ProgramParser::new()
+        .parse(
+            &["type felt = felt;\n".to_string()]
+                .into_iter()
+                .chain(
+                    (0..500).map(|i| format!("libfunc store_temp_felt_{i} = store_temp<felt>;\n")),
+                )
+                .collect::<String>(),
+        )
+        .unwrap();
+    let registry = ProgramRegistry::<CoreType, CoreLibFunc>::new(&program).unwrap();
+    let first = registry.get_libfunc(&"store_temp_felt_0".into()).unwrap();
+    for i in 1..500 {
+        let libfunc = registry.get_libfunc(&format!("store_temp_felt_{i}").into()).unwrap();
+        // All the declarations share the concrete libfunc of the first one.
+        assert!(std::ptr::eq(first, libfunc));
+    }
+}
+
+#[test_case(256 => true; "max_size")]
+#[test_case(257 => false; "too_large")]
+fn storage_read_multi_size(size: usize) -> bool {
+    let program = ProgramParser::new()
+        .parse(&format!(
+            "type felt = felt;
+            type GasBuiltin = GasBuiltin;
+            type System = System;
+            type StorageAddress = StorageAddress;
+            type Big = Struct<ut@Big{}>;
+            libfunc read_big = storage_read_multi<Big>;",
+            ", felt".repeat(size)
+        ))
+        .unwrap();
+    match ProgramRegistry::<CoreType, CoreLibFunc>::new(&program) {
+        Ok(_) => true,
+        Err(error) => {
+            assert_eq!(
+                *error,
+                ProgramRegistryError::LibFuncSpecialization {
+                    concrete_id: "read_big".into(),
+                    error: ExtensionError::LibFuncSpecialization {
+                        libfunc_id: "storage_read_multi".into(),
+                        error: SpecializationError::UnsupportedGenericArg { index: 0 },
+                    },
+                }
+            );
+            false
+        }
+    }
+}
+
+#[test]
+fn type_lookup_stats() {
+    let registry = ProgramRegistry::<CoreType, CoreLibFunc>::new(
+        &ProgramParser::new()
+            .parse(indoc! {"
+                type felt = felt;
+                type NonZeroFelt = NonZero<felt>;
+                libfunc felt_add = felt_add;
+                libfunc felt_div = felt_div;
+                libfunc felt_add_again = felt_add;
+            "})
+            .unwrap(),
+    )
+    .unwrap();
+    // `felt_add` looks up `felt`, `felt_div` also looks up `NonZero<felt>`, and the repeated
+    // declaration is not specialized again.
+    assert_eq!(registry.type_lookup_stats(), TypeLookupStats { hits: 3, misses: 0 });
+}
+
+/// LibFunc defined outside of the core libfuncs, returning its felt argument unchanged.
+#[derive(Default)]
+pub struct ToyIdentityLibFunc {}
+impl NoGenericArgsGenericLibFunc for ToyIdentityLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("toy_identity");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+        Ok(LibFuncSignature::new_non_branch(
+            vec![felt_ty.clone()],
+            vec![OutputVarInfo {
+                ty: felt_ty,
+                ref_info: OutputVarReferenceInfo::SameAsParam { param_idx: 0 },
+            }],
+            SierraApChange::Known { new_vars_only: true },
+        ))
+    }
+}
+
+define_libfunc_hierarchy! {
+    pub enum ExtendedLibFunc {
+        Core(CoreLibFunc),
+        Toy(ToyIdentityLibFunc),
+    }, ExtendedConcreteLibFunc
+}
+
+#[test]
+fn extended_libfuncs() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            libfunc felt_add = felt_add;
+            libfunc toy = toy_identity;
+        "})
+        .unwrap();
+    assert!(ExtendedLibFunc::supported_ids().contains(&"toy_identity".into()));
+    let registry = ProgramRegistry::<CoreType, ExtendedLibFunc>::new(&program).unwrap();
+    assert!(matches!(
+        registry.get_libfunc(&"felt_add".into()).unwrap(),
+        ExtendedConcreteLibFunc::Core(CoreConcreteLibFunc::Felt(_))
+    ));
+    let toy = registry.get_libfunc(&"toy".into()).unwrap();
+    assert!(matches!(toy, ExtendedConcreteLibFunc::Toy(_)));
+    assert_eq!(toy.param_signatures().len(), 1);
+    // The core registry does not know the libfunc.
+    assert_eq!(
+        ProgramRegistry::<CoreType, CoreLibFunc>::new(&program).map(|_| ()).map_err(|error| *error),
+        Err(ProgramRegistryError::LibFuncSpecialization {
+            concrete_id: "toy".into(),
+            error: ExtensionError::LibFuncSpecialization {
+                libfunc_id: "toy_identity".into(),
+                error: SpecializationError::UnsupportedId,
+            },
+        })
+    );
+}
@@ -1,27 +1,48 @@
-use serde;
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+use serde::{Deserialize, Deserializer, Serializer};
 
 use crate::program::Program;
 use crate::ProgramParser;
 
-// TODO(ilya): Use real serialization.
+#[cfg(test)]
+#[path = "serialization_test.rs"]
+mod test;
+
+/// Serializes a program as its textual representation, for formats embedding Sierra programs as
+/// text.
+pub fn serialize_program_as_text<S>(program: &Program, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&program.to_string())
+}
+
+/// Deserializes a program from its textual representation.
+pub fn deserialize_program_from_text<'a, D>(deserializer: D) -> Result<Program, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    let serialized_program = &String::deserialize(deserializer)?;
+    ProgramParser::new()
+        .parse(serialized_program)
+        .map_err(|err| serde::de::Error::custom(format!("Sierra parsing failed.\n{}", err)))
+}
 
-impl serde::Serialize for Program {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        serializer.serialize_str(&format!("{}", *self))
-    }
+/// Serializes a big integer as a decimal string, as it may not fit in a JSON number.
+pub fn serialize_big_int<S>(num: &BigInt, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&num.to_string())
 }
 
-impl<'de> serde::Deserialize<'de> for Program {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let serialized_program = &String::deserialize(deserializer)?;
-        ProgramParser::new()
-            .parse(serialized_program)
-            .map_err(|err| serde::de::Error::custom(format!("Sierra parsing failed.\n{}", err)))
-    }
+/// Deserializes a big integer from a decimal string.
+pub fn deserialize_big_int<'a, D>(deserializer: D) -> Result<BigInt, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    let s = &String::deserialize(deserializer)?;
+    BigInt::from_str(s).map_err(|error| serde::de::Error::custom(format!("{s}: {error}")))
 }
@@ -0,0 +1,56 @@
+use indoc::indoc;
+
+use crate::program::{Program, StatementIdx, StatementLocation};
+use crate::ProgramParser;
+
+/// Asserts that the program is unchanged by a round trip through its JSON serialization, and
+/// returns the serialization.
+fn json_round_trip(program: &Program) -> String {
+    let serialized = serde_json::to_string(program).unwrap();
+    assert_eq!(&serde_json::from_str::<Program>(&serialized).unwrap(), program);
+    serialized
+}
+
+#[test]
+fn program_json_round_trip() {
+    let mut program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type StorageAddress = StorageAddress;
+            type NonZeroFelt = NonZero<felt>;
+
+            libfunc max_address = storage_address_const<3618502788666131106986593281521497120414687020801267626233049500247285300991>;
+            libfunc felt_jump_nz = felt_jump_nz;
+            libfunc drop_nz_felt = drop<NonZeroFelt>;
+            libfunc call_foo = function_call<user@foo>;
+
+            max_address() -> ([2]);
+            felt_jump_nz([1]) { fallthrough() 3([1]) };
+            return([2]);
+            drop_nz_felt([1]) -> ();
+            call_foo() -> ([2]);
+            return([2]);
+
+            foo@0([1]: felt) -> (StorageAddress);
+        "})
+        .unwrap();
+    program
+        .statement_locations
+        .insert(StatementIdx(1), StatementLocation { file_id: 0, start: 7, end: 12 });
+    let serialized = json_round_trip(&program);
+    // Big integers are serialized as decimal strings, and not as (possibly rounded) numbers.
+    assert!(serialized.contains(
+        r#"{"Value":"3618502788666131106986593281521497120414687020801267626233049500247285300991"}"#
+    ));
+}
+
+#[test]
+fn negative_value_json_round_trip() {
+    json_round_trip(
+        &ProgramParser::new()
+            .parse(indoc! {"
+                libfunc negative = felt_const<-5>;
+            "})
+            .unwrap(),
+    );
+}
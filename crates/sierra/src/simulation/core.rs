@@ -3,10 +3,11 @@ use std::collections::HashMap;
 use num_bigint::ToBigInt;
 use num_traits::Zero;
 use utils::extract_matches;
+use utils::short_string::format_for_debug;
 
 use super::value::CoreValue;
 use super::LibFuncSimulationError;
-use crate::extensions::array::ArrayConcreteLibFunc;
+use crate::extensions::array::{ArrayConcreteLibFunc, ARRAY_CLONE_CELL_STEPS};
 use crate::extensions::boolean::BoolConcreteLibFunc;
 use crate::extensions::core::CoreConcreteLibFunc::{
     self, ApTracking, Array, Bitwise, Bool, BranchAlign, Drop, Dup, Enum, Felt, FunctionCall, Gas,
@@ -20,10 +21,13 @@ use crate::extensions::felt::{
     FeltUnaryOpConcreteLibFunc, FeltUnaryOperationConcreteLibFunc, FeltUnaryOperator,
 };
 use crate::extensions::function_call::FunctionCallConcreteLibFunc;
-use crate::extensions::gas::GasConcreteLibFunc::{GetGas, RefundGas};
+use crate::extensions::gas::GasConcreteLibFunc::{
+    GetGas, RefundGas, ReserveCreate, ReserveRelease,
+};
 use crate::extensions::mem::MemConcreteLibFunc::{
     AlignTemps, AllocLocal, FinalizeLocals, Rename, StoreLocal, StoreTemp,
 };
+use crate::extensions::starknet::StarkNetConcreteLibFunc;
 use crate::extensions::strct::StructConcreteLibFunc;
 use crate::extensions::uint128::{
     IntOperator, Uint128Concrete, Uint128ConstConcreteLibFunc, Uint128OperationConcreteLibFunc,
@@ -36,14 +40,23 @@ use crate::ids::FunctionId;
 ///
 /// `simulate_function` is a function that simulates running of a user function. It is provided here
 /// for the case where the extensions need to use it.
+/// `simulate_syscall` simulates running a StarkNet libfunc, handling its syscall.
+/// `debug_print` writes the output of a debug print.
 pub fn simulate<
     GetStatementGasInfo: Fn() -> Option<i64>,
     SimulateFunction: Fn(&FunctionId, Vec<CoreValue>) -> Result<Vec<CoreValue>, LibFuncSimulationError>,
+    SimulateSyscall: Fn(
+        &StarkNetConcreteLibFunc,
+        Vec<CoreValue>,
+    ) -> Result<(Vec<CoreValue>, usize), LibFuncSimulationError>,
+    DebugPrint: Fn(&str) -> Result<(), LibFuncSimulationError>,
 >(
     libfunc: &CoreConcreteLibFunc,
     inputs: Vec<CoreValue>,
     get_statement_gas_info: GetStatementGasInfo,
     simulate_function: SimulateFunction,
+    simulate_syscall: SimulateSyscall,
+    debug_print: DebugPrint,
 ) -> Result<(Vec<CoreValue>, usize), LibFuncSimulationError> {
     match libfunc {
         Bitwise(_) => match &inputs[..] {
@@ -58,6 +71,21 @@ pub fn simulate<
             [_, _] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
             _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
         },
+        CoreConcreteLibFunc::Debug(_) => match &inputs[..] {
+            [CoreValue::Array(values)] => {
+                let felts: Vec<_> = values
+                    .iter()
+                    .map(|value| match value {
+                        CoreValue::Felt(value) => Ok(value.clone()),
+                        _ => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+                    })
+                    .collect::<Result<_, _>>()?;
+                debug_print(&format_for_debug(&felts))?;
+                Ok((inputs, 0))
+            }
+            [_] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
         Drop(_) => match &inputs[..] {
             [_] => Ok((vec![], 0)),
             _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
@@ -95,6 +123,32 @@ pub fn simulate<
             }?;
             Ok((vec![CoreValue::GasBuiltin(gas_counter + count)], 0))
         }
+        Gas(ReserveCreate(libfunc)) => {
+            let gas_counter = match &inputs[..] {
+                [CoreValue::RangeCheck, CoreValue::GasBuiltin(value)] => Ok(value),
+                [_, _] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+                _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+            }?;
+            match i64::try_from(&libfunc.c) {
+                // Have enough gas - the reserve is represented by the amount of gas it holds.
+                Ok(amount) if *gas_counter >= amount => Ok((
+                    vec![
+                        CoreValue::RangeCheck,
+                        CoreValue::GasBuiltin(gas_counter - amount),
+                        CoreValue::GasBuiltin(amount),
+                    ],
+                    0,
+                )),
+                _ => Ok((vec![CoreValue::RangeCheck, CoreValue::GasBuiltin(*gas_counter)], 1)),
+            }
+        }
+        Gas(ReserveRelease(_)) => match &inputs[..] {
+            [CoreValue::GasBuiltin(gas_counter), CoreValue::GasBuiltin(amount)] => {
+                Ok((vec![CoreValue::GasBuiltin(gas_counter + amount)], 0))
+            }
+            [_, _] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
         BranchAlign(_) => {
             get_statement_gas_info().ok_or(LibFuncSimulationError::UnresolvedStatementGasInfo)?;
             Ok((vec![], 0))
@@ -106,6 +160,17 @@ pub fn simulate<
                 Err(LibFuncSimulationError::WrongNumberOfArgs)
             }
         }
+        Array(ArrayConcreteLibFunc::NewWithCapacity(_)) => match &inputs[..] {
+            // The capacity is only a hint, so the array starts empty like with `array_new`.
+            [CoreValue::Uint128(_)] => Ok((vec![CoreValue::Array(vec![])], 0)),
+            [_] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
+        Array(ArrayConcreteLibFunc::PanicArrayNew(_)) => match &inputs[..] {
+            [CoreValue::Felt(_)] => Ok((vec![CoreValue::Array(inputs)], 0)),
+            [_] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
         Array(ArrayConcreteLibFunc::Append(_)) => match &inputs[..] {
             [CoreValue::Array(_), _] => {
                 let mut iter = inputs.into_iter();
@@ -116,7 +181,7 @@ pub fn simulate<
             [_, _] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
             _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
         },
-        Array(ArrayConcreteLibFunc::At(_)) => match &inputs[..] {
+        Array(ArrayConcreteLibFunc::At(_) | ArrayConcreteLibFunc::Get(_)) => match &inputs[..] {
             [CoreValue::RangeCheck, CoreValue::Array(_), CoreValue::Uint128(_)] => {
                 let mut iter = inputs.into_iter();
                 iter.next(); // Ignore range check.
@@ -141,6 +206,52 @@ pub fn simulate<
             [_] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
             _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
         },
+        Array(ArrayConcreteLibFunc::LenChecked(_)) => match &inputs[..] {
+            [CoreValue::RangeCheck, CoreValue::Array(_)] => {
+                let arr = extract_matches!(inputs.into_iter().nth(1).unwrap(), CoreValue::Array);
+                let len = arr.len();
+                Ok((
+                    vec![
+                        CoreValue::RangeCheck,
+                        CoreValue::Array(arr),
+                        CoreValue::Uint128(len as u128),
+                    ],
+                    0,
+                ))
+            }
+            [_, _] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
+        Array(ArrayConcreteLibFunc::Clone(_)) => match &inputs[..] {
+            [CoreValue::RangeCheck, CoreValue::GasBuiltin(gas_counter), CoreValue::Array(_)] => {
+                let gas_counter = *gas_counter;
+                let arr = extract_matches!(inputs.into_iter().nth(2).unwrap(), CoreValue::Array);
+                // The elements are simulated as single values, so they are charged as single cells.
+                let charge = ARRAY_CLONE_CELL_STEPS * arr.len() as i64;
+                if gas_counter >= charge {
+                    Ok((
+                        vec![
+                            CoreValue::RangeCheck,
+                            CoreValue::GasBuiltin(gas_counter - charge),
+                            CoreValue::Array(arr.clone()),
+                            CoreValue::Array(arr),
+                        ],
+                        0,
+                    ))
+                } else {
+                    Ok((
+                        vec![
+                            CoreValue::RangeCheck,
+                            CoreValue::GasBuiltin(gas_counter),
+                            CoreValue::Array(arr),
+                        ],
+                        1,
+                    ))
+                }
+            }
+            [_, _, _] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
         Uint128(libfunc) => simulate_integer_libfunc(libfunc, &inputs),
         Bool(libfunc) => simulate_bool_libfunc(libfunc, &inputs),
         Felt(libfunc) => simulate_felt_libfunc(libfunc, &inputs),
@@ -198,6 +309,14 @@ pub fn simulate<
             [_] => Err(LibFuncSimulationError::WrongArgType),
             _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
         },
+        Struct(StructConcreteLibFunc::MemberGet(libfunc)) => match &inputs[..] {
+            [CoreValue::Struct(members)] => match members.get(libfunc.index) {
+                Some(member) => Ok((vec![inputs[0].clone(), member.clone()], 0)),
+                None => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            },
+            [_] => Err(LibFuncSimulationError::WrongArgType),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
         CoreConcreteLibFunc::DictFeltTo(DictFeltToConcreteLibFunc::New(_)) => {
             if inputs.is_empty() {
                 Ok((vec![CoreValue::Dict(HashMap::new())], 0))
@@ -247,9 +366,7 @@ pub fn simulate<
         CoreConcreteLibFunc::BuiltinCost(_) => {
             todo!("Simulation of the builtin cost functionality is not implemented yet.")
         }
-        &CoreConcreteLibFunc::StarkNet(_) => {
-            unimplemented!("Simulation of the StarkNet functionalities is not implemented yet.")
-        }
+        CoreConcreteLibFunc::StarkNet(libfunc) => simulate_syscall(libfunc, inputs),
     }
 }
 
@@ -358,6 +475,16 @@ fn simulate_integer_libfunc(
                 _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
             }
         }
+        Uint128Concrete::CheckedMul(_) => match inputs {
+            [CoreValue::RangeCheck, CoreValue::Uint128(lhs), CoreValue::Uint128(rhs)] => {
+                Ok(match lhs.checked_mul(*rhs) {
+                    Some(product) => (vec![CoreValue::RangeCheck, CoreValue::Uint128(product)], 0),
+                    None => (vec![CoreValue::RangeCheck], 1),
+                })
+            }
+            [_, _, _] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
         Uint128Concrete::JumpNotZero(_) => {
             match inputs {
                 [CoreValue::Uint128(value)] if *value == 0 => {
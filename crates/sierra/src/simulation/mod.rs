@@ -1,8 +1,11 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::io::Write;
 
 use itertools::izip;
 use thiserror::Error;
 
+use self::syscalls::{InMemorySyscallHandler, SyscallHandler};
 use self::value::CoreValue;
 use crate::edit_state::{put_results, take_args, EditStateError};
 use crate::extensions::core::{CoreConcreteLibFunc, CoreLibFunc, CoreType};
@@ -11,6 +14,7 @@ use crate::program::{Program, Statement, StatementIdx};
 use crate::program_registry::{ProgramRegistry, ProgramRegistryError};
 
 pub mod core;
+pub mod syscalls;
 #[cfg(test)]
 mod test;
 pub mod value;
@@ -28,6 +32,10 @@ pub enum LibFuncSimulationError {
     UnresolvedStatementGasInfo,
     #[error("Error occurred during user function call")]
     FunctionSimulationError(FunctionId, Box<SimulationError>),
+    #[error("Failed writing debug output: {0}")]
+    DebugOutputError(String),
+    #[error("Simulation of the libfunc is not supported")]
+    UnsupportedLibFunc,
 }
 
 /// Error occurring while simulating a program function.
@@ -48,16 +56,40 @@ pub enum SimulationError {
 }
 
 /// Runs a function from the program with the given inputs.
+/// The syscalls of the program are handled by a default [InMemorySyscallHandler], and debug prints
+/// are written to the standard output.
 pub fn run(
     program: &Program,
     statement_gas_info: &HashMap<StatementIdx, i64>,
     function_id: &FunctionId,
     inputs: Vec<CoreValue>,
+) -> Result<Vec<CoreValue>, SimulationError> {
+    run_with_syscall_handler(
+        program,
+        statement_gas_info,
+        function_id,
+        inputs,
+        &mut InMemorySyscallHandler::default(),
+        &mut std::io::stdout(),
+    )
+}
+
+/// Runs a function from the program with the given inputs, handling the syscalls of the program
+/// with `syscall_handler`, and writing debug prints into `debug_output`.
+pub fn run_with_syscall_handler(
+    program: &Program,
+    statement_gas_info: &HashMap<StatementIdx, i64>,
+    function_id: &FunctionId,
+    inputs: Vec<CoreValue>,
+    syscall_handler: &mut dyn SyscallHandler,
+    debug_output: &mut dyn Write,
 ) -> Result<Vec<CoreValue>, SimulationError> {
     let context = SimulationContext {
         program,
         statement_gas_info,
         registry: &ProgramRegistry::new(program)?,
+        syscall_handler: RefCell::new(syscall_handler),
+        debug_output: RefCell::new(debug_output),
     };
     context.simulate_function(function_id, inputs)
 }
@@ -67,6 +99,8 @@ struct SimulationContext<'a> {
     pub program: &'a Program,
     pub statement_gas_info: &'a HashMap<StatementIdx, i64>,
     pub registry: &'a ProgramRegistry<CoreType, CoreLibFunc>,
+    pub syscall_handler: RefCell<&'a mut dyn SyscallHandler>,
+    pub debug_output: RefCell<&'a mut dyn Write>,
 }
 impl SimulationContext<'_> {
     /// Simulates the run of a function, even recursively.
@@ -153,6 +187,15 @@ impl SimulationContext<'_> {
                     )
                 })
             },
+            |libfunc, inputs| {
+                syscalls::simulate(libfunc, inputs, &mut **self.syscall_handler.borrow_mut())
+            },
+            |output| {
+                self.debug_output
+                    .borrow_mut()
+                    .write_all(output.as_bytes())
+                    .map_err(|error| LibFuncSimulationError::DebugOutputError(error.to_string()))
+            },
         )
         .map_err(|error| SimulationError::LibFuncSimulationError(error, current_statement_id))
     }
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use num_bigint::BigInt;
+use num_traits::Zero;
+use utils::short_string;
+
+use super::value::CoreValue;
+use super::LibFuncSimulationError;
+use crate::extensions::starknet::interoperability::CALL_DATA_WORD_GAS;
+use crate::extensions::starknet::StarkNetConcreteLibFunc;
+
+/// The revert reason of the syscalls that run out of gas.
+pub const OUT_OF_GAS: &str = "OOG";
+
+/// The result of a syscall, with the revert reason of the syscall on failure.
+pub type SyscallResult<T> = Result<T, BigInt>;
+
+/// A handler of the StarkNet syscalls of a simulated program.
+/// Every syscall is given the gas counter passed to it by the compiled syscall - after the charge
+/// for its call data, if it has any. A handler charging gas for the execution of a syscall should
+/// fail it with the [OUT_OF_GAS] revert reason if the gas counter can not cover it.
+pub trait SyscallHandler {
+    fn storage_read(
+        &mut self,
+        gas_counter: &mut i64,
+        address_domain: &BigInt,
+        address: &BigInt,
+    ) -> SyscallResult<BigInt>;
+    fn storage_write(
+        &mut self,
+        gas_counter: &mut i64,
+        address_domain: &BigInt,
+        address: &BigInt,
+        value: BigInt,
+    ) -> SyscallResult<()>;
+    fn call_contract(
+        &mut self,
+        gas_counter: &mut i64,
+        contract_address: &BigInt,
+        calldata: Vec<BigInt>,
+    ) -> SyscallResult<Vec<BigInt>>;
+    fn emit_event(
+        &mut self,
+        gas_counter: &mut i64,
+        keys: Vec<BigInt>,
+        data: Vec<BigInt>,
+    ) -> SyscallResult<()>;
+}
+
+/// Handles the calls of [InMemorySyscallHandler] to other contracts, given the called contract and
+/// the call data.
+pub type CallContractHandler = Box<dyn FnMut(&BigInt, Vec<BigInt>) -> SyscallResult<Vec<BigInt>>>;
+
+/// A syscall handler keeping the storage and the emitted events in memory, and handling calls to
+/// other contracts with a user supplied closure.
+/// As the syscall handler of the entry point runner, it charges no gas of its own - the gas of the
+/// syscalls is reserved by the costs of their libfuncs.
+pub struct InMemorySyscallHandler {
+    /// The storage of the contract, by address. Only the address domain 0 is supported.
+    pub storage: HashMap<BigInt, BigInt>,
+    /// The emitted events, as their keys and data.
+    pub events: Vec<(Vec<BigInt>, Vec<BigInt>)>,
+    call_contract: CallContractHandler,
+}
+impl InMemorySyscallHandler {
+    /// Creates a handler with an empty storage, handling contract calls with `call_contract`.
+    pub fn new(
+        call_contract: impl FnMut(&BigInt, Vec<BigInt>) -> SyscallResult<Vec<BigInt>> + 'static,
+    ) -> Self {
+        Self { storage: HashMap::new(), events: vec![], call_contract: Box::new(call_contract) }
+    }
+
+    /// Checks that the address domain is supported by the handler.
+    fn check_address_domain(address_domain: &BigInt) -> SyscallResult<()> {
+        if address_domain.is_zero() {
+            Ok(())
+        } else {
+            Err(revert_reason("Unsupported address domain"))
+        }
+    }
+}
+impl Default for InMemorySyscallHandler {
+    /// Creates a handler failing all the calls to other contracts.
+    fn default() -> Self {
+        Self::new(|_, _| Err(revert_reason("CONTRACT_NOT_DEPLOYED")))
+    }
+}
+impl SyscallHandler for InMemorySyscallHandler {
+    fn storage_read(
+        &mut self,
+        _gas_counter: &mut i64,
+        address_domain: &BigInt,
+        address: &BigInt,
+    ) -> SyscallResult<BigInt> {
+        Self::check_address_domain(address_domain)?;
+        Ok(self.storage.get(address).cloned().unwrap_or_default())
+    }
+
+    fn storage_write(
+        &mut self,
+        _gas_counter: &mut i64,
+        address_domain: &BigInt,
+        address: &BigInt,
+        value: BigInt,
+    ) -> SyscallResult<()> {
+        Self::check_address_domain(address_domain)?;
+        self.storage.insert(address.clone(), value);
+        Ok(())
+    }
+
+    fn call_contract(
+        &mut self,
+        _gas_counter: &mut i64,
+        contract_address: &BigInt,
+        calldata: Vec<BigInt>,
+    ) -> SyscallResult<Vec<BigInt>> {
+        (self.call_contract)(contract_address, calldata)
+    }
+
+    fn emit_event(
+        &mut self,
+        _gas_counter: &mut i64,
+        keys: Vec<BigInt>,
+        data: Vec<BigInt>,
+    ) -> SyscallResult<()> {
+        self.events.push((keys, data));
+        Ok(())
+    }
+}
+
+/// Returns the revert reason encoding `reason` as a short string.
+fn revert_reason(reason: &str) -> BigInt {
+    short_string::from_str(reason).expect("Revert reasons are valid short strings.")
+}
+
+/// Simulates the run of a StarkNet libfunc, handling its syscall with `handler`. Returns the value
+/// representations of the outputs, and the chosen branch - the failure branch, with the revert
+/// reason, if the syscall failed. As in the compiled syscalls, the gas of failed syscalls is not
+/// refunded.
+pub fn simulate(
+    libfunc: &StarkNetConcreteLibFunc,
+    inputs: Vec<CoreValue>,
+    handler: &mut dyn SyscallHandler,
+) -> Result<(Vec<CoreValue>, usize), LibFuncSimulationError> {
+    match libfunc {
+        StarkNetConcreteLibFunc::StorageRead(_) => match &inputs[..] {
+            [
+                CoreValue::GasBuiltin(gas_counter),
+                CoreValue::System,
+                CoreValue::Felt(address_domain),
+                CoreValue::Felt(address),
+            ] => {
+                let mut gas_counter = *gas_counter;
+                let result = handler.storage_read(&mut gas_counter, address_domain, address);
+                Ok(syscall_outputs(
+                    gas_counter,
+                    result.map(|value| vec![CoreValue::Felt(value)]),
+                    [],
+                ))
+            }
+            [_, _, _, _] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
+        StarkNetConcreteLibFunc::StorageWrite(_) => match &inputs[..] {
+            [
+                CoreValue::GasBuiltin(gas_counter),
+                CoreValue::System,
+                CoreValue::Felt(address_domain),
+                CoreValue::Felt(address),
+                CoreValue::Felt(value),
+            ] => {
+                let mut gas_counter = *gas_counter;
+                let result =
+                    handler.storage_write(&mut gas_counter, address_domain, address, value.clone());
+                Ok(syscall_outputs(gas_counter, result.map(|()| vec![]), []))
+            }
+            [_, _, _, _, _] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
+        StarkNetConcreteLibFunc::CallContract(_) => match &inputs[..] {
+            [
+                CoreValue::GasBuiltin(gas_counter),
+                CoreValue::System,
+                CoreValue::Felt(contract_address),
+                CoreValue::Array(calldata),
+            ] => {
+                let calldata = felts(calldata)?;
+                // The failure branch also returns a result array, which is empty in simulation.
+                let failure_outputs = [CoreValue::Array(vec![])];
+                let mut gas_counter = match charge_call_data(*gas_counter, calldata.len()) {
+                    Some(gas_counter) => gas_counter,
+                    None => {
+                        return Ok(syscall_outputs(
+                            0,
+                            Err(revert_reason(OUT_OF_GAS)),
+                            failure_outputs,
+                        ));
+                    }
+                };
+                let result = handler.call_contract(&mut gas_counter, contract_address, calldata);
+                Ok(syscall_outputs(
+                    gas_counter,
+                    result.map(|retdata| vec![felt_array(retdata)]),
+                    failure_outputs,
+                ))
+            }
+            [_, _, _, _] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
+        StarkNetConcreteLibFunc::EmitEvent(_) => match &inputs[..] {
+            [
+                CoreValue::GasBuiltin(gas_counter),
+                CoreValue::System,
+                CoreValue::Array(keys),
+                CoreValue::Array(data),
+            ] => {
+                let mut gas_counter = *gas_counter;
+                let result = handler.emit_event(&mut gas_counter, felts(keys)?, felts(data)?);
+                Ok(syscall_outputs(gas_counter, result.map(|()| vec![]), []))
+            }
+            [_, _, _, _] => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+            _ => Err(LibFuncSimulationError::WrongNumberOfArgs),
+        },
+        _ => Err(LibFuncSimulationError::UnsupportedLibFunc),
+    }
+}
+
+/// Deducts the charge for the call data of a syscall from the gas counter, as the compiled syscall
+/// does before it is handled. Returns `None` if the gas counter can not cover the charge - the
+/// syscall then runs out of gas, leaving no gas in the counter.
+fn charge_call_data(gas_counter: i64, call_data_len: usize) -> Option<i64> {
+    let charge = CALL_DATA_WORD_GAS.checked_mul(i64::try_from(call_data_len).ok()?)?;
+    gas_counter.checked_sub(charge).filter(|gas_counter| *gas_counter >= 0)
+}
+
+/// Returns the outputs of a syscall libfunc and the chosen branch, given the gas counter after the
+/// syscall and its result. The outputs of the failure branch are the revert reason followed by
+/// `failure_outputs`.
+fn syscall_outputs<const N: usize>(
+    gas_counter: i64,
+    result: SyscallResult<Vec<CoreValue>>,
+    failure_outputs: [CoreValue; N],
+) -> (Vec<CoreValue>, usize) {
+    let mut outputs = vec![CoreValue::GasBuiltin(gas_counter), CoreValue::System];
+    match result {
+        Ok(values) => {
+            outputs.extend(values);
+            (outputs, 0)
+        }
+        Err(revert_reason) => {
+            outputs.push(CoreValue::Felt(revert_reason));
+            outputs.extend(failure_outputs);
+            (outputs, 1)
+        }
+    }
+}
+
+/// Returns the values of an array of felts.
+fn felts(values: &[CoreValue]) -> Result<Vec<BigInt>, LibFuncSimulationError> {
+    values
+        .iter()
+        .map(|value| match value {
+            CoreValue::Felt(value) => Ok(value.clone()),
+            _ => Err(LibFuncSimulationError::MemoryLayoutMismatch),
+        })
+        .collect()
+}
+
+/// Returns the array value of the given felts.
+fn felt_array(values: Vec<BigInt>) -> CoreValue {
+    CoreValue::Array(values.into_iter().map(CoreValue::Felt).collect())
+}
@@ -1,14 +1,19 @@
+use std::collections::HashMap;
+
 use bimap::BiMap;
+use indoc::indoc;
 use num_bigint::BigInt;
 use test_case::test_case;
+use utils::short_string;
 
+use super::syscalls::{InMemorySyscallHandler, SyscallResult, OUT_OF_GAS};
 use super::value::CoreValue::{
-    self, Array, GasBuiltin, NonZero, RangeCheck, Uint128, Uninitialized,
+    self, Array, Felt, GasBuiltin, NonZero, RangeCheck, System, Uint128, Uninitialized,
 };
 use super::LibFuncSimulationError::{
     self, FunctionSimulationError, MemoryLayoutMismatch, WrongNumberOfArgs,
 };
-use super::{core, SimulationError};
+use super::{core, run_with_syscall_handler, syscalls, SimulationError};
 use crate::extensions::core::CoreLibFunc;
 use crate::extensions::lib_func::{
     SierraApChange, SignatureSpecializationContext, SpecializationContext,
@@ -19,6 +24,7 @@ use crate::extensions::GenericLibFunc;
 use crate::ids::{ConcreteTypeId, FunctionId, GenericTypeId};
 use crate::program::{ConcreteTypeLongId, Function, FunctionSignature, GenericArg, StatementIdx};
 use crate::test_utils::build_bijective_mapping;
+use crate::ProgramParser;
 
 fn type_arg(name: &str) -> GenericArg {
     GenericArg::Type(name.into())
@@ -129,6 +135,10 @@ fn simulate(
                 ))
             }
         },
+        |libfunc, inputs| {
+            syscalls::simulate(libfunc, inputs, &mut InMemorySyscallHandler::default())
+        },
+        |_| Ok(()),
     )
 }
 
@@ -136,6 +146,11 @@ fn simulate(
              => Ok((vec![RangeCheck, GasBuiltin(1)], 0)); "get_gas(5)")]
 #[test_case("get_gas", vec![], vec![RangeCheck, GasBuiltin(2)]
              => Ok((vec![RangeCheck, GasBuiltin(2)], 1)); "get_gas(2)")]
+#[test_case("gas_reserve_create", vec![value_arg(3)], vec![RangeCheck, GasBuiltin(5)]
+             => Ok((vec![RangeCheck, GasBuiltin(2), GasBuiltin(3)], 0));
+            "gas_reserve_create<3>(5)")]
+#[test_case("gas_reserve_create", vec![value_arg(3)], vec![RangeCheck, GasBuiltin(2)]
+             => Ok((vec![RangeCheck, GasBuiltin(2)], 1)); "gas_reserve_create<3>(2)")]
 #[test_case("u128_jump_nz", vec![], vec![Uint128(2)]
              => Ok((vec![NonZero(Box::new(Uint128(2)))], 1)); "u128_jump_nz(2)")]
 #[test_case("u128_jump_nz", vec![], vec![Uint128(0)] => Ok((vec![], 0)); "u128_jump_nz(0)")]
@@ -155,6 +170,21 @@ fn simulate(
 #[test_case("u128_overflow_mul", vec![], vec![RangeCheck, Uint128(u128::MAX), Uint128(u128::MAX)]
              => Ok((vec![RangeCheck, Uint128(1)], 1));
             "u128_overflow_mul(-1, -1)")]
+#[test_case("u128_checked_mul", vec![], vec![RangeCheck, Uint128(1 << 64), Uint128(u64::MAX.into())]
+             => Ok((vec![RangeCheck, Uint128(u128::MAX - u128::from(u64::MAX))], 0));
+            "u128_checked_mul(2^64, 2^64 - 1)")]
+#[test_case("u128_checked_mul", vec![], vec![RangeCheck, Uint128(1 << 64), Uint128(1 << 64)]
+             => Ok((vec![RangeCheck], 1));
+            "u128_checked_mul(2^64, 2^64)")]
+#[test_case("array_clone", vec![type_arg("u128")],
+             vec![RangeCheck, GasBuiltin(20), Array(vec![Uint128(4)])]
+             => Ok((vec![RangeCheck, GasBuiltin(9), Array(vec![Uint128(4)]),
+                         Array(vec![Uint128(4)])], 0));
+            "array_clone([4]) with enough gas")]
+#[test_case("array_clone", vec![type_arg("u128")],
+             vec![RangeCheck, GasBuiltin(10), Array(vec![Uint128(4)])]
+             => Ok((vec![RangeCheck, GasBuiltin(10), Array(vec![Uint128(4)])], 1));
+            "array_clone([4]) without enough gas")]
 fn simulate_branch(
     id: &str,
     generic_args: Vec<GenericArg>,
@@ -165,13 +195,23 @@ fn simulate_branch(
 
 /// Tests for simulation of a non branch invocations.
 #[test_case("refund_gas", vec![], vec![GasBuiltin(2)] => Ok(vec![GasBuiltin(6)]); "refund_gas(2)")]
+#[test_case("gas_reserve_release", vec![], vec![GasBuiltin(2), GasBuiltin(3)]
+             => Ok(vec![GasBuiltin(5)]); "gas_reserve_release(2, 3)")]
 #[test_case("array_new", vec![type_arg("u128")], vec![] => Ok(vec![Array(vec![])]); "array_new()")]
+#[test_case("array_new_with_capacity", vec![type_arg("u128")], vec![Uint128(8)] =>
+            Ok(vec![Array(vec![])]); "array_new_with_capacity(8)")]
+#[test_case("panic_array_new", vec![], vec![Felt(7.into())] =>
+            Ok(vec![Array(vec![Felt(7.into())])]); "panic_array_new(7)")]
 #[test_case("array_append", vec![type_arg("u128")], vec![Array(vec![]), Uint128(4)] =>
             Ok(vec![Array(vec![Uint128(4)])]); "array_append([], 4)")]
 #[test_case("array_at", vec![type_arg("u128")], vec![RangeCheck, Array(vec![Uint128(5)]), Uint128(0)]
              => Ok(vec![RangeCheck, Array(vec![Uint128(5)]), Uint128(5)]); "array_at([5], 0)")]
+#[test_case("array_get", vec![type_arg("u128")], vec![RangeCheck, Array(vec![Uint128(5)]), Uint128(0)]
+             => Ok(vec![RangeCheck, Array(vec![Uint128(5)]), Uint128(5)]); "array_get([5], 0)")]
 #[test_case("array_len", vec![type_arg("u128")], vec![Array(vec![])] =>
             Ok(vec![Array(vec![]), Uint128(0)]); "array_len([])")]
+#[test_case("array_len_checked", vec![type_arg("u128")], vec![RangeCheck, Array(vec![Uint128(4)])] =>
+            Ok(vec![RangeCheck, Array(vec![Uint128(4)]), Uint128(1)]); "array_len_checked([4])")]
 #[test_case("u128_safe_divmod", vec![], vec![RangeCheck, Uint128(32), NonZero(Box::new(Uint128(5)))]
              => Ok(vec![RangeCheck, Uint128(6), Uint128(2)]); "u128_safe_divmod(32, 5)")]
 #[test_case("u128_const", vec![value_arg(3)], vec![] => Ok(vec![Uint128(3)]);
@@ -247,3 +287,187 @@ fn simulate_error(
 ) -> LibFuncSimulationError {
     simulate(id, generic_args, inputs).err().unwrap()
 }
+
+#[test]
+fn storage_read_after_write() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type GasBuiltin = GasBuiltin;
+            type System = System;
+            type felt = felt;
+            type StorageAddress = StorageAddress;
+
+            libfunc address_domain = felt_const<0>;
+            libfunc dup_address = dup<StorageAddress>;
+            libfunc drop_address = drop<StorageAddress>;
+            libfunc storage_write = storage_write_syscall;
+            libfunc storage_read = storage_read_syscall;
+
+            address_domain() -> (domain);
+            dup_address(address) -> (address, address_);
+            storage_write(gb, system, domain, address_, value) { fallthrough(gb, system) 6(gb, system, value) };
+            address_domain() -> (domain);
+            storage_read(gb, system, domain, address) { fallthrough(gb, system, value) 7(gb, system, value) };
+            return(gb, system, value);
+            drop_address(address) -> ();
+            return(gb, system, value);
+
+            write_and_read@0(gb: GasBuiltin, system: System, address: StorageAddress, value: felt) -> (GasBuiltin, System, felt);
+        "})
+        .unwrap();
+    let mut handler = InMemorySyscallHandler::default();
+    assert_eq!(
+        run_with_syscall_handler(
+            &program,
+            &HashMap::new(),
+            &"write_and_read".into(),
+            vec![GasBuiltin(100), System, Felt(3.into()), Felt(5.into())],
+            &mut handler,
+            &mut vec![],
+        ),
+        Ok(vec![GasBuiltin(100), System, Felt(5.into())])
+    );
+    assert_eq!(handler.storage, HashMap::from([(3.into(), 5.into())]));
+}
+
+/// Runs a program calling a contract with two words of call data and `gas` available, with the
+/// contract calls handled by `call_contract`. Returns the revert reason (or 0 on success) and the
+/// result of the call.
+fn run_call_contract(
+    gas: i64,
+    call_contract: impl FnMut(&BigInt, Vec<BigInt>) -> SyscallResult<Vec<BigInt>> + 'static,
+) -> Result<Vec<CoreValue>, SimulationError> {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type GasBuiltin = GasBuiltin;
+            type System = System;
+            type felt = felt;
+            type ContractAddress = ContractAddress;
+            type FeltArray = Array<felt>;
+
+            libfunc call_contract = call_contract_syscall;
+            libfunc no_revert_reason = felt_const<0>;
+
+            call_contract(gb, system, address, calldata) { fallthrough(gb, system, result) 2(gb, system, reason, result) };
+            no_revert_reason() -> (reason);
+            return(gb, system, reason, result);
+
+            call@0(gb: GasBuiltin, system: System, address: ContractAddress, calldata: FeltArray) -> (GasBuiltin, System, felt, FeltArray);
+        "})
+        .unwrap();
+    run_with_syscall_handler(
+        &program,
+        &HashMap::new(),
+        &"call".into(),
+        vec![GasBuiltin(gas), System, Felt(7.into()), Array(vec![Felt(1.into()), Felt(2.into())])],
+        &mut InMemorySyscallHandler::new(call_contract),
+        &mut vec![],
+    )
+}
+
+#[test]
+fn call_contract_success() {
+    assert_eq!(
+        run_call_contract(100, |address, calldata| {
+            assert_eq!(address, &BigInt::from(7));
+            Ok(calldata.into_iter().rev().collect())
+        }),
+        // The call data is charged 5 gas per word.
+        Ok(vec![
+            GasBuiltin(90),
+            System,
+            Felt(0.into()),
+            Array(vec![Felt(2.into()), Felt(1.into())])
+        ])
+    );
+}
+
+#[test]
+fn call_contract_failure() {
+    assert_eq!(
+        run_call_contract(100, |_, _| Err(42.into())),
+        Ok(vec![GasBuiltin(90), System, Felt(42.into()), Array(vec![])])
+    );
+}
+
+#[test]
+fn call_contract_out_of_gas() {
+    assert_eq!(
+        run_call_contract(9, |_, _| panic!("The call data charge should have failed the call.")),
+        Ok(vec![
+            GasBuiltin(0),
+            System,
+            Felt(short_string::from_str(OUT_OF_GAS).unwrap()),
+            Array(vec![])
+        ])
+    );
+}
+
+#[test]
+fn unsupported_syscall() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type GasBuiltin = GasBuiltin;
+            type System = System;
+            type felt = felt;
+            type FeltArray = Array<felt>;
+
+            libfunc library_call = library_call_syscall;
+
+            library_call(gb, system, class_hash, calldata) { fallthrough(gb, system, result) 1(gb, system, reason, result) };
+            return(gb, system, result);
+
+            call@0(gb: GasBuiltin, system: System, class_hash: felt, calldata: FeltArray) -> (GasBuiltin, System, FeltArray);
+        "})
+        .unwrap();
+    assert_eq!(
+        run_with_syscall_handler(
+            &program,
+            &HashMap::new(),
+            &"call".into(),
+            vec![GasBuiltin(100), System, Felt(7.into()), Array(vec![])],
+            &mut InMemorySyscallHandler::default(),
+            &mut vec![],
+        ),
+        Err(SimulationError::LibFuncSimulationError(
+            LibFuncSimulationError::UnsupportedLibFunc,
+            StatementIdx(0)
+        ))
+    );
+}
+
+#[test]
+fn debug_print_output() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type FeltArray = Array<felt>;
+
+            libfunc array_new_felt = array_new<felt>;
+            libfunc array_append_felt = array_append<felt>;
+            libfunc debug_print = debug_print;
+            libfunc drop_felt_array = drop<FeltArray>;
+
+            array_new_felt() -> (values);
+            array_append_felt(values, value) -> (values);
+            debug_print(values) -> (values);
+            drop_felt_array(values) -> ();
+            return();
+
+            print@0(value: felt) -> ();
+        "})
+        .unwrap();
+    let mut debug_output = vec![];
+    assert_eq!(
+        run_with_syscall_handler(
+            &program,
+            &HashMap::new(),
+            &"print".into(),
+            vec![Felt(18537.into())],
+            &mut InMemorySyscallHandler::default(),
+            &mut debug_output,
+        ),
+        Ok(vec![])
+    );
+    assert_eq!(String::from_utf8(debug_output).unwrap(), "[DEBUG]\t18537 ('Hi')\n");
+}
@@ -20,5 +20,7 @@ pub enum CoreValue {
         index: usize,
     },
     Struct(Vec<CoreValue>),
+    /// The system pointer, with its syscalls handled by the syscall handler of the simulation.
+    System,
     Uninitialized,
 }
@@ -14,26 +14,34 @@ pub fn build_bijective_mapping() -> BiMap<ConcreteTypeId, ConcreteTypeLongId> {
         "U128AndFelt".into(),
         as_named_type_long_id("Struct", "U128AndFelt", &["u128", "felt"]),
     );
+    elements
+        .insert("FeltPair".into(), as_named_type_long_id("Struct", "FeltPair", &["felt", "felt"]));
     elements.insert("Option".into(), as_named_type_long_id("Enum", "Option", &["felt", "Tuple<>"]));
     elements.insert("NonZeroFelt".into(), as_type_long_id("NonZero", &["felt"]));
     elements.insert("NonZeroU128".into(), as_type_long_id("NonZero", &["u128"]));
     elements.insert("ArrayFelt".into(), as_type_long_id("Array", &["felt"]));
     elements.insert("ArrayU128".into(), as_type_long_id("Array", &["u128"]));
+    elements.insert("BoxU128".into(), as_type_long_id("Box", &["u128"]));
     elements.insert("UninitializedFelt".into(), as_type_long_id("Uninitialized", &["felt"]));
     elements.insert("Uninitializedu128".into(), as_type_long_id("Uninitialized", &["u128"]));
     elements.insert("GasBuiltin".into(), as_type_long_id("GasBuiltin", &[]));
+    elements.insert("GasReserve".into(), as_type_long_id("GasReserve", &[]));
     elements.insert("RangeCheck".into(), as_type_long_id("RangeCheck", &[]));
     elements.insert("System".into(), as_type_long_id("System", &[]));
     elements.insert("StorageAddress".into(), as_type_long_id("StorageAddress", &[]));
+    elements.insert("StorageBaseAddress".into(), as_type_long_id("StorageBaseAddress", &[]));
     elements.insert("ContractAddress".into(), as_type_long_id("ContractAddress", &[]));
+    elements.insert("Secp256k1Point".into(), as_type_long_id("Secp256k1Point", &[]));
+    elements.insert("TxInfo".into(), as_type_long_id("TxInfo", &[]));
+    elements.insert("BoxTxInfo".into(), as_type_long_id("Box", &["TxInfo"]));
     elements
 }
 
 fn as_type_long_id(name: &str, args: &[&str]) -> ConcreteTypeLongId {
-    ConcreteTypeLongId {
-        generic_id: name.into(),
-        generic_args: args.iter().map(|s| GenericArg::Type(ConcreteTypeId::from(*s))).collect(),
-    }
+    ConcreteTypeLongId::new(
+        name.into(),
+        args.iter().map(|s| GenericArg::ty(ConcreteTypeId::from(*s))).collect(),
+    )
 }
 
 fn as_named_type_long_id(genetic_name: &str, user_name: &str, args: &[&str]) -> ConcreteTypeLongId {
@@ -31,6 +31,15 @@ fn create_registry(name: &str) {
     ProgramRegistry::<CoreType, CoreLibFunc>::new(&get_example_program(name)).unwrap();
 }
 
+#[test_case("fib_jumps")]
+#[test_case("fib_no_gas")]
+#[test_case("fib_recursive")]
+fn json_round_trip(name: &str) {
+    let program = get_example_program(name);
+    let serialized = serde_json::to_string(&program).unwrap();
+    assert_eq!(serde_json::from_str::<Program>(&serialized).unwrap(), program);
+}
+
 #[test_case((1000, 0), (1011, 1); "0 => 1")]
 #[test_case((1000, 1), (1006, 1); "1 => 1")]
 #[test_case((1000, 2), (987, 2); "2 => 2")]
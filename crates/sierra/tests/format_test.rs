@@ -67,3 +67,35 @@ fn format_test() {
         .to_string())
     );
 }
+
+// Testing that statement locations are printed as comments, keeping the code parsable.
+#[test]
+fn format_locations_test() {
+    let parser = sierra::ProgramParser::new();
+    let mut program = parser
+        .parse(indoc! {"
+            callee(arg1) -> (res1);
+            return(res1);
+
+            Name@0(arg1: T1) -> (T1);
+        "})
+        .unwrap();
+    program.statement_locations.insert(
+        sierra::program::StatementIdx(1),
+        sierra::program::StatementLocation { file_id: 2, start: 10, end: 25 },
+    );
+    let formatted = program.to_string();
+    assert_eq!(
+        formatted,
+        indoc! {"
+
+
+            callee(arg1) -> (res1);
+            return(res1); // @2:10..25
+
+            Name@0(arg1: T1) -> (T1);
+        "}
+    );
+    program.statement_locations.clear();
+    assert_eq!(parser.parse(&formatted), Ok(program));
+}
@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use sierra::ids::FunctionId;
 use sierra::program::StatementIdx;
 
+use crate::StackLayout;
+
 /// Ap change information for a Sierra program.
 #[derive(Debug, Eq, PartialEq)]
 pub struct ApChangeInfo {
@@ -10,4 +12,6 @@ pub struct ApChangeInfo {
     pub variable_values: HashMap<StatementIdx, usize>,
     /// The ap_change of calling the given function.
     pub function_ap_change: HashMap<FunctionId, usize>,
+    /// The layout of values on the stack the ap changes were calculated for.
+    pub stack_layout: StackLayout,
 }
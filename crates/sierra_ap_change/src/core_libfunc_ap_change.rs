@@ -11,7 +11,11 @@ use sierra::extensions::starknet::StarkNetConcreteLibFunc;
 use sierra::extensions::strct::StructConcreteLibFunc;
 use sierra::extensions::uint128::{IntOperator, Uint128Concrete};
 
-use crate::ApChange;
+use crate::{ApChange, ApChangeByInputForm};
+
+#[cfg(test)]
+#[path = "core_libfunc_ap_change_test.rs"]
+mod test;
 
 /// Returns the ap change for a core libfunc.
 /// Values with unknown values will return as None.
@@ -20,9 +24,15 @@ pub fn core_libfunc_ap_change(libfunc: &CoreConcreteLibFunc) -> Vec<ApChange> {
         CoreConcreteLibFunc::ApTracking(_) => vec![ApChange::Unknown],
         CoreConcreteLibFunc::Array(libfunc) => match libfunc {
             ArrayConcreteLibFunc::New(_) => vec![ApChange::Known(1)],
+            ArrayConcreteLibFunc::NewWithCapacity(_) => vec![ApChange::Known(1)],
+            // The pushed argument, the call frame and the routine's two cells.
+            ArrayConcreteLibFunc::PanicArrayNew(_) => vec![ApChange::Known(5)],
             ArrayConcreteLibFunc::Append(_) => vec![ApChange::Known(0)],
             ArrayConcreteLibFunc::At(_) => vec![ApChange::Known(5), ApChange::Known(3)],
+            ArrayConcreteLibFunc::Get(_) => vec![ApChange::Known(6), ApChange::Known(5)],
             ArrayConcreteLibFunc::Len(_) => vec![ApChange::Known(0)],
+            ArrayConcreteLibFunc::LenChecked(_) => vec![ApChange::Known(2)],
+            ArrayConcreteLibFunc::Clone(_) => vec![ApChange::Unknown, ApChange::Known(5)],
         },
         CoreConcreteLibFunc::Bitwise(_) => vec![ApChange::Known(0)],
         CoreConcreteLibFunc::BranchAlign(_) => vec![ApChange::FromMetadata],
@@ -35,12 +45,13 @@ pub fn core_libfunc_ap_change(libfunc: &CoreConcreteLibFunc) -> Vec<ApChange> {
             ApChange::Known(BuiltinCostGetGasLibFunc::cost_computation_max_steps() + 2),
             ApChange::Known(BuiltinCostGetGasLibFunc::cost_computation_max_steps() + 3),
         ],
+        CoreConcreteLibFunc::Debug(_) => vec![ApChange::Known(0)],
         CoreConcreteLibFunc::Drop(_) | CoreConcreteLibFunc::Dup(_) => vec![ApChange::Known(0)],
         CoreConcreteLibFunc::Felt(libfunc) => match libfunc {
             FeltConcrete::BinaryOperation(_)
             | FeltConcrete::UnaryOperation(_)
             | FeltConcrete::Const(_) => vec![ApChange::Known(0)],
-            FeltConcrete::JumpNotZero(_) => vec![ApChange::Known(0), ApChange::Known(0)],
+            FeltConcrete::JumpNotZero(_) => jump_nz_ap_changes(),
         },
         CoreConcreteLibFunc::FunctionCall(libfunc) => {
             vec![ApChange::FunctionCall(libfunc.function.id.clone())]
@@ -48,6 +59,8 @@ pub fn core_libfunc_ap_change(libfunc: &CoreConcreteLibFunc) -> Vec<ApChange> {
         CoreConcreteLibFunc::Gas(libfunc) => match libfunc {
             GasConcreteLibFunc::GetGas(_) => vec![ApChange::Known(2), ApChange::Known(2)],
             GasConcreteLibFunc::RefundGas(_) => vec![ApChange::Known(0)],
+            GasConcreteLibFunc::ReserveCreate(_) => vec![ApChange::Known(2), ApChange::Known(2)],
+            GasConcreteLibFunc::ReserveRelease(_) => vec![ApChange::Known(0)],
         },
         CoreConcreteLibFunc::Uint128(libfunc) => match libfunc {
             Uint128Concrete::Operation(libfunc) => match libfunc.operator {
@@ -57,16 +70,17 @@ pub fn core_libfunc_ap_change(libfunc: &CoreConcreteLibFunc) -> Vec<ApChange> {
                 IntOperator::OverflowingMul => todo!(),
                 IntOperator::DivMod => vec![ApChange::Known(7)],
             },
+            Uint128Concrete::CheckedMul(_) => vec![ApChange::Known(5), ApChange::Known(11)],
             Uint128Concrete::LessThan(_) => vec![ApChange::Known(2), ApChange::Known(3)],
             Uint128Concrete::Equal(_) => vec![ApChange::Known(1), ApChange::Known(1)],
             Uint128Concrete::LessThanOrEqual(_) => vec![ApChange::Known(3), ApChange::Known(2)],
             Uint128Concrete::FromFelt(_) => vec![ApChange::Known(1), ApChange::Known(6)],
             Uint128Concrete::Const(_) | Uint128Concrete::ToFelt(_) => vec![ApChange::Known(0)],
-            Uint128Concrete::JumpNotZero(_) => vec![ApChange::Known(0), ApChange::Known(0)],
+            Uint128Concrete::JumpNotZero(_) => jump_nz_ap_changes(),
         },
         CoreConcreteLibFunc::Mem(libfunc) => match libfunc {
             MemConcreteLibFunc::StoreTemp(libfunc) => {
-                vec![ApChange::KnownByTypeSize(libfunc.ty.clone())]
+                vec![ApChange::KnownByStackSlotSize(libfunc.ty.clone())]
             }
             MemConcreteLibFunc::AlignTemps(libfunc) => {
                 vec![ApChange::KnownByTypeSize(libfunc.ty.clone())]
@@ -89,9 +103,9 @@ pub fn core_libfunc_ap_change(libfunc: &CoreConcreteLibFunc) -> Vec<ApChange> {
             }
         },
         CoreConcreteLibFunc::Struct(libfunc) => match libfunc {
-            StructConcreteLibFunc::Construct(_) | StructConcreteLibFunc::Deconstruct(_) => {
-                vec![ApChange::Known(0)]
-            }
+            StructConcreteLibFunc::Construct(_)
+            | StructConcreteLibFunc::Deconstruct(_)
+            | StructConcreteLibFunc::MemberGet(_) => vec![ApChange::Known(0)],
         },
         CoreConcreteLibFunc::DictFeltTo(libfunc) => match libfunc {
             DictFeltToConcreteLibFunc::New(_) => vec![ApChange::Known(1)],
@@ -101,15 +115,102 @@ pub fn core_libfunc_ap_change(libfunc: &CoreConcreteLibFunc) -> Vec<ApChange> {
         },
         CoreConcreteLibFunc::Pedersen(_) => vec![ApChange::Known(0)],
         CoreConcreteLibFunc::StarkNet(libfunc) => match libfunc {
-            StarkNetConcreteLibFunc::ContractAddressConst(_) => vec![ApChange::Known(0)],
-            StarkNetConcreteLibFunc::CallContract(_) => {
+            StarkNetConcreteLibFunc::ContractAddressConst(_)
+            | StarkNetConcreteLibFunc::ContractAddressToFelt(_) => vec![ApChange::Known(0)],
+            StarkNetConcreteLibFunc::ContractAddressTryFromFelt(_) => {
+                vec![ApChange::Known(7), ApChange::Known(7)]
+            }
+            // The call data length and its gas charge take 3 more cells.
+            StarkNetConcreteLibFunc::CallContract(_)
+            | StarkNetConcreteLibFunc::Deploy(_)
+            | StarkNetConcreteLibFunc::LibraryCall(_) => {
+                vec![ApChange::Known(6), ApChange::Known(6)]
+            }
+            StarkNetConcreteLibFunc::SendMessageToL1(_) => {
+                vec![ApChange::Known(3), ApChange::Known(3)]
+            }
+            StarkNetConcreteLibFunc::EmitEvent(_)
+            | StarkNetConcreteLibFunc::GetCallerAddress(_)
+            | StarkNetConcreteLibFunc::GetContractAddress(_)
+            | StarkNetConcreteLibFunc::GetSequencerAddress(_)
+            | StarkNetConcreteLibFunc::GetBlockNumber(_)
+            | StarkNetConcreteLibFunc::GetBlockTimestamp(_)
+            | StarkNetConcreteLibFunc::GetTxInfo(_)
+            | StarkNetConcreteLibFunc::ReplaceClass(_) => {
                 vec![ApChange::Known(2), ApChange::Known(2)]
             }
-            StarkNetConcreteLibFunc::StorageRead(_) => vec![ApChange::Known(2)],
+            StarkNetConcreteLibFunc::Keccak(_) => vec![ApChange::Known(4), ApChange::Known(2)],
+            // A selector cell and a revert reason cell, followed on success by a cell for every
+            // result.
+            StarkNetConcreteLibFunc::Secp256k1New(_) => {
+                vec![ApChange::Known(4), ApChange::Known(2)]
+            }
+            StarkNetConcreteLibFunc::Secp256k1Add(_) | StarkNetConcreteLibFunc::Secp256k1Mul(_) => {
+                vec![ApChange::Known(3), ApChange::Known(2)]
+            }
+            StarkNetConcreteLibFunc::Secp256k1GetXy(_) => {
+                vec![ApChange::Known(6), ApChange::Known(2)]
+            }
+            StarkNetConcreteLibFunc::StorageRead(_) => {
+                vec![storage_address_ap_change(3, 3), storage_address_ap_change(3, 2)]
+            }
+            StarkNetConcreteLibFunc::StorageReadUnchecked(_) => {
+                vec![storage_address_ap_change(3, 2)]
+            }
+            // A selector cell, a revert reason cell for every read, and an address cell and a gas
+            // counter cell for every read but the first, which uses the given address and gas
+            // counter. On success, followed by a value cell for every read - and on failure, by
+            // the 4 outputs, after padding the cells of the reads that were not made.
+            StarkNetConcreteLibFunc::StorageReadMulti(libfunc) => {
+                vec![
+                    storage_address_ap_change(3, 4 * libfunc.size - 1),
+                    storage_address_ap_change(3, 3 * libfunc.size + 3),
+                ]
+            }
             StarkNetConcreteLibFunc::StorageWrite(_) => {
-                vec![ApChange::Known(2), ApChange::Known(2)]
+                vec![storage_address_ap_change(3, 2), storage_address_ap_change(3, 2)]
+            }
+            StarkNetConcreteLibFunc::StorageWriteUnchecked(_) => {
+                vec![storage_address_ap_change(3, 1)]
+            }
+            // The address check takes 5 additional cells, and the range check shifts the params.
+            StarkNetConcreteLibFunc::StorageReadChecked(_) => {
+                vec![storage_address_ap_change(4, 8), storage_address_ap_change(4, 7)]
+            }
+            StarkNetConcreteLibFunc::StorageWriteChecked(_) => {
+                vec![storage_address_ap_change(4, 7), storage_address_ap_change(4, 7)]
+            }
+            StarkNetConcreteLibFunc::StorageBaseAddressFromFelt(_) => vec![ApChange::Known(7)],
+            StarkNetConcreteLibFunc::StorageAddressConst(_)
+            | StarkNetConcreteLibFunc::StorageBaseAddressConst(_)
+            | StarkNetConcreteLibFunc::StorageAddressFromBaseAndOffset(_) => {
+                vec![ApChange::Known(0)]
             }
-            StarkNetConcreteLibFunc::StorageAddressConst(_) => vec![ApChange::Known(0)],
         },
     }
 }
+
+/// Returns the ap changes of the branches of a jump non-zero libfunc. A binary operation of two
+/// cells is first stored into a temporary variable, which costs an additional cell. Constants are
+/// not accepted by the libfunc, so their ap change is never used.
+fn jump_nz_ap_changes() -> Vec<ApChange> {
+    let ap_change = ApChange::ByInputForm(ApChangeByInputForm {
+        param_idx: 0,
+        deref: 0,
+        immediate: 0,
+        compound: 1,
+    });
+    vec![ap_change.clone(), ap_change]
+}
+
+/// Returns the ap change of a storage syscall branch, given the index of its storage address input
+/// and the ap change when the address is a dereference. Any other address is first copied into a
+/// temporary variable, which costs an additional cell.
+fn storage_address_ap_change(param_idx: usize, deref: usize) -> ApChange {
+    ApChange::ByInputForm(ApChangeByInputForm {
+        param_idx,
+        deref,
+        immediate: deref + 1,
+        compound: deref + 1,
+    })
+}
@@ -0,0 +1,103 @@
+use itertools::Itertools;
+use sierra::extensions::core::{CoreLibFunc, CoreType};
+use sierra::extensions::lib_func::{
+    SierraApChange, SignatureSpecializationContext, SpecializationContext,
+};
+use sierra::extensions::type_specialization_context::TypeSpecializationContext;
+use sierra::extensions::types::TypeInfo;
+use sierra::extensions::{ConcreteLibFunc, ConcreteType, GenericLibFunc, GenericTypeEx};
+use sierra::ids::{ConcreteTypeId, FunctionId, GenericTypeId};
+use sierra::program::{Function, FunctionSignature, GenericArg, StatementIdx};
+
+use super::core_libfunc_ap_change;
+
+/// The generic args tried for every libfunc, until one of them specializes it.
+const DUMMY_GENERIC_ARGS: &[&str] = &[
+    "",
+    "felt",
+    "1",
+    "Struct<ut@Pair, felt, felt>",
+    "Struct<ut@Pair, felt, felt>, 0",
+    "Enum<ut@Pair, felt, felt>",
+    "Enum<ut@Pair, felt, felt>, 0",
+    "user@Foo",
+];
+
+/// A specialization context for any type, with every function taking and returning nothing.
+struct MockSpecializationContext {}
+impl TypeSpecializationContext for MockSpecializationContext {
+    fn try_get_type_info(&self, id: ConcreteTypeId) -> Option<TypeInfo> {
+        let long_id =
+            sierra::ConcreteTypeLongIdParser::new().parse(id.to_string().as_str()).ok()?;
+        Some(
+            CoreType::specialize_by_id(self, &long_id.generic_id, &long_id.generic_args)
+                .ok()?
+                .info()
+                .clone(),
+        )
+    }
+}
+impl SignatureSpecializationContext for MockSpecializationContext {
+    fn try_get_concrete_type(
+        &self,
+        id: GenericTypeId,
+        generic_args: &[GenericArg],
+    ) -> Option<ConcreteTypeId> {
+        Some(if generic_args.is_empty() {
+            id.to_string().into()
+        } else {
+            format!("{id}<{}>", generic_args.iter().map(GenericArg::to_string).join(", ")).into()
+        })
+    }
+
+    fn try_get_function_signature(&self, function_id: &FunctionId) -> Option<FunctionSignature> {
+        self.try_get_function(function_id).map(|function| function.signature)
+    }
+
+    fn try_get_function_ap_change(&self, _function_id: &FunctionId) -> Option<SierraApChange> {
+        Some(SierraApChange::Known { new_vars_only: false })
+    }
+
+    fn as_type_specialization_context(&self) -> &dyn TypeSpecializationContext {
+        self
+    }
+}
+impl SpecializationContext for MockSpecializationContext {
+    fn upcast(&self) -> &dyn SignatureSpecializationContext {
+        self
+    }
+
+    fn try_get_function(&self, function_id: &FunctionId) -> Option<Function> {
+        Some(Function {
+            id: function_id.clone(),
+            signature: FunctionSignature { param_types: vec![], ret_types: vec![] },
+            params: vec![],
+            entry_point: StatementIdx(0),
+        })
+    }
+}
+
+#[test]
+fn test_all_libfuncs_have_ap_changes() {
+    let context = MockSpecializationContext {};
+    for generic_id in CoreLibFunc::supported_ids() {
+        let libfunc = DUMMY_GENERIC_ARGS
+            .iter()
+            .find_map(|args| {
+                let long_id = if args.is_empty() {
+                    generic_id.to_string()
+                } else {
+                    format!("{generic_id}<{args}>")
+                };
+                let long_id =
+                    sierra::ConcreteLibFuncLongIdParser::new().parse(long_id.as_str()).ok()?;
+                CoreLibFunc::by_id(&generic_id)?.specialize(&context, &long_id.generic_args).ok()
+            })
+            .unwrap_or_else(|| panic!("No dummy generic args specialize `{generic_id}`."));
+        assert_eq!(
+            core_libfunc_ap_change(&libfunc).len(),
+            libfunc.branch_signatures().len(),
+            "Wrong number of ap changes for `{generic_id}`."
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use core::fmt;
+use std::collections::HashMap;
 
 use itertools::zip_eq;
-use sierra::ids::{ConcreteLibFuncId, FunctionId};
+use sierra::ids::{ConcreteLibFuncId, FunctionId, VarId};
 use sierra::program::{Program, StatementIdx};
 use solver::Expr;
 
-use crate::{ApChange, ApChangeError};
+use crate::{ApChange, ApChangeError, InputForm};
 
 /// Variable parts of an Ap change expression.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -28,10 +29,20 @@ impl fmt::Display for Var {
 
 type ApChangeExpr = Expr<Var>;
 
+/// The form of an output of a libfunc branch.
+pub enum OutputForm {
+    /// The output has the given form regardless of the inputs.
+    Known(InputForm),
+    /// The output is one of the inputs, and therefore has the same form.
+    SameAsParam(usize),
+}
+
 /// The effects of a libfunc.
 pub struct Effects {
     pub ap_change: ApChange,
     pub locals: usize,
+    /// The forms of the outputs of the branch.
+    pub output_forms: Vec<OutputForm>,
 }
 
 /// Information per statement in the analyzed Sierra program.
@@ -43,6 +54,8 @@ struct StatementInfo {
     past_ap_change: Option<ApChangeExpr>,
     /// The total size of allocated locals up until the statement.
     past_locals: usize,
+    /// The forms of the variables available at the statement.
+    var_forms: HashMap<VarId, InputForm>,
 }
 
 /// Generates a set of equations from a program, and a function to extract cost expressions from a
@@ -61,6 +74,11 @@ pub fn generate_equations<
                 func_id: func.id.clone(),
                 past_ap_change: Some(Expr::from_const(0)),
                 past_locals: 0,
+                var_forms: func
+                    .params
+                    .iter()
+                    .map(|param| (param.id.clone(), InputForm::Deref))
+                    .collect(),
             },
         )?;
     }
@@ -76,6 +94,7 @@ pub fn generate_equations<
                             base_info.func_id,
                         ))),
                         past_locals: base_info.past_locals,
+                        var_forms: HashMap::default(),
                     },
                 )?;
             }
@@ -84,6 +103,13 @@ pub fn generate_equations<
                 if invocation.branches.len() != libfunc_effects.len() {
                     return Err(ApChangeError::IllegalInvocation(idx));
                 }
+                let mut remaining_forms = base_info.var_forms.clone();
+                let input_forms = invocation
+                    .args
+                    .iter()
+                    .map(|arg| remaining_forms.remove(arg))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(ApChangeError::IllegalInvocation(idx))?;
                 for (branch, branch_effects) in zip_eq(&invocation.branches, libfunc_effects) {
                     let branch_ap_change = match branch_effects.ap_change {
                         ApChange::Unknown => None,
@@ -97,7 +123,14 @@ pub fn generate_equations<
                         ApChange::FinalizeLocals => {
                             Some(Expr::from_const(base_info.past_locals as i32))
                         }
+                        ApChange::ByInputForm(by_form) => Some(Expr::from_const(
+                            by_form
+                                .eval(&input_forms)
+                                .ok_or(ApChangeError::IllegalInvocation(idx))?
+                                as i32,
+                        )),
                         ApChange::KnownByTypeSize(_)
+                        | ApChange::KnownByStackSlotSize(_)
                         | ApChange::AtLocalsFinalizationByTypeSize(_) => {
                             unreachable!(
                                 "These arms are translated to `ApChange::Known` in the \
@@ -105,6 +138,21 @@ pub fn generate_equations<
                             )
                         }
                     };
+                    if branch.results.len() != branch_effects.output_forms.len() {
+                        return Err(ApChangeError::IllegalInvocation(idx));
+                    }
+                    let mut var_forms = remaining_forms.clone();
+                    for (var_id, output_form) in
+                        zip_eq(&branch.results, branch_effects.output_forms)
+                    {
+                        let form = match output_form {
+                            OutputForm::Known(form) => form,
+                            OutputForm::SameAsParam(param_idx) => *input_forms
+                                .get(param_idx)
+                                .ok_or(ApChangeError::IllegalInvocation(idx))?,
+                        };
+                        var_forms.insert(var_id.clone(), form);
+                    }
                     let next_idx = idx.next(&branch.target);
                     generator.set_or_add_constraint(
                         &next_idx,
@@ -118,6 +166,7 @@ pub fn generate_equations<
                                 None
                             },
                             past_locals: base_info.past_locals + branch_effects.locals,
+                            var_forms,
                         },
                     )?;
                 }
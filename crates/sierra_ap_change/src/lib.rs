@@ -1,13 +1,16 @@
 use std::collections::HashMap;
 
 use ap_change_info::ApChangeInfo;
-use generate_equations::{Effects, Var};
+use generate_equations::{Effects, OutputForm, Var};
+use itertools::zip_eq;
 use sierra::extensions::core::{CoreLibFunc, CoreType};
-use sierra::extensions::ConcreteType;
+use sierra::extensions::lib_func::{DeferredOutputKind, OutputVarReferenceInfo};
+use sierra::extensions::{ConcreteLibFunc, ConcreteType, GenericLibFunc};
 use sierra::ids::{ConcreteTypeId, FunctionId};
 use sierra::program::{Program, StatementIdx};
 use sierra::program_registry::{ProgramRegistry, ProgramRegistryError};
 use thiserror::Error;
+use utils::diagnostic::Diagnostic;
 
 pub mod ap_change_info;
 pub mod core_libfunc_ap_change;
@@ -28,10 +31,71 @@ pub enum ApChange {
     AtLocalsFinalizationByTypeSize(ConcreteTypeId),
     /// The libfunc changes `ap` by a known size, which is the size of the given type.
     KnownByTypeSize(ConcreteTypeId),
+    /// The libfunc changes `ap` by a known size, which is the size of the stack slot of the given
+    /// type - see [StackLayout::slot_size].
+    KnownByStackSlotSize(ConcreteTypeId),
     /// The libfunc is a function call - it changes according to the given function and call cost.
     FunctionCall(FunctionId),
     // The libfunc allocates locals, the `ap` change depends on the environment.
     FinalizeLocals,
+    /// The libfunc changes `ap` by a known size, which depends on the form of one of its inputs.
+    ByInputForm(ApChangeByInputForm),
+}
+
+/// The form of the reference expression of a libfunc input, as far as it affects the generated
+/// code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InputForm {
+    /// A single cell dereference, e.g. `[ap - 1]`.
+    Deref,
+    /// A single constant, e.g. `7`.
+    Immediate,
+    /// Any other expression, e.g. `[fp + 1] + 3`, `[[ap - 2] + 1]` or a multi-cell expression.
+    Compound,
+}
+
+/// An `ap` change that depends on the form of a single libfunc input.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApChangeByInputForm {
+    /// The index of the input the `ap` change depends on.
+    pub param_idx: usize,
+    /// The `ap` change if the input is a dereference.
+    pub deref: usize,
+    /// The `ap` change if the input is a constant.
+    pub immediate: usize,
+    /// The `ap` change if the input is any other expression.
+    pub compound: usize,
+}
+impl ApChangeByInputForm {
+    /// Returns the `ap` change given the forms of all the inputs of the invocation, or None if
+    /// there is no input at `param_idx`.
+    pub fn eval(&self, input_forms: &[InputForm]) -> Option<usize> {
+        Some(match input_forms.get(self.param_idx)? {
+            InputForm::Deref => self.deref,
+            InputForm::Immediate => self.immediate,
+            InputForm::Compound => self.compound,
+        })
+    }
+}
+
+/// The layout of values stored on the stack, which the ap changes of the program depend on.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StackLayout {
+    /// If set, values of types larger than this number of cells are passed to and returned from
+    /// functions by pointer: storing such a value on the stack copies it into a new segment, and
+    /// the value occupies a slot of `size + 1` cells, the last of which points to the copy.
+    pub by_pointer_threshold: Option<usize>,
+}
+impl StackLayout {
+    /// Returns whether values of a type of the given size are stored on the stack by pointer.
+    pub fn is_by_pointer(&self, type_size: usize) -> bool {
+        matches!(self.by_pointer_threshold, Some(threshold) if type_size > threshold)
+    }
+
+    /// Returns the number of stack cells occupied by a value of a type of the given size.
+    pub fn slot_size(&self, type_size: usize) -> usize {
+        if self.is_by_pointer(type_size) { type_size + 1 } else { type_size }
+    }
 }
 
 /// Error occurring while calculating the costing of a program's variables.
@@ -49,24 +113,93 @@ pub enum ApChangeError {
     SolvingApChangeEquationFailed,
 }
 
+impl From<ApChangeError> for Diagnostic {
+    fn from(error: ApChangeError) -> Self {
+        let message = format!("{error}.");
+        let (code, statement_idx) = match error {
+            ApChangeError::ProgramRegistryError(error) => return Diagnostic::from(*error),
+            ApChangeError::StatementOutOfBounds(statement_idx) => ("E0300", Some(statement_idx)),
+            ApChangeError::StatementOutOfOrder(statement_idx) => ("E0301", Some(statement_idx)),
+            ApChangeError::IllegalInvocation(statement_idx) => ("E0302", Some(statement_idx)),
+            ApChangeError::SolvingApChangeEquationFailed => ("E0303", None),
+        };
+        let diagnostic = Diagnostic::error(code, message);
+        match statement_idx {
+            Some(statement_idx) => diagnostic.with_statement(statement_idx.0),
+            None => diagnostic,
+        }
+    }
+}
+
 /// Calculates gas information for a given program.
 pub fn calc_ap_changes(program: &Program) -> Result<ApChangeInfo, ApChangeError> {
-    let registry = ProgramRegistry::<CoreType, CoreLibFunc>::new(program)?;
+    calc_ap_changes_with_stack_layout(program, StackLayout::default())
+}
+
+/// Calculates gas information for a given program, with values stored on the stack according to
+/// `stack_layout`.
+pub fn calc_ap_changes_with_stack_layout(
+    program: &Program,
+    stack_layout: StackLayout,
+) -> Result<ApChangeInfo, ApChangeError> {
+    calc_ap_changes_for_libfuncs::<CoreLibFunc>(
+        program,
+        stack_layout,
+        core_libfunc_ap_change::core_libfunc_ap_change,
+    )
+}
+
+/// Calculates the ap changes of a program using the libfuncs of `TLibFunc` - a hierarchy
+/// extending the core libfuncs - given the ap changes of the branches of each of its libfuncs.
+pub fn calc_ap_changes_for_libfuncs<TLibFunc: GenericLibFunc>(
+    program: &Program,
+    stack_layout: StackLayout,
+    libfunc_ap_change: impl Fn(&TLibFunc::Concrete) -> Vec<ApChange>,
+) -> Result<ApChangeInfo, ApChangeError> {
+    let registry = ProgramRegistry::<CoreType, TLibFunc>::new(program)?;
     let equations = generate_equations::generate_equations(program, |libfunc_id| {
         let libfunc = registry.get_libfunc(libfunc_id)?;
-        core_libfunc_ap_change::core_libfunc_ap_change(libfunc)
-            .into_iter()
-            .map(|ap_change| {
+        zip_eq(libfunc_ap_change(libfunc), libfunc.branch_signatures())
+            .map(|(ap_change, branch_signature)| {
+                let output_forms = branch_signature
+                    .vars
+                    .iter()
+                    .map(|var| match var.ref_info {
+                        OutputVarReferenceInfo::SameAsParam { param_idx } => {
+                            OutputForm::SameAsParam(param_idx)
+                        }
+                        OutputVarReferenceInfo::NewTempVar { .. }
+                        | OutputVarReferenceInfo::NewLocalVar
+                        | OutputVarReferenceInfo::StoredParam { .. } => {
+                            OutputForm::Known(InputForm::Deref)
+                        }
+                        OutputVarReferenceInfo::Deferred(DeferredOutputKind::Const) => {
+                            OutputForm::Known(InputForm::Immediate)
+                        }
+                        OutputVarReferenceInfo::Deferred(_) => {
+                            OutputForm::Known(InputForm::Compound)
+                        }
+                    })
+                    .collect();
                 Ok(match ap_change {
                     ApChange::KnownByTypeSize(ty) => Effects {
                         ap_change: ApChange::Known(registry.get_type(&ty)?.info().size as usize),
                         locals: 0,
+                        output_forms,
+                    },
+                    ApChange::KnownByStackSlotSize(ty) => Effects {
+                        ap_change: ApChange::Known(
+                            stack_layout.slot_size(registry.get_type(&ty)?.info().size as usize),
+                        ),
+                        locals: 0,
+                        output_forms,
                     },
                     ApChange::AtLocalsFinalizationByTypeSize(ty) => Effects {
                         ap_change: ApChange::Known(0),
                         locals: registry.get_type(&ty)?.info().size as usize,
+                        output_forms,
                     },
-                    _ => Effects { ap_change, locals: 0 },
+                    _ => Effects { ap_change, locals: 0, output_forms },
                 })
             })
             .collect::<Result<Vec<_>, _>>()
@@ -84,5 +217,5 @@ pub fn calc_ap_changes(program: &Program) -> Result<ApChangeInfo, ApChangeError>
             Var::FunctionApChange(func_id) => function_ap_change.insert(func_id, value as usize),
         };
     }
-    Ok(ApChangeInfo { variable_values, function_ap_change })
+    Ok(ApChangeInfo { variable_values, function_ap_change, stack_layout })
 }
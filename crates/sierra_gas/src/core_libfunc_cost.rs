@@ -1,5 +1,6 @@
 use sierra::extensions::builtin_cost::CostTokenType;
 use sierra::extensions::core::CoreConcreteLibFunc;
+use sierra::ids::ConcreteTypeId;
 use sierra::program::StatementIdx;
 use utils::collection_arithmetics::{add_maps, sub_maps};
 use utils::ordered_hash_map::OrderedHashMap;
@@ -27,6 +28,13 @@ impl CostOperations for Ops<'_> {
         self.gas_info.function_costs.get(&function.id).cloned()
     }
 
+    fn store_temp_cost(&self, ty: &ConcreteTypeId) -> Self::CostType {
+        Some(OrderedHashMap::from_iter([(
+            CostTokenType::Step,
+            self.gas_info.store_temp_costs.get(ty).copied().unwrap_or(1),
+        )]))
+    }
+
     fn statement_var_cost(&self, token_type: CostTokenType) -> Self::CostType {
         Some(OrderedHashMap::from_iter([(
             token_type,
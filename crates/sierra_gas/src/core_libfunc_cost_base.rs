@@ -4,20 +4,23 @@ use sierra::extensions::builtin_cost::{
     BuiltinCostConcreteLibFunc, BuiltinCostGetGasLibFunc, CostTokenType,
 };
 use sierra::extensions::core::CoreConcreteLibFunc::{
-    self, ApTracking, Array, Bitwise, Bool, Box, BranchAlign, BuiltinCost, DictFeltTo, Drop, Dup,
-    Enum, Felt, FunctionCall, Gas, Mem, Pedersen, Struct, Uint128, UnconditionalJump,
+    self, ApTracking, Array, Bitwise, Bool, Box, BranchAlign, BuiltinCost, Debug, DictFeltTo, Drop,
+    Dup, Enum, Felt, FunctionCall, Gas, Mem, Pedersen, Struct, Uint128, UnconditionalJump,
     UnwrapNonZero,
 };
 use sierra::extensions::dict_felt_to::DictFeltToConcreteLibFunc;
 use sierra::extensions::enm::EnumConcreteLibFunc;
 use sierra::extensions::felt::FeltConcrete;
 use sierra::extensions::function_call::FunctionCallConcreteLibFunc;
-use sierra::extensions::gas::GasConcreteLibFunc::{GetGas, RefundGas};
+use sierra::extensions::gas::GasConcreteLibFunc::{
+    GetGas, RefundGas, ReserveCreate, ReserveRelease,
+};
 use sierra::extensions::mem::MemConcreteLibFunc::{
     AlignTemps, AllocLocal, FinalizeLocals, Rename, StoreLocal, StoreTemp,
 };
 use sierra::extensions::strct::StructConcreteLibFunc;
 use sierra::extensions::uint128::{IntOperator, Uint128Concrete, Uint128OperationConcreteLibFunc};
+use sierra::ids::ConcreteTypeId;
 use sierra::program::Function;
 
 use crate::starknet_libfunc_cost_base::starknet_libfunc_cost_base;
@@ -32,6 +35,8 @@ pub trait CostOperations {
     fn const_cost_token(&self, value: i32, token_type: CostTokenType) -> Self::CostType;
     /// Get a cost for the content of a function.
     fn function_cost(&mut self, function: &Function) -> Self::CostType;
+    /// Get the cost of storing a value of the given type on the stack.
+    fn store_temp_cost(&self, ty: &ConcreteTypeId) -> Self::CostType;
     /// Get a cost for a variable for the current statement.
     fn statement_var_cost(&self, token_type: CostTokenType) -> Self::CostType;
     /// Adds costs.
@@ -40,8 +45,64 @@ pub trait CostOperations {
     fn sub(&self, lhs: Self::CostType, rhs: Self::CostType) -> Self::CostType;
 }
 
+/// The cost of a single branch of a libfunc.
+pub struct BranchCost<CostType> {
+    /// The cost charged from the wallet for invoking the libfunc.
+    pub charge: CostType,
+    /// The part of the charge that is statically known to be returned to the wallet when the
+    /// branch is taken (e.g. the part of the gas reserved for a storage write that fails without
+    /// writing).
+    pub guaranteed_refund: CostType,
+    /// The change the branch makes to the gas held in gas reserves.
+    pub reserve_change: ReserveChange,
+}
+
+/// The change a branch of a libfunc makes to the gas held in gas reserves. The reserved gas does
+/// not pass through the wallet, so it is accounted for in separate equations, making sure every
+/// reserve is released exactly once by the function creating it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReserveChange {
+    /// No reserve is created or released.
+    None,
+    /// A reserve holding the given amount of gas is created.
+    Create(i32),
+    /// A reserve is released. The amount of gas it holds is a variable of the equations.
+    Release,
+}
+
+/// Returns the charge and guaranteed refund of every branch of a libfunc - a helper function for
+/// creating gas equations that credit the refunds.
+pub fn core_libfunc_branch_costs<Ops: CostOperations>(
+    ops: &mut Ops,
+    libfunc: &CoreConcreteLibFunc,
+) -> Vec<BranchCost<Ops::CostType>> {
+    let reserve_changes = match libfunc {
+        CoreConcreteLibFunc::StarkNet(libfunc) => {
+            return starknet_libfunc_cost_base(ops, libfunc);
+        }
+        Gas(ReserveCreate(libfunc)) => {
+            let amount =
+                i32::try_from(&libfunc.c).expect("The amount is validated on specialization.");
+            vec![ReserveChange::Create(amount), ReserveChange::None]
+        }
+        Gas(ReserveRelease(_)) => vec![ReserveChange::Release],
+        _ => vec![],
+    };
+    core_libfunc_cost_base(ops, libfunc)
+        .into_iter()
+        .enumerate()
+        .map(|(branch, charge)| BranchCost {
+            charge,
+            guaranteed_refund: ops.const_cost(0),
+            reserve_change: reserve_changes.get(branch).copied().unwrap_or(ReserveChange::None),
+        })
+        .collect()
+}
+
 /// Returns some cost value for a libfunc - a helper function to implement costing both for creating
 /// gas equations and getting actual gas usage after having a solution.
+///
+/// The returned value of a branch is its charge, after crediting its guaranteed refund.
 pub fn core_libfunc_cost_base<Ops: CostOperations>(
     ops: &mut Ops,
     libfunc: &CoreConcreteLibFunc,
@@ -63,6 +124,11 @@ pub fn core_libfunc_cost_base<Ops: CostOperations>(
             ]
         }
         Gas(RefundGas(_)) => vec![ops.statement_var_cost(CostTokenType::Step)],
+        // The reserved gas is taken out of the gas counter and returned to it without passing
+        // through the wallet, so only the steps are charged. The reserve itself is accounted for
+        // by the reserve changes of the branches (see `core_libfunc_branch_costs`).
+        Gas(ReserveCreate(_)) => vec![ops.const_cost(3), ops.const_cost(4)],
+        Gas(ReserveRelease(_)) => vec![ops.const_cost(0)],
         BranchAlign(_) => {
             let cost = CostTokenType::iter()
                 .map(|token_type| ops.statement_var_cost(*token_type))
@@ -70,23 +136,35 @@ pub fn core_libfunc_cost_base<Ops: CostOperations>(
             vec![ops.add(cost.unwrap(), ops.const_cost(1))]
         }
         Array(ArrayConcreteLibFunc::New(_)) => vec![ops.const_cost(1)],
+        Array(ArrayConcreteLibFunc::NewWithCapacity(_)) => vec![ops.const_cost(1)],
+        // The call site and the three instructions of the shared routine.
+        Array(ArrayConcreteLibFunc::PanicArrayNew(_)) => vec![ops.const_cost(5)],
         Array(ArrayConcreteLibFunc::Append(_)) => vec![ops.const_cost(2)],
         Array(ArrayConcreteLibFunc::At(_)) => vec![ops.const_cost(4), ops.const_cost(3)],
+        Array(ArrayConcreteLibFunc::Get(_)) => vec![ops.const_cost(5), ops.const_cost(5)],
         Array(ArrayConcreteLibFunc::Len(_)) => vec![ops.const_cost(0)],
+        Array(ArrayConcreteLibFunc::LenChecked(_)) => vec![ops.const_cost(3)],
+        // The iterations of the copy loop are charged per cell at runtime, so only the code around
+        // the loop is part of the cost.
+        Array(ArrayConcreteLibFunc::Clone(_)) => vec![ops.const_cost(16), ops.const_cost(7)],
         Uint128(libfunc) => integer_libfunc_cost(ops, libfunc),
         Felt(libfunc) => felt_libfunc_cost(ops, libfunc),
-        Drop(_) | Dup(_) | ApTracking(_) | UnwrapNonZero(_) | Mem(Rename(_)) | Box(_) => {
+        Drop(_) | Dup(_) | ApTracking(_) | UnwrapNonZero(_) | Mem(Rename(_)) | Box(_)
+        | Debug(_) => {
             vec![ops.const_cost(0)]
         }
-        Mem(StoreLocal(_) | AllocLocal(_) | StoreTemp(_) | AlignTemps(_) | FinalizeLocals(_))
+        Mem(StoreTemp(libfunc)) => vec![ops.store_temp_cost(&libfunc.ty)],
+        Mem(StoreLocal(_) | AllocLocal(_) | AlignTemps(_) | FinalizeLocals(_))
         | UnconditionalJump(_) => vec![ops.const_cost(1)],
         Enum(EnumConcreteLibFunc::Init(_)) => vec![ops.const_cost(1)],
         Enum(EnumConcreteLibFunc::Match(sig)) => {
             vec![ops.const_cost(1); sig.signature.branch_signatures.len()]
         }
-        Struct(StructConcreteLibFunc::Construct(_) | StructConcreteLibFunc::Deconstruct(_)) => {
-            vec![ops.const_cost(0)]
-        }
+        Struct(
+            StructConcreteLibFunc::Construct(_)
+            | StructConcreteLibFunc::Deconstruct(_)
+            | StructConcreteLibFunc::MemberGet(_),
+        ) => vec![ops.const_cost(0)],
         DictFeltTo(DictFeltToConcreteLibFunc::New(_)) => {
             vec![ops.const_cost(1)]
         }
@@ -103,7 +181,10 @@ pub fn core_libfunc_cost_base<Ops: CostOperations>(
         Pedersen(_) => {
             vec![ops.add(ops.const_cost(2), ops.const_cost_token(1, CostTokenType::Pedersen))]
         }
-        BuiltinCost(BuiltinCostConcreteLibFunc::BuiltinGetGas(_)) => {
+        BuiltinCost(
+            BuiltinCostConcreteLibFunc::BuiltinGetGas(_)
+            | BuiltinCostConcreteLibFunc::WithdrawGasAll(_),
+        ) => {
             let cost = CostTokenType::iter()
                 .map(|token_type| ops.statement_var_cost(*token_type))
                 .reduce(|x, y| ops.add(x, y));
@@ -115,7 +196,10 @@ pub fn core_libfunc_cost_base<Ops: CostOperations>(
                 ops.const_cost(compute_requested_cost_steps + 5),
             ]
         }
-        CoreConcreteLibFunc::StarkNet(libfunc) => starknet_libfunc_cost_base(ops, libfunc),
+        CoreConcreteLibFunc::StarkNet(libfunc) => starknet_libfunc_cost_base(ops, libfunc)
+            .into_iter()
+            .map(|cost| ops.sub(cost.charge, cost.guaranteed_refund))
+            .collect(),
     }
 }
 
@@ -138,6 +222,9 @@ fn integer_libfunc_cost<Ops: CostOperations>(
                 }
             }
         }
+        Uint128Concrete::CheckedMul(_) => {
+            vec![ops.const_cost(8), ops.const_cost(15)]
+        }
         Uint128Concrete::Const(_) | Uint128Concrete::ToFelt(_) => {
             vec![ops.const_cost(0)]
         }
@@ -1,10 +1,13 @@
+use std::collections::HashMap;
+
 use sierra::extensions::builtin_cost::CostTokenType;
 use sierra::extensions::core::CoreConcreteLibFunc;
+use sierra::ids::ConcreteTypeId;
 use sierra::program::StatementIdx;
 use utils::collection_arithmetics::{add_maps, sub_maps};
 use utils::ordered_hash_map::OrderedHashMap;
 
-use crate::core_libfunc_cost_base::{core_libfunc_cost_base, CostOperations};
+use crate::core_libfunc_cost_base::{core_libfunc_branch_costs, BranchCost, CostOperations};
 use crate::cost_expr::{CostExpr, Var};
 use crate::generate_equations::StatementFutureCost;
 
@@ -14,6 +17,7 @@ pub type CostExprMap = OrderedHashMap<CostTokenType, CostExpr>;
 struct Ops<'a> {
     statement_future_cost: &'a mut dyn StatementFutureCost,
     idx: StatementIdx,
+    store_temp_costs: &'a HashMap<ConcreteTypeId, i64>,
 }
 impl CostOperations for Ops<'_> {
     type CostType = CostExprMap;
@@ -30,6 +34,10 @@ impl CostOperations for Ops<'_> {
         self.statement_future_cost.get_future_cost(&function.entry_point).clone()
     }
 
+    fn store_temp_cost(&self, ty: &ConcreteTypeId) -> Self::CostType {
+        self.const_cost(self.store_temp_costs.get(ty).map_or(1, |cost| *cost as i32))
+    }
+
     fn statement_var_cost(&self, token_type: CostTokenType) -> Self::CostType {
         Self::CostType::from_iter([(
             token_type,
@@ -46,11 +54,17 @@ impl CostOperations for Ops<'_> {
     }
 }
 
-/// Returns an expression for the gas cost for core libfuncs.
+/// Returns expressions for the gas charges and guaranteed refunds of the branches of core libfuncs.
+/// `store_temp_costs` are the costs of storing values of the types stored on the stack by pointer,
+/// as in [crate::gas_info::GasInfo::store_temp_costs].
 pub fn core_libfunc_cost_expr(
     statement_future_cost: &mut dyn StatementFutureCost,
     idx: &StatementIdx,
     libfunc: &CoreConcreteLibFunc,
-) -> Vec<CostExprMap> {
-    core_libfunc_cost_base(&mut Ops { statement_future_cost, idx: *idx }, libfunc)
+    store_temp_costs: &HashMap<ConcreteTypeId, i64>,
+) -> Vec<BranchCost<CostExprMap>> {
+    core_libfunc_branch_costs(
+        &mut Ops { statement_future_cost, idx: *idx, store_temp_costs },
+        libfunc,
+    )
 }
@@ -11,6 +11,12 @@ pub enum Var {
     LibFuncImplicitGasVariable(StatementIdx, CostTokenType),
     /// Variable marking on a statement's future cost (any route from it to a return).
     StatementFuture(StatementIdx, CostTokenType),
+    /// Variable for the gas held by the reserve released by a libfunc at statement id
+    /// (gas_reserve_release).
+    ReleasedReserve(StatementIdx),
+    /// Variable marking on the gas released from reserves minus the gas reserved, on any route
+    /// from a statement to a return.
+    StatementFutureReserve(StatementIdx),
 }
 impl fmt::Display for Var {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -19,6 +25,8 @@ impl fmt::Display for Var {
                 write!(f, "libfunc@{token_type:?}{idx}")
             }
             Var::StatementFuture(idx, token_type) => write!(f, "future@{token_type:?}{idx}"),
+            Var::ReleasedReserve(idx) => write!(f, "released@{idx}"),
+            Var::StatementFutureReserve(idx) => write!(f, "future_reserve@{idx}"),
         }
     }
 }
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use sierra::extensions::builtin_cost::CostTokenType;
-use sierra::ids::FunctionId;
+use sierra::ids::{ConcreteTypeId, FunctionId};
 use sierra::program::StatementIdx;
 use utils::ordered_hash_map::OrderedHashMap;
 
@@ -12,4 +12,7 @@ pub struct GasInfo {
     pub variable_values: HashMap<(StatementIdx, CostTokenType), i64>,
     /// The costs of calling the given function.
     pub function_costs: HashMap<FunctionId, OrderedHashMap<CostTokenType, i64>>,
+    /// The costs in steps of storing values of the types stored on the stack by pointer, which
+    /// depend on the sizes of the types. Storing a value of any other type costs a single step.
+    pub store_temp_costs: HashMap<ConcreteTypeId, i64>,
 }
@@ -0,0 +1,457 @@
+//! A pass inserting gas withdrawals into cycles of a Sierra program that lack them.
+//!
+//! The gas equations of a program may only be solved if every cycle of statements withdraws gas.
+//! The pass finds the cycles without a withdrawal, and inserts a `get_gas` invocation at the head
+//! of each - a statement dominating a back edge of the cycle - followed by an out-of-gas panic
+//! sequence in its failure branch.
+
+use std::collections::{HashMap, VecDeque};
+
+use sierra::extensions::builtin_cost::BuiltinCostConcreteLibFunc::BuiltinGetGas;
+use sierra::extensions::core::{CoreConcreteLibFunc, CoreLibFunc, CoreType, CoreTypeConcrete};
+use sierra::extensions::gas::GasConcreteLibFunc::GetGas;
+use sierra::extensions::{ConcreteLibFunc, ConcreteType};
+use sierra::ids::{ConcreteLibFuncId, ConcreteTypeId, FunctionId, GenericLibFuncId, VarId};
+use sierra::program::{
+    BranchInfo, BranchTarget, ConcreteLibFuncLongId, GenericArg, Invocation, LibFuncDeclaration,
+    Program, Statement, StatementIdx,
+};
+use sierra::program_registry::{ProgramRegistry, ProgramRegistryError};
+use thiserror::Error;
+use utils::diagnostic::Diagnostic;
+use utils::strongly_connected_components::{compute_scc, GraphNode};
+
+#[cfg(test)]
+#[path = "gas_withdrawals_test.rs"]
+mod test;
+
+/// The panic data of running out of gas - the felt 'OOG'.
+const OUT_OF_GAS_PANIC_DATA: u32 = 0x4f4f47;
+
+/// Error occurring while inserting gas withdrawals into a program.
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum GasWithdrawalError {
+    #[error("error from the program registry")]
+    ProgramRegistryError(#[from] Box<ProgramRegistryError>),
+    #[error("found an illegal statement index during gas withdrawal insertion")]
+    StatementOutOfBounds(StatementIdx),
+    #[error("found an irreducible cycle without a gas withdrawal")]
+    MultipleCycleEntries(StatementIdx),
+    #[error("the range check and gas builtins are not available at the head of a cycle")]
+    MissingBuiltins(StatementIdx),
+    #[error("a variable available at the head of a cycle cannot be dropped")]
+    UndroppableVariable(StatementIdx),
+    #[error("function `{0}` does not return its builtins and a panic result")]
+    UnsupportedSignature(FunctionId),
+    #[error("found a cycle that is not reachable from any function")]
+    UnreachableCycle(StatementIdx),
+}
+
+impl From<GasWithdrawalError> for Diagnostic {
+    fn from(error: GasWithdrawalError) -> Self {
+        let message = format!("{error}.");
+        match error {
+            GasWithdrawalError::ProgramRegistryError(error) => Diagnostic::from(*error),
+            GasWithdrawalError::StatementOutOfBounds(statement_idx) => {
+                Diagnostic::error("E0200", message).with_statement(statement_idx.0)
+            }
+            GasWithdrawalError::MultipleCycleEntries(statement_idx) => {
+                Diagnostic::error("E0202", message).with_statement(statement_idx.0)
+            }
+            GasWithdrawalError::MissingBuiltins(statement_idx) => {
+                Diagnostic::error("E0203", message).with_statement(statement_idx.0)
+            }
+            GasWithdrawalError::UndroppableVariable(statement_idx) => {
+                Diagnostic::error("E0204", message).with_statement(statement_idx.0)
+            }
+            GasWithdrawalError::UnsupportedSignature(function) => {
+                Diagnostic::error("E0205", message).with_function(function)
+            }
+            GasWithdrawalError::UnreachableCycle(statement_idx) => {
+                Diagnostic::error("E0206", message).with_statement(statement_idx.0)
+            }
+        }
+    }
+}
+
+/// A gas withdrawal inserted into a program.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InsertedWithdrawal {
+    /// The function containing the cycle.
+    pub function: FunctionId,
+    /// The index of the inserted `get_gas` statement, right before the original head of the
+    /// cycle.
+    pub statement_idx: StatementIdx,
+    /// The index of the first statement of the out-of-gas panic sequence.
+    pub out_of_gas_idx: StatementIdx,
+}
+
+/// Inserts a gas withdrawal into every cycle of `program` that lacks one.
+///
+/// Each withdrawal is inserted right before the head of its cycle - the first statement of the
+/// cycle that dominates a back edge to it. Its failure branch drops the available variables and
+/// returns the builtins with an 'OOG' panic, whose data is built by the shared panic array routine.
+/// Therefore the function of the cycle must have the range check and gas builtins available at the
+/// head, and return its builtins followed by a panic result.
+/// Returns the transformed program, and the inserted withdrawals - with the statement indices of
+/// the transformed program.
+pub fn insert_gas_withdrawals(
+    program: &Program,
+) -> Result<(Program, Vec<InsertedWithdrawal>), GasWithdrawalError> {
+    let mut program = program.clone();
+    let mut inserted: Vec<InsertedWithdrawal> = vec![];
+    loop {
+        let registry = ProgramRegistry::<CoreType, CoreLibFunc>::new(&program)?;
+        let Some(head) = find_uncovered_cycle_head(&program, &registry)? else {
+            return Ok((program, inserted));
+        };
+        let withdrawal = insert_withdrawal(&mut program, &registry, head)?;
+        for prev in &mut inserted {
+            prev.statement_idx = shifted(prev.statement_idx, head);
+            prev.out_of_gas_idx = shifted(prev.out_of_gas_idx, head);
+        }
+        inserted.push(withdrawal);
+    }
+}
+
+/// Returns the index of a statement after a statement was inserted at `inserted_idx`.
+fn shifted(idx: StatementIdx, inserted_idx: StatementIdx) -> StatementIdx {
+    if idx.0 > inserted_idx.0 {
+        StatementIdx(idx.0 + 1)
+    } else {
+        idx
+    }
+}
+
+/// Returns the head of some cycle of statements that lacks a gas withdrawal, if there is one.
+fn find_uncovered_cycle_head(
+    program: &Program,
+    registry: &ProgramRegistry<CoreType, CoreLibFunc>,
+) -> Result<Option<StatementIdx>, GasWithdrawalError> {
+    let mut is_withdrawal = vec![];
+    for (idx, statement) in program.statements.iter().enumerate() {
+        if let Statement::Invocation(invocation) = statement {
+            for branch in &invocation.branches {
+                let target = StatementIdx(idx).next(&branch.target);
+                if target.0 >= program.statements.len() {
+                    return Err(GasWithdrawalError::StatementOutOfBounds(target));
+                }
+            }
+        }
+        is_withdrawal.push(match statement {
+            Statement::Invocation(invocation) => matches!(
+                registry.get_libfunc(&invocation.libfunc_id)?,
+                CoreConcreteLibFunc::Gas(GetGas(_))
+                    | CoreConcreteLibFunc::BuiltinCost(BuiltinGetGas(_))
+            ),
+            Statement::Return(_) => false,
+        });
+    }
+    for idx in (0..program.statements.len()).map(StatementIdx) {
+        if is_withdrawal[idx.0] {
+            continue;
+        }
+        let node = StatementNode { program, is_withdrawal: &is_withdrawal, idx };
+        let mut scc = compute_scc(node.clone());
+        if scc.len() == 1 && !node.get_neighbors().iter().any(|neighbor| neighbor.idx == idx) {
+            continue;
+        }
+        scc.sort_by_key(|idx| idx.0);
+        let Some(root) = program
+            .funcs
+            .iter()
+            .map(|func| func.entry_point)
+            .find(|entry_point| reachable_statements(program, *entry_point, None)[idx.0])
+        else {
+            return Err(GasWithdrawalError::UnreachableCycle(scc[0]));
+        };
+        // The head is the first statement with a back edge - an edge from a statement of the cycle
+        // that is only reachable through the head.
+        let head = scc.iter().copied().find(|head| {
+            let reachable_without_head = reachable_statements(program, root, Some(*head));
+            scc.iter().any(|source| {
+                !reachable_without_head[source.0] && successors(program, *source).contains(head)
+            })
+        });
+        return head.map(Some).ok_or(GasWithdrawalError::MultipleCycleEntries(scc[0]));
+    }
+    Ok(None)
+}
+
+/// Returns the statements following `idx`.
+fn successors(program: &Program, idx: StatementIdx) -> Vec<StatementIdx> {
+    match &program.statements[idx.0] {
+        Statement::Invocation(invocation) => {
+            invocation.branches.iter().map(|branch| idx.next(&branch.target)).collect()
+        }
+        Statement::Return(_) => vec![],
+    }
+}
+
+/// Returns whether each statement is reachable from `root` without passing through `blocked`.
+fn reachable_statements(
+    program: &Program,
+    root: StatementIdx,
+    blocked: Option<StatementIdx>,
+) -> Vec<bool> {
+    let mut reachable = vec![false; program.statements.len()];
+    let mut stack = vec![root];
+    while let Some(idx) = stack.pop() {
+        if reachable[idx.0] || Some(idx) == blocked {
+            continue;
+        }
+        reachable[idx.0] = true;
+        stack.extend(successors(program, idx));
+    }
+    reachable
+}
+
+/// A statement as a node in the graph of the statements that do not withdraw gas.
+#[derive(Clone)]
+struct StatementNode<'a> {
+    program: &'a Program,
+    is_withdrawal: &'a [bool],
+    idx: StatementIdx,
+}
+impl<'a> GraphNode for StatementNode<'a> {
+    type NodeId = StatementIdx;
+
+    fn get_neighbors(&self) -> Vec<Self> {
+        successors(self.program, self.idx)
+            .into_iter()
+            .filter(|target| !self.is_withdrawal[target.0])
+            .map(|idx| StatementNode { idx, ..self.clone() })
+            .collect()
+    }
+
+    fn get_id(&self) -> Self::NodeId {
+        self.idx
+    }
+}
+
+/// Inserts a gas withdrawal right before `head`, and appends its out-of-gas panic sequence to the
+/// end of the program.
+fn insert_withdrawal(
+    program: &mut Program,
+    registry: &ProgramRegistry<CoreType, CoreLibFunc>,
+    head: StatementIdx,
+) -> Result<InsertedWithdrawal, GasWithdrawalError> {
+    let (function, mut vars) = available_variables(program, registry, head)?;
+    vars.sort_by_key(|(var, _)| var.id);
+    let find_builtin =
+        |is_builtin: fn(&CoreTypeConcrete) -> bool| -> Result<Option<VarId>, GasWithdrawalError> {
+            for (var, ty) in &vars {
+                if is_builtin(registry.get_type(ty)?) {
+                    return Ok(Some(var.clone()));
+                }
+            }
+            Ok(None)
+        };
+    let (Some(range_check), Some(gas_builtin)) = (
+        find_builtin(|ty| matches!(ty, CoreTypeConcrete::RangeCheck(_)))?,
+        find_builtin(|ty| matches!(ty, CoreTypeConcrete::GasBuiltin(_)))?,
+    ) else {
+        return Err(GasWithdrawalError::MissingBuiltins(head));
+    };
+
+    // The returned variables are the available variables of the matching types, followed by the
+    // panic result.
+    let signature = &registry.get_function(&function)?.signature;
+    let unsupported_signature = || GasWithdrawalError::UnsupportedSignature(function.clone());
+    let (panic_result_ty, builtin_tys) =
+        signature.ret_types.split_last().ok_or_else(unsupported_signature)?;
+    let CoreTypeConcrete::Enum(panic_result) = registry.get_type(panic_result_ty)? else {
+        return Err(unsupported_signature());
+    };
+    let [_, panic_data_ty] = &panic_result.variants[..] else {
+        return Err(unsupported_signature());
+    };
+    let CoreTypeConcrete::Array(panic_data) = registry.get_type(panic_data_ty)? else {
+        return Err(unsupported_signature());
+    };
+    let felt_ty = &panic_data.ty;
+    if !matches!(registry.get_type(felt_ty)?, CoreTypeConcrete::Felt(_)) {
+        return Err(unsupported_signature());
+    }
+    let mut returned_vars = vec![];
+    for ty in builtin_tys {
+        let idx =
+            vars.iter().position(|(_, var_ty)| var_ty == ty).ok_or_else(unsupported_signature)?;
+        returned_vars.push(vars.remove(idx));
+    }
+
+    let mut next_var_id = max_var_id(program) + 1;
+    let mut new_var = || {
+        next_var_id += 1;
+        VarId::new(next_var_id - 1)
+    };
+    let out_of_gas_var = new_var();
+    let panic_data_var = new_var();
+    let panic_result_var = new_var();
+    let mut out_of_gas_statements = vec![];
+    for (var, ty) in vars {
+        if !registry.get_type(&ty)?.info().droppable {
+            return Err(GasWithdrawalError::UndroppableVariable(head));
+        }
+        let drop = libfunc_id(program, "drop", vec![GenericArg::Type(ty)]);
+        out_of_gas_statements.push(simple_invocation(drop, vec![var], vec![]));
+    }
+    let felt_const =
+        libfunc_id(program, "felt_const", vec![GenericArg::Value(OUT_OF_GAS_PANIC_DATA.into())]);
+    let panic_array_new = libfunc_id(program, "panic_array_new", vec![]);
+    let enum_init = libfunc_id(
+        program,
+        "enum_init",
+        vec![GenericArg::Type(panic_result_ty.clone()), GenericArg::Value(1.into())],
+    );
+    out_of_gas_statements.extend([
+        simple_invocation(felt_const, vec![], vec![out_of_gas_var.clone()]),
+        simple_invocation(panic_array_new, vec![out_of_gas_var], vec![panic_data_var.clone()]),
+        simple_invocation(enum_init, vec![panic_data_var], vec![panic_result_var.clone()]),
+    ]);
+    returned_vars.push((panic_result_var, panic_result_ty.clone()));
+    for (var, ty) in &returned_vars {
+        let store_temp = libfunc_id(program, "store_temp", vec![GenericArg::Type(ty.clone())]);
+        out_of_gas_statements.push(simple_invocation(
+            store_temp,
+            vec![var.clone()],
+            vec![var.clone()],
+        ));
+    }
+    out_of_gas_statements
+        .push(Statement::Return(returned_vars.into_iter().map(|(var, _)| var).collect()));
+
+    // Shifting the statements following the head, to make room for the withdrawal.
+    for statement in &mut program.statements {
+        if let Statement::Invocation(invocation) = statement {
+            for branch in &mut invocation.branches {
+                if let BranchTarget::Statement(target) = &mut branch.target {
+                    *target = shifted(*target, head);
+                }
+            }
+        }
+    }
+    for func in &mut program.funcs {
+        func.entry_point = shifted(func.entry_point, head);
+    }
+    // The withdrawal takes the place of the head, so it also takes its location.
+    program.statement_locations = program
+        .statement_locations
+        .iter()
+        .flat_map(|(idx, location)| {
+            if idx.0 < head.0 {
+                vec![(*idx, *location)]
+            } else if *idx == head {
+                vec![(head, *location), (StatementIdx(head.0 + 1), *location)]
+            } else {
+                vec![(StatementIdx(idx.0 + 1), *location)]
+            }
+        })
+        .collect();
+
+    let out_of_gas_idx = StatementIdx(program.statements.len() + 1);
+    let get_gas = libfunc_id(program, "get_gas", vec![]);
+    let builtins = vec![range_check, gas_builtin];
+    program.statements.insert(
+        head.0,
+        Statement::Invocation(Invocation {
+            libfunc_id: get_gas,
+            args: builtins.clone(),
+            branches: vec![
+                BranchInfo { target: BranchTarget::Fallthrough, results: builtins.clone() },
+                BranchInfo { target: BranchTarget::Statement(out_of_gas_idx), results: builtins },
+            ],
+        }),
+    );
+    program.statements.extend(out_of_gas_statements);
+    Ok(InsertedWithdrawal { function, statement_idx: head, out_of_gas_idx })
+}
+
+/// Returns the function reaching `target`, and the variables available at it with their types.
+fn available_variables(
+    program: &Program,
+    registry: &ProgramRegistry<CoreType, CoreLibFunc>,
+    target: StatementIdx,
+) -> Result<(FunctionId, Vec<(VarId, ConcreteTypeId)>), GasWithdrawalError> {
+    let mut visited = vec![false; program.statements.len()];
+    let mut queue = VecDeque::new();
+    for func in &program.funcs {
+        let vars: HashMap<VarId, ConcreteTypeId> =
+            func.params.iter().map(|param| (param.id.clone(), param.ty.clone())).collect();
+        queue.push_back((func.entry_point, func.id.clone(), vars));
+    }
+    while let Some((idx, function, mut vars)) = queue.pop_front() {
+        if visited[idx.0] {
+            continue;
+        }
+        visited[idx.0] = true;
+        if idx == target {
+            return Ok((function, vars.into_iter().collect()));
+        }
+        let Statement::Invocation(invocation) = &program.statements[idx.0] else {
+            continue;
+        };
+        let libfunc = registry.get_libfunc(&invocation.libfunc_id)?;
+        for arg in &invocation.args {
+            vars.remove(arg);
+        }
+        for (branch, branch_signature) in
+            invocation.branches.iter().zip(libfunc.branch_signatures())
+        {
+            let mut branch_vars = vars.clone();
+            for (result, var_info) in branch.results.iter().zip(&branch_signature.vars) {
+                branch_vars.insert(result.clone(), var_info.ty.clone());
+            }
+            queue.push_back((idx.next(&branch.target), function.clone(), branch_vars));
+        }
+    }
+    Err(GasWithdrawalError::UnreachableCycle(target))
+}
+
+/// Returns the largest id of a variable in the program.
+fn max_var_id(program: &Program) -> u64 {
+    let params = program.funcs.iter().flat_map(|func| func.params.iter().map(|param| &param.id));
+    let statement_vars = program.statements.iter().flat_map(|statement| match statement {
+        Statement::Invocation(invocation) => invocation
+            .args
+            .iter()
+            .chain(invocation.branches.iter().flat_map(|branch| branch.results.iter()))
+            .collect(),
+        Statement::Return(vars) => vars.iter().collect::<Vec<_>>(),
+    });
+    params.chain(statement_vars).map(|var| var.id).max().unwrap_or_default()
+}
+
+/// Returns the id of the libfunc with the given generic id and arguments, declaring it if it is not
+/// declared yet.
+fn libfunc_id(
+    program: &mut Program,
+    generic_id: &str,
+    generic_args: Vec<GenericArg>,
+) -> ConcreteLibFuncId {
+    let long_id = ConcreteLibFuncLongId {
+        generic_id: GenericLibFuncId::from_string(generic_id),
+        generic_args,
+    };
+    if let Some(declaration) =
+        program.libfunc_declarations.iter().find(|declaration| declaration.long_id == long_id)
+    {
+        return declaration.id.clone();
+    }
+    let id = ConcreteLibFuncId::from_string(long_id.to_string());
+    program.libfunc_declarations.push(LibFuncDeclaration { id: id.clone(), long_id });
+    id
+}
+
+/// Returns an invocation of a libfunc with a single fallthrough branch.
+fn simple_invocation(
+    libfunc_id: ConcreteLibFuncId,
+    args: Vec<VarId>,
+    results: Vec<VarId>,
+) -> Statement {
+    Statement::Invocation(Invocation {
+        libfunc_id,
+        args,
+        branches: vec![BranchInfo { target: BranchTarget::Fallthrough, results }],
+    })
+}
@@ -0,0 +1,324 @@
+use indoc::indoc;
+use sierra::program::{Program, StatementIdx};
+use sierra::ProgramParser;
+
+use super::{insert_gas_withdrawals, GasWithdrawalError, InsertedWithdrawal};
+use crate::{calc_gas_info, CostError};
+
+/// The declarations shared by the test programs.
+const DECLARATIONS: &str = indoc! {"
+    type RangeCheck = RangeCheck;
+    type GasBuiltin = GasBuiltin;
+    type felt = felt;
+    type NonZeroFelt = NonZero<felt>;
+    type ArrayFelt = Array<felt>;
+    type PanicResult = Enum<ut@PanicResult, felt, ArrayFelt>;
+
+    libfunc revoke_ap_tracking = revoke_ap_tracking;
+    libfunc felt_jump_nz = felt_jump_nz;
+    libfunc felt_unwrap_nz = unwrap_nz<felt>;
+    libfunc felt_sub_1 = felt_sub<1>;
+    libfunc felt_const_0 = felt_const<0>;
+    libfunc dup_felt = dup<felt>;
+    libfunc enum_init_ok = enum_init<PanicResult, 0>;
+    libfunc store_temp_rc = store_temp<RangeCheck>;
+    libfunc store_temp_gb = store_temp<GasBuiltin>;
+    libfunc store_temp_felt = store_temp<felt>;
+    libfunc store_temp_result = store_temp<PanicResult>;
+    libfunc jump = jump;
+"};
+
+/// Returns a program counting `[2]` down to zero, without withdrawing gas.
+fn simple_loop_program() -> Program {
+    ProgramParser::new()
+        .parse(&format!(
+            "{DECLARATIONS}{}",
+            indoc! {"
+                revoke_ap_tracking() -> ();
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_felt([2]) -> ([2]);
+                felt_jump_nz([2]) { fallthrough() 11([2]) };
+                felt_const_0() -> ([3]);
+                enum_init_ok([3]) -> ([3]);
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_result([3]) -> ([3]);
+                return([0], [1], [3]);
+                felt_unwrap_nz([2]) -> ([2]);
+                felt_sub_1([2]) -> ([2]);
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_felt([2]) -> ([2]);
+                jump() { 4() };
+
+                count_down@0([0]: RangeCheck, [1]: GasBuiltin, [2]: felt)
+                    -> (RangeCheck, GasBuiltin, PanicResult);
+            "}
+        ))
+        .unwrap()
+}
+
+/// Returns a program counting every number below `[2]` down to zero, without withdrawing gas.
+fn nested_loop_program() -> Program {
+    ProgramParser::new()
+        .parse(&format!(
+            "{DECLARATIONS}{}",
+            indoc! {"
+                revoke_ap_tracking() -> ();
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_felt([2]) -> ([2]);
+                felt_jump_nz([2]) { fallthrough() 11([2]) };
+                felt_const_0() -> ([4]);
+                enum_init_ok([4]) -> ([4]);
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_result([4]) -> ([4]);
+                return([0], [1], [4]);
+                felt_unwrap_nz([2]) -> ([2]);
+                felt_sub_1([2]) -> ([2]);
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_felt([2]) -> ([2]);
+                dup_felt([2]) -> ([2], [3]);
+                store_temp_felt([3]) -> ([3]);
+                felt_jump_nz([3]) { fallthrough() 23([3]) };
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_felt([2]) -> ([2]);
+                jump() { 4() };
+                felt_unwrap_nz([3]) -> ([3]);
+                felt_sub_1([3]) -> ([3]);
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_felt([2]) -> ([2]);
+                store_temp_felt([3]) -> ([3]);
+                jump() { 18() };
+
+                count_nested@0([0]: RangeCheck, [1]: GasBuiltin, [2]: felt)
+                    -> (RangeCheck, GasBuiltin, PanicResult);
+            "}
+        ))
+        .unwrap()
+}
+
+#[test]
+fn simple_loop() {
+    let program = simple_loop_program();
+    assert_eq!(calc_gas_info(&program).unwrap_err(), CostError::SolvingGasEquationFailed);
+
+    let (program, inserted) = insert_gas_withdrawals(&program).unwrap();
+    assert_eq!(
+        inserted,
+        vec![InsertedWithdrawal {
+            function: "count_down".into(),
+            statement_idx: StatementIdx(4),
+            out_of_gas_idx: StatementIdx(18),
+        }]
+    );
+    assert_eq!(
+        program.to_string(),
+        format!(
+            "{DECLARATIONS}{}",
+            indoc! {"
+                libfunc drop<felt> = drop<felt>;
+                libfunc felt_const<5197639> = felt_const<5197639>;
+                libfunc panic_array_new = panic_array_new;
+                libfunc enum_init<PanicResult, 1> = enum_init<PanicResult, 1>;
+                libfunc get_gas = get_gas;
+
+                revoke_ap_tracking() -> ();
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_felt([2]) -> ([2]);
+                get_gas([0], [1]) { fallthrough([0], [1]) 18([0], [1]) };
+                felt_jump_nz([2]) { fallthrough() 12([2]) };
+                felt_const_0() -> ([3]);
+                enum_init_ok([3]) -> ([3]);
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_result([3]) -> ([3]);
+                return([0], [1], [3]);
+                felt_unwrap_nz([2]) -> ([2]);
+                felt_sub_1([2]) -> ([2]);
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_felt([2]) -> ([2]);
+                jump() { 4() };
+                drop<felt>([2]) -> ();
+                felt_const<5197639>() -> ([4]);
+                panic_array_new([4]) -> ([5]);
+                enum_init<PanicResult, 1>([5]) -> ([6]);
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_result([6]) -> ([6]);
+                return([0], [1], [6]);
+
+                count_down@0([0]: RangeCheck, [1]: GasBuiltin, [2]: felt) -> (RangeCheck, GasBuiltin, PanicResult);
+            "}
+        )
+    );
+    assert!(calc_gas_info(&program).is_ok());
+    // A program with withdrawals in all of its cycles is left unchanged.
+    assert_eq!(insert_gas_withdrawals(&program).unwrap(), (program, vec![]));
+}
+
+#[test]
+fn nested_loop() {
+    let program = nested_loop_program();
+    assert_eq!(calc_gas_info(&program).unwrap_err(), CostError::SolvingGasEquationFailed);
+
+    // A withdrawal is first inserted at the entry of the outer loop, which leaves the inner loop
+    // without one.
+    let (program, inserted) = insert_gas_withdrawals(&program).unwrap();
+    assert_eq!(
+        inserted,
+        vec![
+            InsertedWithdrawal {
+                function: "count_nested".into(),
+                statement_idx: StatementIdx(4),
+                out_of_gas_idx: StatementIdx(32),
+            },
+            InsertedWithdrawal {
+                function: "count_nested".into(),
+                statement_idx: StatementIdx(19),
+                out_of_gas_idx: StatementIdx(40),
+            },
+        ]
+    );
+    assert!(calc_gas_info(&program).is_ok());
+}
+
+#[test]
+fn cycle_with_multiple_entries() {
+    // The loop is entered both at its head, and in its middle past the withdrawal of one of its
+    // paths. The head dominates the back edge, so the withdrawal is inserted before it.
+    let program = ProgramParser::new()
+        .parse(&format!(
+            "{DECLARATIONS}{}",
+            indoc! {"
+                libfunc drop_felt = drop<felt>;
+                libfunc get_gas = get_gas;
+
+                revoke_ap_tracking() -> ();
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_felt([2]) -> ([2]);
+                felt_jump_nz([2]) { fallthrough() 11([2]) };
+                felt_const_0() -> ([4]);
+                enum_init_ok([4]) -> ([4]);
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_result([4]) -> ([4]);
+                return([0], [1], [4]);
+                felt_unwrap_nz([2]) -> ([2]);
+                felt_sub_1([2]) -> ([2]);
+                store_temp_felt([2]) -> ([2]);
+                dup_felt([2]) -> ([2], [3]);
+                felt_jump_nz([3]) { fallthrough() 17([3]) };
+                jump() { 20() };
+                felt_unwrap_nz([3]) -> ([3]);
+                drop_felt([3]) -> ();
+                get_gas([0], [1]) { fallthrough([0], [1]) 24([0], [1]) };
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_felt([2]) -> ([2]);
+                jump() { 4() };
+                drop_felt([2]) -> ();
+                felt_const_0() -> ([4]);
+                enum_init_ok([4]) -> ([4]);
+                store_temp_rc([0]) -> ([0]);
+                store_temp_gb([1]) -> ([1]);
+                store_temp_result([4]) -> ([4]);
+                return([0], [1], [4]);
+
+                count_down@0([0]: RangeCheck, [1]: GasBuiltin, [2]: felt)
+                    -> (RangeCheck, GasBuiltin, PanicResult);
+            "}
+        ))
+        .unwrap();
+    assert_eq!(calc_gas_info(&program).unwrap_err(), CostError::SolvingGasEquationFailed);
+
+    let (program, inserted) = insert_gas_withdrawals(&program).unwrap();
+    assert_eq!(
+        inserted,
+        vec![InsertedWithdrawal {
+            function: "count_down".into(),
+            statement_idx: StatementIdx(4),
+            out_of_gas_idx: StatementIdx(32),
+        }]
+    );
+    assert!(calc_gas_info(&program).is_ok());
+}
+
+#[test]
+fn irreducible_cycle() {
+    // Each statement of the cycle may be reached without passing through the other one.
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+
+            libfunc felt_jump_nz = felt_jump_nz;
+            libfunc jump = jump;
+
+            felt_jump_nz([0]) { fallthrough() 2([0]) };
+            jump() { 2() };
+            jump() { 1() };
+
+            irreducible@0([0]: felt) -> ();
+        "})
+        .unwrap();
+    assert_eq!(
+        insert_gas_withdrawals(&program),
+        Err(GasWithdrawalError::MultipleCycleEntries(StatementIdx(1)))
+    );
+}
+
+#[test]
+fn missing_builtins() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+
+            libfunc felt_jump_nz = felt_jump_nz;
+            libfunc felt_unwrap_nz = unwrap_nz<felt>;
+            libfunc felt_sub_1 = felt_sub<1>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc jump = jump;
+
+            store_temp_felt([0]) -> ([0]);
+            felt_jump_nz([0]) { fallthrough() 3([0]) };
+            return();
+            felt_unwrap_nz([0]) -> ([0]);
+            felt_sub_1([0]) -> ([0]);
+            store_temp_felt([0]) -> ([0]);
+            jump() { 1() };
+
+            no_builtins@0([0]: felt) -> ();
+        "})
+        .unwrap();
+    assert_eq!(
+        insert_gas_withdrawals(&program),
+        Err(GasWithdrawalError::MissingBuiltins(StatementIdx(1)))
+    );
+}
+
+#[test]
+fn unreachable_cycle() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            libfunc jump = jump;
+
+            return();
+            jump() { 2() };
+            jump() { 2() };
+
+            empty@0() -> ();
+        "})
+        .unwrap();
+    assert_eq!(
+        insert_gas_withdrawals(&program),
+        Err(GasWithdrawalError::UnreachableCycle(StatementIdx(2)))
+    );
+}
@@ -6,6 +6,7 @@ use utils::collection_arithmetics::{add_maps, sub_maps};
 use utils::ordered_hash_map::OrderedHashMap;
 
 use super::CostError;
+use crate::core_libfunc_cost_base::{BranchCost, ReserveChange};
 use crate::core_libfunc_cost_expr::CostExprMap;
 use crate::cost_expr::{CostExpr, Var};
 
@@ -19,22 +20,40 @@ pub trait StatementFutureCost {
     fn get_future_cost(&mut self, idx: &StatementIdx) -> &CostExprMap;
 }
 
+/// The equations generated for a program.
+pub struct Equations {
+    /// The equations of the costs, per token type.
+    pub costs: OrderedHashMap<CostTokenType, Vec<CostExpr>>,
+    /// The equations of the gas moved through gas reserves, which are satisfiable only if on any
+    /// route from a function entry point to a return, the released reserves hold exactly the gas
+    /// reserved on it.
+    pub reserves: Vec<CostExpr>,
+}
+
 /// Generates a set of equations from a program, and a function to extract cost expressions from a
 /// library function id.
+///
+/// The guaranteed refund of a branch is credited to the wallet when the branch is taken.
 pub fn generate_equations<
-    GetCost: Fn(&mut dyn StatementFutureCost, &StatementIdx, &ConcreteLibFuncId) -> Vec<CostExprMap>,
+    GetCost: Fn(
+        &mut dyn StatementFutureCost,
+        &StatementIdx,
+        &ConcreteLibFuncId,
+    ) -> Vec<BranchCost<CostExprMap>>,
 >(
     program: &Program,
     get_cost: GetCost,
-) -> Result<OrderedHashMap<CostTokenType, Vec<CostExpr>>, CostError> {
+) -> Result<Equations, CostError> {
     // Calculating first to fail early.
     let statement_topological_ordering = get_reverse_topological_ordering(program)?;
     // Vector containing the cost from a statement until the end of the function in some path (may
     // be a variable).
-    let mut generator = EquationGenerator::new(vec![None; program.statements.len()]);
-    // Adding a variable for every function entry point.
+    let mut generator = EquationGenerator::new(program.statements.len());
+    // Adding a variable for every function entry point. Reserves are not passed between
+    // functions, so no gas is released from reserves beyond the reserved gas of a function.
     for func in &program.funcs {
         generator.get_future_cost(&func.entry_point);
+        generator.future_reserves[func.entry_point.0] = Some(CostExpr::from_const(0));
     }
     // Using reverse topological order to go over the program statement so that we'd use less
     // variables (since we create variables in any case where we don't already have a cost
@@ -42,38 +61,59 @@ pub fn generate_equations<
     for idx in statement_topological_ordering {
         match &program.get_statement(&idx).unwrap() {
             sierra::program::Statement::Return(_) => {
-                generator.set_or_add_constraint(&idx, CostExprMap::default());
+                generator.set_or_add_constraint(
+                    &idx,
+                    CostExprMap::default(),
+                    CostExpr::from_const(0),
+                );
             }
             sierra::program::Statement::Invocation(invocation) => {
                 let libfunc_cost = get_cost(&mut generator, &idx, &invocation.libfunc_id);
                 for (branch, branch_cost) in zip_eq(&invocation.branches, libfunc_cost) {
-                    let next_future_cost =
-                        generator.get_future_cost(&idx.next(&branch.target)).clone();
-                    generator.set_or_add_constraint(&idx, add_maps(branch_cost, next_future_cost));
+                    let next_idx = idx.next(&branch.target);
+                    let next_future_cost = generator.get_future_cost(&next_idx).clone();
+                    let next_future_reserve = generator.get_future_reserve(&next_idx).clone();
+                    let BranchCost { charge, guaranteed_refund, reserve_change } = branch_cost;
+                    let released = match reserve_change {
+                        ReserveChange::None => CostExpr::from_const(0),
+                        ReserveChange::Create(amount) => CostExpr::from_const(-amount),
+                        ReserveChange::Release => CostExpr::from_var(Var::ReleasedReserve(idx)),
+                    };
+                    generator.set_or_add_constraint(
+                        &idx,
+                        add_maps(sub_maps(charge, guaranteed_refund), next_future_cost),
+                        released + next_future_reserve,
+                    );
                 }
             }
         }
     }
-    Ok(generator.equations)
+    Ok(Equations { costs: generator.equations, reserves: generator.reserve_equations })
 }
 
 /// Helper to generate the equations for calculating gas variables.
 struct EquationGenerator {
     pub future_costs: Vec<Option<CostExprMap>>,
     pub equations: OrderedHashMap<CostTokenType, Vec<CostExpr>>,
+    /// The gas released from reserves minus the gas reserved, from a statement until the end of
+    /// the function in some path (may be a variable).
+    pub future_reserves: Vec<Option<CostExpr>>,
+    pub reserve_equations: Vec<CostExpr>,
 }
 impl EquationGenerator {
-    fn new(future_costs: Vec<Option<CostExprMap>>) -> Self {
+    fn new(statement_count: usize) -> Self {
         Self {
-            future_costs,
+            future_costs: vec![None; statement_count],
             equations: OrderedHashMap::from_iter(
                 CostTokenType::iter().map(|token_type| (*token_type, vec![])),
             ),
+            future_reserves: vec![None; statement_count],
+            reserve_equations: vec![],
         }
     }
 
     /// Sets some future or adds a matching equation if already set.
-    fn set_or_add_constraint(&mut self, idx: &StatementIdx, cost: CostExprMap) {
+    fn set_or_add_constraint(&mut self, idx: &StatementIdx, cost: CostExprMap, reserve: CostExpr) {
         let entry = &mut self.future_costs[idx.0];
         if let Some(other) = entry {
             for (token_type, val) in sub_maps(other.clone(), cost) {
@@ -82,6 +122,21 @@ impl EquationGenerator {
         } else {
             *entry = Some(cost);
         }
+        let entry = &mut self.future_reserves[idx.0];
+        if let Some(other) = entry {
+            let equation = other.clone() - reserve;
+            if equation != CostExpr::from_const(0) {
+                self.reserve_equations.push(equation);
+            }
+        } else {
+            *entry = Some(reserve);
+        }
+    }
+
+    /// Returns the gas released from reserves minus the gas reserved, starting from a statement.
+    fn get_future_reserve(&mut self, idx: &StatementIdx) -> &CostExpr {
+        self.future_reserves[idx.0]
+            .get_or_insert_with(|| CostExpr::from_var(Var::StatementFutureReserve(*idx)))
     }
 }
 impl StatementFutureCost for EquationGenerator {
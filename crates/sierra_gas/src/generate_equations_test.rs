@@ -7,6 +7,7 @@ use sierra::program::StatementIdx;
 use test_case::test_case;
 
 use super::generate_equations;
+use crate::core_libfunc_cost_base::{BranchCost, ReserveChange};
 use crate::core_libfunc_cost_expr::CostExprMap;
 use crate::cost_expr::{CostExpr, Var};
 use crate::CostError;
@@ -108,9 +109,66 @@ fn generate(
                 .get(libfunc_id)
                 .unwrap()
                 .iter()
-                .map(|x| CostExprMap::from_iter([(CostTokenType::Step, x.clone())]))
+                .map(|x| BranchCost {
+                    charge: CostExprMap::from_iter([(CostTokenType::Step, x.clone())]),
+                    guaranteed_refund: CostExprMap::default(),
+                    reserve_change: ReserveChange::None,
+                })
                 .collect()
         },
-    )?[CostTokenType::Step]
+    )?
+    .costs[CostTokenType::Step]
         .clone())
 }
+
+/// Returns a cost expression for a released reserve variable.
+fn released_reserve(idx: usize) -> CostExpr {
+    CostExpr::from_var(Var::ReleasedReserve(StatementIdx(idx)))
+}
+
+#[test_case(indoc! {"
+                create() { fallthrough() 3() };
+                release() -> ();
+                return();
+                return();
+                test_program@0() -> ();
+            "} =>
+            Ok(vec![
+                // The released reserve holds the gas reserved on the success branch of 'create'.
+                CostExpr::from_const(100) - released_reserve(1),
+            ]);
+            "create and release")]
+#[test_case(indoc! {"
+                create() { fallthrough() 2() };
+                return();
+                release() -> ();
+                return();
+                test_program@0() -> ();
+            "} =>
+            Ok(vec![
+                // The reserve created on the success branch is not released.
+                CostExpr::from_const(100),
+                // The reserve released on the failure branch was not created.
+                CostExpr::from_const(0) - released_reserve(2),
+            ]);
+            "release on wrong branch")]
+fn generate_reserves(code: &str) -> Result<Vec<CostExpr>, CostError> {
+    let reserve_changes = HashMap::<ConcreteLibFuncId, Vec<ReserveChange>>::from([
+        ("create".into(), vec![ReserveChange::Create(100), ReserveChange::None]),
+        ("release".into(), vec![ReserveChange::Release]),
+    ]);
+    Ok(generate_equations(
+        &sierra::ProgramParser::new().parse(code).unwrap(),
+        |_, _idx, libfunc_id| {
+            reserve_changes[libfunc_id]
+                .iter()
+                .map(|reserve_change| BranchCost {
+                    charge: CostExprMap::default(),
+                    guaranteed_refund: CostExprMap::default(),
+                    reserve_change: *reserve_change,
+                })
+                .collect()
+        },
+    )?
+    .reserves)
+}
@@ -1,12 +1,19 @@
 use std::collections::HashMap;
 
-use cost_expr::Var;
+use core_libfunc_cost_base::{BranchCost, ReserveChange};
+use core_libfunc_cost_expr::CostExprMap;
+use cost_expr::{CostExpr, Var};
 use gas_info::GasInfo;
+use generate_equations::StatementFutureCost;
 use sierra::extensions::builtin_cost::CostTokenType;
-use sierra::extensions::core::{CoreLibFunc, CoreType};
+use sierra::extensions::core::{CoreConcreteLibFunc, CoreLibFunc, CoreType};
+use sierra::extensions::{ConcreteType, GenericLibFunc};
+use sierra::ids::{ConcreteLibFuncId, ConcreteTypeId};
 use sierra::program::{Program, StatementIdx};
 use sierra::program_registry::{ProgramRegistry, ProgramRegistryError};
+use sierra_ap_change::StackLayout;
 use thiserror::Error;
+use utils::diagnostic::Diagnostic;
 use utils::ordered_hash_map::OrderedHashMap;
 
 pub mod core_libfunc_cost;
@@ -14,6 +21,7 @@ mod core_libfunc_cost_base;
 mod core_libfunc_cost_expr;
 mod cost_expr;
 pub mod gas_info;
+pub mod gas_withdrawals;
 mod generate_equations;
 mod starknet_libfunc_cost_base;
 
@@ -29,25 +37,116 @@ pub enum CostError {
     StatementOutOfBounds(StatementIdx),
     #[error("failed solving the symbol tables")]
     SolvingGasEquationFailed,
+    #[error("found a gas reserve not released exactly once by the function creating it")]
+    UnbalancedGasReserves,
+}
+
+impl From<CostError> for Diagnostic {
+    fn from(error: CostError) -> Self {
+        let message = format!("{error}.");
+        match error {
+            CostError::ProgramRegistryError(error) => Diagnostic::from(*error),
+            CostError::StatementOutOfBounds(statement_idx) => {
+                Diagnostic::error("E0200", message).with_statement(statement_idx.0)
+            }
+            CostError::SolvingGasEquationFailed => Diagnostic::error("E0201", message),
+            CostError::UnbalancedGasReserves => Diagnostic::error("E0207", message),
+        }
+    }
 }
 
 /// Calculates gas information for a given program.
 pub fn calc_gas_info(program: &Program) -> Result<GasInfo, CostError> {
-    let registry = ProgramRegistry::<CoreType, CoreLibFunc>::new(program)?;
-    let equations = generate_equations::generate_equations(
-        program,
-        |statement_future_cost, idx, libfunc_id| {
-            let libfunc = registry
-                .get_libfunc(libfunc_id)
-                .expect("Program registery creation would have already failed.");
-            core_libfunc_cost_expr::core_libfunc_cost_expr(statement_future_cost, idx, libfunc)
-        },
-    )?;
+    calc_gas_info_with_stack_layout(program, StackLayout::default())
+}
+
+/// Calculates gas information for a given program, with values stored on the stack according to
+/// `stack_layout`.
+pub fn calc_gas_info_with_stack_layout(
+    program: &Program,
+    stack_layout: StackLayout,
+) -> Result<GasInfo, CostError> {
+    calc_gas_info_for_libfuncs::<CoreLibFunc>(program, stack_layout, |libfunc| Ok(libfunc))
+}
+
+/// Calculates gas information for a program using the libfuncs of `TLibFunc` - a hierarchy
+/// extending the core libfuncs. `as_core` returns the core libfunc a libfunc of the hierarchy is,
+/// or the constant cost in steps of each of its branches if it is not a core libfunc.
+pub fn calc_gas_info_for_libfuncs<TLibFunc: GenericLibFunc>(
+    program: &Program,
+    stack_layout: StackLayout,
+    as_core: impl Fn(&TLibFunc::Concrete) -> Result<&CoreConcreteLibFunc, Vec<i32>>,
+) -> Result<GasInfo, CostError> {
+    let registry = ProgramRegistry::<CoreType, TLibFunc>::new(program)?;
+    let mut store_temp_costs = HashMap::<ConcreteTypeId, i64>::default();
+    for declaration in &program.type_declarations {
+        let size = registry.get_type(&declaration.id)?.info().size as usize;
+        if stack_layout.is_by_pointer(size) {
+            store_temp_costs.insert(declaration.id.clone(), by_pointer_store_temp_cost(size));
+        }
+    }
+    solve_gas_info(program, &store_temp_costs, |statement_future_cost, idx, libfunc_id| {
+        let libfunc = registry
+            .get_libfunc(libfunc_id)
+            .expect("Program registery creation would have already failed.");
+        match as_core(libfunc) {
+            Ok(libfunc) => core_libfunc_cost_expr::core_libfunc_cost_expr(
+                statement_future_cost,
+                idx,
+                libfunc,
+                &store_temp_costs,
+            ),
+            Err(costs) => costs
+                .into_iter()
+                .map(|cost| BranchCost {
+                    charge: CostExprMap::from_iter([(
+                        CostTokenType::Step,
+                        CostExpr::from_const(cost),
+                    )]),
+                    guaranteed_refund: CostExprMap::default(),
+                    reserve_change: ReserveChange::None,
+                })
+                .collect(),
+        }
+    })
+}
+
+/// Returns the cost in steps of storing a value of `type_size` cells on the stack by pointer: at
+/// most 2 steps for copying each cell into the new segment, as a cell which is not a plain cell
+/// reference is first stored into a scratch cell, and a step for advancing `ap`.
+pub fn by_pointer_store_temp_cost(type_size: usize) -> i64 {
+    2 * type_size as i64 + 1
+}
+
+/// Calculates gas information for a given program, given the costs of storing values of the types
+/// stored on the stack by pointer and a function to extract the branch costs of a library function
+/// id.
+fn solve_gas_info<
+    GetCost: Fn(
+        &mut dyn StatementFutureCost,
+        &StatementIdx,
+        &ConcreteLibFuncId,
+    ) -> Vec<BranchCost<CostExprMap>>,
+>(
+    program: &Program,
+    store_temp_costs: &HashMap<ConcreteTypeId, i64>,
+    get_cost: GetCost,
+) -> Result<GasInfo, CostError> {
+    let equations = generate_equations::generate_equations(program, get_cost)?;
+    // Without released reserves or cycles, the reserve equations have no variables to solve for.
+    let reserves_balanced = if equations.reserves.iter().all(|eq| eq.var_to_coef.is_empty()) {
+        equations.reserves.iter().all(|eq| eq.const_term == 0)
+    } else {
+        solver::try_solve_equations(equations.reserves).is_some()
+    };
+    if !reserves_balanced {
+        return Err(CostError::UnbalancedGasReserves);
+    }
 
     let mut variable_values = HashMap::<(StatementIdx, CostTokenType), i64>::default();
     let mut function_costs =
         HashMap::<sierra::ids::FunctionId, OrderedHashMap<CostTokenType, i64>>::default();
-    for (token_type, token_equations) in equations {
+    for (token_type, token_equations) in equations.costs {
         let solution = solver::try_solve_equations(token_equations)
             .ok_or(CostError::SolvingGasEquationFailed)?;
         for func in &program.funcs {
@@ -70,5 +169,5 @@ pub fn calc_gas_info(program: &Program) -> Result<GasInfo, CostError> {
             }
         }
     }
-    Ok(GasInfo { variable_values, function_costs })
+    Ok(GasInfo { variable_values, function_costs, store_temp_costs: store_temp_costs.clone() })
 }
@@ -1,21 +1,128 @@
 use sierra::extensions::starknet::StarkNetConcreteLibFunc;
 
-use crate::core_libfunc_cost_base::CostOperations;
+use crate::core_libfunc_cost_base::{BranchCost, CostOperations, ReserveChange};
+
+// TODO(Ilya): Revisit the real costs.
+/// The gas reserved for a storage read syscall - the worst case cost of its execution.
+const STORAGE_READ_GAS: i32 = 50;
+/// The gas reserved for a storage write syscall.
+const STORAGE_WRITE_GAS: i32 = 50;
+/// The gas reserved for a syscall calling a contract or a library, not including the charge per
+/// word of its call data.
+const CALL_GAS: i32 = 100;
+/// The gas reserved for a deploy syscall, not including the charge per word of its call data.
+const DEPLOY_GAS: i32 = 200;
+/// The gas reserved for the emit event and send message to L1 syscalls.
+const EMIT_GAS: i32 = 50;
+/// The gas reserved for the syscalls returning information on the execution.
+const GETTER_GAS: i32 = 20;
+/// The gas reserved for the replace class syscall.
+const REPLACE_CLASS_GAS: i32 = 50;
+/// The gas reserved for the cryptographic syscalls.
+const CRYPTO_GAS: i32 = 100;
 
 /// Returns some cost value for a StarkNet libfunc - a helper function to implement costing both for
 /// creating gas equations and getting actual gas usage after having a solution.
 pub fn starknet_libfunc_cost_base<Ops: CostOperations>(
     ops: &mut Ops,
     libfunc: &StarkNetConcreteLibFunc,
-) -> Vec<Ops::CostType> {
+) -> Vec<BranchCost<Ops::CostType>> {
+    match libfunc {
+        StarkNetConcreteLibFunc::CallContract(_)
+        | StarkNetConcreteLibFunc::Deploy(_)
+        | StarkNetConcreteLibFunc::LibraryCall(_)
+        | StarkNetConcreteLibFunc::SendMessageToL1(_)
+        | StarkNetConcreteLibFunc::EmitEvent(_)
+        | StarkNetConcreteLibFunc::GetCallerAddress(_)
+        | StarkNetConcreteLibFunc::GetContractAddress(_)
+        | StarkNetConcreteLibFunc::GetSequencerAddress(_)
+        | StarkNetConcreteLibFunc::GetBlockNumber(_)
+        | StarkNetConcreteLibFunc::GetBlockTimestamp(_)
+        | StarkNetConcreteLibFunc::GetTxInfo(_)
+        | StarkNetConcreteLibFunc::ReplaceClass(_)
+        | StarkNetConcreteLibFunc::Keccak(_)
+        | StarkNetConcreteLibFunc::Secp256k1New(_)
+        | StarkNetConcreteLibFunc::Secp256k1Add(_)
+        | StarkNetConcreteLibFunc::Secp256k1Mul(_)
+        | StarkNetConcreteLibFunc::Secp256k1GetXy(_)
+        | StarkNetConcreteLibFunc::StorageRead(_)
+        | StarkNetConcreteLibFunc::StorageReadChecked(_)
+        | StarkNetConcreteLibFunc::StorageWrite(_)
+        | StarkNetConcreteLibFunc::StorageWriteChecked(_) => {
+            let reserved_gas = syscall_reserved_gas(libfunc);
+            vec![no_refund_cost(ops, reserved_gas), no_refund_cost(ops, reserved_gas)]
+        }
+        StarkNetConcreteLibFunc::ContractAddressConst(_)
+        | StarkNetConcreteLibFunc::ContractAddressToFelt(_)
+        | StarkNetConcreteLibFunc::StorageAddressConst(_)
+        | StarkNetConcreteLibFunc::StorageBaseAddressConst(_)
+        | StarkNetConcreteLibFunc::StorageAddressFromBaseAndOffset(_) => {
+            vec![no_refund_cost(ops, 0)]
+        }
+        StarkNetConcreteLibFunc::StorageBaseAddressFromFelt(_) => vec![no_refund_cost(ops, 12)],
+        StarkNetConcreteLibFunc::ContractAddressTryFromFelt(_) => {
+            vec![no_refund_cost(ops, 12), no_refund_cost(ops, 13)]
+        }
+        // The following syscalls do not check the revert reason, so their only branch may also be
+        // taken on failure.
+        StarkNetConcreteLibFunc::StorageReadUnchecked(_)
+        | StarkNetConcreteLibFunc::StorageWriteUnchecked(_) => {
+            vec![no_refund_cost(ops, syscall_reserved_gas(libfunc))]
+        }
+        // Both branches reserve the gas of all the reads, including the reads not made after a
+        // failure.
+        StarkNetConcreteLibFunc::StorageReadMulti(libfunc) => {
+            let reserved_gas = STORAGE_READ_GAS * libfunc.size as i32;
+            vec![no_refund_cost(ops, reserved_gas), no_refund_cost(ops, reserved_gas)]
+        }
+    }
+}
+
+/// Returns the gas reserved for a syscall.
+fn syscall_reserved_gas(libfunc: &StarkNetConcreteLibFunc) -> i32 {
     match libfunc {
-        // TODO(Ilya): Revisit the real cost.
-        StarkNetConcreteLibFunc::CallContract(_) => vec![ops.const_cost(50), ops.const_cost(50)],
-        StarkNetConcreteLibFunc::ContractAddressConst(_) => vec![ops.const_cost(0)],
-        // TODO(Ilya): Consider adding a `CostTokenType::StorageRead` or make storage read a branch.
-        StarkNetConcreteLibFunc::StorageRead(_) => vec![ops.const_cost(50)],
-        // TODO(yuval): Revisit the real cost.
-        StarkNetConcreteLibFunc::StorageWrite(_) => vec![ops.const_cost(50), ops.const_cost(50)],
-        StarkNetConcreteLibFunc::StorageAddressConst(_) => vec![ops.const_cost(0)],
+        StarkNetConcreteLibFunc::CallContract(_) | StarkNetConcreteLibFunc::LibraryCall(_) => {
+            CALL_GAS
+        }
+        StarkNetConcreteLibFunc::Deploy(_) => DEPLOY_GAS,
+        StarkNetConcreteLibFunc::SendMessageToL1(_) | StarkNetConcreteLibFunc::EmitEvent(_) => {
+            EMIT_GAS
+        }
+        StarkNetConcreteLibFunc::GetCallerAddress(_)
+        | StarkNetConcreteLibFunc::GetContractAddress(_)
+        | StarkNetConcreteLibFunc::GetSequencerAddress(_)
+        | StarkNetConcreteLibFunc::GetBlockNumber(_)
+        | StarkNetConcreteLibFunc::GetBlockTimestamp(_)
+        | StarkNetConcreteLibFunc::GetTxInfo(_) => GETTER_GAS,
+        StarkNetConcreteLibFunc::ReplaceClass(_) => REPLACE_CLASS_GAS,
+        StarkNetConcreteLibFunc::Keccak(_)
+        | StarkNetConcreteLibFunc::Secp256k1New(_)
+        | StarkNetConcreteLibFunc::Secp256k1Add(_)
+        | StarkNetConcreteLibFunc::Secp256k1Mul(_)
+        | StarkNetConcreteLibFunc::Secp256k1GetXy(_) => CRYPTO_GAS,
+        StarkNetConcreteLibFunc::StorageRead(_)
+        | StarkNetConcreteLibFunc::StorageReadChecked(_)
+        | StarkNetConcreteLibFunc::StorageReadUnchecked(_)
+        | StarkNetConcreteLibFunc::StorageReadMulti(_) => STORAGE_READ_GAS,
+        StarkNetConcreteLibFunc::StorageWrite(_)
+        | StarkNetConcreteLibFunc::StorageWriteChecked(_)
+        | StarkNetConcreteLibFunc::StorageWriteUnchecked(_) => STORAGE_WRITE_GAS,
+        // The libfuncs which are not syscalls do not reserve gas.
+        StarkNetConcreteLibFunc::ContractAddressConst(_)
+        | StarkNetConcreteLibFunc::ContractAddressToFelt(_)
+        | StarkNetConcreteLibFunc::ContractAddressTryFromFelt(_)
+        | StarkNetConcreteLibFunc::StorageAddressConst(_)
+        | StarkNetConcreteLibFunc::StorageBaseAddressConst(_)
+        | StarkNetConcreteLibFunc::StorageBaseAddressFromFelt(_)
+        | StarkNetConcreteLibFunc::StorageAddressFromBaseAndOffset(_) => 0,
+    }
+}
+
+/// Returns a branch cost of a constant value, without a refund.
+fn no_refund_cost<Ops: CostOperations>(ops: &Ops, value: i32) -> BranchCost<Ops::CostType> {
+    BranchCost {
+        charge: ops.const_cost(value),
+        guaranteed_refund: ops.const_cost(0),
+        reserve_change: ReserveChange::None,
     }
 }
@@ -1,12 +1,20 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
+use indoc::{formatdoc, indoc};
 use sierra::extensions::builtin_cost::CostTokenType;
+use sierra::extensions::core::{CoreLibFunc, CoreType};
+use sierra::ids::FunctionId;
 use sierra::program::{Program, StatementIdx};
+use sierra::program_registry::ProgramRegistry;
 use test_case::test_case;
+use utils::ordered_hash_map::OrderedHashMap;
 
+use crate::core_libfunc_cost_base::BranchCost;
+use crate::core_libfunc_cost_expr::{core_libfunc_cost_expr, CostExprMap};
 use crate::gas_info::GasInfo;
-use crate::{calc_gas_info, CostError};
+use crate::{calc_gas_info, solve_gas_info, CostError};
 
 /// Returns a parsed example program from the example directory.
 fn get_example_program(name: &str) -> Program {
@@ -28,7 +36,8 @@ fn get_example_program(name: &str) -> Program {
                 function_costs: [(
                     "Fibonacci".into(),
                     [(CostTokenType::Step, 17)].into_iter().collect()
-                )].into_iter().collect()
+                )].into_iter().collect(),
+                store_temp_costs: HashMap::new(),
             });
             "fib_jumps")]
 #[test_case("fib_recursive" =>
@@ -43,8 +52,199 @@ fn get_example_program(name: &str) -> Program {
                 function_costs: [(
                     "Fibonacci".into(),
                     [(CostTokenType::Step, 11)].into_iter().collect()
-                )].into_iter().collect()
+                )].into_iter().collect(),
+                store_temp_costs: HashMap::new(),
             }))]
 fn solve_gas(path: &str) -> Result<GasInfo, CostError> {
     calc_gas_info(&get_example_program(path))
 }
+
+/// A program performing two consecutive storage writes, where a failure of any of them leads to an
+/// early return.
+const TWO_STORAGE_WRITES: &str = indoc! {"
+    type felt = felt;
+    type GasBuiltin = GasBuiltin;
+    type System = System;
+    type StorageAddress = StorageAddress;
+
+    libfunc storage_address_const = storage_address_const<0>;
+    libfunc storage_write = storage_write_syscall;
+    libfunc branch_align = branch_align;
+    libfunc store_temp_gb = store_temp<GasBuiltin>;
+    libfunc drop_felt = drop<felt>;
+    libfunc jump = jump;
+
+    storage_address_const() -> (addr);
+    storage_write(gb, system, addr, a) { fallthrough(gb, system) 9(gb, system, reason) };
+    branch_align() -> ();
+    storage_address_const() -> (addr);
+    store_temp_gb(gb) -> (gb);
+    storage_write(gb, system, addr, b) { fallthrough(gb, system) 12(gb, system, reason) };
+    branch_align() -> ();
+    store_temp_gb(gb) -> (gb);
+    return(gb, system);
+    branch_align() -> ();
+    drop_felt(b) -> ();
+    jump() { 13() };
+    branch_align() -> ();
+    drop_felt(reason) -> ();
+    store_temp_gb(gb) -> (gb);
+    return(gb, system);
+
+    TwoWrites@0(gb: GasBuiltin, system: System, a: felt, b: felt) -> (GasBuiltin, System);
+"};
+
+/// Returns the gas info of `TWO_STORAGE_WRITES`, given the values of the branch aligns of the
+/// failure branches of the first and the second write, and the function cost.
+fn two_storage_writes_gas_info(
+    align_first_failure: i64,
+    align_second_failure: i64,
+    cost: i64,
+) -> GasInfo {
+    GasInfo {
+        variable_values: [
+            ((StatementIdx(2), CostTokenType::Step), 0),
+            ((StatementIdx(6), CostTokenType::Step), 0),
+            ((StatementIdx(9), CostTokenType::Step), align_first_failure),
+            ((StatementIdx(12), CostTokenType::Step), align_second_failure),
+        ]
+        .into_iter()
+        .collect(),
+        function_costs: [("TwoWrites".into(), [(CostTokenType::Step, cost)].into_iter().collect())]
+            .into_iter()
+            .collect(),
+        store_temp_costs: HashMap::new(),
+    }
+}
+
+#[test]
+fn storage_write_branch_costs() {
+    let program = sierra::ProgramParser::new().parse(TWO_STORAGE_WRITES).unwrap();
+    // No syscall is credited a refund, so the failure branch of the first write, which skips the
+    // second write, is aligned to the cost of its success branch.
+    assert_eq!(calc_gas_info(&program), Ok(two_storage_writes_gas_info(51, 0, 104)));
+
+    let registry = ProgramRegistry::<CoreType, CoreLibFunc>::new(&program).unwrap();
+    let store_temp_costs = HashMap::new();
+    let without_refunds =
+        solve_gas_info(&program, &store_temp_costs, |statement_future_cost, idx, libfunc_id| {
+            core_libfunc_cost_expr(
+                statement_future_cost,
+                idx,
+                registry.get_libfunc(libfunc_id).unwrap(),
+                &store_temp_costs,
+            )
+            .into_iter()
+            .map(|cost| BranchCost {
+                charge: cost.charge,
+                guaranteed_refund: CostExprMap::default(),
+                reserve_change: cost.reserve_change,
+            })
+            .collect()
+        });
+    assert_eq!(without_refunds, Ok(two_storage_writes_gas_info(51, 0, 104)));
+}
+
+/// Returns a program calling a contract with call data of `call_data_len` words, appended to a new
+/// array, or with call data passed as a parameter if `call_data_len` is `None`.
+fn call_contract_program(call_data_len: Option<usize>) -> Program {
+    let (call_data_param, call_data_statements) = match call_data_len {
+        Some(len) => (
+            "",
+            format!(
+                "array_new_felt() -> (arr);\n{}",
+                "felt_const_1() -> (x);\narray_append_felt(arr, x) -> (arr);\n".repeat(len)
+            ),
+        ),
+        None => (", arr: ArrayFelt", String::new()),
+    };
+    let call_idx = call_data_statements.lines().count();
+    let failure_idx = call_idx + 5;
+    sierra::ProgramParser::new()
+        .parse(&formatdoc! {"
+            type felt = felt;
+            type GasBuiltin = GasBuiltin;
+            type System = System;
+            type ContractAddress = ContractAddress;
+            type ArrayFelt = Array<felt>;
+
+            libfunc array_new_felt = array_new<felt>;
+            libfunc array_append_felt = array_append<felt>;
+            libfunc felt_const_1 = felt_const<1>;
+            libfunc call_contract = call_contract_syscall;
+            libfunc branch_align = branch_align;
+            libfunc store_temp_gb = store_temp<GasBuiltin>;
+            libfunc drop_felt = drop<felt>;
+            libfunc drop_arr = drop<ArrayFelt>;
+
+            {call_data_statements}\
+            call_contract(gb, system, address, arr) {{ \
+                fallthrough(gb, system, arr) {failure_idx}(gb, system, reason, arr) }};
+            branch_align() -> ();
+            drop_arr(arr) -> ();
+            store_temp_gb(gb) -> (gb);
+            return(gb, system);
+            branch_align() -> ();
+            drop_felt(reason) -> ();
+            drop_arr(arr) -> ();
+            store_temp_gb(gb) -> (gb);
+            return(gb, system);
+
+            Call@0(gb: GasBuiltin, system: System, address: ContractAddress{call_data_param}) \
+                -> (GasBuiltin, System);
+        "})
+        .unwrap()
+}
+
+#[test_case(Some(1), 105; "one word")]
+#[test_case(Some(3), 109; "three words")]
+#[test_case(None, 102; "unknown length")]
+fn call_data_gas(call_data_len: Option<usize>, expected_cost: i64) {
+    let program = call_contract_program(call_data_len);
+    let gas_info = calc_gas_info(&program).unwrap();
+    // The call data is charged per word at runtime, so only the appends of its words are part of
+    // the static cost.
+    assert_eq!(
+        gas_info.function_costs[&FunctionId::from("Call")],
+        OrderedHashMap::from_iter([(CostTokenType::Step, expected_cost)])
+    );
+}
+
+#[test_case("gas_reserve_release" => Ok(()); "released by the creating function")]
+#[test_case("call_release" => Err(CostError::UnbalancedGasReserves); "released by a callee")]
+fn gas_reserve_release(release_libfunc: &str) -> Result<(), CostError> {
+    let program = sierra::ProgramParser::new()
+        .parse(&formatdoc! {"
+            type RangeCheck = RangeCheck;
+            type GasBuiltin = GasBuiltin;
+            type GasReserve = GasReserve;
+
+            libfunc gas_reserve_create = gas_reserve_create<100>;
+            libfunc gas_reserve_release = gas_reserve_release;
+            libfunc call_release = function_call<user@Release>;
+            libfunc branch_align = branch_align;
+            libfunc store_temp_rc = store_temp<RangeCheck>;
+            libfunc store_temp_gb = store_temp<GasBuiltin>;
+
+            gas_reserve_create(rc, gb) {{ fallthrough(rc, gb, reserve) 6(rc, gb) }};
+            branch_align() -> ();
+            {release_libfunc}(gb, reserve) -> (gb);
+            store_temp_rc(rc) -> (rc);
+            store_temp_gb(gb) -> (gb);
+            return(rc, gb);
+            branch_align() -> ();
+            store_temp_rc(rc) -> (rc);
+            store_temp_gb(gb) -> (gb);
+            return(rc, gb);
+            gas_reserve_release(gb, reserve) -> (gb);
+            store_temp_gb(gb) -> (gb);
+            return(gb);
+
+            Reserve@0(rc: RangeCheck, gb: GasBuiltin) -> (RangeCheck, GasBuiltin);
+            Release@10(gb: GasBuiltin, reserve: GasReserve) -> (GasBuiltin);
+        "})
+        .unwrap();
+    // The gas of a reserve passed to another function is not accounted for as released by the
+    // function creating it.
+    calc_gas_info(&program).map(|_| ())
+}
@@ -2,17 +2,16 @@
 #[path = "canonical_id_replacer_test.rs"]
 mod test;
 
-use std::collections::HashMap;
-
 use sierra::ids::{ConcreteLibFuncId, ConcreteTypeId, FunctionId};
+use utils::unordered_hash_map::UnorderedHashMap;
 
 use crate::replace_ids::SierraIdReplacer;
 
 #[derive(Default)]
 pub struct CanonicalReplacer {
-    type_ids: HashMap<ConcreteTypeId, ConcreteTypeId>,
-    function_ids: HashMap<FunctionId, FunctionId>,
-    libfunc_ids: HashMap<ConcreteLibFuncId, ConcreteLibFuncId>,
+    type_ids: UnorderedHashMap<ConcreteTypeId, ConcreteTypeId>,
+    function_ids: UnorderedHashMap<FunctionId, FunctionId>,
+    libfunc_ids: UnorderedHashMap<ConcreteLibFuncId, ConcreteLibFuncId>,
 }
 
 /// A replacer that replace the Ids in the program with canonical onces.
@@ -21,20 +20,20 @@ pub struct CanonicalReplacer {
 impl CanonicalReplacer {
     /// Builds a replacer from a program.
     pub fn from_program(program: &sierra::program::Program) -> Self {
-        let mut type_ids = HashMap::default();
+        let mut type_ids = UnorderedHashMap::default();
 
         for type_declaration in &program.type_declarations {
             let new_id = ConcreteTypeId::from_usize(type_ids.len());
             type_ids.insert(type_declaration.id.clone(), new_id.clone());
         }
 
-        let mut function_ids = HashMap::default();
+        let mut function_ids = UnorderedHashMap::default();
         for function in &program.funcs {
             let new_id = FunctionId::from_usize(function_ids.len());
             function_ids.insert(function.id.clone(), new_id.clone());
         }
 
-        let mut libfunc_ids = HashMap::default();
+        let mut libfunc_ids = UnorderedHashMap::default();
         for libfunc_declaration in &program.libfunc_declarations {
             let new_id = ConcreteLibFuncId::from_usize(libfunc_ids.len());
             libfunc_ids.insert(libfunc_declaration.id.clone(), new_id.clone());
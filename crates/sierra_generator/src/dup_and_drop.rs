@@ -2,8 +2,6 @@
 #[path = "dup_and_drop_test.rs"]
 mod test;
 
-use std::collections::HashSet;
-
 use itertools::{chain, Itertools};
 use sierra::ids::VarId;
 use sierra::program::Param;
@@ -122,8 +120,8 @@ fn get_existing_vars_per_statement(
                     let entry = &mut statement_existing_vars[next_index];
                     if let Some(previous_value) = entry {
                         assert_eq!(
-                            previous_value.iter().collect::<HashSet<_>>(),
-                            next_existing_vars.iter().collect::<HashSet<_>>(),
+                            previous_value.iter().collect::<UnorderedHashSet<_>>(),
+                            next_existing_vars.iter().collect::<UnorderedHashSet<_>>(),
                             "Got a different set of variables to the same entry point."
                         );
                     }
@@ -301,7 +301,10 @@ impl LocalVariablesState {
     ) {
         for (var_id, var_info) in zip_eq(var_ids, var_infos) {
             match var_info.ref_info {
-                sierra::extensions::OutputVarReferenceInfo::SameAsParam { param_idx } => {
+                // Deferred values are stored before branching libfuncs, so a parameter is never
+                // stored by the libfunc itself.
+                sierra::extensions::OutputVarReferenceInfo::SameAsParam { param_idx }
+                | sierra::extensions::OutputVarReferenceInfo::StoredParam { param_idx } => {
                     self.set_variable_status(*var_id, VariableStatus::Alias(params[param_idx]));
                 }
                 sierra::extensions::OutputVarReferenceInfo::NewTempVar { .. }
@@ -1,4 +1,4 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::VecDeque;
 use std::sync::Arc;
 
 use defs::ids::{FreeFunctionId, ModuleId};
@@ -77,7 +77,7 @@ fn generate_type_declarations<'a>(
     types: impl Iterator<Item = &'a ConcreteTypeId>,
 ) -> Vec<program::TypeDeclaration> {
     let mut declarations = vec![];
-    let mut already_declared = HashSet::new();
+    let mut already_declared = UnorderedHashSet::default();
     for ty in types {
         generate_type_declarations_helper(db, ty, &mut declarations, &mut already_declared);
     }
@@ -90,7 +90,7 @@ fn generate_type_declarations_helper(
     db: &dyn SierraGenGroup,
     ty: &ConcreteTypeId,
     declarations: &mut Vec<program::TypeDeclaration>,
-    already_declared: &mut HashSet<ConcreteTypeId>,
+    already_declared: &mut UnorderedHashSet<ConcreteTypeId>,
 ) {
     if already_declared.contains(ty) {
         return;
@@ -186,6 +186,7 @@ pub fn get_sierra_program_for_functions(
                 )
             })
             .collect(),
+        statement_locations: Default::default(),
     }))
 }
 
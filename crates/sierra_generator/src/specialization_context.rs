@@ -26,10 +26,7 @@ impl SignatureSpecializationContext for SierraSignatureSpecializationContext<'_>
         id: sierra::ids::GenericTypeId,
         generic_args: &[sierra::program::GenericArg],
     ) -> Option<sierra::ids::ConcreteTypeId> {
-        Some(self.0.intern_concrete_type(ConcreteTypeLongId {
-            generic_id: id,
-            generic_args: generic_args.to_vec(),
-        }))
+        Some(self.0.intern_concrete_type(ConcreteTypeLongId::new(id, generic_args.to_vec())))
     }
 
     fn try_get_function_signature(
@@ -97,7 +97,9 @@ impl State {
                 }
                 self.temporary_variables.insert(res, output_info.ty.clone());
             }
-            OutputVarReferenceInfo::SameAsParam { .. } | OutputVarReferenceInfo::NewLocalVar => {}
+            OutputVarReferenceInfo::SameAsParam { .. }
+            | OutputVarReferenceInfo::StoredParam { .. }
+            | OutputVarReferenceInfo::NewLocalVar => {}
         }
     }
 
@@ -242,6 +242,8 @@ pub fn get_concrete_libfunc_id(
                 });
             }
 
+            // The substitution was already validated during the lowering of the function.
+            let extern_id = db.substituted_extern_function(extern_id).unwrap();
             (concrete_function, generic_libfunc_id(db, extern_id, generic_args))
         }
         GenericFunctionId::TraitFunction(_) => {
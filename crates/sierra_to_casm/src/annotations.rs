@@ -1,13 +1,14 @@
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::iter;
 
 use casm::ap_change::{ApChange, ApChangeError, ApplyApChange};
 use itertools::zip_eq;
 use sierra::edit_state::{put_results, take_args};
 use sierra::ids::{ConcreteTypeId, VarId};
-use sierra::program::{BranchInfo, Function, StatementIdx};
+use sierra::program::{BranchInfo, Function, Statement, StatementIdx};
 use thiserror::Error;
+use utils::diagnostic::Diagnostic;
 
 use crate::environment::ap_tracking::update_ap_tracking;
 use crate::environment::frame_state::FrameStateError;
@@ -70,6 +71,50 @@ pub enum AnnotationError {
     InvalidApChangeAnnotation { statement_idx: StatementIdx, expected: ApChange, actual: ApChange },
 }
 
+impl From<AnnotationError> for Diagnostic {
+    fn from(error: AnnotationError) -> Self {
+        let message = error.to_string();
+        let (code, statement_idx) = match error {
+            AnnotationError::InconsistentReferencesAnnotation(statement_idx) => {
+                ("E0120", Some(statement_idx))
+            }
+            AnnotationError::InconsistentEnvironments { statement_idx, .. } => {
+                ("E0121", Some(statement_idx))
+            }
+            AnnotationError::InconsistentReturnAnnotation(statement_idx) => {
+                ("E0122", Some(statement_idx))
+            }
+            AnnotationError::InvalidStatementIdx => ("E0123", None),
+            AnnotationError::MissingAnnotationsForStatement(statement_idx) => {
+                ("E0124", Some(statement_idx))
+            }
+            AnnotationError::MissingReferenceError { statement_idx, .. } => {
+                ("E0125", Some(statement_idx))
+            }
+            AnnotationError::OverrideReferenceError { source_statement_idx, .. } => {
+                ("E0126", Some(source_statement_idx))
+            }
+            AnnotationError::FrameStateError(_) => ("E0127", None),
+            AnnotationError::GasWalletError(_) => ("E0128", None),
+            AnnotationError::ReferencesError(error) => return Diagnostic::from(error),
+            AnnotationError::ApChangeError { source_statement_idx, .. } => {
+                ("E0129", Some(source_statement_idx))
+            }
+            AnnotationError::ApTrackingError { source_statement_idx, .. } => {
+                ("E0130", Some(source_statement_idx))
+            }
+            AnnotationError::InvalidApChangeAnnotation { statement_idx, .. } => {
+                ("E0131", Some(statement_idx))
+            }
+        };
+        let diagnostic = Diagnostic::error(code, message);
+        match statement_idx {
+            Some(statement_idx) => diagnostic.with_statement(statement_idx.0),
+            None => diagnostic,
+        }
+    }
+}
+
 /// An annotation that specifies the expected return properties at each statement.
 /// Used to propagate the return properties to return statements.
 /// Implemented as an index into ProgramAnnotations.return_properties.
@@ -105,25 +150,31 @@ pub struct ProgramAnnotations {
 
     /// Optional per statement annotation.
     per_statement_annotations: Vec<Option<StatementAnnotations>>,
+
+    /// The statements that are targets of jumps from the same or later statements. Such a
+    /// statement is compiled before all its annotations are known, so its environment may not rely
+    /// on the frame constants of the statements jumping to it.
+    backward_jump_targets: HashSet<StatementIdx>,
 }
 impl ProgramAnnotations {
-    fn new(n_statements: usize) -> Self {
+    fn new(statements: &[Statement]) -> Self {
         ProgramAnnotations {
             return_properties: vec![],
-            per_statement_annotations: iter::repeat_with(|| None).take(n_statements).collect(),
+            per_statement_annotations: iter::repeat_with(|| None).take(statements.len()).collect(),
+            backward_jump_targets: get_backward_jump_targets(statements),
         }
     }
 
-    /// Creates a ProgramAnnotations object based on 'n_statements', a given functions list
+    /// Creates a ProgramAnnotations object based on the program statements, a given functions list
     /// and metadata for the program.
     pub fn create(
-        n_statements: usize,
+        statements: &[Statement],
         functions: &[Function],
         metadata: &Metadata,
         gas_usage_check: bool,
         type_sizes: &TypeSizeMap,
     ) -> Result<Self, AnnotationError> {
-        let mut annotations = ProgramAnnotations::new(n_statements);
+        let mut annotations = ProgramAnnotations::new(statements);
         let mut return_annotations: HashMap<ReturnProperties, ReturnAnnotation> = HashMap::new();
         for func in functions {
             let ap_change = match metadata.ap_change_info.function_ap_change.get(&func.id) {
@@ -149,7 +200,11 @@ impl ProgramAnnotations {
             annotations.set_or_assert(
                 func.entry_point,
                 StatementAnnotations {
-                    refs: build_function_arguments_refs(func, type_sizes)?,
+                    refs: build_function_arguments_refs(
+                        func,
+                        type_sizes,
+                        &metadata.ap_change_info.stack_layout,
+                    )?,
                     return_annotation,
                     environment: if gas_usage_check {
                         Environment::new(GasWallet::Value(
@@ -167,15 +222,26 @@ impl ProgramAnnotations {
 
     /// Sets the annotations at 'statement_id' to 'annotations'
     /// If the annotations for this statement were set previously, assert that the previous
-    /// assignment is consistent with the new assignment.
+    /// assignment is consistent with the new assignment, and keep only the frame constants known
+    /// in both.
     pub fn set_or_assert(
         &mut self,
         statement_id: StatementIdx,
-        annotations: StatementAnnotations,
+        mut annotations: StatementAnnotations,
     ) -> Result<(), AnnotationError> {
         let idx = statement_id.0;
-        match self.per_statement_annotations.get(idx).ok_or(AnnotationError::InvalidStatementIdx)? {
-            None => self.per_statement_annotations[idx] = Some(annotations),
+        let is_backward_jump_target = self.backward_jump_targets.contains(&statement_id);
+        match self
+            .per_statement_annotations
+            .get_mut(idx)
+            .ok_or(AnnotationError::InvalidStatementIdx)?
+        {
+            None => {
+                if is_backward_jump_target {
+                    annotations.environment.frame_constants.clear();
+                }
+                self.per_statement_annotations[idx] = Some(annotations);
+            }
             Some(expected_annotations) => {
                 if expected_annotations.refs != annotations.refs {
                     return Err(AnnotationError::InconsistentReferencesAnnotation(statement_id));
@@ -192,6 +258,9 @@ impl ProgramAnnotations {
                     statement_idx: statement_id,
                     error,
                 })?;
+                expected_annotations
+                    .environment
+                    .intersect_frame_constants(&annotations.environment);
             }
         };
         Ok(())
@@ -278,6 +347,7 @@ impl ProgramAnnotations {
                             .environment
                             .gas_wallet
                             .update(branch_result.gas_change)?,
+                        frame_constants: annotations.environment.frame_constants.clone(),
                     },
                 },
             )?;
@@ -321,3 +391,20 @@ impl ProgramAnnotations {
             .map_err(|error| AnnotationError::InconsistentEnvironments { statement_idx, error })
     }
 }
+
+/// Returns the statements that are targets of jumps from the same or later statements.
+fn get_backward_jump_targets(statements: &[Statement]) -> HashSet<StatementIdx> {
+    let mut targets = HashSet::new();
+    for (idx, statement) in statements.iter().enumerate() {
+        if let Statement::Invocation(invocation) = statement {
+            let statement_idx = StatementIdx(idx);
+            for branch in &invocation.branches {
+                let target = statement_idx.next(&branch.target);
+                if target.0 <= idx {
+                    targets.insert(target);
+                }
+            }
+        }
+    }
+    targets
+}
@@ -0,0 +1,181 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+
+use itertools::Itertools;
+use sierra::extraction::{reachable_from_function, ExtractionError};
+use sierra::ids::{ConcreteLibFuncId, FunctionId};
+use sierra::program::{Program, Statement};
+use thiserror::Error;
+use utils::diagnostic::Diagnostic;
+
+use crate::compiler::CairoProgram;
+
+#[cfg(test)]
+#[path = "bytecode_size_test.rs"]
+mod test;
+
+/// The number of largest functions listed when the bytecode exceeds its limit.
+const LISTED_FUNCTIONS_COUNT: usize = 10;
+/// The number of largest libfuncs listed per function.
+const LISTED_LIBFUNCS_COUNT: usize = 3;
+
+/// Limits on the size of the compiled bytecode, in felts.
+#[derive(Clone, Debug, Default)]
+pub struct BytecodeSizeConfig {
+    /// The maximal size of the whole bytecode, if limited.
+    pub max_bytecode_felts: Option<usize>,
+    /// The maximal sizes of specific functions. Functions not in the map are not limited.
+    pub max_function_felts: HashMap<FunctionId, usize>,
+}
+
+/// The size of the code of a function, and the libfuncs contributing to it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionSize {
+    pub id: FunctionId,
+    /// The size of the code of the statements of the function, in felts.
+    pub size: usize,
+    /// The total size of the invocations of each libfunc within the function, from the largest to
+    /// the smallest. Libfuncs compiled to no instructions are omitted.
+    pub libfunc_sizes: Vec<(ConcreteLibFuncId, usize)>,
+}
+impl fmt::Display for FunctionSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`: {} felts", self.id, self.size)?;
+        if !self.libfunc_sizes.is_empty() {
+            write!(
+                f,
+                ", mostly {}",
+                self.libfunc_sizes
+                    .iter()
+                    .take(LISTED_LIBFUNCS_COUNT)
+                    .map(|(libfunc_id, size)| format!("{libfunc_id} ({size})"))
+                    .join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum BytecodeSizeError {
+    #[error(
+        "The bytecode size of {size} felts exceeds the limit of {max} felts. Largest functions: \
+         {}.",
+        .largest_functions.iter().join("; ")
+    )]
+    ProgramTooLarge { size: usize, max: usize, largest_functions: Vec<FunctionSize> },
+    #[error("Function {function} exceeds its limit of {max} felts.")]
+    FunctionTooLarge { function: FunctionSize, max: usize },
+}
+
+impl From<BytecodeSizeError> for Diagnostic {
+    fn from(error: BytecodeSizeError) -> Self {
+        let message = error.to_string();
+        match error {
+            BytecodeSizeError::ProgramTooLarge { .. } => Diagnostic::error("E0170", message),
+            BytecodeSizeError::FunctionTooLarge { function, .. } => {
+                Diagnostic::error("E0171", message).with_function(function.id)
+            }
+        }
+    }
+}
+
+/// Returns the sizes of the functions of `program`, as compiled into `cairo_program`, from the
+/// largest to the smallest.
+/// Each statement is attributed to the function with the last entry point preceding it. The
+/// runtime routines are not attributed to any function.
+pub fn function_sizes(program: &Program, cairo_program: &CairoProgram) -> Vec<FunctionSize> {
+    let mut function_sizes: Vec<_> = program
+        .funcs
+        .iter()
+        .map(|func| FunctionSize { id: func.id.clone(), size: 0, libfunc_sizes: vec![] })
+        .collect();
+    for (statement_id, statement) in program.statements.iter().enumerate() {
+        let statement_size = statement_size(cairo_program, statement_id);
+        if let Some((func_idx, _)) = program
+            .funcs
+            .iter()
+            .enumerate()
+            .filter(|(_, func)| func.entry_point.0 <= statement_id)
+            .max_by_key(|(_, func)| func.entry_point.0)
+        {
+            let function_size = &mut function_sizes[func_idx];
+            function_size.size += statement_size;
+            if let Statement::Invocation(invocation) = statement {
+                match function_size
+                    .libfunc_sizes
+                    .iter_mut()
+                    .find(|(libfunc_id, _)| *libfunc_id == invocation.libfunc_id)
+                {
+                    Some((_, size)) => *size += statement_size,
+                    None => function_size
+                        .libfunc_sizes
+                        .push((invocation.libfunc_id.clone(), statement_size)),
+                }
+            }
+        }
+    }
+    // The sorts are stable, so ties are kept in the order of their first appearance.
+    for function_size in &mut function_sizes {
+        function_size.libfunc_sizes.retain(|(_, size)| *size != 0);
+        function_size.libfunc_sizes.sort_by_key(|(_, size)| Reverse(*size));
+    }
+    function_sizes.sort_by_key(|function_size| Reverse(function_size.size));
+    function_sizes
+}
+
+/// Returns the size of the code reachable from the function `function_id` of `program`, as
+/// compiled into `cairo_program`, in felts. This is the size of the code of the program extracted
+/// for the function, excluding the runtime routines.
+pub fn entry_point_size(
+    program: &Program,
+    cairo_program: &CairoProgram,
+    function_id: &FunctionId,
+) -> Result<usize, ExtractionError> {
+    let reachable = reachable_from_function(program, function_id)?;
+    Ok(reachable.statements.iter().map(|idx| statement_size(cairo_program, idx.0)).sum())
+}
+
+/// Returns the size of the code of a statement, as compiled into `cairo_program`, in felts.
+fn statement_size(cairo_program: &CairoProgram, statement_id: usize) -> usize {
+    let debug_info = &cairo_program.debug_info;
+    let start = debug_info.sierra_statement_info[statement_id].code_offset;
+    let end = debug_info
+        .sierra_statement_info
+        .get(statement_id + 1)
+        .map_or(debug_info.routines_code_offset, |info| info.code_offset);
+    end - start
+}
+
+/// Checks that the bytecode of `cairo_program`, compiled from `program`, is within the limits of
+/// `config`.
+pub fn check_bytecode_size(
+    program: &Program,
+    cairo_program: &CairoProgram,
+    config: &BytecodeSizeConfig,
+) -> Result<(), BytecodeSizeError> {
+    let size: usize =
+        cairo_program.instructions.iter().map(|instruction| instruction.body.op_size()).sum();
+    let function_sizes = function_sizes(program, cairo_program);
+    if let Some(max) = config.max_bytecode_felts {
+        if size > max {
+            return Err(BytecodeSizeError::ProgramTooLarge {
+                size,
+                max,
+                largest_functions: function_sizes
+                    .into_iter()
+                    .take(LISTED_FUNCTIONS_COUNT)
+                    .collect(),
+            });
+        }
+    }
+    for function in function_sizes {
+        if let Some(&max) = config.max_function_felts.get(&function.id) {
+            if function.size > max {
+                return Err(BytecodeSizeError::FunctionTooLarge { function, max });
+            }
+        }
+    }
+    Ok(())
+}
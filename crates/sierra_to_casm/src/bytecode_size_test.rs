@@ -0,0 +1,112 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use sierra::program::Program;
+use sierra::ProgramParser;
+use utils::diagnostic::Diagnostic;
+
+use super::{
+    check_bytecode_size, entry_point_size, BytecodeSizeConfig, BytecodeSizeError, FunctionSize,
+};
+use crate::compiler::{compile, CairoProgram};
+use crate::test_utils::build_metadata;
+
+/// Returns a program with two functions - `big`, of 8 felts, and `small`, of 3 felts - and its
+/// compilation.
+fn compiled_program() -> (Program, CairoProgram) {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+
+            libfunc felt_const_1 = felt_const<1>;
+            libfunc felt_add = felt_add;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc jump = jump;
+
+            felt_const_1() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            felt_const_1() -> ([2]);
+            store_temp_felt([2]) -> ([2]);
+            felt_add([1], [2]) -> ([3]);
+            store_temp_felt([3]) -> ([3]);
+            jump() { 7() };
+            return([3]);
+            felt_const_1() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            return([1]);
+
+            small@8() -> (felt);
+            big@0() -> (felt);
+        "})
+        .unwrap();
+    let cairo_program = compile(&program, &build_metadata(&program, false), false, false)
+        .expect("Compilation failed.");
+    (program, cairo_program)
+}
+
+fn big_function_size() -> FunctionSize {
+    FunctionSize {
+        id: "big".into(),
+        size: 8,
+        libfunc_sizes: vec![("store_temp_felt".into(), 5), ("jump".into(), 2)],
+    }
+}
+
+fn small_function_size() -> FunctionSize {
+    FunctionSize { id: "small".into(), size: 3, libfunc_sizes: vec![("store_temp_felt".into(), 2)] }
+}
+
+#[test]
+fn program_too_large() {
+    let (program, cairo_program) = compiled_program();
+    let config = BytecodeSizeConfig { max_bytecode_felts: Some(10), ..Default::default() };
+    let error = check_bytecode_size(&program, &cairo_program, &config).unwrap_err();
+    assert_eq!(
+        error,
+        BytecodeSizeError::ProgramTooLarge {
+            size: 11,
+            max: 10,
+            largest_functions: vec![big_function_size(), small_function_size()],
+        }
+    );
+    let diagnostic = Diagnostic::from(error);
+    assert_eq!(diagnostic.code, "E0170");
+    assert_eq!(
+        diagnostic.message,
+        "The bytecode size of 11 felts exceeds the limit of 10 felts. Largest functions: `big`: 8 \
+         felts, mostly store_temp_felt (5), jump (2); `small`: 3 felts, mostly store_temp_felt \
+         (2)."
+    );
+
+    let config = BytecodeSizeConfig { max_bytecode_felts: Some(11), ..Default::default() };
+    assert_eq!(check_bytecode_size(&program, &cairo_program, &config), Ok(()));
+}
+
+#[test]
+fn function_too_large() {
+    let (program, cairo_program) = compiled_program();
+    let config = BytecodeSizeConfig {
+        max_bytecode_felts: None,
+        max_function_felts: [("small".into(), 2)].into_iter().collect(),
+    };
+    let error = check_bytecode_size(&program, &cairo_program, &config).unwrap_err();
+    assert_eq!(
+        error,
+        BytecodeSizeError::FunctionTooLarge { function: small_function_size(), max: 2 }
+    );
+    let diagnostic = Diagnostic::from(error);
+    assert_eq!(diagnostic.code, "E0171");
+    assert_eq!(diagnostic.function, Some("small".into()));
+
+    let config = BytecodeSizeConfig {
+        max_bytecode_felts: None,
+        max_function_felts: [("small".into(), 3)].into_iter().collect(),
+    };
+    assert_eq!(check_bytecode_size(&program, &cairo_program, &config), Ok(()));
+}
+
+#[test]
+fn entry_point_sizes() {
+    let (program, cairo_program) = compiled_program();
+    assert_eq!(entry_point_size(&program, &cairo_program, &"big".into()), Ok(8));
+    assert_eq!(entry_point_size(&program, &cairo_program, &"small".into()), Ok(3));
+}
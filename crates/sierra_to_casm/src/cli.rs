@@ -2,9 +2,8 @@ use std::fs;
 
 use clap::Parser;
 use sierra::ProgramParser;
-use sierra_ap_change::calc_ap_changes;
-use sierra_gas::calc_gas_info;
-use sierra_to_casm::metadata::Metadata;
+use sierra_to_casm::bytecode_size::{check_bytecode_size, BytecodeSizeConfig};
+use utils::diagnostic::Diagnostic;
 use utils::logging::init_logging;
 
 /// Command line args parser.
@@ -15,6 +14,18 @@ struct Args {
     /// The file to compile
     file: String,
     output: String,
+    /// Whether to remove the debug print hints from the output, as required for proving.
+    #[arg(long, default_value_t = false)]
+    strip_debug_hints: bool,
+    /// Whether to print the comments describing the instructions in the output.
+    #[arg(long, default_value_t = false)]
+    emit_comments: bool,
+    /// The maximal size of the compiled bytecode, in felts.
+    #[arg(long)]
+    max_bytecode_felts: Option<usize>,
+    /// The file to write the debug information section of the compiled program into, as JSON.
+    #[arg(long)]
+    debug_info: Option<String>,
 }
 
 fn main() {
@@ -26,18 +37,33 @@ fn main() {
     let sierra_code = fs::read_to_string(args.file).expect("Could not read file!");
     let program = ProgramParser::new().parse(&sierra_code).unwrap();
 
-    let gas_info = calc_gas_info(&program).expect("Failed calculating gas variables.");
-
-    let gas_usage_check = true;
-    let cairo_program = sierra_to_casm::compiler::compile(
+    let mut cairo_program = match sierra_to_casm::compiler::compile_with_diagnostics(
         &program,
-        &Metadata {
-            ap_change_info: calc_ap_changes(&program).expect("Failed calculating ap changes."),
-            gas_info,
-        },
-        gas_usage_check,
-    )
-    .expect("Compilation failed.");
+        true,
+        args.emit_comments,
+    ) {
+        Ok(cairo_program) => cairo_program,
+        Err(diagnostics) => {
+            for diagnostic in diagnostics {
+                eprintln!("{diagnostic}");
+            }
+            std::process::exit(1);
+        }
+    };
+    let size_config =
+        BytecodeSizeConfig { max_bytecode_felts: args.max_bytecode_felts, ..Default::default() };
+    if let Err(error) = check_bytecode_size(&program, &cairo_program, &size_config) {
+        eprintln!("{}", Diagnostic::from(error));
+        std::process::exit(1);
+    }
+    if args.strip_debug_hints {
+        cairo_program.strip_debug_hints();
+    }
 
+    if let Some(debug_info_path) = args.debug_info {
+        let section = serde_json::to_string_pretty(&cairo_program.debug_info.section())
+            .expect("Failed to serialize the debug info.");
+        fs::write(debug_info_path, section).expect("Failed to write the debug info.");
+    }
     fs::write(args.output, format!("{}", cairo_program)).expect("Failed to write output.");
 }
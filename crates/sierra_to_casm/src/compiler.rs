@@ -1,19 +1,38 @@
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::ops::Range;
 
+use casm::ap_change::ApChange;
+use casm::cost::CostWeights;
+use casm::hint_soundness::{check_hint_soundness, HintSoundnessError};
+use casm::hints::Hint;
 use casm::instructions::{Instruction, InstructionBody, RetInstruction};
+use casm::operand::{CellRef, DerefOrImmediate, Register, ResOperand};
+use itertools::{chain, zip_eq, Itertools};
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
 use sierra::extensions::core::{CoreConcreteLibFunc, CoreLibFunc, CoreType};
+use sierra::extensions::mem::MemConcreteLibFunc;
 use sierra::extensions::ConcreteLibFunc;
-use sierra::program::{BranchTarget, Invocation, Program, Statement, StatementIdx};
+use sierra::ids::{ConcreteTypeId, FunctionId, VarId};
+use sierra::program::{
+    BranchTarget, ConcreteLibFuncLongId, Invocation, Program, Statement, StatementIdx,
+    StatementLocation,
+};
 use sierra::program_registry::{ProgramRegistry, ProgramRegistryError};
+use sierra_ap_change::calc_ap_changes;
+use sierra_gas::calc_gas_info;
+use sierra_gas::gas_info::GasInfo;
 use thiserror::Error;
+use utils::diagnostic::Diagnostic;
 
 use crate::annotations::{AnnotationError, ProgramAnnotations, StatementAnnotations};
-use crate::invocations::{
-    check_references_on_stack, compile_invocation, InvocationError, ProgramInfo,
-};
+use crate::extensions::{compile_libfunc_invocation, CompilableLibFunc};
+use crate::invocations::{check_references_on_stack, InvocationError, ProgramInfo};
 use crate::metadata::Metadata;
-use crate::references::{check_types_match, ReferencesError};
+use crate::references::{check_types_match, CellExpression, ReferencesError};
 use crate::relocations::{relocate_instructions, RelocationEntry};
+use crate::runtime_routines::append_requested_routines;
 use crate::type_sizes::get_type_size_map;
 
 #[cfg(test)]
@@ -28,14 +47,85 @@ pub enum CompilationError {
     ProgramRegistryError(Box<ProgramRegistryError>),
     #[error(transparent)]
     AnnotationError(#[from] AnnotationError),
-    #[error("#{statement_idx}: {error}")]
-    InvocationError { statement_idx: StatementIdx, error: InvocationError },
+    #[error("#{statement_idx}: {libfunc}: {error}")]
+    InvocationError {
+        statement_idx: StatementIdx,
+        libfunc: ConcreteLibFuncLongId,
+        error: InvocationError,
+    },
     #[error("#{statement_idx}: Return arguments are not on the stack.")]
     ReturnArgumentsNotOnStack { statement_idx: StatementIdx },
     #[error(transparent)]
     ReferencesError(#[from] ReferencesError),
     #[error("#{statement_idx}: Invocation mismatched to libfunc")]
     LibFuncInvocationMismatch { statement_idx: StatementIdx },
+    #[error("#{statement_idx}: Expected {expected} branches, got {actual}.")]
+    WrongNumberOfBranches { statement_idx: StatementIdx, expected: usize, actual: usize },
+    #[error("#{statement_idx}: Branch #{branch_idx} expected {expected} results, got {actual}.")]
+    WrongNumberOfBranchResults {
+        statement_idx: StatementIdx,
+        branch_idx: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("#{statement_idx}: Branch #{branch_idx} is expected to be a fallthrough.")]
+    ExpectedFallthroughBranch { statement_idx: StatementIdx, branch_idx: usize },
+    #[error("#{statement_idx}: Branch #{branch_idx} is not allowed to be a fallthrough.")]
+    UnexpectedFallthroughBranch { statement_idx: StatementIdx, branch_idx: usize },
+    #[error(
+        "#{statement_idx}: Branches #{first_branch_idx} and #{second_branch_idx} both target \
+         #{target}."
+    )]
+    DuplicateBranchTarget {
+        statement_idx: StatementIdx,
+        first_branch_idx: usize,
+        second_branch_idx: usize,
+        target: StatementIdx,
+    },
+    #[error(transparent)]
+    HintSoundnessError(#[from] HintSoundnessError),
+}
+
+impl From<CompilationError> for Diagnostic {
+    fn from(error: CompilationError) -> Self {
+        let message = error.to_string();
+        let (code, statement_idx) = match error {
+            CompilationError::FailedBuildingTypeInformation => ("E0100", None),
+            CompilationError::ProgramRegistryError(error) => return Diagnostic::from(*error),
+            CompilationError::AnnotationError(error) => return Diagnostic::from(error),
+            CompilationError::InvocationError { statement_idx, error, .. } => {
+                (Diagnostic::from(error).code, Some(statement_idx))
+            }
+            CompilationError::ReturnArgumentsNotOnStack { statement_idx } => {
+                ("E0101", Some(statement_idx))
+            }
+            CompilationError::ReferencesError(error) => return Diagnostic::from(error),
+            CompilationError::LibFuncInvocationMismatch { statement_idx } => {
+                ("E0102", Some(statement_idx))
+            }
+            CompilationError::WrongNumberOfBranches { statement_idx, .. } => {
+                ("E0103", Some(statement_idx))
+            }
+            CompilationError::WrongNumberOfBranchResults { statement_idx, .. } => {
+                ("E0104", Some(statement_idx))
+            }
+            CompilationError::ExpectedFallthroughBranch { statement_idx, .. } => {
+                ("E0105", Some(statement_idx))
+            }
+            CompilationError::UnexpectedFallthroughBranch { statement_idx, .. } => {
+                ("E0106", Some(statement_idx))
+            }
+            CompilationError::DuplicateBranchTarget { statement_idx, .. } => {
+                ("E0107", Some(statement_idx))
+            }
+            CompilationError::HintSoundnessError(_) => ("E0108", None),
+        };
+        let diagnostic = Diagnostic::error(code, message);
+        match statement_idx {
+            Some(statement_idx) => diagnostic.with_statement(statement_idx.0),
+            None => diagnostic,
+        }
+    }
 }
 
 /// The casm program representation.
@@ -44,20 +134,158 @@ pub struct CairoProgram {
     pub instructions: Vec<Instruction>,
     pub debug_info: CairoProgramDebugInfo,
 }
+impl CairoProgram {
+    /// Removes the debug print hints from the program.
+    /// Should be used for programs that are meant to be proven, as these hints are not supported by
+    /// the prover.
+    pub fn strip_debug_hints(&mut self) {
+        for instruction in &mut self.instructions {
+            instruction.hints.retain(|hint| !matches!(hint, Hint::DebugPrint { .. }));
+        }
+    }
+
+    /// Assembles the program into its bytecode, collecting the hints with their offsets.
+    pub fn assemble(&self) -> AssembledCairoProgram {
+        let mut bytecode = vec![];
+        let mut hints = vec![];
+        for instruction in &self.instructions {
+            if !instruction.hints.is_empty() {
+                hints.push((bytecode.len(), instruction.hints.clone()));
+            }
+            bytecode.extend(instruction.assemble().encode());
+        }
+        AssembledCairoProgram { bytecode, hints }
+    }
+
+    /// Returns a listing of the program, with the offset of every instruction in the bytecode and
+    /// its hints printed right before it. If `with_statements` is set, the code of every Sierra
+    /// statement is preceded by the index of the statement.
+    pub fn disassemble(&self, with_statements: bool) -> CairoProgramDisassembly<'_> {
+        CairoProgramDisassembly { program: self, with_statements }
+    }
+}
+
+/// The assembled form of a casm program.
+#[derive(Debug, Eq, PartialEq)]
+pub struct AssembledCairoProgram {
+    /// The encodings of the instructions of the program. The values are not reduced modulo the
+    /// field prime, and may be negative for negative immediates.
+    pub bytecode: Vec<BigInt>,
+    /// The hints of the program, with the offset in the bytecode of the instruction they precede.
+    pub hints: Vec<(usize, Vec<Hint>)>,
+}
 impl Display for CairoProgram {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for instruction in &self.instructions {
+        let mut comments = self.debug_info.instruction_comments.iter().peekable();
+        for (instruction_idx, instruction) in self.instructions.iter().enumerate() {
+            while let Some((_, comment)) = comments.next_if(|(idx, _)| *idx == instruction_idx) {
+                writeln!(f, "// {comment}")?;
+            }
             writeln!(f, "{};", instruction)?
         }
         Ok(())
     }
 }
 
+/// A displayable listing of a casm program, see [CairoProgram::disassemble].
+pub struct CairoProgramDisassembly<'a> {
+    program: &'a CairoProgram,
+    with_statements: bool,
+}
+impl Display for CairoProgramDisassembly<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let debug_info = &self.program.debug_info;
+        let mut comments = debug_info.instruction_comments.iter().peekable();
+        // Statements compiled to no instructions are skipped, as they have no code to precede.
+        let mut statements = debug_info
+            .statement_code_ranges
+            .iter()
+            .filter(|range| !range.instructions.is_empty())
+            .peekable();
+        let routines_start =
+            debug_info.statement_code_ranges.last().map(|range| range.instructions.end);
+        let mut pc = 0;
+        for (instruction_idx, instruction) in self.program.instructions.iter().enumerate() {
+            if self.with_statements {
+                if let Some(range) =
+                    statements.next_if(|range| range.instructions.start == instruction_idx)
+                {
+                    writeln!(f, "// Statement #{}:", range.statement_idx)?;
+                } else if routines_start == Some(instruction_idx) {
+                    writeln!(f, "// Runtime routines:")?;
+                }
+            }
+            while let Some((_, comment)) = comments.next_if(|(idx, _)| *idx == instruction_idx) {
+                writeln!(f, "{:6}// {comment}", "")?;
+            }
+            for hint in &instruction.hints {
+                writeln!(f, "{:6}{hint}", "")?;
+            }
+            write!(f, "{pc:>4}: {}", instruction.body)?;
+            if instruction.inc_ap {
+                write!(f, ", ap++")?;
+            }
+            writeln!(f, ";")?;
+            pc += instruction.body.op_size();
+        }
+        Ok(())
+    }
+}
+
 /// The debug information of a compilation from Sierra to casm.
 #[derive(Debug, Eq, PartialEq)]
 pub struct SierraStatementDebugInfo {
     /// The offset of the sierra statement within the bytecode.
     pub code_offset: usize,
+    /// The change to ap from the beginning of the function to the beginning of the statement.
+    pub ap_tracking: ApChange,
+    /// The source location of the statement, if the program provided one.
+    pub location: Option<StatementLocation>,
+}
+
+/// The code compiled from a single Sierra statement.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StatementCodeRange {
+    /// The index of the Sierra statement.
+    pub statement_idx: usize,
+    /// The indices of the instructions of the statement within the instructions of the program.
+    pub instructions: Range<usize>,
+    /// The offsets of the code of the statement within the bytecode.
+    pub code: Range<usize>,
+}
+
+/// The debug information of a single instruction.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct InstructionDebugInfo {
+    /// The offset of the instruction within the bytecode.
+    pub code_offset: usize,
+    /// The Sierra statement the instruction was compiled from, or `None` for the instructions of
+    /// the runtime routines.
+    pub statement_idx: Option<usize>,
+    /// The change to ap from the beginning of the frame - the function, or the runtime routine -
+    /// to the instruction.
+    pub ap_tracking: ApChange,
+}
+
+/// The debug information of a local variable of a function.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LocalDebugInfo {
+    /// The Sierra variable holding the local, as returned from `alloc_local`.
+    pub var_id: VarId,
+    /// The type of the value stored in the local.
+    pub ty: ConcreteTypeId,
+    /// The offset of the local relative to fp.
+    pub fp_offset: i16,
+}
+
+/// The frame description of a Sierra function.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FunctionDebugInfo {
+    pub id: FunctionId,
+    /// The offset of the entry point of the function within the bytecode.
+    pub code_offset: usize,
+    /// The locals of the function, in allocation order.
+    pub locals: Vec<LocalDebugInfo>,
 }
 
 /// The debug information of a compilation from Sierra to casm.
@@ -65,52 +293,194 @@ pub struct SierraStatementDebugInfo {
 pub struct CairoProgramDebugInfo {
     /// The debug information per Sierra statement.
     pub sierra_statement_info: Vec<SierraStatementDebugInfo>,
+    /// The debug information per instruction, in the order of the instructions.
+    pub instruction_info: Vec<InstructionDebugInfo>,
+    /// The debug information per Sierra function, in the order of the program functions.
+    pub function_info: Vec<FunctionDebugInfo>,
+    /// The code of each Sierra statement, in the order of the statements. The ranges are
+    /// contiguous, and end where the runtime routines start.
+    pub statement_code_ranges: Vec<StatementCodeRange>,
+    /// The offset of the runtime routines within the bytecode - the end of the code of the
+    /// statements.
+    pub routines_code_offset: usize,
+    /// The comments describing the instructions, with the index of the instruction each
+    /// describes, sorted by the index. Empty unless comments were requested on compilation.
+    pub instruction_comments: Vec<(usize, String)>,
+}
+impl CairoProgramDebugInfo {
+    /// Returns the debug information of the instruction at `code_offset`, if an instruction starts
+    /// there.
+    pub fn instruction_at(&self, code_offset: usize) -> Option<&InstructionDebugInfo> {
+        let idx = self
+            .instruction_info
+            .binary_search_by_key(&code_offset, |info| info.code_offset)
+            .ok()?;
+        Some(&self.instruction_info[idx])
+    }
+
+    /// Returns the section of the debug information serialized into artifacts.
+    pub fn section(&self) -> DebugInfoSection {
+        DebugInfoSection {
+            version: DEBUG_INFO_SECTION_VERSION,
+            instructions: self.instruction_info.clone(),
+            statements: self.statement_code_ranges.clone(),
+            functions: self.function_info.clone(),
+        }
+    }
+
+    /// Returns the debug information of the function containing the instruction at `code_offset`.
+    /// Assumes the statements of each function directly follow its entry point.
+    pub fn function_at(&self, code_offset: usize) -> Option<&FunctionDebugInfo> {
+        self.function_info
+            .iter()
+            .filter(|info| info.code_offset <= code_offset)
+            .max_by_key(|info| info.code_offset)
+    }
+
+    /// Returns the source location of the statement whose code contains the instruction at
+    /// `code_offset`, if the statement has one.
+    /// Instructions of the runtime routines are attributed to the last statement.
+    pub fn location_at(&self, code_offset: usize) -> Option<StatementLocation> {
+        // Statements compiled to no instructions share their offset with the following statement,
+        // so the last statement starting at or before the offset is the one containing it.
+        self.sierra_statement_info
+            .iter()
+            .filter(|info| info.code_offset <= code_offset)
+            .max_by_key(|info| info.code_offset)?
+            .location
+    }
+}
+
+/// The version of the format of [DebugInfoSection]. Bumped on every change of the format.
+pub const DEBUG_INFO_SECTION_VERSION: usize = 1;
+
+/// The debug information of a casm program required by external debuggers, as serialized into
+/// artifacts.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DebugInfoSection {
+    /// The version of the format of the section.
+    pub version: usize,
+    /// The debug information per instruction, sorted by the code offset.
+    pub instructions: Vec<InstructionDebugInfo>,
+    /// The code of each Sierra statement, in the order of the statements.
+    pub statements: Vec<StatementCodeRange>,
+    /// The frame descriptions of the Sierra functions.
+    pub functions: Vec<FunctionDebugInfo>,
 }
 
 /// Ensure the basic structure of the invocation is the same as the library function.
 pub fn check_basic_structure(
     statement_idx: StatementIdx,
     invocation: &Invocation,
-    libfunc: &CoreConcreteLibFunc,
+    libfunc: &dyn ConcreteLibFunc,
 ) -> Result<(), CompilationError> {
-    if invocation.args.len() != libfunc.param_signatures().len()
-        || !itertools::equal(
-            invocation.branches.iter().map(|branch| branch.results.len()),
-            libfunc.output_types().iter().map(|types| types.len()),
-        )
-        || match libfunc.fallthrough() {
-            Some(expected_fallthrough) => {
-                invocation.branches[expected_fallthrough].target != BranchTarget::Fallthrough
-            }
-            None => false,
-        }
+    if invocation.args.len() != libfunc.param_signatures().len() {
+        return Err(CompilationError::LibFuncInvocationMismatch { statement_idx });
+    }
+    let branch_signatures = libfunc.branch_signatures();
+    if invocation.branches.len() != branch_signatures.len() {
+        return Err(CompilationError::WrongNumberOfBranches {
+            statement_idx,
+            expected: branch_signatures.len(),
+            actual: invocation.branches.len(),
+        });
+    }
+    // Maps each branch destination to the first branch targeting it.
+    let mut destinations = HashMap::<StatementIdx, usize>::new();
+    for (branch_idx, (branch, branch_signature)) in
+        zip_eq(&invocation.branches, branch_signatures).enumerate()
     {
-        Err(CompilationError::LibFuncInvocationMismatch { statement_idx })
-    } else {
-        Ok(())
+        if branch.results.len() != branch_signature.vars.len() {
+            return Err(CompilationError::WrongNumberOfBranchResults {
+                statement_idx,
+                branch_idx,
+                expected: branch_signature.vars.len(),
+                actual: branch.results.len(),
+            });
+        }
+        let is_fallthrough = branch.target == BranchTarget::Fallthrough;
+        if libfunc.fallthrough() == Some(branch_idx) {
+            if !is_fallthrough {
+                return Err(CompilationError::ExpectedFallthroughBranch {
+                    statement_idx,
+                    branch_idx,
+                });
+            }
+        } else if is_fallthrough {
+            return Err(CompilationError::UnexpectedFallthroughBranch {
+                statement_idx,
+                branch_idx,
+            });
+        }
+        let target = statement_idx.next(&branch.target);
+        if let Some(first_branch_idx) = destinations.insert(target, branch_idx) {
+            return Err(CompilationError::DuplicateBranchTarget {
+                statement_idx,
+                first_branch_idx,
+                second_branch_idx: branch_idx,
+                target,
+            });
+        }
     }
+    Ok(())
 }
 
+/// Compiles a Sierra program to casm.
+/// The runtime routines requested by the invocations are emitted once each, after the code of the
+/// statements.
+/// The result only depends on the inputs - compiling the same program with the same metadata
+/// always yields the same program, regardless of hash map iteration order.
+/// If `emit_comments` is set, the comments of the invocations are kept in the debug information,
+/// and printed when displaying the program.
 pub fn compile(
     program: &Program,
     metadata: &Metadata,
     gas_usage_check: bool,
+    emit_comments: bool,
+) -> Result<CairoProgram, CompilationError> {
+    compile_with_libfuncs::<CoreLibFunc>(program, metadata, gas_usage_check, emit_comments)
+}
+
+/// Compiles a Sierra program using the libfuncs of `TLibFunc` - a hierarchy extending the core
+/// libfuncs - to casm, as [compile] does for programs using only the core libfuncs.
+pub fn compile_with_libfuncs<TLibFunc: CompilableLibFunc>(
+    program: &Program,
+    metadata: &Metadata,
+    gas_usage_check: bool,
+    emit_comments: bool,
 ) -> Result<CairoProgram, CompilationError> {
     let mut instructions = Vec::new();
     let mut relocations: Vec<RelocationEntry> = Vec::new();
+    let mut instruction_comments = vec![];
 
     // Maps statement_idx to program_offset.
     let mut statement_offsets = Vec::with_capacity(program.statements.len());
+    // Maps statement_idx to the index of its first instruction.
+    let mut statement_instruction_idxs = Vec::with_capacity(program.statements.len());
+    // Maps statement_idx to the ap tracking at the beginning of the statement.
+    let mut statement_ap_tracking = Vec::with_capacity(program.statements.len());
+    // The locals allocated in the program, with the statement allocating them.
+    let mut locals: Vec<(StatementIdx, LocalDebugInfo)> = vec![];
 
-    let registry = ProgramRegistry::<CoreType, CoreLibFunc>::with_ap_change(
+    let registry = ProgramRegistry::<CoreType, TLibFunc>::with_ap_change(
         program,
         metadata.ap_change_info.function_ap_change.clone(),
     )
     .map_err(CompilationError::ProgramRegistryError)?;
     let type_sizes = get_type_size_map(program, &registry)
         .ok_or(CompilationError::FailedBuildingTypeInformation)?;
+    let type_long_ids = program
+        .type_declarations
+        .iter()
+        .map(|declaration| (declaration.id.clone(), declaration.long_id.clone()))
+        .collect();
+    let libfunc_long_ids = program
+        .libfunc_declarations
+        .iter()
+        .map(|declaration| (declaration.id.clone(), declaration.long_id.clone()))
+        .collect();
     let mut program_annotations = ProgramAnnotations::create(
-        program.statements.len(),
+        &program.statements,
         &program.funcs,
         metadata,
         gas_usage_check,
@@ -122,12 +492,15 @@ pub fn compile(
     for (statement_id, statement) in program.statements.iter().enumerate() {
         let statement_idx = StatementIdx(statement_id);
         statement_offsets.push(program_offset);
+        statement_instruction_idxs.push(instructions.len());
         match statement {
             Statement::Return(ref_ids) => {
                 let (annotations, return_refs) = program_annotations
                     .get_annotations_after_take_args(statement_idx, ref_ids.iter())?;
+                statement_ap_tracking.push(annotations.environment.ap_tracking);
 
-                if let Some(var_id) = annotations.refs.keys().next() {
+                // Reporting the smallest id, so that the reported error is deterministic.
+                if let Some(var_id) = annotations.refs.keys().min_by_key(|var_id| var_id.id) {
                     return Err(ReferencesError::DanglingReferences {
                         statement_idx,
                         var_id: var_id.clone(),
@@ -140,12 +513,9 @@ pub fn compile(
                     &annotations,
                     &return_refs,
                 )?;
-                check_references_on_stack(&return_refs).map_err(|error| match error {
-                    InvocationError::InvalidReferenceExpressionForArgument => {
-                        CompilationError::ReturnArgumentsNotOnStack { statement_idx }
-                    }
-                    _ => CompilationError::InvocationError { statement_idx, error },
-                })?;
+                // The only possible error is an argument not being on the stack.
+                check_references_on_stack(&return_refs, &metadata.ap_change_info.stack_layout)
+                    .map_err(|_| CompilationError::ReturnArgumentsNotOnStack { statement_idx })?;
 
                 let ret_instruction = RetInstruction {};
                 program_offset += ret_instruction.op_size();
@@ -154,6 +524,7 @@ pub fn compile(
             Statement::Invocation(invocation) => {
                 let (annotations, invoke_refs) = program_annotations
                     .get_annotations_after_take_args(statement_idx, invocation.args.iter())?;
+                statement_ap_tracking.push(annotations.environment.ap_tracking);
 
                 let libfunc = registry
                     .get_libfunc(&invocation.libfunc_id)
@@ -166,26 +537,61 @@ pub fn compile(
                     .map(|param_signature| param_signature.ty.clone())
                     .collect();
                 check_types_match(&invoke_refs, &param_types)?;
-                let compiled_invocation = compile_invocation(
-                    ProgramInfo { metadata, type_sizes: &type_sizes },
+                let compiled_invocation = compile_libfunc_invocation::<TLibFunc>(
+                    ProgramInfo {
+                        metadata,
+                        type_sizes: &type_sizes,
+                        type_long_ids: &type_long_ids,
+                        libfunc_long_ids: &libfunc_long_ids,
+                    },
                     invocation,
                     libfunc,
                     statement_idx,
                     &invoke_refs,
                     annotations.environment,
                 )
-                .map_err(|error| CompilationError::InvocationError { statement_idx, error })?;
+                .map_err(|error| CompilationError::InvocationError {
+                    statement_idx,
+                    libfunc: libfunc_long_ids[&invocation.libfunc_id].clone(),
+                    error,
+                })?;
 
                 for instruction in &compiled_invocation.instructions {
                     program_offset += instruction.body.op_size();
                 }
 
+                if let Some(CoreConcreteLibFunc::Mem(MemConcreteLibFunc::AllocLocal(alloc_local))) =
+                    TLibFunc::as_core(libfunc)
+                {
+                    let local = &compiled_invocation.results[0].refs[0];
+                    if let [CellExpression::Deref(CellRef { register: Register::FP, offset })] =
+                        local.expression.cells[..]
+                    {
+                        locals.push((
+                            statement_idx,
+                            LocalDebugInfo {
+                                var_id: invocation.branches[0].results[0].clone(),
+                                ty: alloc_local.ty.clone(),
+                                fp_offset: offset,
+                            },
+                        ));
+                    }
+                }
+
                 for entry in compiled_invocation.relocations {
                     relocations.push(RelocationEntry {
                         instruction_idx: instructions.len() + entry.instruction_idx,
                         relocation: entry.relocation,
                     });
                 }
+                if emit_comments {
+                    instruction_comments.extend(
+                        compiled_invocation
+                            .comments
+                            .into_iter()
+                            .map(|(idx, comment)| (instructions.len() + idx, comment)),
+                    );
+                }
                 instructions.extend(compiled_invocation.instructions);
 
                 program_annotations.propagate_annotations(
@@ -201,15 +607,202 @@ pub fn compile(
         }
     }
 
-    relocate_instructions(&relocations, &statement_offsets, &mut instructions);
+    let routines_code_offset = program_offset;
+    let statement_code_ranges = zip_eq(
+        statement_instruction_idxs.iter().copied().chain([instructions.len()]).tuple_windows(),
+        statement_offsets.iter().copied().chain([routines_code_offset]).tuple_windows(),
+    )
+    .enumerate()
+    .map(|(statement_idx, ((instructions_start, instructions_end), (code_start, code_end)))| {
+        StatementCodeRange {
+            statement_idx,
+            instructions: instructions_start..instructions_end,
+            code: code_start..code_end,
+        }
+    })
+    .collect::<Vec<_>>();
+    let routine_offsets =
+        append_requested_routines(&relocations, &mut instructions, program_offset);
+    relocate_instructions(&relocations, &statement_offsets, &routine_offsets, &mut instructions);
+    // The hints of the core libfuncs are all constrained, so a failure here is a libfunc bug.
+    check_hint_soundness(&instructions)?;
+
+    let code_offsets: Vec<usize> = instructions
+        .iter()
+        .scan(0, |offset, instruction| {
+            let current = *offset;
+            *offset += instruction.body.op_size();
+            Some(current)
+        })
+        .collect();
+    // The code of every statement starts with the ap tracking of the statement, and the code of
+    // every runtime routine starts its own frame.
+    let routines_start = statement_code_ranges.last().map_or(0, |range| range.instructions.end);
+    let routine_starts = (routines_start..instructions.len())
+        .filter(|idx| routine_offsets.values().any(|offset| *offset == code_offsets[*idx]));
+    let routine_code_ranges = routine_starts
+        .chain([instructions.len()])
+        .tuple_windows()
+        .map(|(start, end)| (None, start..end, ApChange::Known(0)));
+    let mut instruction_info = vec![];
+    for (statement_idx, range, ap_tracking) in statement_code_ranges
+        .iter()
+        .map(|range| {
+            let statement_idx = range.statement_idx;
+            (Some(statement_idx), range.instructions.clone(), statement_ap_tracking[statement_idx])
+        })
+        .chain(routine_code_ranges)
+    {
+        let range_ap_tracking = code_ap_tracking(&instructions[range.clone()], ap_tracking);
+        instruction_info.extend(zip_eq(&code_offsets[range], range_ap_tracking).map(
+            |(code_offset, ap_tracking)| InstructionDebugInfo {
+                code_offset: *code_offset,
+                statement_idx,
+                ap_tracking,
+            },
+        ));
+    }
+
+    let mut function_info: Vec<_> = program
+        .funcs
+        .iter()
+        .map(|func| FunctionDebugInfo {
+            id: func.id.clone(),
+            code_offset: statement_offsets[func.entry_point.0],
+            locals: vec![],
+        })
+        .collect();
+    for (statement_idx, local) in locals {
+        // A local belongs to the function with the last entry point preceding its allocation.
+        if let Some((func_idx, _)) = program
+            .funcs
+            .iter()
+            .enumerate()
+            .filter(|(_, func)| func.entry_point.0 <= statement_idx.0)
+            .max_by_key(|(_, func)| func.entry_point.0)
+        {
+            function_info[func_idx].locals.push(local);
+        }
+    }
 
     Ok(CairoProgram {
         instructions,
         debug_info: CairoProgramDebugInfo {
-            sierra_statement_info: statement_offsets
-                .into_iter()
-                .map(|code_offset| SierraStatementDebugInfo { code_offset })
+            sierra_statement_info: zip_eq(statement_offsets, statement_ap_tracking)
+                .enumerate()
+                .map(|(statement_id, (code_offset, ap_tracking))| SierraStatementDebugInfo {
+                    code_offset,
+                    ap_tracking,
+                    location: program.get_statement_location(&StatementIdx(statement_id)).copied(),
+                })
                 .collect(),
+            instruction_info,
+            function_info,
+            statement_code_ranges,
+            routines_code_offset,
+            instruction_comments,
         },
     })
 }
+
+/// Returns the ap tracking before each of the instructions of a code sequence, given the ap
+/// tracking at its start.
+/// The ap tracking is propagated along the flow of the code: to the following instruction, to the
+/// targets of relative jumps by immediates within the code, and to all the following instructions
+/// from jump table dispatches. Instructions reached with different ap tracking, instructions after
+/// calls, and unreached instructions have an unknown ap tracking.
+fn code_ap_tracking(instructions: &[Instruction], ap_tracking: ApChange) -> Vec<ApChange> {
+    let mut code_offsets = vec![];
+    let mut offset = 0;
+    for instruction in instructions {
+        code_offsets.push(offset);
+        offset += instruction.body.op_size();
+    }
+    let mut result: Vec<Option<ApChange>> = vec![None; instructions.len()];
+    let mut pending = vec![];
+    if !instructions.is_empty() {
+        result[0] = Some(ap_tracking);
+        pending.push(0);
+    }
+    while let Some(idx) = pending.pop() {
+        let instruction = &instructions[idx];
+        let ap_tracking = result[idx].unwrap();
+        let ap_tracking_after = match (&instruction.body, ap_tracking) {
+            (InstructionBody::Call(_), _) | (_, ApChange::Unknown) => ApChange::Unknown,
+            (InstructionBody::AddAp(insn), ApChange::Known(change)) => match &insn.operand {
+                ResOperand::Immediate(size) => usize::try_from(size)
+                    .map_or(ApChange::Unknown, |size| ApChange::Known(change + size)),
+                _ => ApChange::Unknown,
+            },
+            (_, ApChange::Known(change)) => {
+                ApChange::Known(if instruction.inc_ap { change + 1 } else { change })
+            }
+        };
+        let jump_target = |jump_offset: &DerefOrImmediate| -> Vec<usize> {
+            match jump_offset {
+                DerefOrImmediate::Deref(_) => (idx + 1..instructions.len()).collect(),
+                DerefOrImmediate::Immediate(value) => {
+                    usize::try_from(BigInt::from(code_offsets[idx]) + value)
+                        .ok()
+                        .and_then(|target_offset| code_offsets.binary_search(&target_offset).ok())
+                        .into_iter()
+                        .collect()
+                }
+            }
+        };
+        let successors: Vec<usize> = match &instruction.body {
+            InstructionBody::Jump(insn) if insn.relative => jump_target(&insn.target),
+            InstructionBody::Jump(_) | InstructionBody::Ret(_) => vec![],
+            InstructionBody::Jnz(insn) => chain!([idx + 1], jump_target(&insn.jump_offset))
+                .filter(|target| *target < instructions.len())
+                .collect(),
+            _ => (idx + 1..instructions.len()).take(1).collect(),
+        };
+        for target in successors {
+            let merged = match result[target] {
+                Some(previous) if previous != ap_tracking_after => ApChange::Unknown,
+                _ => ap_tracking_after,
+            };
+            if result[target] != Some(merged) {
+                result[target] = Some(merged);
+                pending.push(target);
+            }
+        }
+    }
+    result.into_iter().map(|ap_tracking| ap_tracking.unwrap_or(ApChange::Unknown)).collect()
+}
+
+/// Computes the metadata of a Sierra program and compiles it to casm.
+/// Unlike [compile], reports all the independent errors found, rather than only the first one:
+/// every failing libfunc declaration, and both the ap change and the gas calculation failures.
+pub fn compile_with_diagnostics(
+    program: &Program,
+    calc_gas: bool,
+    emit_comments: bool,
+) -> Result<CairoProgram, Vec<Diagnostic>> {
+    // Validating the declarations first, as the following stages stop at their first error.
+    ProgramRegistry::<CoreType, CoreLibFunc>::with_ap_change_collecting_errors(
+        program,
+        HashMap::default(),
+    )
+    .map_err(|errors| errors.into_iter().map(Diagnostic::from).collect::<Vec<_>>())?;
+    let ap_change_info = calc_ap_changes(program).map_err(Diagnostic::from);
+    let gas_info = if calc_gas {
+        calc_gas_info(program).map_err(Diagnostic::from)
+    } else {
+        Ok(GasInfo {
+            variable_values: HashMap::new(),
+            function_costs: HashMap::new(),
+            store_temp_costs: HashMap::new(),
+        })
+    };
+    let (ap_change_info, gas_info) = match (ap_change_info, gas_info) {
+        (Ok(ap_change_info), Ok(gas_info)) => (ap_change_info, gas_info),
+        (ap_change_info, gas_info) => {
+            return Err(ap_change_info.err().into_iter().chain(gas_info.err()).collect());
+        }
+    };
+    let metadata = Metadata { ap_change_info, gas_info, cost_weights: CostWeights::default() };
+    compile(program, &metadata, calc_gas, emit_comments)
+        .map_err(|error| vec![Diagnostic::from(error)])
+}
@@ -1,10 +1,32 @@
-use indoc::indoc;
+use std::path::PathBuf;
+
+use casm::ap_change::ApChange;
+use casm::cost::CostWeights;
+use casm::hints::Hint;
+use casm::instructions::Instruction;
+use casm::{casm, res};
+use indoc::{formatdoc, indoc};
 use pretty_assertions;
+use sierra::extensions::builtin_cost::CostTokenType;
+use sierra::ids::FunctionId;
+use sierra::program::{StatementIdx, StatementLocation};
 use sierra::ProgramParser;
+use sierra_ap_change::StackLayout;
+use sierra_gas::by_pointer_store_temp_cost;
 use test_case::test_case;
-
-use crate::compiler::compile;
-use crate::test_utils::{build_metadata, read_sierra_example_file, strip_comments_and_linebreaks};
+use test_utils::compare_contents_or_fix_with_path;
+use utils::diagnostic::Diagnostic;
+
+use crate::compiler::{
+    compile, compile_with_diagnostics, CairoProgram, DebugInfoSection, FunctionDebugInfo,
+    LocalDebugInfo, StatementCodeRange, DEBUG_INFO_SECTION_VERSION,
+};
+use crate::metadata::Metadata;
+use crate::syscall_records::{selector_value, GET_CALLER_ADDRESS_SELECTOR};
+use crate::test_utils::{
+    build_metadata, build_metadata_with_stack_layout, read_sierra_example_file,
+    storage_writes_program, strip_comments_and_linebreaks,
+};
 
 #[test_case(indoc! {"
                 type felt = felt;
@@ -110,6 +132,56 @@ use crate::test_utils::{build_metadata, read_sierra_example_file, strip_comments
             "good_flow")]
 #[test_case(indoc! {"
                 type felt = felt;
+
+                libfunc felt_dup = dup<felt>;
+                libfunc felt_add_1 = felt_add<1>;
+                libfunc felt_add_2 = felt_add<2>;
+                libfunc felt_add_3 = felt_add<3>;
+                libfunc felt_add_4 = felt_add<4>;
+                libfunc store_temp_felt = store_temp<felt>;
+                libfunc call_five = function_call<user@five>;
+
+                store_temp_felt([1]) -> ([1]);                          // #0
+                call_five([1]) -> ([1], [2], [3], [4], [5]);            // #1
+                return([1], [2], [3], [4], [5]);                        // #2
+
+                felt_dup([1]) -> ([1], [2]);                            // #3
+                felt_add_1([2]) -> ([2]);                               // #4
+                felt_dup([1]) -> ([1], [3]);                            // #5
+                felt_add_2([3]) -> ([3]);                               // #6
+                felt_dup([1]) -> ([1], [4]);                            // #7
+                felt_add_3([4]) -> ([4]);                               // #8
+                felt_dup([1]) -> ([1], [5]);                            // #9
+                felt_add_4([5]) -> ([5]);                               // #10
+                store_temp_felt([1]) -> ([1]);                          // #11
+                store_temp_felt([2]) -> ([2]);                          // #12
+                store_temp_felt([3]) -> ([3]);                          // #13
+                store_temp_felt([4]) -> ([4]);                          // #14
+                store_temp_felt([5]) -> ([5]);                          // #15
+                return([1], [2], [3], [4], [5]);                        // #16
+
+                five_wrapper@0([1]: felt) -> (felt, felt, felt, felt, felt);
+                five@3([1]: felt) -> (felt, felt, felt, felt, felt);
+            "},
+            false,
+            indoc! {"
+                // five_wrapper:
+                [ap + 0] = [fp + -3], ap++;
+                // The results of `five` are used in place, without any copies.
+                call rel 3;
+                ret;
+
+                // five:
+                [ap + 0] = [fp + -3], ap++;
+                [ap + 0] = [fp + -3] + 1, ap++;
+                [ap + 0] = [fp + -3] + 2, ap++;
+                [ap + 0] = [fp + -3] + 3, ap++;
+                [ap + 0] = [fp + -3] + 4, ap++;
+                ret;
+            "};
+            "multiple_results")]
+#[test_case(indoc! {"
+                type felt = felt;
                 type UninitializedFelt = Uninitialized<felt>;
                 type ArrayFelt = Array<felt>;
                 type UninitializedArrayFelt = Uninitialized<ArrayFelt>;
@@ -677,17 +749,299 @@ use crate::test_utils::{build_metadata, read_sierra_example_file, strip_comments
                 ret;
             "};
             "dict test")]
+#[test_case(indoc! {"
+                type felt = felt;
+                type NonZeroFelt = NonZero<felt>;
+                type Unit = Struct<ut@Unit>;
+                type UnitPair = Struct<ut@UnitPair, Unit, Unit>;
+
+                libfunc finalize_locals = finalize_locals;
+                libfunc unit_construct = struct_construct<Unit>;
+                libfunc unit_pair_construct = struct_construct<UnitPair>;
+                libfunc unit_pair_deconstruct = struct_deconstruct<UnitPair>;
+                libfunc alloc_local_unit = alloc_local<Unit>;
+                libfunc store_local_unit = store_local<Unit>;
+                libfunc store_temp_unit = store_temp<Unit>;
+                libfunc store_temp_unit_pair = store_temp<UnitPair>;
+                libfunc rename_unit = rename<Unit>;
+                libfunc drop_unit = drop<Unit>;
+                libfunc drop_nz_felt = drop<NonZeroFelt>;
+                libfunc felt_jump_nz = felt_jump_nz;
+                libfunc call_unit_pair = function_call<user@unit_pair>;
+
+                unit_construct() -> ([2]);                      // #0
+                felt_jump_nz([1]) { fallthrough() 4([1]) };     // #1
+                store_temp_unit([2]) -> ([2]);                  // #2
+                return([2]);                                    // #3
+                drop_nz_felt([1]) -> ();                        // #4
+                rename_unit([2]) -> ([2]);                      // #5
+                call_unit_pair([2]) -> ([2]);                   // #6
+                return([2]);                                    // #7
+
+                alloc_local_unit() -> ([2]);                    // #8
+                finalize_locals() -> ();                        // #9
+                store_local_unit([2], [1]) -> ([2]);            // #10
+                unit_construct() -> ([3]);                      // #11
+                unit_pair_construct([2], [3]) -> ([4]);         // #12
+                store_temp_unit_pair([4]) -> ([4]);             // #13
+                unit_pair_deconstruct([4]) -> ([5], [6]);       // #14
+                drop_unit([5]) -> ();                           // #15
+                store_temp_unit([6]) -> ([6]);                  // #16
+                return([6]);                                    // #17
+
+                unit_branch@0([1]: felt) -> (Unit);
+                unit_pair@8([1]: Unit) -> (Unit);
+            "},
+            false,
+            indoc! {"
+                // unit_branch:
+                jmp rel 3 if [fp + -3] != 0;
+                ret;
+                call rel 3;
+                ret;
+
+                // unit_pair:
+                ap += 0;
+                ret;
+            "};
+            "zero sized values")]
+#[test_case(indoc! {"
+                type felt = felt;
+                type ArrayFelt = Array<felt>;
+
+                libfunc panic_array_new = panic_array_new;
+                libfunc felt_const_7 = felt_const<7>;
+                libfunc drop_array = drop<ArrayFelt>;
+
+                panic_array_new([1]) -> ([2]);                  // #0
+                drop_array([2]) -> ();                          // #1
+                felt_const_7() -> ([3]);                        // #2
+                panic_array_new([3]) -> ([4]);                  // #3
+                return([4]);                                    // #4
+
+                test_program@0([1]: felt) -> (ArrayFelt);
+            "},
+            false,
+            indoc! {"
+                [ap + 0] = [fp + -3], ap++;
+                call rel 7;
+                [ap + 0] = 7, ap++;
+                call rel 3;
+                ret;
+
+                // The panic array routine, emitted once for both calls.
+                %{ memory[ap + 0] = segments.add() %}
+                [ap + 1] = [ap + 0] + 1, ap++;
+                [fp + -3] = [[ap + -1] + 0], ap++;
+                ret;
+            "};
+            "shared runtime routine")]
 
 fn sierra_to_casm(sierra_code: &str, check_gas_usage: bool, expected_casm: &str) {
     let program = ProgramParser::new().parse(sierra_code).unwrap();
     pretty_assertions::assert_eq!(
-        compile(&program, &build_metadata(&program, check_gas_usage), check_gas_usage)
+        compile(&program, &build_metadata(&program, check_gas_usage), check_gas_usage, false)
             .expect("Compilation failed.")
             .to_string(),
         strip_comments_and_linebreaks(expected_casm)
     );
 }
 
+#[test]
+fn values_by_pointer() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type Triple = Struct<ut@Triple, felt, felt, felt>;
+
+            libfunc felt_const_5 = felt_const<5>;
+            libfunc triple_construct = struct_construct<Triple>;
+            libfunc triple_deconstruct = struct_deconstruct<Triple>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc store_temp_triple = store_temp<Triple>;
+            libfunc drop_felt = drop<felt>;
+            libfunc call_make = function_call<user@make>;
+            libfunc call_first = function_call<user@first>;
+
+            felt_const_5() -> ([3]);                        // #0
+            triple_construct([1], [2], [3]) -> ([4]);       // #1
+            store_temp_triple([4]) -> ([4]);                // #2
+            return([4]);                                    // #3
+
+            store_temp_felt([1]) -> ([1]);                  // #4
+            store_temp_felt([2]) -> ([2]);                  // #5
+            call_make([1], [2]) -> ([3]);                   // #6
+            felt_const_5() -> ([4]);                        // #7
+            store_temp_felt([4]) -> ([4]);                  // #8
+            drop_felt([4]) -> ();                           // #9
+            store_temp_triple([3]) -> ([3]);                // #10
+            call_first([3]) -> ([5]);                       // #11
+            return([5]);                                    // #12
+
+            triple_deconstruct([1]) -> ([2], [3], [4]);     // #13
+            drop_felt([3]) -> ();                           // #14
+            drop_felt([4]) -> ();                           // #15
+            store_temp_felt([2]) -> ([2]);                  // #16
+            return([2]);                                    // #17
+
+            make@0([1]: felt, [2]: felt) -> (Triple);
+            test_program@4([1]: felt, [2]: felt) -> (felt);
+            first@13([1]: Triple) -> (felt);
+        "})
+        .unwrap();
+    let metadata = build_metadata_with_stack_layout(
+        &program,
+        false,
+        StackLayout { by_pointer_threshold: Some(2) },
+    );
+    pretty_assertions::assert_eq!(
+        compile(&program, &metadata, false, false).expect("Compilation failed.").to_string(),
+        strip_comments_and_linebreaks(indoc! {"
+            // make:
+            %{ memory[ap + 3] = segments.add() %}
+            [fp + -4] = [[ap + 3] + 0];
+            [fp + -3] = [[ap + 3] + 1];
+            [ap + 2] = 5;
+            [ap + 2] = [[ap + 3] + 2];
+            ap += 4;
+            ret;
+
+            // test_program:
+            [ap + 0] = [fp + -4], ap++;
+            [ap + 0] = [fp + -3], ap++;
+            call rel -10;
+            [ap + 0] = 5, ap++;
+            [ap + 3] = [ap + -2];
+            ap += 4;
+            call rel 3;
+            ret;
+
+            // first:
+            [ap + 0] = [[fp + -3] + 0], ap++;
+            ret;
+        "})
+    );
+}
+
+/// Builds a program where `outer` and `middle` each return the `Big` value of the function they
+/// call, after another value was pushed on top of it.
+fn nested_big_returns_program(size: usize) -> String {
+    let members = vec!["felt"; size].join(", ");
+    let args = (0..size).map(|i| format!("[{i}]")).collect::<Vec<_>>().join(", ");
+    let consts =
+        (0..size).map(|i| format!("felt_const_1() -> ([{i}]);\n")).collect::<Vec<_>>().concat();
+    let forward = |callee: &str| {
+        formatdoc! {"
+            {callee}() -> ([0]);
+            felt_const_1() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            drop_felt([1]) -> ();
+            store_temp_big([0]) -> ([0]);
+            return([0]);
+        "}
+    };
+    formatdoc! {"
+        type felt = felt;
+        type Big = Struct<ut@Big, {members}>;
+
+        libfunc felt_const_1 = felt_const<1>;
+        libfunc big_construct = struct_construct<Big>;
+        libfunc store_temp_felt = store_temp<felt>;
+        libfunc store_temp_big = store_temp<Big>;
+        libfunc drop_felt = drop<felt>;
+        libfunc call_inner = function_call<user@inner>;
+        libfunc call_middle = function_call<user@middle>;
+        libfunc call_outer = function_call<user@outer>;
+
+        {consts}big_construct({args}) -> ([0]);
+        store_temp_big([0]) -> ([0]);
+        return([0]);
+        {middle}{outer}{top}
+        inner@0() -> (Big);
+        middle@{middle_start}() -> (Big);
+        outer@{outer_start}() -> (Big);
+        top@{top_start}() -> (Big);
+    ",
+        middle = forward("call_inner"),
+        outer = forward("call_middle"),
+        top = forward("call_outer"),
+        middle_start = size + 3,
+        outer_start = size + 9,
+        top_start = size + 15,
+    }
+}
+
+/// Checks the number of instructions required to return a value of 16 felts through 3 levels of
+/// calls, with the value copied on every return, and with the value passed by pointer.
+#[test_case(None => 74; "by value")]
+#[test_case(Some(15) => 49; "by pointer")]
+fn nested_big_returns_instructions(by_pointer_threshold: Option<usize>) -> usize {
+    let program = ProgramParser::new().parse(&nested_big_returns_program(16)).unwrap();
+    let metadata =
+        build_metadata_with_stack_layout(&program, false, StackLayout { by_pointer_threshold });
+    compile(&program, &metadata, false, false).expect("Compilation failed.").instructions.len()
+}
+
+/// Checks the gas cost of returning a value of 16 felts through 3 levels of calls: storing the
+/// value by pointer is charged for copying each of its cells, instead of a single step.
+#[test]
+fn nested_big_returns_gas() {
+    let program = ProgramParser::new().parse(&nested_big_returns_program(16)).unwrap();
+    let top_cost = |by_pointer_threshold| {
+        build_metadata_with_stack_layout(&program, true, StackLayout { by_pointer_threshold })
+            .gas_info
+            .function_costs[&FunctionId::from("top")][CostTokenType::Step]
+    };
+    // The value is stored once by the innermost function, and once more by every call layer.
+    assert_eq!(top_cost(Some(15)) - top_cost(None), 4 * (by_pointer_store_temp_cost(16) - 1));
+}
+
+/// Checks the number of instructions of a program creating two panic arrays, by the cost weights
+/// of the compilation. A call to the panic array routine takes 3 felts and 5 steps, and inlining it
+/// takes 6 felts and 4 steps, so it is inlined only when a step weighs more than 3 felts.
+#[test_case(CostWeights { size: 1, steps: 0 } => 8; "size only")]
+#[test_case(CostWeights { size: 1, steps: 3 } => 8; "at threshold")]
+#[test_case(CostWeights { size: 1, steps: 4 } => 9; "above threshold")]
+#[test_case(CostWeights { size: 0, steps: 1 } => 9; "steps only")]
+fn panic_array_instructions(cost_weights: CostWeights) -> usize {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type ArrayFelt = Array<felt>;
+
+            libfunc panic_array_new = panic_array_new;
+            libfunc drop_array = drop<ArrayFelt>;
+
+            panic_array_new([1]) -> ([2]);
+            drop_array([2]) -> ();
+            panic_array_new([1]) -> ([3]);
+            return([3]);
+
+            test_program@0([1]: felt) -> (ArrayFelt);
+        "})
+        .unwrap();
+    let metadata = Metadata { cost_weights, ..build_metadata(&program, false) };
+    compile(&program, &metadata, false, false).expect("Compilation failed.").instructions.len()
+}
+
+/// Checks the number of instructions saved in a program with consecutive storage writes, by reading
+/// the selector of a write from the cell the previous write stored it in. The cell is found using
+/// the ap tracking, so without it every write stores the selector again.
+#[test_case(1 => 0; "single write")]
+#[test_case(5 => 4; "five writes")]
+fn storage_writes_saved_instructions(n_writes: usize) -> usize {
+    let instructions = |revoke_ap_tracking| {
+        let program = ProgramParser::new()
+            .parse(&storage_writes_program(n_writes, revoke_ap_tracking))
+            .unwrap();
+        compile(&program, &build_metadata(&program, false), false, false)
+            .expect("Compilation failed.")
+            .instructions
+            .len()
+    };
+    instructions(true) - instructions(false)
+}
+
 // TODO(ilya, 10/10/2022): Improve error messages.
 #[test_case(indoc! {"
                 return([2]);
@@ -743,7 +1097,7 @@ fn sierra_to_casm(sierra_code: &str, check_gas_usage: bool, expected_casm: &str)
 
                 test_program@0([1]: felt, [2]: felt, [3]: felt) -> ();
             "},
-            "#1: One of the arguments does not satisfy the requirements of the libfunc.";
+            "#1: felt_add: One of the arguments does not satisfy the requirements of the libfunc.";
             "Invalid reference expression for felt_add")]
 #[test_case(indoc! {"
                 type felt = felt;
@@ -789,22 +1143,48 @@ fn sierra_to_casm(sierra_code: &str, check_gas_usage: bool, expected_casm: &str)
                 libfunc felt_add = felt_add;
                 felt_add([1], [2]) -> ([3], [4]);
                 test_program@0([1]: felt, [2]: felt) -> ();
-            "}, "#0: Invocation mismatched to libfunc";
+            "}, "#0: Branch #0 expected 1 results, got 2.";
             "output type mismatch")]
 #[test_case(indoc! {"
                 type felt = felt;
                 libfunc felt_add = felt_add;
                 felt_add([1], [2]) { 0([3]) 1([3]) };
                 test_program@0([1]: felt, [2]: felt) -> ();
-            "}, "#0: Invocation mismatched to libfunc";
+            "}, "#0: Expected 1 branches, got 2.";
             "branch count mismatch")]
 #[test_case(indoc! {"
                 type felt = felt;
                 libfunc felt_add = felt_add;
                 felt_add([1], [2]) { 0([3]) };
                 test_program@0([1]: felt, [2]: felt) -> ();
-            "}, "#0: Invocation mismatched to libfunc";
+            "}, "#0: Branch #0 is expected to be a fallthrough.";
             "fallthrough mismatch")]
+#[test_case(indoc! {"
+                libfunc jump = jump;
+                jump() { fallthrough() };
+                return();
+                test_program@0() -> ();
+            "}, "#0: Branch #0 is not allowed to be a fallthrough.";
+            "unexpected fallthrough")]
+#[test_case(indoc! {"
+                type felt = felt;
+                type NonZeroFelt = NonZero<felt>;
+                libfunc felt_jump_nz = felt_jump_nz;
+                felt_jump_nz([1]) { fallthrough() 1([1]) };
+                return();
+                test_program@0([1]: felt) -> ();
+            "}, "#0: Branches #0 and #1 both target #1.";
+            "duplicate branch target")]
+#[test_case(indoc! {"
+                type felt = felt;
+                type NonZeroFelt = NonZero<felt>;
+                libfunc felt_jump_nz = felt_jump_nz;
+                felt_jump_nz([1]) { fallthrough() 2() };
+                return();
+                return();
+                test_program@0([1]: felt) -> ();
+            "}, "#0: Branch #1 expected 1 results, got 0.";
+            "branch result count mismatch")]
 #[test_case(indoc! {"
                 type felt = felt;
                 libfunc felt_dup = dup<felt>;
@@ -872,6 +1252,33 @@ of the libfunc or return statement.";
                 foo@0([1]: felt) -> ();
             "}, "#2->#3: Got 'Unknown ap change' error while moving [1].";
             "Ap change error")]
+#[test_case(indoc! {"
+                type felt = felt;
+                type ArrayFelt = Array<felt>;
+                type RangeCheck = RangeCheck;
+                type GasBuiltin = GasBuiltin;
+
+                libfunc felt_const_1 = felt_const<1>;
+                libfunc store_temp_felt = store_temp<felt>;
+                libfunc array_new_felt = array_new<felt>;
+                libfunc array_clone_felt = array_clone<felt>;
+                libfunc drop_arr = drop<ArrayFelt>;
+
+                felt_const_1() -> ([1]);
+                store_temp_felt([1]) -> ([1]);
+                array_new_felt() -> ([2]);
+                array_clone_felt([4], [5], [2]) {
+                    fallthrough([4], [5], [2], [3]) 7([4], [5], [2])
+                };
+                drop_arr([2]) -> ();
+                drop_arr([3]) -> ();
+                return([4], [5], [1]);
+                drop_arr([2]) -> ();
+                return([4], [5], [1]);
+
+                test_program@0([4]: RangeCheck, [5]: GasBuiltin) -> (RangeCheck, GasBuiltin, felt);
+            "}, "#3->#4: Got 'Unknown ap change' error while moving [1].";
+            "Ap change error after loop")]
 #[test_case(indoc! {"
                 type felt = felt;
                 type NonZeroFelt = NonZero<felt>;
@@ -961,14 +1368,460 @@ of the libfunc or return statement.";
                 return ();
 
                 foo@0() -> ();
-            "}, "#1: The functionality is supported only for sized types.";
+            "}, "#1: store_temp<UninitializedFelt>: The functionality is supported only for sized types.";
             "store_temp<Uninitialized<felt>()")]
 fn compiler_errors(sierra_code: &str, expected_result: &str) {
     let program = ProgramParser::new().parse(sierra_code).unwrap();
     pretty_assertions::assert_eq!(
-        compile(&program, &build_metadata(&program, false), false)
+        compile(&program, &build_metadata(&program, false), false, false)
             .expect_err("Compilation is expected to fail.")
             .to_string(),
         expected_result
     );
 }
+
+#[test_case(indoc! {"
+                type felt = felt;
+                libfunc felt_dup = dup<felt>;
+
+                felt_dup([1]) -> ([1], [2]);
+                return ([1]);
+                test_program@0([1]: felt) -> ();
+            "}, "E0141", Some(1);
+            "Dangling references")]
+#[test_case(indoc! {"
+                type felt = felt;
+                type NonZeroFelt = NonZero<felt>;
+                libfunc felt_jump_nz = felt_jump_nz;
+                felt_jump_nz([1]) { fallthrough() 1([1]) };
+                return();
+                test_program@0([1]: felt) -> ();
+            "}, "E0107", Some(0);
+            "duplicate branch target")]
+#[test_case(indoc! {"
+                type felt = felt;
+                libfunc felt_add = felt_add;
+
+                felt_add([1], [2]) -> ([4]);
+                felt_add([3], [4]) -> ([5]);
+
+                test_program@0([1]: felt, [2]: felt, [3]: felt) -> ();
+            "}, "E0150", Some(1);
+            "invalid reference expression")]
+#[test_case(indoc! {"
+                used_id@0() -> ();
+                used_id@0() -> ();
+            "}, "E0010", None;
+            "function id double declaration")]
+fn compiler_diagnostic_codes(sierra_code: &str, code: &str, statement_idx: Option<usize>) {
+    let program = ProgramParser::new().parse(sierra_code).unwrap();
+    let diagnostics = compile_with_diagnostics(&program, false, false)
+        .expect_err("Compilation is expected to fail.");
+    pretty_assertions::assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.statement_idx))
+            .collect::<Vec<_>>(),
+        vec![(code, statement_idx)]
+    );
+}
+
+#[test]
+fn compile_through_unknown_ap_change() {
+    // The copy loop of `array_clone` changes ap by an unknown amount, so ap tracking is lost after
+    // it, while the cloned arrays remain usable. The failure branch, which skips the loop, keeps
+    // tracking ap.
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type ArrayFelt = Array<felt>;
+            type RangeCheck = RangeCheck;
+            type GasBuiltin = GasBuiltin;
+
+            libfunc array_new_felt = array_new<felt>;
+            libfunc array_clone_felt = array_clone<felt>;
+            libfunc store_temp_rc = store_temp<RangeCheck>;
+            libfunc store_temp_gb = store_temp<GasBuiltin>;
+            libfunc store_temp_arr = store_temp<ArrayFelt>;
+
+            array_new_felt() -> ([1]);
+            array_clone_felt([3], [4], [1]) { fallthrough([3], [4], [1], [2]) 7([3], [4], [1]) };
+            store_temp_rc([3]) -> ([3]);
+            store_temp_gb([4]) -> ([4]);
+            store_temp_arr([1]) -> ([1]);
+            store_temp_arr([2]) -> ([2]);
+            return([3], [4], [1], [2]);
+            array_new_felt() -> ([2]);
+            store_temp_rc([3]) -> ([3]);
+            store_temp_gb([4]) -> ([4]);
+            store_temp_arr([1]) -> ([1]);
+            store_temp_arr([2]) -> ([2]);
+            return([3], [4], [1], [2]);
+
+            test_program@0([3]: RangeCheck, [4]: GasBuiltin)
+                -> (RangeCheck, GasBuiltin, ArrayFelt, ArrayFelt);
+        "})
+        .unwrap();
+    let metadata = build_metadata(&program, false);
+    assert_eq!(
+        metadata.ap_change_info.function_ap_change.get(&FunctionId::from("test_program")),
+        None
+    );
+    let cairo_program = compile(&program, &metadata, false, false).expect("Compilation failed.");
+    pretty_assertions::assert_eq!(
+        cairo_program
+            .debug_info
+            .sierra_statement_info
+            .iter()
+            .map(|statement_info| statement_info.ap_tracking)
+            .collect::<Vec<_>>(),
+        vec![
+            ApChange::Known(0),
+            ApChange::Known(1),
+            ApChange::Unknown,
+            ApChange::Unknown,
+            ApChange::Unknown,
+            ApChange::Unknown,
+            ApChange::Unknown,
+            ApChange::Known(6),
+            ApChange::Known(7),
+            ApChange::Known(8),
+            ApChange::Known(9),
+            ApChange::Known(11),
+            ApChange::Known(13)
+        ]
+    );
+}
+
+#[test]
+fn compiler_collects_all_registry_diagnostics() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type u128 = u128;
+
+            libfunc negative = u128_const<-1>;
+            libfunc valid = u128_const<5>;
+            libfunc too_big = u128_const<340282366920938463463374607431768211456>;
+            libfunc also_negative = u128_const<-2>;
+
+            return();
+
+            foo@0() -> ();
+        "})
+        .unwrap();
+    let diagnostics = compile_with_diagnostics(&program, false, false)
+        .expect_err("Compilation is expected to fail.");
+    pretty_assertions::assert_eq!(
+        diagnostics.iter().map(|diagnostic| diagnostic.code).collect::<Vec<_>>(),
+        vec!["E0003"; 3]
+    );
+    pretty_assertions::assert_eq!(
+        diagnostics[1],
+        Diagnostic::error(
+            "E0003",
+            "Libfunc `too_big`: Could not specialize libfunc `u128_const`: Provided generic arg \
+             #0 is unsupported."
+        )
+    );
+}
+
+#[test]
+fn strip_debug_hints() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type ArrayFelt = Array<felt>;
+
+            libfunc array_new_felt = array_new<felt>;
+            libfunc debug_print = debug_print;
+            libfunc array_felt_drop = drop<ArrayFelt>;
+
+            array_new_felt() -> ([1]);
+            debug_print([1]) -> ([1]);
+            array_felt_drop([1]) -> ();
+            return();
+
+            test_program@0() -> ();
+        "})
+        .unwrap();
+    let mut cairo_program = compile(&program, &build_metadata(&program, false), false, false)
+        .expect("Compilation failed.");
+    let has_debug_hints = |cairo_program: &CairoProgram| {
+        cairo_program
+            .instructions
+            .iter()
+            .any(|instruction| matches!(instruction.hints[..], [Hint::DebugPrint { .. }]))
+    };
+    assert!(has_debug_hints(&cairo_program));
+    cairo_program.strip_debug_hints();
+    assert!(!has_debug_hints(&cairo_program));
+}
+
+#[test]
+fn assemble() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type GasBuiltin = GasBuiltin;
+            type System = System;
+            type ContractAddress = ContractAddress;
+
+            libfunc get_caller_address = get_caller_address;
+            libfunc store_temp_gb = store_temp<GasBuiltin>;
+            libfunc store_temp_system = store_temp<System>;
+            libfunc drop_address = drop<ContractAddress>;
+            libfunc drop_felt = drop<felt>;
+
+            get_caller_address([1], [2]) { fallthrough([1], [2], [3]) 5([1], [2], [3]) }; // #0
+            drop_address([3]) -> ();                                                        // #1
+            store_temp_gb([1]) -> ([1]);                                                    // #2
+            store_temp_system([2]) -> ([2]);                                                // #3
+            return([1], [2]);                                                               // #4
+            drop_felt([3]) -> ();                                                           // #5
+            store_temp_gb([1]) -> ([1]);                                                    // #6
+            store_temp_system([2]) -> ([2]);                                                // #7
+            return([1], [2]);                                                               // #8
+
+            test_program@0([1]: GasBuiltin, [2]: System) -> (GasBuiltin, System);
+        "})
+        .unwrap();
+    let assembled = compile(&program, &build_metadata(&program, false), false, false)
+        .expect("Compilation failed.")
+        .assemble();
+    // The selector is pushed by a two word instruction, followed by two single word instructions
+    // writing the request, preceding the instruction with the system call hint.
+    assert_eq!(assembled.bytecode[1], selector_value(GET_CALLER_ADDRESS_SELECTOR));
+    assert_eq!(assembled.hints, vec![(4, vec![Hint::SystemCall { system: res!([fp - 3]) }])]);
+    // The syscall, and for each branch: a single word store of the gas builtin, a two word store
+    // of the system pointer, and a return.
+    assert_eq!(assembled.bytecode.len(), 15);
+}
+
+#[test]
+fn disassemble_call_contract() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type GasBuiltin = GasBuiltin;
+            type System = System;
+            type ContractAddress = ContractAddress;
+            type ArrayFelt = Array<felt>;
+
+            libfunc call_contract = call_contract_syscall;
+            libfunc store_temp_gb = store_temp<GasBuiltin>;
+            libfunc store_temp_system = store_temp<System>;
+            libfunc drop_array = drop<ArrayFelt>;
+            libfunc drop_felt = drop<felt>;
+
+            call_contract([1], [2], [3], [4]) {
+                fallthrough([1], [2], [4]) 5([1], [2], [3], [4])
+            };                                                                              // #0
+            drop_array([4]) -> ();                                                          // #1
+            store_temp_gb([1]) -> ([1]);                                                    // #2
+            store_temp_system([2]) -> ([2]);                                                // #3
+            return([1], [2]);                                                               // #4
+            drop_felt([3]) -> ();                                                           // #5
+            drop_array([4]) -> ();                                                          // #6
+            store_temp_gb([1]) -> ([1]);                                                    // #7
+            store_temp_system([2]) -> ([2]);                                                // #8
+            return([1], [2]);                                                               // #9
+
+            test_program@0([1]: GasBuiltin, [2]: System, [3]: ContractAddress, [4]: ArrayFelt)
+                -> (GasBuiltin, System);
+        "})
+        .unwrap();
+    let cairo_program = compile(&program, &build_metadata(&program, false), false, false)
+        .expect("Compilation failed.");
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.extend(["test_data", "call_contract.casm"]);
+    compare_contents_or_fix_with_path(&path, cairo_program.disassemble(true).to_string());
+}
+
+#[test]
+fn debug_info() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type UninitializedFelt = Uninitialized<felt>;
+
+            libfunc alloc_local_felt = alloc_local<felt>;
+            libfunc store_local_felt = store_local<felt>;
+            libfunc finalize_locals = finalize_locals;
+            libfunc store_temp_felt = store_temp<felt>;
+
+            store_temp_felt([1]) -> ([1]);
+            alloc_local_felt() -> ([2]);
+            finalize_locals() -> ();
+            store_local_felt([2], [1]) -> ([2]);
+            store_temp_felt([2]) -> ([2]);
+            return([2]);
+
+            foo@0([1]: felt) -> (felt);
+        "})
+        .unwrap();
+    let debug_info = compile(&program, &build_metadata(&program, false), false, false)
+        .expect("Compilation failed.")
+        .debug_info;
+    assert_eq!(
+        debug_info.sierra_statement_info.iter().map(|info| info.ap_tracking).collect::<Vec<_>>(),
+        [0, 1, 1, 2, 2, 3].map(ApChange::Known)
+    );
+    assert_eq!(
+        debug_info.function_info,
+        vec![FunctionDebugInfo {
+            id: "foo".into(),
+            code_offset: 0,
+            locals: vec![LocalDebugInfo { var_id: 2.into(), ty: "felt".into(), fp_offset: 1 }],
+        }]
+    );
+    assert_eq!(debug_info.function_at(3), Some(&debug_info.function_info[0]));
+    // `alloc_local` has no code, and `finalize_locals` advances ap over the local.
+    assert_eq!(
+        debug_info
+            .instruction_info
+            .iter()
+            .map(|info| (info.code_offset, info.statement_idx, info.ap_tracking))
+            .collect::<Vec<_>>(),
+        [(0, Some(0), 0), (1, Some(2), 1), (3, Some(3), 2), (4, Some(4), 2), (5, Some(5), 3)].map(
+            |(code_offset, statement_idx, ap_tracking)| {
+                (code_offset, statement_idx, ApChange::Known(ap_tracking))
+            }
+        )
+    );
+    assert_eq!(debug_info.instruction_at(3).map(|info| info.ap_tracking), Some(ApChange::Known(2)));
+    assert_eq!(debug_info.instruction_at(2), None);
+
+    let section = debug_info.section();
+    assert_eq!(section.version, DEBUG_INFO_SECTION_VERSION);
+    let serialized = serde_json::to_string(&section).expect("Serialization failed.");
+    assert_eq!(
+        serde_json::from_str::<DebugInfoSection>(&serialized).expect("Deserialization failed."),
+        section
+    );
+}
+
+/// Checks the ap tracking propagated along the flow of the code: through the two branches of a
+/// conditional jump, and after a call.
+#[test_case(
+    casm! {
+        jmp rel 5 if [fp - 3] != 0;
+        [ap + 0] = 1, ap++;
+        ret;
+        [ap + 0] = [fp - 3], ap++;
+        ap += 2;
+        call rel 10;
+        ret;
+    }.instructions,
+    vec![
+        ApChange::Known(0),
+        ApChange::Known(0),
+        ApChange::Known(1),
+        ApChange::Known(0),
+        ApChange::Known(1),
+        ApChange::Known(3),
+        ApChange::Unknown,
+    ];
+    "branches and call"
+)]
+#[test_case(
+    casm! {
+        jmp rel 4 if [fp - 3] != 0;
+        [ap + 0] = 1, ap++;
+        [ap + 0] = 2, ap++;
+        ret;
+    }.instructions,
+    vec![ApChange::Known(0), ApChange::Known(0), ApChange::Unknown, ApChange::Unknown];
+    "merge of different ap changes"
+)]
+fn code_ap_tracking(instructions: Vec<Instruction>, expected: Vec<ApChange>) {
+    assert_eq!(super::code_ap_tracking(&instructions, ApChange::Known(0)), expected);
+}
+
+#[test]
+fn debug_info_locations() {
+    let mut program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+
+            libfunc felt_dup = dup<felt>;
+            libfunc felt_add = felt_add;
+            libfunc store_temp_felt = store_temp<felt>;
+
+            felt_dup([1]) -> ([1], [2]);
+            felt_add([1], [2]) -> ([3]);
+            store_temp_felt([3]) -> ([3]);
+            return([3]);
+
+            foo@0([1]: felt) -> (felt);
+        "})
+        .unwrap();
+    let location = StatementLocation { file_id: 1, start: 40, end: 45 };
+    program.statement_locations.insert(StatementIdx(2), location);
+    let debug_info = compile(&program, &build_metadata(&program, false), false, false)
+        .expect("Compilation failed.")
+        .debug_info;
+    assert_eq!(
+        debug_info.sierra_statement_info.iter().map(|info| info.location).collect::<Vec<_>>(),
+        [None, None, Some(location), None]
+    );
+    // The duplication and the addition compile to no instructions, so the code of the statements
+    // starts with the store of the addition result.
+    assert_eq!(debug_info.location_at(0), Some(location));
+    assert_eq!(debug_info.location_at(1), None);
+}
+
+#[test]
+fn debug_info_statement_code_ranges() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type NonZeroFelt = NonZero<felt>;
+
+            libfunc felt_jump_nz = felt_jump_nz;
+            libfunc non_zero_drop = drop<NonZeroFelt>;
+            libfunc felt_const_1 = felt_const<1>;
+            libfunc store_temp_felt = store_temp<felt>;
+
+            felt_jump_nz([1]) { fallthrough() 4([1]) };
+            felt_const_1() -> ([2]);
+            store_temp_felt([2]) -> ([2]);
+            return([2]);
+            non_zero_drop([1]) -> ();
+            felt_const_1() -> ([2]);
+            store_temp_felt([2]) -> ([2]);
+            return([2]);
+
+            foo@0([1]: felt) -> (felt);
+        "})
+        .unwrap();
+    let cairo_program = compile(&program, &build_metadata(&program, false), false, false)
+        .expect("Compilation failed.");
+    let ranges = &cairo_program.debug_info.statement_code_ranges;
+    assert_eq!(
+        ranges.iter().map(|range| range.instructions.clone()).collect::<Vec<_>>(),
+        vec![0..1, 1..1, 1..2, 2..3, 3..3, 3..3, 3..4, 4..5]
+    );
+    // The ranges are contiguous, and cover all the instructions and the entire bytecode.
+    for (prev, next) in ranges.iter().zip(ranges.iter().skip(1)) {
+        assert_eq!(prev.instructions.end, next.instructions.start);
+        assert_eq!(prev.code.end, next.code.start);
+    }
+    let last = ranges.last().unwrap();
+    assert_eq!(last.instructions.end, cairo_program.instructions.len());
+    assert_eq!(last.code.end, cairo_program.debug_info.routines_code_offset);
+    assert_eq!(
+        last.code.end,
+        cairo_program
+            .instructions
+            .iter()
+            .map(|instruction| instruction.body.op_size())
+            .sum::<usize>()
+    );
+
+    let serialized = serde_json::to_string(&ranges[0]).unwrap();
+    assert_eq!(
+        serialized,
+        r#"{"statement_idx":0,"instructions":{"start":0,"end":1},"code":{"start":0,"end":2}}"#
+    );
+    assert_eq!(serde_json::from_str::<StatementCodeRange>(&serialized).unwrap(), ranges[0]);
+}
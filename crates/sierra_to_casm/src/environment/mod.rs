@@ -1,5 +1,8 @@
+use std::collections::HashMap;
+
 use casm::ap_change::ApChange;
 use frame_state::{FrameState, FrameStateError};
+use num_bigint::BigInt;
 use thiserror::Error;
 
 use self::frame_state::validate_final_frame_state;
@@ -29,6 +32,10 @@ pub struct Environment {
     pub ap_tracking: ApChange,
     pub frame_state: FrameState,
     pub gas_wallet: GasWallet,
+    /// The cells of the current frame known to hold constant values - the offset of such a cell
+    /// from fp, by its value. Memory is immutable, so such a cell keeps holding the value until
+    /// the function returns, and libfuncs may use it instead of storing the value again.
+    pub frame_constants: HashMap<BigInt, i16>,
 }
 impl Environment {
     pub fn new(gas_wallet: GasWallet) -> Self {
@@ -37,11 +44,20 @@ impl Environment {
             ap_tracking,
             frame_state: FrameState::Allocating { allocated: 0, last_ap_tracking: ap_tracking },
             gas_wallet,
+            frame_constants: HashMap::new(),
         }
     }
+
+    /// Keeps only the frame constants that are also known in `other` - as required at a statement
+    /// reachable from statements with both environments.
+    pub fn intersect_frame_constants(&mut self, other: &Environment) {
+        self.frame_constants
+            .retain(|value, offset| other.frame_constants.get(value) == Some(offset));
+    }
 }
 
 // Validates that the environments match and returns appropriate error if not.
+// The frame constants are not compared, as they may only be intersected at merges.
 pub fn validate_environment_equality(
     a: &Environment,
     b: &Environment,
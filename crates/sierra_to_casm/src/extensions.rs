@@ -0,0 +1,131 @@
+//! Compilation of libfuncs defined outside of the core libfuncs.
+
+use std::collections::HashMap;
+
+use casm::cost::CostWeights;
+use sierra::extensions::builtin_cost::CostTokenType;
+use sierra::extensions::core::{CoreConcreteLibFunc, CoreLibFunc};
+use sierra::extensions::GenericLibFunc;
+use sierra::program::{Invocation, Program, StatementIdx};
+use sierra_ap_change::core_libfunc_ap_change::core_libfunc_ap_change;
+use sierra_ap_change::{calc_ap_changes_for_libfuncs, ApChange, ApChangeError, StackLayout};
+use sierra_gas::gas_info::GasInfo;
+use sierra_gas::{calc_gas_info_for_libfuncs, CostError};
+use thiserror::Error;
+use utils::ordered_hash_map::OrderedHashMap;
+
+use crate::environment::Environment;
+use crate::invocations::{
+    compile_invocation, CompiledInvocation, CompiledInvocationBuilder, InvocationError, ProgramInfo,
+};
+use crate::metadata::Metadata;
+use crate::references::ReferenceValue;
+
+#[cfg(test)]
+#[path = "extensions_test.rs"]
+mod test;
+
+/// A libfunc hierarchy whose programs can be compiled to casm - the core libfuncs, possibly
+/// extended by libfuncs defined outside of them.
+///
+/// An extended hierarchy is defined by
+/// [define_libfunc_hierarchy](sierra::define_libfunc_hierarchy), with a variant wrapping
+/// [CoreLibFunc]. The libfuncs of the extension have a known ap change and a constant cost in steps
+/// per branch, and their invocations are compiled by [CompilableLibFunc::compile].
+pub trait CompilableLibFunc: GenericLibFunc {
+    /// Returns the core libfunc `libfunc` is, or None if it is a libfunc of the extension.
+    fn as_core(libfunc: &Self::Concrete) -> Option<&CoreConcreteLibFunc>;
+    /// Returns the ap change of each of the branches of a libfunc of the extension.
+    fn ap_changes(libfunc: &Self::Concrete) -> Vec<usize>;
+    /// Returns the cost in steps of each of the branches of a libfunc of the extension.
+    fn costs(libfunc: &Self::Concrete) -> Vec<i32>;
+    /// Compiles an invocation of a libfunc of the extension.
+    fn compile(
+        libfunc: &Self::Concrete,
+        builder: CompiledInvocationBuilder<'_>,
+    ) -> Result<CompiledInvocation, InvocationError>;
+}
+impl CompilableLibFunc for CoreLibFunc {
+    fn as_core(libfunc: &Self::Concrete) -> Option<&CoreConcreteLibFunc> {
+        Some(libfunc)
+    }
+
+    fn ap_changes(_libfunc: &Self::Concrete) -> Vec<usize> {
+        unreachable!("The core libfuncs have no extension.")
+    }
+
+    fn costs(_libfunc: &Self::Concrete) -> Vec<i32> {
+        unreachable!("The core libfuncs have no extension.")
+    }
+
+    fn compile(
+        _libfunc: &Self::Concrete,
+        _builder: CompiledInvocationBuilder<'_>,
+    ) -> Result<CompiledInvocation, InvocationError> {
+        unreachable!("The core libfuncs have no extension.")
+    }
+}
+
+/// Given a Sierra invocation statement and a concrete libfunc of `TLibFunc`, creates a compiled
+/// casm representation of the Sierra statement.
+pub fn compile_libfunc_invocation<TLibFunc: CompilableLibFunc>(
+    program_info: ProgramInfo<'_>,
+    invocation: &Invocation,
+    libfunc: &TLibFunc::Concrete,
+    idx: StatementIdx,
+    refs: &[ReferenceValue],
+    environment: Environment,
+) -> Result<CompiledInvocation, InvocationError> {
+    if let Some(libfunc) = TLibFunc::as_core(libfunc) {
+        return compile_invocation(program_info, invocation, libfunc, idx, refs, environment);
+    }
+    let builder = CompiledInvocationBuilder {
+        program_info,
+        invocation,
+        libfunc,
+        ap_changes: TLibFunc::ap_changes(libfunc).into_iter().map(ApChange::Known).collect(),
+        gas_changes: TLibFunc::costs(libfunc)
+            .into_iter()
+            .map(|cost| Some(OrderedHashMap::from_iter([(CostTokenType::Step, cost as i64)])))
+            .collect(),
+        idx,
+        refs,
+        environment,
+    };
+    TLibFunc::compile(libfunc, builder)
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum MetadataError {
+    #[error(transparent)]
+    ApChangeError(#[from] ApChangeError),
+    #[error(transparent)]
+    CostError(#[from] CostError),
+}
+
+/// Calculates the metadata of a Sierra program using the libfuncs of `TLibFunc`.
+/// The gas information is only calculated if `calc_gas` is set.
+pub fn calc_metadata<TLibFunc: CompilableLibFunc>(
+    program: &Program,
+    calc_gas: bool,
+) -> Result<Metadata, MetadataError> {
+    let ap_change_info =
+        calc_ap_changes_for_libfuncs::<TLibFunc>(program, StackLayout::default(), |libfunc| {
+            match TLibFunc::as_core(libfunc) {
+                Some(libfunc) => core_libfunc_ap_change(libfunc),
+                None => TLibFunc::ap_changes(libfunc).into_iter().map(ApChange::Known).collect(),
+            }
+        })?;
+    let gas_info = if calc_gas {
+        calc_gas_info_for_libfuncs::<TLibFunc>(program, StackLayout::default(), |libfunc| {
+            TLibFunc::as_core(libfunc).ok_or_else(|| TLibFunc::costs(libfunc))
+        })?
+    } else {
+        GasInfo {
+            variable_values: HashMap::new(),
+            function_costs: HashMap::new(),
+            store_temp_costs: HashMap::new(),
+        }
+    };
+    Ok(Metadata { ap_change_info, gas_info, cost_weights: CostWeights::default() })
+}
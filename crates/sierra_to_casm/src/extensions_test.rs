@@ -0,0 +1,121 @@
+use casm::builder::CasmBuilder;
+use casm::casm_build_extend;
+use casm::operand::ResOperand;
+use indoc::indoc;
+use pretty_assertions;
+use sierra::extensions::core::{CoreConcreteLibFunc, CoreLibFunc};
+use sierra::extensions::felt::FeltType;
+use sierra::extensions::lib_func::{
+    LibFuncSignature, OutputVarInfo, SierraApChange, SignatureSpecializationContext,
+};
+use sierra::extensions::{
+    NamedType, NoGenericArgsGenericLibFunc, OutputVarReferenceInfo, SpecializationError,
+};
+use sierra::ids::GenericLibFuncId;
+use sierra::{define_libfunc_hierarchy, ProgramParser};
+
+use super::{calc_metadata, CompilableLibFunc};
+use crate::compiler::{compile, compile_with_libfuncs, CompilationError};
+use crate::invocations::{
+    unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError,
+};
+use crate::test_utils::strip_comments_and_linebreaks;
+
+/// LibFunc defined outside of the core libfuncs, doubling its felt argument into a new temporary
+/// variable.
+#[derive(Default)]
+pub struct ToyDoubleLibFunc {}
+impl NoGenericArgsGenericLibFunc for ToyDoubleLibFunc {
+    const ID: GenericLibFuncId = GenericLibFuncId::new_inline("toy_double");
+
+    fn specialize_signature(
+        &self,
+        context: &dyn SignatureSpecializationContext,
+    ) -> Result<LibFuncSignature, SpecializationError> {
+        let felt_ty = context.get_concrete_type(FeltType::id(), &[])?;
+        Ok(LibFuncSignature::new_non_branch(
+            vec![felt_ty.clone()],
+            vec![OutputVarInfo {
+                ty: felt_ty,
+                ref_info: OutputVarReferenceInfo::NewTempVar { idx: Some(0) },
+            }],
+            SierraApChange::Known { new_vars_only: true },
+        ))
+    }
+}
+
+define_libfunc_hierarchy! {
+    pub enum ExtendedLibFunc {
+        Core(CoreLibFunc),
+        ToyDouble(ToyDoubleLibFunc),
+    }, ExtendedConcreteLibFunc
+}
+
+impl CompilableLibFunc for ExtendedLibFunc {
+    fn as_core(libfunc: &Self::Concrete) -> Option<&CoreConcreteLibFunc> {
+        match libfunc {
+            ExtendedConcreteLibFunc::Core(libfunc) => Some(libfunc),
+            ExtendedConcreteLibFunc::ToyDouble(_) => None,
+        }
+    }
+
+    fn ap_changes(_libfunc: &Self::Concrete) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn costs(_libfunc: &Self::Concrete) -> Vec<i32> {
+        vec![1]
+    }
+
+    fn compile(
+        _libfunc: &Self::Concrete,
+        builder: CompiledInvocationBuilder<'_>,
+    ) -> Result<CompiledInvocation, InvocationError> {
+        let [value] = unpack_refs::<1>(&builder)?;
+        let value = value.expression.try_unpack_single()?.to_deref()?;
+        let mut casm_builder = CasmBuilder::default();
+        let value = casm_builder.add_var(ResOperand::Deref(value));
+        casm_build_extend! {casm_builder,
+            tempvar doubled;
+            assert doubled = value + value;
+        };
+        builder.build_from_casm_builder(casm_builder, &[], [vec![vec![doubled]]])
+    }
+}
+
+#[test]
+fn compile_extended_libfuncs() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+
+            libfunc felt_add = felt_add;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc toy_double = toy_double;
+
+            felt_add([1], [2]) -> ([3]);
+            store_temp_felt([3]) -> ([3]);
+            toy_double([3]) -> ([4]);
+            return([4]);
+
+            test_program@0([1]: felt, [2]: felt) -> (felt);
+        "})
+        .unwrap();
+    let metadata = calc_metadata::<ExtendedLibFunc>(&program, false).unwrap();
+    assert_eq!(metadata.ap_change_info.function_ap_change.get(&"test_program".into()), Some(&2));
+    pretty_assertions::assert_eq!(
+        compile_with_libfuncs::<ExtendedLibFunc>(&program, &metadata, false, false)
+            .expect("Compilation failed.")
+            .to_string(),
+        strip_comments_and_linebreaks(indoc! {"
+            [ap + 0] = [fp + -4] + [fp + -3], ap++;
+            [ap + 0] = [ap + -1] + [ap + -1], ap++;
+            ret;
+        "})
+    );
+    // The core libfuncs do not know the libfunc.
+    assert!(matches!(
+        compile(&program, &metadata, false, false),
+        Err(CompilationError::ProgramRegistryError(_))
+    ));
+}
@@ -1,19 +1,27 @@
-use casm::builder::{CasmBuildResult, CasmBuilder};
+use casm::builder::{CasmBuildResult, CasmBuilder, Var};
+use casm::cost::InstructionsCost;
+use casm::hints::Hint;
 use casm::operand::{ap_cell_ref, BinOpOperand, CellRef, DerefOrImmediate, ResOperand};
 use casm::{casm, casm_build_extend, casm_extend};
-use sierra::extensions::array::ArrayConcreteLibFunc;
+use num_bigint::BigInt;
+use sierra::extensions::array::{ArrayConcreteLibFunc, ARRAY_CLONE_CELL_STEPS};
 use sierra::extensions::felt::FeltBinaryOperator;
-use sierra::extensions::ConcreteLibFunc;
 use sierra::ids::ConcreteTypeId;
-use sierra_ap_change::core_libfunc_ap_change;
 use utils::try_extract_matches;
 
 use super::{
-    CompiledInvocation, CompiledInvocationBuilder, InvocationError, ReferenceExpressionView,
+    unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError,
+    ReferenceExpressionView,
 };
+use crate::invocations::gas::STEP_COST;
 use crate::invocations::{get_non_fallthrough_statement_id, ProgramInfo};
 use crate::references::{BinOpExpression, CellExpression, ReferenceExpression, ReferenceValue};
 use crate::relocations::{Relocation, RelocationEntry};
+use crate::runtime_routines::{add_routine_call, add_routine_inline, AddRoutine, RuntimeRoutine};
+
+#[cfg(test)]
+#[path = "array_test.rs"]
+mod test;
 
 /// Builds instructions for Sierra array operations.
 pub fn build(
@@ -22,9 +30,14 @@ pub fn build(
 ) -> Result<CompiledInvocation, InvocationError> {
     match libfunc {
         ArrayConcreteLibFunc::New(_) => build_array_new(builder),
+        ArrayConcreteLibFunc::NewWithCapacity(_) => build_array_new_with_capacity(builder),
+        ArrayConcreteLibFunc::PanicArrayNew(_) => build_panic_array_new(builder),
         ArrayConcreteLibFunc::Append(_) => build_array_append(builder),
-        ArrayConcreteLibFunc::At(libfunc) => build_array_at(&libfunc.ty, builder),
+        ArrayConcreteLibFunc::At(libfunc) => build_array_access(&libfunc.ty, false, builder),
+        ArrayConcreteLibFunc::Get(libfunc) => build_array_access(&libfunc.ty, true, builder),
         ArrayConcreteLibFunc::Len(libfunc) => build_array_len(&libfunc.ty, builder),
+        ArrayConcreteLibFunc::LenChecked(libfunc) => build_array_len_checked(&libfunc.ty, builder),
+        ArrayConcreteLibFunc::Clone(_) => build_array_clone(builder),
     }
 }
 
@@ -32,14 +45,9 @@ pub fn build(
 fn build_array_new(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    if !builder.refs.is_empty() {
-        return Err(InvocationError::WrongNumberOfArguments {
-            expected: 0,
-            actual: builder.refs.len(),
-        });
-    }
+    let [] = unpack_refs::<0>(&builder)?;
 
-    Ok(builder.build(
+    builder.build(
         casm! {
             %{ memory[ap + 0] = segments.add() %}
             ap += 1;
@@ -54,31 +62,95 @@ fn build_array_new(
         }]
         .into_iter()]
         .into_iter(),
-    ))
+    )
+}
+
+/// Handles a Sierra statement for creating the panic data array holding a single felt, by a call
+/// to the shared panic array routine.
+fn build_panic_array_new(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let [ReferenceValue { expression: expr_panic_data, .. }] = unpack_refs::<1>(&builder)?;
+    let panic_data = match expr_panic_data.try_unpack_single()? {
+        CellExpression::Deref(cell) => ResOperand::Deref(cell),
+        CellExpression::Immediate(value) => ResOperand::Immediate(value),
+        _ => return Err(InvocationError::InvalidReferenceExpressionForArgument),
+    };
+
+    // The routine is either called, sharing its code with the other calls, or inlined, saving the
+    // steps of the call and the return.
+    let lowerings: [AddRoutine; 2] = [add_routine_call, add_routine_inline];
+    let add_lowering = |add_routine: AddRoutine| {
+        let mut casm_builder = CasmBuilder::default();
+        let panic_data = casm_builder.add_var(panic_data.clone());
+        let outputs = add_routine(&mut casm_builder, RuntimeRoutine::PanicArray, &[panic_data]);
+        (casm_builder, outputs)
+    };
+    let [call_cost, inline_cost] = lowerings.map(|add_routine| {
+        InstructionsCost::of_code(&add_lowering(add_routine).0.build().instructions)
+    });
+    let costs = [RuntimeRoutine::PanicArray.call_cost(call_cost), inline_cost];
+    let (casm_builder, outputs) = add_lowering(lowerings[builder.choose_lowering(&costs)]);
+    builder.build_from_casm_builder(casm_builder, &[], [vec![outputs]])
+}
+
+/// Handles a Sierra statement for creating a new array, with a capacity hint for its segment.
+fn build_array_new_with_capacity(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let [ReferenceValue { expression: expr_capacity, .. }] = unpack_refs::<1>(&builder)?;
+    let capacity = match expr_capacity.try_unpack_single()? {
+        CellExpression::Deref(cell) => ResOperand::Deref(cell),
+        CellExpression::Immediate(value) => ResOperand::Immediate(value),
+        _ => return Err(InvocationError::InvalidReferenceExpressionForArgument),
+    };
+    // The capacity is given in elements, and the segment holds their cells.
+    let elem_size = builder.type_size(builder.libfunc_generic_type_arg(0)?)? as u16;
+
+    let mut casm_builder = CasmBuilder::default();
+    let capacity = casm_builder.add_var(capacity);
+    casm_build_extend! {casm_builder,
+        tempvar arr_start;
+    };
+    casm_builder.add_hint(
+        |[capacity], [dst]| Hint::AllocSegmentWithCapacity { capacity, elem_size, dst },
+        [capacity],
+        [arr_start],
+    );
+    casm_build_extend! {casm_builder,
+        ap += 1;
+    };
+    let CasmBuildResult { instructions, fallthrough_state, .. } = casm_builder.build();
+    builder.check_ap_changes([fallthrough_state.full_ap_change()])?;
+    let arr_start = fallthrough_state.get_adjusted_as_cell_ref(arr_start);
+    builder.build(
+        instructions,
+        vec![],
+        [[ReferenceExpression {
+            cells: vec![CellExpression::Deref(arr_start), CellExpression::Deref(arr_start)],
+        }]
+        .into_iter()]
+        .into_iter(),
+    )
 }
 
 /// Handles a Sierra statement for appending an element to an array.
 fn build_array_append(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (mut array_view, elem) = match builder.refs {
-        [
-            ReferenceValue { expression: expr_arr, .. },
-            ReferenceValue { expression: expr_elem, .. },
-        ] => {
-            let concrete_array_type = &builder.libfunc.param_signatures()[0].ty;
-            let array_view =
-                ArrayView::try_get_view(expr_arr, &builder.program_info, concrete_array_type)
-                    .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
-            (array_view, expr_elem)
-        }
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 2,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [_, ReferenceValue { expression: elem, .. }] = unpack_refs::<2>(&builder)?;
+    let mut array_view = ArrayView::from_arg(&builder, 0)?;
+    let element_size = builder.type_size(builder.libfunc_generic_type_arg(0)?)?;
+    if elem.cells.len() != element_size {
+        return Err(InvocationError::InvalidReferenceExpressionForArgument);
+    }
+    // The end is advanced only in the returned reference, so consecutive appends write at
+    // increasing offsets from the same stored end, and the end is materialized only when a later
+    // statement (e.g. `store_temp`) requires it.
+    array_view
+        .end_offset
+        .checked_add(element_size as i16)
+        .ok_or(InvocationError::InvalidReferenceExpressionForArgument)?;
     let mut ctx = casm! {};
     for expr in &elem.cells {
         let cell = try_extract_matches!(expr, CellExpression::Deref)
@@ -87,39 +159,31 @@ fn build_array_append(
         array_view.end_offset += 1;
     }
     let output_expressions = [vec![array_view.to_reference_expression()].into_iter()].into_iter();
-    Ok(builder.build(ctx.instructions, vec![], output_expressions))
+    builder.build(ctx.instructions, vec![], output_expressions)
 }
 
 /// Handles a Sierra statement for fetching an array element at a specific index.
-fn build_array_at(
+/// If `boxed` is set, the element is returned as a box - a pointer to the element - and the
+/// computed values are the same for all the element sizes, so that the ap change does not depend on
+/// the element type.
+fn build_array_access(
     elem_ty: &ConcreteTypeId,
+    boxed: bool,
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (range_check, array_view, index) = match builder.refs {
-        [
-            ReferenceValue { expression: expr_range_check, .. },
-            ReferenceValue { expression: expr_arr, .. },
-            ReferenceValue { expression: expr_value, .. },
-        ] => {
-            let concrete_array_type = &builder.libfunc.param_signatures()[1].ty;
-            let array_view =
-                ArrayView::try_get_view(expr_arr, &builder.program_info, concrete_array_type)
-                    .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
-            let elem_value = match expr_value.try_unpack_single()? {
-                CellExpression::Deref(op) => DerefOrImmediate::Deref(op),
-                CellExpression::Immediate(op) => DerefOrImmediate::from(op),
-                _ => return Err(InvocationError::InvalidReferenceExpressionForArgument),
-            };
-            (expr_range_check.try_unpack_single()?.to_deref()?, array_view, elem_value)
-        }
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 3,
-                actual: refs.len(),
-            });
-        }
+    let [
+        ReferenceValue { expression: expr_range_check, .. },
+        _,
+        ReferenceValue { expression: expr_value, .. },
+    ] = unpack_refs::<3>(&builder)?;
+    let array_view = ArrayView::from_arg(&builder, 1)?;
+    let index = match expr_value.try_unpack_single()? {
+        CellExpression::Deref(op) => DerefOrImmediate::Deref(op),
+        CellExpression::Immediate(op) => DerefOrImmediate::from(op),
+        _ => return Err(InvocationError::InvalidReferenceExpressionForArgument),
     };
-    let element_size = builder.program_info.type_sizes[elem_ty];
+    let range_check = expr_range_check.try_unpack_single()?.to_deref()?;
+    let element_size = builder.type_size(elem_ty)?;
 
     if array_view.end_offset != 0 {
         // TODO(Gil): handle when DoubleDeref will support a BinOp variant, e.g. [[ap+1]+1]
@@ -141,7 +205,7 @@ fn build_array_at(
         // Compute the length of the array (in felts).
         assert array_end = array_cell_size + array_start;
     };
-    let element_offset = if element_size == 1 {
+    let element_offset = if element_size == 1 && !boxed {
         index
     } else {
         casm_build_extend! {casm_builder,
@@ -162,7 +226,7 @@ fn build_array_at(
         tempvar offset_length_diff;
         assert element_offset = offset_length_diff + array_cell_size;
     };
-    let array_length = if element_size == 1 {
+    let array_length = if element_size == 1 && !boxed {
         array_cell_size
     } else {
         casm_build_extend! {casm_builder,
@@ -191,18 +255,17 @@ fn build_array_at(
         tempvar target_cell;
         assert target_cell = array_start + element_offset;
     };
-    let CasmBuildResult { instructions, awaiting_relocations, label_state, fallthrough_state } =
-        casm_builder.build();
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change, label_state["FailureHandle"].ap_change]
-            .map(sierra_ap_change::ApChange::Known)
-    );
-    let [relocation_index] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
+    let CasmBuildResult {
+        instructions, awaiting_relocations, label_state, fallthrough_state, ..
+    } = casm_builder.build();
+    builder.check_ap_changes([
+        fallthrough_state.full_ap_change(),
+        label_state["FailureHandle"].full_ap_change(),
+    ])?;
+    let [(relocation_index, _)] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
     let relocations = vec![RelocationEntry {
         instruction_idx: *relocation_index,
-        relocation: Relocation::RelativeStatementId(get_non_fallthrough_statement_id(&builder)),
+        relocation: Relocation::RelativeStatementId(get_non_fallthrough_statement_id(&builder)?),
     }];
     let output_expressions = [
         vec![
@@ -215,15 +278,16 @@ fn build_array_at(
                     CellExpression::Deref(fallthrough_state.get_adjusted_as_cell_ref(array_end)),
                 ],
             },
-            ReferenceExpression {
-                cells: (0..element_size)
-                    .map(|i| {
-                        CellExpression::DoubleDeref(
-                            fallthrough_state.get_adjusted_as_cell_ref(target_cell),
-                            i,
-                        )
-                    })
-                    .collect(),
+            if boxed {
+                ReferenceExpression::from_cell(CellExpression::Deref(
+                    fallthrough_state.get_adjusted_as_cell_ref(target_cell),
+                ))
+            } else {
+                ReferenceExpression::from_double_deref(
+                    fallthrough_state.get_adjusted_as_cell_ref(target_cell),
+                    0,
+                    element_size,
+                )?
             },
         ]
         .into_iter(),
@@ -245,7 +309,7 @@ fn build_array_at(
         .into_iter(),
     ]
     .into_iter();
-    Ok(builder.build(instructions, relocations, output_expressions))
+    builder.build(instructions, relocations, output_expressions)
 }
 
 /// Handles a Sierra statement for getting the length of an array.
@@ -253,25 +317,14 @@ fn build_array_len(
     elem_ty: &ConcreteTypeId,
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let array_view = match builder.refs {
-        [ReferenceValue { expression: expr_arr, .. }] => {
-            let concrete_array_type = &builder.libfunc.param_signatures()[0].ty;
-            ArrayView::try_get_view(expr_arr, &builder.program_info, concrete_array_type)
-                .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?
-        }
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
+    unpack_refs::<1>(&builder)?;
+    let array_view = ArrayView::from_arg(&builder, 0)?;
     if array_view.end_offset != 0 {
         // The array must be stored before calling to array_len, as it is not possible to return
         // [end]-[start]+offset as a CellRef.
         return Err(InvocationError::InvalidReferenceExpressionForArgument);
     }
-    let element_size = builder.program_info.type_sizes[elem_ty];
+    let element_size = builder.type_size(elem_ty)?;
     if element_size == 1 {
         let len_ref_expr = ReferenceExpression::from_cell(CellExpression::BinOp(BinOpExpression {
             op: FeltBinaryOperator::Sub,
@@ -279,7 +332,7 @@ fn build_array_len(
             b: DerefOrImmediate::Deref(array_view.start),
         }));
         let output_expressions = [array_view.to_reference_expression(), len_ref_expr].into_iter();
-        return Ok(builder.build_only_reference_changes(output_expressions));
+        return builder.build_only_reference_changes(output_expressions);
     }
     let mut casm_builder = CasmBuilder::default();
     let start = casm_builder.add_var(ResOperand::Deref(array_view.start));
@@ -292,12 +345,56 @@ fn build_array_len(
         assert end_total_offset = length * element_size;
     };
     let CasmBuildResult { instructions, fallthrough_state, .. } = casm_builder.build();
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change].map(sierra_ap_change::ApChange::Known)
-    );
+    builder.check_ap_changes([fallthrough_state.full_ap_change()])?;
+    let output_expressions = [vec![
+        ReferenceExpression {
+            cells: vec![
+                CellExpression::Deref(fallthrough_state.get_adjusted_as_cell_ref(start)),
+                CellExpression::Deref(fallthrough_state.get_adjusted_as_cell_ref(end)),
+            ],
+        },
+        ReferenceExpression::from_cell(CellExpression::Deref(
+            fallthrough_state.get_adjusted_as_cell_ref(length),
+        )),
+    ]
+    .into_iter()]
+    .into_iter();
+    builder.build(instructions, vec![], output_expressions)
+}
+
+/// Handles a Sierra statement for getting the length of an array, range checking it.
+fn build_array_len_checked(
+    elem_ty: &ConcreteTypeId,
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let [ReferenceValue { expression: expr_range_check, .. }, _] = unpack_refs::<2>(&builder)?;
+    let (range_check, array_view) =
+        (expr_range_check.try_unpack_single()?.to_buffer(1)?, ArrayView::from_arg(&builder, 1)?);
+    if array_view.end_offset != 0 {
+        // The array must be stored before calling to array_len_checked, as it is not possible to
+        // return [end]-[start]+offset as a CellRef.
+        return Err(InvocationError::InvalidReferenceExpressionForArgument);
+    }
+    let element_size = builder.type_size(elem_ty)?;
+    let mut casm_builder = CasmBuilder::default();
+    let range_check = casm_builder.add_var(range_check);
+    let start = casm_builder.add_var(ResOperand::Deref(array_view.start));
+    let end = casm_builder.add_var(ResOperand::Deref(array_view.end));
+    let element_size = casm_builder.add_var(ResOperand::Immediate(element_size.into()));
+    // If the end is before the start, the length wraps around the prime and fails the range check.
+    casm_build_extend! {casm_builder,
+        tempvar end_total_offset;
+        assert end = start + end_total_offset;
+        tempvar length;
+        assert end_total_offset = length * element_size;
+        assert *(range_check++) = length;
+    };
+    let CasmBuildResult { instructions, fallthrough_state, .. } = casm_builder.build();
+    builder.check_ap_changes([fallthrough_state.full_ap_change()])?;
     let output_expressions = [vec![
+        ReferenceExpression::from_cell(CellExpression::from_res_operand(
+            fallthrough_state.get_adjusted(range_check),
+        )),
         ReferenceExpression {
             cells: vec![
                 CellExpression::Deref(fallthrough_state.get_adjusted_as_cell_ref(start)),
@@ -310,7 +407,78 @@ fn build_array_len(
     ]
     .into_iter()]
     .into_iter();
-    Ok(builder.build(instructions, vec![], output_expressions))
+    builder.build(instructions, vec![], output_expressions)
+}
+
+/// Handles a Sierra statement for cloning an array.
+/// The copy loop is charged per cell from the gas counter before it runs, branching to the failure
+/// branch if the gas counter can not cover it.
+fn build_array_clone(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let [
+        ReferenceValue { expression: expr_range_check, .. },
+        ReferenceValue { expression: expr_gas_counter, .. },
+        _,
+    ] = unpack_refs::<3>(&builder)?;
+    let (range_check, gas_counter, array_view) = (
+        expr_range_check.try_unpack_single()?.to_buffer(1)?,
+        expr_gas_counter.try_unpack_single()?.to_deref()?,
+        ArrayView::from_arg(&builder, 2)?,
+    );
+    if array_view.end_offset != 0 {
+        // The array must be stored before calling to array_clone, as the copy loop requires the
+        // end as a CellRef.
+        return Err(InvocationError::InvalidReferenceExpressionForArgument);
+    }
+    let mut casm_builder = CasmBuilder::default();
+    let range_check = casm_builder.add_var(range_check);
+    let gas_counter = casm_builder.add_var(ResOperand::Deref(gas_counter));
+    let start = casm_builder.add_var(ResOperand::Deref(array_view.start));
+    let end = casm_builder.add_var(ResOperand::Deref(array_view.end));
+    // The cells of the array are copied regardless of the element size, and the original array is
+    // carried through the copy loop, so it remains accessible after it.
+    casm_build_extend! {casm_builder,
+        const cell_gas = BigInt::from(ARRAY_CLONE_CELL_STEPS * STEP_COST);
+        const u128_limit = BigInt::from(u128::MAX) + 1;
+        tempvar cells;
+        assert end = start + cells;
+        tempvar charge;
+        assert charge = cells * cell_gas;
+        tempvar has_enough_gas;
+        hint TestLessThanOrEqual {lhs: charge, rhs: gas_counter} into {dst: has_enough_gas};
+        jump HasEnoughGas if has_enough_gas != 0;
+        // Proving the gas counter is below the charge, by range checking their difference shifted
+        // by 2**128.
+        tempvar gas_diff;
+        assert gas_counter = gas_diff + charge;
+        tempvar shifted_gas_diff;
+        assert shifted_gas_diff = gas_diff + u128_limit;
+        assert *(range_check++) = shifted_gas_diff;
+        jump Failure;
+        HasEnoughGas:
+        tempvar updated_gas;
+        assert gas_counter = updated_gas + charge;
+        assert *(range_check++) = updated_gas;
+        tempvar copy_start;
+        hint AllocSegment {} into {dst: copy_start};
+        ap += 1;
+        let copy_end = copy_range(start, end, copy_start)
+            carrying (range_check, updated_gas, start, end, copy_start);
+    };
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
+        [
+            vec![
+                vec![range_check],
+                vec![updated_gas],
+                vec![start, end],
+                vec![copy_start, copy_end],
+            ],
+            vec![vec![range_check], vec![gas_counter], vec![start, end]],
+        ],
+    )
 }
 
 /// A struct representing an actual array value in the Sierra program.
@@ -328,8 +496,28 @@ pub struct ArrayView {
     pub end_offset: i16,
 }
 impl ArrayView {
+    /// Returns the view of the array passed as argument #`param_idx` of the invocation.
+    pub fn from_arg(
+        builder: &CompiledInvocationBuilder<'_>,
+        param_idx: usize,
+    ) -> Result<Self, InvocationError> {
+        Self::from_cells(builder.try_unpack_arg(param_idx)?)
+    }
+
+    /// Returns the view of the array represented by the cells `[start, end]`.
+    fn from_cells([start, end]: [&CellExpression; 2]) -> Result<Self, InvocationError> {
+        let start = start.to_deref()?;
+        let (end, end_offset) = end.to_deref_with_offset()?;
+        if end_offset < 0 {
+            // The end of an array is never before its start, so a negative offset means the
+            // reference is corrupted.
+            return Err(InvocationError::InvalidReferenceExpressionForArgument);
+        }
+        Ok(ArrayView { start, end, end_offset })
+    }
+
     /// Returns the end as a `ResOperand`.
-    fn end_operand(&self) -> ResOperand {
+    pub fn end_operand(&self) -> ResOperand {
         if self.end_offset == 0 {
             ResOperand::Deref(self.end)
         } else {
@@ -340,6 +528,36 @@ impl ArrayView {
             })
         }
     }
+
+    /// Returns the number of cells of the array, if it is known statically - which is the case
+    /// when the start and the stored end are the same cell, so all the cells of the array were
+    /// appended since the last store.
+    pub fn known_cell_len(&self) -> Option<usize> {
+        (self.start == self.end).then_some(self.end_offset as usize)
+    }
+
+    /// Returns the number of elements of the array, if it is known statically.
+    pub fn known_len(&self, element_size: usize) -> Option<usize> {
+        Some(self.known_cell_len()? / element_size)
+    }
+
+    /// Adds a var holding the number of cells of the array to `casm_builder`.
+    /// The var is an immediate if the number is known statically, and otherwise is computed as
+    /// `[end] - [start]` into a new temporary variable, offset by the pending `end_offset`.
+    pub fn add_cell_len(&self, casm_builder: &mut CasmBuilder) -> Var {
+        if let Some(cell_len) = self.known_cell_len() {
+            return casm_builder.add_var(ResOperand::Immediate(cell_len.into()));
+        }
+        let start = casm_builder.add_var(ResOperand::Deref(self.start));
+        let end = casm_builder.add_var(ResOperand::Deref(self.end));
+        let end_offset = casm_builder.add_var(ResOperand::Immediate(self.end_offset.into()));
+        casm_build_extend! {casm_builder,
+            tempvar stored_cell_len;
+            assert end = start + stored_cell_len;
+            let cell_len = stored_cell_len + end_offset;
+        };
+        cell_len
+    }
 }
 
 impl ReferenceExpressionView for ArrayView {
@@ -350,12 +568,10 @@ impl ReferenceExpressionView for ArrayView {
         _program_info: &ProgramInfo<'_>,
         _concrete_type_id: &ConcreteTypeId,
     ) -> Result<Self, Self::Error> {
-        let [start, end] = &expr.cells[..] else {
-            return Err(InvocationError::InvalidReferenceExpressionForArgument);
-        };
-        let start = start.to_deref()?;
-        let (end, end_offset) = end.to_deref_with_offset()?;
-        Ok(ArrayView { start, end, end_offset })
+        let cells = expr
+            .try_unpack()
+            .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
+        Self::from_cells(cells)
     }
 
     fn to_reference_expression(self) -> ReferenceExpression {
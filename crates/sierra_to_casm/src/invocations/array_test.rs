@@ -0,0 +1,306 @@
+use casm::ap_change::ApChange;
+use casm::builder::{CasmBuildResult, CasmBuilder};
+use casm::hints::Hint;
+use casm::operand::{DerefOrImmediate, ResOperand};
+use casm::{casm, deref, res};
+use num_bigint::BigInt;
+use pretty_assertions::assert_eq;
+use sierra::program::StatementIdx;
+use test_case::test_case;
+use test_log::test;
+
+use super::ArrayView;
+use crate::invocations::test_utils::{
+    compile_libfunc, try_compile_libfunc, ReducedBranchChanges, ReducedCompiledInvocation,
+};
+use crate::invocations::InvocationError;
+use crate::ref_expr;
+use crate::relocations::{Relocation, RelocationEntry};
+use crate::runtime_routines::RuntimeRoutine;
+
+const TRIPLE_APPEND: &str = "array_append<Struct<ut@Triple, felt, felt, felt>>";
+const TEN_FELTS: &str =
+    "Struct<ut@Ten, felt, felt, felt, felt, felt, felt, felt, felt, felt, felt>";
+
+#[test]
+fn test_array_append_multi_cell() {
+    assert_eq!(
+        compile_libfunc(
+            TRIPLE_APPEND,
+            vec![ref_expr!([fp + 1], [fp + 2] + 3), ref_expr!([ap - 3], [ap - 2], [ap - 1])]
+        ),
+        ReducedCompiledInvocation {
+            instructions: casm! {
+                [ap - 3] = [[fp + 2] + 3];
+                [ap - 2] = [[fp + 2] + 4];
+                [ap - 1] = [[fp + 2] + 5];
+            }
+            .instructions,
+            relocations: vec![],
+            results: vec![ReducedBranchChanges {
+                refs: vec![ref_expr!([fp + 1], [fp + 2] + 6)],
+                ap_change: ApChange::Known(0)
+            }]
+        }
+    );
+}
+
+#[test]
+fn test_array_append_multi_cell_consecutive() {
+    let mut array = ref_expr!([fp + 1], [fp + 2]);
+    let mut instructions = vec![];
+    for _ in 0..3 {
+        let compiled =
+            compile_libfunc(TRIPLE_APPEND, vec![array, ref_expr!([ap - 3], [ap - 2], [ap - 1])]);
+        array = compiled.results[0].refs[0].clone();
+        instructions.extend(compiled.instructions);
+    }
+    // Only the element cells are written, the end pointer is not materialized.
+    assert_eq!(
+        instructions,
+        casm! {
+            [ap - 3] = [[fp + 2] + 0];
+            [ap - 2] = [[fp + 2] + 1];
+            [ap - 1] = [[fp + 2] + 2];
+            [ap - 3] = [[fp + 2] + 3];
+            [ap - 2] = [[fp + 2] + 4];
+            [ap - 1] = [[fp + 2] + 5];
+            [ap - 3] = [[fp + 2] + 6];
+            [ap - 2] = [[fp + 2] + 7];
+            [ap - 1] = [[fp + 2] + 8];
+        }
+        .instructions
+    );
+    assert_eq!(array, ref_expr!([fp + 1], [fp + 2] + 9));
+}
+
+#[test]
+fn test_array_new_with_capacity() {
+    let array_new = compile_libfunc("array_new<felt>", vec![]);
+    let array_new_with_capacity =
+        compile_libfunc("array_new_with_capacity<felt>", vec![ref_expr!([fp - 3])]);
+    // Only the hint differs from `array_new`, which carries the capacity to the allocator.
+    let mut expected_instructions = array_new.instructions.clone();
+    expected_instructions[0].hints = vec![Hint::AllocSegmentWithCapacity {
+        capacity: deref!([fp - 3]).into(),
+        elem_size: 1,
+        dst: deref!([ap + 0]),
+    }];
+    assert_eq!(
+        array_new_with_capacity,
+        ReducedCompiledInvocation {
+            instructions: expected_instructions,
+            relocations: vec![],
+            results: array_new.results,
+        }
+    );
+}
+
+#[test]
+fn test_array_new_with_capacity_multi_cell() {
+    let compiled =
+        compile_libfunc(&format!("array_new_with_capacity<{TEN_FELTS}>"), vec![ref_expr!(4)]);
+    assert_eq!(
+        compiled.instructions[0].hints,
+        vec![Hint::AllocSegmentWithCapacity {
+            capacity: DerefOrImmediate::from(4),
+            elem_size: 10,
+            dst: deref!([ap + 0]),
+        }]
+    );
+}
+
+#[test]
+fn test_panic_array_new() {
+    assert_eq!(
+        compile_libfunc("panic_array_new", vec![ref_expr!([fp - 3])]),
+        ReducedCompiledInvocation {
+            instructions: casm! {
+                [ap + 0] = [fp + -3], ap++;
+                call rel 0;
+            }
+            .instructions,
+            relocations: vec![RelocationEntry {
+                instruction_idx: 1,
+                relocation: Relocation::RelativeRoutine(RuntimeRoutine::PanicArray),
+            }],
+            results: vec![ReducedBranchChanges {
+                refs: vec![ref_expr!([ap - 2], [ap - 1])],
+                ap_change: ApChange::Known(5)
+            }]
+        }
+    );
+}
+
+#[test]
+fn test_array_get_boxes_element() {
+    let compiled = compile_libfunc(
+        &format!("array_get<{TEN_FELTS}>"),
+        vec![ref_expr!([fp - 5]), ref_expr!([fp - 4], [fp - 3]), ref_expr!([fp - 6])],
+    );
+    // The element is returned as a pointer to its first cell, computed last on the success branch.
+    assert_eq!(compiled.results[0].refs[2], ref_expr!([ap - 1]));
+    assert_eq!(
+        compiled.results.iter().map(|result| result.ap_change).collect::<Vec<_>>(),
+        [ApChange::Known(6), ApChange::Known(5)]
+    );
+    // The code does not depend on the element size, apart from the size immediate.
+    let single_cell_compiled = compile_libfunc(
+        "array_get<felt>",
+        vec![ref_expr!([fp - 5]), ref_expr!([fp - 4], [fp - 3]), ref_expr!([fp - 6])],
+    );
+    assert_eq!(compiled.instructions.len(), single_cell_compiled.instructions.len());
+}
+
+#[test]
+fn test_array_get_single_field_read() {
+    // Unboxing only creates references to the element cells within the array, so reading a single
+    // field of a boxed element costs no more than the access itself.
+    let unboxed = compile_libfunc(&format!("unbox<{TEN_FELTS}>"), vec![ref_expr!([ap - 1])]);
+    assert!(unboxed.instructions.is_empty());
+    assert_eq!(
+        unboxed.results[0].refs[0].cells[..],
+        ref_expr!(
+            [[ap - 1]],
+            [[ap - 1] + 1],
+            [[ap - 1] + 2],
+            [[ap - 1] + 3],
+            [[ap - 1] + 4],
+            [[ap - 1] + 5],
+            [[ap - 1] + 6],
+            [[ap - 1] + 7],
+            [[ap - 1] + 8],
+            [[ap - 1] + 9]
+        )
+        .cells[..]
+    );
+    let copying = compile_libfunc(
+        "array_at<felt>",
+        vec![ref_expr!([fp - 5]), ref_expr!([fp - 4], [fp - 3]), ref_expr!([fp - 6])],
+    );
+    let boxed = compile_libfunc(
+        "array_get<felt>",
+        vec![ref_expr!([fp - 5]), ref_expr!([fp - 4], [fp - 3]), ref_expr!([fp - 6])],
+    );
+    // Boxing only adds the size-independent offset and length computations.
+    assert_eq!(boxed.instructions.len(), copying.instructions.len() + 2);
+}
+
+#[test]
+fn test_array_len_checked() {
+    assert_eq!(
+        compile_libfunc(
+            "array_len_checked<felt>",
+            vec![ref_expr!([fp - 5]), ref_expr!([fp - 4], [fp - 3])]
+        ),
+        ReducedCompiledInvocation {
+            instructions: casm! {
+                [fp - 3] = [fp - 4] + [ap + 0], ap++;
+                [ap - 1] = [ap + 0], ap++;
+                [ap - 1] = [[fp - 5] + 0];
+            }
+            .instructions,
+            relocations: vec![],
+            results: vec![ReducedBranchChanges {
+                refs: vec![
+                    ref_expr!([fp - 5] + 1),
+                    ref_expr!([fp - 4], [fp - 3]),
+                    ref_expr!([ap - 1])
+                ],
+                ap_change: ApChange::Known(2)
+            }]
+        }
+    );
+}
+
+#[test]
+fn test_array_with_end_before_start() {
+    // An end offset can only be negative if the reference of the array is corrupted.
+    assert_eq!(
+        try_compile_libfunc(
+            "array_append<felt>",
+            vec![ref_expr!([fp - 4], [fp - 4] + (-1)), ref_expr!([fp - 3])]
+        ),
+        Err(InvocationError::InvalidReferenceExpressionForArgument)
+    );
+}
+
+#[test]
+fn test_array_with_wrong_number_of_cells() {
+    assert_eq!(
+        try_compile_libfunc("array_len<felt>", vec![ref_expr!([fp - 4])]),
+        Err(InvocationError::WrongArgumentSize { param_idx: 0, expected: 2, actual: 1 })
+    );
+}
+
+#[test]
+fn test_array_clone() {
+    let u128_limit: BigInt = BigInt::from(u128::MAX) + 1;
+    assert_eq!(
+        compile_libfunc(
+            "array_clone<felt>",
+            vec![ref_expr!([fp - 6]), ref_expr!([fp - 5]), ref_expr!([fp - 4], [fp - 3])]
+        ),
+        ReducedCompiledInvocation {
+            instructions: casm! {
+                [fp - 3] = [fp - 4] + [ap + 0], ap++;
+                [ap + 0] = [ap - 1] * 1100, ap++;
+                %{ memory[ap + 0] = memory[ap - 1] <= memory[fp - 5] %}
+                jmp rel 8 if [ap + 0] != 0, ap++;
+                [fp - 5] = [ap + 0] + [ap - 2], ap++;
+                [ap + 0] = [ap - 1] + u128_limit, ap++;
+                [ap - 1] = [[fp - 6] + 0];
+                jmp rel 0;
+                [fp - 5] = [ap + 0] + [ap - 2], ap++;
+                [ap - 1] = [[fp - 6] + 0];
+                %{ memory[ap + 0] = segments.add() %}
+                ap += 1;
+                [ap + 0] = [fp - 6] + 1, ap++;
+                [ap + 0] = [ap - 3], ap++;
+                [ap + 0] = [fp - 4], ap++;
+                [ap + 0] = [fp - 3], ap++;
+                [ap + 0] = [ap - 5], ap++;
+                [ap + 0] = [fp - 4], ap++;
+                [ap + 0] = [ap - 7], ap++;
+                [fp - 3] = [ap + 0] + [fp - 4], ap++;
+                jmp rel 4 if [ap - 1] != 0;
+                jmp rel 17;
+                [ap + 0] = [[ap - 3] + 0], ap++;
+                [ap - 1] = [[ap - 3] + 0];
+                [ap + 0] = [ap - 9], ap++;
+                [ap + 0] = [ap - 9], ap++;
+                [ap + 0] = [ap - 9], ap++;
+                [ap + 0] = [ap - 9], ap++;
+                [ap + 0] = [ap - 9], ap++;
+                [ap + 0] = [ap - 9] + 1, ap++;
+                [ap + 0] = [ap - 9] + 1, ap++;
+                [ap - 9] = [ap + 0] + 1, ap++;
+                jmp rel (-13) if [ap - 1] != 0;
+            }
+            .instructions,
+            relocations: vec![RelocationEntry {
+                instruction_idx: 6,
+                relocation: Relocation::RelativeStatementId(StatementIdx(1))
+            }],
+            results: vec![
+                ReducedBranchChanges {
+                    refs: vec![
+                        ref_expr!([ap - 8]),
+                        ref_expr!([ap - 7]),
+                        ref_expr!([ap - 6], [ap - 5]),
+                        ref_expr!([ap - 4], [ap - 2])
+                    ],
+                    ap_change: ApChange::Unknown
+                },
+                ReducedBranchChanges {
+                    refs: vec![
+                        ref_expr!([fp - 6] + 1),
+                        ref_expr!([fp - 5]),
+                        ref_expr!([fp - 4], [fp - 3])
+                    ],
+                    ap_change: ApChange::Known(5)
+                }
+            ]
+        }
+    );
+}
@@ -1,9 +1,8 @@
 use casm::builder::{CasmBuildResult, CasmBuilder};
 use casm::casm_build_extend;
 use casm::operand::ResOperand;
-use sierra_ap_change::core_libfunc_ap_change;
 
-use super::{CompiledInvocation, CompiledInvocationBuilder, InvocationError};
+use super::{unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
 use crate::references::{CellExpression, ReferenceExpression, ReferenceValue};
 
 #[cfg(test)]
@@ -21,30 +20,23 @@ pub fn build(
 fn build_bitwise(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (bitwise, x, y) = match builder.refs {
-        [
-            ReferenceValue { expression: expr_bitwise, .. },
-            ReferenceValue { expression: expr_x, .. },
-            ReferenceValue { expression: expr_y, .. },
-        ] => (
-            expr_bitwise.try_unpack_single()?.to_buffer(4)?,
-            expr_x.try_unpack_single()?.to_deref()?,
-            expr_y.try_unpack_single()?.to_deref()?,
-        ),
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 3,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [
+        ReferenceValue { expression: expr_bitwise, .. },
+        ReferenceValue { expression: expr_x, .. },
+        ReferenceValue { expression: expr_y, .. },
+    ] = unpack_refs::<3>(&builder)?;
+    let (bitwise, x, y) = (
+        expr_bitwise.try_unpack_single()?.to_buffer(4)?,
+        expr_x.try_unpack_single()?.to_deref()?,
+        expr_y.try_unpack_single()?.to_deref()?,
+    );
 
     let mut casm_builder = CasmBuilder::default();
     let x = casm_builder.add_var(ResOperand::Deref(x));
     let y = casm_builder.add_var(ResOperand::Deref(y));
     let bitwise = casm_builder.add_var(bitwise);
     casm_build_extend! {casm_builder,
-        let original_bitwise = bitwise;
+        let original_bitwise = snapshot bitwise;
         assert *(bitwise++) = x;
         assert *(bitwise++) = y;
         hint Bitwise { ptr: original_bitwise };
@@ -56,11 +48,7 @@ fn build_bitwise(
 
     let CasmBuildResult { instructions, fallthrough_state, .. } = casm_builder.build();
 
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change].map(sierra_ap_change::ApChange::Known)
-    );
+    builder.check_ap_changes([fallthrough_state.full_ap_change()])?;
 
     let output_expressions = [vec![
         ReferenceExpression::from_cell(CellExpression::from_res_operand(
@@ -78,5 +66,5 @@ fn build_bitwise(
     ]
     .into_iter()]
     .into_iter();
-    Ok(builder.build(instructions, vec![], output_expressions))
+    builder.build(instructions, vec![], output_expressions)
 }
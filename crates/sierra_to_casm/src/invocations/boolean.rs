@@ -3,7 +3,7 @@ use casm::operand::{ap_cell_ref, DerefOrImmediate};
 use sierra::extensions::boolean::BoolConcreteLibFunc;
 use sierra::extensions::felt::FeltBinaryOperator;
 
-use super::{CompiledInvocation, CompiledInvocationBuilder, InvocationError};
+use super::{unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
 use crate::references::{BinOpExpression, CellExpression, ReferenceExpression, ReferenceValue};
 
 /// Builds instructions for Sierra bool operations.
@@ -21,18 +21,13 @@ pub fn build(
 fn build_bool_and(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (a, b) = match builder.refs {
-        [ReferenceValue { expression: expr_a, .. }, ReferenceValue { expression: expr_b, .. }] => {
-            (expr_a.try_unpack_single()?.to_deref()?, expr_b.try_unpack_single()?.to_deref()?)
-        }
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 2,
-                actual: refs.len(),
-            });
-        }
-    };
-    Ok(builder.build(
+    let [
+        ReferenceValue { expression: expr_a, .. },
+        ReferenceValue { expression: expr_b, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    let a = expr_a.try_unpack_single()?.to_deref()?;
+    let b = expr_b.try_unpack_single()?.to_deref()?;
+    builder.build(
         vec![],
         vec![],
         [[ReferenceExpression::from_cell(CellExpression::BinOp(BinOpExpression {
@@ -42,26 +37,19 @@ fn build_bool_and(
         }))]
         .into_iter()]
         .into_iter(),
-    ))
+    )
 }
 
 /// Handles instructions for boolean NOT.
 fn build_bool_not(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let a = match builder.refs {
-        [ReferenceValue { expression, .. }] => expression.try_unpack_single()?.to_deref()?,
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [ReferenceValue { expression, .. }] = unpack_refs::<1>(&builder)?;
+    let a = expression.try_unpack_single()?.to_deref()?;
 
     // We want to output `1 - a`, but a SUB expression cannot have an immediate value on the LHS.
     // Store 1 in AP first, advance AP and return `[ap - 1] - a`.
-    Ok(builder.build(
+    builder.build(
         casm! { [ap + 0] = 1, ap++; }.instructions,
         vec![],
         [[ReferenceExpression::from_cell(CellExpression::BinOp(BinOpExpression {
@@ -71,5 +59,5 @@ fn build_bool_not(
         }))]
         .into_iter()]
         .into_iter(),
-    ))
+    )
 }
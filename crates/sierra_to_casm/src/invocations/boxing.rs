@@ -1,7 +1,8 @@
+use casm::operand::CellRef;
 use sierra::extensions::boxing::BoxConcreteLibFunc;
 use sierra::extensions::ConcreteLibFunc;
 
-use super::{CompiledInvocation, CompiledInvocationBuilder, InvocationError};
+use super::{unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
 use crate::references::{CellExpression, ReferenceExpression, ReferenceValue};
 
 /// Builds instructions for Sierra box operations.
@@ -16,54 +17,43 @@ pub fn build(
 }
 
 /// Handles instruction for creating a box.
+/// The cells of the boxed value must be consecutive in memory, and the box points to the first.
 fn build_into_box(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    if builder.program_info.type_sizes.get(&builder.libfunc.output_types()[0][0]) != Some(&1) {
-        todo!("Add support for taking non-single cell references.");
-    }
-    let expression = match builder.refs {
-        [ReferenceValue { expression, .. }] => expression,
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
+    let [ReferenceValue { expression, .. }] = unpack_refs::<1>(&builder)?;
+    let Some(CellExpression::Deref(first)) = expression.cells.first() else {
+        return Err(InvocationError::InvalidReferenceExpressionForArgument);
     };
-    if let CellExpression::Deref(operand) = expression
-        .try_unpack_single()
-        .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?
-    {
-        Ok(builder.build_only_reference_changes(
-            [ReferenceExpression::from_cell(CellExpression::IntoSingleCellRef(operand))]
-                .into_iter(),
-        ))
-    } else {
-        Err(InvocationError::InvalidReferenceExpressionForArgument)
+    let is_consecutive = expression.cells.iter().enumerate().all(|(i, cell)| {
+        *cell
+            == CellExpression::Deref(CellRef {
+                register: first.register,
+                offset: first.offset + i as i16,
+            })
+    });
+    if !is_consecutive {
+        return Err(InvocationError::InvalidReferenceExpressionForArgument);
     }
+    builder.build_only_reference_changes(
+        [ReferenceExpression::from_cell(CellExpression::IntoSingleCellRef(*first))].into_iter(),
+    )
 }
 
 /// Handles instruction for unboxing a box.
 fn build_unbox(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let expression = match builder.refs {
-        [ReferenceValue { expression, .. }] => expression,
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [ReferenceValue { expression, .. }] = unpack_refs::<1>(&builder)?;
+    let size = builder.type_size(&builder.libfunc.output_types()[0][0])?;
     if let CellExpression::Deref(operand) = expression
         .try_unpack_single()
         .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?
     {
-        Ok(builder.build_only_reference_changes(
-            [ReferenceExpression::from_cell(CellExpression::DoubleDeref(operand, 0))].into_iter(),
-        ))
+        // The cells of the boxed value are consecutive, starting at the address the box holds.
+        builder.build_only_reference_changes(
+            [ReferenceExpression::from_double_deref(operand, 0, size)?].into_iter(),
+        )
     } else {
         Err(InvocationError::InvalidReferenceExpressionForArgument)
     }
@@ -5,9 +5,8 @@ use num_bigint::BigInt;
 use sierra::extensions::builtin_cost::{
     BuiltinCostConcreteLibFunc, BuiltinCostGetGasLibFunc, CostTokenType,
 };
-use sierra_ap_change::core_libfunc_ap_change;
 
-use super::{CompiledInvocation, CompiledInvocationBuilder, InvocationError};
+use super::{unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
 use crate::invocations::gas::STEP_COST;
 use crate::invocations::get_non_fallthrough_statement_id;
 use crate::references::{CellExpression, ReferenceExpression, ReferenceValue};
@@ -19,34 +18,31 @@ pub fn build(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
     match libfunc {
-        BuiltinCostConcreteLibFunc::BuiltinGetGas(_) => build_builtin_get_gas(builder),
+        BuiltinCostConcreteLibFunc::BuiltinGetGas(_) => build_builtin_get_gas(builder, false),
+        BuiltinCostConcreteLibFunc::WithdrawGasAll(_) => build_builtin_get_gas(builder, true),
     }
 }
 
 /// Handles the get gas invocation.
+/// If `drain_counter` is true, the gas counter remaining after the withdrawal is returned as a
+/// felt, and the returned gas counter is 0.
 fn build_builtin_get_gas(
     builder: CompiledInvocationBuilder<'_>,
+    drain_counter: bool,
 ) -> Result<CompiledInvocation, InvocationError> {
     // TODO(lior): Share code with get_gas().
-    let (range_check, gas_counter, builtin_cost) = match builder.refs {
-        [
-            ReferenceValue { expression: range_check_expression, .. },
-            ReferenceValue { expression: gas_counter_expression, .. },
-            ReferenceValue { expression: builtin_cost_expression, .. },
-        ] => (
-            range_check_expression.try_unpack_single()?.to_buffer(1)?,
-            gas_counter_expression.try_unpack_single()?.to_deref()?,
-            builtin_cost_expression.try_unpack_single()?.to_deref()?,
-        ),
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 3,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [
+        ReferenceValue { expression: range_check_expression, .. },
+        ReferenceValue { expression: gas_counter_expression, .. },
+        ReferenceValue { expression: builtin_cost_expression, .. },
+    ] = unpack_refs::<3>(&builder)?;
+    let (range_check, gas_counter, builtin_cost) = (
+        range_check_expression.try_unpack_single()?.to_buffer(1)?,
+        gas_counter_expression.try_unpack_single()?.to_deref()?,
+        builtin_cost_expression.try_unpack_single()?.to_deref()?,
+    );
 
-    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder);
+    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder)?;
 
     let variable_values = &builder.program_info.metadata.gas_info.variable_values;
     if !CostTokenType::iter().all(|token| variable_values.contains_key(&(builder.idx, *token))) {
@@ -145,31 +141,31 @@ fn build_builtin_get_gas(
         assert *(range_check++) = updated_gas;
     };
 
-    let CasmBuildResult { instructions, awaiting_relocations, label_state, fallthrough_state } =
-        casm_builder.build();
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change, label_state["Failure"].ap_change]
-            .map(sierra_ap_change::ApChange::Known)
-    );
-    let [relocation_index] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
-    Ok(builder.build(
+    let CasmBuildResult {
+        instructions, awaiting_relocations, label_state, fallthrough_state, ..
+    } = casm_builder.build();
+    builder.check_ap_changes([
+        fallthrough_state.full_ap_change(),
+        label_state["Failure"].full_ap_change(),
+    ])?;
+    let [(relocation_index, _)] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
+    let mut success_outputs = vec![ReferenceExpression::from_cell(
+        CellExpression::from_res_operand(fallthrough_state.get_adjusted(range_check)),
+    )];
+    if drain_counter {
+        success_outputs.push(ReferenceExpression::from_cell(CellExpression::Immediate(0.into())));
+    }
+    success_outputs.push(ReferenceExpression::from_cell(CellExpression::Deref(
+        fallthrough_state.get_adjusted_as_cell_ref(updated_gas),
+    )));
+    builder.build(
         instructions,
         vec![RelocationEntry {
             instruction_idx: *relocation_index,
             relocation: Relocation::RelativeStatementId(failure_handle_statement_id),
         }],
         [
-            vec![
-                ReferenceExpression::from_cell(CellExpression::from_res_operand(
-                    fallthrough_state.get_adjusted(range_check),
-                )),
-                ReferenceExpression::from_cell(CellExpression::Deref(
-                    fallthrough_state.get_adjusted_as_cell_ref(updated_gas),
-                )),
-            ]
-            .into_iter(),
+            success_outputs.into_iter(),
             vec![
                 ReferenceExpression::from_cell(CellExpression::from_res_operand(
                     label_state["Failure"].get_adjusted(range_check),
@@ -181,5 +177,5 @@ fn build_builtin_get_gas(
             .into_iter(),
         ]
         .into_iter(),
-    ))
+    )
 }
@@ -0,0 +1,43 @@
+use casm::hints::Hint;
+use casm::instructions::{AddApInstruction, Instruction, InstructionBody};
+use casm::operand::ResOperand;
+use sierra::extensions::debug::DebugConcreteLibFunc;
+
+use super::array::ArrayView;
+use super::{unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
+
+#[cfg(test)]
+#[path = "debug_test.rs"]
+mod test;
+
+/// Builds instructions for Sierra debug operations.
+pub fn build(
+    libfunc: &DebugConcreteLibFunc,
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    match libfunc {
+        DebugConcreteLibFunc::Print(_) => build_print(builder),
+    }
+}
+
+/// Handles instruction for debug printing the content of an array.
+fn build_print(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    unpack_refs::<1>(&builder)?;
+    let array_view = ArrayView::from_arg(&builder, 0)?;
+
+    builder.build(
+        vec![Instruction {
+            // An instruction is required for holding the hint.
+            body: InstructionBody::AddAp(AddApInstruction { operand: ResOperand::from(0) }),
+            inc_ap: false,
+            hints: vec![Hint::DebugPrint {
+                start: ResOperand::Deref(array_view.start),
+                end: array_view.end_operand(),
+            }],
+        }],
+        vec![],
+        [[array_view.to_reference_expression()].into_iter()].into_iter(),
+    )
+}
@@ -0,0 +1,30 @@
+use casm::ap_change::ApChange;
+use casm::hints::Hint;
+use casm::{casm, casm_extend, res};
+use pretty_assertions::assert_eq;
+use test_log::test;
+
+use crate::invocations::test_utils::{
+    compile_libfunc, ReducedBranchChanges, ReducedCompiledInvocation,
+};
+use crate::ref_expr;
+
+#[test]
+fn test_debug_print() {
+    let mut expected_casm = casm! {};
+    expected_casm
+        .current_hints
+        .push(Hint::DebugPrint { start: res!([fp + 1]), end: res!([ap - 1] + 2) });
+    casm_extend!(expected_casm, ap += 0;);
+    assert_eq!(
+        compile_libfunc("debug_print", vec![ref_expr!([fp + 1], [ap - 1] + 2)]),
+        ReducedCompiledInvocation {
+            instructions: expected_casm.instructions,
+            relocations: vec![],
+            results: vec![ReducedBranchChanges {
+                refs: vec![ref_expr!([fp + 1], [ap - 1] + 2)],
+                ap_change: ApChange::Known(0)
+            }]
+        }
+    );
+}
@@ -14,7 +14,7 @@ use sierra::ids::ConcreteTypeId;
 use utils::try_extract_matches;
 
 use super::{
-    CompiledInvocation, CompiledInvocationBuilder, InvocationError, ProgramInfo,
+    unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError, ProgramInfo,
     ReferenceExpressionView,
 };
 use crate::references::{
@@ -38,19 +38,10 @@ pub fn build(
 fn build_dict_felt_to_new(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let default_value = match builder.refs {
-        [ReferenceValue { expression: expr_default_value, .. }] => {
-            expr_default_value.try_unpack_single()?.to_deref()?
-        }
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [ReferenceValue { expression: expr_default_value, .. }] = unpack_refs::<1>(&builder)?;
+    let default_value = expr_default_value.try_unpack_single()?.to_deref()?;
 
-    Ok(builder.build(
+    builder.build(
         vec![Instruction {
             body: InstructionBody::AddAp(AddApInstruction { operand: ResOperand::from(1) }),
             inc_ap: false,
@@ -68,32 +59,22 @@ fn build_dict_felt_to_new(
         }]
         .into_iter()]
         .into_iter(),
-    ))
+    )
 }
 
 /// Handles instruction for reading from a single cell dict.
 fn build_dict_felt_to_read(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (mut dict_view, mut key) = match builder.refs {
-        [
-            ReferenceValue { expression: expr_dict, .. },
-            ReferenceValue { expression: expr_key, .. },
-        ] => {
-            let concrete_dict_type = &builder.libfunc.param_signatures()[0].ty;
-            let dict_view =
-                DictFeltToView::try_get_view(expr_dict, &builder.program_info, concrete_dict_type)
-                    .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
-            let key = expr_key.try_unpack_single()?.to_deref()?;
-            (dict_view, key)
-        }
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 2,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [
+        ReferenceValue { expression: expr_dict, .. },
+        ReferenceValue { expression: expr_key, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    let concrete_dict_type = &builder.libfunc.param_signatures()[0].ty;
+    let mut dict_view =
+        DictFeltToView::try_get_view(expr_dict, &builder.program_info, concrete_dict_type)
+            .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
+    let mut key = expr_key.try_unpack_single()?.to_deref()?;
     let mut instructions = vec![Instruction {
         body: InstructionBody::AddAp(AddApInstruction { operand: ResOperand::from(1) }),
         inc_ap: false,
@@ -118,7 +99,7 @@ fn build_dict_felt_to_read(
     );
     dict_view.end_offset += DictFeltToAccess::size() as u16;
 
-    Ok(builder.build(
+    builder.build(
         instructions,
         vec![],
         [[
@@ -129,34 +110,24 @@ fn build_dict_felt_to_read(
         ]
         .into_iter()]
         .into_iter(),
-    ))
+    )
 }
 
 /// Handles instruction for writing to a single cell dict.
 fn build_dict_felt_to_write(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (mut dict_view, mut key, mut value) = match builder.refs {
-        [
-            ReferenceValue { expression: expr_dict, .. },
-            ReferenceValue { expression: expr_key, .. },
-            ReferenceValue { expression: expr_value, .. },
-        ] => {
-            let concrete_dict_type = &builder.libfunc.param_signatures()[0].ty;
-            let dict_view =
-                DictFeltToView::try_get_view(expr_dict, &builder.program_info, concrete_dict_type)
-                    .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
-            let key = expr_key.try_unpack_single()?.to_deref()?;
-            let value = expr_value.try_unpack_single()?.to_deref()?;
-            (dict_view, key, value)
-        }
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 3,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [
+        ReferenceValue { expression: expr_dict, .. },
+        ReferenceValue { expression: expr_key, .. },
+        ReferenceValue { expression: expr_value, .. },
+    ] = unpack_refs::<3>(&builder)?;
+    let concrete_dict_type = &builder.libfunc.param_signatures()[0].ty;
+    let mut dict_view =
+        DictFeltToView::try_get_view(expr_dict, &builder.program_info, concrete_dict_type)
+            .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
+    let mut key = expr_key.try_unpack_single()?.to_deref()?;
+    let mut value = expr_value.try_unpack_single()?.to_deref()?;
     let mut instructions = vec![Instruction {
         body: InstructionBody::AddAp(AddApInstruction { operand: ResOperand::from(1) }),
         inc_ap: false,
@@ -182,36 +153,26 @@ fn build_dict_felt_to_write(
         .get_instructions(&dict_view),
     );
     dict_view.end_offset += DictFeltToAccess::size() as u16;
-    Ok(builder.build(
+    builder.build(
         instructions,
         vec![],
         [[dict_view.to_reference_expression()].into_iter()].into_iter(),
-    ))
+    )
 }
 
 /// Handles the dict_squash instruction.
 fn build_dict_felt_to_squash(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (range_check, dict_view) = match builder.refs {
-        [
-            ReferenceValue { expression: expr_range_check, .. },
-            ReferenceValue { expression: expr_dict, .. },
-        ] => {
-            let concrete_dict_type = &builder.libfunc.param_signatures()[0].ty;
-            let dict_view =
-                DictFeltToView::try_get_view(expr_dict, &builder.program_info, concrete_dict_type)
-                    .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
-            let range_check = expr_range_check.try_unpack_single()?.to_deref()?;
-            (range_check, dict_view)
-        }
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [
+        ReferenceValue { expression: expr_range_check, .. },
+        ReferenceValue { expression: expr_dict, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    let concrete_dict_type = &builder.libfunc.param_signatures()[0].ty;
+    let dict_view =
+        DictFeltToView::try_get_view(expr_dict, &builder.program_info, concrete_dict_type)
+            .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
+    let range_check = expr_range_check.try_unpack_single()?.to_deref()?;
     let mut start_expr = dict_view.start;
     let mut end_expr = dict_view.end;
     let end_offset = dict_view.end_offset;
@@ -419,7 +380,7 @@ fn build_dict_felt_to_squash(
         call rel (-69);
         ret;
     );
-    Ok(builder.build(
+    builder.build(
         casm_ctx.instructions,
         vec![],
         [[
@@ -437,7 +398,7 @@ fn build_dict_felt_to_squash(
         ]
         .into_iter()]
         .into_iter(),
-    ))
+    )
 }
 
 /// A struct representing a dict in the Sierra program.
@@ -9,7 +9,8 @@ use sierra::program::{BranchInfo, BranchTarget, StatementIdx};
 use utils::try_extract_matches;
 
 use super::{
-    CompiledInvocation, CompiledInvocationBuilder, InvocationError, ReferenceExpressionView,
+    unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError,
+    ReferenceExpressionView,
 };
 use crate::invocations::ProgramInfo;
 use crate::references::{CellExpression, ReferenceExpression, ReferenceValue, ReferencesError};
@@ -51,15 +52,7 @@ fn build_enum_init(
     index: usize,
     num_variants: usize,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let expression = match builder.refs {
-        [ReferenceValue { expression, .. }] => expression,
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [ReferenceValue { expression, .. }] = unpack_refs::<1>(&builder)?;
     let init_arg_cells = &expression.cells;
     let variant_selector = if num_variants <= 2 {
         // For num_branches <= 2, we use the index as the variant_selector as the `match`
@@ -82,23 +75,17 @@ fn build_enum_init(
         2 * index + 1
     };
 
-    let variant_size = builder
-        .program_info
-        .type_sizes
-        .get(&builder.libfunc.param_signatures()[0].ty)
-        .ok_or(InvocationError::UnknownTypeData)?
-        .to_owned();
-    if init_arg_cells.len() != variant_size as usize {
+    let variant_size = builder.type_size(&builder.libfunc.param_signatures()[0].ty)?;
+    if init_arg_cells.len() != variant_size {
         return Err(InvocationError::InvalidReferenceExpressionForArgument);
     }
     // Pad the variant to match the size of the largest variant
     let concrete_enum_type = &builder.libfunc.output_types()[0][0];
-    let enum_size = get_enum_size(&builder.program_info, concrete_enum_type)
-        .ok_or(InvocationError::UnknownTypeData)?;
+    let enum_size = builder.type_size(concrete_enum_type)?;
     let num_padding = enum_size - 1 - variant_size;
     let inner_value = chain!(
         init_arg_cells.clone(),
-        repeat_n(CellExpression::Immediate(BigInt::from(0)), num_padding as usize)
+        repeat_n(CellExpression::Immediate(BigInt::from(0)), num_padding)
     )
     .collect();
 
@@ -107,7 +94,7 @@ fn build_enum_init(
         inner_value,
     };
     let output_expressions = [enum_val.to_reference_expression()].into_iter();
-    Ok(builder.build_only_reference_changes(output_expressions))
+    builder.build_only_reference_changes(output_expressions)
 }
 
 /// Handles statement for matching an enum.
@@ -115,39 +102,31 @@ fn build_enum_match(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
     let concrete_enum_type = &builder.libfunc.param_signatures()[0].ty;
-    let matched_var = match builder.refs {
-        [ReferenceValue { expression, .. }] => {
-            EnumView::try_get_view(expression, &builder.program_info, concrete_enum_type)
-                .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?
-        }
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [ReferenceValue { expression, .. }] = unpack_refs::<1>(&builder)?;
+    let matched_var = EnumView::try_get_view(expression, &builder.program_info, concrete_enum_type)
+        .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
     // Verify variant_selector is of type deref. This is the case with an enum_value
     // that was validly created and then stored.
     let variant_selector =
         try_extract_matches!(matched_var.variant_selector, CellExpression::Deref)
             .ok_or(InvocationError::InvalidReferenceExpressionForArgument)?;
 
-    let target_statement_ids = builder.invocation.branches.iter().map(|b| match b {
-        BranchInfo { target: BranchTarget::Statement(stmnt_id), .. } => *stmnt_id,
-        _ => panic!("malformed invocation"),
-    });
+    let target_statement_ids = builder
+        .invocation
+        .branches
+        .iter()
+        .map(|b| match b {
+            BranchInfo { target: BranchTarget::Statement(stmnt_id), .. } => Ok(*stmnt_id),
+            _ => Err(InvocationError::MalformedBranches),
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter();
 
     let mut branch_output_sizes: Vec<usize> = Vec::new();
     for branch_outputs in &builder.libfunc.output_types() {
         // Each branch has a single output.
         let branch_output = &branch_outputs[0];
-        let branch_output_size = builder
-            .program_info
-            .type_sizes
-            .get(branch_output)
-            .ok_or(InvocationError::UnknownTypeData)?;
-        branch_output_sizes.push(*branch_output_size as usize);
+        branch_output_sizes.push(builder.type_size(branch_output)?);
     }
     let output_expressions = branch_output_sizes.into_iter().map(|size| {
         // The size of an output must be smaller than the size of `matched_var.inner_value` as the
@@ -228,7 +207,7 @@ fn build_enum_match_short(
         relocation: Relocation::RelativeStatementId(first_target_statement),
     });
 
-    Ok(builder.build(instructions, relocations, output_expressions))
+    builder.build(instructions, relocations, output_expressions)
 }
 
 /// Handles statement for matching an enum with 3+ variants.
@@ -255,6 +234,10 @@ fn build_enum_match_short(
 /// Where in the first location of the enum_var there will be the jmp_table_idx (1 for the first
 /// branch, 2 for the second and so on).
 ///
+/// A jump table is never more costly than a chain of conditional jumps over the variants, in both
+/// size and steps, so unlike lowerings with a real trade-off it does not consult the cost weights
+/// of the program.
+///
 /// Assumes that self.invocation.branches.len() == target_statement_ids.len()
 /// == output_expressions.len() and that self.invocation.branches.len() > 2.
 fn build_enum_match_long(
@@ -278,7 +261,7 @@ fn build_enum_match_long(
         });
     }
 
-    Ok(builder.build(ctx.instructions, relocations, output_expressions))
+    builder.build(ctx.instructions, relocations, output_expressions)
 }
 
 /// A struct representing an actual enum value in the Sierra program.
@@ -7,7 +7,7 @@ use sierra::extensions::felt::{
 };
 
 use super::misc::build_jump_nz;
-use super::{CompiledInvocation, CompiledInvocationBuilder, InvocationError};
+use super::{unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
 use crate::references::{
     BinOpExpression, CellExpression, ReferenceExpression, ReferenceValue, UnaryOpExpression,
 };
@@ -32,10 +32,10 @@ pub fn build(
             FeltOperationWithConstConcreteLibFunc { operator, c, .. },
         )) => build_felt_op_with_const(builder, *operator, c.clone()),
         FeltConcrete::JumpNotZero(_) => build_jump_nz(builder),
-        FeltConcrete::Const(libfunc) => Ok(builder.build_only_reference_changes(
+        FeltConcrete::Const(libfunc) => builder.build_only_reference_changes(
             [ReferenceExpression::from_cell(CellExpression::Immediate(libfunc.c.clone()))]
                 .into_iter(),
-        )),
+        ),
     }
 }
 
@@ -44,15 +44,7 @@ fn build_felt_unary_op(
     builder: CompiledInvocationBuilder<'_>,
     op: FeltUnaryOperator,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let expr = match builder.refs {
-        [ReferenceValue { expression: expr, .. }] => expr,
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [ReferenceValue { expression: expr, .. }] = unpack_refs::<1>(&builder)?;
     let cell = expr
         .try_unpack_single()
         .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
@@ -64,9 +56,9 @@ fn build_felt_unary_op(
             _ => return Err(InvocationError::InvalidReferenceExpressionForArgument),
         },
     };
-    Ok(builder.build_only_reference_changes(
+    builder.build_only_reference_changes(
         [ReferenceExpression::from_cell(CellExpression::UnaryOp(expression))].into_iter(),
-    ))
+    )
 }
 
 /// Handles a felt operation with the given op.
@@ -74,17 +66,10 @@ fn build_felt_op(
     builder: CompiledInvocationBuilder<'_>,
     op: FeltBinaryOperator,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (expr_a, expr_b) = match builder.refs {
-        [ReferenceValue { expression: expr_a, .. }, ReferenceValue { expression: expr_b, .. }] => {
-            (expr_a, expr_b)
-        }
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 2,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [
+        ReferenceValue { expression: expr_a, .. },
+        ReferenceValue { expression: expr_b, .. },
+    ] = unpack_refs::<2>(&builder)?;
     let cell_a = expr_a
         .try_unpack_single()
         .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
@@ -100,9 +85,9 @@ fn build_felt_op(
         }
         _ => return Err(InvocationError::InvalidReferenceExpressionForArgument),
     };
-    Ok(builder.build_only_reference_changes(
+    builder.build_only_reference_changes(
         [ReferenceExpression::from_cell(CellExpression::BinOp(bin_expression))].into_iter(),
-    ))
+    )
 }
 
 /// Handles a felt operation with a const.
@@ -111,15 +96,7 @@ fn build_felt_op_with_const(
     op: FeltBinaryOperator,
     c: BigInt,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let expr = match builder.refs {
-        [ReferenceValue { expression, .. }] => expression,
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [ReferenceValue { expression: expr, .. }] = unpack_refs::<1>(&builder)?;
     let cell_expr = expr
         .try_unpack_single()
         .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?;
@@ -128,7 +105,7 @@ fn build_felt_op_with_const(
     } else {
         return Err(InvocationError::InvalidReferenceExpressionForArgument);
     };
-    Ok(builder.build_only_reference_changes(
+    builder.build_only_reference_changes(
         [ReferenceExpression::from_cell(CellExpression::BinOp(ref_expression))].into_iter(),
-    ))
+    )
 }
@@ -4,8 +4,9 @@ use sierra::program::StatementIdx;
 use test_log::test;
 
 use crate::invocations::test_utils::{
-    compile_libfunc, ReducedBranchChanges, ReducedCompiledInvocation,
+    compile_libfunc, try_compile_libfunc, ReducedBranchChanges, ReducedCompiledInvocation,
 };
+use crate::invocations::InvocationError;
 use crate::ref_expr;
 use crate::relocations::{Relocation, RelocationEntry};
 
@@ -59,3 +60,36 @@ fn test_jump_nz() {
         }
     );
 }
+
+#[test]
+fn test_jump_nz_sub() {
+    assert_eq!(
+        compile_libfunc("felt_jump_nz", vec![ref_expr!([fp + 1] - [fp + 2])]),
+        ReducedCompiledInvocation {
+            instructions: casm! {
+                [fp + 1] = [ap + 0] + [fp + 2], ap++;
+                jmp rel 0 if [ap - 1] != 0;
+            }
+            .instructions,
+            relocations: vec![RelocationEntry {
+                instruction_idx: 1,
+                relocation: Relocation::RelativeStatementId(StatementIdx(1))
+            }],
+            results: vec![
+                ReducedBranchChanges { refs: vec![], ap_change: ApChange::Known(1) },
+                ReducedBranchChanges {
+                    refs: vec![ref_expr!([ap - 1])],
+                    ap_change: ApChange::Known(1)
+                }
+            ]
+        }
+    );
+}
+
+#[test]
+fn test_jump_nz_add_const() {
+    assert_eq!(
+        try_compile_libfunc("felt_jump_nz", vec![ref_expr!([ap - 2] + 3)]),
+        Err(InvocationError::InvalidReferenceExpressionForArgument)
+    );
+}
@@ -16,34 +16,44 @@ pub fn build(
     libfunc: &FunctionCallConcreteLibFunc,
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    check_references_on_stack(builder.refs)?;
+    let stack_layout = *builder.program_info.stack_layout();
+    check_references_on_stack(builder.refs, &stack_layout)?;
 
     let output_types = libfunc.output_types();
     let fallthrough_outputs = &output_types[0];
 
+    // The callee returns with its results densely packed at the top of the stack, so they are
+    // referenced in place (relative to the `ap` after the call) rather than copied. This holds
+    // whether or not the ap change of the callee is known. Values returned by pointer are
+    // referenced through the pointer at the end of their stack slot.
     let mut refs = VecDeque::with_capacity(fallthrough_outputs.len());
 
     let mut offset = -1;
     for output_type in fallthrough_outputs.iter().rev() {
-        let size = builder
-            .program_info
-            .type_sizes
-            .get(output_type)
-            .ok_or(InvocationError::UnknownVariableData)?;
-        refs.push_front(ReferenceExpression {
-            cells: ((offset - size + 1)..(offset + 1))
-                .map(|i| CellExpression::Deref(CellRef { register: Register::AP, offset: i }))
-                .collect(),
+        let size = builder.type_size(output_type)?;
+        refs.push_front(if stack_layout.is_by_pointer(size) {
+            ReferenceExpression::from_double_deref(
+                CellRef { register: Register::AP, offset },
+                0,
+                size,
+            )?
+        } else {
+            let size = size as i16;
+            ReferenceExpression {
+                cells: ((offset - size + 1)..(offset + 1))
+                    .map(|i| CellExpression::Deref(CellRef { register: Register::AP, offset: i }))
+                    .collect(),
+            }
         });
-        offset -= size;
+        offset -= stack_layout.slot_size(size) as i16;
     }
 
-    Ok(builder.build(
+    builder.build(
         casm! { call rel 0; }.instructions,
         vec![RelocationEntry {
             instruction_idx: 0,
             relocation: Relocation::RelativeStatementId(libfunc.function.entry_point),
         }],
         [refs.into_iter()].into_iter(),
-    ))
+    )
 }
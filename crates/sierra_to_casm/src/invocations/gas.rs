@@ -5,10 +5,9 @@ use num_bigint::BigInt;
 use sierra::extensions::builtin_cost::CostTokenType;
 use sierra::extensions::felt::FeltBinaryOperator;
 use sierra::extensions::gas::GasConcreteLibFunc;
-use sierra_ap_change::core_libfunc_ap_change;
 use utils::try_extract_matches;
 
-use super::{CompiledInvocation, CompiledInvocationBuilder, InvocationError};
+use super::{unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
 use crate::invocations::get_non_fallthrough_statement_id;
 use crate::references::{BinOpExpression, CellExpression, ReferenceExpression, ReferenceValue};
 use crate::relocations::{Relocation, RelocationEntry};
@@ -23,6 +22,8 @@ pub fn build(
     match libfunc {
         GasConcreteLibFunc::GetGas(_) => build_get_gas(builder),
         GasConcreteLibFunc::RefundGas(_) => build_refund_gas(builder),
+        GasConcreteLibFunc::ReserveCreate(libfunc) => build_gas_reserve_create(builder, &libfunc.c),
+        GasConcreteLibFunc::ReserveRelease(_) => build_gas_reserve_release(builder),
     }
 }
 
@@ -38,30 +39,46 @@ fn build_get_gas(
         .get(&(builder.idx, CostTokenType::Step))
         .ok_or(InvocationError::UnknownVariableData)?
         * STEP_COST;
-    let (range_check, gas_counter_value) = match builder.refs {
-        [
-            ReferenceValue { expression: range_check_expression, .. },
-            ReferenceValue { expression: gas_counter_expression, .. },
-        ] => (
-            range_check_expression.try_unpack_single()?.to_buffer(1)?,
-            gas_counter_expression.try_unpack_single()?.to_deref()?,
-        ),
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 2,
-                actual: refs.len(),
-            });
-        }
-    };
+    build_gas_withdrawal(builder, requested_count.into(), vec![])
+}
 
-    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder);
+/// Handles the gas reserve creation invocation, withdrawing `amount` from the gas counter.
+fn build_gas_reserve_create(
+    builder: CompiledInvocationBuilder<'_>,
+    amount: &BigInt,
+) -> Result<CompiledInvocation, InvocationError> {
+    build_gas_withdrawal(
+        builder,
+        amount.clone(),
+        vec![ReferenceExpression::from_cell(CellExpression::Immediate(amount.clone()))],
+    )
+}
+
+/// Builds the instructions withdrawing `requested_count` from the gas counter, branching to the
+/// failure branch if the counter does not hold enough gas.
+/// `success_outputs` are appended to the outputs of the success branch.
+fn build_gas_withdrawal(
+    builder: CompiledInvocationBuilder<'_>,
+    requested_count: BigInt,
+    success_outputs: Vec<ReferenceExpression>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let [
+        ReferenceValue { expression: range_check_expression, .. },
+        ReferenceValue { expression: gas_counter_expression, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    let (range_check, gas_counter_value) = (
+        range_check_expression.try_unpack_single()?.to_buffer(1)?,
+        gas_counter_expression.try_unpack_single()?.to_deref()?,
+    );
+
+    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder)?;
 
     let mut casm_builder = CasmBuilder::default();
     let range_check = casm_builder.add_var(range_check);
     let gas_counter = casm_builder.add_var(ResOperand::Deref(gas_counter_value));
     let gas_counter_fix =
-        casm_builder.add_var(ResOperand::Immediate(BigInt::from(u128::MAX) + 1 - requested_count));
-    let requested_count = casm_builder.add_var(ResOperand::Immediate(requested_count.into()));
+        casm_builder.add_var(ResOperand::Immediate(BigInt::from(u128::MAX) + 1 - &requested_count));
+    let requested_count = casm_builder.add_var(ResOperand::Immediate(requested_count));
 
     casm_build_extend! {casm_builder,
         tempvar has_enough_gas;
@@ -77,16 +94,15 @@ fn build_get_gas(
         assert *(range_check++) = updated_gas;
     };
 
-    let CasmBuildResult { instructions, awaiting_relocations, label_state, fallthrough_state } =
-        casm_builder.build();
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change, label_state["Failure"].ap_change]
-            .map(sierra_ap_change::ApChange::Known)
-    );
-    let [relocation_index] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
-    Ok(builder.build(
+    let CasmBuildResult {
+        instructions, awaiting_relocations, label_state, fallthrough_state, ..
+    } = casm_builder.build();
+    builder.check_ap_changes([
+        fallthrough_state.full_ap_change(),
+        label_state["Failure"].full_ap_change(),
+    ])?;
+    let [(relocation_index, _)] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
+    builder.build(
         instructions,
         vec![RelocationEntry {
             instruction_idx: *relocation_index,
@@ -101,7 +117,8 @@ fn build_get_gas(
                     fallthrough_state.get_adjusted_as_cell_ref(updated_gas),
                 )),
             ]
-            .into_iter(),
+            .into_iter()
+            .chain(success_outputs),
             vec![
                 ReferenceExpression::from_cell(CellExpression::from_res_operand(
                     label_state["Failure"].get_adjusted(range_check),
@@ -113,7 +130,7 @@ fn build_get_gas(
             .into_iter(),
         ]
         .into_iter(),
-    ))
+    )
 }
 
 /// Handles the refund gas invocation.
@@ -127,15 +144,7 @@ fn build_refund_gas(
         .variable_values
         .get(&(builder.idx, CostTokenType::Step))
         .ok_or(InvocationError::UnknownVariableData)?;
-    let expression = match builder.refs {
-        [ReferenceValue { expression, .. }] => expression,
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [ReferenceValue { expression, .. }] = unpack_refs::<1>(&builder)?;
     let gas_counter_value = try_extract_matches!(
         expression
             .try_unpack_single()
@@ -144,7 +153,7 @@ fn build_refund_gas(
     )
     .ok_or(InvocationError::InvalidReferenceExpressionForArgument)?;
 
-    Ok(builder.build_only_reference_changes(
+    builder.build_only_reference_changes(
         [if *requested_count == 0 {
             ReferenceExpression::from_cell(CellExpression::Deref(gas_counter_value))
         } else {
@@ -155,5 +164,32 @@ fn build_refund_gas(
             }))
         }]
         .into_iter(),
-    ))
+    )
+}
+
+/// Handles the gas reserve release invocation, adding the reserved gas back to the gas counter.
+fn build_gas_reserve_release(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let [
+        ReferenceValue { expression: gas_counter_expression, .. },
+        ReferenceValue { expression: reserve_expression, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    let (gas_counter, reserve) = (
+        gas_counter_expression.try_unpack_single()?.to_deref()?,
+        match reserve_expression.try_unpack_single()? {
+            CellExpression::Deref(reserve) => DerefOrImmediate::Deref(reserve),
+            CellExpression::Immediate(reserve) => DerefOrImmediate::Immediate(reserve),
+            _ => return Err(InvocationError::InvalidReferenceExpressionForArgument),
+        },
+    );
+
+    builder.build_only_reference_changes(
+        [ReferenceExpression::from_cell(CellExpression::BinOp(BinOpExpression {
+            op: FeltBinaryOperator::Add,
+            a: gas_counter,
+            b: reserve,
+        }))]
+        .into_iter(),
+    )
 }
@@ -1,4 +1,7 @@
+use std::iter::zip;
+
 use casm::ap_change::{ApChange, ApplyApChange};
+use casm::inline::CasmContext;
 use casm::instructions::Instruction;
 use casm::operand::{CellRef, DerefOrImmediate, Register};
 use casm::{casm, casm_extend};
@@ -9,7 +12,7 @@ use sierra::ids::ConcreteTypeId;
 use utils::casts::usize_as_i16;
 use utils::try_extract_matches;
 
-use super::{misc, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
+use super::{misc, unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
 use crate::environment::frame_state;
 use crate::references::{
     BinOpExpression, CellExpression, ReferenceExpression, ReferenceValue, UnaryOpExpression,
@@ -45,8 +48,9 @@ macro_rules! add_instruction {
     }}
 }
 
-/// Returns a store instruction. Helper function for store_temp and store_local.
-fn get_store_instructions(
+/// Returns a store instruction. Helper function for store_temp, store_local and the jump non-zero
+/// libfuncs.
+pub fn get_store_instructions(
     builder: &CompiledInvocationBuilder<'_>,
     src_type: &ConcreteTypeId,
     mut dst: CellRef,
@@ -64,34 +68,7 @@ fn get_store_instructions(
     for cell_expr_orig in &src_expr.cells {
         let cell_expr =
             cell_expr_orig.clone().apply_ap_change(ApChange::Known(ap_change as usize)).unwrap();
-        match cell_expr {
-            CellExpression::Deref(operand) => add_instruction!(ctx, dst = operand),
-            CellExpression::DoubleDeref(operand, offset) => {
-                add_instruction!(ctx, dst = [[&operand] + offset])
-            }
-            CellExpression::IntoSingleCellRef(operand) => add_instruction!(
-                ctx,
-                %{ memory dst = segments.add() %}
-                operand = [[&dst]]
-            ),
-            CellExpression::Immediate(operand) => add_instruction!(ctx, dst = operand),
-            CellExpression::UnaryOp(UnaryOpExpression { op, a }) => match op {
-                FeltUnaryOperator::Neg => match a {
-                    DerefOrImmediate::Deref(cell_ref) => {
-                        add_instruction!(ctx, dst = cell_ref * (-1))
-                    }
-                    DerefOrImmediate::Immediate(imm) => add_instruction!(ctx, dst = (-imm)),
-                },
-            },
-            CellExpression::BinOp(BinOpExpression { op, a, b }) => match op {
-                FeltBinaryOperator::Add => add_instruction!(ctx, dst = a + b),
-                FeltBinaryOperator::Mul => add_instruction!(ctx, dst = a * b),
-                // dst = a - b => a = dst + b
-                FeltBinaryOperator::Sub => add_instruction!(ctx, a = dst + b),
-                // dst = a / b => a = dst * b
-                FeltBinaryOperator::Div => add_instruction!(ctx, a = dst * b),
-            },
-        }
+        add_store_cell_instruction(&mut ctx, dst, cell_expr);
         if inc_ap {
             ap_change += 1;
             ctx.instructions.last_mut().unwrap().inc_ap = true;
@@ -102,20 +79,47 @@ fn get_store_instructions(
     Ok(ctx.instructions)
 }
 
+/// Adds an instruction writing the value of `cell_expr` into `dst`, without changing ap.
+fn add_store_cell_instruction(ctx: &mut CasmContext, dst: CellRef, cell_expr: CellExpression) {
+    match cell_expr {
+        CellExpression::Deref(operand) => add_instruction!(ctx, dst = operand),
+        CellExpression::DoubleDeref(operand, offset) => {
+            add_instruction!(ctx, dst = [[&operand] + offset])
+        }
+        CellExpression::IntoSingleCellRef(operand) => add_instruction!(
+            ctx,
+            %{ memory dst = segments.add() %}
+            operand = [[&dst]]
+        ),
+        CellExpression::Immediate(operand) => add_instruction!(ctx, dst = operand),
+        CellExpression::UnaryOp(UnaryOpExpression { op, a }) => match op {
+            FeltUnaryOperator::Neg => match a {
+                DerefOrImmediate::Deref(cell_ref) => {
+                    add_instruction!(ctx, dst = cell_ref * (-1))
+                }
+                DerefOrImmediate::Immediate(imm) => add_instruction!(ctx, dst = (-imm)),
+            },
+        },
+        CellExpression::BinOp(BinOpExpression { op, a, b }) => match op {
+            FeltBinaryOperator::Add => add_instruction!(ctx, dst = a + b),
+            FeltBinaryOperator::Mul => add_instruction!(ctx, dst = a * b),
+            // dst = a - b => a = dst + b
+            FeltBinaryOperator::Sub => add_instruction!(ctx, a = dst + b),
+            // dst = a / b => a = dst * b
+            FeltBinaryOperator::Div => add_instruction!(ctx, a = dst * b),
+        },
+    }
+}
+
 /// Handles store_temp for the given type.
 fn build_store_temp(
     builder: CompiledInvocationBuilder<'_>,
     ty: &ConcreteTypeId,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let expression = match builder.refs {
-        [ReferenceValue { expression, .. }] => expression,
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
+    if builder.program_info.stack_layout().is_by_pointer(builder.type_size(ty)?) {
+        return build_store_temp_by_pointer(builder, ty);
+    }
+    let [ReferenceValue { expression, .. }] = unpack_refs::<1>(&builder)?;
 
     let instructions = get_store_instructions(
         &builder,
@@ -123,8 +127,8 @@ fn build_store_temp(
         CellRef { register: Register::AP, offset: 0 },
         expression,
     )?;
-    let type_size = builder.program_info.type_sizes[ty];
-    Ok(builder.build(
+    let type_size = builder.type_size(ty)? as i16;
+    builder.build(
         instructions,
         vec![],
         [[ReferenceExpression {
@@ -134,7 +138,58 @@ fn build_store_temp(
         }]
         .into_iter()]
         .into_iter(),
-    ))
+    )
+}
+
+/// Handles store_temp for a type stored on the stack by pointer.
+/// The value is copied into a new segment - unless it is already referenced through a pointer -
+/// and the pointer is written to the last cell of the stack slot. The other cells of the slot serve
+/// as scratch space for copying cells which are not plain cell references.
+fn build_store_temp_by_pointer(
+    builder: CompiledInvocationBuilder<'_>,
+    ty: &ConcreteTypeId,
+) -> Result<CompiledInvocation, InvocationError> {
+    let [ReferenceValue { expression, .. }] = unpack_refs::<1>(&builder)?;
+    let type_size = builder.type_size(ty)?;
+    let pointer = CellRef { register: Register::AP, offset: usize_as_i16(type_size) };
+    let mut ctx = casm!();
+    if let Some(src) = boxed_value_pointer(expression) {
+        add_instruction!(ctx, pointer = src);
+    } else {
+        casm_extend!(ctx, %{ memory pointer = segments.add() %});
+        for (i, cell_expr) in zip(0_i16.., &expression.cells) {
+            if let CellExpression::Deref(operand) = cell_expr {
+                add_instruction!(ctx, operand = [[&pointer] + i]);
+            } else {
+                let scratch = CellRef { register: Register::AP, offset: i };
+                add_store_cell_instruction(&mut ctx, scratch, cell_expr.clone());
+                add_instruction!(ctx, scratch = [[&pointer] + i]);
+            }
+        }
+    }
+    add_instruction!(ctx, ap += ((type_size + 1) as i128));
+    builder.build(
+        ctx.instructions,
+        vec![],
+        [[ReferenceExpression::from_double_deref(
+            CellRef { register: Register::AP, offset: -1 },
+            0,
+            type_size,
+        )?]
+        .into_iter()]
+        .into_iter(),
+    )
+}
+
+/// Returns the pointer through which the value of `expression` is referenced, if its cells are the
+/// consecutive cells pointed to by a single cell, e.g. `[[ap - 1] + 0], [[ap - 1] + 1]`.
+fn boxed_value_pointer(expression: &ReferenceExpression) -> Option<CellRef> {
+    let CellExpression::DoubleDeref(pointer, 0) = expression.cells.first()? else {
+        return None;
+    };
+    zip(0_i16.., &expression.cells)
+        .all(|(i, cell)| *cell == CellExpression::DoubleDeref(*pointer, i))
+        .then_some(*pointer)
 }
 
 /// Handles store_local for the given type.
@@ -142,13 +197,14 @@ fn build_store_local(
     builder: CompiledInvocationBuilder<'_>,
     ty: &ConcreteTypeId,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (dst_expr, src_expr) = match builder.refs {
-        [
-            ReferenceValue { expression: dst_expr, .. },
-            ReferenceValue { expression: src_expr, .. },
-        ] => Ok((dst_expr, src_expr)),
-        refs => Err(InvocationError::WrongNumberOfArguments { expected: 2, actual: refs.len() }),
-    }?;
+    let [
+        ReferenceValue { expression: dst_expr, .. },
+        ReferenceValue { expression: src_expr, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    if builder.type_size(ty)? == 0 {
+        // Zero-sized locals are never allocated, so there is nothing to store.
+        return builder.build_only_reference_changes([ReferenceExpression::unit()].into_iter());
+    }
     let dst = try_extract_matches!(
         dst_expr
             .try_unpack_single()
@@ -157,8 +213,8 @@ fn build_store_local(
     )
     .ok_or(InvocationError::InvalidReferenceExpressionForArgument)?;
     let instructions = get_store_instructions(&builder, ty, dst, src_expr)?;
-    let type_size = builder.program_info.type_sizes[ty];
-    Ok(builder.build(
+    let type_size = builder.type_size(ty)? as i16;
+    builder.build(
         instructions,
         vec![],
         [[ReferenceExpression {
@@ -173,7 +229,7 @@ fn build_store_local(
         }]
         .into_iter()]
         .into_iter(),
-    ))
+    )
 }
 
 /// Handles a locals allocation finalization instruction.
@@ -185,11 +241,11 @@ fn build_finalize_locals(
         builder.environment.ap_tracking,
     )?;
     builder.environment.frame_state = frame_state;
-    Ok(builder.build(
+    builder.build(
         casm! { ap += (n_slots as i128); }.instructions,
         vec![],
         [[].into_iter()].into_iter(),
-    ))
+    )
 }
 
 /// Handles the local variable allocation instruction.
@@ -202,6 +258,10 @@ fn build_alloc_local(
         .type_sizes
         .get(ty)
         .ok_or_else(|| InvocationError::NotSized(builder.invocation.clone()))?;
+    if allocation_size == 0 {
+        // Zero-sized locals require no slots.
+        return builder.build_only_reference_changes([ReferenceExpression::unit()].into_iter());
+    }
 
     let (slot, frame_state) = frame_state::handle_alloc_local(
         builder.environment.frame_state,
@@ -210,11 +270,11 @@ fn build_alloc_local(
     )?;
     builder.environment.frame_state = frame_state;
 
-    Ok(builder.build_only_reference_changes(
+    builder.build_only_reference_changes(
         [ReferenceExpression::from_cell(CellExpression::Deref(CellRef {
             register: Register::FP,
             offset: usize_as_i16(slot),
         }))]
         .into_iter(),
-    ))
+    )
 }
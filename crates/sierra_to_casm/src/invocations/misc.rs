@@ -1,42 +1,35 @@
 use casm::casm;
+use casm::operand::{CellRef, DerefOrImmediate, Register};
 use sierra::program::{BranchInfo, BranchTarget};
-use utils::try_extract_matches;
 
+use super::mem::get_store_instructions;
 use super::{
-    get_non_fallthrough_statement_id, CompiledInvocation, CompiledInvocationBuilder,
+    get_non_fallthrough_statement_id, unpack_refs, CompiledInvocation, CompiledInvocationBuilder,
     InvocationError,
 };
-use crate::references::{CellExpression, ReferenceExpression, ReferenceValue};
+use crate::references::{BinOpExpression, CellExpression, ReferenceExpression, ReferenceValue};
 use crate::relocations::{Relocation, RelocationEntry};
 
 /// Handles a revoke ap tracking instruction.
 pub fn build_revoke_ap_tracking(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    Ok(builder.build(vec![], vec![], [[].into_iter()].into_iter()))
+    builder.build(vec![], vec![], [[].into_iter()].into_iter())
 }
 
 /// Handles a dup instruction.
 pub fn build_dup(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let expression = match builder.refs {
-        [ReferenceValue { expression, .. }] => expression,
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
-    Ok(builder.build_only_reference_changes([expression.clone(), expression.clone()].into_iter()))
+    let [ReferenceValue { expression, .. }] = unpack_refs::<1>(&builder)?;
+    builder.build_only_reference_changes([expression.clone(), expression.clone()].into_iter())
 }
 
 /// Handles a drop instruction.
 pub fn build_drop(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    Ok(builder.build_only_reference_changes([].into_iter()))
+    builder.build_only_reference_changes([].into_iter())
 }
 
 /// Handles a jump non zero statement.
@@ -48,32 +41,41 @@ pub fn build_drop(
 /// ```ignore
 /// jmp rel <jump_offset_1000> if [ap-10] != 0
 /// ```
+/// Since only a cell can be checked by a conditional jump, a binary operation of two cells (e.g.
+/// `[fp + 1] - [fp + 2]`) is first stored into a temporary variable, within a single instruction,
+/// and the non-zero value is the new temporary variable:
+/// ```ignore
+/// [fp + 1] = [ap + 0] + [fp + 2], ap++
+/// jmp rel <jump_offset_1000> if [ap - 1] != 0
+/// ```
+/// Any other expression (e.g. `[fp + 1] + 3`) is rejected, as it cannot be checked without first
+/// being stored explicitly.
 pub fn build_jump_nz(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let dst_expr = match builder.refs {
-        [ReferenceValue { expression, .. }] => expression,
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 1,
-                actual: refs.len(),
-            });
-        }
-    };
-    let value = try_extract_matches!(
-        dst_expr
-            .try_unpack_single()
-            .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?,
-        CellExpression::Deref
-    )
-    .ok_or(InvocationError::InvalidReferenceExpressionForArgument)?;
+    let [ReferenceValue { expression: dst_expr, ty }] = unpack_refs::<1>(&builder)?;
+    let target_statement_id = get_non_fallthrough_statement_id(&builder)?;
 
-    let target_statement_id = get_non_fallthrough_statement_id(&builder);
+    let (mut instructions, value) = match dst_expr.try_unpack_single()? {
+        CellExpression::Deref(value) => (vec![], value),
+        CellExpression::BinOp(BinOpExpression { b: DerefOrImmediate::Deref(_), .. }) => (
+            get_store_instructions(
+                &builder,
+                ty,
+                CellRef { register: Register::AP, offset: 0 },
+                dst_expr,
+            )?,
+            CellRef { register: Register::AP, offset: -1 },
+        ),
+        _ => return Err(InvocationError::InvalidReferenceExpressionForArgument),
+    };
+    let relocation_index = instructions.len();
+    instructions.extend(casm! { jmp rel 0 if value != 0; }.instructions);
 
-    Ok(builder.build(
-        casm! { jmp rel 0 if value != 0; }.instructions,
+    builder.build(
+        instructions,
         vec![RelocationEntry {
-            instruction_idx: 0,
+            instruction_idx: relocation_index,
             relocation: Relocation::RelativeStatementId(target_statement_id),
         }],
         [
@@ -81,7 +83,7 @@ pub fn build_jump_nz(
             vec![ReferenceExpression::from_cell(CellExpression::Deref(value))].into_iter(),
         ]
         .into_iter(),
-    ))
+    )
 }
 
 /// Handles a jump instruction.
@@ -90,17 +92,17 @@ pub fn build_jump(
 ) -> Result<CompiledInvocation, InvocationError> {
     let target_statement_id = match builder.invocation.branches.as_slice() {
         [BranchInfo { target: BranchTarget::Statement(statement_id), .. }] => statement_id,
-        _ => panic!("malformed invocation"),
+        _ => return Err(InvocationError::MalformedBranches),
     };
 
-    Ok(builder.build(
+    builder.build(
         casm! { jmp rel 0; }.instructions,
         vec![RelocationEntry {
             instruction_idx: 0,
             relocation: Relocation::RelativeStatementId(*target_statement_id),
         }],
         [vec![].into_iter()].into_iter(),
-    ))
+    )
 }
 
 /// Handles an operations that does no changes to the reference expressions.
@@ -108,7 +110,7 @@ pub fn build_identity(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
     let outputs = builder.refs.iter().map(|r| r.expression.clone());
-    Ok(builder.build_only_reference_changes(outputs))
+    builder.build_only_reference_changes(outputs)
 }
 
 pub fn build_branch_align(
@@ -122,9 +124,9 @@ pub fn build_branch_align(
         .get(&builder.idx)
         .copied()
         .unwrap_or(0);
-    Ok(builder.build(
+    builder.build(
         if ap_fix > 0 { casm! {ap += ap_fix;}.instructions } else { vec![] },
         vec![],
         [vec![].into_iter()].into_iter(),
-    ))
+    )
 }
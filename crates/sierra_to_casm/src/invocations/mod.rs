@@ -1,16 +1,27 @@
+use std::collections::HashMap;
+
 use assert_matches::assert_matches;
 use casm::ap_change::ApChange;
+use casm::builder::{CasmBuildResult, CasmBuilder, State, Var};
+use casm::cost::{cheapest, InstructionsCost};
 use casm::instructions::Instruction;
 use casm::operand::{CellRef, Register};
-use itertools::zip_eq;
+use itertools::{zip_eq, Itertools};
 use sierra::extensions::builtin_cost::CostTokenType;
 use sierra::extensions::core::CoreConcreteLibFunc;
-use sierra::extensions::lib_func::BranchSignature;
+use sierra::extensions::lib_func::{BranchSignature, DeferredOutputKind};
+use sierra::extensions::strct::StructType;
+use sierra::extensions::types::NamedType;
 use sierra::extensions::{ConcreteLibFunc, OutputVarReferenceInfo};
-use sierra::ids::ConcreteTypeId;
-use sierra::program::{BranchInfo, BranchTarget, Invocation, StatementIdx};
+use sierra::ids::{ConcreteLibFuncId, ConcreteTypeId, GenericLibFuncId};
+use sierra::program::{
+    BranchInfo, BranchTarget, ConcreteLibFuncLongId, ConcreteTypeLongId, GenericArg, Invocation,
+    StatementIdx,
+};
 use sierra_ap_change::core_libfunc_ap_change::core_libfunc_ap_change;
+use sierra_ap_change::{InputForm, StackLayout};
 use thiserror::Error;
+use utils::diagnostic::Diagnostic;
 use utils::ordered_hash_map::OrderedHashMap;
 use {casm, sierra};
 
@@ -18,7 +29,8 @@ use crate::environment::frame_state::{FrameState, FrameStateError};
 use crate::environment::Environment;
 use crate::metadata::Metadata;
 use crate::references::{CellExpression, ReferenceExpression, ReferenceValue};
-use crate::relocations::RelocationEntry;
+use crate::relocations::{Relocation, RelocationEntry};
+use crate::runtime_routines::RuntimeRoutine;
 use crate::type_sizes::TypeSizeMap;
 
 mod array;
@@ -26,6 +38,7 @@ mod bitwise;
 mod boolean;
 mod boxing;
 mod builtin_cost;
+mod debug;
 mod dict_felt_to;
 mod enm;
 mod felt;
@@ -39,6 +52,8 @@ mod starknet;
 mod strct;
 mod uint128;
 
+#[cfg(test)]
+mod test;
 #[cfg(test)]
 mod test_utils;
 
@@ -48,8 +63,16 @@ pub enum InvocationError {
     InvalidReferenceExpressionForArgument,
     #[error("Unexpected error - an unregistered type id used.")]
     UnknownTypeId(ConcreteTypeId),
-    #[error("Expected a different number of arguments.")]
-    WrongNumberOfArguments { expected: usize, actual: usize },
+    #[error(
+        "Expected {expected} arguments for libfunc `{libfunc}`, got {actual}: ({}).",
+        .actual_types.iter().join(", ")
+    )]
+    WrongNumberOfArguments {
+        libfunc: GenericLibFuncId,
+        expected: usize,
+        actual: usize,
+        actual_types: Vec<ConcreteTypeId>,
+    },
     #[error("The requested functionality is not implemented yet.")]
     NotImplemented(Invocation),
     #[error("The functionality is supported only for sized types.")]
@@ -62,10 +85,81 @@ pub enum InvocationError {
     InvalidGenericArg,
     #[error("Invalid generic argument for libfunc.")]
     IntegerOverflow,
+    #[error("The invocation branches do not match the structure the libfunc expects.")]
+    MalformedBranches,
+    #[error("Expected a struct type, got `{0}`.")]
+    NotAStruct(ConcreteTypeId),
+    #[error("The libfunc has no type generic argument #{0}.")]
+    MissingGenericTypeArg(usize),
+    #[error("Expected {expected} output branches, got {actual}.")]
+    WrongNumberOfBranches { expected: usize, actual: usize },
+    #[error("Expected {expected} outputs in branch #{branch_idx}, got {actual}.")]
+    WrongNumberOfOutputs { branch_idx: usize, expected: usize, actual: usize },
+    #[error(
+        "Expected output #{var_idx} of branch #{branch_idx} to have {expected} cells, got \
+         {actual}."
+    )]
+    WrongOutputSize { branch_idx: usize, var_idx: usize, expected: usize, actual: usize },
+    #[error("Expected argument #{param_idx} to have {expected} cells, got {actual}.")]
+    WrongArgumentSize { param_idx: usize, expected: usize, actual: usize },
+    #[error(
+        "The code of libfunc `{libfunc}` changes ap by {actual} in branch #{branch_idx}, while an \
+         ap change of {expected} is declared."
+    )]
+    ApChangeMismatch {
+        libfunc: GenericLibFuncId,
+        branch_idx: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[error(
+        "The code of libfunc `{libfunc}` changes ap by an unknown amount in branch #{branch_idx}, \
+         while an ap change of {expected} is declared."
+    )]
+    UnknownApChange { libfunc: GenericLibFuncId, branch_idx: usize, expected: usize },
+    #[error(
+        "Output #{var_idx} of branch #{branch_idx} of libfunc `{libfunc}` advances its builtin by \
+         {actual}, while the code uses it {expected} times."
+    )]
+    BuiltinUsageMismatch {
+        libfunc: GenericLibFuncId,
+        branch_idx: usize,
+        var_idx: usize,
+        expected: usize,
+        actual: isize,
+    },
     #[error(transparent)]
     FrameStateError(#[from] FrameStateError),
 }
 
+impl From<InvocationError> for Diagnostic {
+    fn from(error: InvocationError) -> Self {
+        let code = match &error {
+            InvocationError::InvalidReferenceExpressionForArgument => "E0150",
+            InvocationError::UnknownTypeId(_) => "E0151",
+            InvocationError::WrongNumberOfArguments { .. } => "E0152",
+            InvocationError::NotImplemented(_) => "E0153",
+            InvocationError::NotSized(_) => "E0154",
+            InvocationError::UnknownTypeData => "E0155",
+            InvocationError::UnknownVariableData => "E0156",
+            InvocationError::InvalidGenericArg => "E0157",
+            InvocationError::IntegerOverflow => "E0158",
+            InvocationError::MalformedBranches => "E0159",
+            InvocationError::FrameStateError(_) => "E0160",
+            InvocationError::WrongNumberOfBranches { .. } => "E0161",
+            InvocationError::WrongNumberOfOutputs { .. } => "E0162",
+            InvocationError::WrongOutputSize { .. } => "E0163",
+            InvocationError::NotAStruct(_) => "E0164",
+            InvocationError::MissingGenericTypeArg(_) => "E0165",
+            InvocationError::ApChangeMismatch { .. } => "E0166",
+            InvocationError::BuiltinUsageMismatch { .. } => "E0167",
+            InvocationError::WrongArgumentSize { .. } => "E0168",
+            InvocationError::UnknownApChange { .. } => "E0169",
+        };
+        Diagnostic::error(code, error.to_string())
+    }
+}
+
 /// Describes the changes to the set of references at a single branch target, as well as changes to
 /// the environment.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -111,6 +205,12 @@ impl BranchChanges {
                                 )
                             });
                         }
+                        OutputVarReferenceInfo::StoredParam { .. } => {
+                            expression
+                                .cells
+                                .iter()
+                                .for_each(|cell| assert_matches!(cell, CellExpression::Deref(_)));
+                        }
                         _ => (),
                     };
                     ReferenceValue { expression, ty: var_info.ty.clone() }
@@ -134,14 +234,34 @@ pub struct CompiledInvocation {
     pub results: Vec<BranchChanges>,
     /// The environment after the invocation statement.
     pub environment: Environment,
+    /// Comments describing the instructions, with the index of the instruction each describes.
+    pub comments: Vec<(usize, String)>,
 }
 
 /// Checks that the list of reference is contiguous on the stack and ends at ap - 1.
 /// This is the requirement for function call and return statements.
-pub fn check_references_on_stack(refs: &[ReferenceValue]) -> Result<(), InvocationError> {
+/// Values stored on the stack by pointer are expected to be referenced through the last cell of
+/// their stack slot, as `[[ap + offset] + i]`.
+pub fn check_references_on_stack(
+    refs: &[ReferenceValue],
+    stack_layout: &StackLayout,
+) -> Result<(), InvocationError> {
     let mut expected_offset: i16 = -1;
     for return_ref in refs.iter().rev() {
-        for cell_expr in return_ref.expression.cells.iter().rev() {
+        let cells = &return_ref.expression.cells;
+        if stack_layout.is_by_pointer(cells.len()) {
+            let pointer = CellRef { register: Register::AP, offset: expected_offset };
+            if !cells
+                .iter()
+                .enumerate()
+                .all(|(i, cell)| *cell == CellExpression::DoubleDeref(pointer, i as i16))
+            {
+                return Err(InvocationError::InvalidReferenceExpressionForArgument);
+            }
+            expected_offset -= stack_layout.slot_size(cells.len()) as i16;
+            continue;
+        }
+        for cell_expr in cells.iter().rev() {
             match cell_expr {
                 CellExpression::Deref(CellRef { register: Register::AP, offset })
                     if *offset == expected_offset =>
@@ -155,17 +275,94 @@ pub fn check_references_on_stack(refs: &[ReferenceValue]) -> Result<(), Invocati
     Ok(())
 }
 
+/// The layout of a single member of a struct.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemberLayout {
+    /// The type of the member.
+    pub ty: ConcreteTypeId,
+    /// The offset of the first cell of the member, from the first cell of the struct.
+    pub offset: usize,
+    /// The number of cells of the member.
+    pub size: usize,
+}
+
 /// Helper for building compiled invocations.
 pub struct CompiledInvocationBuilder<'a> {
     pub program_info: ProgramInfo<'a>,
     pub invocation: &'a Invocation,
-    pub libfunc: &'a CoreConcreteLibFunc,
+    pub libfunc: &'a dyn ConcreteLibFunc,
+    /// The ap changes declared for the branches of the libfunc.
+    pub ap_changes: Vec<sierra_ap_change::ApChange>,
+    /// The gas costs of the branches of the libfunc, for this invocation.
+    pub gas_changes: Vec<Option<OrderedHashMap<CostTokenType, i64>>>,
     pub idx: StatementIdx,
     pub refs: &'a [ReferenceValue],
     pub environment: Environment,
 }
-impl CompiledInvocationBuilder<'_> {
+impl<'a> CompiledInvocationBuilder<'a> {
+    /// Returns the size of the given type.
+    pub fn type_size(&self, ty: &ConcreteTypeId) -> Result<usize, InvocationError> {
+        self.program_info.type_size(ty)
+    }
+
+    /// Returns the layout of the members of the given struct type, in order.
+    pub fn struct_layout(&self, ty: &ConcreteTypeId) -> Result<Vec<MemberLayout>, InvocationError> {
+        self.program_info.struct_layout(ty)
+    }
+
+    /// Returns the `n`th generic argument of the invoked libfunc, which is expected to be a type.
+    pub fn libfunc_generic_type_arg(&self, n: usize) -> Result<&ConcreteTypeId, InvocationError> {
+        match self
+            .program_info
+            .libfunc_long_ids
+            .get(&self.invocation.libfunc_id)
+            .and_then(|long_id| long_id.generic_args.get(n))
+        {
+            Some(GenericArg::Type(ty)) => Ok(ty),
+            _ => Err(InvocationError::MissingGenericTypeArg(n)),
+        }
+    }
+
+    /// Returns the generic id of the invoked libfunc.
+    fn generic_libfunc_id(&self) -> GenericLibFuncId {
+        self.program_info.libfunc_long_ids[&self.invocation.libfunc_id].generic_id.clone()
+    }
+
+    /// Returns the error of the invocation having a wrong number of arguments, where `expected`
+    /// were expected.
+    pub fn wrong_number_of_arguments(&self, expected: usize) -> InvocationError {
+        InvocationError::WrongNumberOfArguments {
+            libfunc: self.generic_libfunc_id(),
+            expected,
+            actual: self.refs.len(),
+            actual_types: self.refs.iter().map(|r| r.ty.clone()).collect(),
+        }
+    }
+
+    /// Returns the index of the cheapest of alternative lowerings of the invocation, given their
+    /// costs, by the cost weights of the program.
+    pub fn choose_lowering(&self, costs: &[InstructionsCost]) -> usize {
+        cheapest(costs, &self.program_info.metadata.cost_weights)
+    }
+
+    /// Returns the `N` cells of argument #`param_idx` of the invocation.
+    pub fn try_unpack_arg<const N: usize>(
+        &self,
+        param_idx: usize,
+    ) -> Result<[&'a CellExpression; N], InvocationError> {
+        let Some(arg) = self.refs.get(param_idx) else {
+            return Err(self.wrong_number_of_arguments(self.libfunc.param_signatures().len()));
+        };
+        arg.expression.try_unpack().map_err(|_| InvocationError::WrongArgumentSize {
+            param_idx,
+            expected: N,
+            actual: arg.expression.cells.len(),
+        })
+    }
+
     /// Creates a new invocation.
+    ///
+    /// Fails if the output expressions do not match the branch signatures of the libfunc.
     fn build(
         self,
         instructions: Vec<Instruction>,
@@ -173,20 +370,12 @@ impl CompiledInvocationBuilder<'_> {
         output_expressions: impl ExactSizeIterator<
             Item = impl ExactSizeIterator<Item = ReferenceExpression>,
         >,
-    ) -> CompiledInvocation {
-        let gas_changes = sierra_gas::core_libfunc_cost::core_libfunc_cost(
-            &self.program_info.metadata.gas_info,
-            &self.idx,
-            self.libfunc,
-        );
-
+    ) -> Result<CompiledInvocation, InvocationError> {
         let branch_signatures = self.libfunc.branch_signatures();
-        assert_eq!(
-            branch_signatures.len(),
-            output_expressions.len(),
-            "The number of output expressions does not match signature."
-        );
-        let ap_changes = core_libfunc_ap_change(self.libfunc);
+        let output_expressions: Vec<Vec<ReferenceExpression>> =
+            output_expressions.map(|expressions| expressions.collect()).collect();
+        self.validate_outputs(branch_signatures, &output_expressions)?;
+        let (ap_changes, gas_changes) = (self.ap_changes.clone(), self.gas_changes.clone());
         assert_eq!(
             branch_signatures.len(),
             ap_changes.len(),
@@ -198,7 +387,7 @@ impl CompiledInvocationBuilder<'_> {
             "The number of gas changes does not match signature."
         );
 
-        CompiledInvocation {
+        Ok(CompiledInvocation {
             instructions,
             relocations,
             results: zip_eq(
@@ -206,38 +395,7 @@ impl CompiledInvocationBuilder<'_> {
                 zip_eq(output_expressions, ap_changes),
             )
             .map(|((branch_signature, gas_change), (expressions, ap_change))| {
-                let ap_change = match ap_change {
-                    sierra_ap_change::ApChange::Known(x) => ApChange::Known(x),
-                    sierra_ap_change::ApChange::AtLocalsFinalizationByTypeSize(_) => {
-                        ApChange::Known(0)
-                    }
-                    sierra_ap_change::ApChange::FinalizeLocals => {
-                        match self.environment.frame_state {
-                            FrameState::Finalized { allocated } => ApChange::Known(allocated),
-                            _ => panic!("Unexpected frame state."),
-                        }
-                    }
-                    sierra_ap_change::ApChange::KnownByTypeSize(ty) => {
-                        ApChange::Known(self.program_info.type_sizes[&ty] as usize)
-                    }
-                    sierra_ap_change::ApChange::FunctionCall(id) => self
-                        .program_info
-                        .metadata
-                        .ap_change_info
-                        .function_ap_change
-                        .get(&id)
-                        .map_or(ApChange::Unknown, |x| ApChange::Known(x + 2)),
-                    sierra_ap_change::ApChange::FromMetadata => ApChange::Known(
-                        *self
-                            .program_info
-                            .metadata
-                            .ap_change_info
-                            .variable_values
-                            .get(&self.idx)
-                            .unwrap_or(&0),
-                    ),
-                    sierra_ap_change::ApChange::Unknown => ApChange::Unknown,
-                };
+                let ap_change = self.evaluate_ap_change(ap_change);
 
                 BranchChanges::new(
                     ap_change,
@@ -246,20 +404,270 @@ impl CompiledInvocationBuilder<'_> {
                         .iter()
                         .map(|(token_type, val)| (*token_type, -val))
                         .collect(),
-                    expressions,
+                    expressions.into_iter(),
                     branch_signature,
                 )
             })
             .collect(),
             environment: self.environment,
+            comments: vec![],
+        })
+    }
+
+    /// Creates a new invocation out of the code of `casm_builder`.
+    ///
+    /// The fallthrough branch of the invocation, if any, continues at the end of the code, and the
+    /// other branches start at `branch_labels`, in order. Each of `outputs_per_branch` holds the
+    /// outputs of a branch, given as the variables of their cells. Validates the ap changes of the
+    /// branches against the libfunc.
+    pub fn build_from_casm_builder(
+        self,
+        casm_builder: CasmBuilder,
+        branch_labels: &[&str],
+        outputs_per_branch: impl IntoIterator<Item = Vec<Vec<Var>>>,
+    ) -> Result<CompiledInvocation, InvocationError> {
+        let CasmBuildResult {
+            instructions,
+            awaiting_relocations,
+            mut label_state,
+            fallthrough_state,
+            comments,
+        } = casm_builder.build();
+        let mut branch_labels = branch_labels.iter();
+        let mut label_statement_ids = HashMap::new();
+        let mut branch_states = vec![];
+        for branch in &self.invocation.branches {
+            branch_states.push(match branch.target {
+                BranchTarget::Fallthrough => fallthrough_state.clone(),
+                BranchTarget::Statement(statement_id) => {
+                    let label = *branch_labels.next().ok_or(InvocationError::MalformedBranches)?;
+                    label_statement_ids.insert(label, statement_id);
+                    label_state.remove(label).expect("Malformed casm builder usage.")
+                }
+            });
+        }
+        if branch_labels.next().is_some() {
+            return Err(InvocationError::MalformedBranches);
+        }
+        self.check_ap_changes(branch_states.iter().map(State::full_ap_change))?;
+
+        let relocations = awaiting_relocations
+            .into_iter()
+            .map(|(instruction_idx, label)| RelocationEntry {
+                instruction_idx,
+                relocation: match RuntimeRoutine::from_label(&label) {
+                    Some(routine) => Relocation::RelativeRoutine(routine),
+                    None => Relocation::RelativeStatementId(
+                        *label_statement_ids
+                            .get(label.as_str())
+                            .expect("Malformed casm builder usage."),
+                    ),
+                },
+            })
+            .collect();
+        let outputs_per_branch = outputs_per_branch.into_iter().collect_vec();
+        if outputs_per_branch.len() != branch_states.len() {
+            return Err(InvocationError::WrongNumberOfBranches {
+                expected: branch_states.len(),
+                actual: outputs_per_branch.len(),
+            });
+        }
+        self.check_builtin_usages(&branch_states, &outputs_per_branch)?;
+        let output_expressions = zip_eq(branch_states, outputs_per_branch)
+            .map(|(state, outputs)| {
+                outputs
+                    .into_iter()
+                    .map(|cells| ReferenceExpression {
+                        cells: cells
+                            .into_iter()
+                            .map(|cell| CellExpression::from_res_operand(state.get_adjusted(cell)))
+                            .collect(),
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+        let compiled = self.build(
+            instructions,
+            relocations,
+            output_expressions.into_iter().map(|expressions| expressions.into_iter()),
+        )?;
+        Ok(CompiledInvocation { comments, ..compiled })
+    }
+
+    /// Evaluates the ap change declared for a branch of the libfunc, for this invocation.
+    fn evaluate_ap_change(&self, ap_change: sierra_ap_change::ApChange) -> ApChange {
+        match ap_change {
+            sierra_ap_change::ApChange::Known(x) => ApChange::Known(x),
+            sierra_ap_change::ApChange::AtLocalsFinalizationByTypeSize(_) => ApChange::Known(0),
+            sierra_ap_change::ApChange::FinalizeLocals => match self.environment.frame_state {
+                FrameState::Finalized { allocated } => ApChange::Known(allocated),
+                _ => panic!("Unexpected frame state."),
+            },
+            sierra_ap_change::ApChange::KnownByTypeSize(ty) => {
+                ApChange::Known(self.program_info.type_sizes[&ty] as usize)
+            }
+            sierra_ap_change::ApChange::KnownByStackSlotSize(ty) => ApChange::Known(
+                self.program_info
+                    .stack_layout()
+                    .slot_size(self.program_info.type_sizes[&ty] as usize),
+            ),
+            sierra_ap_change::ApChange::FunctionCall(id) => self
+                .program_info
+                .metadata
+                .ap_change_info
+                .function_ap_change
+                .get(&id)
+                .map_or(ApChange::Unknown, |x| ApChange::Known(x + 2)),
+            sierra_ap_change::ApChange::FromMetadata => ApChange::Known(
+                *self
+                    .program_info
+                    .metadata
+                    .ap_change_info
+                    .variable_values
+                    .get(&self.idx)
+                    .unwrap_or(&0),
+            ),
+            sierra_ap_change::ApChange::ByInputForm(by_form) => ApChange::Known(
+                by_form
+                    .eval(&self.input_forms())
+                    .expect("The number of arguments does not match signature."),
+            ),
+            sierra_ap_change::ApChange::Unknown => ApChange::Unknown,
         }
     }
 
+    /// Checks that `ap_changes`, the ap changes of the branches of the code of the invocation as
+    /// computed while building it, match the ap changes declared for the libfunc.
+    /// Branches whose declared ap change is unknown are not checked, so their code may change ap by
+    /// an unknown amount, e.g. by looping.
+    pub fn check_ap_changes(
+        &self,
+        ap_changes: impl IntoIterator<Item = ApChange>,
+    ) -> Result<(), InvocationError> {
+        let declared_ap_changes = self.ap_changes.clone();
+        for (branch_idx, (declared_ap_change, actual)) in
+            zip_eq(declared_ap_changes, ap_changes).enumerate()
+        {
+            match (self.evaluate_ap_change(declared_ap_change), actual) {
+                (ApChange::Known(expected), ApChange::Known(actual)) if expected != actual => {
+                    return Err(InvocationError::ApChangeMismatch {
+                        libfunc: self.generic_libfunc_id(),
+                        branch_idx,
+                        expected,
+                        actual,
+                    });
+                }
+                (ApChange::Known(expected), ApChange::Unknown) => {
+                    return Err(InvocationError::UnknownApChange {
+                        libfunc: self.generic_libfunc_id(),
+                        branch_idx,
+                        expected,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every builtin output of the form `[ref] + const` is advanced from its input by
+    /// the number of times the code of the branch used the builtin, as recorded in `branch_states`.
+    /// Mismatches in the number of branches or outputs are left to `validate_outputs`.
+    fn check_builtin_usages(
+        &self,
+        branch_states: &[State],
+        outputs_per_branch: &[Vec<Vec<Var>>],
+    ) -> Result<(), InvocationError> {
+        for (branch_idx, ((state, outputs), branch_signature)) in branch_states
+            .iter()
+            .zip(outputs_per_branch)
+            .zip(self.libfunc.branch_signatures())
+            .enumerate()
+        {
+            for (var_idx, (cells, var_info)) in
+                outputs.iter().zip(&branch_signature.vars).enumerate()
+            {
+                let (
+                    [var],
+                    OutputVarReferenceInfo::Deferred(DeferredOutputKind::AddConst { param_idx }),
+                ) = (cells.as_slice(), &var_info.ref_info) else {
+                    continue;
+                };
+                let input_offset =
+                    self.refs[*param_idx].expression.try_unpack_single()?.to_deref_with_offset()?.1;
+                let output_offset = CellExpression::from_res_operand(state.get_adjusted(*var))
+                    .to_deref_with_offset()?
+                    .1;
+                let expected = state.buffer_uses(*var);
+                let actual = isize::from(output_offset) - isize::from(input_offset);
+                if actual != expected as isize {
+                    return Err(InvocationError::BuiltinUsageMismatch {
+                        libfunc: self.generic_libfunc_id(),
+                        branch_idx,
+                        var_idx,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the output expressions have a branch per branch signature, a variable per
+    /// output of the branch, and a cell per unit of the size of the output type.
+    /// Types without a known size (such as `Uninitialized`) are not size checked.
+    fn validate_outputs(
+        &self,
+        branch_signatures: &[BranchSignature],
+        output_expressions: &[Vec<ReferenceExpression>],
+    ) -> Result<(), InvocationError> {
+        if branch_signatures.len() != output_expressions.len() {
+            return Err(InvocationError::WrongNumberOfBranches {
+                expected: branch_signatures.len(),
+                actual: output_expressions.len(),
+            });
+        }
+        for (branch_idx, (branch_signature, expressions)) in
+            zip_eq(branch_signatures, output_expressions).enumerate()
+        {
+            if branch_signature.vars.len() != expressions.len() {
+                return Err(InvocationError::WrongNumberOfOutputs {
+                    branch_idx,
+                    expected: branch_signature.vars.len(),
+                    actual: expressions.len(),
+                });
+            }
+            for (var_idx, (var, expression)) in
+                zip_eq(&branch_signature.vars, expressions).enumerate()
+            {
+                let expected = match self.program_info.type_sizes.get(&var.ty) {
+                    Some(size) => *size as usize,
+                    None => continue,
+                };
+                if expression.cells.len() != expected {
+                    return Err(InvocationError::WrongOutputSize {
+                        branch_idx,
+                        var_idx,
+                        expected,
+                        actual: expression.cells.len(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the forms of the arguments of the invocation.
+    fn input_forms(&self) -> Vec<InputForm> {
+        self.refs.iter().map(|r| r.expression.input_form()).collect()
+    }
+
     /// Creates a new invocation with only reference changes.
     fn build_only_reference_changes(
         self,
         output_expressions: impl ExactSizeIterator<Item = ReferenceExpression>,
-    ) -> CompiledInvocation {
+    ) -> Result<CompiledInvocation, InvocationError> {
         self.build(vec![], vec![], [output_expressions].into_iter())
     }
 }
@@ -268,6 +676,45 @@ impl CompiledInvocationBuilder<'_> {
 pub struct ProgramInfo<'a> {
     pub metadata: &'a Metadata,
     pub type_sizes: &'a TypeSizeMap,
+    /// The long ids of the declared types.
+    pub type_long_ids: &'a HashMap<ConcreteTypeId, ConcreteTypeLongId>,
+    /// The long ids of the declared libfuncs.
+    pub libfunc_long_ids: &'a HashMap<ConcreteLibFuncId, ConcreteLibFuncLongId>,
+}
+impl ProgramInfo<'_> {
+    /// Returns the layout of values stored on the stack.
+    pub fn stack_layout(&self) -> &StackLayout {
+        &self.metadata.ap_change_info.stack_layout
+    }
+
+    /// Returns the size of the given type.
+    pub fn type_size(&self, ty: &ConcreteTypeId) -> Result<usize, InvocationError> {
+        match self.type_sizes.get(ty) {
+            Some(size) => Ok(*size as usize),
+            None => Err(InvocationError::UnknownTypeId(ty.clone())),
+        }
+    }
+
+    /// Returns the layout of the members of the given struct type, in order.
+    pub fn struct_layout(&self, ty: &ConcreteTypeId) -> Result<Vec<MemberLayout>, InvocationError> {
+        let long_id =
+            self.type_long_ids.get(ty).ok_or_else(|| InvocationError::UnknownTypeId(ty.clone()))?;
+        if long_id.generic_id != StructType::ID {
+            return Err(InvocationError::NotAStruct(ty.clone()));
+        }
+        // The first generic argument is the user type of the struct, the rest are its members.
+        let mut offset = 0;
+        let mut layout = vec![];
+        for arg in long_id.generic_args.iter().skip(1) {
+            let GenericArg::Type(member_ty) = arg else {
+                return Err(InvocationError::NotAStruct(ty.clone()));
+            };
+            let size = self.type_size(member_ty)?;
+            layout.push(MemberLayout { ty: member_ty.clone(), offset, size });
+            offset += size;
+        }
+        Ok(layout)
+    }
 }
 
 /// Given a Sierra invocation statement and concrete libfunc, creates a compiled casm representation
@@ -280,8 +727,29 @@ pub fn compile_invocation(
     refs: &[ReferenceValue],
     environment: Environment,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let builder =
-        CompiledInvocationBuilder { program_info, invocation, libfunc, idx, refs, environment };
+    let builder = CompiledInvocationBuilder {
+        ap_changes: core_libfunc_ap_change(libfunc),
+        gas_changes: sierra_gas::core_libfunc_cost::core_libfunc_cost(
+            &program_info.metadata.gas_info,
+            &idx,
+            libfunc,
+        ),
+        program_info,
+        invocation,
+        libfunc,
+        idx,
+        refs,
+        environment,
+    };
+    compile_core_invocation(libfunc, builder)
+}
+
+/// Creates a compiled casm representation of an invocation of a core libfunc, out of the builder of
+/// the invocation.
+pub fn compile_core_invocation(
+    libfunc: &CoreConcreteLibFunc,
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
     match libfunc {
         // TODO(ilya, 10/10/2022): Handle type.
         CoreConcreteLibFunc::Felt(libfunc) => felt::build(libfunc, builder),
@@ -304,6 +772,7 @@ pub fn compile_invocation(
         CoreConcreteLibFunc::DictFeltTo(libfunc) => dict_felt_to::build(libfunc, builder),
         CoreConcreteLibFunc::Pedersen(libfunc) => pedersen::build(libfunc, builder),
         CoreConcreteLibFunc::BuiltinCost(libfunc) => builtin_cost::build(libfunc, builder),
+        CoreConcreteLibFunc::Debug(libfunc) => debug::build(libfunc, builder),
         CoreConcreteLibFunc::StarkNet(libfunc) => starknet::build(libfunc, builder),
     }
 }
@@ -326,12 +795,24 @@ trait ReferenceExpressionView: Sized {
 
 /// Fetches the non-fallthrough jump target of the invocation, assuming this invocation is a
 /// conditional jump.
-pub fn get_non_fallthrough_statement_id(builder: &CompiledInvocationBuilder<'_>) -> StatementIdx {
+/// Returns an error if the invocation is not made of a fallthrough branch followed by a single
+/// jump branch.
+pub fn get_non_fallthrough_statement_id(
+    builder: &CompiledInvocationBuilder<'_>,
+) -> Result<StatementIdx, InvocationError> {
     match builder.invocation.branches.as_slice() {
         [
             BranchInfo { target: BranchTarget::Fallthrough, .. },
             BranchInfo { target: BranchTarget::Statement(target_statement_id), .. },
-        ] => *target_statement_id,
-        _ => panic!("malformed invocation"),
+        ] => Ok(*target_statement_id),
+        _ => Err(InvocationError::MalformedBranches),
     }
 }
+
+/// Returns the references of the arguments of the invocation.
+/// Returns an error if the invocation does not have exactly `N` arguments.
+pub fn unpack_refs<'a, const N: usize>(
+    builder: &CompiledInvocationBuilder<'a>,
+) -> Result<&'a [ReferenceValue; N], InvocationError> {
+    builder.refs.try_into().map_err(|_| builder.wrong_number_of_arguments(N))
+}
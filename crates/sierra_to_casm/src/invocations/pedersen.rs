@@ -6,9 +6,8 @@ use casm::builder::{CasmBuildResult, CasmBuilder};
 use casm::casm_build_extend;
 use casm::operand::ResOperand;
 use sierra::extensions::pedersen::PedersenConcreteLibFunc;
-use sierra_ap_change::core_libfunc_ap_change;
 
-use super::{CompiledInvocation, CompiledInvocationBuilder, InvocationError};
+use super::{unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
 use crate::references::{CellExpression, ReferenceExpression, ReferenceValue};
 
 /// Builds instructions for Sierra array operations.
@@ -25,23 +24,16 @@ pub fn build(
 fn build_pedersen_hash(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (pedersen, x, y) = match builder.refs {
-        [
-            ReferenceValue { expression: expr_pedersen, .. },
-            ReferenceValue { expression: expr_x, .. },
-            ReferenceValue { expression: expr_y, .. },
-        ] => (
-            expr_pedersen.try_unpack_single()?.to_buffer(2)?,
-            expr_x.try_unpack_single()?.to_deref()?,
-            expr_y.try_unpack_single()?.to_deref()?,
-        ),
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 3,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [
+        ReferenceValue { expression: expr_pedersen, .. },
+        ReferenceValue { expression: expr_x, .. },
+        ReferenceValue { expression: expr_y, .. },
+    ] = unpack_refs::<3>(&builder)?;
+    let (pedersen, x, y) = (
+        expr_pedersen.try_unpack_single()?.to_buffer(2)?,
+        expr_x.try_unpack_single()?.to_deref()?,
+        expr_y.try_unpack_single()?.to_deref()?,
+    );
 
     let mut casm_builder = CasmBuilder::default();
     let pedersen = casm_builder.add_var(pedersen);
@@ -55,12 +47,8 @@ fn build_pedersen_hash(
         let result = *(pedersen++);
     };
     let CasmBuildResult { instructions, fallthrough_state, .. } = casm_builder.build();
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change].map(sierra_ap_change::ApChange::Known)
-    );
-    Ok(builder.build(
+    builder.check_ap_changes([fallthrough_state.full_ap_change()])?;
+    builder.build(
         instructions,
         vec![],
         [vec![
@@ -73,5 +61,5 @@ fn build_pedersen_hash(
         ]
         .into_iter()]
         .into_iter(),
-    ))
+    )
 }
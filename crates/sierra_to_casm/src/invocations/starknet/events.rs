@@ -0,0 +1,69 @@
+use casm::builder::CasmBuilder;
+use casm::operand::ResOperand;
+use utils::casts::usize_as_i16;
+
+use super::{
+    add_syscall, unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError,
+    SyscallInput,
+};
+use crate::invocations::array::ArrayView;
+use crate::references::ReferenceValue;
+use crate::syscall_records::{
+    selector_value, syscall_size, EmitEventRequest, EmitEventResponse, EMIT_EVENT_SELECTOR,
+};
+
+#[cfg(test)]
+#[path = "events_test.rs"]
+mod test;
+
+/// Builds instructions for StarkNet emit event system call.
+pub fn build_emit_event(
+    mut builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let system_slack = usize_as_i16(syscall_size::<EmitEventRequest, EmitEventResponse>());
+
+    let [
+        ReferenceValue { expression: expr_gas_builtin, .. },
+        ReferenceValue { expression: expr_system, .. },
+        _,
+        _,
+    ] = unpack_refs::<4>(&builder)?;
+    let (gas_builtin, system, keys, data) = (
+        expr_gas_builtin.try_unpack_single()?.to_deref()?,
+        expr_system.try_unpack_single()?.to_buffer(system_slack)?,
+        ArrayView::from_arg(&builder, 2)?,
+        ArrayView::from_arg(&builder, 3)?,
+    );
+
+    if keys.end_offset != 0 || data.end_offset != 0 {
+        return Err(InvocationError::InvalidReferenceExpressionForArgument);
+    }
+
+    let mut casm_builder = CasmBuilder::default();
+    let system = casm_builder.add_var(system);
+    let gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
+    let keys_start = casm_builder.add_var(ResOperand::Deref(keys.start));
+    let keys_end = casm_builder.add_var(ResOperand::Deref(keys.end));
+    let data_start = casm_builder.add_var(ResOperand::Deref(data.start));
+    let data_end = casm_builder.add_var(ResOperand::Deref(data.end));
+    let syscall = add_syscall(
+        &mut casm_builder,
+        &mut builder.environment,
+        selector_value(EMIT_EVENT_SELECTOR),
+        system,
+        gas_builtin,
+        &[keys_start, keys_end, data_start, data_end].map(SyscallInput::Cell),
+        0,
+    );
+
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
+        [
+            // Success branch - return (gas builtin, system)
+            syscall.builtin_outputs(),
+            // Failure branch - return (gas builtin, system, revert_reason)
+            syscall.failure_outputs(),
+        ],
+    )
+}
@@ -0,0 +1,15 @@
+use crate::invocations::test_utils::compare_libfunc_snapshot;
+use crate::ref_expr;
+
+#[test]
+fn test_emit_event() {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    let keys = ref_expr!([fp + 3], [fp + 4]);
+    let data = ref_expr!([fp + 5], [fp + 6]);
+    compare_libfunc_snapshot(
+        "starknet/emit_event",
+        "emit_event_syscall",
+        vec![gas_builtin, system, keys, data],
+    );
+}
@@ -1,17 +1,23 @@
-use casm::builder::{CasmBuildResult, CasmBuilder};
+use casm::builder::{CasmBuilder, Var};
 use casm::casm_build_extend;
 use casm::operand::ResOperand;
+use itertools::chain;
 use num_bigint::BigInt;
 use sierra::extensions::consts::SignatureAndConstConcreteLibFunc;
-use sierra::extensions::lib_func::SignatureOnlyConcreteLibFunc;
-use sierra::extensions::SignatureBasedConcreteLibFunc;
-use sierra_ap_change::core_libfunc_ap_change;
+use sierra::extensions::starknet::interoperability::CALL_DATA_WORD_GAS;
+use utils::casts::usize_as_i16;
 
-use super::{CompiledInvocation, CompiledInvocationBuilder, InvocationError};
+use super::{
+    add_syscall, unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError,
+    SyscallInput,
+};
 use crate::invocations::array::ArrayView;
-use crate::invocations::{get_non_fallthrough_statement_id, ReferenceExpressionView};
 use crate::references::{CellExpression, ReferenceExpression, ReferenceValue};
-use crate::relocations::{Relocation, RelocationEntry};
+use crate::syscall_records::{
+    selector_value, syscall_size, CallContractRequest, CallContractResponse, DeployRequest,
+    DeployResponse, LibraryCallRequest, LibraryCallResponse, SendMessageToL1Request,
+    SendMessageToL1Response, SyscallRecord, DEPLOY_SELECTOR, SEND_MESSAGE_TO_L1_SELECTOR,
+};
 
 #[cfg(test)]
 #[path = "interoperability_test.rs"]
@@ -20,123 +26,214 @@ mod test;
 /// Builds instructions for StarkNet call contract system call.
 pub fn build_call_contract(
     builder: CompiledInvocationBuilder<'_>,
-    libfunc: &SignatureOnlyConcreteLibFunc,
+    libfunc: &SignatureAndConstConcreteLibFunc,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder);
-    let selector_imm = BigInt::from_bytes_le(num_bigint::Sign::Plus, "call_contract".as_bytes());
+    build_call::<CallContractRequest, CallContractResponse>(builder, libfunc)
+}
+
+/// Builds instructions for StarkNet library call system call.
+pub fn build_library_call(
+    builder: CompiledInvocationBuilder<'_>,
+    libfunc: &SignatureAndConstConcreteLibFunc,
+) -> Result<CompiledInvocation, InvocationError> {
+    build_call::<LibraryCallRequest, LibraryCallResponse>(builder, libfunc)
+}
+
+/// Builds instructions for a system call with the selector given as the const of the libfunc,
+/// calling a function of a contract or class - given as a single cell - with a call data array, and
+/// returning its result array.
+fn build_call<Request: SyscallRecord, Response: SyscallRecord>(
+    mut builder: CompiledInvocationBuilder<'_>,
+    libfunc: &SignatureAndConstConcreteLibFunc,
+) -> Result<CompiledInvocation, InvocationError> {
+    let system_slack = usize_as_i16(syscall_size::<Request, Response>());
+
+    let [
+        ReferenceValue { expression: expr_gas_builtin, .. },
+        ReferenceValue { expression: expr_system, .. },
+        ReferenceValue { expression: expr_target, .. },
+        _,
+    ] = unpack_refs::<4>(&builder)?;
+    let (gas_builtin, system, target, call_data) = (
+        expr_gas_builtin.try_unpack_single()?.to_deref()?,
+        expr_system.try_unpack_single()?.to_buffer(system_slack)?,
+        expr_target.try_unpack_single()?.to_deref()?,
+        ArrayView::from_arg(&builder, 3)?,
+    );
+
+    let mut casm_builder = CasmBuilder::default();
+    let system = casm_builder.add_var(system);
+    let gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
+    let target = casm_builder.add_var(ResOperand::Deref(target));
+    let call_data_start = casm_builder.add_var(ResOperand::Deref(call_data.start));
+    let call_data_end = casm_builder.add_var(call_data.end_operand());
+    let (request_gas, call_data_end) =
+        add_call_data_charge(&mut casm_builder, gas_builtin, call_data_start, call_data_end);
+    let syscall = add_syscall(
+        &mut casm_builder,
+        &mut builder.environment,
+        libfunc.c.clone(),
+        system,
+        request_gas,
+        &[
+            SyscallInput::Cell(target),
+            SyscallInput::Cell(call_data_start),
+            SyscallInput::Cell(call_data_end),
+        ],
+        2,
+    );
+    let [res_start, res_end] = syscall.outputs[..] else { unreachable!() };
 
-    let concrete_array_type = &libfunc.signature().param_signatures[2].ty;
-    let (gas_builtin, system, contract_address, call_data) = match builder.refs {
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
         [
-            ReferenceValue { expression: expr_gas_builtin, .. },
-            ReferenceValue { expression: expr_system, .. },
-            ReferenceValue { expression: expr_address, .. },
-            ReferenceValue { expression: expr_arr, .. },
-        ] => (
-            expr_gas_builtin.try_unpack_single()?.to_deref()?,
-            expr_system.try_unpack_single()?.to_buffer(8)?,
-            expr_address.try_unpack_single()?.to_deref()?,
-            ArrayView::try_get_view(expr_arr, &builder.program_info, concrete_array_type)
-                .map_err(|_| InvocationError::InvalidReferenceExpressionForArgument)?,
-        ),
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 2,
-                actual: refs.len(),
-            });
-        }
-    };
+            // Success branch - return (gas builtin, system, result_array)
+            chain!(syscall.builtin_outputs(), [vec![res_start, res_end]]).collect(),
+            // Failure branch - return (gas builtin, system, revert_reason, result_array)
+            chain!(syscall.failure_outputs(), [vec![res_start, res_end]]).collect(),
+        ],
+    )
+}
 
-    if call_data.end_offset != 0 {
-        return Err(InvocationError::InvalidReferenceExpressionForArgument);
-    }
+/// Builds instructions for StarkNet deploy system call.
+pub fn build_deploy(
+    mut builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let system_slack = usize_as_i16(syscall_size::<DeployRequest, DeployResponse>());
+
+    let [
+        ReferenceValue { expression: expr_gas_builtin, .. },
+        ReferenceValue { expression: expr_system, .. },
+        ReferenceValue { expression: expr_class_hash, .. },
+        ReferenceValue { expression: expr_salt, .. },
+        _,
+    ] = unpack_refs::<5>(&builder)?;
+    let (gas_builtin, system, class_hash, contract_address_salt, call_data) = (
+        expr_gas_builtin.try_unpack_single()?.to_deref()?,
+        expr_system.try_unpack_single()?.to_buffer(system_slack)?,
+        expr_class_hash.try_unpack_single()?.to_deref()?,
+        expr_salt.try_unpack_single()?.to_deref()?,
+        ArrayView::from_arg(&builder, 4)?,
+    );
 
     let mut casm_builder = CasmBuilder::default();
     let system = casm_builder.add_var(system);
-    let selector_imm = casm_builder.add_var(ResOperand::Immediate(selector_imm));
     let gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
-    let contract_address = casm_builder.add_var(ResOperand::Deref(contract_address));
+    let class_hash = casm_builder.add_var(ResOperand::Deref(class_hash));
+    let contract_address_salt = casm_builder.add_var(ResOperand::Deref(contract_address_salt));
     let call_data_start = casm_builder.add_var(ResOperand::Deref(call_data.start));
-    let call_data_end = casm_builder.add_var(ResOperand::Deref(call_data.end));
+    let call_data_end = casm_builder.add_var(call_data.end_operand());
+    let (request_gas, call_data_end) =
+        add_call_data_charge(&mut casm_builder, gas_builtin, call_data_start, call_data_end);
+    let syscall = add_syscall(
+        &mut casm_builder,
+        &mut builder.environment,
+        selector_value(DEPLOY_SELECTOR),
+        system,
+        request_gas,
+        &[
+            SyscallInput::Cell(class_hash),
+            SyscallInput::Cell(contract_address_salt),
+            SyscallInput::Cell(call_data_start),
+            SyscallInput::Cell(call_data_end),
+        ],
+        3,
+    );
+    let [contract_address, res_start, res_end] = syscall.outputs[..] else { unreachable!() };
+
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
+        [
+            // Success branch - return (gas builtin, system, contract_address, result_array)
+            chain!(syscall.builtin_outputs(), [vec![contract_address], vec![res_start, res_end]])
+                .collect(),
+            // Failure branch - return (gas builtin, system, revert_reason, result_array)
+            chain!(syscall.failure_outputs(), [vec![res_start, res_end]]).collect(),
+        ],
+    )
+}
+
+/// Charges [CALL_DATA_WORD_GAS] per word of the call data `[call_data_start, call_data_end)` from
+/// `gas_builtin`, returning the gas counter to pass to the system call, and the end of the call
+/// data in a temporary variable.
+///
+/// The end of the call data may still have a pending offset of the appended elements, so it is
+/// materialized before the length is computed from it. If the gas builtin is smaller than the
+/// charge, the returned gas counter wraps around to a value of at least `2**128`, which the system
+/// call handler fails as out of gas.
+fn add_call_data_charge(
+    casm_builder: &mut CasmBuilder,
+    gas_builtin: Var,
+    call_data_start: Var,
+    call_data_end: Var,
+) -> (Var, Var) {
     casm_build_extend! {casm_builder,
-        tempvar selector;
-        assert selector = selector_imm;
-        let original_system = system;
-        assert *(system++) = selector;
-         assert *(system++) = gas_builtin;
-        assert *(system++) = contract_address;
-        assert *(system++) = call_data_start;
-        assert *(system++) = call_data_end;
-        hint SystemCall { system: original_system };
-
-        let updated_gas_builtin = *(system++);
-        // `revert_reason` is 0 on success, nonzero on failure/revert.
-        tempvar revert_reason;
-        assert *(system++) = revert_reason;
-        let res_start = *(system++);
-        let res_end = *(system++);
-        jump Failure if revert_reason != 0;
+        const word_gas = BigInt::from(CALL_DATA_WORD_GAS);
+        tempvar stored_call_data_end;
+        assert stored_call_data_end = call_data_end;
+        tempvar call_data_len;
+        assert stored_call_data_end = call_data_start + call_data_len;
+        tempvar call_data_charge;
+        assert call_data_charge = call_data_len * word_gas;
+        tempvar request_gas;
+        assert gas_builtin = request_gas + call_data_charge;
     };
+    (request_gas, stored_call_data_end)
+}
 
-    let CasmBuildResult { instructions, awaiting_relocations, label_state, fallthrough_state } =
-        casm_builder.build();
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change, label_state["Failure"].ap_change]
-            .map(sierra_ap_change::ApChange::Known)
+/// Builds instructions for StarkNet send message to L1 system call.
+pub fn build_send_message_to_l1(
+    mut builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let system_slack =
+        usize_as_i16(syscall_size::<SendMessageToL1Request, SendMessageToL1Response>());
+
+    let [
+        ReferenceValue { expression: expr_gas_builtin, .. },
+        ReferenceValue { expression: expr_system, .. },
+        ReferenceValue { expression: expr_address, .. },
+        _,
+    ] = unpack_refs::<4>(&builder)?;
+    let (gas_builtin, system, to_address, payload) = (
+        expr_gas_builtin.try_unpack_single()?.to_deref()?,
+        expr_system.try_unpack_single()?.to_buffer(system_slack)?,
+        expr_address.try_unpack_single()?.to_deref()?,
+        ArrayView::from_arg(&builder, 3)?,
     );
 
-    let [relocation_index] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
-    Ok(builder.build(
-        instructions,
-        vec![RelocationEntry {
-            instruction_idx: *relocation_index,
-            relocation: Relocation::RelativeStatementId(failure_handle_statement_id),
-        }],
+    let mut casm_builder = CasmBuilder::default();
+    let system = casm_builder.add_var(system);
+    let gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
+    let to_address = casm_builder.add_var(ResOperand::Deref(to_address));
+    let payload_start = casm_builder.add_var(ResOperand::Deref(payload.start));
+    // As in `call_contract`, the end of the payload is materialized before being written.
+    let payload_end = casm_builder.add_var(payload.end_operand());
+    let syscall = add_syscall(
+        &mut casm_builder,
+        &mut builder.environment,
+        selector_value(SEND_MESSAGE_TO_L1_SELECTOR),
+        system,
+        gas_builtin,
+        &[
+            SyscallInput::Cell(to_address),
+            SyscallInput::Cell(payload_start),
+            SyscallInput::Value(payload_end),
+        ],
+        0,
+    );
+
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
         [
-            // Success branch - return (gas builtin, system, result_array)
-            vec![
-                ReferenceExpression::from_cell(CellExpression::from_res_operand(
-                    fallthrough_state.get_adjusted(updated_gas_builtin),
-                )),
-                ReferenceExpression::from_cell(CellExpression::from_res_operand(
-                    fallthrough_state.get_adjusted(system),
-                )),
-                ReferenceExpression {
-                    cells: vec![
-                        CellExpression::from_res_operand(fallthrough_state.get_adjusted(res_start)),
-                        CellExpression::from_res_operand(fallthrough_state.get_adjusted(res_end)),
-                    ],
-                },
-            ]
-            .into_iter(),
-            // Failure branch - return (gas builtin, system, revert_reason, result_array)
-            vec![
-                ReferenceExpression::from_cell(CellExpression::from_res_operand(
-                    label_state["Failure"].get_adjusted(updated_gas_builtin),
-                )),
-                ReferenceExpression::from_cell(CellExpression::from_res_operand(
-                    label_state["Failure"].get_adjusted(system),
-                )),
-                ReferenceExpression::from_cell(CellExpression::Deref(
-                    label_state["Failure"].get_adjusted_as_cell_ref(revert_reason),
-                )),
-                ReferenceExpression {
-                    cells: vec![
-                        CellExpression::from_res_operand(
-                            label_state["Failure"].get_adjusted(res_start),
-                        ),
-                        CellExpression::from_res_operand(
-                            label_state["Failure"].get_adjusted(res_end),
-                        ),
-                    ],
-                },
-            ]
-            .into_iter(),
-        ]
-        .into_iter(),
-    ))
+            // Success branch - return (gas builtin, system)
+            syscall.builtin_outputs(),
+            // Failure branch - return (gas builtin, system, revert_reason)
+            syscall.failure_outputs(),
+        ],
+    )
 }
 
 /// Handles the storage_address_const libfunc.
@@ -149,7 +246,90 @@ pub fn build_contract_address_const(
         return Err(InvocationError::InvalidGenericArg);
     }
 
-    Ok(builder.build_only_reference_changes(
+    builder.build_only_reference_changes(
         [ReferenceExpression::from_cell(CellExpression::Immediate(libfunc.c.clone()))].into_iter(),
-    ))
+    )
+}
+
+/// Handles the contract_addr_from_felt libfunc.
+pub fn build_contract_address_try_from_felt(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let [
+        ReferenceValue { expression: range_check_expression, .. },
+        ReferenceValue { expression: expr_value, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    let (range_check, value) = (
+        range_check_expression.try_unpack_single()?.to_buffer(4)?,
+        expr_value.try_unpack_single()?.to_deref()?,
+    );
+    let u128_bound: BigInt = BigInt::from(u128::MAX) + 1; // = 2**128.
+                                                          // A value is a valid address iff its high 128 bits are below 2**123 (= 2**251 / 2**128).
+    let x_bound: BigInt = BigInt::from(1) << 123;
+    // Represent the maximal possible value (PRIME - 1) as 2**128 * max_x + max_y.
+    let max_x: i128 = 10633823966279327296825105735305134080;
+    let max_y: i128 = 0;
+    let mut casm_builder = CasmBuilder::default();
+    // Defining params and constants.
+    let range_check = casm_builder.add_var(range_check);
+    let value = casm_builder.add_var(ResOperand::Deref(value));
+    let u128_limit = casm_builder.add_var(ResOperand::Immediate(u128_bound.clone()));
+    let le_max_y_fix = casm_builder.add_var(ResOperand::Immediate(u128_bound.clone() - max_y - 1));
+    let lt_max_x_fix = casm_builder.add_var(ResOperand::Immediate(u128_bound.clone() - max_x));
+    let minus_max_x = casm_builder.add_var(ResOperand::Immediate(BigInt::from(-max_x)));
+    let x_limit = casm_builder.add_var(ResOperand::Immediate(x_bound.clone()));
+    let lt_x_limit_fix = casm_builder.add_var(ResOperand::Immediate(u128_bound - &x_bound));
+    let minus_x_limit = casm_builder.add_var(ResOperand::Immediate(-x_bound));
+    casm_build_extend! {casm_builder,
+            tempvar x_2_128;
+            tempvar x_minus_max_x;
+            tempvar rced_value;
+            tempvar x;
+            tempvar y;
+            // Write value as 2**128 * x + y.
+            hint DivMod { lhs: value, rhs: u128_limit } into { quotient: x, remainder: y };
+            // Check x in [0, 2**128).
+            assert *(range_check++) = x;
+            // Check y in [0, 2**128).
+            assert *(range_check++) = y;
+            // Check that value = 2**128 * x + y (mod PRIME).
+            assert x_2_128 = x * u128_limit;
+            assert value = x_2_128 + y;
+            // Check that there is no overflow in the computation of 2**128 * x + y.
+            // Start by checking if x==max_x.
+            assert x_minus_max_x = x + minus_max_x;
+            jump XNotMaxX if x_minus_max_x != 0;
+            // If x == max_x, check that y <= max_y.
+            assert rced_value = y + le_max_y_fix;
+            jump WriteRcedValue;
+        XNotMaxX:
+            // If x != max_x, check that x < max_x.
+            assert rced_value = x + lt_max_x_fix;
+        WriteRcedValue:
+            // In both cases, range-check the calculated value.
+            assert *(range_check++) = rced_value;
+            // The representation is now unique, so value < 2**251 iff x < 2**123.
+            tempvar is_valid;
+            tempvar x_fixed;
+            hint TestLessThan { lhs: x, rhs: x_limit } into { dst: is_valid };
+            jump Valid if is_valid != 0;
+            // Check that x >= 2**123.
+            assert x_fixed = x + minus_x_limit;
+            assert *(range_check++) = x_fixed;
+            jump FailureHandle;
+        Valid:
+            // Check that x < 2**123.
+            assert x_fixed = x + lt_x_limit_fix;
+            assert *(range_check++) = x_fixed;
+    };
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["FailureHandle"],
+        [
+            // Success branch - return (range_check, value)
+            vec![vec![range_check], vec![value]],
+            // Failure branch - return (range_check)
+            vec![vec![range_check]],
+        ],
+    )
 }
@@ -1,10 +1,9 @@
 use casm::ap_change::ApChange;
-use casm::casm;
 use pretty_assertions::assert_eq;
 use sierra::program::StatementIdx;
 
 use crate::invocations::test_utils::{
-    compile_libfunc, ReducedBranchChanges, ReducedCompiledInvocation,
+    compare_libfunc_snapshot, compile_libfunc, ReducedBranchChanges, ReducedCompiledInvocation,
 };
 use crate::ref_expr;
 use crate::relocations::{Relocation, RelocationEntry};
@@ -15,50 +14,103 @@ fn test_call_contract() {
     let system = ref_expr!([fp + 2]);
     let contract_address = ref_expr!([fp + 3]);
     let call_data = ref_expr!([fp + 5], [fp + 6]);
+    compare_libfunc_snapshot(
+        "starknet/call_contract",
+        "call_contract_syscall",
+        vec![gas_builtin, system, contract_address, call_data],
+    );
+}
+
+#[test]
+fn test_call_contract_with_appended_call_data() {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    let contract_address = ref_expr!([fp + 3]);
+    // Two elements were appended to the call data since its end was last stored, so the real end
+    // of the call data is materialized before being written.
+    let call_data = ref_expr!([fp + 5], [fp + 6] + 2);
+    compare_libfunc_snapshot(
+        "starknet/call_contract_with_appended_call_data",
+        "call_contract_syscall",
+        vec![gas_builtin, system, contract_address, call_data],
+    );
+}
 
+#[test]
+fn test_deploy() {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    let class_hash = ref_expr!([fp + 3]);
+    let contract_address_salt = ref_expr!([fp + 4]);
+    // Three elements were appended to the constructor call data since its end was last stored.
+    let call_data = ref_expr!([fp + 5], [fp + 6] + 3);
+    compare_libfunc_snapshot(
+        "starknet/deploy",
+        "deploy_syscall",
+        vec![gas_builtin, system, class_hash, contract_address_salt, call_data],
+    );
+}
+
+#[test]
+fn test_library_call() {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    let class_hash = ref_expr!([fp + 3]);
+    let call_data = ref_expr!([fp + 5], [fp + 6]);
+    compare_libfunc_snapshot(
+        "starknet/library_call",
+        "library_call_syscall",
+        vec![gas_builtin, system, class_hash, call_data],
+    );
+}
+
+#[test]
+fn test_send_message_to_l1() {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    let to_address = ref_expr!([fp + 3]);
+    // A payload of 3 elements, all appended since the array was created.
+    let payload = ref_expr!([fp + 5], [fp + 5] + 3);
+    compare_libfunc_snapshot(
+        "starknet/send_message_to_l1",
+        "send_message_to_l1",
+        vec![gas_builtin, system, to_address, payload],
+    );
+}
+
+#[test]
+fn test_contract_address_to_felt() {
+    compare_libfunc_snapshot(
+        "starknet/contract_address_to_felt",
+        "contract_addr_to_felt",
+        vec![ref_expr!([fp + 3])],
+    );
+}
+
+#[test]
+fn test_contract_address_try_from_felt() {
+    let ReducedCompiledInvocation { relocations, results, .. } =
+        compile_libfunc("contract_addr_from_felt", vec![ref_expr!([fp + 1]), ref_expr!([fp + 2])]);
+    assert_eq!(
+        relocations,
+        vec![RelocationEntry {
+            instruction_idx: 13,
+            relocation: Relocation::RelativeStatementId(StatementIdx(1)),
+        }]
+    );
     assert_eq!(
-        compile_libfunc(
-            "call_contract_syscall",
-            vec![gas_builtin, system, contract_address, call_data]
-        ),
-        ReducedCompiledInvocation {
-            instructions: casm! {
-                [ap + 0] = 9221223673929037989702739452259u128, ap++;
-                [ap + -1] = [[fp + 2] + 0];
-                [fp + 1] = [[fp + 2] + 1];
-                [fp + 3] = [[fp + 2] + 2];
-                [fp + 5] = [[fp + 2] + 3];
-                [fp + 6] = [[fp + 2] + 4];
-                %{ syscall_handler.syscall(syscall_ptr=memory[fp + 2]) %}
-                [ap + 0] = [[fp + 2] + 6], ap++;
-                jmp rel 0 if [ap + -1] != 0;
-            }
-            .instructions,
-            relocations: vec![RelocationEntry {
-                instruction_idx: 7,
-                relocation: Relocation::RelativeStatementId(StatementIdx(1,),),
-            },],
-            results: vec![
-                // Success branch - return (gas builtin, system, result_array)
-                ReducedBranchChanges {
-                    refs: vec![
-                        ref_expr!([[fp + 2] + 5]),
-                        ref_expr!([fp + 2] + 9),
-                        ref_expr!([[fp + 2] + 7], [[fp + 2] + 8]),
-                    ],
-                    ap_change: ApChange::Known(2)
-                },
-                // Failure branch - return (gas builtin, system, revert_reason, result_array)
-                ReducedBranchChanges {
-                    refs: vec![
-                        ref_expr!([[fp + 2] + 5]),
-                        ref_expr!([fp + 2] + 9),
-                        ref_expr!([ap - 1]),
-                        ref_expr!([[fp + 2] + 7], [[fp + 2] + 8]),
-                    ],
-                    ap_change: ApChange::Known(2)
-                }
-            ]
-        }
+        results,
+        vec![
+            // Success branch - return (range_check, address)
+            ReducedBranchChanges {
+                refs: vec![ref_expr!([fp + 1] + 4), ref_expr!([fp + 2])],
+                ap_change: ApChange::Known(7)
+            },
+            // Failure branch - return (range_check)
+            ReducedBranchChanges {
+                refs: vec![ref_expr!([fp + 1] + 4)],
+                ap_change: ApChange::Known(7)
+            },
+        ]
     );
 }
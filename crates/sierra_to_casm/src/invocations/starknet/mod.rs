@@ -1,17 +1,50 @@
+use casm::ap_change::ApChange;
+use casm::builder::{CasmBuilder, Var};
+use casm::casm_build_extend;
+use casm::operand::{CellRef, DerefOrImmediate, Register, ResOperand};
+use itertools::{chain, Itertools};
 use num_bigint::BigInt;
 use sierra::extensions::consts::SignatureAndConstConcreteLibFunc;
+use sierra::extensions::felt::FeltBinaryOperator;
 use sierra::extensions::starknet::StarkNetConcreteLibFunc;
 
-use self::interoperability::{build_call_contract, build_contract_address_const};
+use self::interoperability::{
+    build_call_contract, build_contract_address_const, build_contract_address_try_from_felt,
+    build_deploy, build_library_call, build_send_message_to_l1,
+};
 use super::{CompiledInvocation, CompiledInvocationBuilder};
-use crate::invocations::InvocationError;
-use crate::references::{CellExpression, ReferenceExpression};
+use crate::environment::Environment;
+use crate::invocations::{misc, unpack_refs, InvocationError};
+use crate::references::{BinOpExpression, CellExpression, ReferenceExpression, ReferenceValue};
+use crate::syscall_records::{
+    GetBlockNumberRequest, GetBlockNumberResponse, GetBlockTimestampRequest,
+    GetBlockTimestampResponse, GetCallerAddressRequest, GetCallerAddressResponse,
+    GetContractAddressRequest, GetContractAddressResponse, GetSequencerAddressRequest,
+    GetSequencerAddressResponse, GetTxInfoRequest, GetTxInfoResponse, GET_BLOCK_NUMBER_SELECTOR,
+    GET_BLOCK_TIMESTAMP_SELECTOR, GET_CALLER_ADDRESS_SELECTOR, GET_CONTRACT_ADDRESS_SELECTOR,
+    GET_SEQUENCER_ADDRESS_SELECTOR, GET_TX_INFO_SELECTOR,
+};
 
 mod storage;
-use storage::{build_storage_read, build_storage_write};
+use storage::{
+    build_storage_base_address_from_felt, build_storage_read, build_storage_read_checked,
+    build_storage_read_multi, build_storage_read_unchecked, build_storage_write,
+    build_storage_write_checked, build_storage_write_unchecked,
+};
 
 mod interoperability;
 
+mod events;
+use events::build_emit_event;
+
+mod syscalls;
+use syscalls::{build_empty_args_syscall, build_keccak, build_replace_class};
+
+mod secp256k1;
+use secp256k1::{
+    build_secp256k1_add, build_secp256k1_get_xy, build_secp256k1_mul, build_secp256k1_new,
+};
+
 /// Builds instructions for Sierra array operations.
 pub fn build(
     libfunc: &StarkNetConcreteLibFunc,
@@ -22,15 +55,75 @@ pub fn build(
         StarkNetConcreteLibFunc::ContractAddressConst(libfunc) => {
             build_contract_address_const(builder, libfunc)
         }
+        StarkNetConcreteLibFunc::Deploy(_) => build_deploy(builder),
+        StarkNetConcreteLibFunc::LibraryCall(libfunc) => build_library_call(builder, libfunc),
+        StarkNetConcreteLibFunc::SendMessageToL1(_) => build_send_message_to_l1(builder),
+        StarkNetConcreteLibFunc::ContractAddressToFelt(_) => misc::build_identity(builder),
+        StarkNetConcreteLibFunc::ContractAddressTryFromFelt(_) => {
+            build_contract_address_try_from_felt(builder)
+        }
+        StarkNetConcreteLibFunc::EmitEvent(_) => build_emit_event(builder),
+        StarkNetConcreteLibFunc::GetCallerAddress(_) => {
+            build_empty_args_syscall::<GetCallerAddressRequest, GetCallerAddressResponse>(
+                builder,
+                GET_CALLER_ADDRESS_SELECTOR,
+            )
+        }
+        StarkNetConcreteLibFunc::GetContractAddress(_) => {
+            build_empty_args_syscall::<GetContractAddressRequest, GetContractAddressResponse>(
+                builder,
+                GET_CONTRACT_ADDRESS_SELECTOR,
+            )
+        }
+        StarkNetConcreteLibFunc::GetSequencerAddress(_) => {
+            build_empty_args_syscall::<GetSequencerAddressRequest, GetSequencerAddressResponse>(
+                builder,
+                GET_SEQUENCER_ADDRESS_SELECTOR,
+            )
+        }
+        StarkNetConcreteLibFunc::GetBlockNumber(_) => build_empty_args_syscall::<
+            GetBlockNumberRequest,
+            GetBlockNumberResponse,
+        >(builder, GET_BLOCK_NUMBER_SELECTOR),
+        StarkNetConcreteLibFunc::GetBlockTimestamp(_) => {
+            build_empty_args_syscall::<GetBlockTimestampRequest, GetBlockTimestampResponse>(
+                builder,
+                GET_BLOCK_TIMESTAMP_SELECTOR,
+            )
+        }
+        StarkNetConcreteLibFunc::GetTxInfo(_) => build_empty_args_syscall::<
+            GetTxInfoRequest,
+            GetTxInfoResponse,
+        >(builder, GET_TX_INFO_SELECTOR),
+        StarkNetConcreteLibFunc::ReplaceClass(_) => build_replace_class(builder),
+        StarkNetConcreteLibFunc::Keccak(_) => build_keccak(builder),
+        StarkNetConcreteLibFunc::Secp256k1New(_) => build_secp256k1_new(builder),
+        StarkNetConcreteLibFunc::Secp256k1Add(_) => build_secp256k1_add(builder),
+        StarkNetConcreteLibFunc::Secp256k1Mul(_) => build_secp256k1_mul(builder),
+        StarkNetConcreteLibFunc::Secp256k1GetXy(_) => build_secp256k1_get_xy(builder),
         StarkNetConcreteLibFunc::StorageRead(_) => build_storage_read(builder),
+        StarkNetConcreteLibFunc::StorageReadUnchecked(_) => build_storage_read_unchecked(builder),
         StarkNetConcreteLibFunc::StorageWrite(_) => build_storage_write(builder),
-        StarkNetConcreteLibFunc::StorageAddressConst(libfunc) => {
+        StarkNetConcreteLibFunc::StorageReadChecked(_) => build_storage_read_checked(builder),
+        StarkNetConcreteLibFunc::StorageReadMulti(libfunc) => {
+            build_storage_read_multi(builder, libfunc.size)
+        }
+        StarkNetConcreteLibFunc::StorageWriteChecked(_) => build_storage_write_checked(builder),
+        StarkNetConcreteLibFunc::StorageWriteUnchecked(_) => build_storage_write_unchecked(builder),
+        StarkNetConcreteLibFunc::StorageAddressConst(libfunc)
+        | StarkNetConcreteLibFunc::StorageBaseAddressConst(libfunc) => {
             build_storage_address_const(builder, libfunc)
         }
+        StarkNetConcreteLibFunc::StorageBaseAddressFromFelt(_) => {
+            build_storage_base_address_from_felt(builder)
+        }
+        StarkNetConcreteLibFunc::StorageAddressFromBaseAndOffset(_) => {
+            build_storage_address_from_base_and_offset(builder)
+        }
     }
 }
 
-/// Handles the storage_address_const libfunc.
+/// Handles the storage_address_const and storage_base_addr_const libfuncs.
 fn build_storage_address_const(
     builder: CompiledInvocationBuilder<'_>,
     libfunc: &SignatureAndConstConcreteLibFunc,
@@ -40,7 +133,141 @@ fn build_storage_address_const(
         return Err(InvocationError::InvalidGenericArg);
     }
 
-    Ok(builder.build_only_reference_changes(
+    builder.build_only_reference_changes(
         [ReferenceExpression::from_cell(CellExpression::Immediate(libfunc.c.clone()))].into_iter(),
-    ))
+    )
+}
+
+/// Handles the storage_addr_from_base libfunc.
+fn build_storage_address_from_base_and_offset(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let [
+        ReferenceValue { expression: expr_base, .. },
+        ReferenceValue { expression: expr_offset, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    let (base, offset) =
+        (expr_base.try_unpack_single()?.to_deref()?, expr_offset.try_unpack_single()?);
+    let offset = match offset {
+        CellExpression::Deref(offset) => DerefOrImmediate::Deref(offset),
+        CellExpression::Immediate(offset) => DerefOrImmediate::Immediate(offset),
+        _ => return Err(InvocationError::InvalidReferenceExpressionForArgument),
+    };
+    builder.build_only_reference_changes(
+        [ReferenceExpression::from_cell(CellExpression::BinOp(BinOpExpression {
+            op: FeltBinaryOperator::Add,
+            a: base,
+            b: offset,
+        }))]
+        .into_iter(),
+    )
+}
+
+/// An input of a system call, written into its request.
+enum SyscallInput {
+    /// A cell, written into the request as is.
+    Cell(Var),
+    /// A value that may not be a cell, copied into a temporary variable - allocated right after
+    /// the selector - which is written into the request.
+    Value(Var),
+}
+
+/// The variables of a system call added by [add_syscall].
+struct SyscallVars {
+    /// The system buffer, advanced past the system call.
+    system: Var,
+    /// The gas builtin, as updated by the system call.
+    updated_gas_builtin: Var,
+    /// The revert reason of the system call, in a temporary variable.
+    revert_reason: Var,
+    /// The response cells following the revert reason.
+    outputs: Vec<Var>,
+}
+impl SyscallVars {
+    /// Returns the outputs of the gas builtin and the system.
+    fn builtin_outputs(&self) -> Vec<Vec<Var>> {
+        vec![vec![self.updated_gas_builtin], vec![self.system]]
+    }
+
+    /// Returns the outputs of the gas builtin, the system and the revert reason.
+    fn failure_outputs(&self) -> Vec<Vec<Var>> {
+        chain!(self.builtin_outputs(), [vec![self.revert_reason]]).collect()
+    }
+}
+
+/// Adds a StarkNet system call with the given selector value to `casm_builder` - writing the
+/// selector, the gas builtin and `inputs` into the system buffer, invoking the system call, and
+/// jumping to the `Failure` label if it reverted.
+///
+/// The response is expected to hold the updated gas builtin and the revert reason, followed by
+/// `output_count` cells, which are returned as read from the response.
+///
+/// If a previous system call of the function stored the same selector in a cell of the frame, the
+/// selector is read from that cell instead of being stored again. Otherwise, the cell the selector
+/// is stored in is added to the frame constants of `environment`, for the following system calls.
+fn add_syscall(
+    casm_builder: &mut CasmBuilder,
+    environment: &mut Environment,
+    selector_value: BigInt,
+    system: Var,
+    gas_builtin: Var,
+    inputs: &[SyscallInput],
+    output_count: usize,
+) -> SyscallVars {
+    let inputs = inputs
+        .iter()
+        .map(|input| match *input {
+            SyscallInput::Cell(var) => var,
+            SyscallInput::Value(value) => {
+                casm_build_extend! {casm_builder,
+                    tempvar stored;
+                    assert stored = value;
+                };
+                stored
+            }
+        })
+        .collect_vec();
+    let known_selector_offset = environment.frame_constants.get(&selector_value).copied();
+    let selector = match known_selector_offset {
+        Some(offset) => {
+            // The cell the selector would have been stored in is left unused, so that the ap
+            // change is the same in both cases.
+            casm_builder.alloc_var();
+            casm_builder.add_var(ResOperand::Deref(CellRef { register: Register::FP, offset }))
+        }
+        None => casm_builder.add_const(selector_value.clone()),
+    };
+    casm_build_extend! {casm_builder,
+        let original_system = snapshot system;
+        assert *(system++) = (selector, gas_builtin);
+    };
+    if known_selector_offset.is_none() {
+        // The selector is now stored in a cell, which is in the frame at a known offset if the ap
+        // tracking is known - as ap is at `fp + ap_tracking` at the beginning of the libfunc.
+        let offset = match (environment.ap_tracking, casm_builder.initial_cell_ref(selector)) {
+            (ApChange::Known(ap_tracking), Some(CellRef { register: Register::AP, offset })) => {
+                i16::try_from(ap_tracking).ok().and_then(|start| start.checked_add(offset))
+            }
+            _ => None,
+        };
+        if let Some(offset) = offset {
+            environment.frame_constants.insert(selector_value, offset);
+        }
+    }
+    for input in inputs {
+        casm_builder.buffer_write_and_inc(system, input);
+    }
+    casm_build_extend! {casm_builder,
+        /// Invoke the system call.
+        hint SystemCall { system: original_system };
+        let (updated_gas_builtin, response_revert_reason) = *(system++);
+    };
+    let outputs = (0..output_count).map(|_| casm_builder.get_ref_and_inc(system)).collect();
+    casm_build_extend! {casm_builder,
+        // `revert_reason` is 0 on success, nonzero on failure/revert.
+        tempvar revert_reason;
+        assert revert_reason = response_revert_reason;
+        jump Failure if revert_reason != 0;
+    };
+    SyscallVars { system, updated_gas_builtin, revert_reason, outputs }
 }
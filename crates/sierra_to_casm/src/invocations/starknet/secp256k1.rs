@@ -0,0 +1,125 @@
+use casm::builder::CasmBuilder;
+use casm::casm_build_extend;
+use casm::operand::ResOperand;
+use itertools::{chain, Itertools};
+use utils::casts::usize_as_i16;
+
+use super::{
+    add_syscall, CompiledInvocation, CompiledInvocationBuilder, InvocationError, SyscallInput,
+};
+use crate::syscall_records::{
+    selector_value, syscall_size, Secp256k1AddRequest, Secp256k1AddResponse, Secp256k1GetXyRequest,
+    Secp256k1GetXyResponse, Secp256k1MulRequest, Secp256k1MulResponse, Secp256k1NewRequest,
+    Secp256k1NewResponse, SyscallRecord, SECP256K1_ADD_SELECTOR, SECP256K1_GET_XY_SELECTOR,
+    SECP256K1_MUL_SELECTOR, SECP256K1_NEW_SELECTOR,
+};
+
+#[cfg(test)]
+#[path = "secp256k1_test.rs"]
+mod test;
+
+/// Builds instructions for StarkNet secp256k1 new system call.
+pub fn build_secp256k1_new(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    build_secp256k1_syscall::<Secp256k1NewRequest, Secp256k1NewResponse>(
+        builder,
+        SECP256K1_NEW_SELECTOR,
+    )
+}
+
+/// Builds instructions for StarkNet secp256k1 add system call.
+pub fn build_secp256k1_add(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    build_secp256k1_syscall::<Secp256k1AddRequest, Secp256k1AddResponse>(
+        builder,
+        SECP256K1_ADD_SELECTOR,
+    )
+}
+
+/// Builds instructions for StarkNet secp256k1 mul system call.
+pub fn build_secp256k1_mul(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    build_secp256k1_syscall::<Secp256k1MulRequest, Secp256k1MulResponse>(
+        builder,
+        SECP256K1_MUL_SELECTOR,
+    )
+}
+
+/// Builds instructions for StarkNet secp256k1 get xy system call.
+pub fn build_secp256k1_get_xy(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    build_secp256k1_syscall::<Secp256k1GetXyRequest, Secp256k1GetXyResponse>(
+        builder,
+        SECP256K1_GET_XY_SELECTOR,
+    )
+}
+
+/// Builds instructions for a StarkNet secp256k1 system call.
+///
+/// The arguments following the gas builtin and the system are single cells, written in order
+/// after the gas counter of `Request`. `Response` must be laid out as the updated gas builtin and
+/// the revert reason, followed by the results of the system call, which are copied into new
+/// temporary variables on success.
+fn build_secp256k1_syscall<Request: SyscallRecord, Response: SyscallRecord>(
+    mut builder: CompiledInvocationBuilder<'_>,
+    selector: &str,
+) -> Result<CompiledInvocation, InvocationError> {
+    let system_slack = usize_as_i16(syscall_size::<Request, Response>());
+
+    // The gas builtin and the system, followed by an argument for every request cell but the gas
+    // counter.
+    let expected_args = Request::SIZE + 1;
+    if builder.refs.len() != expected_args {
+        return Err(builder.wrong_number_of_arguments(expected_args));
+    }
+    let gas_builtin = builder.refs[0].expression.try_unpack_single()?.to_deref()?;
+    let system = builder.refs[1].expression.try_unpack_single()?.to_buffer(system_slack)?;
+    let args = builder.refs[2..]
+        .iter()
+        .map(|arg| arg.expression.try_unpack_single()?.to_deref())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut casm_builder = CasmBuilder::default();
+    let system = casm_builder.add_var(system);
+    let gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
+    let args = args
+        .into_iter()
+        .map(|arg| SyscallInput::Cell(casm_builder.add_var(ResOperand::Deref(arg))))
+        .collect_vec();
+    let syscall = add_syscall(
+        &mut casm_builder,
+        &mut builder.environment,
+        selector_value(selector),
+        system,
+        gas_builtin,
+        &args,
+        Response::SIZE - 2,
+    );
+    let results = syscall
+        .outputs
+        .iter()
+        .map(|&response| {
+            casm_build_extend! {casm_builder,
+                tempvar result;
+                assert result = response;
+            };
+            result
+        })
+        .collect_vec();
+
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
+        [
+            // Success branch - return (gas builtin, system, results...)
+            chain!(syscall.builtin_outputs(), results.into_iter().map(|result| vec![result]))
+                .collect(),
+            // Failure branch - return (gas builtin, system, revert_reason)
+            syscall.failure_outputs(),
+        ],
+    )
+}
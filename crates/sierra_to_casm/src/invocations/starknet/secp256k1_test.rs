@@ -0,0 +1,56 @@
+use crate::invocations::test_utils::compare_libfunc_snapshot;
+use crate::ref_expr;
+
+#[test]
+fn test_secp256k1_new() {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    let x_low = ref_expr!([fp + 3]);
+    let x_high = ref_expr!([fp + 4]);
+    let y_low = ref_expr!([fp + 5]);
+    let y_high = ref_expr!([fp + 6]);
+    compare_libfunc_snapshot(
+        "starknet/secp256k1_new",
+        "secp256k1_new",
+        vec![gas_builtin, system, x_low, x_high, y_low, y_high],
+    );
+}
+
+#[test]
+fn test_secp256k1_add() {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    let p0 = ref_expr!([fp + 3]);
+    let p1 = ref_expr!([fp + 4]);
+    compare_libfunc_snapshot(
+        "starknet/secp256k1_add",
+        "secp256k1_add",
+        vec![gas_builtin, system, p0, p1],
+    );
+}
+
+#[test]
+fn test_secp256k1_mul() {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    let point = ref_expr!([fp + 3]);
+    let scalar_low = ref_expr!([fp + 4]);
+    let scalar_high = ref_expr!([fp + 5]);
+    compare_libfunc_snapshot(
+        "starknet/secp256k1_mul",
+        "secp256k1_mul",
+        vec![gas_builtin, system, point, scalar_low, scalar_high],
+    );
+}
+
+#[test]
+fn test_secp256k1_get_xy() {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    let point = ref_expr!([fp + 3]);
+    compare_libfunc_snapshot(
+        "starknet/secp256k1_get_xy",
+        "secp256k1_get_xy",
+        vec![gas_builtin, system, point],
+    );
+}
@@ -1,13 +1,20 @@
-use casm::builder::{CasmBuildResult, CasmBuilder};
+use casm::builder::{CasmBuildResult, CasmBuilder, Var};
 use casm::casm_build_extend;
-use casm::operand::ResOperand;
+use casm::hints::Hint;
+use casm::operand::{Operation, ResOperand};
+use itertools::{chain, zip_eq, Itertools};
 use num_bigint::BigInt;
-use sierra_ap_change::core_libfunc_ap_change;
+use utils::casts::usize_as_i16;
 
-use super::{CompiledInvocation, CompiledInvocationBuilder, InvocationError};
-use crate::invocations::get_non_fallthrough_statement_id;
+use super::{
+    add_syscall, unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError,
+    SyscallInput,
+};
 use crate::references::{CellExpression, ReferenceExpression, ReferenceValue};
-use crate::relocations::{Relocation, RelocationEntry};
+use crate::syscall_records::{
+    selector_value, syscall_size, StorageReadRequest, StorageReadResponse, StorageWriteRequest,
+    StorageWriteResponse, STORAGE_READ_SELECTOR, STORAGE_WRITE_SELECTOR,
+};
 
 #[cfg(test)]
 #[path = "storage_test.rs"]
@@ -17,149 +24,552 @@ mod test;
 pub fn build_storage_read(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let selector_imm = BigInt::from_bytes_le(num_bigint::Sign::Plus, "storage_read".as_bytes());
-    let (system, storage_address) = match builder.refs {
-        [
-            ReferenceValue { expression: expr_system, .. },
-            ReferenceValue { expression: expr_address, .. },
-        ] => (
-            expr_system.try_unpack_single()?.to_buffer(3)?,
-            expr_address.try_unpack_single()?.to_deref()?,
-        ),
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 2,
-                actual: refs.len(),
-            });
+    build_storage_read_ex(builder, false)
+}
+
+/// Builds instructions for StarkNet read system call, checking the storage address first.
+pub fn build_storage_read_checked(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    build_storage_read_ex(builder, true)
+}
+
+/// Builds instructions for StarkNet read system call, checking the storage address first if
+/// `checked` - in which case the first argument is a range check.
+fn build_storage_read_ex(
+    mut builder: CompiledInvocationBuilder<'_>,
+    checked: bool,
+) -> Result<CompiledInvocation, InvocationError> {
+    let system_slack = usize_as_i16(syscall_size::<StorageReadRequest, StorageReadResponse>());
+    let (range_check, refs) = split_range_check(builder.refs, checked)?;
+    let (gas_builtin, system, address_domain, storage_address) = match refs {
+        [ReferenceValue { expression: expr_gas_builtin, .. }, ReferenceValue { expression: expr_system, .. }, ReferenceValue { expression: expr_address_domain, .. }, ReferenceValue { expression: expr_address, .. }] => {
+            (
+                expr_gas_builtin.try_unpack_single()?.to_deref()?,
+                expr_system.try_unpack_single()?.to_buffer(system_slack)?,
+                expr_address_domain.try_unpack_single()?.to_deref()?,
+                expr_address.try_unpack_single()?.to_res_operand()?,
+            )
         }
+        _ => return Err(builder.wrong_number_of_arguments(4 + usize::from(checked))),
     };
 
     let mut casm_builder = CasmBuilder::default();
+    let range_check = range_check.map(|range_check| casm_builder.add_var(range_check));
     let system = casm_builder.add_var(system);
+    let gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
+    let address_domain = casm_builder.add_var(ResOperand::Deref(address_domain));
+    let storage_address = store_storage_address(&mut casm_builder, storage_address);
+    if let Some(range_check) = range_check {
+        check_storage_address(&mut casm_builder, range_check, storage_address);
+    }
+    let syscall = add_syscall(
+        &mut casm_builder,
+        &mut builder.environment,
+        selector_value(STORAGE_READ_SELECTOR),
+        system,
+        gas_builtin,
+        &[SyscallInput::Cell(address_domain), SyscallInput::Cell(storage_address)],
+        1,
+    );
+    let response_value = syscall.outputs[0];
+    casm_build_extend! {casm_builder,
+        tempvar read_value;
+        assert read_value = response_value;
+    };
+
+    let range_check_outputs = range_check.map(|range_check| vec![range_check]);
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
+        [
+            // Success branch - return ([range check], gas builtin, system, read_value)
+            chain!(range_check_outputs.clone(), syscall.builtin_outputs(), [vec![read_value]])
+                .collect(),
+            // Failure branch - return ([range check], gas builtin, system, revert_reason)
+            chain!(range_check_outputs, syscall.failure_outputs()).collect(),
+        ],
+    )
+}
+
+/// Builds instructions for the unchecked StarkNet read system call, which ignores the revert
+/// reason of the syscall.
+pub fn build_storage_read_unchecked(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    build_storage_reads(builder, 1, false)
+}
+
+/// Builds instructions for reading a value of `size` cells from storage, with a StarkNet read
+/// system call for each cell - at the given address plus the index of the cell. Jumps to `Failure`
+/// on the first read that reverts, with the index of the read and its revert reason.
+pub fn build_storage_read_multi(
+    builder: CompiledInvocationBuilder<'_>,
+    size: usize,
+) -> Result<CompiledInvocation, InvocationError> {
+    build_storage_reads(builder, size, true)
+}
+
+/// Builds instructions for reading `size` cells from storage, with a StarkNet read system call for
+/// each cell - at the given address plus the index of the cell.
+/// If `branch_on_failure`, jumps to `Failure` on the first read that reverts, as described in
+/// [build_storage_read_multi]. Otherwise, the revert reasons of the syscalls are ignored.
+fn build_storage_reads(
+    builder: CompiledInvocationBuilder<'_>,
+    size: usize,
+    branch_on_failure: bool,
+) -> Result<CompiledInvocation, InvocationError> {
+    let selector_imm = selector_value(STORAGE_READ_SELECTOR);
+    let system_slack =
+        usize_as_i16(size * syscall_size::<StorageReadRequest, StorageReadResponse>());
+    let [
+        ReferenceValue { expression: expr_gas_builtin, .. },
+        ReferenceValue { expression: expr_system, .. },
+        ReferenceValue { expression: expr_address_domain, .. },
+        ReferenceValue { expression: expr_address, .. },
+    ] = unpack_refs::<4>(&builder)?;
+    let (gas_builtin, system, address_domain, storage_address) = (
+        expr_gas_builtin.try_unpack_single()?.to_deref()?,
+        expr_system.try_unpack_single()?.to_buffer(system_slack)?,
+        expr_address_domain.try_unpack_single()?.to_deref()?,
+        expr_address.try_unpack_single()?.to_res_operand()?,
+    );
+
+    let mut casm_builder = CasmBuilder::default();
+    let mut system = casm_builder.add_var(system);
     let selector_imm = casm_builder.add_var(ResOperand::Immediate(selector_imm));
-    let storage_address = casm_builder.add_var(ResOperand::Deref(storage_address));
+    let mut gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
+    let address_domain = casm_builder.add_var(ResOperand::Deref(address_domain));
+    let storage_address = store_storage_address(&mut casm_builder, storage_address);
     casm_build_extend! {casm_builder,
-        let original_system = system;
         tempvar selector;
         assert selector = selector_imm;
-        assert *(system++) = selector;
-        assert *(system++) = storage_address;
-        hint SystemCall { system: original_system };
-        tempvar read_value;
-        assert *(system++) = read_value;
     };
+    // Making all the requests first, so that the read values are allocated last, and are therefore
+    // contiguous at the top of the stack.
+    let mut responses = vec![];
+    let mut failed_reads = vec![];
+    for i in 0..size {
+        let address = if i == 0 {
+            storage_address
+        } else {
+            let offset = casm_builder.add_var(ResOperand::Immediate(BigInt::from(i)));
+            let address_value = casm_builder.bin_op(Operation::Add, storage_address, offset);
+            let address = casm_builder.alloc_var();
+            casm_builder.assert_vars_eq(address, address_value);
+            // The gas counter of the previous response is copied, as it can not be written into
+            // the request directly.
+            let gas_counter = casm_builder.alloc_var();
+            casm_builder.assert_vars_eq(gas_counter, gas_builtin);
+            gas_builtin = gas_counter;
+            address
+        };
+        // Every read advances a system buffer of its own, starting where the buffer of the
+        // previous read ended - so the buffer of a failed read is not advanced by the next reads.
+        system = casm_builder.snapshot_var(system);
+        let original_system = casm_builder.snapshot_var(system);
+        casm_builder.buffer_write_and_inc(system, selector);
+        casm_builder.buffer_write_and_inc(system, gas_builtin);
+        casm_builder.buffer_write_and_inc(system, address_domain);
+        casm_builder.buffer_write_and_inc(system, address);
+        casm_builder.add_buffer_hint(|system| Hint::SystemCall { system }, original_system);
+        gas_builtin = casm_builder.get_ref_and_inc(system);
+        let response_revert_reason = casm_builder.get_ref_and_inc(system);
+        responses.push(casm_builder.get_ref_and_inc(system));
+        if branch_on_failure {
+            casm_build_extend! {casm_builder,
+                tempvar revert_reason;
+                assert revert_reason = response_revert_reason;
+            };
+            casm_builder.jump_nz(revert_reason, format!("ReadFailed{i}"));
+            failed_reads.push(ReadVars { gas_builtin, system, revert_reason });
+        }
+    }
+    let read_values: Vec<_> = responses
+        .into_iter()
+        .map(|response| {
+            let read_value = casm_builder.alloc_var();
+            casm_builder.assert_vars_eq(read_value, response);
+            read_value
+        })
+        .collect();
 
-    let CasmBuildResult { instructions, fallthrough_state, .. } = casm_builder.build();
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [sierra_ap_change::ApChange::Known(fallthrough_state.ap_change)]
-    );
-    Ok(builder.build(
-        instructions,
-        vec![],
-        [vec![
-            ReferenceExpression::from_cell(CellExpression::from_res_operand(
-                fallthrough_state.get_adjusted(system),
-            )),
-            ReferenceExpression::from_cell(CellExpression::Deref(
-                fallthrough_state.get_adjusted_as_cell_ref(read_value),
-            )),
-        ]
-        .into_iter()]
-        .into_iter(),
-    ))
+    let success_outputs = vec![vec![gas_builtin], vec![system], read_values];
+    if !branch_on_failure {
+        return builder.build_from_casm_builder(casm_builder, &[], [success_outputs]);
+    }
+    let failure_outputs = add_storage_read_failures(&mut casm_builder, failed_reads);
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
+        [
+            // Success branch - return (gas builtin, system, read values)
+            success_outputs,
+            // Failure branch - return (gas builtin, system, failed read index, revert reason)
+            failure_outputs,
+        ],
+    )
+}
+
+/// The variables of a read made by [build_storage_reads], right after the read - the outputs of the
+/// failure branch if the read reverts.
+struct ReadVars {
+    gas_builtin: Var,
+    system: Var,
+    /// The revert reason of the read, in a temporary variable.
+    revert_reason: Var,
+}
+
+/// Adds the failure branch of [build_storage_read_multi], after the code of its success branch.
+/// The jump of every read that reverts, to `ReadFailed<index>`, lands on code copying its gas
+/// builtin, system, index and revert reason into temporary variables, and jumping to `Failure`.
+/// These copies are made at the same cells for all the reads - after padding the stack to the
+/// height it had at the last read - so all the reads share them as the outputs of the branch,
+/// which are returned.
+fn add_storage_read_failures(
+    casm_builder: &mut CasmBuilder,
+    failed_reads: Vec<ReadVars>,
+) -> Vec<Vec<Var>> {
+    let stack_heights = failed_reads
+        .iter()
+        .map(|read| casm_builder.initial_cell_ref(read.revert_reason).unwrap().offset)
+        .collect_vec();
+    let max_stack_height = *stack_heights.iter().max().unwrap();
+    casm_build_extend! {casm_builder, jump Done;};
+    let mut outputs: Option<Vec<Var>> = None;
+    for (i, (read, stack_height)) in zip_eq(failed_reads, stack_heights).enumerate() {
+        casm_builder.label(format!("ReadFailed{i}"));
+        let padding = usize::try_from(max_stack_height - stack_height).unwrap();
+        if padding > 0 {
+            for _ in 0..padding {
+                casm_builder.alloc_var();
+            }
+            casm_builder.add_ap(padding);
+        }
+        let ReadVars { gas_builtin, system, revert_reason } = read;
+        let index = casm_builder.add_var(ResOperand::Immediate(BigInt::from(i)));
+        casm_build_extend! {casm_builder,
+            tempvar failed_gas_builtin;
+            assert failed_gas_builtin = gas_builtin;
+            tempvar failed_system;
+            assert failed_system = system;
+            tempvar failed_index;
+            assert failed_index = index;
+            tempvar failed_revert_reason;
+            assert failed_revert_reason = revert_reason;
+        };
+        let copies = [failed_gas_builtin, failed_system, failed_index, failed_revert_reason];
+        match &outputs {
+            Some(outputs) => {
+                for (output, copy) in zip_eq(outputs, copies) {
+                    casm_builder.rebind_var(*output, copy);
+                }
+            }
+            None => outputs = Some(copies.to_vec()),
+        }
+        casm_build_extend! {casm_builder, jump Failure;};
+    }
+    casm_build_extend! {casm_builder, Done:};
+    outputs.unwrap().into_iter().map(|output| vec![output]).collect()
 }
 
 /// Builds instructions for StarkNet write system call.
 pub fn build_storage_write(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder);
-    let selector_imm = BigInt::from_bytes_le(num_bigint::Sign::Plus, "storage_write".as_bytes());
+    build_storage_write_ex(builder, false)
+}
 
-    let (gas_builtin, system, storage_address, value) = match builder.refs {
-        [
-            ReferenceValue { expression: expr_gas_builtin, .. },
-            ReferenceValue { expression: expr_system, .. },
-            ReferenceValue { expression: expr_address, .. },
-            ReferenceValue { expression: expr_value, .. },
-        ] => (
-            expr_gas_builtin.try_unpack_single()?.to_deref()?,
-            expr_system.try_unpack_single()?.to_buffer(6)?,
-            expr_address.try_unpack_single()?.to_deref()?,
-            expr_value.try_unpack_single()?.to_deref()?,
-        ),
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 4,
-                actual: refs.len(),
-            });
+/// Builds instructions for StarkNet write system call, checking the storage address first.
+pub fn build_storage_write_checked(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    build_storage_write_ex(builder, true)
+}
+
+/// Builds instructions for StarkNet write system call, checking the storage address first if
+/// `checked` - in which case the first argument is a range check.
+fn build_storage_write_ex(
+    mut builder: CompiledInvocationBuilder<'_>,
+    checked: bool,
+) -> Result<CompiledInvocation, InvocationError> {
+    let system_slack = usize_as_i16(syscall_size::<StorageWriteRequest, StorageWriteResponse>());
+
+    let (range_check, refs) = split_range_check(builder.refs, checked)?;
+    let (gas_builtin, system, address_domain, storage_address, value) = match refs {
+        [ReferenceValue { expression: expr_gas_builtin, .. }, ReferenceValue { expression: expr_system, .. }, ReferenceValue { expression: expr_address_domain, .. }, ReferenceValue { expression: expr_address, .. }, ReferenceValue { expression: expr_value, .. }] => {
+            (
+                expr_gas_builtin.try_unpack_single()?.to_deref()?,
+                expr_system.try_unpack_single()?.to_buffer(system_slack)?,
+                expr_address_domain.try_unpack_single()?.to_deref()?,
+                expr_address.try_unpack_single()?.to_res_operand()?,
+                expr_value.try_unpack_single()?.to_deref()?,
+            )
         }
+        _ => return Err(builder.wrong_number_of_arguments(5 + usize::from(checked))),
     };
     let mut casm_builder = CasmBuilder::default();
+    let range_check = range_check.map(|range_check| casm_builder.add_var(range_check));
+    let system = casm_builder.add_var(system);
+    let gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
+    let address_domain = casm_builder.add_var(ResOperand::Deref(address_domain));
+    let storage_address = store_storage_address(&mut casm_builder, storage_address);
+    if let Some(range_check) = range_check {
+        check_storage_address(&mut casm_builder, range_check, storage_address);
+    }
+    let value = casm_builder.add_var(ResOperand::Deref(value));
+    // The reserved cell of the response is ignored.
+    let syscall = add_syscall(
+        &mut casm_builder,
+        &mut builder.environment,
+        selector_value(STORAGE_WRITE_SELECTOR),
+        system,
+        gas_builtin,
+        &[
+            SyscallInput::Cell(address_domain),
+            SyscallInput::Cell(storage_address),
+            SyscallInput::Cell(value),
+        ],
+        1,
+    );
+
+    let range_check_outputs = range_check.map(|range_check| vec![range_check]);
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
+        [
+            // Success branch - return ([range check], gas builtin, system)
+            chain!(range_check_outputs.clone(), syscall.builtin_outputs()).collect(),
+            // Failure branch - return ([range check], gas builtin, system, revert_reason)
+            chain!(range_check_outputs, syscall.failure_outputs()).collect(),
+        ],
+    )
+}
+
+/// Builds instructions for the unchecked StarkNet write system call, which ignores the revert
+/// reason of the syscall.
+pub fn build_storage_write_unchecked(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let selector_imm = selector_value(STORAGE_WRITE_SELECTOR);
+    let system_slack = usize_as_i16(syscall_size::<StorageWriteRequest, StorageWriteResponse>());
+
+    let [
+        ReferenceValue { expression: expr_gas_builtin, .. },
+        ReferenceValue { expression: expr_system, .. },
+        ReferenceValue { expression: expr_address_domain, .. },
+        ReferenceValue { expression: expr_address, .. },
+        ReferenceValue { expression: expr_value, .. },
+    ] = unpack_refs::<5>(&builder)?;
+    let (gas_builtin, system, address_domain, storage_address, value) = (
+        expr_gas_builtin.try_unpack_single()?.to_deref()?,
+        expr_system.try_unpack_single()?.to_buffer(system_slack)?,
+        expr_address_domain.try_unpack_single()?.to_deref()?,
+        expr_address.try_unpack_single()?.to_res_operand()?,
+        expr_value.try_unpack_single()?.to_deref()?,
+    );
+    let mut casm_builder = CasmBuilder::default();
     let system = casm_builder.add_var(system);
     let selector_imm = casm_builder.add_var(ResOperand::Immediate(selector_imm));
     let gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
-    let storage_address = casm_builder.add_var(ResOperand::Deref(storage_address));
+    let address_domain = casm_builder.add_var(ResOperand::Deref(address_domain));
+    let storage_address = store_storage_address(&mut casm_builder, storage_address);
     let value = casm_builder.add_var(ResOperand::Deref(value));
     casm_build_extend! {casm_builder,
-        let original_system = system;
+        let original_system = snapshot system;
         tempvar selector;
         assert selector = selector_imm;
         assert *(system++) = selector;
         assert *(system++) = gas_builtin;
+        assert *(system++) = address_domain;
         assert *(system++) = storage_address;
         assert *(system++) = value;
         hint SystemCall { system: original_system };
         let updated_gas_builtin = *(system++);
-        // `revert_reason` is 0 on success, nonzero on failure/revert.
-        tempvar revert_reason;
-        assert *(system++) = revert_reason;
+        // The revert reason and the reserved cell are skipped.
+        let _revert_reason = *(system++);
         let _ignore = *(system++);
-        jump Failure if revert_reason != 0;
     };
 
-    let CasmBuildResult { instructions, awaiting_relocations, label_state, fallthrough_state } =
-        casm_builder.build();
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change, label_state["Failure"].ap_change]
-            .map(sierra_ap_change::ApChange::Known)
+    let CasmBuildResult { instructions, fallthrough_state, .. } = casm_builder.build();
+    builder.check_ap_changes([fallthrough_state.full_ap_change()])?;
+    builder.build(
+        instructions,
+        vec![],
+        [vec![
+            ReferenceExpression::from_cell(CellExpression::from_res_operand(
+                fallthrough_state.get_adjusted(updated_gas_builtin),
+            )),
+            ReferenceExpression::from_cell(CellExpression::from_res_operand(
+                fallthrough_state.get_adjusted(system),
+            )),
+        ]
+        .into_iter()]
+        .into_iter(),
+    )
+}
+
+/// Handles the storage_base_from_felt libfunc, reducing the felt modulo `2**251 - 256`.
+pub fn build_storage_base_address_from_felt(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let [
+        ReferenceValue { expression: expr_range_check, .. },
+        ReferenceValue { expression: expr_value, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    let (range_check, value) = (
+        expr_range_check.try_unpack_single()?.to_buffer(STORAGE_ADDRESS_CHECK_RANGE_CHECKS)?,
+        expr_value.try_unpack_single()?.to_deref()?,
     );
-    let [relocation_index] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
-    Ok(builder.build(
+    let u128_bound: BigInt = BigInt::from(u128::MAX) + 1; // = 2**128.
+    let addr_bound: BigInt = (BigInt::from(1) << 251) - 256;
+    // A value at least `addr_bound` is reduced to `value - addr_bound`, which is at most
+    // PRIME - addr_bound - 1 = 17 * 2**192 + 256. Represent it as 2**128 * max_x + max_y.
+    let max_x: BigInt = BigInt::from(17) << 64;
+    let max_y: BigInt = BigInt::from(256);
+    let mut casm_builder = CasmBuilder::default();
+    let range_check = casm_builder.add_var(range_check);
+    let value = casm_builder.add_var(ResOperand::Deref(value));
+    let addr_limit = casm_builder.add_var(ResOperand::Immediate(addr_bound.clone()));
+    let minus_addr_limit = casm_builder.add_var(ResOperand::Immediate(-addr_bound));
+    let u128_limit = casm_builder.add_var(ResOperand::Immediate(u128_bound.clone()));
+    let le_max_y_fix = casm_builder.add_var(ResOperand::Immediate(u128_bound.clone() - max_y - 1));
+    let lt_max_x_fix = casm_builder.add_var(ResOperand::Immediate(u128_bound - max_x.clone()));
+    let minus_max_x = casm_builder.add_var(ResOperand::Immediate(-max_x));
+    casm_build_extend! {casm_builder,
+            tempvar address;
+            tempvar is_small;
+            hint TestLessThan { lhs: value, rhs: addr_limit } into { dst: is_small };
+            jump IsSmall if is_small != 0;
+            // The value is in [addr_bound, PRIME), and is reduced by subtracting the bound.
+            assert address = value + minus_addr_limit;
+            tempvar x_2_128;
+            tempvar x_minus_max_x;
+            tempvar rced_value;
+            tempvar x;
+            tempvar y;
+            // Write the address as 2**128 * x + y.
+            hint DivMod { lhs: address, rhs: u128_limit } into { quotient: x, remainder: y };
+            // Check x in [0, 2**128).
+            assert *(range_check++) = x;
+            // Check y in [0, 2**128).
+            assert *(range_check++) = y;
+            // Check that address = 2**128 * x + y (mod PRIME).
+            assert x_2_128 = x * u128_limit;
+            assert address = x_2_128 + y;
+            // Check that 2**128 * x + y is at most PRIME - addr_bound - 1, so that the value is
+            // indeed at least addr_bound. Start by checking if x==max_x.
+            assert x_minus_max_x = x + minus_max_x;
+            jump ShiftedXNotMaxX if x_minus_max_x != 0;
+            // If x == max_x, check that y <= max_y.
+            assert rced_value = y + le_max_y_fix;
+            jump ShiftedWriteRcedValue;
+        ShiftedXNotMaxX:
+            // If x != max_x, check that x < max_x.
+            assert rced_value = x + lt_max_x_fix;
+        ShiftedWriteRcedValue:
+            // In both cases, range-check the calculated value.
+            assert *(range_check++) = rced_value;
+            jump Done;
+        IsSmall:
+            // The value is below addr_bound, and is used as is.
+            assert address = value;
+    };
+    check_storage_address(&mut casm_builder, range_check, address);
+    casm_build_extend! {casm_builder, Done:};
+    let CasmBuildResult { instructions, fallthrough_state, .. } = casm_builder.build();
+    builder.check_ap_changes([fallthrough_state.full_ap_change()])?;
+    builder.build(
         instructions,
-        vec![RelocationEntry {
-            instruction_idx: *relocation_index,
-            relocation: Relocation::RelativeStatementId(failure_handle_statement_id),
-        }],
-        [
-            // Success branch - return (gas builtin, system)
-            vec![
-                ReferenceExpression::from_cell(CellExpression::from_res_operand(
-                    fallthrough_state.get_adjusted(updated_gas_builtin),
-                )),
-                ReferenceExpression::from_cell(CellExpression::from_res_operand(
-                    fallthrough_state.get_adjusted(system),
-                )),
-            ]
-            .into_iter(),
-            // Failure branch - return (gas builtin, system, revert_reason)
-            vec![
-                ReferenceExpression::from_cell(CellExpression::from_res_operand(
-                    label_state["Failure"].get_adjusted(updated_gas_builtin),
-                )),
-                ReferenceExpression::from_cell(CellExpression::from_res_operand(
-                    label_state["Failure"].get_adjusted(system),
-                )),
-                ReferenceExpression::from_cell(CellExpression::Deref(
-                    label_state["Failure"].get_adjusted_as_cell_ref(revert_reason),
-                )),
-            ]
-            .into_iter(),
+        vec![],
+        [vec![
+            ReferenceExpression::from_cell(CellExpression::from_res_operand(
+                fallthrough_state.get_adjusted(range_check),
+            )),
+            ReferenceExpression::from_cell(CellExpression::Deref(
+                fallthrough_state.get_adjusted_as_cell_ref(address),
+            )),
         ]
+        .into_iter()]
         .into_iter(),
-    ))
+    )
+}
+
+/// Adds the storage address to the builder, copying it into a temporary variable first if it is not
+/// a plain dereference, as the system call request requires a cell.
+fn store_storage_address(casm_builder: &mut CasmBuilder, storage_address: ResOperand) -> Var {
+    if matches!(storage_address, ResOperand::Deref(_)) {
+        return casm_builder.add_var(storage_address);
+    }
+    let storage_address = casm_builder.add_var(storage_address);
+    casm_build_extend! {casm_builder,
+        tempvar stored_address;
+        assert stored_address = storage_address;
+    };
+    stored_address
+}
+
+/// Splits the range check argument of the checked storage syscalls from the rest of the arguments.
+/// Returns no range check if not `checked`.
+fn split_range_check(
+    refs: &[ReferenceValue],
+    checked: bool,
+) -> Result<(Option<ResOperand>, &[ReferenceValue]), InvocationError> {
+    match refs {
+        [ReferenceValue { expression: expr_range_check, .. }, refs @ ..] if checked => Ok((
+            Some(
+                expr_range_check
+                    .try_unpack_single()?
+                    .to_buffer(STORAGE_ADDRESS_CHECK_RANGE_CHECKS)?,
+            ),
+            refs,
+        )),
+        _ => Ok((None, refs)),
+    }
+}
+
+/// The number of range checks used by [check_storage_address].
+const STORAGE_ADDRESS_CHECK_RANGE_CHECKS: i16 = 3;
+
+/// Adds instructions verifying that `storage_address` is a valid storage address - below
+/// `2**251 - 256`. The storage address must be a cell.
+fn check_storage_address(casm_builder: &mut CasmBuilder, range_check: Var, storage_address: Var) {
+    let u128_bound: BigInt = BigInt::from(u128::MAX) + 1; // = 2**128.
+                                                          // Represent the maximal valid address (2**251 - 257) as 2**128 * max_x + max_y.
+    let max_x: BigInt = (BigInt::from(1) << 123) - 1;
+    let max_y: BigInt = u128_bound.clone() - 257;
+    let u128_limit = casm_builder.add_var(ResOperand::Immediate(u128_bound.clone()));
+    let le_max_y_fix = casm_builder.add_var(ResOperand::Immediate(u128_bound.clone() - max_y - 1));
+    let lt_max_x_fix = casm_builder.add_var(ResOperand::Immediate(u128_bound - max_x.clone()));
+    let minus_max_x = casm_builder.add_var(ResOperand::Immediate(-max_x));
+    casm_build_extend! {casm_builder,
+            // Allocating all values required so that `x` and `y` would be last.
+            tempvar x_2_128;
+            tempvar x_minus_max_x;
+            tempvar rced_value;
+            tempvar x;
+            tempvar y;
+            // Write the address as 2**128 * x + y.
+            hint DivMod { lhs: storage_address, rhs: u128_limit } into { quotient: x, remainder: y };
+            // Check x in [0, 2**128).
+            assert *(range_check++) = x;
+            // Check y in [0, 2**128).
+            assert *(range_check++) = y;
+            // Check that address = 2**128 * x + y (mod PRIME).
+            assert x_2_128 = x * u128_limit;
+            assert storage_address = x_2_128 + y;
+            // Check that 2**128 * x + y is at most the maximal valid address, which also rules out
+            // an overflow in its computation. Start by checking if x==max_x.
+            assert x_minus_max_x = x + minus_max_x;
+            jump XNotMaxX if x_minus_max_x != 0;
+            // If x == max_x, check that y <= max_y.
+            assert rced_value = y + le_max_y_fix;
+            jump WriteRcedValue;
+        XNotMaxX:
+            // If x != max_x, check that x < max_x.
+            assert rced_value = x + lt_max_x_fix;
+        WriteRcedValue:
+            // In both cases, range-check the calculated value.
+            assert *(range_check++) = rced_value;
+    };
 }
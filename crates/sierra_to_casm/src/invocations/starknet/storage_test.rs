@@ -1,80 +1,276 @@
 use casm::ap_change::ApChange;
-use casm::casm;
+use indoc::indoc;
 use pretty_assertions::assert_eq;
-use sierra::program::StatementIdx;
 
 use crate::invocations::test_utils::{
-    compile_libfunc, ReducedBranchChanges, ReducedCompiledInvocation,
+    compare_libfunc_snapshot, compile_libfunc, compile_libfunc_reference_deltas,
+    ReducedBranchChanges, ReducedCompiledInvocation,
 };
 use crate::ref_expr;
-use crate::relocations::{Relocation, RelocationEntry};
-
-// TODO(yuval): move tests to test infrastructure.
 
 #[test]
 fn test_storage_read() {
-    assert_eq!(
-        compile_libfunc("storage_read_syscall", vec![ref_expr!([fp + 1] + 3), ref_expr!([ap + 5])],),
-        ReducedCompiledInvocation {
-            instructions: casm! {
-                [ap + 0] = 31066245855454734213960397939u128, ap++;
-                [ap + -1] = [[fp + 1] + 3];
-                [ap + 4] = [[fp + 1] + 4];
-                %{ syscall_handler.syscall(syscall_ptr=memory[fp + 1] + 3) %}
-                [ap + 0] = [[fp + 1] + 5], ap++;
-            }
-            .instructions,
-            relocations: vec![],
-            results: vec![ReducedBranchChanges {
-                refs: vec![ref_expr!([fp + 1] + 6), ref_expr!([ap - 1])],
-                ap_change: ApChange::Known(2)
-            }]
-        }
+    compare_libfunc_snapshot(
+        "starknet/storage_read",
+        "storage_read_syscall",
+        vec![
+            ref_expr!([fp + 0]),
+            ref_expr!([fp + 1] + 3),
+            ref_expr!([fp + 2]),
+            ref_expr!([ap + 5]),
+        ],
+    );
+}
+
+#[test]
+fn test_storage_read_unchecked() {
+    compare_libfunc_snapshot(
+        "starknet/storage_read_unchecked",
+        "storage_read_unchecked",
+        vec![
+            ref_expr!([fp + 0]),
+            ref_expr!([fp + 1] + 3),
+            ref_expr!([fp + 2]),
+            ref_expr!([ap + 5]),
+        ],
+    );
+}
+
+#[test]
+fn test_storage_read_multi() {
+    compare_libfunc_snapshot(
+        "starknet/storage_read_multi",
+        "storage_read_multi<Struct<ut@Pair, felt, felt>>",
+        vec![
+            ref_expr!([fp + 0]),
+            ref_expr!([fp + 1] + 3),
+            ref_expr!([fp + 2]),
+            ref_expr!([ap + 5]),
+        ],
     );
 }
 
 #[test]
 fn test_storage_write() {
+    compare_libfunc_snapshot(
+        "starknet/storage_write",
+        "storage_write_syscall",
+        vec![
+            ref_expr!([fp + 1]),
+            ref_expr!([fp + 2]),
+            ref_expr!([fp + 3]),
+            ref_expr!([ap + 5]),
+            ref_expr!([ap + 6]),
+        ],
+    );
+}
+
+#[test]
+fn test_storage_read_immediate_address() {
+    let refs =
+        |address| vec![ref_expr!([fp + 0]), ref_expr!([fp + 1] + 3), ref_expr!([fp + 2]), address];
+    // A non deref address costs an additional instruction, which a deref address does not pay.
     assert_eq!(
-        compile_libfunc(
+        compile_libfunc("storage_read_syscall", refs(ref_expr!(1234))).instructions.len(),
+        compile_libfunc("storage_read_syscall", refs(ref_expr!([ap + 5]))).instructions.len() + 1
+    );
+    // The read value is listed as a change of the address domain, as they share a type.
+    assert_eq!(
+        compile_libfunc_reference_deltas("storage_read_syscall", refs(ref_expr!(1234))),
+        [
+            indoc! {"
+                [0]: [fp + 0] -> [[fp + 1] + 7]
+                [1]: += 7
+                [2]: [fp + 2] -> [ap + -1]
+                [3]: consumed
+            "},
+            indoc! {"
+                [0]: [fp + 0] -> [[fp + 1] + 7]
+                [1]: += 7
+                [2]: [fp + 2] -> [ap + -1]
+                [3]: consumed
+            "}
+        ]
+    );
+}
+
+#[test]
+fn test_storage_write_compound_address() {
+    // The compound address is computed into a tempvar, so the references are the same as for a
+    // deref address, other than the ap change.
+    assert_eq!(
+        compile_libfunc_reference_deltas(
             "storage_write_syscall",
             vec![
                 ref_expr!([fp + 1]),
                 ref_expr!([fp + 2]),
-                ref_expr!([ap + 5]),
+                ref_expr!([fp + 3]),
+                ref_expr!([fp + 4] + 1),
                 ref_expr!([ap + 6])
             ],
         ),
-        ReducedCompiledInvocation {
-            instructions: casm! {
-                [ap + 0] = 8038072152842849266968829064307u128, ap++;
-                [ap + -1] = [[fp + 2] + 0];
-                [fp + 1] = [[fp + 2] + 1];
-                [ap + 4] = [[fp + 2] + 2];
-                [ap + 5] = [[fp + 2] + 3];
-                %{ syscall_handler.syscall(syscall_ptr=memory[fp + 2]) %}
-                [ap + 0] = [[fp + 2] + 5], ap++;
-                jmp rel 0 if [ap + -1] != 0;
-            }
-            .instructions,
-            relocations: vec![RelocationEntry {
-                instruction_idx: 6,
-                relocation: Relocation::RelativeStatementId(StatementIdx(1,),),
-            },],
-            results: vec![
-                ReducedBranchChanges {
-                    refs: vec![ref_expr!([[fp + 2] + 4]), ref_expr!([fp + 2] + 7)],
-                    ap_change: ApChange::Known(2)
-                },
-                ReducedBranchChanges {
-                    refs: vec![
-                        ref_expr!([[fp + 2] + 4]),
-                        ref_expr!([fp + 2] + 7),
-                        ref_expr!([ap - 1])
-                    ],
-                    ap_change: ApChange::Known(2)
-                }
-            ]
-        }
+        [
+            indoc! {"
+                [0]: [fp + 1] -> [[fp + 2] + 5]
+                [1]: += 8
+                [2]: consumed
+                [3]: consumed
+                [4]: consumed
+            "},
+            indoc! {"
+                [0]: [fp + 1] -> [[fp + 2] + 5]
+                [1]: += 8
+                [2]: [fp + 3] -> [ap + -1]
+                [3]: consumed
+                [4]: consumed
+            "}
+        ]
+    );
+}
+
+#[test]
+fn test_storage_write_unchecked() {
+    compare_libfunc_snapshot(
+        "starknet/storage_write_unchecked",
+        "storage_write_unchecked",
+        vec![
+            ref_expr!([fp + 1]),
+            ref_expr!([fp + 2]),
+            ref_expr!([fp + 3]),
+            ref_expr!([ap + 5]),
+            ref_expr!([ap + 6]),
+        ],
+    );
+}
+
+#[test]
+fn test_storage_read_checked() {
+    let refs =
+        vec![ref_expr!([fp + 2]), ref_expr!([fp + 3]), ref_expr!([fp + 4]), ref_expr!([fp + 5])];
+    let unchecked = compile_libfunc("storage_read_syscall", refs.clone());
+    let checked = compile_libfunc(
+        "storage_read_checked",
+        [ref_expr!([fp + 1])].into_iter().chain(refs.clone()).collect(),
+    );
+    // The address is checked by 10 additional instructions, before the system call.
+    assert_eq!(checked.instructions.len(), unchecked.instructions.len() + 10);
+    assert_eq!(checked.instructions[10..], unchecked.instructions[..]);
+    // Only the checked variant uses the range check.
+    assert_eq!(
+        compile_libfunc_reference_deltas("storage_read_syscall", refs.clone()),
+        [
+            indoc! {"
+                [0]: [fp + 2] -> [[fp + 3] + 4]
+                [1]: += 7
+                [2]: [fp + 4] -> [ap + -1]
+                [3]: consumed
+            "},
+            indoc! {"
+                [0]: [fp + 2] -> [[fp + 3] + 4]
+                [1]: += 7
+                [2]: [fp + 4] -> [ap + -1]
+                [3]: consumed
+            "}
+        ]
+    );
+    assert_eq!(
+        compile_libfunc_reference_deltas(
+            "storage_read_checked",
+            [ref_expr!([fp + 1])].into_iter().chain(refs).collect()
+        ),
+        [
+            indoc! {"
+                [0]: += 3
+                [1]: [fp + 2] -> [[fp + 3] + 4]
+                [2]: += 7
+                [3]: [fp + 4] -> [ap + -1]
+                [4]: consumed
+            "},
+            indoc! {"
+                [0]: += 3
+                [1]: [fp + 2] -> [[fp + 3] + 4]
+                [2]: += 7
+                [3]: [fp + 4] -> [ap + -1]
+                [4]: consumed
+            "}
+        ]
+    );
+}
+
+#[test]
+fn test_storage_write_checked() {
+    let refs = vec![
+        ref_expr!([fp + 2]),
+        ref_expr!([fp + 3]),
+        ref_expr!([fp + 4]),
+        ref_expr!([fp + 5]),
+        ref_expr!([ap + 6]),
+    ];
+    let unchecked = compile_libfunc("storage_write_syscall", refs.clone());
+    let checked = compile_libfunc(
+        "storage_write_checked",
+        [ref_expr!([fp + 1])].into_iter().chain(refs.clone()).collect(),
+    );
+    assert_eq!(checked.instructions.len(), unchecked.instructions.len() + 10);
+    assert_eq!(
+        compile_libfunc_reference_deltas(
+            "storage_write_checked",
+            [ref_expr!([fp + 1])].into_iter().chain(refs).collect()
+        ),
+        [
+            indoc! {"
+                [0]: += 3
+                [1]: [fp + 2] -> [[fp + 3] + 5]
+                [2]: += 8
+                [3]: consumed
+                [4]: consumed
+                [5]: consumed
+            "},
+            indoc! {"
+                [0]: += 3
+                [1]: [fp + 2] -> [[fp + 3] + 5]
+                [2]: += 8
+                [3]: [fp + 4] -> [ap + -1]
+                [4]: consumed
+                [5]: consumed
+            "}
+        ]
+    );
+}
+
+#[test]
+fn test_storage_base_address_const() {
+    compare_libfunc_snapshot(
+        "starknet/storage_base_address_const",
+        "storage_base_addr_const<1234>",
+        vec![],
+    );
+}
+
+#[test]
+fn test_storage_address_from_base_and_offset() {
+    compare_libfunc_snapshot(
+        "starknet/storage_address_from_base_and_deref_offset",
+        "storage_addr_from_base",
+        vec![ref_expr!([fp + 1]), ref_expr!([ap + 2])],
+    );
+    compare_libfunc_snapshot(
+        "starknet/storage_address_from_base_and_immediate_offset",
+        "storage_addr_from_base",
+        vec![ref_expr!([fp + 1]), ref_expr!(3)],
+    );
+}
+
+#[test]
+fn test_storage_base_address_from_felt() {
+    let ReducedCompiledInvocation { relocations, results, .. } =
+        compile_libfunc("storage_base_from_felt", vec![ref_expr!([fp + 1]), ref_expr!([fp + 2])]);
+    assert_eq!(relocations, vec![]);
+    assert_eq!(
+        results,
+        vec![ReducedBranchChanges {
+            refs: vec![ref_expr!([fp + 1] + 3), ref_expr!([ap - 7])],
+            ap_change: ApChange::Known(7)
+        }]
     );
 }
@@ -0,0 +1,163 @@
+use casm::builder::CasmBuilder;
+use casm::casm_build_extend;
+use casm::operand::ResOperand;
+use itertools::chain;
+use utils::casts::usize_as_i16;
+
+use super::{
+    add_syscall, unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError,
+    SyscallInput,
+};
+use crate::invocations::array::ArrayView;
+use crate::references::ReferenceValue;
+use crate::syscall_records::{
+    selector_value, syscall_size, KeccakRequest, KeccakResponse, ReplaceClassRequest,
+    ReplaceClassResponse, SyscallRecord, KECCAK_SELECTOR, REPLACE_CLASS_SELECTOR,
+};
+
+#[cfg(test)]
+#[path = "syscalls_test.rs"]
+mod test;
+
+/// Builds instructions for a StarkNet system call that takes no arguments besides the gas builtin.
+///
+/// `Response` must be laid out as the updated gas builtin, the revert reason and the single result
+/// of the system call.
+pub fn build_empty_args_syscall<Request: SyscallRecord, Response: SyscallRecord>(
+    mut builder: CompiledInvocationBuilder<'_>,
+    selector: &str,
+) -> Result<CompiledInvocation, InvocationError> {
+    let system_slack = usize_as_i16(syscall_size::<Request, Response>());
+
+    let [
+        ReferenceValue { expression: expr_gas_builtin, .. },
+        ReferenceValue { expression: expr_system, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    let (gas_builtin, system) = (
+        expr_gas_builtin.try_unpack_single()?.to_deref()?,
+        expr_system.try_unpack_single()?.to_buffer(system_slack)?,
+    );
+
+    let mut casm_builder = CasmBuilder::default();
+    let system = casm_builder.add_var(system);
+    let gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
+    let syscall = add_syscall(
+        &mut casm_builder,
+        &mut builder.environment,
+        selector_value(selector),
+        system,
+        gas_builtin,
+        &[],
+        1,
+    );
+
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
+        [
+            // Success branch - return (gas builtin, system, result)
+            chain!(syscall.builtin_outputs(), [vec![syscall.outputs[0]]]).collect(),
+            // Failure branch - return (gas builtin, system, revert_reason)
+            syscall.failure_outputs(),
+        ],
+    )
+}
+
+/// Builds instructions for StarkNet replace class system call.
+pub fn build_replace_class(
+    mut builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let system_slack = usize_as_i16(syscall_size::<ReplaceClassRequest, ReplaceClassResponse>());
+
+    let [
+        ReferenceValue { expression: expr_gas_builtin, .. },
+        ReferenceValue { expression: expr_system, .. },
+        ReferenceValue { expression: expr_class_hash, .. },
+    ] = unpack_refs::<3>(&builder)?;
+    let (gas_builtin, system, class_hash) = (
+        expr_gas_builtin.try_unpack_single()?.to_deref()?,
+        expr_system.try_unpack_single()?.to_buffer(system_slack)?,
+        expr_class_hash.try_unpack_single()?.to_deref()?,
+    );
+
+    let mut casm_builder = CasmBuilder::default();
+    let system = casm_builder.add_var(system);
+    let gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
+    let class_hash = casm_builder.add_var(ResOperand::Deref(class_hash));
+    let syscall = add_syscall(
+        &mut casm_builder,
+        &mut builder.environment,
+        selector_value(REPLACE_CLASS_SELECTOR),
+        system,
+        gas_builtin,
+        &[SyscallInput::Cell(class_hash)],
+        0,
+    );
+
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
+        [
+            // Success branch - return (gas builtin, system)
+            syscall.builtin_outputs(),
+            // Failure branch - return (gas builtin, system, revert_reason)
+            syscall.failure_outputs(),
+        ],
+    )
+}
+
+/// Builds instructions for StarkNet keccak system call.
+pub fn build_keccak(
+    mut builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let system_slack = usize_as_i16(syscall_size::<KeccakRequest, KeccakResponse>());
+
+    let [
+        ReferenceValue { expression: expr_gas_builtin, .. },
+        ReferenceValue { expression: expr_system, .. },
+        _,
+    ] = unpack_refs::<3>(&builder)?;
+    let (gas_builtin, system, input) = (
+        expr_gas_builtin.try_unpack_single()?.to_deref()?,
+        expr_system.try_unpack_single()?.to_buffer(system_slack)?,
+        ArrayView::from_arg(&builder, 2)?,
+    );
+
+    // The bounds of the input are passed to the syscall as is, so they must be materialized.
+    if input.end_offset != 0 {
+        return Err(InvocationError::InvalidReferenceExpressionForArgument);
+    }
+
+    let mut casm_builder = CasmBuilder::default();
+    let system = casm_builder.add_var(system);
+    let gas_builtin = casm_builder.add_var(ResOperand::Deref(gas_builtin));
+    let input_start = casm_builder.add_var(ResOperand::Deref(input.start));
+    let input_end = casm_builder.add_var(ResOperand::Deref(input.end));
+    let syscall = add_syscall(
+        &mut casm_builder,
+        &mut builder.environment,
+        selector_value(KECCAK_SELECTOR),
+        system,
+        gas_builtin,
+        &[SyscallInput::Cell(input_start), SyscallInput::Cell(input_end)],
+        2,
+    );
+    let [response_low, response_high] = syscall.outputs[..] else { unreachable!() };
+    casm_build_extend! {casm_builder,
+        tempvar result_low;
+        assert result_low = response_low;
+        tempvar result_high;
+        assert result_high = response_high;
+    };
+
+    builder.build_from_casm_builder(
+        casm_builder,
+        &["Failure"],
+        [
+            // Success branch - return (gas builtin, system, result_low, result_high)
+            chain!(syscall.builtin_outputs(), [vec![result_low], vec![result_high]]).collect(),
+            // Failure branch - return (gas builtin, system, revert_reason)
+            syscall.failure_outputs(),
+        ],
+    )
+}
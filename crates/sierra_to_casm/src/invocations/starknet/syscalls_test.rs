@@ -0,0 +1,62 @@
+use pretty_assertions::assert_eq;
+use test_case::test_case;
+
+use crate::invocations::test_utils::{compare_libfunc_snapshot, try_compile_libfunc};
+use crate::invocations::InvocationError;
+use crate::ref_expr;
+
+#[test_case("get_caller_address"; "get_caller_address")]
+#[test_case("get_contract_address"; "get_contract_address")]
+#[test_case("get_sequencer_address"; "get_sequencer_address")]
+#[test_case("get_block_number"; "get_block_number")]
+#[test_case("get_block_timestamp"; "get_block_timestamp")]
+#[test_case("get_tx_info"; "get_tx_info")]
+fn test_empty_args_syscall(libfunc: &str) {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    compare_libfunc_snapshot(&format!("starknet/{libfunc}"), libfunc, vec![gas_builtin, system]);
+}
+
+#[test]
+fn test_replace_class() {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    let class_hash = ref_expr!([fp + 3]);
+    compare_libfunc_snapshot(
+        "starknet/replace_class",
+        "replace_class_syscall",
+        vec![gas_builtin, system, class_hash],
+    );
+}
+
+#[test]
+fn test_keccak() {
+    let gas_builtin = ref_expr!([fp + 1]);
+    let system = ref_expr!([fp + 2]);
+    // The bounds of an input of several words.
+    let input = ref_expr!([fp + 3], [fp + 4]);
+    compare_libfunc_snapshot("starknet/keccak", "keccak_syscall", vec![gas_builtin, system, input]);
+}
+
+#[test]
+fn test_keccak_with_unmaterialized_input() {
+    // The end of the input is still pending the offset of the last appended words.
+    assert_eq!(
+        try_compile_libfunc(
+            "keccak_syscall",
+            vec![ref_expr!([fp + 1]), ref_expr!([fp + 2]), ref_expr!([fp + 3], [fp + 4] + 2)]
+        ),
+        Err(InvocationError::InvalidReferenceExpressionForArgument)
+    );
+}
+
+#[test]
+fn test_keccak_with_wrong_number_of_cells() {
+    assert_eq!(
+        try_compile_libfunc(
+            "keccak_syscall",
+            vec![ref_expr!([fp + 1]), ref_expr!([fp + 2]), ref_expr!([fp + 3], [fp + 4], [fp + 5])]
+        ),
+        Err(InvocationError::WrongArgumentSize { param_idx: 2, expected: 2, actual: 3 })
+    );
+}
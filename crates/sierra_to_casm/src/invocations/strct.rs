@@ -1,8 +1,16 @@
 use sierra::extensions::strct::StructConcreteLibFunc;
 use sierra::extensions::ConcreteLibFunc;
+use sierra::ids::ConcreteTypeId;
 
-use super::{CompiledInvocation, CompiledInvocationBuilder, InvocationError};
-use crate::references::{ReferenceExpression, ReferenceValue};
+use super::{
+    unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError, ProgramInfo,
+    ReferenceExpressionView,
+};
+use crate::references::ReferenceExpression;
+
+#[cfg(test)]
+#[path = "strct_test.rs"]
+mod test;
 
 /// Builds instructions for Sierra struct operations.
 pub fn build(
@@ -11,41 +19,72 @@ pub fn build(
 ) -> Result<CompiledInvocation, InvocationError> {
     match libfunc {
         StructConcreteLibFunc::Construct(_) => {
-            let cells = builder
-                .refs
-                .iter()
-                .flat_map(|ref_value| &ref_value.expression.cells)
-                .cloned()
-                .collect();
-            Ok(builder.build_only_reference_changes([ReferenceExpression { cells }].into_iter()))
+            let members = builder.refs.iter().map(|ref_value| ref_value.expression.clone());
+            let struct_expression =
+                StructView { members: members.collect() }.to_reference_expression();
+            builder.build_only_reference_changes([struct_expression].into_iter())
+        }
+        StructConcreteLibFunc::Deconstruct(_) => {
+            unpack_refs::<1>(&builder)?;
+            let struct_view = StructView::from_arg(&builder, 0)?;
+            builder.build_only_reference_changes(struct_view.members.into_iter())
+        }
+        StructConcreteLibFunc::MemberGet(libfunc) => {
+            unpack_refs::<1>(&builder)?;
+            let struct_view = StructView::from_arg(&builder, 0)?;
+            let member = struct_view.members[libfunc.index].clone();
+            builder.build_only_reference_changes(
+                [struct_view.to_reference_expression(), member].into_iter(),
+            )
+        }
+    }
+}
+
+/// A struct representing an actual struct value in the Sierra program.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StructView {
+    /// The values of the members of the struct, in order. Each is of the size of the type of the
+    /// member, so a member which is itself a struct may be viewed in turn.
+    pub members: Vec<ReferenceExpression>,
+}
+impl StructView {
+    /// Returns the view of the struct passed as argument #`param_idx` of the invocation.
+    pub fn from_arg(
+        builder: &CompiledInvocationBuilder<'_>,
+        param_idx: usize,
+    ) -> Result<Self, InvocationError> {
+        let Some(arg) = builder.refs.get(param_idx) else {
+            return Err(builder.wrong_number_of_arguments(builder.libfunc.param_signatures().len()));
+        };
+        Self::try_get_view(&arg.expression, &builder.program_info, &arg.ty)
+    }
+}
+
+impl ReferenceExpressionView for StructView {
+    type Error = InvocationError;
+
+    fn try_get_view(
+        expr: &ReferenceExpression,
+        program_info: &ProgramInfo<'_>,
+        struct_type: &ConcreteTypeId,
+    ) -> Result<Self, Self::Error> {
+        let layout = program_info.struct_layout(struct_type)?;
+        if expr.cells.len() != program_info.type_size(struct_type)? {
+            return Err(InvocationError::InvalidReferenceExpressionForArgument);
         }
-        StructConcreteLibFunc::Deconstruct(libfunc) => {
-            let struct_type = &libfunc.param_signatures()[0].ty;
-            let cells = match builder.refs {
-                [ReferenceValue { expression: ReferenceExpression { cells }, .. }]
-                    if cells.len() == builder.program_info.type_sizes[struct_type] as usize =>
-                {
-                    cells
-                }
-                [_] => return Err(InvocationError::InvalidReferenceExpressionForArgument),
-                refs => {
-                    return Err(InvocationError::WrongNumberOfArguments {
-                        expected: 1,
-                        actual: refs.len(),
-                    });
-                }
-            };
-            let output_types = libfunc.output_types();
-            assert_eq!(output_types.len(), 1, "Wrong number of branches configured.");
-            let mut offset = 0_usize;
-            let mut outputs = vec![];
-            for ty in &output_types[0] {
-                let size = builder.program_info.type_sizes[ty] as usize;
-                outputs
-                    .push(ReferenceExpression { cells: cells[offset..(offset + size)].to_vec() });
-                offset += size;
-            }
-            Ok(builder.build_only_reference_changes(outputs.into_iter()))
+        Ok(StructView {
+            members: layout
+                .into_iter()
+                .map(|member| ReferenceExpression {
+                    cells: expr.cells[member.offset..(member.offset + member.size)].to_vec(),
+                })
+                .collect(),
+        })
+    }
+
+    fn to_reference_expression(self) -> ReferenceExpression {
+        ReferenceExpression {
+            cells: self.members.into_iter().flat_map(|member| member.cells).collect(),
         }
     }
 }
@@ -0,0 +1,91 @@
+use casm::ap_change::ApChange;
+use pretty_assertions::assert_eq;
+use test_case::test_case;
+
+use super::StructView;
+use crate::invocations::test_utils::{
+    compile_libfunc, with_invocation_builder, ReducedBranchChanges, ReducedCompiledInvocation,
+};
+use crate::invocations::{InvocationError, ReferenceExpressionView};
+use crate::ref_expr;
+use crate::references::ReferenceExpression;
+
+/// A struct with members of sizes 1, 2 and 1.
+const MIXED: &str = "Struct<ut@Mixed, felt, Struct<ut@Pair, felt, felt>, felt>";
+const PAIR: &str = "Struct<ut@Pair, felt, felt>";
+
+#[test_case(0, ref_expr!([fp + 1]); "first")]
+#[test_case(1, ref_expr!([fp + 2], [fp + 3]); "middle")]
+#[test_case(2, ref_expr!([fp + 4]); "last")]
+fn test_struct_member_get(index: usize, member: ReferenceExpression) {
+    let struct_expr = ref_expr!([fp + 1], [fp + 2], [fp + 3], [fp + 4]);
+    assert_eq!(
+        compile_libfunc(&format!("struct_member_get<{MIXED}, {index}>"), vec![struct_expr.clone()]),
+        ReducedCompiledInvocation {
+            instructions: vec![],
+            relocations: vec![],
+            results: vec![ReducedBranchChanges {
+                refs: vec![struct_expr, member],
+                ap_change: ApChange::Known(0)
+            }]
+        }
+    );
+}
+
+#[test]
+fn test_struct_view() {
+    let struct_expr = ref_expr!([fp + 1], [fp + 2], [fp + 3], [fp + 4]);
+    with_invocation_builder(
+        &format!("struct_deconstruct<{MIXED}>"),
+        vec![struct_expr.clone()],
+        |builder| {
+            let struct_view = StructView::from_arg(&builder, 0).unwrap();
+            assert_eq!(
+                struct_view,
+                StructView {
+                    members: vec![
+                        ref_expr!([fp + 1]),
+                        ref_expr!([fp + 2], [fp + 3]),
+                        ref_expr!([fp + 4])
+                    ]
+                }
+            );
+            // The nested struct member may be viewed in turn.
+            assert_eq!(
+                StructView::try_get_view(
+                    &struct_view.members[1],
+                    &builder.program_info,
+                    &PAIR.into()
+                ),
+                Ok(StructView { members: vec![ref_expr!([fp + 2]), ref_expr!([fp + 3])] })
+            );
+            assert_eq!(struct_view.to_reference_expression(), struct_expr);
+        },
+    );
+}
+
+#[test]
+fn test_struct_view_errors() {
+    with_invocation_builder(
+        &format!("struct_deconstruct<{MIXED}>"),
+        vec![ref_expr!([fp + 1], [fp + 2], [fp + 3], [fp + 4])],
+        |builder| {
+            assert_eq!(
+                StructView::try_get_view(
+                    &ref_expr!([fp + 1], [fp + 2], [fp + 3]),
+                    &builder.program_info,
+                    &MIXED.into()
+                ),
+                Err(InvocationError::InvalidReferenceExpressionForArgument)
+            );
+            assert_eq!(
+                StructView::try_get_view(
+                    &ref_expr!([fp + 1]),
+                    &builder.program_info,
+                    &"felt".into()
+                ),
+                Err(InvocationError::NotAStruct("felt".into()))
+            );
+        },
+    );
+}
@@ -0,0 +1,283 @@
+use casm::ap_change::ApChange;
+use casm::builder::CasmBuilder;
+use casm::operand::ResOperand;
+use casm::{casm, casm_build_extend, res};
+use sierra::ids::ConcreteTypeId;
+use sierra::program::StatementIdx;
+use test_log::test;
+
+use super::test_utils::{
+    try_compile_libfunc, with_core_invocation_builder, with_invocation_builder,
+    ReducedBranchChanges, ReducedCompiledInvocation,
+};
+use super::{compile_core_invocation, CompiledInvocationBuilder, InvocationError, MemberLayout};
+use crate::ref_expr;
+use crate::references::ReferenceExpression;
+use crate::relocations::{Relocation, RelocationEntry};
+
+/// A struct with members of sizes 1, 2 and 1.
+const MIXED: &str = "Struct<ut@Mixed, felt, Struct<ut@Pair, felt, felt>, felt>";
+const PAIR: &str = "Struct<ut@Pair, felt, felt>";
+
+fn mixed_expr() -> ReferenceExpression {
+    ref_expr!([fp + 1], [fp + 2], [fp + 3], [fp + 4])
+}
+
+#[test]
+fn test_output_size_mismatch() {
+    // `rename` forwards its argument, so a two cell argument yields a two cell `felt` output.
+    assert_eq!(
+        try_compile_libfunc("rename<felt>", vec![ref_expr!([fp + 1], [fp + 2])]),
+        Err(InvocationError::WrongOutputSize { branch_idx: 0, var_idx: 0, expected: 1, actual: 2 })
+    );
+}
+
+#[test]
+fn test_type_size() {
+    with_invocation_builder(&format!("rename<{MIXED}>"), vec![mixed_expr()], |builder| {
+        assert_eq!(builder.type_size(&MIXED.into()), Ok(4));
+        assert_eq!(builder.type_size(&PAIR.into()), Ok(2));
+        assert_eq!(
+            builder.type_size(&"unknown".into()),
+            Err(InvocationError::UnknownTypeId("unknown".into()))
+        );
+    });
+}
+
+#[test]
+fn test_struct_layout() {
+    with_invocation_builder(&format!("rename<{MIXED}>"), vec![mixed_expr()], |builder| {
+        let felt_ty: ConcreteTypeId = "felt".into();
+        assert_eq!(
+            builder.struct_layout(&MIXED.into()),
+            Ok(vec![
+                MemberLayout { ty: felt_ty.clone(), offset: 0, size: 1 },
+                MemberLayout { ty: PAIR.into(), offset: 1, size: 2 },
+                MemberLayout { ty: felt_ty.clone(), offset: 3, size: 1 },
+            ])
+        );
+        assert_eq!(builder.struct_layout(&felt_ty), Err(InvocationError::NotAStruct(felt_ty)));
+        assert_eq!(
+            builder.struct_layout(&"unknown".into()),
+            Err(InvocationError::UnknownTypeId("unknown".into()))
+        );
+    });
+}
+
+#[test]
+fn test_libfunc_generic_type_arg() {
+    with_invocation_builder(
+        &format!("struct_member_get<{MIXED}, 1>"),
+        vec![mixed_expr()],
+        |builder| {
+            assert_eq!(builder.libfunc_generic_type_arg(0), Ok(&MIXED.into()));
+            // The second generic argument is a value, not a type.
+            assert_eq!(
+                builder.libfunc_generic_type_arg(1),
+                Err(InvocationError::MissingGenericTypeArg(1))
+            );
+            assert_eq!(
+                builder.libfunc_generic_type_arg(2),
+                Err(InvocationError::MissingGenericTypeArg(2))
+            );
+        },
+    );
+}
+
+#[test]
+fn test_wrong_number_of_arguments() {
+    let refs = vec![
+        ref_expr!([fp + 1]),
+        ref_expr!([fp + 2]),
+        ref_expr!([fp + 3]),
+        ref_expr!([fp + 4], [fp + 5]),
+    ];
+    with_core_invocation_builder("call_contract_syscall", refs, |libfunc, builder| {
+        // Dropping the target and the call data from the arguments of the invocation.
+        let refs = &builder.refs[..2];
+        let error = compile_core_invocation(libfunc, CompiledInvocationBuilder { refs, ..builder })
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Expected 4 arguments for libfunc `call_contract_syscall`, got 2: (GasBuiltin, \
+             System)."
+        );
+    });
+}
+
+#[test]
+fn test_check_ap_changes() {
+    with_invocation_builder("store_temp<felt>", vec![ref_expr!([fp + 1])], |builder| {
+        assert_eq!(builder.check_ap_changes([ApChange::Known(1)]), Ok(()));
+        // Simulating code that does not match the ap change declared for the libfunc.
+        assert_eq!(
+            builder.check_ap_changes([ApChange::Known(2)]),
+            Err(InvocationError::ApChangeMismatch {
+                libfunc: "store_temp".into(),
+                branch_idx: 0,
+                expected: 1,
+                actual: 2,
+            })
+        );
+        // Simulating code looping, while a known ap change is declared for the libfunc.
+        assert_eq!(
+            builder.check_ap_changes([ApChange::Unknown]),
+            Err(InvocationError::UnknownApChange {
+                libfunc: "store_temp".into(),
+                branch_idx: 0,
+                expected: 1,
+            })
+        );
+    });
+    // The ap change of `revoke_ap_tracking` is unknown, so any ap change is accepted.
+    with_invocation_builder("revoke_ap_tracking", vec![], |builder| {
+        assert_eq!(builder.check_ap_changes([ApChange::Known(5)]), Ok(()));
+        assert_eq!(builder.check_ap_changes([ApChange::Unknown]), Ok(()));
+    });
+}
+
+#[test]
+fn test_build_from_casm_builder() {
+    // None of the branches of `enum_match` is the fallthrough, so both are labelled.
+    let refs = vec![ref_expr!([fp + 1], [fp + 2])];
+    with_invocation_builder("enum_match<Enum<ut@Pair, felt, felt>>", refs, |builder| {
+        let mut casm_builder = CasmBuilder::default();
+        let variant_selector = casm_builder.add_var(res!([fp + 1]));
+        let value = casm_builder.add_var(res!([fp + 2]));
+        casm_build_extend! {casm_builder,
+            jump Second if variant_selector != 0;
+            jump First;
+        };
+        let compiled = builder
+            .build_from_casm_builder(
+                casm_builder,
+                &["First", "Second"],
+                [vec![vec![value]], vec![vec![value]]],
+            )
+            .unwrap();
+        assert_eq!(
+            ReducedCompiledInvocation::new(compiled),
+            ReducedCompiledInvocation {
+                instructions: casm! {
+                    jmp rel 0 if [fp + 1] != 0;
+                    jmp rel 0;
+                }
+                .instructions,
+                relocations: vec![
+                    RelocationEntry {
+                        instruction_idx: 0,
+                        relocation: Relocation::RelativeStatementId(StatementIdx(1)),
+                    },
+                    RelocationEntry {
+                        instruction_idx: 1,
+                        relocation: Relocation::RelativeStatementId(StatementIdx(0)),
+                    },
+                ],
+                results: vec![
+                    ReducedBranchChanges {
+                        refs: vec![ref_expr!([fp + 2])],
+                        ap_change: ApChange::Known(0),
+                    },
+                    ReducedBranchChanges {
+                        refs: vec![ref_expr!([fp + 2])],
+                        ap_change: ApChange::Known(0),
+                    },
+                ],
+            }
+        );
+    });
+}
+
+#[test]
+fn test_build_from_casm_builder_comments() {
+    let refs = vec![ref_expr!([fp + 1], [fp + 2])];
+    with_invocation_builder("enum_match<Enum<ut@Pair, felt, felt>>", refs, |builder| {
+        let mut casm_builder = CasmBuilder::default();
+        let variant_selector = casm_builder.add_var(res!([fp + 1]));
+        let value = casm_builder.add_var(res!([fp + 2]));
+        casm_build_extend! {casm_builder,
+            /// Jump to the second variant.
+            jump Second if variant_selector != 0;
+            /// Jump to the first variant.
+            jump First;
+        };
+        let compiled = builder
+            .build_from_casm_builder(
+                casm_builder,
+                &["First", "Second"],
+                [vec![vec![value]], vec![vec![value]]],
+            )
+            .unwrap();
+        assert_eq!(
+            compiled.comments,
+            vec![
+                (0, "Jump to the second variant.".to_owned()),
+                (1, "Jump to the first variant.".to_owned()),
+            ]
+        );
+    });
+}
+
+/// Builds code for `u128_overflowing_sub` using the range check once in the fallthrough branch and
+/// twice in the failure branch, starting with the range check at `range_check`.
+fn build_with_branch_dependent_builtin_usages(
+    range_check: ResOperand,
+) -> Result<Vec<ReducedBranchChanges>, InvocationError> {
+    let refs = vec![ref_expr!([fp - 5]), ref_expr!([fp - 4]), ref_expr!([fp - 3])];
+    with_invocation_builder("u128_overflowing_sub", refs, |builder| {
+        let mut casm_builder = CasmBuilder::default();
+        let range_check = casm_builder.add_var(range_check);
+        let a = casm_builder.add_var(res!([fp - 4]));
+        let b = casm_builder.add_var(res!([fp - 3]));
+        casm_build_extend! {casm_builder,
+                tempvar x;
+                assert x = a;
+                jump NoFailure if x != 0;
+                assert *(range_check++) = a;
+                assert *(range_check++) = b;
+                tempvar y;
+                assert y = a;
+                tempvar z;
+                assert z = b;
+                jump Failure;
+            NoFailure:
+                assert *(range_check++) = b;
+                tempvar w;
+                assert w = b;
+        };
+        let compiled = builder.build_from_casm_builder(
+            casm_builder,
+            &["Failure"],
+            [vec![vec![range_check], vec![w]], vec![vec![range_check], vec![z]]],
+        )?;
+        Ok(ReducedCompiledInvocation::new(compiled).results)
+    })
+}
+
+#[test]
+fn test_build_from_casm_builder_builtin_usages() {
+    assert_eq!(
+        build_with_branch_dependent_builtin_usages(res!([fp - 5])),
+        Ok(vec![
+            ReducedBranchChanges {
+                refs: vec![ref_expr!([fp - 5] + 1), ref_expr!([ap - 1])],
+                ap_change: ApChange::Known(2),
+            },
+            ReducedBranchChanges {
+                refs: vec![ref_expr!([fp - 5] + 2), ref_expr!([ap - 1])],
+                ap_change: ApChange::Known(3),
+            },
+        ])
+    );
+    // Simulating code that starts with a range check which does not match the input reference.
+    assert_eq!(
+        build_with_branch_dependent_builtin_usages(res!([fp - 5] + 1)),
+        Err(InvocationError::BuiltinUsageMismatch {
+            libfunc: "u128_overflowing_sub".into(),
+            branch_idx: 0,
+            var_idx: 0,
+            expected: 1,
+            actual: 2,
+        })
+    );
+}
@@ -1,23 +1,36 @@
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::PathBuf;
 
 use casm::ap_change::ApChange;
+use casm::cost::CostWeights;
 use casm::instructions::Instruction;
 use itertools::{zip_eq, Itertools};
-use sierra::extensions::core::{CoreLibFunc, CoreType};
+use sierra::extensions::core::{CoreConcreteLibFunc, CoreLibFunc, CoreType};
 use sierra::extensions::lib_func::{SignatureSpecializationContext, SpecializationContext};
 use sierra::extensions::type_specialization_context::TypeSpecializationContext;
 use sierra::extensions::types::TypeInfo;
 use sierra::extensions::{ConcreteLibFunc, ConcreteType, GenericLibFuncEx, GenericTypeEx};
 use sierra::ids::{ConcreteTypeId, VarId};
-use sierra::program::{BranchInfo, BranchTarget, Invocation, StatementIdx};
+use sierra::program::{
+    BranchInfo, BranchTarget, ConcreteTypeLongId, GenericArg, Invocation, StatementIdx,
+};
 use sierra_ap_change::ap_change_info::ApChangeInfo;
+use sierra_ap_change::core_libfunc_ap_change::core_libfunc_ap_change;
+use sierra_ap_change::StackLayout;
+use sierra_gas::core_libfunc_cost::core_libfunc_cost;
 use sierra_gas::gas_info::GasInfo;
+use test_utils::compare_contents_or_fix_with_path;
 
-use super::{compile_invocation, CompiledInvocation, ProgramInfo};
+use super::{
+    compile_core_invocation, CompiledInvocation, CompiledInvocationBuilder, InvocationError,
+    ProgramInfo,
+};
 use crate::environment::gas_wallet::GasWallet;
 use crate::environment::Environment;
 use crate::metadata::Metadata;
-use crate::references::{ReferenceExpression, ReferenceValue};
+use crate::reference_deltas::{reference_deltas, render_reference_deltas};
+use crate::references::{ReferenceExpression, ReferenceValue, StatementRefs};
 use crate::relocations::RelocationEntry;
 
 /// Creates a FeltBinaryOperator from a token operator.
@@ -68,6 +81,12 @@ macro_rules! ref_expr_extend {
         );
         $crate::ref_expr_extend!($cells $(, $tok)*)
     };
+    ($cells:ident, [[$a:ident $($op:tt $offset:expr)?] - $offset2:expr] $(, $tok:tt)*) => {
+        $cells.push(
+            $crate::references::CellExpression::DoubleDeref(casm::deref!([$a $($op $offset)?]), -$offset2)
+        );
+        $crate::ref_expr_extend!($cells $(, $tok)*)
+    };
     ($cells:ident, & $a:ident $($op:tt $offset:expr)? $(, $tok:tt)*) => {
         $cells.push($crate::references::CellExpression::IntoSingleCellRef(
             casm::deref!([$a $($op $offset)?])
@@ -188,7 +207,7 @@ pub struct ReducedCompiledInvocation {
     pub results: Vec<ReducedBranchChanges>,
 }
 impl ReducedCompiledInvocation {
-    fn new(compiled_invocation: CompiledInvocation) -> ReducedCompiledInvocation {
+    pub fn new(compiled_invocation: CompiledInvocation) -> ReducedCompiledInvocation {
         ReducedCompiledInvocation {
             instructions: compiled_invocation.instructions,
             relocations: compiled_invocation.relocations,
@@ -215,6 +234,45 @@ impl std::fmt::Debug for ReducedCompiledInvocation {
             .finish()
     }
 }
+impl Display for ReducedCompiledInvocation {
+    /// Displays the invocation as compared to snapshots: the instructions, the relocations, and the
+    /// references and the ap change of every branch.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "// Instructions:")?;
+        for instruction in &self.instructions {
+            writeln!(f, "{instruction};")?;
+        }
+        writeln!(f, "// Relocations:")?;
+        for entry in &self.relocations {
+            writeln!(f, "#{}: {:?}", entry.instruction_idx, entry.relocation)?;
+        }
+        for (branch_idx, branch) in self.results.iter().enumerate() {
+            writeln!(f, "// Branch #{branch_idx} ({}):", branch.ap_change)?;
+            for (var_idx, expr) in branch.refs.iter().enumerate() {
+                writeln!(f, "[{var_idx}]: {expr}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds the size and the long id of `ty` to `type_sizes` and `type_long_ids`, as well as those of
+/// the types it is generic on (e.g. the members of a struct).
+fn add_type_info(
+    context: &MockSpecializationContext,
+    type_sizes: &mut HashMap<ConcreteTypeId, i16>,
+    type_long_ids: &mut HashMap<ConcreteTypeId, ConcreteTypeLongId>,
+    ty: &ConcreteTypeId,
+) {
+    let info = context.try_get_type_info(ty.clone()).unwrap();
+    type_sizes.insert(ty.clone(), info.size);
+    type_long_ids.insert(ty.clone(), info.long_id.clone());
+    for arg in &info.long_id.generic_args {
+        if let GenericArg::Type(arg_ty) = arg {
+            add_type_info(context, type_sizes, type_long_ids, arg_ty);
+        }
+    }
+}
 
 /// Compiles a libfunc into a [ReducedCompiledInvocation].
 /// the arguments are auto-filled according to the signature
@@ -228,6 +286,84 @@ impl std::fmt::Debug for ReducedCompiledInvocation {
 ///     k([0], [2],..., [n_k])
 /// }
 pub fn compile_libfunc(libfunc: &str, refs: Vec<ReferenceExpression>) -> ReducedCompiledInvocation {
+    try_compile_libfunc(libfunc, refs).expect("Failed to compile invocation.")
+}
+
+/// Same as [compile_libfunc], but returns the compilation error instead of panicking.
+pub fn try_compile_libfunc(
+    libfunc: &str,
+    refs: Vec<ReferenceExpression>,
+) -> Result<ReducedCompiledInvocation, InvocationError> {
+    with_core_invocation_builder(libfunc, refs, |libfunc, builder| {
+        compile_core_invocation(libfunc, builder).map(ReducedCompiledInvocation::new)
+    })
+}
+
+/// Compiles a libfunc as in [compile_libfunc], and compares the result to the snapshot
+/// `test_data/invocations/{name}.casm`, or overrides the snapshot if `CAIRO_FIX_TESTS=1`.
+/// A new libfunc is tested by adding such a call, and running the test with `CAIRO_FIX_TESTS=1` to
+/// create its snapshot.
+pub fn compare_libfunc_snapshot(name: &str, libfunc: &str, refs: Vec<ReferenceExpression>) {
+    let path: PathBuf =
+        [env!("CARGO_MANIFEST_DIR"), "test_data", "invocations", &format!("{name}.casm")]
+            .into_iter()
+            .collect();
+    compare_contents_or_fix_with_path(&path, compile_libfunc(libfunc, refs).to_string());
+}
+
+/// Compiles a libfunc as in [compile_libfunc], and returns the rendering of the changes of the
+/// references of each branch, relative to the arguments of the libfunc.
+/// The arguments are `[0]`, `[1]`,..., `[n - 1]`. The `i`th result of a branch is `[i]` if it is of
+/// the type of the `i`th argument - as in the common case of a builtin passed through the libfunc -
+/// and `[n + i]` otherwise.
+pub fn compile_libfunc_reference_deltas(
+    libfunc: &str,
+    refs: Vec<ReferenceExpression>,
+) -> Vec<String> {
+    with_core_invocation_builder(libfunc, refs, |libfunc, builder| {
+        let args: StatementRefs =
+            zip_eq(builder.invocation.args.iter().cloned(), builder.refs.iter().cloned()).collect();
+        let compiled =
+            compile_core_invocation(libfunc, builder).expect("Failed to compile invocation.");
+        compiled
+            .results
+            .into_iter()
+            .map(|changes| {
+                let results: StatementRefs = changes
+                    .refs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, result)| {
+                        let id = match args.get(&VarId::from_usize(i)) {
+                            Some(arg) if arg.ty == result.ty => i,
+                            _ => args.len() + i,
+                        };
+                        (VarId::from_usize(id), result)
+                    })
+                    .collect();
+                render_reference_deltas(&reference_deltas(&args, changes.ap_change, &results))
+            })
+            .collect()
+    })
+}
+
+/// Calls `f` with a [CompiledInvocationBuilder] for invoking the libfunc, auto-filled as in
+/// [compile_libfunc].
+pub fn with_invocation_builder<R>(
+    libfunc: &str,
+    refs: Vec<ReferenceExpression>,
+    f: impl FnOnce(CompiledInvocationBuilder<'_>) -> R,
+) -> R {
+    with_core_invocation_builder(libfunc, refs, |_, builder| f(builder))
+}
+
+/// Calls `f` with the invoked core libfunc and a [CompiledInvocationBuilder] for invoking it, as in
+/// [with_invocation_builder].
+pub fn with_core_invocation_builder<R>(
+    libfunc: &str,
+    refs: Vec<ReferenceExpression>,
+    f: impl FnOnce(&CoreConcreteLibFunc, CompiledInvocationBuilder<'_>) -> R,
+) -> R {
     let long_id =
         sierra::ConcreteLibFuncLongIdParser::new().parse(libfunc.to_string().as_str()).unwrap();
     let context = MockSpecializationContext {};
@@ -236,57 +372,66 @@ pub fn compile_libfunc(libfunc: &str, refs: Vec<ReferenceExpression>) -> Reduced
             .unwrap();
 
     let mut type_sizes = HashMap::default();
+    let mut type_long_ids = HashMap::default();
     for param in libfunc.param_signatures() {
-        type_sizes
-            .insert(param.ty.clone(), context.try_get_type_info(param.ty.clone()).unwrap().size);
+        add_type_info(&context, &mut type_sizes, &mut type_long_ids, &param.ty);
     }
     for branch_signature in libfunc.branch_signatures() {
         for var in &branch_signature.vars {
-            type_sizes
-                .insert(var.ty.clone(), context.try_get_type_info(var.ty.clone()).unwrap().size);
+            add_type_info(&context, &mut type_sizes, &mut type_long_ids, &var.ty);
         }
     }
+    let libfunc_long_ids = HashMap::from([("".into(), long_id)]);
     let program_info = ProgramInfo {
         metadata: &Metadata {
             ap_change_info: ApChangeInfo {
                 variable_values: HashMap::default(),
                 function_ap_change: HashMap::default(),
+                stack_layout: StackLayout::default(),
+            },
+            gas_info: GasInfo {
+                variable_values: HashMap::new(),
+                function_costs: HashMap::new(),
+                store_temp_costs: HashMap::new(),
             },
-            gas_info: GasInfo { variable_values: HashMap::new(), function_costs: HashMap::new() },
+            cost_weights: CostWeights::default(),
         },
         type_sizes: &type_sizes,
+        type_long_ids: &type_long_ids,
+        libfunc_long_ids: &libfunc_long_ids,
     };
 
     let args: Vec<ReferenceValue> = zip_eq(refs.into_iter(), libfunc.param_signatures())
         .map(|(expression, param)| ReferenceValue { expression, ty: param.ty.clone() })
         .collect();
 
-    let environment = Environment::new(GasWallet::Disabled);
-    ReducedCompiledInvocation::new(
-        compile_invocation(
-            program_info,
-            &Invocation {
-                libfunc_id: "".into(),
-                args: (0..args.len()).map(VarId::from_usize).collect(),
-                branches: libfunc
-                    .branch_signatures()
-                    .iter()
-                    .enumerate()
-                    .map(|(i, branch)| BranchInfo {
-                        target: if libfunc.fallthrough() == Some(i) {
-                            BranchTarget::Fallthrough
-                        } else {
-                            BranchTarget::Statement(StatementIdx(i))
-                        },
-                        results: (0..branch.vars.len()).map(VarId::from_usize).collect(),
-                    })
-                    .collect(),
-            },
-            &libfunc,
-            StatementIdx(0),
-            &args,
-            environment,
-        )
-        .expect("Failed to compile invocation."),
-    )
+    let invocation = Invocation {
+        libfunc_id: "".into(),
+        args: (0..args.len()).map(VarId::from_usize).collect(),
+        branches: libfunc
+            .branch_signatures()
+            .iter()
+            .enumerate()
+            .map(|(i, branch)| BranchInfo {
+                target: if libfunc.fallthrough() == Some(i) {
+                    BranchTarget::Fallthrough
+                } else {
+                    BranchTarget::Statement(StatementIdx(i))
+                },
+                results: (0..branch.vars.len()).map(VarId::from_usize).collect(),
+            })
+            .collect(),
+    };
+    let idx = StatementIdx(0);
+    let builder = CompiledInvocationBuilder {
+        ap_changes: core_libfunc_ap_change(&libfunc),
+        gas_changes: core_libfunc_cost(&program_info.metadata.gas_info, &idx, &libfunc),
+        program_info,
+        invocation: &invocation,
+        libfunc: &libfunc,
+        idx,
+        refs: &args,
+        environment: Environment::new(GasWallet::Disabled),
+    };
+    f(&libfunc, builder)
 }
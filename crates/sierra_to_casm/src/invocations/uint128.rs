@@ -3,9 +3,8 @@ use casm::casm_build_extend;
 use casm::operand::{CellRef, ResOperand};
 use num_bigint::BigInt;
 use sierra::extensions::uint128::{IntOperator, Uint128Concrete, Uint128OperationConcreteLibFunc};
-use sierra_ap_change::core_libfunc_ap_change;
 
-use super::{misc, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
+use super::{misc, unpack_refs, CompiledInvocation, CompiledInvocationBuilder, InvocationError};
 use crate::invocations::get_non_fallthrough_statement_id;
 use crate::references::{CellExpression, ReferenceExpression, ReferenceValue};
 use crate::relocations::{Relocation, RelocationEntry};
@@ -23,11 +22,12 @@ pub fn build(
         Uint128Concrete::Operation(Uint128OperationConcreteLibFunc { operator, .. }) => {
             build_u128_op(builder, *operator)
         }
+        Uint128Concrete::CheckedMul(_) => build_u128_checked_mul(builder),
         Uint128Concrete::JumpNotZero(_) => misc::build_jump_nz(builder),
-        Uint128Concrete::Const(libfunc) => Ok(builder.build_only_reference_changes(
+        Uint128Concrete::Const(libfunc) => builder.build_only_reference_changes(
             [ReferenceExpression::from_cell(CellExpression::Immediate(BigInt::from(libfunc.c)))]
                 .into_iter(),
-        )),
+        ),
         Uint128Concrete::FromFelt(_) => build_u128_from_felt(builder),
         Uint128Concrete::ToFelt(_) => misc::build_identity(builder),
         Uint128Concrete::LessThan(_) => build_u128_lt(builder),
@@ -40,19 +40,16 @@ pub fn build(
 pub fn unwrap_range_check_based_binary_op_refs(
     builder: &CompiledInvocationBuilder<'_>,
 ) -> Result<(ResOperand, CellRef, CellRef), InvocationError> {
-    match builder.refs {
-        [
-            ReferenceValue { expression: range_check_expression, .. },
-            ReferenceValue { expression: expr_a, .. },
-            ReferenceValue { expression: expr_b, .. },
-        ] => Ok((
-            range_check_expression.try_unpack_single()?.to_buffer(0)?,
-            expr_a.try_unpack_single()?.to_deref()?,
-            expr_b.try_unpack_single()?.to_deref()?,
-        )),
-
-        refs => Err(InvocationError::WrongNumberOfArguments { expected: 3, actual: refs.len() }),
-    }
+    let [
+        ReferenceValue { expression: range_check_expression, .. },
+        ReferenceValue { expression: expr_a, .. },
+        ReferenceValue { expression: expr_b, .. },
+    ] = unpack_refs::<3>(builder)?;
+    Ok((
+        range_check_expression.try_unpack_single()?.to_buffer(0)?,
+        expr_a.try_unpack_single()?.to_deref()?,
+        expr_b.try_unpack_single()?.to_deref()?,
+    ))
 }
 
 /// Handles a u128 operation with the given op.
@@ -63,7 +60,7 @@ fn build_u128_op(
     let (range_check, a, b) = unwrap_range_check_based_binary_op_refs(&builder)?;
     match op {
         IntOperator::OverflowingAdd | IntOperator::OverflowingSub => {
-            let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder);
+            let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder)?;
             let mut casm_builder = CasmBuilder::default();
             let u128_limit =
                 casm_builder.add_var(ResOperand::Immediate(BigInt::from(u128::MAX) + 1));
@@ -112,15 +109,14 @@ fn build_u128_op(
                 awaiting_relocations,
                 label_state,
                 fallthrough_state,
+                ..
             } = casm_builder.build();
-            // TODO(orizi): Extract the assertion out of the libfunc implementation.
-            assert_eq!(
-                core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-                [fallthrough_state.ap_change, label_state["Target"].ap_change]
-                    .map(sierra_ap_change::ApChange::Known)
-            );
-            let [relocation_index] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
-            Ok(builder.build(
+            builder.check_ap_changes([
+                fallthrough_state.full_ap_change(),
+                label_state["Target"].full_ap_change(),
+            ])?;
+            let [(relocation_index, _)] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
+            builder.build(
                 instructions,
                 vec![RelocationEntry {
                     instruction_idx: *relocation_index,
@@ -147,60 +143,15 @@ fn build_u128_op(
                     .into_iter(),
                 ]
                 .into_iter(),
-            ))
+            )
         }
         IntOperator::DivMod => {
             let mut casm_builder = CasmBuilder::default();
-            let u128_bound_minus_u64_bound = casm_builder
-                .add_var(ResOperand::Immediate(BigInt::from(u128::MAX) - BigInt::from(u64::MAX)));
-            let u64_bound = casm_builder.add_var(ResOperand::Immediate(BigInt::from(u64::MAX) + 1));
-            let one = casm_builder.add_var(ResOperand::Immediate(BigInt::from(1)));
             let range_check = casm_builder.add_var(range_check);
             let a = casm_builder.add_var(ResOperand::Deref(a));
             let b = casm_builder.add_var(ResOperand::Deref(b));
             casm_build_extend! {casm_builder,
-                tempvar r_plus_1;
-                tempvar b_minus_r_minus_1;
-                tempvar q_is_small;
-                tempvar b_or_q_bound_rc_value;
-                tempvar bq;
-                tempvar q;
-                tempvar r;
-                hint DivMod { lhs: a, rhs: b } into { quotient: q, remainder: r };
-                // Both `q` and `r` must be uint128.
-                // We must check `r` explicitly: we later check that `0 <= b - (r + 1)` and
-                // `b * q + r = a`, however, if `r = -1` we may pass both of these checks (say, if
-                // `b = a + 1` and `q = 1`).
-                // We must also check `q` explicitly; the only arithmetic constraint on `q` is
-                // `b * q + r = a`, and if `b = 2`, `a = 1` and `r = 0`, we can take `q` to be the
-                // inverse of 2 (`(PRIME + 1) / 2`, much larger than 2^128) and pass this
-                // constraint.
-                assert *(range_check++) = q;
-                assert *(range_check++) = r;
-                // Verify `r < b` by constraining `0 <= b - (r + 1)`.
-                assert r_plus_1 = r + one;
-                assert b = b_minus_r_minus_1 + r_plus_1;
-                assert *(range_check++) = b_minus_r_minus_1;
-                // Verify `b * q + r = a`.
-                // Since both `b` and `q` can be 2^128-1, we may overflow on `b * q`. To verify this
-                // is not the case, use the fact that `b * q` must be less than 2^128. We know
-                // `min(b, q)` must be less than 2^64. We guess which is less and verify.
-                hint TestLessThan {lhs: q, rhs: u64_bound} into {dst: q_is_small};
-                jump QIsSmall if q_is_small != 0;
-                // `q >= 2^64`, so to verify `b < 2^64` we assert `2^128 - 2^64 + b` is in the range
-                // check bound.
-                assert b_or_q_bound_rc_value = b + u128_bound_minus_u64_bound;
-                jump VerifyBQ;
-                QIsSmall:
-                // `q < 2^64`, compute `2^64 - q`.
-                assert b_or_q_bound_rc_value = q + u128_bound_minus_u64_bound;
-                VerifyBQ:
-                // Now, b_or_q_bound_rc_value contains either `2^128 - 2^64 + q` or
-                // `2^128 - 2^64 + b`. Verify this value is in [0, 2^128).
-                assert *(range_check++) = b_or_q_bound_rc_value;
-                // Range validations done; verify `b * q + r = a` and that's it.
-                assert bq = b * q;
-                assert a = bq + r;
+                tempvar (q, r) = divmod(a, b) with range_check;
             };
 
             let CasmBuildResult {
@@ -208,19 +159,16 @@ fn build_u128_op(
                 awaiting_relocations,
                 label_state: _,
                 fallthrough_state,
+                ..
             } = casm_builder.build();
-            // TODO(orizi): Extract the assertion out of the libfunc implementation.
-            assert_eq!(
-                core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-                [fallthrough_state.ap_change].map(sierra_ap_change::ApChange::Known)
-            );
+            builder.check_ap_changes([fallthrough_state.full_ap_change()])?;
 
             assert!(
                 awaiting_relocations.is_empty(),
                 "Malformed casm builder usage (no non-fallthrough branch in divmod)."
             );
 
-            Ok(builder.build(
+            builder.build(
                 instructions,
                 vec![],
                 vec![
@@ -238,7 +186,7 @@ fn build_u128_op(
                     .into_iter(),
                 ]
                 .into_iter(),
-            ))
+            )
         }
         IntOperator::OverflowingMul => {
             Err(InvocationError::NotImplemented(builder.invocation.clone()))
@@ -246,26 +194,123 @@ fn build_u128_op(
     }
 }
 
+/// Handles a u128 multiplication, branching on whether the product overflows.
+fn build_u128_checked_mul(
+    builder: CompiledInvocationBuilder<'_>,
+) -> Result<CompiledInvocation, InvocationError> {
+    let (range_check, a, b) = unwrap_range_check_based_binary_op_refs(&builder)?;
+    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder)?;
+    let mut casm_builder = CasmBuilder::default();
+    let u64_limit = casm_builder.add_var(ResOperand::Immediate(BigInt::from(u64::MAX) + 1));
+    let u128_limit = casm_builder.add_var(ResOperand::Immediate(BigInt::from(u128::MAX) + 1));
+    let u128_limit_minus_u64_limit = casm_builder
+        .add_var(ResOperand::Immediate(BigInt::from(u128::MAX) - BigInt::from(u64::MAX)));
+    let u128_max = casm_builder.add_var(ResOperand::Immediate(BigInt::from(u128::MAX)));
+    let one = casm_builder.add_var(ResOperand::Immediate(BigInt::from(1)));
+    let range_check = casm_builder.add_var(range_check);
+    let a = casm_builder.add_var(ResOperand::Deref(a));
+    let b = casm_builder.add_var(ResOperand::Deref(b));
+    casm_build_extend! {casm_builder,
+            // Allocating all the values of the no overflow branch, so that `product` would be last.
+            tempvar a_is_small;
+            tempvar b_is_small;
+            tempvar no_overflow;
+            tempvar small_rc_value;
+            tempvar product;
+            ap += 5;
+            // The product of two u128s is below 2^128 only if one of them is below 2^64, in which
+            // case the product is below 2^192 and is computed without wrapping around the prime.
+            hint TestLessThan {lhs: a, rhs: u64_limit} into {dst: a_is_small};
+            jump AIsSmall if a_is_small != 0;
+            hint TestLessThan {lhs: b, rhs: u64_limit} into {dst: b_is_small};
+            jump BIsSmall if b_is_small != 0;
+            // Both `a` and `b` are at least 2^64, so the product is at least 2^128.
+            jump Overflow;
+        AIsSmall:
+            // Verified on success by range checking `2^128 - 2^64 + a`.
+            assert small_rc_value = a + u128_limit_minus_u64_limit;
+            jump SmallValueSet;
+        BIsSmall:
+            // Verified on success by range checking `2^128 - 2^64 + b`.
+            assert small_rc_value = b + u128_limit_minus_u64_limit;
+        SmallValueSet:
+            assert product = a * b;
+            hint TestLessThan {lhs: product, rhs: u128_limit} into {dst: no_overflow};
+            jump NoOverflow if no_overflow != 0;
+        Overflow:
+            // Verify `a * b >= 2^128`, using `q = (2^128 - 1) / a`, by constraining both
+            // `0 <= b - (q + 1)` and `0 <= (q + 1) * a - 2^128`.
+            tempvar q_plus_1;
+            tempvar b_minus_q_minus_1;
+            tempvar q_plus_1_times_a;
+            tempvar excess;
+            tempvar q;
+            tempvar r;
+            hint DivMod {lhs: u128_max, rhs: a} into {quotient: q, remainder: r};
+            // `q` must be a u128, so that `(q + 1) * a` does not wrap around the prime.
+            assert *(range_check++) = q;
+            assert q_plus_1 = q + one;
+            assert b = b_minus_q_minus_1 + q_plus_1;
+            assert *(range_check++) = b_minus_q_minus_1;
+            assert q_plus_1_times_a = q_plus_1 * a;
+            assert q_plus_1_times_a = excess + u128_limit;
+            assert *(range_check++) = excess;
+            // The remainder is not needed for the verification, but is range checked as well, so
+            // that no cell written by the hint is left unconstrained.
+            assert *(range_check++) = r;
+            jump Target;
+        NoOverflow:
+            // Here `product` is below 2^128 (verified by the range check) and equals `a * b`, as
+            // the smaller of the two is below 2^64.
+            assert *(range_check++) = small_rc_value;
+            assert *(range_check++) = product;
+    };
+    let CasmBuildResult {
+        instructions, awaiting_relocations, label_state, fallthrough_state, ..
+    } = casm_builder.build();
+    builder.check_ap_changes([
+        fallthrough_state.full_ap_change(),
+        label_state["Target"].full_ap_change(),
+    ])?;
+    let [(relocation_index, _)] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
+    builder.build(
+        instructions,
+        vec![RelocationEntry {
+            instruction_idx: *relocation_index,
+            relocation: Relocation::RelativeStatementId(failure_handle_statement_id),
+        }],
+        [
+            vec![
+                ReferenceExpression::from_cell(CellExpression::from_res_operand(
+                    fallthrough_state.get_adjusted(range_check),
+                )),
+                ReferenceExpression::from_cell(CellExpression::Deref(
+                    fallthrough_state.get_adjusted_as_cell_ref(product),
+                )),
+            ]
+            .into_iter(),
+            vec![ReferenceExpression::from_cell(CellExpression::from_res_operand(
+                label_state["Target"].get_adjusted(range_check),
+            ))]
+            .into_iter(),
+        ]
+        .into_iter(),
+    )
+}
+
 /// Handles a casting a felt into u128.
 fn build_u128_from_felt(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (range_check, value) = match builder.refs {
-        [
-            ReferenceValue { expression: range_check_expression, .. },
-            ReferenceValue { expression: expr_value, .. },
-        ] => (
-            range_check_expression.try_unpack_single()?.to_buffer(3)?,
-            expr_value.try_unpack_single()?.to_deref()?,
-        ),
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 2,
-                actual: refs.len(),
-            });
-        }
-    };
-    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder);
+    let [
+        ReferenceValue { expression: range_check_expression, .. },
+        ReferenceValue { expression: expr_value, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    let (range_check, value) = (
+        range_check_expression.try_unpack_single()?.to_buffer(3)?,
+        expr_value.try_unpack_single()?.to_deref()?,
+    );
+    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder)?;
     let u128_bound: BigInt = BigInt::from(u128::MAX) + 1; // = 2**128.
     // Represent the maximal possible value (PRIME - 1) as 2**128 * max_x + max_y.
     let max_x: i128 = 10633823966279327296825105735305134080;
@@ -318,16 +363,15 @@ fn build_u128_from_felt(
         NoOverflow:
             assert *(range_check++) = value;
     };
-    let CasmBuildResult { instructions, awaiting_relocations, label_state, fallthrough_state } =
-        casm_builder.build();
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change, label_state["FailureHandle"].ap_change]
-            .map(sierra_ap_change::ApChange::Known)
-    );
-    let [relocation_index] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
-    Ok(builder.build(
+    let CasmBuildResult {
+        instructions, awaiting_relocations, label_state, fallthrough_state, ..
+    } = casm_builder.build();
+    builder.check_ap_changes([
+        fallthrough_state.full_ap_change(),
+        label_state["FailureHandle"].full_ap_change(),
+    ])?;
+    let [(relocation_index, _)] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
+    builder.build(
         instructions,
         vec![RelocationEntry {
             instruction_idx: *relocation_index,
@@ -357,14 +401,14 @@ fn build_u128_from_felt(
             .into_iter(),
         ]
         .into_iter(),
-    ))
+    )
 }
 
 fn build_u128_lt(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
     let (range_check, a, b) = unwrap_range_check_based_binary_op_refs(&builder)?;
-    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder);
+    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder)?;
     let mut casm_builder = CasmBuilder::default();
     let u128_limit = casm_builder.add_var(ResOperand::Immediate(BigInt::from(u128::MAX) + 1));
     let range_check = casm_builder.add_var(range_check);
@@ -383,16 +427,15 @@ fn build_u128_lt(
         False:
             assert *(range_check++) = a_minus_b;
     };
-    let CasmBuildResult { instructions, awaiting_relocations, label_state, fallthrough_state } =
-        casm_builder.build();
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change, label_state["True"].ap_change]
-            .map(sierra_ap_change::ApChange::Known)
-    );
-    let [relocation_index] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
-    Ok(builder.build(
+    let CasmBuildResult {
+        instructions, awaiting_relocations, label_state, fallthrough_state, ..
+    } = casm_builder.build();
+    builder.check_ap_changes([
+        fallthrough_state.full_ap_change(),
+        label_state["True"].full_ap_change(),
+    ])?;
+    let [(relocation_index, _)] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
+    builder.build(
         instructions,
         vec![RelocationEntry {
             instruction_idx: *relocation_index,
@@ -409,14 +452,14 @@ fn build_u128_lt(
             .into_iter(),
         ]
         .into_iter(),
-    ))
+    )
 }
 
 fn build_u128_le(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
     let (range_check, a, b) = unwrap_range_check_based_binary_op_refs(&builder)?;
-    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder);
+    let failure_handle_statement_id = get_non_fallthrough_statement_id(&builder)?;
     let mut casm_builder = CasmBuilder::default();
     let u128_limit = casm_builder.add_var(ResOperand::Immediate(BigInt::from(u128::MAX) + 1));
     let range_check = casm_builder.add_var(range_check);
@@ -435,16 +478,15 @@ fn build_u128_le(
             assert wrapping_a_minus_b = b_minus_a + u128_limit;
             assert *(range_check++) = wrapping_a_minus_b;
     };
-    let CasmBuildResult { instructions, awaiting_relocations, label_state, fallthrough_state } =
-        casm_builder.build();
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change, label_state["True"].ap_change]
-            .map(sierra_ap_change::ApChange::Known)
-    );
-    let [relocation_index] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
-    Ok(builder.build(
+    let CasmBuildResult {
+        instructions, awaiting_relocations, label_state, fallthrough_state, ..
+    } = casm_builder.build();
+    builder.check_ap_changes([
+        fallthrough_state.full_ap_change(),
+        label_state["True"].full_ap_change(),
+    ])?;
+    let [(relocation_index, _)] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
+    builder.build(
         instructions,
         vec![RelocationEntry {
             instruction_idx: *relocation_index,
@@ -461,27 +503,21 @@ fn build_u128_le(
             .into_iter(),
         ]
         .into_iter(),
-    ))
+    )
 }
 
 // Handle u128 equality check.
 fn build_u128_eq(
     builder: CompiledInvocationBuilder<'_>,
 ) -> Result<CompiledInvocation, InvocationError> {
-    let (a, b) = match builder.refs {
-        [ReferenceValue { expression: expr_a, .. }, ReferenceValue { expression: expr_b, .. }] => {
-            (expr_a.try_unpack_single()?.to_deref()?, expr_b.try_unpack_single()?.to_deref()?)
-        }
-        refs => {
-            return Err(InvocationError::WrongNumberOfArguments {
-                expected: 2,
-                actual: refs.len(),
-            });
-        }
-    };
+    let [
+        ReferenceValue { expression: expr_a, .. },
+        ReferenceValue { expression: expr_b, .. },
+    ] = unpack_refs::<2>(&builder)?;
+    let (a, b) = (expr_a.try_unpack_single()?.to_deref()?, expr_b.try_unpack_single()?.to_deref()?);
 
     // The target line to jump to if a != b.
-    let target_statement_id = get_non_fallthrough_statement_id(&builder);
+    let target_statement_id = get_non_fallthrough_statement_id(&builder)?;
 
     let mut casm_builder = CasmBuilder::default();
     let a = casm_builder.add_var(ResOperand::Deref(a));
@@ -495,22 +531,21 @@ fn build_u128_eq(
             jump Equal;
         NotEqual:
     };
-    let CasmBuildResult { instructions, awaiting_relocations, fallthrough_state, label_state } =
-        casm_builder.build();
+    let CasmBuildResult {
+        instructions, awaiting_relocations, fallthrough_state, label_state, ..
+    } = casm_builder.build();
 
-    // TODO(orizi): Extract the assertion out of the libfunc implementation.
-    assert_eq!(
-        core_libfunc_ap_change::core_libfunc_ap_change(builder.libfunc),
-        [fallthrough_state.ap_change, label_state["Equal"].ap_change]
-            .map(sierra_ap_change::ApChange::Known)
-    );
-    let [relocation_index] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
-    Ok(builder.build(
+    builder.check_ap_changes([
+        fallthrough_state.full_ap_change(),
+        label_state["Equal"].full_ap_change(),
+    ])?;
+    let [(relocation_index, _)] = &awaiting_relocations[..] else { panic!("Malformed casm builder usage.") };
+    builder.build(
         instructions,
         vec![RelocationEntry {
             instruction_idx: *relocation_index,
             relocation: Relocation::RelativeStatementId(target_statement_id),
         }],
         vec![vec![].into_iter(), vec![].into_iter()].into_iter(),
-    ))
+    )
 }
@@ -179,3 +179,60 @@ fn test_eq() {
         }
     );
 }
+
+#[test]
+fn test_checked_mul() {
+    let u64_limit: BigInt = BigInt::from(u64::MAX) + 1;
+    let u128_limit: BigInt = BigInt::from(u128::MAX) + 1;
+    let u128_limit_minus_u64_limit: BigInt = &u128_limit - &u64_limit;
+    let u128_max: BigInt = BigInt::from(u128::MAX);
+    assert_eq!(
+        compile_libfunc(
+            "u128_checked_mul",
+            vec![ref_expr!([fp - 5]), ref_expr!([fp - 4]), ref_expr!([fp - 3])]
+        ),
+        ReducedCompiledInvocation {
+            instructions: casm! {
+                ap += 5;
+                %{ memory[ap - 5] = memory[fp - 4] < (u64_limit.clone()) %}
+                jmp rel 6 if [ap - 5] != 0;
+                %{ memory[ap - 4] = memory[fp - 3] < (u64_limit.clone()) %}
+                jmp rel 8 if [ap - 4] != 0;
+                jmp rel 11;
+                [ap - 2] = [fp - 4] + (u128_limit_minus_u64_limit.clone());
+                jmp rel 4;
+                [ap - 2] = [fp - 3] + (u128_limit_minus_u64_limit.clone());
+                [ap - 1] = [fp - 4] * [fp - 3];
+                %{ memory[ap - 3] = memory[ap - 1] < (u128_limit.clone()) %}
+                jmp rel 14 if [ap - 3] != 0;
+                %{ (memory[ap + 4], memory[ap + 5]) = divmod((u128_max.clone()), memory[fp - 4]) %}
+                [ap + 4] = [[fp - 5] + 0], ap++;
+                [ap - 1] = [ap + 3] + 1, ap++;
+                [fp - 3] = [ap - 1] + [ap - 2], ap++;
+                [ap - 2] = [[fp - 5] + 1], ap++;
+                [ap - 2] = [ap - 4] * [fp - 4], ap++;
+                [ap - 3] = [ap - 2] + (u128_limit.clone()), ap++;
+                [ap - 3] = [[fp - 5] + 2];
+                [ap - 1] = [[fp - 5] + 3];
+                jmp rel 0;
+                [ap - 2] = [[fp - 5] + 0];
+                [ap - 1] = [[fp - 5] + 1];
+            }
+            .instructions,
+            relocations: vec![RelocationEntry {
+                instruction_idx: 17,
+                relocation: Relocation::RelativeStatementId(StatementIdx(1))
+            }],
+            results: vec![
+                ReducedBranchChanges {
+                    refs: vec![ref_expr!([fp - 5] + 2), ref_expr!([ap - 1])],
+                    ap_change: ApChange::Known(5)
+                },
+                ReducedBranchChanges {
+                    refs: vec![ref_expr!([fp - 5] + 4)],
+                    ap_change: ApChange::Known(11)
+                }
+            ]
+        }
+    );
+}
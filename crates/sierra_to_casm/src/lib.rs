@@ -1,12 +1,17 @@
 //! CASM backend. Compiles from Sierra down to CASM. See [sierra] and [casm]
 
 pub mod annotations;
+pub mod bytecode_size;
 pub mod compiler;
 pub mod environment;
+pub mod extensions;
 pub mod invocations;
 pub mod metadata;
+pub mod reference_deltas;
 pub mod references;
 pub mod relocations;
+pub mod runtime_routines;
+pub mod syscall_records;
 #[cfg(any(feature = "testing", test))]
 pub mod test_utils;
 pub mod type_sizes;
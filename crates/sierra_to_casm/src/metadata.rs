@@ -1,3 +1,4 @@
+use casm::cost::CostWeights;
 use sierra_ap_change::ap_change_info::ApChangeInfo;
 use sierra_gas::gas_info::GasInfo;
 
@@ -7,4 +8,7 @@ pub struct Metadata {
     pub ap_change_info: ApChangeInfo,
     /// Gas information for validating Sierra code and taking the apporiate amount of gas.
     pub gas_info: GasInfo,
+    /// The relative weights of code size and steps, for choosing between alternative lowerings of
+    /// an invocation.
+    pub cost_weights: CostWeights,
 }
@@ -0,0 +1,117 @@
+//! Rendering of the changes of the references of the variables across a statement.
+//!
+//! Only the variables whose reference changed are listed, so that the rendering of a statement is
+//! not affected by changes to the unrelated variables around it.
+
+use std::fmt::Display;
+
+use casm::ap_change::{ApChange, ApplyApChange};
+use casm::operand::CellRef;
+use itertools::Itertools;
+use sierra::ids::VarId;
+
+use crate::references::{CellExpression, ReferenceExpression, StatementRefs};
+
+#[cfg(test)]
+#[path = "reference_deltas_test.rs"]
+mod test;
+
+/// The change of the reference of a single variable across a statement.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReferenceDelta {
+    /// The variable is defined by the statement.
+    Defined(ReferenceExpression),
+    /// The variable is consumed by the statement.
+    Consumed,
+    /// A non-deref expression of the variable was computed into a cell.
+    MaterializedToTempvar(CellRef),
+    /// The variable still points to the same cell, with its offset changed by the given amount.
+    OffsetBumped(i16),
+    /// Any other change of the reference of the variable.
+    Changed { from: ReferenceExpression, to: ReferenceExpression },
+}
+impl ReferenceDelta {
+    /// Returns the delta between the reference of a variable before a statement, with the ap
+    /// change of the statement already applied, and its reference after the statement.
+    /// Returns None if the reference is unchanged.
+    fn between(before: &ReferenceExpression, after: &ReferenceExpression) -> Option<Self> {
+        if before == after {
+            return None;
+        }
+        Some(match (&before.cells[..], &after.cells[..]) {
+            ([before_cell], [after_cell]) => {
+                match (before_cell.to_deref_with_offset(), after_cell.to_deref_with_offset()) {
+                    (Ok((before_base, before_offset)), Ok((after_base, after_offset)))
+                        if before_base == after_base =>
+                    {
+                        ReferenceDelta::OffsetBumped(after_offset - before_offset)
+                    }
+                    _ => match (before_cell, after_cell) {
+                        (CellExpression::Deref(_), _) => ReferenceDelta::changed(before, after),
+                        (_, CellExpression::Deref(cell)) => {
+                            ReferenceDelta::MaterializedToTempvar(*cell)
+                        }
+                        _ => ReferenceDelta::changed(before, after),
+                    },
+                }
+            }
+            _ => ReferenceDelta::changed(before, after),
+        })
+    }
+
+    fn changed(from: &ReferenceExpression, to: &ReferenceExpression) -> Self {
+        ReferenceDelta::Changed { from: from.clone(), to: to.clone() }
+    }
+}
+impl Display for ReferenceDelta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReferenceDelta::Defined(expression) => write!(f, "= {expression}"),
+            ReferenceDelta::Consumed => write!(f, "consumed"),
+            ReferenceDelta::MaterializedToTempvar(cell) => write!(f, "-> tempvar {cell}"),
+            ReferenceDelta::OffsetBumped(offset) if *offset < 0 => write!(f, "-= {}", -offset),
+            ReferenceDelta::OffsetBumped(offset) => write!(f, "+= {offset}"),
+            ReferenceDelta::Changed { from, to } => write!(f, "{from} -> {to}"),
+        }
+    }
+}
+
+/// Returns the changes of the references of the variables across a statement, given the
+/// references before the statement, the ap change of the statement and the references after it.
+/// The references before the statement are compared after applying the ap change, so references
+/// only moved by the ap change are considered unchanged. References the ap change cannot be
+/// applied to are compared as is.
+/// Only the changed variables are returned, ordered by their ids.
+pub fn reference_deltas(
+    before: &StatementRefs,
+    ap_change: ApChange,
+    after: &StatementRefs,
+) -> Vec<(VarId, ReferenceDelta)> {
+    before
+        .keys()
+        .chain(after.keys())
+        .unique()
+        .sorted_by_key(|var_id| var_id.id)
+        .filter_map(|var_id| {
+            let delta = match (before.get(var_id), after.get(var_id)) {
+                (Some(before), Some(after)) => {
+                    let before_expression = before
+                        .expression
+                        .clone()
+                        .apply_ap_change(ap_change)
+                        .unwrap_or_else(|_| before.expression.clone());
+                    ReferenceDelta::between(&before_expression, &after.expression)?
+                }
+                (Some(_), None) => ReferenceDelta::Consumed,
+                (None, Some(after)) => ReferenceDelta::Defined(after.expression.clone()),
+                (None, None) => unreachable!("The variable is taken from one of the maps."),
+            };
+            Some((var_id.clone(), delta))
+        })
+        .collect()
+}
+
+/// Renders the changes of the references of variables, a line per variable.
+pub fn render_reference_deltas(deltas: &[(VarId, ReferenceDelta)]) -> String {
+    deltas.iter().map(|(var_id, delta)| format!("{var_id}: {delta}\n")).collect()
+}
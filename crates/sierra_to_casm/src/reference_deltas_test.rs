@@ -0,0 +1,128 @@
+use casm::ap_change::ApChange;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use sierra::ids::VarId;
+
+use super::{reference_deltas, render_reference_deltas};
+use crate::ref_expr;
+use crate::references::{ReferenceExpression, ReferenceValue, StatementRefs};
+
+/// Returns the references of the variables with the given ids.
+fn refs(refs: Vec<(usize, ReferenceExpression)>) -> StatementRefs {
+    refs.into_iter()
+        .map(|(id, expression)| {
+            (VarId::from_usize(id), ReferenceValue { expression, ty: "felt".into() })
+        })
+        .collect()
+}
+
+/// Renders the deltas of the references of the variables across a statement.
+fn render(before: StatementRefs, ap_change: ApChange, after: StatementRefs) -> String {
+    render_reference_deltas(&reference_deltas(&before, ap_change, &after))
+}
+
+#[test]
+fn unchanged_references_are_omitted() {
+    assert_eq!(
+        render(
+            refs(vec![(0, ref_expr!([fp + 1])), (1, ref_expr!([ap - 1]))]),
+            ApChange::Known(2),
+            refs(vec![(0, ref_expr!([fp + 1])), (1, ref_expr!([ap - 3]))]),
+        ),
+        ""
+    );
+}
+
+#[test]
+fn defined_and_consumed() {
+    assert_eq!(
+        render(
+            refs(vec![(0, ref_expr!([fp + 1])), (1, ref_expr!([fp + 2]))]),
+            ApChange::Known(0),
+            refs(vec![(2, ref_expr!([fp + 1] * [fp + 2]))]),
+        ),
+        indoc! {"
+            [0]: consumed
+            [1]: consumed
+            [2]: = [fp + 1] * [fp + 2]
+        "}
+    );
+}
+
+#[test]
+fn materialized_to_tempvar() {
+    assert_eq!(
+        render(
+            refs(vec![(0, ref_expr!([fp + 3] + 1)), (1, ref_expr!(5))]),
+            ApChange::Known(2),
+            refs(vec![(0, ref_expr!([ap - 2])), (1, ref_expr!([ap - 1]))]),
+        ),
+        indoc! {"
+            [0]: -> tempvar [ap + -2]
+            [1]: -> tempvar [ap + -1]
+        "}
+    );
+}
+
+#[test]
+fn offset_bumped() {
+    assert_eq!(
+        render(
+            refs(vec![
+                (0, ref_expr!([fp + 1])),
+                (1, ref_expr!([fp + 2] + 3)),
+                (2, ref_expr!([ap - 1] + 4)),
+            ]),
+            ApChange::Known(1),
+            refs(vec![
+                (0, ref_expr!([fp + 1] + 2)),
+                (1, ref_expr!([fp + 2] + 1)),
+                (2, ref_expr!([ap - 2] + 5)),
+            ]),
+        ),
+        indoc! {"
+            [0]: += 2
+            [1]: -= 2
+            [2]: += 1
+        "}
+    );
+}
+
+#[test]
+fn changed() {
+    assert_eq!(
+        render(
+            refs(vec![(0, ref_expr!([fp + 1])), (1, ref_expr!([fp + 2], [fp + 3]))]),
+            ApChange::Known(0),
+            refs(vec![(0, ref_expr!([[fp + 2] + 4])), (1, ref_expr!([fp + 2], [ap - 1]))]),
+        ),
+        indoc! {"
+            [0]: [fp + 1] -> [[fp + 2] + 4]
+            [1]: ([fp + 2], [fp + 3]) -> ([fp + 2], [ap + -1])
+        "}
+    );
+}
+
+#[test]
+fn unknown_ap_change() {
+    // References to ap based cells can not be moved by an unknown ap change, and are compared as
+    // is.
+    assert_eq!(
+        render(
+            refs(vec![(0, ref_expr!([fp + 1])), (1, ref_expr!([ap - 1]))]),
+            ApChange::Unknown,
+            refs(vec![(0, ref_expr!([fp + 1])), (1, ref_expr!([fp + 4]))]),
+        ),
+        indoc! {"
+            [1]: [ap + -1] -> [fp + 4]
+        "}
+    );
+    assert_eq!(
+        reference_deltas(
+            &refs(vec![(0, ref_expr!([ap - 1]))]),
+            ApChange::Unknown,
+            &refs(vec![(0, ref_expr!([ap - 1]))]),
+        ),
+        vec![]
+    );
+}
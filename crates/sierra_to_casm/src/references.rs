@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt::Display;
 
 use casm::ap_change::ApplyApChange;
 use casm::operand::{BinOpOperand, CellRef, DerefOrImmediate, Register, ResOperand};
@@ -7,13 +8,19 @@ use num_traits::cast::ToPrimitive;
 use sierra::extensions::felt::{FeltBinaryOperator, FeltUnaryOperator};
 use sierra::ids::{ConcreteTypeId, VarId};
 use sierra::program::{Function, StatementIdx};
+use sierra_ap_change::{InputForm, StackLayout};
 use thiserror::Error;
+use utils::diagnostic::Diagnostic;
 use utils::try_extract_matches;
 use {casm, sierra};
 
 use crate::invocations::InvocationError;
 use crate::type_sizes::TypeSizeMap;
 
+#[cfg(test)]
+#[path = "references_test.rs"]
+mod test;
+
 #[derive(Error, Debug, Eq, PartialEq)]
 pub enum ReferencesError {
     #[error("Invalid function declaration.")]
@@ -24,6 +31,24 @@ pub enum ReferencesError {
         "One of the arguments does not match the expected type of the libfunc or return statement."
     )]
     InvalidReferenceTypeForArgument,
+    #[error("Expected a reference of {expected} cells, got {actual}.")]
+    WrongNumberOfCells { expected: usize, actual: usize },
+}
+
+impl From<ReferencesError> for Diagnostic {
+    fn from(error: ReferencesError) -> Self {
+        let message = error.to_string();
+        match error {
+            ReferencesError::InvalidFunctionDeclaration(function) => {
+                Diagnostic::error("E0140", message).with_function(function.id)
+            }
+            ReferencesError::DanglingReferences { statement_idx, .. } => {
+                Diagnostic::error("E0141", message).with_statement(statement_idx.0)
+            }
+            ReferencesError::InvalidReferenceTypeForArgument => Diagnostic::error("E0142", message),
+            ReferencesError::WrongNumberOfCells { .. } => Diagnostic::error("E0143", message),
+        }
+    }
 }
 
 pub type StatementRefs = HashMap<VarId, ReferenceValue>;
@@ -41,6 +66,13 @@ pub struct UnaryOpExpression {
     pub op: FeltUnaryOperator,
     pub a: DerefOrImmediate,
 }
+impl Display for UnaryOpExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.op {
+            FeltUnaryOperator::Neg => write!(f, "-{}", self.a),
+        }
+    }
+}
 impl ApplyApChange for UnaryOpExpression {
     fn apply_known_ap_change(self, ap_change: usize) -> Option<Self> {
         Some(UnaryOpExpression { op: self.op, a: self.a.apply_known_ap_change(ap_change)? })
@@ -57,6 +89,17 @@ pub struct BinOpExpression {
     pub a: CellRef,
     pub b: DerefOrImmediate,
 }
+impl Display for BinOpExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self.op {
+            FeltBinaryOperator::Add => "+",
+            FeltBinaryOperator::Sub => "-",
+            FeltBinaryOperator::Mul => "*",
+            FeltBinaryOperator::Div => "/",
+        };
+        write!(f, "{} {} {}", self.a, op, self.b)
+    }
+}
 impl ApplyApChange for BinOpExpression {
     fn apply_known_ap_change(self, ap_change: usize) -> Option<Self> {
         Some(BinOpExpression {
@@ -82,6 +125,18 @@ pub enum CellExpression {
     UnaryOp(UnaryOpExpression),
     BinOp(BinOpExpression),
 }
+impl Display for CellExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CellExpression::Deref(cell) => write!(f, "{cell}"),
+            CellExpression::DoubleDeref(cell, offset) => write!(f, "[{cell} + {offset}]"),
+            CellExpression::IntoSingleCellRef(cell) => write!(f, "&{cell}"),
+            CellExpression::Immediate(imm) => write!(f, "{imm}"),
+            CellExpression::UnaryOp(operand) => write!(f, "{operand}"),
+            CellExpression::BinOp(operand) => write!(f, "{operand}"),
+        }
+    }
+}
 impl CellExpression {
     pub fn from_res_operand(operand: ResOperand) -> Self {
         match operand {
@@ -122,6 +177,33 @@ impl CellExpression {
         }
     }
 
+    /// Returns the cell expression as a single operand of an instruction.
+    pub fn to_res_operand(&self) -> Result<ResOperand, InvocationError> {
+        match self {
+            CellExpression::Deref(cell) => Ok(ResOperand::Deref(*cell)),
+            CellExpression::DoubleDeref(cell, offset) => {
+                Ok(ResOperand::DoubleDeref(*cell, *offset))
+            }
+            CellExpression::Immediate(imm) => Ok(ResOperand::Immediate(imm.clone())),
+            CellExpression::BinOp(BinOpExpression { op, a, b }) => {
+                Ok(ResOperand::BinOp(BinOpOperand {
+                    op: match op {
+                        FeltBinaryOperator::Add => casm::operand::Operation::Add,
+                        FeltBinaryOperator::Mul => casm::operand::Operation::Mul,
+                        FeltBinaryOperator::Sub | FeltBinaryOperator::Div => {
+                            return Err(InvocationError::InvalidReferenceExpressionForArgument);
+                        }
+                    },
+                    a: *a,
+                    b: b.clone(),
+                }))
+            }
+            CellExpression::IntoSingleCellRef(_) | CellExpression::UnaryOp(_) => {
+                Err(InvocationError::InvalidReferenceExpressionForArgument)
+            }
+        }
+    }
+
     /// Returns the reference as a buffer with at least `required_slack` next cells that can be
     /// written as an instruction offset.
     pub fn to_buffer(&self, required_slack: i16) -> Result<ResOperand, InvocationError> {
@@ -146,12 +228,58 @@ impl CellExpression {
 pub struct ReferenceExpression {
     pub cells: Vec<CellExpression>,
 }
+impl Display for ReferenceExpression {
+    /// Displays a single cell expression as is, and other expressions as a tuple of their cells.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let [cell_expr] = &self.cells[..] {
+            return write!(f, "{cell_expr}");
+        }
+        write!(f, "(")?;
+        for (i, cell_expr) in self.cells.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{cell_expr}")?;
+        }
+        write!(f, ")")
+    }
+}
 
 impl ReferenceExpression {
     /// Builds a reference expression containing only a single cell
     pub fn from_cell(cell_expr: CellExpression) -> Self {
         Self { cells: vec![cell_expr] }
     }
+    /// Builds a reference expression containing no cells - the value of a zero-sized type.
+    pub fn unit() -> Self {
+        Self { cells: vec![] }
+    }
+    /// Builds a reference expression of `size` consecutive cells, starting `offset` cells after the
+    /// address held in `cell` - such as the value pointed to by a box.
+    /// Fails if the offset of any of the cells does not fit in an instruction offset.
+    pub fn from_double_deref(
+        cell: CellRef,
+        offset: i16,
+        size: usize,
+    ) -> Result<Self, InvocationError> {
+        let cells = (0..size)
+            .map(|i| {
+                i16::try_from(i)
+                    .ok()
+                    .and_then(|i| offset.checked_add(i))
+                    .map(|offset| CellExpression::DoubleDeref(cell, offset))
+                    .ok_or(InvocationError::InvalidReferenceExpressionForArgument)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { cells })
+    }
+    /// If there are exactly `N` cells in the ReferenceExpression returns the contained
+    /// CellExpressions.
+    pub fn try_unpack<const N: usize>(&self) -> Result<[&CellExpression; N], ReferencesError> {
+        self.cells.iter().collect::<Vec<_>>().try_into().map_err(|_| {
+            ReferencesError::WrongNumberOfCells { expected: N, actual: self.cells.len() }
+        })
+    }
     /// If there is only one cell in the ReferenceExpression returns the contained CellExpression.
     pub fn try_unpack_single(&self) -> Result<CellExpression, InvocationError> {
         if let [cell_expr] = &self.cells[..] {
@@ -160,6 +288,15 @@ impl ReferenceExpression {
             Err(InvocationError::InvalidReferenceExpressionForArgument)
         }
     }
+
+    /// Returns the form of the expression, as used for calculating form dependent ap changes.
+    pub fn input_form(&self) -> InputForm {
+        match &self.cells[..] {
+            [CellExpression::Deref(_)] => InputForm::Deref,
+            [CellExpression::Immediate(_)] => InputForm::Immediate,
+            _ => InputForm::Compound,
+        }
+    }
 }
 
 impl ApplyApChange for CellExpression {
@@ -216,32 +353,34 @@ impl ApplyApChange for ReferenceExpression {
 pub fn build_function_arguments_refs(
     func: &Function,
     type_sizes: &TypeSizeMap,
+    stack_layout: &StackLayout,
 ) -> Result<StatementRefs, ReferencesError> {
     let mut refs = HashMap::with_capacity(func.params.len());
     let mut offset = -3_i16;
     for param in func.params.iter().rev() {
-        let size = type_sizes
+        let size = *type_sizes
             .get(&param.ty)
             .ok_or_else(|| ReferencesError::InvalidFunctionDeclaration(func.clone()))?;
+        // Values passed by pointer are referenced through the last cell of their stack slot.
+        let cells = if stack_layout.is_by_pointer(size as usize) {
+            (0..size)
+                .map(|i| CellExpression::DoubleDeref(CellRef { register: Register::FP, offset }, i))
+                .collect()
+        } else {
+            ((offset - size + 1)..(offset + 1))
+                .map(|i| CellExpression::Deref(CellRef { register: Register::FP, offset: i }))
+                .collect()
+        };
         if refs
             .insert(
                 param.id.clone(),
-                ReferenceValue {
-                    expression: ReferenceExpression {
-                        cells: ((offset - size + 1)..(offset + 1))
-                            .map(|i| {
-                                CellExpression::Deref(CellRef { register: Register::FP, offset: i })
-                            })
-                            .collect(),
-                    },
-                    ty: param.ty.clone(),
-                },
+                ReferenceValue { expression: ReferenceExpression { cells }, ty: param.ty.clone() },
             )
             .is_some()
         {
             return Err(ReferencesError::InvalidFunctionDeclaration(func.clone()));
         }
-        offset -= size;
+        offset -= stack_layout.slot_size(size as usize) as i16;
     }
     Ok(refs)
 }
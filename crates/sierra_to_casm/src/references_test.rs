@@ -0,0 +1,95 @@
+use casm::ap_change::{ApChange, ApChangeError, ApplyApChange};
+use casm::operand::ResOperand;
+use casm::{deref, res};
+use pretty_assertions::assert_eq;
+use test_case::test_case;
+
+use super::{CellExpression, ReferenceExpression, ReferencesError};
+use crate::invocations::InvocationError;
+use crate::ref_expr;
+
+#[test_case(res!([[fp + 1] + 3]); "positive offset")]
+#[test_case(res!([[ap + 2] - 4]); "negative offset")]
+#[test_case(res!([[fp - 3] + i16::MAX]); "max offset")]
+#[test_case(res!([[ap + 0] + i16::MIN]); "min offset")]
+fn double_deref_round_trip(operand: ResOperand) {
+    assert_eq!(CellExpression::from_res_operand(operand.clone()).to_res_operand(), Ok(operand));
+}
+
+#[test]
+fn double_deref_is_not_a_cell_ref() {
+    let expr = ref_expr!([[fp + 1] - 2]).try_unpack_single().unwrap();
+    assert_eq!(expr.to_deref(), Err(InvocationError::InvalidReferenceExpressionForArgument));
+    assert_eq!(expr.to_buffer(0), Err(InvocationError::InvalidReferenceExpressionForArgument));
+}
+
+#[test]
+fn from_double_deref() {
+    assert_eq!(
+        ReferenceExpression::from_double_deref(deref!([fp + 1]), -1, 3),
+        Ok(ref_expr!([[fp + 1] - 1], [[fp + 1]], [[fp + 1] + 1]))
+    );
+    assert_eq!(
+        ReferenceExpression::from_double_deref(deref!([fp + 1]), i16::MAX - 1, 2),
+        Ok(ref_expr!([[fp + 1] + (i16::MAX - 1)], [[fp + 1] + i16::MAX]))
+    );
+}
+
+#[test_case(i16::MAX - 1, 3; "past max offset")]
+#[test_case(i16::MAX, 2; "at max offset")]
+#[test_case(0, i16::MAX as usize + 2; "too many cells")]
+fn from_double_deref_offset_overflow(offset: i16, size: usize) {
+    assert_eq!(
+        ReferenceExpression::from_double_deref(deref!([ap + 1]), offset, size),
+        Err(InvocationError::InvalidReferenceExpressionForArgument)
+    );
+}
+
+#[test]
+fn double_deref_ap_change_overflow() {
+    let expr = CellExpression::from_res_operand(res!([[ap + (i16::MIN + 1)] + 5]));
+    assert_eq!(
+        expr.clone().apply_ap_change(ApChange::Known(1)),
+        Ok(CellExpression::from_res_operand(res!([[ap + i16::MIN] + 5])))
+    );
+    assert_eq!(expr.apply_ap_change(ApChange::Known(2)), Err(ApChangeError::OffsetOverflow));
+}
+
+#[test]
+fn buffer_offset_overflow() {
+    let expr = ref_expr!([fp + 1] + (i16::MAX - 1)).try_unpack_single().unwrap();
+    assert_eq!(expr.to_buffer(1), Ok(res!([fp + 1] + (i16::MAX - 1))));
+    assert_eq!(expr.to_buffer(2), Err(InvocationError::InvalidReferenceExpressionForArgument));
+}
+
+#[test]
+fn try_unpack() {
+    let expr = ref_expr!([fp + 1], [[fp + 2] + 3]);
+    assert_eq!(
+        expr.try_unpack::<2>(),
+        Ok([
+            &CellExpression::Deref(deref!([fp + 1])),
+            &CellExpression::DoubleDeref(deref!([fp + 2]), 3)
+        ])
+    );
+    assert_eq!(
+        expr.try_unpack::<1>(),
+        Err(ReferencesError::WrongNumberOfCells { expected: 1, actual: 2 })
+    );
+    assert_eq!(
+        expr.try_unpack::<3>(),
+        Err(ReferencesError::WrongNumberOfCells { expected: 3, actual: 2 })
+    );
+}
+
+#[test]
+fn unit() {
+    let expr = ReferenceExpression::unit();
+    assert_eq!(expr.to_string(), "()");
+    assert_eq!(expr.try_unpack::<0>(), Ok([]));
+    assert_eq!(
+        expr.try_unpack_single(),
+        Err(InvocationError::InvalidReferenceExpressionForArgument)
+    );
+    assert_eq!(expr.apply_ap_change(ApChange::Unknown), Ok(ReferenceExpression::unit()));
+}
@@ -3,14 +3,20 @@ use casm::instructions::{
 };
 use casm::operand::DerefOrImmediate;
 use sierra::program::StatementIdx;
+use utils::ordered_hash_map::OrderedHashMap;
 
-type CodeOffset = usize;
+use crate::runtime_routines::RuntimeRoutine;
+
+pub type CodeOffset = usize;
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum Relocation {
     /// Adds program_offset(StatementIdx) and subtracts the program offset of the casm instruction
     /// that is being relocated.
     RelativeStatementId(StatementIdx),
+    /// Adds the program offset of the runtime routine and subtracts the program offset of the
+    /// casm instruction that is being relocated.
+    RelativeRoutine(RuntimeRoutine),
 }
 
 impl Relocation {
@@ -18,41 +24,43 @@ impl Relocation {
         &self,
         instruction_offset: CodeOffset,
         statement_offsets: &[CodeOffset],
+        routine_offsets: &OrderedHashMap<RuntimeRoutine, CodeOffset>,
         instruction: &mut Instruction,
     ) {
-        match self {
-            Relocation::RelativeStatementId(statement_idx) => match instruction {
-                Instruction {
-                    body:
-                        InstructionBody::Call(CallInstruction {
-                            target: DerefOrImmediate::Immediate(value),
-                            relative: true,
-                        }),
-                    inc_ap: false,
-                    ..
-                }
-                | Instruction {
-                    body:
-                        InstructionBody::Jnz(JnzInstruction {
-                            jump_offset: DerefOrImmediate::Immediate(value),
-                            condition: _,
-                        }),
-                    ..
-                }
-                | Instruction {
-                    body:
-                        InstructionBody::Jump(JumpInstruction {
-                            target: DerefOrImmediate::Immediate(value),
-                            relative: true,
-                        }),
-                    inc_ap: false,
-                    ..
-                } => {
-                    *value +=
-                        statement_offsets[statement_idx.0] as i128 - instruction_offset as i128;
-                }
-                _ => panic!("Bad relocation."),
-            },
+        let target_offset = match self {
+            Relocation::RelativeStatementId(statement_idx) => statement_offsets[statement_idx.0],
+            Relocation::RelativeRoutine(routine) => routine_offsets[*routine],
+        };
+        match instruction {
+            Instruction {
+                body:
+                    InstructionBody::Call(CallInstruction {
+                        target: DerefOrImmediate::Immediate(value),
+                        relative: true,
+                    }),
+                inc_ap: false,
+                ..
+            }
+            | Instruction {
+                body:
+                    InstructionBody::Jnz(JnzInstruction {
+                        jump_offset: DerefOrImmediate::Immediate(value),
+                        condition: _,
+                    }),
+                ..
+            }
+            | Instruction {
+                body:
+                    InstructionBody::Jump(JumpInstruction {
+                        target: DerefOrImmediate::Immediate(value),
+                        relative: true,
+                    }),
+                inc_ap: false,
+                ..
+            } => {
+                *value += target_offset as i128 - instruction_offset as i128;
+            }
+            _ => panic!("Bad relocation."),
         }
     }
 }
@@ -73,6 +81,7 @@ pub struct RelocationEntry {
 pub fn relocate_instructions(
     relocations: &[RelocationEntry],
     statement_offsets: &[usize],
+    routine_offsets: &OrderedHashMap<RuntimeRoutine, CodeOffset>,
     instructions: &mut [Instruction],
 ) {
     let mut program_offset = 0;
@@ -83,7 +92,7 @@ pub fn relocate_instructions(
             Some(RelocationEntry { instruction_idx: relocation_idx, relocation })
                 if *relocation_idx == instruction_idx =>
             {
-                relocation.apply(program_offset, statement_offsets, instruction);
+                relocation.apply(program_offset, statement_offsets, routine_offsets, instruction);
                 relocation_entry = relocations_iter.next();
             }
             _ => (),
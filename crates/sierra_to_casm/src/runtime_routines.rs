@@ -0,0 +1,199 @@
+use casm::builder::{CasmBuildResult, CasmBuilder, Var};
+use casm::casm_build_extend;
+use casm::cost::InstructionsCost;
+use casm::instructions::Instruction;
+use casm::operand::{CellRef, Register, ResOperand};
+use utils::ordered_hash_map::OrderedHashMap;
+
+use crate::relocations::{CodeOffset, Relocation, RelocationEntry};
+
+#[cfg(test)]
+#[path = "runtime_routines_test.rs"]
+mod test;
+
+/// A code sequence shared by invocations. Every routine requested by an invocation (by a
+/// [Relocation::RelativeRoutine] relocation of a call instruction) is emitted once, at the end of
+/// the program.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RuntimeRoutine {
+    /// Constructs an array of a single felt - the panic data - from its argument.
+    PanicArray,
+}
+
+/// The calling contract of a routine.
+/// Routines only write to cells of their own frame, so apart from the call frame (the previous fp
+/// and the return address), calling them clobbers no memory of the caller.
+#[derive(Debug, Eq, PartialEq)]
+pub struct RoutineContract {
+    /// The number of arguments, expected at `[fp - 3 - (args_count - 1)]` to `[fp - 3]`.
+    pub args_count: usize,
+    /// The ap change of the routine, not including the call frame.
+    pub ap_change: usize,
+    /// The number of returned values, at the last cells before ap on return.
+    pub outputs_count: usize,
+}
+
+impl RuntimeRoutine {
+    /// All the routines.
+    const ALL: [RuntimeRoutine; 1] = [RuntimeRoutine::PanicArray];
+
+    /// Returns the label of the routine in a [CasmBuilder] calling it. The labels of the routines
+    /// are reserved, and are never the labels of the branches of an invocation.
+    fn label(&self) -> &'static str {
+        match self {
+            RuntimeRoutine::PanicArray => "RuntimeRoutine::PanicArray",
+        }
+    }
+
+    /// Returns the routine a call to `label` in a [CasmBuilder] calls, if any.
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|routine| routine.label() == label)
+    }
+
+    /// Returns the calling contract of the routine.
+    pub fn contract(&self) -> RoutineContract {
+        match self {
+            RuntimeRoutine::PanicArray => {
+                RoutineContract { args_count: 1, ap_change: 2, outputs_count: 2 }
+            }
+        }
+    }
+
+    /// Adds the body of the routine to `casm_builder`, with `args` as its arguments, and returns
+    /// the returned values.
+    fn add_body(&self, casm_builder: &mut CasmBuilder, args: &[Var]) -> Vec<Var> {
+        match self {
+            RuntimeRoutine::PanicArray => {
+                let [panic_data] = args else { panic!("Wrong number of arguments to a routine.") };
+                let panic_data = *panic_data;
+                let one = casm_builder.add_var(ResOperand::Immediate(1.into()));
+                casm_build_extend! {casm_builder,
+                    tempvar arr_start;
+                    tempvar arr_end;
+                    hint AllocSegment {} into {dst: arr_start};
+                    assert arr_end = arr_start + one;
+                    let arr_ptr = arr_start;
+                    assert *(arr_ptr++) = panic_data;
+                };
+                vec![arr_start, arr_end]
+            }
+        }
+    }
+
+    /// Returns the instructions of the routine.
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let mut casm_builder = CasmBuilder::default();
+        let args_count = self.contract().args_count as i16;
+        let args: Vec<Var> = (0..args_count)
+            .map(|i| {
+                casm_builder.add_var(ResOperand::Deref(CellRef {
+                    register: Register::FP,
+                    offset: -2 - args_count + i,
+                }))
+            })
+            .collect();
+        self.add_body(&mut casm_builder, &args);
+        casm_build_extend! {casm_builder, ret;};
+        let CasmBuildResult { instructions, awaiting_relocations, fallthrough_state, .. } =
+            casm_builder.build();
+        assert!(awaiting_relocations.is_empty(), "Routines are expected to be self contained.");
+        assert_eq!(
+            fallthrough_state.ap_change,
+            self.contract().ap_change,
+            "Routine does not match its contract."
+        );
+        instructions
+    }
+
+    /// Returns the cost of a call to the routine, given the cost of its call site. The code of the
+    /// routine is shared by all the calls, so only its steps are added.
+    pub fn call_cost(&self, call_site_cost: InstructionsCost) -> InstructionsCost {
+        let routine_cost = InstructionsCost::of_code(&self.instructions());
+        InstructionsCost { steps: call_site_cost.steps + routine_cost.steps, ..call_site_cost }
+    }
+}
+
+/// A function adding a lowering of a routine with its arguments to a [CasmBuilder] - either
+/// [add_routine_call] or [add_routine_inline].
+pub type AddRoutine = fn(&mut CasmBuilder, RuntimeRoutine, &[Var]) -> Vec<Var>;
+
+/// Pushes `args` as the arguments of a call to `routine`, and returns the pushed variables.
+fn push_routine_args(
+    casm_builder: &mut CasmBuilder,
+    routine: RuntimeRoutine,
+    args: &[Var],
+) -> Vec<Var> {
+    assert_eq!(
+        args.len(),
+        routine.contract().args_count,
+        "Wrong number of arguments to a routine."
+    );
+    args.iter()
+        .map(|arg| {
+            let arg = *arg;
+            casm_build_extend! {casm_builder,
+                tempvar pushed_arg;
+                assert pushed_arg = arg;
+            };
+            pushed_arg
+        })
+        .collect()
+}
+
+/// Adds a call to `routine` to `casm_builder`, with `args` as its arguments. Returns variables
+/// pointing to the returned values.
+/// The call awaits relocation, and becomes a [Relocation::RelativeRoutine] relocation when the
+/// invocation is built.
+pub fn add_routine_call(
+    casm_builder: &mut CasmBuilder,
+    routine: RuntimeRoutine,
+    args: &[Var],
+) -> Vec<Var> {
+    push_routine_args(casm_builder, routine, args);
+    let contract = routine.contract();
+    casm_builder.call(routine.label().into(), contract.ap_change, contract.outputs_count)
+}
+
+/// Adds the body of `routine` inline to `casm_builder`, with `args` as its arguments, as an
+/// alternative to [add_routine_call]. Returns variables pointing to the returned values.
+/// The arguments are pushed and ap is advanced over the cells of a call frame, so both
+/// alternatives have the same ap change and returned values.
+pub fn add_routine_inline(
+    casm_builder: &mut CasmBuilder,
+    routine: RuntimeRoutine,
+    args: &[Var],
+) -> Vec<Var> {
+    let pushed_args = push_routine_args(casm_builder, routine, args);
+    // Skipping the cells of the call frame, so the returned values are the last cells before ap.
+    casm_builder.alloc_var();
+    casm_builder.alloc_var();
+    casm_build_extend! {casm_builder, ap += 2;};
+    routine.add_body(casm_builder, &pushed_args)
+}
+
+/// Appends the routines requested by `relocations` to `instructions`, each one once, in the order
+/// of their first request.
+/// `program_offset` is the code offset at the end of `instructions`.
+/// Returns the code offsets of the emitted routines.
+pub fn append_requested_routines(
+    relocations: &[RelocationEntry],
+    instructions: &mut Vec<Instruction>,
+    mut program_offset: CodeOffset,
+) -> OrderedHashMap<RuntimeRoutine, CodeOffset> {
+    let mut routine_offsets = OrderedHashMap::default();
+    for entry in relocations {
+        let routine = match entry.relocation {
+            Relocation::RelativeRoutine(routine) => routine,
+            Relocation::RelativeStatementId(_) => continue,
+        };
+        if routine_offsets.contains_key(&routine) {
+            continue;
+        }
+        routine_offsets.insert(routine, program_offset);
+        for instruction in routine.instructions() {
+            program_offset += instruction.body.op_size();
+            instructions.push(instruction);
+        }
+    }
+    routine_offsets
+}
@@ -0,0 +1,107 @@
+use casm::builder::{CasmBuildResult, CasmBuilder};
+use casm::casm;
+use casm::instructions::Instruction;
+use casm::operand::{ap_cell_ref, ResOperand};
+use casm::run::run_function;
+use indoc::indoc;
+use itertools::join;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use pretty_assertions::assert_eq;
+
+use super::{add_routine_call, add_routine_inline, append_requested_routines, RuntimeRoutine};
+use crate::relocations::{relocate_instructions, Relocation, RelocationEntry};
+
+/// Returns the instructions of a call to the panic array routine with `panic_data` as its argument,
+/// and the index of the call instruction.
+fn panic_array_call_site(panic_data: i32) -> (Vec<Instruction>, usize) {
+    let mut casm_builder = CasmBuilder::default();
+    let panic_data = casm_builder.add_var(ResOperand::Immediate(panic_data.into()));
+    add_routine_call(&mut casm_builder, RuntimeRoutine::PanicArray, &[panic_data]);
+    let CasmBuildResult { instructions, awaiting_relocations, .. } = casm_builder.build();
+    let [(call_idx, label)] = &awaiting_relocations[..] else { panic!("Expected a single call.") };
+    assert_eq!(RuntimeRoutine::from_label(label), Some(RuntimeRoutine::PanicArray));
+    (instructions, *call_idx)
+}
+
+#[test]
+fn test_shared_routine() {
+    let mut instructions = vec![];
+    let mut relocations = vec![];
+    for panic_data in [7, 8] {
+        let (call_site_instructions, call_idx) = panic_array_call_site(panic_data);
+        relocations.push(RelocationEntry {
+            instruction_idx: instructions.len() + call_idx,
+            relocation: Relocation::RelativeRoutine(RuntimeRoutine::PanicArray),
+        });
+        instructions.extend(call_site_instructions);
+    }
+    instructions.extend(casm! { ret; }.instructions);
+    let program_offset: usize =
+        instructions.iter().map(|instruction| instruction.body.op_size()).sum();
+
+    let routine_offsets =
+        append_requested_routines(&relocations, &mut instructions, program_offset);
+    assert_eq!(routine_offsets.len(), 1);
+    assert_eq!(routine_offsets[RuntimeRoutine::PanicArray], program_offset);
+
+    relocate_instructions(&relocations, &[], &routine_offsets, &mut instructions);
+    assert_eq!(
+        join(instructions.iter().map(|instruction| format!("{instruction};\n")), ""),
+        indoc! {"
+            [ap + 0] = 7, ap++;
+            call rel 7;
+            [ap + 0] = 8, ap++;
+            call rel 3;
+            ret;
+            %{ memory[ap + 0] = segments.add() %}
+            [ap + 1] = [ap + 0] + 1, ap++;
+            [fp + -3] = [[ap + -1] + 0], ap++;
+            ret;
+        "}
+    );
+
+    let (memory, ap) = run_function(instructions).expect("Running the program failed.");
+    let cell = |address: usize| memory[address].clone().expect("Missing value.");
+    // Each call leaves the argument, the call frame and the two returned values.
+    for (panic_data, outputs_offset) in [(7, 7), (8, 2)] {
+        let arr_start = cell(ap - outputs_offset);
+        let arr_end = cell(ap - outputs_offset + 1);
+        assert_eq!(arr_end, &arr_start + 1);
+        assert_eq!(cell(arr_start.to_usize().unwrap()), BigInt::from(panic_data));
+    }
+}
+
+#[test]
+fn test_inline_routine() {
+    let mut casm_builder = CasmBuilder::default();
+    let panic_data = casm_builder.add_var(ResOperand::Immediate(7.into()));
+    let outputs = add_routine_inline(&mut casm_builder, RuntimeRoutine::PanicArray, &[panic_data]);
+    let CasmBuildResult { mut instructions, awaiting_relocations, fallthrough_state, .. } =
+        casm_builder.build();
+    assert!(awaiting_relocations.is_empty());
+    // The same ap change and returned values as a call.
+    assert_eq!(fallthrough_state.ap_change, 5);
+    assert_eq!(
+        outputs.iter().map(|output| fallthrough_state.get_adjusted(*output)).collect::<Vec<_>>(),
+        [ResOperand::Deref(ap_cell_ref(-2)), ResOperand::Deref(ap_cell_ref(-1))]
+    );
+    instructions.extend(casm! { ret; }.instructions);
+    assert_eq!(
+        join(instructions.iter().map(|instruction| format!("{instruction};\n")), ""),
+        indoc! {"
+            [ap + 0] = 7, ap++;
+            ap += 2;
+            %{ memory[ap + 0] = segments.add() %}
+            [ap + 1] = [ap + 0] + 1, ap++;
+            [ap + -4] = [[ap + -1] + 0], ap++;
+            ret;
+        "}
+    );
+
+    let (memory, ap) = run_function(instructions).expect("Running the program failed.");
+    let cell = |address: usize| memory[address].clone().expect("Missing value.");
+    let arr_start = cell(ap - 2);
+    assert_eq!(cell(ap - 1), &arr_start + 1);
+    assert_eq!(cell(arr_start.to_usize().unwrap()), BigInt::from(7));
+}
@@ -0,0 +1,490 @@
+//! The memory layout of the StarkNet system calls.
+//!
+//! A system call is written into the system segment as the selector, followed by the request
+//! record, followed by the response record written by the syscall handler.
+
+use num_bigint::BigInt;
+use sierra::extensions::starknet::interoperability::{
+    CallContractLibFuncWrapped, CallSyscallGenLibFunc, LibraryCallLibFuncWrapped,
+};
+pub use sierra::extensions::starknet::selector_value;
+use thiserror::Error;
+
+#[cfg(test)]
+#[path = "syscall_records_test.rs"]
+mod test;
+
+pub const STORAGE_READ_SELECTOR: &str = "storage_read";
+pub const STORAGE_WRITE_SELECTOR: &str = "storage_write";
+pub const CALL_CONTRACT_SELECTOR: &str = CallContractLibFuncWrapped::SELECTOR;
+pub const DEPLOY_SELECTOR: &str = "deploy";
+pub const LIBRARY_CALL_SELECTOR: &str = LibraryCallLibFuncWrapped::SELECTOR;
+pub const SEND_MESSAGE_TO_L1_SELECTOR: &str = "send_message_to_l1";
+pub const EMIT_EVENT_SELECTOR: &str = "emit_event";
+pub const GET_CALLER_ADDRESS_SELECTOR: &str = "get_caller_address";
+pub const GET_CONTRACT_ADDRESS_SELECTOR: &str = "get_contract_address";
+pub const GET_SEQUENCER_ADDRESS_SELECTOR: &str = "get_sequencer_address";
+pub const GET_BLOCK_NUMBER_SELECTOR: &str = "get_block_number";
+pub const GET_BLOCK_TIMESTAMP_SELECTOR: &str = "get_block_timestamp";
+pub const GET_TX_INFO_SELECTOR: &str = "get_tx_info";
+pub const REPLACE_CLASS_SELECTOR: &str = "replace_class";
+pub const KECCAK_SELECTOR: &str = "keccak";
+pub const SECP256K1_NEW_SELECTOR: &str = "secp256k1_new";
+pub const SECP256K1_ADD_SELECTOR: &str = "secp256k1_add";
+pub const SECP256K1_MUL_SELECTOR: &str = "secp256k1_mul";
+pub const SECP256K1_GET_XY_SELECTOR: &str = "secp256k1_get_xy";
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum SyscallRecordError {
+    #[error("Memory cell {address} is not initialized.")]
+    UninitializedCell { address: usize },
+    #[error("Unknown syscall selector {selector}.")]
+    UnknownSelector { selector: BigInt },
+}
+
+/// A memory that syscall records can be read from and written to.
+pub trait SyscallMemory {
+    /// Returns the value at `address`, if initialized.
+    fn get(&self, address: usize) -> Option<BigInt>;
+    /// Sets the value at `address`.
+    fn set(&mut self, address: usize, value: BigInt);
+}
+impl SyscallMemory for Vec<Option<BigInt>> {
+    fn get(&self, address: usize) -> Option<BigInt> {
+        self.as_slice().get(address).cloned().flatten()
+    }
+
+    fn set(&mut self, address: usize, value: BigInt) {
+        if self.len() <= address {
+            self.resize(address + 1, None);
+        }
+        self[address] = Some(value);
+    }
+}
+
+/// A request or a response of a syscall, occupying `SIZE` consecutive cells.
+pub trait SyscallRecord: Sized {
+    /// The number of cells of the record.
+    const SIZE: usize;
+
+    /// Reads the record starting at `ptr`.
+    fn read_from_memory(
+        memory: &impl SyscallMemory,
+        ptr: usize,
+    ) -> Result<Self, SyscallRecordError>;
+
+    /// Writes the record starting at `ptr`.
+    fn write_to_memory(&self, memory: &mut impl SyscallMemory, ptr: usize);
+}
+
+/// Returns the total number of cells of a syscall with the given request and response records,
+/// including the selector.
+pub fn syscall_size<Request: SyscallRecord, Response: SyscallRecord>() -> usize {
+    1 + Request::SIZE + Response::SIZE
+}
+
+/// Defines a record struct, where every field is a single cell, in declaration order.
+macro_rules! define_syscall_record {
+    ($(#[$attr:meta])* $name:ident { $($(#[$field_attr:meta])* $field:ident),* $(,)? }) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, Eq, PartialEq)]
+        pub struct $name {
+            $($(#[$field_attr])* pub $field: BigInt,)*
+        }
+        impl SyscallRecord for $name {
+            const SIZE: usize = [$(stringify!($field)),*].len();
+
+            fn read_from_memory(
+                memory: &impl SyscallMemory,
+                ptr: usize,
+            ) -> Result<Self, SyscallRecordError> {
+                let mut cells = (ptr..).map(|address| {
+                    memory.get(address).ok_or(SyscallRecordError::UninitializedCell { address })
+                });
+                Ok(Self { $($field: cells.next().unwrap()?,)* })
+            }
+
+            fn write_to_memory(&self, memory: &mut impl SyscallMemory, ptr: usize) {
+                for (address, value) in (ptr..).zip([$(&self.$field),*]) {
+                    memory.set(address, value.clone());
+                }
+            }
+        }
+    };
+}
+
+define_syscall_record!(
+    /// The request of the `storage_read` syscall.
+    StorageReadRequest {
+        gas_counter,
+        /// The domain of the address - only the domain 0 is currently in use.
+        address_domain,
+        address,
+    }
+);
+define_syscall_record!(
+    /// The response of the `storage_read` syscall.
+    StorageReadResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        value,
+    }
+);
+define_syscall_record!(
+    /// The request of the `storage_write` syscall.
+    StorageWriteRequest {
+        gas_counter,
+        /// The domain of the address - only the domain 0 is currently in use.
+        address_domain,
+        address,
+        value,
+    }
+);
+define_syscall_record!(
+    /// The response of the `storage_write` syscall.
+    StorageWriteResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        /// Reserved for future use.
+        reserved,
+    }
+);
+define_syscall_record!(
+    /// The request of the `call_contract` syscall.
+    CallContractRequest { gas_counter, contract_address, calldata_start, calldata_end }
+);
+define_syscall_record!(
+    /// The response of the `call_contract` syscall.
+    CallContractResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        retdata_start,
+        retdata_end,
+    }
+);
+define_syscall_record!(
+    /// The request of the `library_call` syscall.
+    LibraryCallRequest { gas_counter, class_hash, calldata_start, calldata_end }
+);
+define_syscall_record!(
+    /// The response of the `library_call` syscall.
+    LibraryCallResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        retdata_start,
+        retdata_end,
+    }
+);
+define_syscall_record!(
+    /// The request of the `deploy` syscall.
+    DeployRequest { gas_counter, class_hash, contract_address_salt, calldata_start, calldata_end }
+);
+define_syscall_record!(
+    /// The response of the `deploy` syscall.
+    DeployResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        contract_address,
+        /// The return data of the constructor.
+        retdata_start,
+        retdata_end,
+    }
+);
+define_syscall_record!(
+    /// The request of the `send_message_to_l1` syscall.
+    SendMessageToL1Request { gas_counter, to_address, payload_start, payload_end }
+);
+define_syscall_record!(
+    /// The response of the `send_message_to_l1` syscall.
+    SendMessageToL1Response {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+    }
+);
+define_syscall_record!(
+    /// The request of the `emit_event` syscall.
+    EmitEventRequest { gas_counter, keys_start, keys_end, data_start, data_end }
+);
+define_syscall_record!(
+    /// The response of the `emit_event` syscall.
+    EmitEventResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+    }
+);
+define_syscall_record!(
+    /// The request of the `get_caller_address` syscall.
+    GetCallerAddressRequest { gas_counter }
+);
+define_syscall_record!(
+    /// The response of the `get_caller_address` syscall.
+    GetCallerAddressResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        caller_address,
+    }
+);
+define_syscall_record!(
+    /// The request of the `get_contract_address` syscall.
+    GetContractAddressRequest { gas_counter }
+);
+define_syscall_record!(
+    /// The response of the `get_contract_address` syscall.
+    GetContractAddressResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        contract_address,
+    }
+);
+define_syscall_record!(
+    /// The request of the `get_sequencer_address` syscall.
+    GetSequencerAddressRequest { gas_counter }
+);
+define_syscall_record!(
+    /// The response of the `get_sequencer_address` syscall.
+    GetSequencerAddressResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        sequencer_address,
+    }
+);
+define_syscall_record!(
+    /// The request of the `get_block_number` syscall.
+    GetBlockNumberRequest { gas_counter }
+);
+define_syscall_record!(
+    /// The response of the `get_block_number` syscall.
+    GetBlockNumberResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        block_number,
+    }
+);
+define_syscall_record!(
+    /// The request of the `get_block_timestamp` syscall.
+    GetBlockTimestampRequest { gas_counter }
+);
+define_syscall_record!(
+    /// The response of the `get_block_timestamp` syscall.
+    GetBlockTimestampResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        block_timestamp,
+    }
+);
+define_syscall_record!(
+    /// The request of the `get_tx_info` syscall.
+    GetTxInfoRequest { gas_counter }
+);
+define_syscall_record!(
+    /// The response of the `get_tx_info` syscall.
+    GetTxInfoResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        /// A pointer to the transaction info.
+        tx_info,
+    }
+);
+define_syscall_record!(
+    /// The request of the `replace_class` syscall.
+    ReplaceClassRequest { gas_counter, class_hash }
+);
+define_syscall_record!(
+    /// The response of the `replace_class` syscall.
+    ReplaceClassResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+    }
+);
+define_syscall_record!(
+    /// The request of the `keccak` syscall.
+    KeccakRequest {
+        gas_counter,
+        /// The bounds of the input array, each of its cells holding a 64-bit word.
+        input_start,
+        input_end,
+    }
+);
+define_syscall_record!(
+    /// The response of the `keccak` syscall.
+    KeccakResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        /// The low 128 bits of the hash.
+        result_low,
+        /// The high 128 bits of the hash.
+        result_high,
+    }
+);
+define_syscall_record!(
+    /// The request of the `secp256k1_new` syscall.
+    Secp256k1NewRequest { gas_counter, x_low, x_high, y_low, y_high }
+);
+define_syscall_record!(
+    /// The response of the `secp256k1_new` syscall.
+    Secp256k1NewResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        /// Nonzero if the coordinates are of a point on the curve.
+        is_on_curve,
+        /// A pointer to the point, meaningful only if it is on the curve.
+        point,
+    }
+);
+define_syscall_record!(
+    /// The request of the `secp256k1_add` syscall.
+    Secp256k1AddRequest { gas_counter, p0, p1 }
+);
+define_syscall_record!(
+    /// The response of the `secp256k1_add` syscall.
+    Secp256k1AddResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        point,
+    }
+);
+define_syscall_record!(
+    /// The request of the `secp256k1_mul` syscall.
+    Secp256k1MulRequest { gas_counter, point, scalar_low, scalar_high }
+);
+define_syscall_record!(
+    /// The response of the `secp256k1_mul` syscall.
+    Secp256k1MulResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        point,
+    }
+);
+define_syscall_record!(
+    /// The request of the `secp256k1_get_xy` syscall.
+    Secp256k1GetXyRequest { gas_counter, point }
+);
+define_syscall_record!(
+    /// The response of the `secp256k1_get_xy` syscall.
+    Secp256k1GetXyResponse {
+        gas_counter,
+        /// 0 on success, nonzero on failure.
+        revert_reason,
+        x_low,
+        x_high,
+        y_low,
+        y_high,
+    }
+);
+
+/// A handler of the StarkNet syscalls, operating on the decoded records.
+pub trait SyscallHandler {
+    fn storage_read(&mut self, request: StorageReadRequest) -> StorageReadResponse;
+    fn storage_write(&mut self, request: StorageWriteRequest) -> StorageWriteResponse;
+    fn call_contract(&mut self, request: CallContractRequest) -> CallContractResponse;
+    fn library_call(&mut self, request: LibraryCallRequest) -> LibraryCallResponse;
+    fn deploy(&mut self, request: DeployRequest) -> DeployResponse;
+    fn send_message_to_l1(&mut self, request: SendMessageToL1Request) -> SendMessageToL1Response;
+    fn emit_event(&mut self, request: EmitEventRequest) -> EmitEventResponse;
+    fn get_caller_address(&mut self, request: GetCallerAddressRequest) -> GetCallerAddressResponse;
+    fn get_contract_address(
+        &mut self,
+        request: GetContractAddressRequest,
+    ) -> GetContractAddressResponse;
+    fn get_sequencer_address(
+        &mut self,
+        request: GetSequencerAddressRequest,
+    ) -> GetSequencerAddressResponse;
+    fn get_block_number(&mut self, request: GetBlockNumberRequest) -> GetBlockNumberResponse;
+    fn get_block_timestamp(
+        &mut self,
+        request: GetBlockTimestampRequest,
+    ) -> GetBlockTimestampResponse;
+    fn get_tx_info(&mut self, request: GetTxInfoRequest) -> GetTxInfoResponse;
+    fn replace_class(&mut self, request: ReplaceClassRequest) -> ReplaceClassResponse;
+    fn keccak(&mut self, request: KeccakRequest) -> KeccakResponse;
+    fn secp256k1_new(&mut self, request: Secp256k1NewRequest) -> Secp256k1NewResponse;
+    fn secp256k1_add(&mut self, request: Secp256k1AddRequest) -> Secp256k1AddResponse;
+    fn secp256k1_mul(&mut self, request: Secp256k1MulRequest) -> Secp256k1MulResponse;
+    fn secp256k1_get_xy(&mut self, request: Secp256k1GetXyRequest) -> Secp256k1GetXyResponse;
+}
+
+/// Executes the syscall written at `system_ptr` using `handler`, and writes its response.
+/// Returns the pointer to the cell following the syscall.
+pub fn execute_syscall(
+    handler: &mut impl SyscallHandler,
+    memory: &mut impl SyscallMemory,
+    system_ptr: usize,
+) -> Result<usize, SyscallRecordError> {
+    let selector = memory
+        .get(system_ptr)
+        .ok_or(SyscallRecordError::UninitializedCell { address: system_ptr })?;
+    let request_ptr = system_ptr + 1;
+    if selector == selector_value(STORAGE_READ_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.storage_read(request))
+    } else if selector == selector_value(STORAGE_WRITE_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.storage_write(request))
+    } else if selector == selector_value(CALL_CONTRACT_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.call_contract(request))
+    } else if selector == selector_value(LIBRARY_CALL_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.library_call(request))
+    } else if selector == selector_value(DEPLOY_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.deploy(request))
+    } else if selector == selector_value(SEND_MESSAGE_TO_L1_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.send_message_to_l1(request))
+    } else if selector == selector_value(EMIT_EVENT_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.emit_event(request))
+    } else if selector == selector_value(GET_CALLER_ADDRESS_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.get_caller_address(request))
+    } else if selector == selector_value(GET_CONTRACT_ADDRESS_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.get_contract_address(request))
+    } else if selector == selector_value(GET_SEQUENCER_ADDRESS_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.get_sequencer_address(request))
+    } else if selector == selector_value(GET_BLOCK_NUMBER_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.get_block_number(request))
+    } else if selector == selector_value(GET_BLOCK_TIMESTAMP_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.get_block_timestamp(request))
+    } else if selector == selector_value(GET_TX_INFO_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.get_tx_info(request))
+    } else if selector == selector_value(REPLACE_CLASS_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.replace_class(request))
+    } else if selector == selector_value(KECCAK_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.keccak(request))
+    } else if selector == selector_value(SECP256K1_NEW_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.secp256k1_new(request))
+    } else if selector == selector_value(SECP256K1_ADD_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.secp256k1_add(request))
+    } else if selector == selector_value(SECP256K1_MUL_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.secp256k1_mul(request))
+    } else if selector == selector_value(SECP256K1_GET_XY_SELECTOR) {
+        handle_syscall(memory, request_ptr, |request| handler.secp256k1_get_xy(request))
+    } else {
+        Err(SyscallRecordError::UnknownSelector { selector })
+    }
+}
+
+/// Reads the request at `request_ptr`, and writes the response of `handle` right after it.
+/// Returns the pointer to the cell following the response.
+fn handle_syscall<Request: SyscallRecord, Response: SyscallRecord>(
+    memory: &mut impl SyscallMemory,
+    request_ptr: usize,
+    handle: impl FnOnce(Request) -> Response,
+) -> Result<usize, SyscallRecordError> {
+    let request = Request::read_from_memory(&*memory, request_ptr)?;
+    let response_ptr = request_ptr + Request::SIZE;
+    handle(request).write_to_memory(memory, response_ptr);
+    Ok(response_ptr + Response::SIZE)
+}
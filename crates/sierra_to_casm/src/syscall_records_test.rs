@@ -0,0 +1,268 @@
+use num_bigint::BigInt;
+use pretty_assertions::assert_eq;
+use test_case::test_case;
+
+use crate::syscall_records::{
+    execute_syscall, selector_value, CallContractRequest, CallContractResponse, DeployRequest,
+    DeployResponse, EmitEventRequest, EmitEventResponse, GetBlockNumberRequest,
+    GetBlockNumberResponse, GetBlockTimestampRequest, GetBlockTimestampResponse,
+    GetCallerAddressRequest, GetCallerAddressResponse, GetContractAddressRequest,
+    GetContractAddressResponse, GetSequencerAddressRequest, GetSequencerAddressResponse,
+    GetTxInfoRequest, GetTxInfoResponse, KeccakRequest, KeccakResponse, LibraryCallRequest,
+    LibraryCallResponse, ReplaceClassRequest, ReplaceClassResponse, Secp256k1AddRequest,
+    Secp256k1AddResponse, Secp256k1GetXyRequest, Secp256k1GetXyResponse, Secp256k1MulRequest,
+    Secp256k1MulResponse, Secp256k1NewRequest, Secp256k1NewResponse, SendMessageToL1Request,
+    SendMessageToL1Response, StorageReadRequest, StorageReadResponse, StorageWriteRequest,
+    StorageWriteResponse, SyscallHandler, SyscallMemory, SyscallRecord, SyscallRecordError,
+    CALL_CONTRACT_SELECTOR, STORAGE_WRITE_SELECTOR,
+};
+
+/// Writes `record` at `ptr` and reads it back.
+fn round_trip<T: SyscallRecord>(record: &T, ptr: usize) -> (Vec<Option<BigInt>>, T) {
+    let mut memory: Vec<Option<BigInt>> = vec![];
+    record.write_to_memory(&mut memory, ptr);
+    let read = T::read_from_memory(&memory, ptr).unwrap();
+    (memory, read)
+}
+
+#[test_case(3; "at_3")]
+#[test_case(0; "at_0")]
+fn test_round_trip(ptr: usize) {
+    let record =
+        StorageReadRequest { gas_counter: 6.into(), address_domain: 0.into(), address: 7.into() };
+    assert_eq!(round_trip(&record, ptr).1, record);
+    let record =
+        StorageReadResponse { gas_counter: 6.into(), revert_reason: 0.into(), value: 8.into() };
+    assert_eq!(round_trip(&record, ptr).1, record);
+    let record = StorageWriteRequest {
+        gas_counter: 1.into(),
+        address_domain: 0.into(),
+        address: 2.into(),
+        value: 3.into(),
+    };
+    assert_eq!(round_trip(&record, ptr).1, record);
+    let record =
+        StorageWriteResponse { gas_counter: 1.into(), revert_reason: 2.into(), reserved: 3.into() };
+    assert_eq!(round_trip(&record, ptr).1, record);
+    let record = CallContractRequest {
+        gas_counter: 1.into(),
+        contract_address: 2.into(),
+        calldata_start: 3.into(),
+        calldata_end: 4.into(),
+    };
+    let (memory, read) = round_trip(&record, ptr);
+    assert_eq!(read, record);
+    assert_eq!(memory[ptr..], [1, 2, 3, 4].map(|v| Some(BigInt::from(v))));
+    let record = CallContractResponse {
+        gas_counter: 1.into(),
+        revert_reason: 2.into(),
+        retdata_start: 3.into(),
+        retdata_end: 4.into(),
+    };
+    assert_eq!(round_trip(&record, ptr).1, record);
+    let record = EmitEventRequest {
+        gas_counter: 1.into(),
+        keys_start: 2.into(),
+        keys_end: 3.into(),
+        data_start: 4.into(),
+        data_end: 5.into(),
+    };
+    assert_eq!(round_trip(&record, ptr).1, record);
+    let record = EmitEventResponse { gas_counter: 1.into(), revert_reason: 2.into() };
+    assert_eq!(round_trip(&record, ptr).1, record);
+    let record =
+        KeccakRequest { gas_counter: 1.into(), input_start: 2.into(), input_end: 3.into() };
+    assert_eq!(round_trip(&record, ptr).1, record);
+    let record = KeccakResponse {
+        gas_counter: 1.into(),
+        revert_reason: 0.into(),
+        result_low: 2.into(),
+        result_high: 3.into(),
+    };
+    let (memory, read) = round_trip(&record, ptr);
+    assert_eq!(read, record);
+    assert_eq!(memory[ptr..], [1, 0, 2, 3].map(|v| Some(BigInt::from(v))));
+}
+
+#[test]
+fn test_read_uninitialized() {
+    let memory = vec![Some(BigInt::from(1)), None, Some(BigInt::from(3))];
+    assert_eq!(
+        StorageWriteRequest::read_from_memory(&memory, 0),
+        Err(SyscallRecordError::UninitializedCell { address: 1 })
+    );
+}
+
+/// A handler recording the requests it received.
+#[derive(Default)]
+struct RecordingHandler {
+    storage_writes: Vec<StorageWriteRequest>,
+    contract_calls: Vec<CallContractRequest>,
+}
+impl SyscallHandler for RecordingHandler {
+    fn storage_read(&mut self, _request: StorageReadRequest) -> StorageReadResponse {
+        unreachable!("No storage reads are expected.")
+    }
+
+    fn storage_write(&mut self, request: StorageWriteRequest) -> StorageWriteResponse {
+        let gas_counter = request.gas_counter.clone() - 100;
+        self.storage_writes.push(request);
+        StorageWriteResponse { gas_counter, revert_reason: 0.into(), reserved: 0.into() }
+    }
+
+    fn call_contract(&mut self, request: CallContractRequest) -> CallContractResponse {
+        let gas_counter = request.gas_counter.clone() - 200;
+        self.contract_calls.push(request);
+        CallContractResponse {
+            gas_counter,
+            revert_reason: 0.into(),
+            retdata_start: 50.into(),
+            retdata_end: 52.into(),
+        }
+    }
+
+    fn library_call(&mut self, _request: LibraryCallRequest) -> LibraryCallResponse {
+        unreachable!("No library calls are expected.")
+    }
+
+    fn deploy(&mut self, _request: DeployRequest) -> DeployResponse {
+        unreachable!("No deployments are expected.")
+    }
+
+    fn send_message_to_l1(&mut self, _request: SendMessageToL1Request) -> SendMessageToL1Response {
+        unreachable!("No messages are expected.")
+    }
+
+    fn emit_event(&mut self, _request: EmitEventRequest) -> EmitEventResponse {
+        unreachable!("No events are expected.")
+    }
+
+    fn get_caller_address(
+        &mut self,
+        _request: GetCallerAddressRequest,
+    ) -> GetCallerAddressResponse {
+        unreachable!("No caller address requests are expected.")
+    }
+
+    fn get_contract_address(
+        &mut self,
+        _request: GetContractAddressRequest,
+    ) -> GetContractAddressResponse {
+        unreachable!("No contract address requests are expected.")
+    }
+
+    fn get_sequencer_address(
+        &mut self,
+        _request: GetSequencerAddressRequest,
+    ) -> GetSequencerAddressResponse {
+        unreachable!("No sequencer address requests are expected.")
+    }
+
+    fn get_block_number(&mut self, _request: GetBlockNumberRequest) -> GetBlockNumberResponse {
+        unreachable!("No block number requests are expected.")
+    }
+
+    fn get_block_timestamp(
+        &mut self,
+        _request: GetBlockTimestampRequest,
+    ) -> GetBlockTimestampResponse {
+        unreachable!("No block timestamp requests are expected.")
+    }
+
+    fn get_tx_info(&mut self, _request: GetTxInfoRequest) -> GetTxInfoResponse {
+        unreachable!("No transaction info requests are expected.")
+    }
+
+    fn replace_class(&mut self, _request: ReplaceClassRequest) -> ReplaceClassResponse {
+        unreachable!("No class replacements are expected.")
+    }
+
+    fn keccak(&mut self, _request: KeccakRequest) -> KeccakResponse {
+        unreachable!("No keccak requests are expected.")
+    }
+
+    fn secp256k1_new(&mut self, _request: Secp256k1NewRequest) -> Secp256k1NewResponse {
+        unreachable!("No secp256k1 requests are expected.")
+    }
+
+    fn secp256k1_add(&mut self, _request: Secp256k1AddRequest) -> Secp256k1AddResponse {
+        unreachable!("No secp256k1 requests are expected.")
+    }
+
+    fn secp256k1_mul(&mut self, _request: Secp256k1MulRequest) -> Secp256k1MulResponse {
+        unreachable!("No secp256k1 requests are expected.")
+    }
+
+    fn secp256k1_get_xy(&mut self, _request: Secp256k1GetXyRequest) -> Secp256k1GetXyResponse {
+        unreachable!("No secp256k1 requests are expected.")
+    }
+}
+
+#[test]
+fn test_execute_syscall() {
+    let mut memory: Vec<Option<BigInt>> = vec![];
+    memory.set(0, selector_value(STORAGE_WRITE_SELECTOR));
+    StorageWriteRequest {
+        gas_counter: 1000.into(),
+        address_domain: 0.into(),
+        address: 2.into(),
+        value: 3.into(),
+    }
+    .write_to_memory(&mut memory, 1);
+    memory.set(8, selector_value(CALL_CONTRACT_SELECTOR));
+    CallContractRequest {
+        gas_counter: 900.into(),
+        contract_address: 5.into(),
+        calldata_start: 30.into(),
+        calldata_end: 32.into(),
+    }
+    .write_to_memory(&mut memory, 9);
+
+    let mut handler = RecordingHandler::default();
+    assert_eq!(execute_syscall(&mut handler, &mut memory, 0), Ok(8));
+    assert_eq!(execute_syscall(&mut handler, &mut memory, 8), Ok(17));
+    assert_eq!(
+        handler.storage_writes,
+        vec![StorageWriteRequest {
+            gas_counter: 1000.into(),
+            address_domain: 0.into(),
+            address: 2.into(),
+            value: 3.into(),
+        }]
+    );
+    assert_eq!(
+        handler.contract_calls,
+        vec![CallContractRequest {
+            gas_counter: 900.into(),
+            contract_address: 5.into(),
+            calldata_start: 30.into(),
+            calldata_end: 32.into(),
+        }]
+    );
+    assert_eq!(
+        StorageWriteResponse::read_from_memory(&memory, 5),
+        Ok(StorageWriteResponse {
+            gas_counter: 900.into(),
+            revert_reason: 0.into(),
+            reserved: 0.into()
+        })
+    );
+    assert_eq!(
+        CallContractResponse::read_from_memory(&memory, 13),
+        Ok(CallContractResponse {
+            gas_counter: 700.into(),
+            revert_reason: 0.into(),
+            retdata_start: 50.into(),
+            retdata_end: 52.into(),
+        })
+    );
+}
+
+#[test]
+fn test_unknown_selector() {
+    let mut memory = vec![Some(BigInt::from(1234))];
+    assert_eq!(
+        execute_syscall(&mut RecordingHandler::default(), &mut memory, 0),
+        Err(SyscallRecordError::UnknownSelector { selector: 1234.into() })
+    );
+}
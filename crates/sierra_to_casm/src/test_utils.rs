@@ -2,27 +2,47 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
+use casm::cost::CostWeights;
 use itertools::Itertools;
 use sierra::program::Program;
 use sierra_ap_change::ap_change_info::ApChangeInfo;
-use sierra_ap_change::calc_ap_changes;
-use sierra_gas::calc_gas_info;
+use sierra_ap_change::{calc_ap_changes_with_stack_layout, StackLayout};
+use sierra_gas::calc_gas_info_with_stack_layout;
 use sierra_gas::gas_info::GasInfo;
 
 use crate::metadata::Metadata;
 
 /// Builds the metadata for a Sierra program.
 pub fn build_metadata(program: &Program, calculate_gas_info: bool) -> Metadata {
+    build_metadata_with_stack_layout(program, calculate_gas_info, StackLayout::default())
+}
+
+/// Builds the metadata for a Sierra program, with values stored on the stack according to
+/// `stack_layout`.
+pub fn build_metadata_with_stack_layout(
+    program: &Program,
+    calculate_gas_info: bool,
+    stack_layout: StackLayout,
+) -> Metadata {
     Metadata {
-        ap_change_info: calc_ap_changes(program).unwrap_or(ApChangeInfo {
-            function_ap_change: HashMap::default(),
-            variable_values: HashMap::default(),
-        }),
+        ap_change_info: calc_ap_changes_with_stack_layout(program, stack_layout).unwrap_or(
+            ApChangeInfo {
+                function_ap_change: HashMap::default(),
+                variable_values: HashMap::default(),
+                stack_layout,
+            },
+        ),
         gas_info: if calculate_gas_info {
-            calc_gas_info(program).expect("Failed calculating gas variables.")
+            calc_gas_info_with_stack_layout(program, stack_layout)
+                .expect("Failed calculating gas variables.")
         } else {
-            GasInfo { variable_values: HashMap::new(), function_costs: HashMap::new() }
+            GasInfo {
+                variable_values: HashMap::new(),
+                function_costs: HashMap::new(),
+                store_temp_costs: HashMap::new(),
+            }
         },
+        cost_weights: CostWeights::default(),
     }
 }
 
@@ -42,3 +62,95 @@ pub fn strip_comments_and_linebreaks(program: &str) -> String {
         .join("\n")
         + "\n";
 }
+
+/// Returns a program of a contract entry point with `n_writes` consecutive storage writes, writing
+/// the value `i` into the storage address `1000 + i` for every `i` in `1..=n_writes`.
+/// If `revoke_ap_tracking`, the ap tracking is revoked before the writes, so the location of the
+/// cell holding the selector of a write is unknown to the following writes.
+pub fn storage_writes_program(n_writes: usize, revoke_ap_tracking: bool) -> String {
+    let mut lines = [
+        "type GasBuiltin = GasBuiltin;",
+        "type System = System;",
+        "type felt = felt;",
+        "type StorageAddress = StorageAddress;",
+        "type ArrayFelt = Array<felt>;",
+        "type PanicResult = Enum<ut@core::PanicResult::<core::array::Array::<core::felt>>, \
+         ArrayFelt, ArrayFelt>;",
+        "libfunc revoke_ap_tracking = revoke_ap_tracking;",
+        "libfunc drop_calldata = drop<ArrayFelt>;",
+        "libfunc felt_const_0 = felt_const<0>;",
+        "libfunc store_temp_felt = store_temp<felt>;",
+        "libfunc storage_write = storage_write_syscall;",
+        "libfunc branch_align = branch_align;",
+        "libfunc store_temp_gb = store_temp<GasBuiltin>;",
+        "libfunc store_temp_system = store_temp<System>;",
+        "libfunc array_new_felt = array_new<felt>;",
+        "libfunc array_append_felt = array_append<felt>;",
+        "libfunc panic_result_ok = enum_init<PanicResult, 0>;",
+        "libfunc panic_result_err = enum_init<PanicResult, 1>;",
+        "libfunc store_temp_panic_result = store_temp<PanicResult>;",
+    ]
+    .map(String::from)
+    .to_vec();
+    for i in 1..=n_writes {
+        lines.push(format!("libfunc felt_const_{i} = felt_const<{i}>;"));
+        lines.push(format!("libfunc address_const_{i} = storage_address_const<{}>;", 1000 + i));
+    }
+    if revoke_ap_tracking {
+        lines.push("revoke_ap_tracking() -> ();".into());
+    }
+    // The writes start after the drop of the calldata, and each takes 8 statements. They are
+    // followed by the 6 statements of the success flow, and then by the 8 statements of the
+    // failure flow of each write.
+    let writes_start = usize::from(revoke_ap_tracking) + 1;
+    let failure_flows_start = writes_start + 8 * n_writes + 6;
+    lines.push("drop_calldata([2]) -> ();".into());
+    for i in 1..=n_writes {
+        let failure_flow = failure_flows_start + 8 * (i - 1);
+        lines.extend([
+            "store_temp_gb([0]) -> ([0]);".into(),
+            "felt_const_0() -> ([3]);".into(),
+            "store_temp_felt([3]) -> ([3]);".into(),
+            format!("felt_const_{i}() -> ([4]);"),
+            "store_temp_felt([4]) -> ([4]);".into(),
+            format!("address_const_{i}() -> ([5]);"),
+            format!(
+                "storage_write([0], [1], [3], [5], [4]) {{ fallthrough([0], [1]) \
+                 {failure_flow}([0], [1], [6]) }};"
+            ),
+            "branch_align() -> ();".into(),
+        ]);
+    }
+    lines.extend(
+        [
+            "array_new_felt() -> ([7]);",
+            "panic_result_ok([7]) -> ([8]);",
+            "store_temp_gb([0]) -> ([0]);",
+            "store_temp_system([1]) -> ([1]);",
+            "store_temp_panic_result([8]) -> ([8]);",
+            "return([0], [1], [8]);",
+        ]
+        .map(String::from),
+    );
+    for _ in 1..=n_writes {
+        lines.extend(
+            [
+                "branch_align() -> ();",
+                "array_new_felt() -> ([7]);",
+                "array_append_felt([7], [6]) -> ([7]);",
+                "panic_result_err([7]) -> ([8]);",
+                "store_temp_gb([0]) -> ([0]);",
+                "store_temp_system([1]) -> ([1]);",
+                "store_temp_panic_result([8]) -> ([8]);",
+                "return([0], [1], [8]);",
+            ]
+            .map(String::from),
+        );
+    }
+    lines.push(
+        "store_writes@0([0]: GasBuiltin, [1]: System, [2]: ArrayFelt) -> (GasBuiltin, System, \
+         PanicResult);"
+            .into(),
+    );
+    lines.join("\n") + "\n"
+}
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 
-use sierra::extensions::core::{CoreLibFunc, CoreType, CoreTypeConcrete};
+use sierra::extensions::core::{CoreType, CoreTypeConcrete};
 use sierra::extensions::non_zero::NonZeroConcreteType;
 use sierra::extensions::starknet::StarkNetTypeConcrete;
+use sierra::extensions::GenericLibFunc;
 use sierra::ids::ConcreteTypeId;
 use sierra::program::Program;
 use sierra::program_registry::ProgramRegistry;
@@ -10,9 +11,9 @@ use sierra::program_registry::ProgramRegistry;
 pub type TypeSizeMap = HashMap<ConcreteTypeId, i16>;
 
 /// Returns a mapping for the sizes of all types for the given program.
-pub fn get_type_size_map(
+pub fn get_type_size_map<TLibFunc: GenericLibFunc>(
     program: &Program,
-    registry: &ProgramRegistry<CoreType, CoreLibFunc>,
+    registry: &ProgramRegistry<CoreType, TLibFunc>,
 ) -> Option<TypeSizeMap> {
     let mut type_sizes = TypeSizeMap::new();
     for declaration in &program.type_declarations {
@@ -20,6 +21,7 @@ pub fn get_type_size_map(
         let size = match ty {
             CoreTypeConcrete::Felt(_)
             | CoreTypeConcrete::GasBuiltin(_)
+            | CoreTypeConcrete::GasReserve(_)
             | CoreTypeConcrete::Bitwise(_)
             | CoreTypeConcrete::BuiltinCosts(_)
             | CoreTypeConcrete::Uint128(_)
@@ -27,8 +29,10 @@ pub fn get_type_size_map(
             | CoreTypeConcrete::Box(_)
             | CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::System(_))
             | CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::StorageAddress(_))
+            | CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::StorageBaseAddress(_))
             | CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::ContractAddress(_))
             | CoreTypeConcrete::Pedersen(_) => Some(1),
+            CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::TxInfo(_)) => Some(8),
             CoreTypeConcrete::Array(_)
             | CoreTypeConcrete::DictFeltTo(_)
             | CoreTypeConcrete::SquashedDictFeltTo(_) => Some(2),
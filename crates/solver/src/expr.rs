@@ -1,34 +1,45 @@
-use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 
 use itertools::chain;
 use utils::collection_arithmetics::HasZero;
+use utils::ordered_hash_map::OrderedHashMap;
 
 #[cfg(test)]
 #[path = "expr_test.rs"]
 mod test;
 
 /// An linear expression of varialbes.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug)]
 pub struct Expr<Var: Clone + Debug + PartialEq + Eq + Hash> {
     /// The constant term of the expression.
     pub const_term: i32,
     /// The coefficient for every variable in the expression.
-    pub var_to_coef: HashMap<Var, i64>,
+    /// Ordered, so that the order of the variables given to the solver is deterministic.
+    pub var_to_coef: OrderedHashMap<Var, i64>,
 }
 impl<Var: Clone + Debug + PartialEq + Eq + Hash> Expr<Var> {
     /// Creates a cost expression based on const value only.
     pub fn from_const(const_term: i32) -> Self {
-        Self { const_term, var_to_coef: HashMap::default() }
+        Self { const_term, var_to_coef: OrderedHashMap::default() }
     }
 
     /// Creates a cost expression based on variable only.
     pub fn from_var(var: Var) -> Self {
-        Self { const_term: 0, var_to_coef: HashMap::from([(var, 1)]) }
+        Self { const_term: 0, var_to_coef: OrderedHashMap::from([(var, 1)]) }
     }
 }
 
+// Equality is independent of the order of the variables.
+impl<Var: Clone + Debug + PartialEq + Eq + Hash> PartialEq for Expr<Var> {
+    fn eq(&self, other: &Self) -> bool {
+        self.const_term == other.const_term
+            && self.var_to_coef.len() == other.var_to_coef.len()
+            && self.var_to_coef.iter().all(|(var, coef)| other.var_to_coef.get(var) == Some(coef))
+    }
+}
+impl<Var: Clone + Debug + PartialEq + Eq + Hash> Eq for Expr<Var> {}
+
 impl<Var: Clone + Debug + PartialEq + Eq + Hash> HasZero for Expr<Var> {
     fn zero() -> Self {
         Self::from_const(0)
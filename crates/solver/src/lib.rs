@@ -1,27 +1,25 @@
 pub mod expr;
 
-use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 
 pub use expr::Expr;
 use good_lp::{default_solver, variable, variables, Expression, Solution, SolverModel};
+use utils::ordered_hash_map::OrderedHashMap;
 
 /// Solving a set of equations and returning the values of the symbols contained in them.
 pub fn try_solve_equations<Var: Clone + Debug + PartialEq + Eq + Hash>(
     equations: Vec<Expr<Var>>,
 ) -> Option<HashMap<Var, i64>> {
     let mut vars = variables!();
-    let mut orig_to_solver_var = HashMap::new();
+    let mut orig_to_solver_var = OrderedHashMap::default();
     // Add all variables to structure and map.
     for eq in &equations {
         for var in eq.var_to_coef.keys() {
-            match orig_to_solver_var.entry(var.clone()) {
-                Entry::Occupied(_) => {}
-                Entry::Vacant(e) => {
-                    e.insert(vars.add(variable().min(0).name(format!("{:?}", var))));
-                }
+            if !orig_to_solver_var.contains_key(var) {
+                orig_to_solver_var
+                    .insert(var.clone(), vars.add(variable().min(0).name(format!("{:?}", var))));
             }
         }
     }
@@ -3,6 +3,8 @@ use semantic::db::SemanticGroup;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::events::EventDefinition;
+
 #[cfg(test)]
 #[path = "abi_test.rs"]
 mod test;
@@ -58,6 +60,22 @@ impl Contract {
         Ok(())
     }
 
+    /// Adds an event to the ABI.
+    pub fn add_event(&mut self, db: &dyn SemanticGroup, event: &EventDefinition) {
+        self.items.push(Item::Event(Event {
+            name: event.name.clone(),
+            inputs: event
+                .fields
+                .iter()
+                .map(|field| EventInput {
+                    name: field.name.clone(),
+                    ty: field.ty.format(db),
+                    kind: if field.is_key { EventFieldKind::Key } else { EventFieldKind::Data },
+                })
+                .collect(),
+        }));
+    }
+
     pub fn json(&self) -> String {
         serde_json::to_string_pretty(&self).unwrap()
     }
@@ -77,6 +95,8 @@ pub enum ABIError {
 pub enum Item {
     #[serde(rename = "function")]
     Function(Function),
+    #[serde(rename = "event")]
+    Event(Event),
 }
 
 /// Contract function ABI.
@@ -93,3 +113,27 @@ pub struct Input {
     pub name: String,
     pub ty: String,
 }
+
+/// Contract event ABI.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    pub name: String,
+    pub inputs: Vec<EventInput>,
+}
+
+/// Event field ABI.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventInput {
+    pub name: String,
+    pub ty: String,
+    pub kind: EventFieldKind,
+}
+
+/// Whether an event field is serialized into the keys or the data of the emitted event.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventFieldKind {
+    #[serde(rename = "key")]
+    Key,
+    #[serde(rename = "data")]
+    Data,
+}
@@ -4,6 +4,7 @@ mod test;
 
 use std::collections::HashMap;
 
+use casm::cost::CostWeights;
 use num_bigint::BigUint;
 use num_integer::Integer;
 use num_traits::{Num, Signed};
@@ -16,11 +17,12 @@ use sierra::extensions::NoGenericArgsGenericType;
 use sierra::ids::ConcreteTypeId;
 use sierra_ap_change::{calc_ap_changes, ApChangeError};
 use sierra_gas::{calc_gas_info, CostError};
-use sierra_to_casm::compiler::CompilationError;
+use sierra_to_casm::compiler::{CompilationError, DebugInfoSection};
 use sierra_to_casm::metadata::Metadata;
 use thiserror::Error;
 
-use crate::contract_class::{ContractClass, ContractEntryPoint};
+use crate::contract_class::{ArtifactMode, ContractClass, ContractEntryPoint};
+use crate::hint_verification::HintVerificationError;
 
 #[derive(Error, Debug, Eq, PartialEq)]
 pub enum StarknetSierraCompilationError {
@@ -34,6 +36,8 @@ pub enum StarknetSierraCompilationError {
     EntryPointError,
     #[error("{0} is not a supported builtin type.")]
     InvalidBuiltinType(ConcreteTypeId),
+    #[error(transparent)]
+    HintVerificationError(#[from] HintVerificationError),
 }
 
 /// Represents a contract in the StarkNet network.
@@ -44,12 +48,27 @@ pub struct CasmContractClass {
     pub compiler_version: String,
     pub bytecode: Vec<BigIntAsHex>,
     pub hints: Vec<(usize, Vec<String>)>,
+    /// The hints in the structured format, which can be parsed back into hints in order to run
+    /// the class outside of the sequencer.
+    pub structured_hints: Vec<(usize, Vec<String>)>,
     pub entry_points_by_type: CasmContractEntryPoints,
+    /// The debug information of the code for external debuggers, kept only in debug artifacts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug_info: Option<DebugInfoSection>,
 }
 
 impl CasmContractClass {
+    /// Compiles a contract class for the sequencer, keeping no debug information.
     pub fn from_contract_class(
         contract_class: ContractClass,
+    ) -> Result<Self, StarknetSierraCompilationError> {
+        Self::from_contract_class_with_mode(contract_class, ArtifactMode::Release)
+    }
+
+    /// Compiles a contract class, keeping the debug print hints only in [ArtifactMode::Debug].
+    pub fn from_contract_class_with_mode(
+        contract_class: ContractClass,
+        artifact_mode: ArtifactMode,
     ) -> Result<Self, StarknetSierraCompilationError> {
         let prime = BigUint::from_str_radix(
             "800000000000011000000000000000000000000000000000000000000000001",
@@ -61,29 +80,46 @@ impl CasmContractClass {
         let gas_info = calc_gas_info(&program)?;
 
         let gas_usage_check = true;
-        let cairo_program = sierra_to_casm::compiler::compile(
+        let mut cairo_program = sierra_to_casm::compiler::compile(
             &program,
-            &Metadata { ap_change_info: calc_ap_changes(&program)?, gas_info },
+            &Metadata {
+                ap_change_info: calc_ap_changes(&program)?,
+                gas_info,
+                cost_weights: CostWeights::default(),
+            },
             gas_usage_check,
+            false,
         )?;
+        // Contract classes are executed by the sequencer and proven, so debug prints are only kept
+        // in debug artifacts.
+        if artifact_mode == ArtifactMode::Release {
+            cairo_program.strip_debug_hints();
+        }
 
-        let mut bytecode = vec![];
-        let mut hints = vec![];
-        for instruction in cairo_program.instructions {
-            if !instruction.hints.is_empty() {
-                hints.push((
-                    bytecode.len(),
-                    instruction.hints.iter().map(|hint| hint.to_string()).collect(),
-                ))
-            }
-            bytecode.extend(instruction.assemble().encode().iter().map(|big_int| {
+        let assembled_program = cairo_program.assemble();
+        let bytecode = assembled_program
+            .bytecode
+            .iter()
+            .map(|big_int| {
                 let (_q, reminder) = big_int.magnitude().div_rem(&prime);
 
                 BigIntAsHex {
                     value: if big_int.is_negative() { &prime - reminder } else { reminder },
                 }
-            }))
-        }
+            })
+            .collect();
+        let hints = assembled_program
+            .hints
+            .iter()
+            .map(|(offset, hints)| (*offset, hints.iter().map(|hint| hint.to_string()).collect()))
+            .collect();
+        let structured_hints = assembled_program
+            .hints
+            .iter()
+            .map(|(offset, hints)| {
+                (*offset, hints.iter().map(|hint| hint.structured().to_string()).collect())
+            })
+            .collect();
 
         let name_by_debug_id = HashMap::<u64, String>::from(
             [RangeCheckType::ID, PedersenType::ID, GasBuiltinType::ID].map(|generic_id| {
@@ -148,17 +184,26 @@ impl CasmContractClass {
             Ok::<Vec<CasmContractEntryPoint>, StarknetSierraCompilationError>(entry_points)
         };
 
-        Ok(Self {
+        let mut casm_contract = Self {
             prime,
             compiler_version: "1.0.0".to_string(),
             bytecode,
             hints,
+            structured_hints,
             entry_points_by_type: CasmContractEntryPoints {
                 external: as_casm_entry_points(contract_class.entry_points_by_type.external)?,
                 l1_handler: as_casm_entry_points(contract_class.entry_points_by_type.l1_handler)?,
                 constructor: as_casm_entry_points(contract_class.entry_points_by_type.constructor)?,
             },
-        })
+            debug_info: match artifact_mode {
+                ArtifactMode::Debug => Some(cairo_program.debug_info.section()),
+                ArtifactMode::Release => None,
+            },
+        };
+        if artifact_mode == ArtifactMode::Release {
+            casm_contract.strip_nonessential_hints()?;
+        }
+        Ok(casm_contract)
     }
 }
 
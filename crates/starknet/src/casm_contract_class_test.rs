@@ -1,7 +1,10 @@
+use test_case::test_case;
 use test_utils::compare_contents_or_fix_with_path;
 
 use crate::casm_contract_class::CasmContractClass;
-use crate::test_utils::{get_example_file_path, get_test_contract};
+use crate::test_utils::{
+    assert_code_independent_of_artifact_mode, get_example_file_path, get_test_contract,
+};
 
 #[test]
 fn test_casm_contract_from_contract_class() {
@@ -13,3 +16,14 @@ fn test_casm_contract_from_contract_class() {
         serde_json::to_string_pretty(&casm_contract).unwrap() + "\n",
     );
 }
+
+#[test_case("test_contract.cairo", &[]; "test_contract")]
+#[test_case("hardcoded_addresses.cairo", &[]; "hardcoded_addresses")]
+#[test_case(
+    "unchecked_storage.cairo",
+    &["unchecked_storage::unchecked_storage::TrustedToken"];
+    "unchecked_storage"
+)]
+fn test_artifact_modes_compile_to_same_code(file_name: &str, unchecked_storage_allowlist: &[&str]) {
+    assert_code_independent_of_artifact_mode(file_name, unchecked_storage_allowlist);
+}
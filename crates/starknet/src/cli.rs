@@ -3,7 +3,7 @@ use std::path::PathBuf;
 
 use anyhow::Context;
 use clap::Parser;
-use starknet::contract_class::compile_path;
+use starknet::contract_class::{compile_path_with_config, ArtifactMode, ContractCompilationConfig};
 
 /// Command line args parser.
 /// Exits with 0/1 if the input is formatted correctly/incorrectly.
@@ -17,11 +17,39 @@ struct Args {
     /// Replaces sierra ids with human readable ones.
     #[arg(short, long, default_value_t = false)]
     replace_ids: bool,
+    /// Writes the storage layout table of the contract to the given file.
+    #[arg(long)]
+    storage_layout: Option<String>,
+    /// Allows the given function, or the functions of the given module, to use unchecked storage
+    /// access. May be repeated.
+    #[arg(long)]
+    allow_unchecked_storage: Vec<String>,
+    /// Whether to check the storage addresses at runtime (default: checked only when replacing
+    /// the sierra ids, i.e. for debug artifacts).
+    #[arg(long)]
+    check_storage_addresses: Option<bool>,
+    /// Whether to range check the lengths of arrays at runtime (default: checked only when
+    /// replacing the sierra ids, i.e. for debug artifacts).
+    #[arg(long)]
+    check_array_views: Option<bool>,
+    /// Strips all the debug information from the output, including the human readable ids.
+    #[arg(long, default_value_t = false)]
+    release: bool,
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let contract = compile_path(&PathBuf::from(args.path), args.replace_ids)?;
+    let config = ContractCompilationConfig {
+        unchecked_storage_allowlist: args.allow_unchecked_storage,
+        check_storage_addresses: args.check_storage_addresses.unwrap_or(args.replace_ids),
+        check_array_views: args.check_array_views.unwrap_or(args.replace_ids),
+        artifact_mode: if args.release { ArtifactMode::Release } else { ArtifactMode::Debug },
+    };
+    let contract = compile_path_with_config(&PathBuf::from(args.path), args.replace_ids, &config)?;
+    if let Some(path) = args.storage_layout {
+        fs::write(path, contract.storage_layout.to_string())
+            .with_context(|| "Failed to write storage layout.")?;
+    }
     let res = serde_json::to_string_pretty(&contract).with_context(|| "Serialization failed.")?;
     match args.output {
         Some(path) => fs::write(path, res).with_context(|| "Failed to write output.")?,
@@ -16,16 +16,23 @@ use semantic::corelib::get_core_ty_by_name;
 use semantic::db::SemanticGroup;
 use semantic::{ConcreteFunction, FunctionLongId};
 use serde::{Deserialize, Serialize};
+use sierra::serialization::{deserialize_program_from_text, serialize_program_as_text};
 use sierra::{self};
 use sierra_generator::canonical_id_replacer::CanonicalReplacer;
 use sierra_generator::db::SierraGenGroup;
 use sierra_generator::replace_ids::{replace_sierra_ids_in_program, SierraIdReplacer};
 use thiserror::Error;
+use utils::unordered_hash_map::UnorderedHashMap;
 
 use crate::abi;
 use crate::casm_contract_class::{deserialize_big_uint, serialize_big_uint};
 use crate::contract::{find_contracts, get_external_functions, starknet_keccak};
+use crate::entry_points::{compute_selectors, EntryPointKind};
+use crate::error_codes::collect_error_codes;
 use crate::plugin::StarkNetPlugin;
+use crate::referenced_constants::ReferencedConstants;
+use crate::storage_layout::StorageLayout;
+use crate::unchecked_storage::UncheckedStorageAccess;
 
 #[cfg(test)]
 #[path = "contract_class_test.rs"]
@@ -40,9 +47,57 @@ pub enum StarknetCompilationError {
 /// Represents a contract in the StarkNet network.
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ContractClass {
+    #[serde(
+        serialize_with = "serialize_program_as_text",
+        deserialize_with = "deserialize_program_from_text"
+    )]
     pub sierra_program: sierra::program::Program,
     pub entry_points_by_type: ContractEntryPoints,
     pub abi: abi::Contract,
+    pub storage_layout: StorageLayout,
+    /// The constants hardcoded in the code reachable from each entry point.
+    pub referenced_constants: ReferencedConstants,
+    /// The functions using unchecked storage access.
+    pub unchecked_storage_access: UncheckedStorageAccess,
+    /// Symbolic names for the felt error codes the contract may panic with, sorted by code.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub error_codes: Vec<ErrorCode>,
+}
+
+/// A symbolic name of a felt error code.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorCode {
+    #[serde(serialize_with = "serialize_big_uint", deserialize_with = "deserialize_big_uint")]
+    pub code: BigUint,
+    pub name: String,
+}
+
+/// Configuration for the compilation of a contract.
+#[derive(Default, Debug)]
+pub struct ContractCompilationConfig {
+    /// The functions allowed to use unchecked storage access - full function names, or names of
+    /// modules whose functions are all allowed. Meant for trusted system contracts only.
+    pub unchecked_storage_allowlist: Vec<String>,
+    /// Whether to check at runtime that the addresses of storage accesses are valid storage
+    /// addresses, in addition to the checks on their construction.
+    pub check_storage_addresses: bool,
+    /// Whether to range check the lengths of arrays at runtime, catching corrupted arrays whose
+    /// end is before their start.
+    pub check_array_views: bool,
+    /// The amount of debug information kept in the artifact.
+    pub artifact_mode: ArtifactMode,
+}
+
+/// The amount of debug information kept in compiled artifacts. Never affects the compiled code.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum ArtifactMode {
+    /// Keeps the debug names of the Sierra ids (when replacing the ids), the source locations of
+    /// the statements and the debug print hints.
+    #[default]
+    Debug,
+    /// Strips all the debug information: the Sierra ids are always canonical, and only the hints
+    /// required for running the code are kept.
+    Release,
 }
 
 #[derive(Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -65,8 +120,18 @@ pub struct ContractEntryPoint {
 }
 
 // Compile the contract given by path.
-// If `replace_ids` is true, replaces sierra ids with human readable ones.
+// If `replace_ids` is true, replaces sierra ids with human readable ones, unless compiling in
+// release mode.
 pub fn compile_path(path: &Path, replace_ids: bool) -> anyhow::Result<ContractClass> {
+    compile_path_with_config(path, replace_ids, &ContractCompilationConfig::default())
+}
+
+// Same as `compile_path`, with the given compilation configuration.
+pub fn compile_path_with_config(
+    path: &Path,
+    replace_ids: bool,
+    config: &ContractCompilationConfig,
+) -> anyhow::Result<ContractClass> {
     let mut db_val = RootDatabase::default();
     let db = &mut db_val;
 
@@ -79,6 +144,15 @@ pub fn compile_path(path: &Path, replace_ids: bool) -> anyhow::Result<ContractCl
             .map(|name| get_core_ty_by_name(db, name.into(), vec![]))
             .collect_vec(),
     ));
+    let mut substitutions = UnorderedHashMap::default();
+    if config.check_storage_addresses {
+        substitutions.insert("storage_read_syscall".into(), "storage_read_checked".into());
+        substitutions.insert("storage_write_syscall".into(), "storage_write_checked".into());
+    }
+    if config.check_array_views {
+        substitutions.insert("array_len".into(), "array_len_checked".into());
+    }
+    db.set_extern_function_substitutions(Arc::new(substitutions));
 
     let mut plugins = get_default_plugins();
     plugins.push(Arc::new(StarkNetPlugin {}));
@@ -105,16 +179,33 @@ pub fn compile_path(path: &Path, replace_ids: bool) -> anyhow::Result<ContractCl
         .with_context(|| "Compilation failed without any diagnostics.")?;
 
     let replacer = CanonicalReplacer::from_program(&sierra_program);
-    let sierra_program = if replace_ids {
-        replace_sierra_ids_in_program(db, &sierra_program)
+    let debug_sierra_program = replace_sierra_ids_in_program(db, &sierra_program);
+    let unchecked_storage_access =
+        UncheckedStorageAccess::new(&debug_sierra_program, &config.unchecked_storage_allowlist)?;
+    let error_codes = collect_error_codes(&debug_sierra_program);
+    let mut sierra_program = if replace_ids && config.artifact_mode == ArtifactMode::Debug {
+        debug_sierra_program
     } else {
         replacer.apply(&sierra_program)
     };
+    if config.artifact_mode == ArtifactMode::Release {
+        sierra_program.statement_locations.clear();
+    }
 
     let entry_points_by_type = get_entry_points(db, &external_functions, &replacer)?;
     // TODO(ilya): fix abi.
     let abi = abi::Contract::default();
-    Ok(ContractClass { sierra_program, entry_points_by_type, abi })
+    let storage_layout = StorageLayout::from_contract(db, contract)?;
+    let referenced_constants = ReferencedConstants::new(&sierra_program, &entry_points_by_type)?;
+    Ok(ContractClass {
+        sierra_program,
+        entry_points_by_type,
+        abi,
+        storage_layout,
+        referenced_constants,
+        unchecked_storage_access,
+        error_codes,
+    })
 }
 
 /// Return the entry points given a trait and a module_id where they are implemented.
@@ -123,8 +214,14 @@ fn get_entry_points(
     external_functions: &[FreeFunctionId],
     replacer: &CanonicalReplacer,
 ) -> Result<ContractEntryPoints, anyhow::Error> {
+    let names: Vec<_> = external_functions
+        .iter()
+        .map(|free_func_id| (EntryPointKind::External, free_func_id.name(db).to_string()))
+        .collect();
+    let selectors = compute_selectors(&names, |name| starknet_keccak(name.as_bytes()))?;
+
     let mut entry_points_by_type = ContractEntryPoints::default();
-    for free_func_id in external_functions {
+    for (free_func_id, selector) in external_functions.iter().zip(selectors) {
         let func_id = db.intern_function(FunctionLongId {
             function: ConcreteFunction {
                 generic_function: GenericFunctionId::Free(*free_func_id),
@@ -135,7 +232,7 @@ fn get_entry_points(
         let sierra_id = db.intern_sierra_function(func_id);
 
         entry_points_by_type.external.push(ContractEntryPoint {
-            selector: starknet_keccak(free_func_id.name(db).as_bytes()),
+            selector,
             function_idx: replacer.replace_function_id(&sierra_id).id as usize,
         });
     }
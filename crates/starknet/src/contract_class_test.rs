@@ -4,8 +4,14 @@ use pretty_assertions::assert_eq;
 use test_utils::compare_contents_or_fix_with_path;
 
 use crate::abi;
-use crate::contract_class::{ContractClass, ContractEntryPoint, ContractEntryPoints};
+use crate::contract_class::{
+    compile_path_with_config, ContractClass, ContractCompilationConfig, ContractEntryPoint,
+    ContractEntryPoints,
+};
+use crate::referenced_constants::ReferencedConstants;
+use crate::storage_layout::StorageLayout;
 use crate::test_utils::{get_example_file_path, get_test_contract};
+use crate::unchecked_storage::UncheckedStorageAccess;
 
 #[test]
 fn test_serialization() {
@@ -17,6 +23,7 @@ fn test_serialization() {
             libfunc_declarations: vec![],
             statements: vec![],
             funcs: vec![],
+            statement_locations: Default::default(),
         },
         entry_points_by_type: ContractEntryPoints {
             external,
@@ -24,6 +31,10 @@ fn test_serialization() {
             constructor: vec![],
         },
         abi: abi::Contract::default(),
+        storage_layout: StorageLayout::default(),
+        referenced_constants: ReferencedConstants::default(),
+        unchecked_storage_access: UncheckedStorageAccess::default(),
+        error_codes: vec![],
     };
 
     let serialized = serde_json::to_string_pretty(&contract).unwrap();
@@ -44,7 +55,10 @@ fn test_serialization() {
             "L1_HANDLER": [],
             "CONSTRUCTOR": []
           },
-          "abi": []
+          "abi": [],
+          "storage_layout": [],
+          "referenced_constants": [],
+          "unchecked_storage_access": []
         }"#}
     );
 
@@ -74,3 +88,51 @@ fn test_compile_path() {
         contract.sierra_program.to_string(),
     );
 }
+
+#[test]
+fn test_compile_path_with_checked_storage_addresses() {
+    let libfunc_names = |check_storage_addresses| {
+        let contract = compile_path_with_config(
+            &get_example_file_path("test_contract.cairo"),
+            true,
+            &ContractCompilationConfig { check_storage_addresses, ..Default::default() },
+        )
+        .unwrap();
+        contract
+            .sierra_program
+            .libfunc_declarations
+            .into_iter()
+            .map(|declaration| declaration.long_id.generic_id.to_string())
+            .filter(|name| name.starts_with("storage_"))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        libfunc_names(false),
+        ["storage_address_const", "storage_read_syscall", "storage_write_syscall"]
+    );
+    assert_eq!(
+        libfunc_names(true),
+        ["storage_address_const", "storage_read_checked", "storage_write_checked"]
+    );
+}
+
+#[test]
+fn test_compile_path_with_checked_array_views() {
+    let libfunc_names = |check_array_views| {
+        let contract = compile_path_with_config(
+            &get_example_file_path("test_contract.cairo"),
+            true,
+            &ContractCompilationConfig { check_array_views, ..Default::default() },
+        )
+        .unwrap();
+        contract
+            .sierra_program
+            .libfunc_declarations
+            .into_iter()
+            .map(|declaration| declaration.long_id.generic_id.to_string())
+            .filter(|name| name.starts_with("array_len"))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(libfunc_names(false), ["array_len"]);
+    assert_eq!(libfunc_names(true), ["array_len_checked"]);
+}
@@ -0,0 +1,70 @@
+use num_bigint::BigUint;
+use thiserror::Error;
+
+#[cfg(test)]
+#[path = "entry_points_test.rs"]
+mod test;
+
+/// The maximal number of bits of an entry point selector.
+pub const MAX_SELECTOR_BITS: u64 = 250;
+
+/// The kinds of the entry points of a contract.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryPointKind {
+    External,
+    L1Handler,
+    Constructor,
+}
+
+/// Names reserved for an entry point of a specific kind.
+const RESERVED_NAMES: [(&str, EntryPointKind); 3] = [
+    ("__default__", EntryPointKind::External),
+    ("__l1_default__", EntryPointKind::L1Handler),
+    ("constructor", EntryPointKind::Constructor),
+];
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum EntryPointError {
+    #[error(
+        "Functions `{first}` and `{second}` have the same selector {selector:#x}, and would \
+         shadow each other."
+    )]
+    SelectorCollision { first: String, second: String, selector: BigUint },
+    #[error("Function `{name}` uses the name reserved for the {expected:?} entry point.")]
+    ReservedName { name: String, expected: EntryPointKind },
+    #[error("The selector {selector:#x} of function `{name}` exceeds 250 bits.")]
+    SelectorOutOfRange { name: String, selector: BigUint },
+}
+
+/// Computes the selectors of the given entry points using `selector_fn`, validating that the
+/// selectors are in range and unique across all the entry point kinds, and that reserved names are
+/// only used by entry points of the matching kind.
+///
+/// Returns the selectors, in the order of the entry points.
+pub fn compute_selectors(
+    entry_points: &[(EntryPointKind, String)],
+    selector_fn: impl Fn(&str) -> BigUint,
+) -> Result<Vec<BigUint>, EntryPointError> {
+    let mut selectors: Vec<BigUint> = vec![];
+    for (kind, name) in entry_points {
+        if let Some((_, expected)) = RESERVED_NAMES
+            .iter()
+            .find(|(reserved, expected)| *reserved == name.as_str() && expected != kind)
+        {
+            return Err(EntryPointError::ReservedName { name: name.clone(), expected: *expected });
+        }
+        let selector = selector_fn(name);
+        if selector.bits() > MAX_SELECTOR_BITS {
+            return Err(EntryPointError::SelectorOutOfRange { name: name.clone(), selector });
+        }
+        if let Some(other_idx) = selectors.iter().position(|other| *other == selector) {
+            return Err(EntryPointError::SelectorCollision {
+                first: entry_points[other_idx].1.clone(),
+                second: name.clone(),
+                selector,
+            });
+        }
+        selectors.push(selector);
+    }
+    Ok(selectors)
+}
@@ -0,0 +1,65 @@
+use num_bigint::BigUint;
+use pretty_assertions::assert_eq;
+use test_case::test_case;
+
+use crate::contract::starknet_keccak;
+use crate::entry_points::{compute_selectors, EntryPointError, EntryPointKind};
+
+/// Returns the given names as entry points of the given kind.
+fn entry_points(kind: EntryPointKind, names: &[&str]) -> Vec<(EntryPointKind, String)> {
+    names.iter().map(|name| (kind, name.to_string())).collect()
+}
+
+#[test]
+fn test_valid_selectors() {
+    assert_eq!(
+        compute_selectors(&entry_points(EntryPointKind::External, &["foo", "bar"]), |name| {
+            starknet_keccak(name.as_bytes())
+        }),
+        Ok(vec![starknet_keccak(b"foo"), starknet_keccak(b"bar")])
+    );
+}
+
+#[test]
+fn test_selector_collision() {
+    // A selector function where all the names starting with the same letter collide.
+    let first_letter_selector = |name: &str| BigUint::from(name.as_bytes()[0]);
+    let mut names = entry_points(EntryPointKind::External, &["foo", "bar"]);
+    names.extend(entry_points(EntryPointKind::L1Handler, &["baz"]));
+    assert_eq!(
+        compute_selectors(&names, first_letter_selector),
+        Err(EntryPointError::SelectorCollision {
+            first: "bar".into(),
+            second: "baz".into(),
+            selector: BigUint::from(b'b'),
+        })
+    );
+}
+
+#[test]
+fn test_selector_out_of_range() {
+    let selector = BigUint::from(1u32) << 250;
+    assert_eq!(
+        compute_selectors(&entry_points(EntryPointKind::External, &["foo"]), |_| selector.clone()),
+        Err(EntryPointError::SelectorOutOfRange { name: "foo".into(), selector: selector.clone() })
+    );
+}
+
+#[test_case(EntryPointKind::External, "constructor", Some(EntryPointKind::Constructor);
+            "constructor as external")]
+#[test_case(EntryPointKind::External, "__l1_default__", Some(EntryPointKind::L1Handler);
+            "l1 default as external")]
+#[test_case(EntryPointKind::L1Handler, "__default__", Some(EntryPointKind::External);
+            "default as l1 handler")]
+#[test_case(EntryPointKind::External, "__default__", None; "default as external")]
+#[test_case(EntryPointKind::Constructor, "constructor", None; "constructor")]
+fn test_reserved_names(kind: EntryPointKind, name: &str, expected: Option<EntryPointKind>) {
+    assert_eq!(
+        compute_selectors(&entry_points(kind, &[name]), |name| starknet_keccak(name.as_bytes()))
+            .map(|_| ()),
+        match expected {
+            Some(expected) => Err(EntryPointError::ReservedName { name: name.into(), expected }),
+            None => Ok(()),
+        }
+    );
+}
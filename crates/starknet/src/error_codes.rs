@@ -0,0 +1,137 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use num_bigint::{BigInt, BigUint};
+use sierra::extensions::array::{ArrayAppendLibFunc, PanicArrayNewLibFunc};
+use sierra::extensions::duplicate::DupLibFunc;
+use sierra::extensions::enm::{EnumInitLibFunc, EnumType};
+use sierra::extensions::felt::FeltConstLibFunc;
+use sierra::extensions::function_call::FunctionCallLibFunc;
+use sierra::extensions::mem::{RenameLibFunc, StoreLocalLibFunc, StoreTempLibFunc};
+use sierra::extensions::{args_as_single_value, NamedLibFunc, NamedType};
+use sierra::ids::{ConcreteTypeId, FunctionId, VarId};
+use sierra::program::{Function, GenericArg, Program, Statement};
+use utils::short_string;
+
+use crate::contract_class::ErrorCode;
+
+#[cfg(test)]
+#[path = "error_codes_test.rs"]
+mod test;
+
+/// Collects the felt error codes `program` may panic with - the constants that become panic data,
+/// either as the argument of `panic_array_new` or appended to the data of a panic result, directly
+/// or through the parameters of the functions passing them on.
+/// Codes that are printable short strings are named by their text, and other codes by the function
+/// creating them.
+///
+/// The functions of `program` are expected to have their debug names.
+pub fn collect_error_codes(program: &Program) -> Vec<ErrorCode> {
+    let libfuncs: HashMap<_, _> =
+        program.libfunc_declarations.iter().map(|decl| (&decl.id, &decl.long_id)).collect();
+    let panic_results: HashSet<&ConcreteTypeId> = program
+        .type_declarations
+        .iter()
+        .filter(|decl| {
+            decl.long_id.generic_id == EnumType::ID
+                && matches!(
+                    decl.long_id.generic_args.first(),
+                    Some(GenericArg::UserType(user_type)) if user_type
+                        .debug_name
+                        .as_ref()
+                        .map_or(false, |name| name.starts_with("core::PanicResult"))
+                )
+        })
+        .map(|decl| &decl.id)
+        .collect();
+    let functions: HashMap<&FunctionId, &Function> =
+        program.funcs.iter().map(|func| (&func.id, func)).collect();
+    let mut entry_points: Vec<_> =
+        program.funcs.iter().map(|func| (func.entry_point.0, &func.id)).collect();
+    entry_points.sort();
+
+    // The variables holding panic data, with the functions they belong to. Computed backwards from
+    // the panics until no more variables are found.
+    let mut panic_vars: HashSet<(&FunctionId, &VarId)> = HashSet::new();
+    let mut codes: BTreeMap<BigUint, &FunctionId> = BTreeMap::new();
+    loop {
+        let mut changed = false;
+        for (idx, statement) in program.statements.iter().enumerate().rev() {
+            let Statement::Invocation(invocation) = statement else {
+                continue;
+            };
+            let Some(long_id) = libfuncs.get(&invocation.libfunc_id) else {
+                continue;
+            };
+            // Functions are laid out contiguously, so a statement belongs to the function with the
+            // last entry point before it.
+            let Some(&(_, function)) =
+                entry_points.iter().take_while(|(entry_point, _)| *entry_point <= idx).last()
+            else {
+                continue;
+            };
+            let mut results = invocation.branches.iter().flat_map(|branch| &branch.results);
+            let has_panic_result = results.any(|var| panic_vars.contains(&(function, var)));
+            let generic_id = &long_id.generic_id;
+            let new_panic_vars: Vec<&VarId> = if *generic_id == PanicArrayNewLibFunc::ID {
+                invocation.args.iter().collect()
+            } else if *generic_id == EnumInitLibFunc::ID {
+                match &long_id.generic_args[..] {
+                    [GenericArg::Type(ty), GenericArg::Value(variant)]
+                        if panic_results.contains(ty) && *variant == BigInt::from(1) =>
+                    {
+                        invocation.args.iter().collect()
+                    }
+                    _ => vec![],
+                }
+            } else if [
+                StoreTempLibFunc::ID,
+                StoreLocalLibFunc::ID,
+                RenameLibFunc::ID,
+                DupLibFunc::ID,
+                ArrayAppendLibFunc::ID,
+            ]
+            .contains(generic_id)
+            {
+                if has_panic_result { invocation.args.iter().collect() } else { vec![] }
+            } else if *generic_id == FunctionCallLibFunc::ID {
+                match &long_id.generic_args[..] {
+                    [GenericArg::UserFunc(callee)] => match functions.get_key_value(callee) {
+                        Some((callee, callee_func)) => invocation
+                            .args
+                            .iter()
+                            .zip(&callee_func.params)
+                            .filter(|(_, param)| panic_vars.contains(&(*callee, &param.id)))
+                            .map(|(arg, _)| arg)
+                            .collect(),
+                        None => vec![],
+                    },
+                    _ => vec![],
+                }
+            } else {
+                if *generic_id == FeltConstLibFunc::ID && has_panic_result {
+                    if let Some(code) = args_as_single_value(&long_id.generic_args)
+                        .ok()
+                        .and_then(|value| value.to_biguint())
+                    {
+                        codes.entry(code).or_insert(function);
+                    }
+                }
+                vec![]
+            };
+            for var in new_panic_vars {
+                changed |= panic_vars.insert((function, var));
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    codes
+        .into_iter()
+        .map(|(code, function)| {
+            let name = short_string::to_str(&BigInt::from(code.clone()))
+                .unwrap_or_else(|| format!("{function}: {code}"));
+            ErrorCode { code, name }
+        })
+        .collect()
+}
@@ -0,0 +1,74 @@
+use indoc::indoc;
+use num_bigint::BigUint;
+use pretty_assertions::assert_eq;
+use sierra::ProgramParser;
+use utils::short_string;
+
+use crate::contract_class::{compile_path, ErrorCode};
+use crate::error_codes::collect_error_codes;
+use crate::test_utils::get_example_file_path;
+
+#[test]
+fn test_collect_error_codes() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type ArrayFelt = Array<felt>;
+            type PanicResult = Enum<ut@core::PanicResult, felt, ArrayFelt>;
+
+            libfunc zero_code = felt_const<1514492495>;
+            libfunc overflow_code = felt_const<7>;
+            libfunc unused_code = felt_const<9>;
+            libfunc drop_felt = drop<felt>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc store_temp_result = store_temp<PanicResult>;
+            libfunc call_fail = function_call<user@fail>;
+            libfunc panic_array_new = panic_array_new;
+            libfunc array_new = array_new<felt>;
+            libfunc array_append = array_append<felt>;
+            libfunc enum_init_err = enum_init<PanicResult, 1>;
+
+            zero_code() -> ([0]);                           // #0
+            unused_code() -> ([1]);                         // #1
+            drop_felt([1]) -> ();                           // #2
+            store_temp_felt([0]) -> ([0]);                  // #3
+            call_fail([0]) -> ([2]);                        // #4
+            return([2]);                                    // #5
+
+            panic_array_new([0]) -> ([1]);                  // #6
+            enum_init_err([1]) -> ([2]);                    // #7
+            store_temp_result([2]) -> ([2]);                // #8
+            return([2]);                                    // #9
+
+            array_new() -> ([0]);                           // #10
+            overflow_code() -> ([1]);                       // #11
+            array_append([0], [1]) -> ([0]);                // #12
+            enum_init_err([0]) -> ([2]);                    // #13
+            store_temp_result([2]) -> ([2]);                // #14
+            return([2]);                                    // #15
+
+            check@0() -> (PanicResult);
+            fail@6([0]: felt) -> (PanicResult);
+            overflow@10() -> (PanicResult);
+        "})
+        .unwrap();
+    assert_eq!(
+        collect_error_codes(&program),
+        vec![
+            ErrorCode { code: BigUint::from(7u32), name: "overflow: 7".into() },
+            ErrorCode { code: BigUint::from(1514492495u32), name: "ZERO".into() },
+        ]
+    );
+}
+
+#[test]
+fn test_contract_error_codes() {
+    let contract = compile_path(&get_example_file_path("failing_contract.cairo"), true).unwrap();
+    assert_eq!(
+        contract.error_codes,
+        vec![ErrorCode {
+            code: short_string::from_str("ZERO_AMOUNT").unwrap().to_biguint().unwrap(),
+            name: "ZERO_AMOUNT".into(),
+        }]
+    );
+}
@@ -0,0 +1,213 @@
+use itertools::Itertools;
+use num_bigint::BigUint;
+use semantic::db::SemanticGroup;
+use semantic::TypeId;
+use sierra::program::Program;
+use thiserror::Error;
+
+use crate::contract::starknet_keccak;
+
+#[cfg(test)]
+#[path = "events_test.rs"]
+mod test;
+
+/// The types that are serialized into a single felt, and may therefore be used as event keys.
+/// Each is given with the generic Sierra type it is lowered to, and the libfunc converting it into
+/// a felt, if it is not a felt already.
+const SINGLE_FELT_TYPES: [(&str, &str, Option<&str>); 3] = [
+    ("core::felt", "felt", None),
+    ("core::integer::u128", "u128", Some("u128_to_felt")),
+    ("core::starknet::ContractAddress", "ContractAddress", Some("contract_addr_to_felt")),
+];
+
+/// Returns the generic Sierra type and the felt conversion of a single felt type, given its name.
+fn single_felt_type(ty: &str) -> Option<(&'static str, Option<&'static str>)> {
+    SINGLE_FELT_TYPES
+        .iter()
+        .find(|(name, _, _)| *name == ty)
+        .map(|(_, sierra_ty, conversion)| (*sierra_ty, *conversion))
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum EventError {
+    #[error("Event `{event}` has more than one field named `{field}`.")]
+    DuplicateField { event: String, field: String },
+    #[error("Key field `{field}` of event `{event}` has type `{ty}`, which is not a single felt.")]
+    KeyNotSingleFelt { event: String, field: String, ty: String },
+    #[error("Data field `{field}` of event `{event}` has type `{ty}`, which can not be emitted.")]
+    UnsupportedDataField { event: String, field: String, ty: String },
+    #[error("Event `{event}` has {expected} fields, but {actual} values were given.")]
+    WrongNumberOfValues { event: String, expected: usize, actual: usize },
+}
+
+/// A single field of an event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventField {
+    pub name: String,
+    pub ty: TypeId,
+    /// Whether the field is indexed - i.e. serialized into the keys rather than the data.
+    pub is_key: bool,
+}
+
+/// A typed contract event.
+///
+/// An emitted event is laid out as two felt arrays: the keys, starting with the selector of the
+/// event name followed by the key fields, and the data, holding the serialization of the rest of
+/// the fields. Both keep the order of the fields in the definition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventDefinition {
+    pub name: String,
+    pub fields: Vec<EventField>,
+}
+
+impl EventDefinition {
+    /// Registers an event, validating that its field names are unique and that all its key fields
+    /// are single felts.
+    pub fn new(
+        db: &dyn SemanticGroup,
+        name: String,
+        fields: Vec<EventField>,
+    ) -> Result<Self, EventError> {
+        for (i, field) in fields.iter().enumerate() {
+            if fields[..i].iter().any(|other| other.name == field.name) {
+                return Err(EventError::DuplicateField { event: name, field: field.name.clone() });
+            }
+            let ty = field.ty.format(db);
+            if field.is_key && single_felt_type(&ty).is_none() {
+                return Err(EventError::KeyNotSingleFelt {
+                    event: name,
+                    field: field.name.clone(),
+                    ty,
+                });
+            }
+        }
+        Ok(Self { name, fields })
+    }
+
+    /// Returns the selector of the event, which is the first of its keys.
+    pub fn selector(&self) -> BigUint {
+        starknet_keccak(self.name.as_bytes())
+    }
+
+    /// Partitions the serialized fields of an event into its keys and data.
+    ///
+    /// `values` holds the serialization of each of the fields, in the order of the definition.
+    pub fn layout(
+        &self,
+        values: Vec<Vec<BigUint>>,
+    ) -> Result<(Vec<BigUint>, Vec<BigUint>), EventError> {
+        if values.len() != self.fields.len() {
+            return Err(EventError::WrongNumberOfValues {
+                event: self.name.clone(),
+                expected: self.fields.len(),
+                actual: values.len(),
+            });
+        }
+        let mut keys = vec![self.selector()];
+        let mut data = vec![];
+        for (field, value) in self.fields.iter().zip(values) {
+            if field.is_key {
+                keys.extend(value)
+            } else {
+                data.extend(value)
+            }
+        }
+        Ok((keys, data))
+    }
+
+    /// Generates the Sierra function `emit_<name>`, emitting the event through the `emit_event`
+    /// syscall.
+    ///
+    /// The function takes the gas builtin, the system pointer and the fields in the order of the
+    /// definition, and returns the gas builtin, the system pointer and the revert reason of the
+    /// syscall - 0 if the event was emitted. The keys and data it emits are laid out as by
+    /// [Self::layout]. All the fields, including the data fields, must be single felts.
+    pub fn emit_function(&self, db: &dyn SemanticGroup) -> Result<Program, EventError> {
+        let field_types = self
+            .fields
+            .iter()
+            .map(|field| {
+                let ty = field.ty.format(db);
+                single_felt_type(&ty).ok_or_else(|| EventError::UnsupportedDataField {
+                    event: self.name.clone(),
+                    field: field.name.clone(),
+                    ty,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The variables of the function - the fields follow the gas builtin and system pointer.
+        let field_var = |idx: usize| idx + 2;
+        let keys = field_var(self.fields.len());
+        let data = keys + 1;
+        let tmp = data + 1;
+        let mut statements = vec![
+            format!("array_new_felt() -> ([{keys}]);"),
+            format!("felt_const_selector() -> ([{tmp}]);"),
+            format!("store_temp_felt([{tmp}]) -> ([{tmp}]);"),
+            format!("array_append_felt([{keys}], [{tmp}]) -> ([{keys}]);"),
+            format!("array_new_felt() -> ([{data}]);"),
+        ];
+        for (idx, (field, (_, conversion))) in self.fields.iter().zip(&field_types).enumerate() {
+            let var = field_var(idx);
+            if let Some(conversion) = conversion {
+                statements.push(format!("{conversion}([{var}]) -> ([{var}]);"));
+            }
+            let array = if field.is_key { keys } else { data };
+            statements.push(format!("array_append_felt([{array}], [{var}]) -> ([{array}]);"));
+        }
+        statements.extend([
+            format!("store_temp_array([{keys}]) -> ([{keys}]);"),
+            format!("store_temp_array([{data}]) -> ([{data}]);"),
+        ]);
+        // The failure branch follows the success branch - a constant and a return of 4 statements.
+        let failure = statements.len() + 1 + 5;
+        let branches = format!("fallthrough([0], [1]) {failure}([0], [1], [{tmp}])");
+        statements.extend([
+            format!("emit_event([0], [1], [{keys}], [{data}]) {{ {branches} }};"),
+            format!("felt_const_0() -> ([{tmp}]);"),
+        ]);
+        for _ in 0..2 {
+            statements.extend([
+                "store_temp_gb([0]) -> ([0]);".into(),
+                "store_temp_system([1]) -> ([1]);".into(),
+                format!("store_temp_felt([{tmp}]) -> ([{tmp}]);"),
+                format!("return([0], [1], [{tmp}]);"),
+            ]);
+        }
+
+        let field_sierra_types = field_types.iter().map(|(sierra_ty, _)| *sierra_ty);
+        let types = ["GasBuiltin", "System", "felt"]
+            .into_iter()
+            .chain(field_sierra_types.filter(|ty| *ty != "felt").unique())
+            .map(|ty| format!("type {ty} = {ty};"))
+            .chain(["type ArrayFelt = Array<felt>;".into()]);
+        let conversions = field_types.iter().filter_map(|(_, conversion)| *conversion).unique();
+        let libfuncs = [
+            format!("libfunc felt_const_selector = felt_const<{}>;", self.selector()),
+            "libfunc felt_const_0 = felt_const<0>;".into(),
+            "libfunc array_new_felt = array_new<felt>;".into(),
+            "libfunc array_append_felt = array_append<felt>;".into(),
+            "libfunc store_temp_felt = store_temp<felt>;".into(),
+            "libfunc store_temp_array = store_temp<ArrayFelt>;".into(),
+            "libfunc store_temp_gb = store_temp<GasBuiltin>;".into(),
+            "libfunc store_temp_system = store_temp<System>;".into(),
+            "libfunc emit_event = emit_event_syscall;".into(),
+        ]
+        .into_iter()
+        .chain(conversions.map(|conversion| format!("libfunc {conversion} = {conversion};")));
+        let params = field_types
+            .iter()
+            .enumerate()
+            .map(|(idx, (ty, _))| format!(", [{}]: {ty}", field_var(idx)))
+            .join("");
+        let function = format!(
+            "emit_{}@0([0]: GasBuiltin, [1]: System{params}) -> (GasBuiltin, System, felt);",
+            self.name
+        );
+        let code = types.chain(libfuncs).chain(statements).chain([function]).join("\n");
+        Ok(sierra::ProgramParser::new()
+            .parse(&code)
+            .expect("The generated emit function is a valid Sierra program."))
+    }
+}
@@ -0,0 +1,108 @@
+use indoc::indoc;
+use num_bigint::BigUint;
+use pretty_assertions::assert_eq;
+use semantic::corelib::{core_bool_ty, core_felt_ty, get_core_ty_by_name};
+use semantic::test_utils::SemanticDatabaseForTesting;
+
+use crate::abi::Contract;
+use crate::contract::starknet_keccak;
+use crate::events::{EventDefinition, EventError, EventField};
+
+#[test]
+fn test_event_abi_and_layout() {
+    let db = &SemanticDatabaseForTesting::default();
+    let event = EventDefinition::new(
+        db,
+        "Transfer".into(),
+        vec![
+            EventField { name: "amount".into(), ty: core_felt_ty(db), is_key: false },
+            EventField {
+                name: "to".into(),
+                ty: get_core_ty_by_name(db, "u128".into(), vec![]),
+                is_key: true,
+            },
+        ],
+    )
+    .unwrap();
+
+    let mut abi = Contract::default();
+    abi.add_event(db, &event);
+    assert_eq!(
+        abi.json(),
+        indoc! {
+        r#"[
+            {
+              "type": "event",
+              "name": "Transfer",
+              "inputs": [
+                {
+                  "name": "amount",
+                  "ty": "core::felt",
+                  "kind": "data"
+                },
+                {
+                  "name": "to",
+                  "ty": "core::integer::u128",
+                  "kind": "key"
+                }
+              ]
+            }
+          ]"#}
+    );
+
+    let (keys, data) =
+        event.layout(vec![vec![BigUint::from(7u32)], vec![BigUint::from(3u32)]]).unwrap();
+    assert_eq!(keys, vec![starknet_keccak(b"Transfer"), BigUint::from(3u32)]);
+    assert_eq!(data, vec![BigUint::from(7u32)]);
+    assert_eq!(
+        event.layout(vec![vec![BigUint::from(7u32)]]),
+        Err(EventError::WrongNumberOfValues { event: "Transfer".into(), expected: 2, actual: 1 })
+    );
+}
+
+#[test]
+fn test_event_validation() {
+    let db = &SemanticDatabaseForTesting::default();
+    assert_eq!(
+        EventDefinition::new(
+            db,
+            "Flag".into(),
+            vec![EventField { name: "value".into(), ty: core_bool_ty(db), is_key: true }],
+        ),
+        Err(EventError::KeyNotSingleFelt {
+            event: "Flag".into(),
+            field: "value".into(),
+            ty: "core::bool".into(),
+        })
+    );
+    assert_eq!(
+        EventDefinition::new(
+            db,
+            "Twice".into(),
+            vec![
+                EventField { name: "x".into(), ty: core_felt_ty(db), is_key: true },
+                EventField { name: "x".into(), ty: core_felt_ty(db), is_key: false },
+            ],
+        ),
+        Err(EventError::DuplicateField { event: "Twice".into(), field: "x".into() })
+    );
+}
+
+#[test]
+fn test_emit_function_validation() {
+    let db = &SemanticDatabaseForTesting::default();
+    let event = EventDefinition::new(
+        db,
+        "Flag".into(),
+        vec![EventField { name: "value".into(), ty: core_bool_ty(db), is_key: false }],
+    )
+    .unwrap();
+    assert_eq!(
+        event.emit_function(db),
+        Err(EventError::UnsupportedDataField {
+            event: "Flag".into(),
+            field: "value".into(),
+            ty: "core::bool".into(),
+        })
+    );
+}
@@ -0,0 +1,109 @@
+#[cfg(test)]
+#[path = "hint_verification_test.rs"]
+mod test;
+
+use casm::hints::structured::{parse_hint, HintParseError};
+use casm::hints::Hint;
+use thiserror::Error;
+
+use crate::casm_contract_class::CasmContractClass;
+
+/// The kinds of the hints the compiler emits for the core libfuncs, by their structured names.
+pub const CORE_HINT_KINDS: &[&str] = &[
+    "alloc_segment",
+    "alloc_segment_with_capacity",
+    "alloc_dict_felt_to",
+    "dict_felt_to_read",
+    "dict_felt_to_write",
+    "test_less_than",
+    "test_less_than_or_equal",
+    "div_mod",
+    "enter_scope",
+    "exit_scope",
+    "dict_squash",
+    "system_call",
+    "bitwise",
+];
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum HintVerificationError {
+    #[error("Invalid structured hint at pc {pc}: {error}")]
+    InvalidHint { pc: usize, error: HintParseError },
+    #[error("The hints at pc {pc} do not match their structured form.")]
+    UnaccountedHints { pc: usize },
+    #[error("Hint `{name}` at pc {pc} is not allowed.")]
+    DisallowedHint { pc: usize, name: &'static str },
+    #[error("Hint `{name}` at pc {pc} may write to memory, so it cannot be stripped.")]
+    UnstrippableHint { pc: usize, name: &'static str },
+}
+
+/// Hints attached to the instructions of a class, keyed by the pc of the instruction.
+type HintsByPc<T> = Vec<(usize, Vec<T>)>;
+
+impl CasmContractClass {
+    /// Returns the hints of the class, parsed from their structured form, along with the pcs of the
+    /// instructions they are attached to.
+    /// Fails if the hints executed by the sequencer are not exactly the structured hints, so that
+    /// no hint is left unaccounted for.
+    pub fn hints_by_pc(&self) -> Result<HintsByPc<Hint>, HintVerificationError> {
+        let hints = self
+            .structured_hints
+            .iter()
+            .map(|(pc, structured_hints)| {
+                let hints = structured_hints
+                    .iter()
+                    .map(|hint| parse_hint(hint))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|error| HintVerificationError::InvalidHint { pc: *pc, error })?;
+                Ok((*pc, hints))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let rendered_hints = render_hints(&hints, Hint::to_string);
+        if let Some(idx) = (0..std::cmp::max(self.hints.len(), rendered_hints.len()))
+            .find(|idx| self.hints.get(*idx) != rendered_hints.get(*idx))
+        {
+            let pc_at = |hints: &HintsByPc<String>| hints.get(idx).map(|(pc, _)| *pc);
+            let pc = pc_at(&self.hints).into_iter().chain(pc_at(&rendered_hints)).min().unwrap();
+            return Err(HintVerificationError::UnaccountedHints { pc });
+        }
+        Ok(hints)
+    }
+
+    /// Verifies that every hint of the class is of one of `allowed_kinds`, given by their
+    /// structured names (e.g. [CORE_HINT_KINDS]).
+    pub fn verify_hints(&self, allowed_kinds: &[&str]) -> Result<(), HintVerificationError> {
+        for (pc, hints) in self.hints_by_pc()? {
+            if let Some(hint) = hints.iter().find(|hint| !allowed_kinds.contains(&hint.name())) {
+                return Err(HintVerificationError::DisallowedHint { pc, name: hint.name() });
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the hints that are not of one of the [CORE_HINT_KINDS] (e.g. debug prints).
+    /// Fails without changing the class if such a hint may write to memory, as removing it may
+    /// change the values asserted by the instructions.
+    pub fn strip_nonessential_hints(&mut self) -> Result<(), HintVerificationError> {
+        let mut hints = self.hints_by_pc()?;
+        for (pc, pc_hints) in &mut hints {
+            for hint in pc_hints.iter() {
+                if !CORE_HINT_KINDS.contains(&hint.name()) && hint.writes_memory() {
+                    return Err(HintVerificationError::UnstrippableHint {
+                        pc: *pc,
+                        name: hint.name(),
+                    });
+                }
+            }
+            pc_hints.retain(|hint| CORE_HINT_KINDS.contains(&hint.name()));
+        }
+        hints.retain(|(_, pc_hints)| !pc_hints.is_empty());
+        self.hints = render_hints(&hints, Hint::to_string);
+        self.structured_hints = render_hints(&hints, |hint| hint.structured().to_string());
+        Ok(())
+    }
+}
+
+/// Renders each of the hints using `render`, keeping their pcs.
+fn render_hints(hints: &HintsByPc<Hint>, render: impl Fn(&Hint) -> String) -> HintsByPc<String> {
+    hints.iter().map(|(pc, hints)| (*pc, hints.iter().map(&render).collect())).collect()
+}
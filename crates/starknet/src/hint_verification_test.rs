@@ -0,0 +1,107 @@
+use casm::hints::Hint;
+use casm::operand::{CellRef, DerefOrImmediate, Register, ResOperand};
+use pretty_assertions::assert_eq;
+
+use super::{HintVerificationError, CORE_HINT_KINDS};
+use crate::casm_contract_class::CasmContractClass;
+use crate::test_utils::get_test_contract;
+
+/// Returns a class with the given hints, and no code.
+fn class_with_hints(hints: Vec<(usize, Vec<Hint>)>) -> CasmContractClass {
+    CasmContractClass {
+        hints: hints
+            .iter()
+            .map(|(pc, hints)| (*pc, hints.iter().map(Hint::to_string).collect()))
+            .collect(),
+        structured_hints: hints
+            .iter()
+            .map(|(pc, hints)| {
+                (*pc, hints.iter().map(|hint| hint.structured().to_string()).collect())
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn alloc_segment() -> Hint {
+    Hint::AllocSegment { dst: CellRef { register: Register::AP, offset: 0 } }
+}
+
+fn test_less_than() -> Hint {
+    Hint::TestLessThan {
+        lhs: DerefOrImmediate::Deref(CellRef { register: Register::FP, offset: -3 }),
+        rhs: DerefOrImmediate::from(10),
+        dst: CellRef { register: Register::AP, offset: 0 },
+    }
+}
+
+fn debug_print() -> Hint {
+    Hint::DebugPrint {
+        start: ResOperand::Deref(CellRef { register: Register::AP, offset: -2 }),
+        end: ResOperand::Deref(CellRef { register: Register::AP, offset: -1 }),
+    }
+}
+
+#[test]
+fn test_strip_debug_print() {
+    let mut class = class_with_hints(vec![
+        (0, vec![alloc_segment()]),
+        (3, vec![debug_print()]),
+        (5, vec![test_less_than(), debug_print()]),
+    ]);
+    assert_eq!(
+        class.verify_hints(CORE_HINT_KINDS),
+        Err(HintVerificationError::DisallowedHint { pc: 3, name: "debug_print" })
+    );
+
+    class.strip_nonessential_hints().unwrap();
+    assert_eq!(
+        class,
+        class_with_hints(vec![(0, vec![alloc_segment()]), (5, vec![test_less_than()])])
+    );
+    assert_eq!(class.verify_hints(CORE_HINT_KINDS), Ok(()));
+}
+
+#[test]
+fn test_core_hints_pass_untouched() {
+    let mut class = CasmContractClass::from_contract_class(get_test_contract()).unwrap();
+    assert!(!class.hints.is_empty());
+    assert_eq!(class.verify_hints(CORE_HINT_KINDS), Ok(()));
+
+    let before_stripping = serde_json::to_string(&class).unwrap();
+    class.strip_nonessential_hints().unwrap();
+    assert_eq!(serde_json::to_string(&class).unwrap(), before_stripping);
+}
+
+#[test]
+fn test_configured_allowlist() {
+    let class = class_with_hints(vec![(0, vec![alloc_segment()]), (2, vec![test_less_than()])]);
+    assert_eq!(class.verify_hints(&["alloc_segment", "test_less_than"]), Ok(()));
+    assert_eq!(
+        class.verify_hints(&["alloc_segment"]),
+        Err(HintVerificationError::DisallowedHint { pc: 2, name: "test_less_than" })
+    );
+}
+
+#[test]
+fn test_unaccounted_hints() {
+    // A hint executed by the sequencer, missing from the structured hints.
+    let mut class = class_with_hints(vec![(0, vec![alloc_segment()])]);
+    class.hints.push((4, vec![alloc_segment().to_string()]));
+    assert_eq!(
+        class.verify_hints(CORE_HINT_KINDS),
+        Err(HintVerificationError::UnaccountedHints { pc: 4 })
+    );
+
+    // A hint whose code differs from its structured form.
+    let mut class = class_with_hints(vec![(0, vec![alloc_segment()]), (2, vec![test_less_than()])]);
+    class.hints[1].1[0] = debug_print().to_string();
+    assert_eq!(
+        class.verify_hints(CORE_HINT_KINDS),
+        Err(HintVerificationError::UnaccountedHints { pc: 2 })
+    );
+    assert_eq!(
+        class.strip_nonessential_hints(),
+        Err(HintVerificationError::UnaccountedHints { pc: 2 })
+    );
+}
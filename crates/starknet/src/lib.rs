@@ -2,7 +2,14 @@ pub mod abi;
 pub mod casm_contract_class;
 pub mod contract;
 pub mod contract_class;
+pub mod entry_points;
+pub mod error_codes;
+pub mod events;
+pub mod hint_verification;
 pub mod plugin;
+pub mod referenced_constants;
+pub mod storage_layout;
+pub mod unchecked_storage;
 
 #[cfg(test)]
 pub mod test_utils;
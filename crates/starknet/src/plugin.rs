@@ -114,13 +114,13 @@ fn handle_storage_struct(db: &dyn SyntaxGroup, struct_ast: ast::ItemStruct) -> S
 
         let generated_submodule = quote! {
             mod $name {
-                func read(ref system: System) -> felt {
+                func read(ref system: System) -> Result::<felt, felt> {
                     starknet::storage_read_syscall(
-                        system, starknet::storage_address_const::<$(address.clone())>())
+                        system, 0, starknet::storage_address_const::<$(address.clone())>())
                 }
                 func write(ref system: System, value: felt) -> Result::<(), felt> {
                     starknet::storage_write_syscall(
-                        system, starknet::storage_address_const::<$address>(), value)
+                        system, 0, starknet::storage_address_const::<$address>(), value)
                 }
             }
         };
@@ -0,0 +1,166 @@
+use std::collections::{HashMap, HashSet};
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sierra::extensions::consts::ConstGenLibFunc;
+use sierra::extensions::function_call::FunctionCallLibFunc;
+use sierra::extensions::starknet::interoperability::ContractAddressConstLibFuncWrapped;
+use sierra::extensions::{args_as_single_value, NamedLibFunc};
+use sierra::ids::{ConcreteLibFuncId, FunctionId};
+use sierra::program::{GenericArg, LibFuncDeclaration, Program, Statement, StatementIdx};
+use thiserror::Error;
+
+use crate::casm_contract_class::{deserialize_big_uint, serialize_big_uint};
+use crate::contract_class::ContractEntryPoints;
+
+#[cfg(test)]
+#[path = "referenced_constants_test.rs"]
+mod test;
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum ReferencedConstantsError {
+    #[error("Entry point function #{0} does not exist.")]
+    MissingEntryPoint(usize),
+    #[error("Function `{0}` does not exist.")]
+    MissingFunction(FunctionId),
+    #[error("Libfunc `{0}` is not declared.")]
+    MissingLibFunc(ConcreteLibFuncId),
+    #[error("Statement #{0} does not exist.")]
+    StatementOutOfBounds(StatementIdx),
+    #[error("Libfunc `{0}` has invalid generic arguments.")]
+    InvalidGenericArgs(ConcreteLibFuncId),
+}
+
+/// The constants hardcoded in the code reachable from each entry point of a contract.
+#[derive(Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ReferencedConstants {
+    /// The constants per entry point, in the order of the entry points in the contract.
+    pub entry_points: Vec<EntryPointConstants>,
+}
+
+/// The constants hardcoded in the code reachable from a single entry point.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryPointConstants {
+    /// The idx of the user function declaration of the entry point in the sierra program.
+    pub function_idx: usize,
+    /// The referenced constants, sorted by kind, value and declaring function.
+    pub constants: Vec<ReferencedConstant>,
+}
+
+/// The kind of a hardcoded constant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstantKind {
+    /// A value created by `contract_address_const`.
+    ContractAddress,
+}
+
+/// A constant hardcoded in a function of the contract.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ReferencedConstant {
+    pub kind: ConstantKind,
+    #[serde(serialize_with = "serialize_big_uint", deserialize_with = "deserialize_big_uint")]
+    pub value: BigUint,
+    /// The name of the function whose code creates the constant.
+    pub function: String,
+}
+
+impl ReferencedConstants {
+    /// Collects the constants reachable from each of the given entry points of the program.
+    pub fn new(
+        program: &Program,
+        entry_points: &ContractEntryPoints,
+    ) -> Result<Self, ReferencedConstantsError> {
+        let collector = ConstantsCollector::new(program);
+        Ok(Self {
+            entry_points: entry_points
+                .external
+                .iter()
+                .chain(&entry_points.l1_handler)
+                .chain(&entry_points.constructor)
+                .map(|entry_point| {
+                    Ok(EntryPointConstants {
+                        function_idx: entry_point.function_idx,
+                        constants: collector.collect(entry_point.function_idx)?,
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Helper for walking the statements reachable from a function.
+struct ConstantsCollector<'a> {
+    program: &'a Program,
+    libfuncs: HashMap<&'a ConcreteLibFuncId, &'a LibFuncDeclaration>,
+    functions: HashMap<&'a FunctionId, StatementIdx>,
+}
+impl<'a> ConstantsCollector<'a> {
+    fn new(program: &'a Program) -> Self {
+        Self {
+            program,
+            libfuncs: program.libfunc_declarations.iter().map(|decl| (&decl.id, decl)).collect(),
+            functions: program.funcs.iter().map(|func| (&func.id, func.entry_point)).collect(),
+        }
+    }
+
+    /// Returns the constants created by the statements reachable from the function at
+    /// `function_idx`, including the statements of the functions it calls.
+    fn collect(
+        &self,
+        function_idx: usize,
+    ) -> Result<Vec<ReferencedConstant>, ReferencedConstantsError> {
+        let entry_function = self
+            .program
+            .funcs
+            .get(function_idx)
+            .ok_or(ReferencedConstantsError::MissingEntryPoint(function_idx))?;
+        let mut constants = vec![];
+        let mut visited = HashSet::new();
+        // The statements left to visit, with the function they belong to.
+        let mut stack = vec![(entry_function.entry_point, &entry_function.id)];
+        while let Some((statement_idx, function_id)) = stack.pop() {
+            if !visited.insert(statement_idx) {
+                continue;
+            }
+            let invocation = match self.program.get_statement(&statement_idx) {
+                Some(Statement::Invocation(invocation)) => invocation,
+                Some(Statement::Return(_)) => continue,
+                None => return Err(ReferencedConstantsError::StatementOutOfBounds(statement_idx)),
+            };
+            let libfunc = self.libfuncs.get(&invocation.libfunc_id).ok_or_else(|| {
+                ReferencedConstantsError::MissingLibFunc(invocation.libfunc_id.clone())
+            })?;
+            let generic_id = &libfunc.long_id.generic_id;
+            let invalid_args = || ReferencedConstantsError::InvalidGenericArgs(libfunc.id.clone());
+            if *generic_id == ContractAddressConstLibFuncWrapped::ID {
+                let value = args_as_single_value(&libfunc.long_id.generic_args)
+                    .ok()
+                    .and_then(|value| value.to_biguint())
+                    .ok_or_else(invalid_args)?;
+                constants.push(ReferencedConstant {
+                    kind: ConstantKind::ContractAddress,
+                    value,
+                    function: function_id.to_string(),
+                });
+            } else if *generic_id == FunctionCallLibFunc::ID {
+                let callee = match &libfunc.long_id.generic_args[..] {
+                    [GenericArg::UserFunc(callee)] => callee,
+                    _ => return Err(invalid_args()),
+                };
+                let (callee, entry_point) = self
+                    .functions
+                    .get_key_value(callee)
+                    .ok_or_else(|| ReferencedConstantsError::MissingFunction(callee.clone()))?;
+                stack.push((*entry_point, *callee));
+            }
+            for branch in &invocation.branches {
+                stack.push((statement_idx.next(&branch.target), function_id));
+            }
+        }
+        constants.sort();
+        constants.dedup();
+        Ok(constants)
+    }
+}
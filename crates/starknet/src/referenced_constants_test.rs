@@ -0,0 +1,130 @@
+use indoc::indoc;
+use num_bigint::BigUint;
+use pretty_assertions::assert_eq;
+use sierra::ProgramParser;
+
+use crate::contract_class::{compile_path, ContractEntryPoint, ContractEntryPoints};
+use crate::referenced_constants::{
+    ConstantKind, EntryPointConstants, ReferencedConstant, ReferencedConstants,
+    ReferencedConstantsError,
+};
+use crate::test_utils::get_example_file_path;
+
+fn contract_address(value: u64, function: &str) -> ReferencedConstant {
+    ReferencedConstant {
+        kind: ConstantKind::ContractAddress,
+        value: value.into(),
+        function: function.into(),
+    }
+}
+
+fn external_entry_points(function_indices: &[usize]) -> ContractEntryPoints {
+    ContractEntryPoints {
+        external: function_indices
+            .iter()
+            .map(|function_idx| ContractEntryPoint {
+                selector: BigUint::from(*function_idx),
+                function_idx: *function_idx,
+            })
+            .collect(),
+        l1_handler: vec![],
+        constructor: vec![],
+    }
+}
+
+#[test]
+fn test_reachable_constants() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type ContractAddress = ContractAddress;
+            type NonZeroFelt = NonZero<felt>;
+
+            libfunc oracle_address = contract_address_const<7>;
+            libfunc token_address = contract_address_const<9>;
+            libfunc unused_address = contract_address_const<11>;
+            libfunc drop_address = drop<ContractAddress>;
+            libfunc felt_jump_nz = felt_jump_nz;
+            libfunc drop_nz_felt = drop<NonZeroFelt>;
+            libfunc call_token = function_call<user@token>;
+
+            felt_jump_nz([1]) { fallthrough() 4([2]) };     // #0
+            oracle_address() -> ([3]);                      // #1
+            drop_address([3]) -> ();                        // #2
+            return ();                                      // #3
+            drop_nz_felt([2]) -> ();                        // #4
+            call_token() -> ();                             // #5
+            return ();                                      // #6
+
+            oracle_address() -> ([1]);                      // #7
+            drop_address([1]) -> ();                        // #8
+            return ();                                      // #9
+
+            token_address() -> ([1]);                       // #10
+            drop_address([1]) -> ();                        // #11
+            return ();                                      // #12
+            unused_address() -> ([1]);                      // #13
+            drop_address([1]) -> ();                        // #14
+            return ();                                      // #15
+
+            branching@0([1]: felt) -> ();
+            oracle_only@7() -> ();
+            token@10() -> ();
+        "})
+        .unwrap();
+    assert_eq!(
+        ReferencedConstants::new(&program, &external_entry_points(&[0, 1, 2])),
+        Ok(ReferencedConstants {
+            entry_points: vec![
+                EntryPointConstants {
+                    function_idx: 0,
+                    constants: vec![contract_address(7, "branching"), contract_address(9, "token")],
+                },
+                EntryPointConstants {
+                    function_idx: 1,
+                    constants: vec![contract_address(7, "oracle_only")],
+                },
+                EntryPointConstants {
+                    function_idx: 2,
+                    constants: vec![contract_address(9, "token")],
+                },
+            ],
+        })
+    );
+}
+
+#[test]
+fn test_missing_entry_point() {
+    let program = ProgramParser::new().parse("return();\nfoo@0() -> ();").unwrap();
+    assert_eq!(
+        ReferencedConstants::new(&program, &external_entry_points(&[1])),
+        Err(ReferencedConstantsError::MissingEntryPoint(1))
+    );
+}
+
+#[test]
+fn test_contract_referenced_constants() {
+    let contract = compile_path(&get_example_file_path("hardcoded_addresses.cairo"), true).unwrap();
+    let mut constants_by_entry_point = contract
+        .referenced_constants
+        .entry_points
+        .iter()
+        .map(|entry_point| {
+            (
+                contract.sierra_program.funcs[entry_point.function_idx].id.to_string(),
+                entry_point.constants.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    constants_by_entry_point.sort();
+    let module = "hardcoded_addresses::hardcoded_addresses";
+    let oracle = contract_address(0x1234, &format!("{module}::HardcodedAddresses::oracle"));
+    let fee_token = contract_address(0x5678, &format!("{module}::HardcodedAddresses::fee_token"));
+    assert_eq!(
+        constants_by_entry_point,
+        vec![
+            (format!("{module}::__external::pay_fee"), vec![oracle.clone(), fee_token]),
+            (format!("{module}::__external::read_price"), vec![oracle]),
+        ]
+    );
+}
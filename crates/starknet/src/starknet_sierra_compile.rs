@@ -3,7 +3,7 @@ use std::fs;
 use anyhow::Context;
 use clap::Parser;
 use starknet::casm_contract_class::CasmContractClass;
-use starknet::contract_class::ContractClass;
+use starknet::contract_class::{ArtifactMode, ContractClass};
 
 /// Command line args parser.
 /// Exits with 0/1 if the input is formatted correctly/incorrectly.
@@ -14,6 +14,9 @@ struct Args {
     file: String,
     /// The output file name (default: stdout).
     output: Option<String>,
+    /// Keeps the debug print hints, which are not supported by the sequencer.
+    #[arg(long, default_value_t = false)]
+    debug: bool,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -25,9 +28,11 @@ fn main() -> anyhow::Result<()> {
     )
     .with_context(|| "deserialization Failed.")?;
 
+    let artifact_mode = if args.debug { ArtifactMode::Debug } else { ArtifactMode::Release };
     // TODO(ilya): Fix entry points.
-    let casm_contract = CasmContractClass::from_contract_class(contract_class)
-        .with_context(|| "Compilation failed.")?;
+    let casm_contract =
+        CasmContractClass::from_contract_class_with_mode(contract_class, artifact_mode)
+            .with_context(|| "Compilation failed.")?;
 
     let res =
         serde_json::to_string_pretty(&casm_contract).with_context(|| "Serialization failed.")?;
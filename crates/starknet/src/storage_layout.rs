@@ -0,0 +1,108 @@
+use std::fmt::{Display, Formatter};
+
+use defs::ids::ModuleItemId;
+use num_bigint::BigUint;
+use semantic::db::SemanticGroup;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::casm_contract_class::{deserialize_big_uint, serialize_big_uint};
+use crate::contract::{starknet_keccak, ContractDeclaration};
+
+#[cfg(test)]
+#[path = "storage_layout_test.rs"]
+mod test;
+
+/// The name of the struct that holds the storage variables of a contract.
+pub const STORAGE_STRUCT_NAME: &str = "Storage";
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum StorageLayoutError {
+    #[error("Compilation error.")]
+    CompilationError,
+    #[error("Storage variables `{first}` and `{second}` have colliding addresses.")]
+    AddressCollision { first: String, second: String },
+}
+
+/// The storage layout of a contract.
+#[derive(Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StorageLayout {
+    /// The storage variables, sorted by address.
+    pub variables: Vec<StorageVariable>,
+}
+
+/// A single storage variable, occupying `size` consecutive slots starting at `address`.
+// TODO(ilya): Add the hash formula of mappings once they are supported by the plugin.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageVariable {
+    pub name: String,
+    pub ty: String,
+    #[serde(serialize_with = "serialize_big_uint", deserialize_with = "deserialize_big_uint")]
+    pub address: BigUint,
+    pub size: usize,
+}
+
+impl StorageLayout {
+    /// Creates a storage layout from the given variables, validating that no two variables
+    /// occupy the same slot.
+    pub fn new(mut variables: Vec<StorageVariable>) -> Result<Self, StorageLayoutError> {
+        variables.sort_by(|a, b| a.address.cmp(&b.address));
+        for (prev, curr) in variables.iter().zip(variables.iter().skip(1)) {
+            if prev.address.clone() + prev.size > curr.address {
+                return Err(StorageLayoutError::AddressCollision {
+                    first: prev.name.clone(),
+                    second: curr.name.clone(),
+                });
+            }
+        }
+        Ok(Self { variables })
+    }
+
+    /// Creates the storage layout of the given contract from its storage struct.
+    pub fn from_contract(
+        db: &dyn SemanticGroup,
+        contract: &ContractDeclaration,
+    ) -> Result<Self, StorageLayoutError> {
+        let module_items = db
+            .module_items(contract.module_id())
+            .map_err(|_| StorageLayoutError::CompilationError)?;
+        let struct_id = match module_items.items.get(STORAGE_STRUCT_NAME) {
+            Some(ModuleItemId::Struct(struct_id)) => struct_id,
+            _ => return Ok(Self::default()),
+        };
+        let members =
+            db.struct_members(*struct_id).map_err(|_| StorageLayoutError::CompilationError)?;
+        Self::new(
+            members
+                .iter()
+                .map(|(name, member)| StorageVariable {
+                    name: name.to_string(),
+                    ty: member.ty.format(db),
+                    address: starknet_keccak(name.as_bytes()),
+                    // The plugin currently stores every variable in a single slot.
+                    size: 1,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Display for StorageLayout {
+    /// Formats the layout as a text table.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name_width =
+            self.variables.iter().map(|var| var.name.len()).chain(["name".len()]).max().unwrap();
+        let ty_width =
+            self.variables.iter().map(|var| var.ty.len()).chain(["type".len()]).max().unwrap();
+        writeln!(f, "{:name_width$} | {:ty_width$} | size | address", "name", "type")?;
+        for var in &self.variables {
+            writeln!(
+                f,
+                "{:name_width$} | {:ty_width$} | {:4} | 0x{:x}",
+                var.name, var.ty, var.size, var.address
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use indoc::indoc;
+use num_bigint::BigUint;
+use pretty_assertions::assert_eq;
+
+use crate::storage_layout::{StorageLayout, StorageLayoutError, StorageVariable};
+use crate::test_utils::get_test_contract;
+
+fn variable(name: &str, address: u64, size: usize) -> StorageVariable {
+    StorageVariable { name: name.into(), ty: "core::felt".into(), address: address.into(), size }
+}
+
+#[test]
+fn test_contract_storage_layout() {
+    let storage_layout = get_test_contract().storage_layout;
+    assert_eq!(
+        storage_layout,
+        StorageLayout {
+            variables: vec![StorageVariable {
+                name: "my_storage_var".into(),
+                ty: "core::felt".into(),
+                address: BigUint::parse_bytes(
+                    b"1275130f95dda36bcbb6e9d28796c1d7e10b6e9fd5ed083e0ede4b12f613528",
+                    16
+                )
+                .unwrap(),
+                size: 1,
+            }],
+        }
+    );
+    assert_eq!(
+        storage_layout.to_string(),
+        indoc! {"
+            name           | type       | size | address
+            my_storage_var | core::felt |    1 | 0x1275130f95dda36bcbb6e9d28796c1d7e10b6e9fd5ed083e0ede4b12f613528
+        "}
+    );
+}
+
+#[test]
+fn test_sorted_by_address() {
+    let storage_layout =
+        StorageLayout::new(vec![variable("b", 5, 2), variable("a", 3, 2), variable("c", 7, 1)])
+            .unwrap();
+    assert_eq!(
+        storage_layout.variables.iter().map(|var| var.name.as_str()).collect::<Vec<_>>(),
+        vec!["a", "b", "c"]
+    );
+}
+
+#[test]
+fn test_address_collision() {
+    assert_eq!(
+        StorageLayout::new(vec![variable("a", 3, 1), variable("b", 3, 1)]),
+        Err(StorageLayoutError::AddressCollision { first: "a".into(), second: "b".into() })
+    );
+    assert_eq!(
+        StorageLayout::new(vec![variable("b", 5, 1), variable("a", 3, 3)]),
+        Err(StorageLayoutError::AddressCollision { first: "a".into(), second: "b".into() })
+    );
+}
@@ -1,6 +1,9 @@
 use std::path::PathBuf;
 
-use crate::contract_class::compile_path;
+use crate::casm_contract_class::CasmContractClass;
+use crate::contract_class::{
+    compile_path, compile_path_with_config, ArtifactMode, ContractCompilationConfig,
+};
 
 /// Returns a path to example contract that matches `name`.
 pub fn get_example_file_path(file_name: &str) -> PathBuf {
@@ -15,3 +18,36 @@ pub fn get_test_contract() -> crate::contract_class::ContractClass {
     let replace_ids = true;
     compile_path(&path, replace_ids).unwrap()
 }
+
+/// Compiles the example contract that matches `file_name` in both artifact modes, and asserts that
+/// the compiled code is identical - as debug information must never affect the code.
+pub fn assert_code_independent_of_artifact_mode(
+    file_name: &str,
+    unchecked_storage_allowlist: &[&str],
+) {
+    let path = get_example_file_path(file_name);
+    let compile = |artifact_mode| {
+        let config = ContractCompilationConfig {
+            unchecked_storage_allowlist: unchecked_storage_allowlist
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            artifact_mode,
+            ..Default::default()
+        };
+        let replace_ids = true;
+        let contract_class = compile_path_with_config(&path, replace_ids, &config).unwrap();
+        CasmContractClass::from_contract_class_with_mode(contract_class, artifact_mode).unwrap()
+    };
+    let debug = compile(ArtifactMode::Debug);
+    let release = compile(ArtifactMode::Release);
+    assert_eq!(debug.bytecode, release.bytecode, "Bytecode of {file_name} depends on the mode.");
+    assert_eq!(
+        debug.entry_points_by_type, release.entry_points_by_type,
+        "Entry points of {file_name} depend on the mode."
+    );
+    assert!(
+        debug.debug_info.is_some() && release.debug_info.is_none(),
+        "The debug info section of {file_name} must be kept only in debug artifacts."
+    );
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use sierra::extensions::starknet::storage::{
+    StorageReadMultiLibFunc, StorageReadUncheckedLibFunc, StorageWriteUncheckedLibFunc,
+};
+use sierra::extensions::NamedLibFunc;
+use sierra::ids::ConcreteLibFuncId;
+use sierra::program::{Program, Statement};
+use thiserror::Error;
+
+#[cfg(test)]
+#[path = "unchecked_storage_test.rs"]
+mod test;
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum UncheckedStorageError {
+    #[error(
+        "Function `{0}` uses unchecked storage access, but is not allowed to by the compiler \
+         configuration."
+    )]
+    NotAllowed(String),
+}
+
+/// The functions of a contract that use unchecked storage access, recorded in the contract class
+/// for auditability.
+#[derive(Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UncheckedStorageAccess {
+    /// The names of the functions using unchecked storage libfuncs, sorted - where
+    /// `storage_read_multi` counts as unchecked, as it reads values of any layout of felts.
+    pub functions: Vec<String>,
+}
+
+impl UncheckedStorageAccess {
+    /// Collects the functions of `program` that use unchecked storage access.
+    /// Fails on the first of them, by name, that is not matched by `allowlist` - see [is_allowed].
+    ///
+    /// The functions of `program` are expected to have their debug names.
+    pub fn new(program: &Program, allowlist: &[String]) -> Result<Self, UncheckedStorageError> {
+        let unchecked_libfuncs: Vec<&ConcreteLibFuncId> = program
+            .libfunc_declarations
+            .iter()
+            .filter(|decl| {
+                [
+                    StorageReadUncheckedLibFunc::ID,
+                    StorageWriteUncheckedLibFunc::ID,
+                    StorageReadMultiLibFunc::ID,
+                ]
+                .contains(&decl.long_id.generic_id)
+            })
+            .map(|decl| &decl.id)
+            .collect();
+        let mut entry_points: Vec<_> =
+            program.funcs.iter().map(|func| (func.entry_point.0, &func.id)).collect();
+        entry_points.sort();
+
+        let mut functions = vec![];
+        for (idx, statement) in program.statements.iter().enumerate() {
+            let invocation = match statement {
+                Statement::Invocation(invocation) => invocation,
+                Statement::Return(_) => continue,
+            };
+            if !unchecked_libfuncs.contains(&&invocation.libfunc_id) {
+                continue;
+            }
+            // Functions are laid out contiguously, so a statement belongs to the function with
+            // the last entry point not after it. Statements before the first function are
+            // unreachable.
+            let function_pos = entry_points.partition_point(|(entry_point, _)| *entry_point <= idx);
+            let function = match function_pos.checked_sub(1) {
+                Some(pos) => entry_points[pos].1.to_string(),
+                None => continue,
+            };
+            functions.push(function);
+        }
+        functions.sort();
+        functions.dedup();
+        if let Some(function) = functions.iter().find(|function| !is_allowed(function, allowlist)) {
+            return Err(UncheckedStorageError::NotAllowed(function.clone()));
+        }
+        Ok(Self { functions })
+    }
+}
+
+/// Returns true if `function` is matched by an entry of `allowlist` - either the full name of the
+/// function, or one of the modules containing it.
+pub fn is_allowed(function: &str, allowlist: &[String]) -> bool {
+    allowlist.iter().any(|allowed| {
+        function == allowed
+            || function.strip_prefix(allowed.as_str()).map_or(false, |rest| rest.starts_with("::"))
+    })
+}
@@ -0,0 +1,106 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use sierra::ProgramParser;
+use test_case::test_case;
+
+use crate::contract_class::{compile_path_with_config, ContractCompilationConfig};
+use crate::test_utils::get_example_file_path;
+use crate::unchecked_storage::{is_allowed, UncheckedStorageAccess, UncheckedStorageError};
+
+#[test_case("a::b::foo", &["a::b::foo"], true; "exact")]
+#[test_case("a::b::foo", &["a::b"], true; "module")]
+#[test_case("a::b::foo", &["a::b::f"], false; "name prefix")]
+#[test_case("a::b::foo", &["a::b::foo::bar"], false; "inner")]
+#[test_case("a::b::foo", &[], false; "empty")]
+fn test_is_allowed(function: &str, allowlist: &[&str], expected: bool) {
+    let allowlist: Vec<String> = allowlist.iter().map(|s| s.to_string()).collect();
+    assert_eq!(is_allowed(function, &allowlist), expected);
+}
+
+#[test]
+fn test_collect_functions() {
+    let program = ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type GasBuiltin = GasBuiltin;
+            type System = System;
+            type StorageAddress = StorageAddress;
+            type Pair = Struct<ut@Pair, felt, felt>;
+
+            libfunc address = storage_address_const<1>;
+            libfunc domain = felt_const<0>;
+            libfunc read_unchecked = storage_read_unchecked;
+            libfunc read = storage_read_syscall;
+            libfunc read_multi = storage_read_multi<Pair>;
+
+            domain() -> ([2]);                                                        // #0
+            address() -> ([3]);                                                       // #1
+            read([0], [1], [2], [3]) { fallthrough([0], [1], [4]) 3([0], [1], [4]) }; // #2
+            return([0], [1], [4]);                                                    // #3
+
+            domain() -> ([2]);                                                        // #4
+            address() -> ([3]);                                                       // #5
+            read_unchecked([0], [1], [2], [3]) -> ([0], [1], [4]);                    // #6
+            return([0], [1], [4]);                                                    // #7
+
+            domain() -> ([2]);                                                        // #8
+            address() -> ([3]);                                                       // #9
+            read_multi([0], [1], [2], [3]) {                                          // #10
+                fallthrough([0], [1], [4]) 11([0], [1], [5], [4])
+            };
+            return([0], [1], [4]);                                                    // #11
+
+            checked@0([0]: GasBuiltin, [1]: System) -> (GasBuiltin, System, felt);
+            unchecked@4([0]: GasBuiltin, [1]: System) -> (GasBuiltin, System, felt);
+            multi@8([0]: GasBuiltin, [1]: System) -> (GasBuiltin, System, Pair);
+        "})
+        .unwrap();
+    assert_eq!(
+        UncheckedStorageAccess::new(&program, &[]),
+        Err(UncheckedStorageError::NotAllowed("multi".into()))
+    );
+    assert_eq!(
+        UncheckedStorageAccess::new(&program, &["unchecked".into()]),
+        Err(UncheckedStorageError::NotAllowed("multi".into()))
+    );
+    assert_eq!(
+        UncheckedStorageAccess::new(&program, &["multi".into(), "unchecked".into()]),
+        Ok(UncheckedStorageAccess { functions: vec!["multi".into(), "unchecked".into()] })
+    );
+}
+
+#[test]
+fn test_contract_rejected_by_default() {
+    let error = compile_path_with_config(
+        &get_example_file_path("unchecked_storage.cairo"),
+        true,
+        &ContractCompilationConfig::default(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.downcast::<UncheckedStorageError>().unwrap(),
+        UncheckedStorageError::NotAllowed(
+            "unchecked_storage::unchecked_storage::TrustedToken::get_fee".into()
+        )
+    );
+}
+
+#[test]
+fn test_contract_allowed() {
+    let module = "unchecked_storage::unchecked_storage::TrustedToken";
+    let contract = compile_path_with_config(
+        &get_example_file_path("unchecked_storage.cairo"),
+        false,
+        &ContractCompilationConfig {
+            unchecked_storage_allowlist: vec![module.into()],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        contract.unchecked_storage_access,
+        UncheckedStorageAccess {
+            functions: vec![format!("{module}::get_fee"), format!("{module}::set_fee")],
+        }
+    );
+}
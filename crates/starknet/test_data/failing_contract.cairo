@@ -0,0 +1,8 @@
+#[contract]
+mod FailingContract {
+    #[external]
+    func withdraw(ref system: System, amount: felt) -> felt {
+        assert(amount != 0, 'ZERO_AMOUNT');
+        amount
+    }
+}
@@ -0,0 +1,23 @@
+#[contract]
+mod HardcodedAddresses {
+    func oracle() -> starknet::ContractAddress {
+        starknet::contract_address_const::<0x1234>()
+    }
+
+    func fee_token() -> starknet::ContractAddress {
+        starknet::contract_address_const::<0x5678>()
+    }
+
+    #[external]
+    func read_price(ref system: System, arg: felt) -> felt {
+        let oracle_address = oracle();
+        arg
+    }
+
+    #[external]
+    func pay_fee(ref system: System, arg: felt) -> felt {
+        let oracle_address = oracle();
+        let fee_token_address = fee_token();
+        arg
+    }
+}
@@ -8,7 +8,10 @@ mod TestContract {
 
     #[external]
     func test(ref system: System, ref arg: felt, arg1: felt, arg2: felt) -> felt {
-        let x = super::my_storage_var::read(system);
+        let x = match super::my_storage_var::read(system) {
+            Result::Ok(x) => x,
+            Result::Err(revert_reason) => 0,
+        };
         super::my_storage_var::write(system, x + 1);
         x + internal_func(system)
     }
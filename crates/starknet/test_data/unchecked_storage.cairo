@@ -0,0 +1,12 @@
+#[contract]
+mod TrustedToken {
+    #[external]
+    func set_fee(ref system: System, fee: felt) {
+        starknet::storage_write_unchecked(system, 0, starknet::storage_address_const::<0x1>(), fee);
+    }
+
+    #[external]
+    func get_fee(ref system: System) -> felt {
+        starknet::storage_read_unchecked(system, 0, starknet::storage_address_const::<0x1>())
+    }
+}
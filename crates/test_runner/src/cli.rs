@@ -14,13 +14,13 @@ use defs::ids::{FreeFunctionId, GenericFunctionId, ModuleItemId};
 use diagnostics::ToOption;
 use filesystem::ids::CrateId;
 use itertools::Itertools;
-use num_bigint::BigInt;
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use runner::{RunResultValue, SierraCasmRunner};
 use semantic::db::SemanticGroup;
 use semantic::{ConcreteFunction, FunctionLongId};
 use sierra_generator::db::SierraGenGroup;
 use sierra_generator::replace_ids::replace_sierra_ids_in_program;
+use utils::short_string;
 
 /// Command line args parser.
 /// Exits with 0/1 if the input is formatted correctly/incorrectly.
@@ -96,7 +96,7 @@ fn main() -> anyhow::Result<()> {
                 RunResultValue::Panic(values) => {
                     print!("panicked with [");
                     for value in &values {
-                        match as_cairo_short_string(value) {
+                        match short_string::to_str(value) {
                             Some(as_string) => print!("{value} ('{as_string}'), "),
                             None => print!("{value}, "),
                         }
@@ -116,22 +116,6 @@ fn main() -> anyhow::Result<()> {
     }
 }
 
-/// Converts a bigint representing a felt to a Cairo short-string.
-fn as_cairo_short_string(value: &BigInt) -> Option<String> {
-    let mut as_string = String::default();
-    let mut is_end = false;
-    for byte in value.to_bytes_be().1 {
-        if byte == 0 {
-            is_end = true;
-        } else if is_end || !byte.is_ascii() {
-            return None;
-        } else {
-            as_string.push(byte as char);
-        }
-    }
-    Some(as_string)
-}
-
 /// Summary data of the ran tests.
 struct TestsSummary {
     passed: Vec<String>,
@@ -0,0 +1,85 @@
+use std::fmt;
+
+#[cfg(test)]
+#[path = "diagnostic_test.rs"]
+mod test;
+
+/// The severity of a diagnostic.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A diagnostic reported by one of the compilation stages, in a shape shared by all of them.
+/// The error types of the compiler crates convert into it, so a driver can present them uniformly.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// A stable code identifying the kind of the diagnostic, e.g. `E0042`.
+    pub code: &'static str,
+    pub message: String,
+    /// The index of the Sierra statement the diagnostic refers to, if any.
+    pub statement_idx: Option<usize>,
+    /// The id of the function the diagnostic refers to, if any.
+    pub function: Option<String>,
+    /// The offset in the compiled bytecode the diagnostic refers to, if any.
+    pub pc: Option<usize>,
+}
+impl Diagnostic {
+    /// Creates an error diagnostic, without location information.
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+            statement_idx: None,
+            function: None,
+            pc: None,
+        }
+    }
+
+    /// Creates a warning diagnostic, without location information.
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, ..Self::error(code, message) }
+    }
+
+    pub fn with_statement(self, statement_idx: usize) -> Self {
+        Self { statement_idx: Some(statement_idx), ..self }
+    }
+
+    pub fn with_function(self, function: impl fmt::Display) -> Self {
+        Self { function: Some(function.to_string()), ..self }
+    }
+
+    pub fn with_pc(self, pc: usize) -> Self {
+        Self { pc: Some(pc), ..self }
+    }
+}
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+        let mut locations = vec![];
+        if let Some(function) = &self.function {
+            locations.push(format!("in {function}"));
+        }
+        if let Some(statement_idx) = self.statement_idx {
+            locations.push(format!("#{statement_idx}"));
+        }
+        if let Some(pc) = self.pc {
+            locations.push(format!("pc {pc}"));
+        }
+        if !locations.is_empty() {
+            write!(f, " ({})", locations.join(", "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+use test_case::test_case;
+
+use super::Diagnostic;
+
+#[test_case(Diagnostic::error("E0001", "Bad."), "error[E0001]: Bad."; "no_location")]
+#[test_case(
+    Diagnostic::warning("E0002", "Odd.").with_statement(4),
+    "warning[E0002]: Odd. (#4)";
+    "statement"
+)]
+#[test_case(
+    Diagnostic::error("E0003", "Bad.").with_pc(7).with_statement(2).with_function("foo"),
+    "error[E0003]: Bad. (in foo, #2, pc 7)";
+    "all_locations"
+)]
+fn test_display(diagnostic: Diagnostic, expected: &str) {
+    assert_eq!(diagnostic.to_string(), expected);
+}
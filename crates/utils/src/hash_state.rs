@@ -0,0 +1,52 @@
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::hash::{BuildHasher, Hasher};
+
+#[cfg(feature = "testing")]
+thread_local! {
+    static HASH_SEED: std::cell::Cell<Option<u64>> = std::cell::Cell::new(None);
+}
+
+/// Sets the seed used by every [HashState] subsequently created on the current thread.
+/// `None` restores the default random keys.
+///
+/// Used by tests to check that compilation outputs do not depend on the iteration order of the hash
+/// maps created along the way.
+#[cfg(feature = "testing")]
+pub fn set_hash_seed(seed: Option<u64>) {
+    HASH_SEED.with(|cell| cell.set(seed));
+}
+
+/// The hasher builder used by [UnorderedHashMap](crate::unordered_hash_map::UnorderedHashMap) and
+/// [UnorderedHashSet](crate::unordered_hash_set::UnorderedHashSet).
+/// Uses random keys, unless a seed was set through `set_hash_seed` (available with the `testing`
+/// feature).
+#[derive(Clone, Debug)]
+pub enum HashState {
+    Random(RandomState),
+    Seeded(u64),
+}
+
+impl Default for HashState {
+    fn default() -> Self {
+        #[cfg(feature = "testing")]
+        if let Some(seed) = HASH_SEED.with(|cell| cell.get()) {
+            return Self::Seeded(seed);
+        }
+        Self::Random(RandomState::new())
+    }
+}
+
+impl BuildHasher for HashState {
+    type Hasher = DefaultHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        match self {
+            Self::Random(state) => state.build_hasher(),
+            Self::Seeded(seed) => {
+                let mut hasher = DefaultHasher::new();
+                hasher.write_u64(*seed);
+                hasher
+            }
+        }
+    }
+}
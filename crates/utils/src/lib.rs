@@ -2,10 +2,13 @@ use std::fmt;
 
 pub mod casts;
 pub mod collection_arithmetics;
+pub mod diagnostic;
 pub mod extract_matches;
+pub mod hash_state;
 pub mod logging;
 pub mod ordered_hash_map;
 pub mod ordered_hash_set;
+pub mod short_string;
 pub mod strongly_connected_components;
 pub mod unordered_hash_map;
 pub mod unordered_hash_set;
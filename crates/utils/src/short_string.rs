@@ -0,0 +1,67 @@
+use num_bigint::{BigInt, Sign};
+use thiserror::Error;
+
+#[cfg(test)]
+#[path = "short_string_test.rs"]
+mod test;
+
+/// The maximal number of characters in a short string. Longer strings may not fit in a felt.
+pub const MAX_SHORT_STRING_LEN: usize = 31;
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum ShortStringError {
+    #[error("Short strings are limited to {MAX_SHORT_STRING_LEN} characters, got {0}.")]
+    TooLong(usize),
+    #[error("Short strings can only include ASCII characters.")]
+    NotAscii,
+}
+
+/// Encodes `s` as a Cairo short string - the big-endian number whose bytes are the characters of
+/// the string.
+pub fn from_str(s: &str) -> Result<BigInt, ShortStringError> {
+    validate(s)?;
+    Ok(BigInt::from_bytes_be(Sign::Plus, s.as_bytes()))
+}
+
+/// Encodes `s` as the little-endian number whose bytes are the characters of the string, with the
+/// same limits as [from_str]. This is the encoding of the selectors of system calls.
+pub fn from_str_le(s: &str) -> Result<BigInt, ShortStringError> {
+    validate(s)?;
+    Ok(BigInt::from_bytes_le(Sign::Plus, s.as_bytes()))
+}
+
+/// Checks that `s` can be encoded into a felt.
+fn validate(s: &str) -> Result<(), ShortStringError> {
+    if !s.is_ascii() {
+        return Err(ShortStringError::NotAscii);
+    }
+    if s.len() > MAX_SHORT_STRING_LEN {
+        return Err(ShortStringError::TooLong(s.len()));
+    }
+    Ok(())
+}
+
+/// Returns the Cairo short string encoded in `value`, if it consists only of printable ASCII
+/// characters and fits in a short string.
+pub fn to_str(value: &BigInt) -> Option<String> {
+    let (sign, bytes) = value.to_bytes_be();
+    if sign != Sign::Plus || bytes.len() > MAX_SHORT_STRING_LEN {
+        return None;
+    }
+    if !bytes.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Formats felts for debug printing, one per line, adding the short string representation of
+/// values that can be decoded as such.
+pub fn format_for_debug(values: &[BigInt]) -> String {
+    values
+        .iter()
+        .map(|value| match to_str(value) {
+            Some(short_string) => format!("[DEBUG]\t{value} ('{short_string}')\n"),
+            None => format!("[DEBUG]\t{value}\n"),
+        })
+        .collect()
+}
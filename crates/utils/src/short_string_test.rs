@@ -0,0 +1,45 @@
+use num_bigint::{BigInt, Sign};
+use test_case::test_case;
+
+use super::{format_for_debug, from_str, from_str_le, to_str, ShortStringError};
+
+#[test_case(BigInt::from_bytes_be(Sign::Plus, b"hello"), Some("hello"); "printable")]
+#[test_case(BigInt::from_bytes_be(Sign::Plus, b"a b"), Some("a b"); "with space")]
+#[test_case(BigInt::from(0), None; "zero")]
+#[test_case(BigInt::from(7), None; "non printable")]
+#[test_case(BigInt::from(-97), None; "negative")]
+#[test_case(BigInt::from_bytes_be(Sign::Plus, &[b'a'; 32]), None; "too long")]
+fn short_string(value: BigInt, expected: Option<&str>) {
+    assert_eq!(to_str(&value), expected.map(|s| s.to_string()));
+}
+
+#[test_case("", Ok(BigInt::from(0)); "empty")]
+#[test_case("Hi", Ok(BigInt::from(0x4869)); "short")]
+#[test_case(&"a".repeat(31), Ok(BigInt::from_bytes_be(Sign::Plus, &[b'a'; 31])); "max length")]
+#[test_case(&"a".repeat(32), Err(ShortStringError::TooLong(32)); "too long")]
+#[test_case("caf\u{e9}", Err(ShortStringError::NotAscii); "not ascii")]
+fn encode(s: &str, expected: Result<BigInt, ShortStringError>) {
+    assert_eq!(from_str(s), expected);
+}
+
+#[test_case("Hi", Ok(BigInt::from(0x6948)); "short")]
+#[test_case(&"a".repeat(32), Err(ShortStringError::TooLong(32)); "too long")]
+#[test_case("caf\u{e9}", Err(ShortStringError::NotAscii); "not ascii")]
+fn encode_le(s: &str, expected: Result<BigInt, ShortStringError>) {
+    assert_eq!(from_str_le(s), expected);
+}
+
+#[test_case("hello"; "word")]
+#[test_case("storage_write"; "with underscore")]
+#[test_case(&"z".repeat(31); "max length")]
+fn round_trip(s: &str) {
+    assert_eq!(to_str(&from_str(s).unwrap()).as_deref(), Some(s));
+}
+
+#[test]
+fn debug_format() {
+    assert_eq!(
+        format_for_debug(&[BigInt::from_bytes_be(Sign::Plus, b"Hi"), BigInt::from(5)]),
+        "[DEBUG]\t18537 ('Hi')\n[DEBUG]\t5\n"
+    );
+}
@@ -2,11 +2,13 @@ use std::borrow::Borrow;
 use std::collections::HashSet;
 use std::hash::Hash;
 
+use crate::hash_state::HashState;
+
 /// A hash set that does not care about the order of insertion.
 /// In particular, it does not support iterating, in order to guarantee deterministic compilation.
 /// For an iterable version see [OrderedHashSet](crate::ordered_hash_set::OrderedHashSet).
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct UnorderedHashSet<Key: Hash + Eq>(HashSet<Key>);
+pub struct UnorderedHashSet<Key: Hash + Eq>(HashSet<Key, HashState>);
 
 impl<Key: Hash + Eq> UnorderedHashSet<Key> {
     /// Inserts the value into the set.
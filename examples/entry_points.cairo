@@ -0,0 +1,15 @@
+// Functions with separate entry points, two of which share a helper.
+func square(a: felt) -> felt {
+    a * a
+}
+
+func sum_of_squares(a: felt, b: felt) -> felt {
+    square(a) + square(b)
+}
+
+func fib(a: felt, b: felt, n: felt) -> felt {
+    match n {
+        0 => a,
+        _ => fib(b, a + b, n - 1),
+    }
+}
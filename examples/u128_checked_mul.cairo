@@ -0,0 +1,4 @@
+// Multiplies two u128s, returning `None` on overflow.
+func checked_mul(a: u128, b: u128) -> Option::<u128> implicits(RangeCheck) nopanic {
+    integer::u128_checked_mul(a, b)
+}
@@ -1,15 +1,37 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::rc::Rc;
 
 use assert_matches::assert_matches;
+use casm::ap_change::ApChange;
 use compiler::db::RootDatabase;
 use compiler::diagnostics::check_and_eprint_diagnostics;
 use compiler::project::setup_project;
 use filesystem::ids::CrateId;
-use num_bigint::BigInt;
-use runner::{RunResultValue, SierraCasmRunner};
+use indoc::indoc;
+use num_bigint::{BigInt, BigUint};
+use runner::entry_point::{run_entry_point, EmittedEvent, EntryPointRunConfig};
+use runner::test_env::TestEnv;
+use runner::{RunResultValue, RunnerError, SierraCasmRunner};
+use semantic::corelib::{core_felt_ty, get_core_ty_by_name};
+use semantic::test_utils::SemanticDatabaseForTesting;
+use sierra::extensions::builtin_cost::CostTokenType;
+use sierra::extraction::extract_entry_point_program;
+use sierra::ids::FunctionId;
+use sierra::program::StatementIdx;
+use sierra_ap_change::StackLayout;
+use sierra_gas::calc_gas_info;
+use sierra_gas::gas_withdrawals::insert_gas_withdrawals;
 use sierra_generator::db::SierraGenGroup;
 use sierra_generator::replace_ids::replace_sierra_ids_in_program;
-use sierra_to_casm::test_utils::build_metadata;
+use sierra_to_casm::test_utils::{build_metadata, storage_writes_program};
+use starknet::casm_contract_class::CasmContractClass;
+use starknet::contract::starknet_keccak;
+use starknet::contract_class::{
+    compile_path_with_config, ContractClass, ContractCompilationConfig, ContractEntryPoint,
+    ContractEntryPoints,
+};
+use starknet::events::{EventDefinition, EventField};
 use test_case::test_case;
 use test_utils::compare_contents_or_fix_with_path;
 use utils::extract_matches;
@@ -99,6 +121,33 @@ fn cairo_to_casm(name: &str, enable_gas_checks: bool) {
     );
 }
 
+/// Tests that compiling the same example with different hash seeds yields byte-identical artifacts,
+/// i.e. that no output of the compilation depends on the iteration order of a hash map.
+#[test]
+fn deterministic_compilation() {
+    let compile = |seed| {
+        utils::hash_state::set_hash_seed(Some(seed));
+        let program = checked_compile_to_sierra("hash_chain_gas");
+        let casm =
+            sierra_to_casm::compiler::compile(&program, &build_metadata(&program, true), true)
+                .unwrap();
+        utils::hash_state::set_hash_seed(None);
+        let assembled = casm.assemble();
+        let mut casm_bytes = vec![];
+        for word in &assembled.bytecode {
+            casm_bytes.extend(word.to_signed_bytes_le());
+            casm_bytes.push(b',');
+        }
+        casm_bytes.extend(format!("{:?}", assembled.hints).into_bytes());
+        let debug_info_bytes = serde_json::to_vec(&casm.debug_info.section()).unwrap();
+        (program.to_string().into_bytes(), casm_bytes, debug_info_bytes)
+    };
+    let expected = compile(0);
+    for seed in 1..50 {
+        assert_eq!(compile(seed), expected, "Compilation with hash seed {seed} differs.");
+    }
+}
+
 #[test_case("fib")]
 #[test_case("fib_box")]
 #[test_case("fib_array")]
@@ -111,6 +160,8 @@ fn cairo_to_casm(name: &str, enable_gas_checks: bool) {
 #[test_case("fib_unary")]
 #[test_case("corelib_usage")]
 #[test_case("hash_chain")]
+#[test_case("u128_checked_mul")]
+#[test_case("entry_points")]
 #[test_case("testing")]
 fn lowering_test(name: &str) {
     setup(name);
@@ -189,6 +240,32 @@ fn lowering_test(name: &str) {
     //     b"2dca1ad81a6107a9ef68c69f791bcdbda1df257aab76bd43ded73d96ed6227d", 16).unwrap()])
     => ignore["reason"];
     "hash_chain")]
+#[test_case(
+    "u128_checked_mul",
+    &[BigInt::from(1) << 64, (BigInt::from(1) << 64) - 1], None =>
+    RunResultValue::Success(
+        vec![/*ok*/BigInt::from(0), /*product*/(BigInt::from(1) << 128) - (BigInt::from(1) << 64)]
+    );
+    "u128_checked_mul"
+)]
+#[test_case(
+    "u128_checked_mul",
+    &[BigInt::from(0), BigInt::from(u128::MAX)], None =>
+    RunResultValue::Success([/*ok*/0, /*product*/0].map(BigInt::from).into_iter().collect());
+    "u128_checked_mul_zero"
+)]
+#[test_case(
+    "u128_checked_mul",
+    &[BigInt::from(1) << 64, BigInt::from(1) << 64], None =>
+    RunResultValue::Success([/*err*/1, /*padding*/0].map(BigInt::from).into_iter().collect());
+    "u128_checked_mul_overflow"
+)]
+#[test_case(
+    "u128_checked_mul",
+    &[BigInt::from(2), BigInt::from(1) << 127], None =>
+    RunResultValue::Success([/*err*/1, /*padding*/0].map(BigInt::from).into_iter().collect());
+    "u128_checked_mul_small_operand_overflow"
+)]
 #[test_case("testing", &[], None => RunResultValue::Success(vec![]); "testing")]
 fn run_function_test(
     name: &str,
@@ -203,6 +280,43 @@ fn run_function_test(
     result.value
 }
 
+/// Tests that the ap tracking of the debug information of each executed instruction matches the
+/// ap of the run, relative to the frame of the instruction.
+#[test_case("fib_local", &[6].map(BigInt::from), None; "fib_local")]
+#[test_case("fib_gas", &[1, 1, 10].map(BigInt::from), Some(200000); "fib_gas")]
+#[test_case("fib_array", &[5].map(BigInt::from), None; "fib_array")]
+fn run_function_ap_tracking(name: &str, params: &[BigInt], available_gas: Option<usize>) {
+    let runner = SierraCasmRunner::new(checked_compile_to_sierra(name), available_gas.is_some())
+        .expect("Failed setting up runner.");
+    let debug_info = runner.debug_info().section();
+    let result = runner
+        .run_function(/* find first */ "", params, &available_gas)
+        .expect("Failed running the function.");
+    let mut n_checked_steps = 0;
+    for entry in &result.trace {
+        let idx = debug_info
+            .instructions
+            .binary_search_by_key(&entry.pc, |info| info.code_offset)
+            .expect("Executed code offsets must start instructions.");
+        if let ApChange::Known(ap_tracking) = debug_info.instructions[idx].ap_tracking {
+            assert_eq!(
+                entry.ap - entry.fp,
+                ap_tracking,
+                "Wrong ap tracking at code offset {}.",
+                entry.pc
+            );
+            n_checked_steps += 1;
+        }
+    }
+    assert!(n_checked_steps > 0);
+    // All the steps out of the runtime routines are attributed to functions.
+    let routines_code_offset = debug_info.statements.last().unwrap().code.end;
+    assert_eq!(
+        result.function_steps.values().sum::<usize>(),
+        result.trace.iter().filter(|entry| entry.pc < routines_code_offset).count()
+    );
+}
+
 #[test_case(2, 1)]
 #[test_case(3, 2)]
 #[test_case(4, 3)]
@@ -221,3 +335,1098 @@ fn run_fib_array_len(n: usize, last: usize) {
         [_, _, actual_last, actual_len] if actual_last == &BigInt::from(last) && actual_len == &BigInt::from(n)
     );
 }
+
+/// Tests that the program extracted for a function of an example runs the function as the original
+/// program does, with only the functions it needs.
+#[test_case("::square", &[5].map(BigInt::from), 1; "square")]
+#[test_case("::sum_of_squares", &[3, 4].map(BigInt::from), 2; "sum_of_squares")]
+#[test_case("::fib", &[1, 1, 7].map(BigInt::from), 1; "fib")]
+fn run_extracted_entry_point(name_suffix: &str, params: &[BigInt], expected_funcs_count: usize) {
+    let program = checked_compile_to_sierra("entry_points");
+    let function = program
+        .funcs
+        .iter()
+        .find(|func| func.id.debug_name.as_ref().unwrap().ends_with(name_suffix))
+        .unwrap();
+    let extracted = extract_entry_point_program(&program, &function.id).unwrap();
+    assert_eq!(extracted.funcs.len(), expected_funcs_count);
+    let run = |program| {
+        SierraCasmRunner::new(program, false)
+            .expect("Failed setting up runner.")
+            .run_function(name_suffix, params, &None)
+            .expect("Failed running the function.")
+            .value
+    };
+    assert_eq!(run(extracted), run(program));
+}
+
+#[test]
+fn run_debug_print() {
+    let program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type ArrayFelt = Array<felt>;
+
+            libfunc array_new_felt = array_new<felt>;
+            libfunc array_append_felt = array_append<felt>;
+            libfunc felt_const_hi = felt_const<18537>;
+            libfunc felt_const_5 = felt_const<5>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc debug_print = debug_print;
+            libfunc array_felt_drop = drop<ArrayFelt>;
+
+            array_new_felt() -> ([0]);
+            felt_const_hi() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            array_append_felt([0], [1]) -> ([0]);
+            felt_const_5() -> ([2]);
+            store_temp_felt([2]) -> ([2]);
+            array_append_felt([0], [2]) -> ([0]);
+            debug_print([0]) -> ([0]);
+            array_felt_drop([0]) -> ();
+            return();
+
+            debug_print_test@0() -> ();
+        "})
+        .unwrap();
+    let runner = SierraCasmRunner::new(program, false).expect("Failed setting up runner.");
+    let mut debug_output = vec![];
+    let result = runner
+        .run_function_with_debug_output("debug_print_test", &[], &None, &mut debug_output)
+        .expect("Failed running the function.");
+    assert_eq!(result.value, RunResultValue::Success(vec![]));
+    assert_eq!(String::from_utf8(debug_output).unwrap(), "[DEBUG]\t18537 ('Hi')\n[DEBUG]\t5\n");
+}
+
+#[test_case("is_zero", &[0] => 1; "deref_zero")]
+#[test_case("is_zero", &[7] => 0; "deref_non_zero")]
+#[test_case("is_equal", &[5, 5] => 1; "sub_zero")]
+#[test_case("is_equal", &[5, 3] => 0; "sub_non_zero")]
+#[test_case("is_minus_3", &[-3] => 1; "add_const_zero")]
+#[test_case("is_minus_3", &[3] => 0; "add_const_non_zero")]
+fn run_jump_nz(name: &str, params: &[i64]) -> i64 {
+    // Each function returns 1 if the checked value is zero, and 0 otherwise. The checked value is
+    // a dereference in `is_zero` and `is_minus_3`, where the added constant has to be stored
+    // explicitly, and a deferred expression in `is_equal`.
+    let program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type NonZeroFelt = NonZero<felt>;
+
+            libfunc felt_sub = felt_sub;
+            libfunc felt_add_3 = felt_add<3>;
+            libfunc felt_jump_nz = felt_jump_nz;
+            libfunc felt_const_0 = felt_const<0>;
+            libfunc felt_const_1 = felt_const<1>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc drop_nz_felt = drop<NonZeroFelt>;
+
+            felt_jump_nz([0]) { fallthrough() 4([0]) };
+            felt_const_1() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            return([1]);
+            drop_nz_felt([0]) -> ();
+            felt_const_0() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            return([1]);
+            felt_sub([0], [1]) -> ([0]);
+            felt_jump_nz([0]) { fallthrough() 13([0]) };
+            felt_const_1() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            return([1]);
+            drop_nz_felt([0]) -> ();
+            felt_const_0() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            return([1]);
+            felt_add_3([0]) -> ([0]);
+            store_temp_felt([0]) -> ([0]);
+            felt_jump_nz([0]) { fallthrough() 23([0]) };
+            felt_const_1() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            return([1]);
+            drop_nz_felt([0]) -> ();
+            felt_const_0() -> ([1]);
+            store_temp_felt([1]) -> ([1]);
+            return([1]);
+
+            is_zero@0([0]: felt) -> (felt);
+            is_equal@8([0]: felt, [1]: felt) -> (felt);
+            is_minus_3@17([0]: felt) -> (felt);
+        "})
+        .unwrap();
+    let runner = SierraCasmRunner::new(program, false).expect("Failed setting up runner.");
+    let params = params.iter().map(|param| BigInt::from(*param)).collect::<Vec<_>>();
+    let result = runner.run_function(name, &params, &None).expect("Failed running the function.");
+    let value = extract_matches!(result.value, RunResultValue::Success);
+    assert_eq!(value.len(), 1);
+    i64::try_from(&value[0]).unwrap()
+}
+
+#[test]
+fn run_function_call_multiple_results() {
+    // `five_wrapper` returns the results of `five` as is, which requires no copies after the call.
+    let program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+
+            libfunc felt_dup = dup<felt>;
+            libfunc felt_add_1 = felt_add<1>;
+            libfunc felt_add_2 = felt_add<2>;
+            libfunc felt_add_3 = felt_add<3>;
+            libfunc felt_add_4 = felt_add<4>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc call_five = function_call<user@five>;
+
+            store_temp_felt([1]) -> ([1]);
+            call_five([1]) -> ([1], [2], [3], [4], [5]);
+            return([1], [2], [3], [4], [5]);
+            felt_dup([1]) -> ([1], [2]);
+            felt_add_1([2]) -> ([2]);
+            felt_dup([1]) -> ([1], [3]);
+            felt_add_2([3]) -> ([3]);
+            felt_dup([1]) -> ([1], [4]);
+            felt_add_3([4]) -> ([4]);
+            felt_dup([1]) -> ([1], [5]);
+            felt_add_4([5]) -> ([5]);
+            store_temp_felt([1]) -> ([1]);
+            store_temp_felt([2]) -> ([2]);
+            store_temp_felt([3]) -> ([3]);
+            store_temp_felt([4]) -> ([4]);
+            store_temp_felt([5]) -> ([5]);
+            return([1], [2], [3], [4], [5]);
+
+            five_wrapper@0([1]: felt) -> (felt, felt, felt, felt, felt);
+            five@3([1]: felt) -> (felt, felt, felt, felt, felt);
+        "})
+        .unwrap();
+    let runner = SierraCasmRunner::new(program, false).expect("Failed setting up runner.");
+    let result = runner
+        .run_function("five_wrapper", &[BigInt::from(7)], &None)
+        .expect("Failed running the function.");
+    assert_eq!(result.value, RunResultValue::Success((7..12).map(BigInt::from).collect()));
+}
+
+#[test_case(None, "make", &[1, 2] => vec![1, 2, 5]; "make by value")]
+#[test_case(Some(2), "make", &[1, 2] => vec![1, 2, 5]; "make by pointer")]
+#[test_case(None, "first", &[7, 8, 9] => vec![7]; "first by value")]
+#[test_case(Some(2), "first", &[7, 8, 9] => vec![7]; "first by pointer")]
+#[test_case(None, "make_and_get", &[4, 6] => vec![4]; "make_and_get by value")]
+#[test_case(Some(2), "make_and_get", &[4, 6] => vec![4]; "make_and_get by pointer")]
+fn run_values_by_pointer(
+    by_pointer_threshold: Option<usize>,
+    name: &str,
+    params: &[i64],
+) -> Vec<i64> {
+    let program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type Triple = Struct<ut@Triple, felt, felt, felt>;
+
+            libfunc felt_const_5 = felt_const<5>;
+            libfunc triple_construct = struct_construct<Triple>;
+            libfunc triple_deconstruct = struct_deconstruct<Triple>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc store_temp_triple = store_temp<Triple>;
+            libfunc drop_felt = drop<felt>;
+            libfunc call_make = function_call<user@make>;
+            libfunc call_first = function_call<user@first>;
+
+            felt_const_5() -> ([3]);
+            triple_construct([1], [2], [3]) -> ([4]);
+            store_temp_triple([4]) -> ([4]);
+            return([4]);
+            store_temp_felt([1]) -> ([1]);
+            store_temp_felt([2]) -> ([2]);
+            call_make([1], [2]) -> ([3]);
+            felt_const_5() -> ([4]);
+            store_temp_felt([4]) -> ([4]);
+            drop_felt([4]) -> ();
+            store_temp_triple([3]) -> ([3]);
+            call_first([3]) -> ([5]);
+            return([5]);
+            triple_deconstruct([1]) -> ([2], [3], [4]);
+            drop_felt([3]) -> ();
+            drop_felt([4]) -> ();
+            store_temp_felt([2]) -> ([2]);
+            return([2]);
+
+            make@0([1]: felt, [2]: felt) -> (Triple);
+            make_and_get@4([1]: felt, [2]: felt) -> (felt);
+            first@13([1]: Triple) -> (felt);
+        "})
+        .unwrap();
+    let runner = SierraCasmRunner::new_with_stack_layout(
+        program,
+        false,
+        StackLayout { by_pointer_threshold },
+    )
+    .expect("Failed setting up runner.");
+    let params = params.iter().map(|param| BigInt::from(*param)).collect::<Vec<_>>();
+    let result = runner.run_function(name, &params, &None).expect("Failed running the function.");
+    extract_matches!(result.value, RunResultValue::Success)
+        .iter()
+        .map(|value| i64::try_from(value).unwrap())
+        .collect()
+}
+
+#[test]
+fn run_array_append_multi_cell() {
+    let program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type felt = felt;
+            type u128 = u128;
+            type Triple = Struct<ut@Triple, felt, felt, felt>;
+            type ArrayTriple = Array<Triple>;
+
+            libfunc array_new_triple = array_new<Triple>;
+            libfunc array_append_triple = array_append<Triple>;
+            libfunc array_len_triple = array_len<Triple>;
+            libfunc felt_const_1 = felt_const<1>;
+            libfunc felt_const_2 = felt_const<2>;
+            libfunc felt_const_3 = felt_const<3>;
+            libfunc struct_construct_triple = struct_construct<Triple>;
+            libfunc store_temp_triple = store_temp<Triple>;
+            libfunc dup_triple = dup<Triple>;
+            libfunc store_temp_array_triple = store_temp<ArrayTriple>;
+            libfunc drop_array_triple = drop<ArrayTriple>;
+            libfunc store_temp_u128 = store_temp<u128>;
+
+            array_new_triple() -> ([0]);
+            felt_const_1() -> ([1]);
+            felt_const_2() -> ([2]);
+            felt_const_3() -> ([3]);
+            struct_construct_triple([1], [2], [3]) -> ([4]);
+            store_temp_triple([4]) -> ([4]);
+            dup_triple([4]) -> ([4], [5]);
+            dup_triple([4]) -> ([4], [6]);
+            array_append_triple([0], [4]) -> ([0]);
+            array_append_triple([0], [5]) -> ([0]);
+            array_append_triple([0], [6]) -> ([0]);
+            store_temp_array_triple([0]) -> ([0]);
+            array_len_triple([0]) -> ([0], [7]);
+            drop_array_triple([0]) -> ();
+            store_temp_u128([7]) -> ([7]);
+            return([7]);
+
+            array_append_multi_cell_test@0() -> (u128);
+        "})
+        .unwrap();
+    let runner = SierraCasmRunner::new(program, false).expect("Failed setting up runner.");
+    let result = runner
+        .run_function("array_append_multi_cell_test", &[], &None)
+        .expect("Failed running the function.");
+    assert_eq!(result.value, RunResultValue::Success(vec![BigInt::from(3)]));
+}
+
+/// Returns the middle member of the element of `[(1, 2, 3), (4, 5, 6)]` at `index`, fetched by
+/// `array_get` and unboxed, or 0 if the index is out of range.
+#[test_case(0 => 2; "first")]
+#[test_case(1 => 5; "second")]
+#[test_case(2 => 0; "out_of_range")]
+fn run_array_get(index: i64) -> i64 {
+    let program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type RangeCheck = RangeCheck;
+            type felt = felt;
+            type u128 = u128;
+            type Triple = Struct<ut@Triple, felt, felt, felt>;
+            type ArrayTriple = Array<Triple>;
+            type BoxTriple = Box<Triple>;
+
+            libfunc array_new_triple = array_new<Triple>;
+            libfunc array_append_triple = array_append<Triple>;
+            libfunc array_get_triple = array_get<Triple>;
+            libfunc unbox_triple = unbox<Triple>;
+            libfunc felt_const_0 = felt_const<0>;
+            libfunc felt_const_1 = felt_const<1>;
+            libfunc felt_const_2 = felt_const<2>;
+            libfunc felt_const_3 = felt_const<3>;
+            libfunc felt_const_4 = felt_const<4>;
+            libfunc felt_const_5 = felt_const<5>;
+            libfunc felt_const_6 = felt_const<6>;
+            libfunc struct_construct_triple = struct_construct<Triple>;
+            libfunc struct_deconstruct_triple = struct_deconstruct<Triple>;
+            libfunc store_temp_triple = store_temp<Triple>;
+            libfunc store_temp_array_triple = store_temp<ArrayTriple>;
+            libfunc drop_array_triple = drop<ArrayTriple>;
+            libfunc drop_felt = drop<felt>;
+            libfunc store_temp_rc = store_temp<RangeCheck>;
+            libfunc store_temp_felt = store_temp<felt>;
+
+            array_new_triple() -> ([2]);
+            felt_const_1() -> ([3]);
+            felt_const_2() -> ([4]);
+            felt_const_3() -> ([5]);
+            struct_construct_triple([3], [4], [5]) -> ([6]);
+            store_temp_triple([6]) -> ([6]);
+            array_append_triple([2], [6]) -> ([2]);
+            felt_const_4() -> ([3]);
+            felt_const_5() -> ([4]);
+            felt_const_6() -> ([5]);
+            struct_construct_triple([3], [4], [5]) -> ([6]);
+            store_temp_triple([6]) -> ([6]);
+            array_append_triple([2], [6]) -> ([2]);
+            store_temp_array_triple([2]) -> ([2]);
+            array_get_triple([0], [2], [1]) { fallthrough([0], [2], [7]) 23([0], [2]) };
+            drop_array_triple([2]) -> ();
+            unbox_triple([7]) -> ([8]);
+            struct_deconstruct_triple([8]) -> ([9], [10], [11]);
+            drop_felt([9]) -> ();
+            drop_felt([11]) -> ();
+            store_temp_rc([0]) -> ([0]);
+            store_temp_felt([10]) -> ([10]);
+            return([0], [10]);
+            drop_array_triple([2]) -> ();
+            felt_const_0() -> ([10]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_felt([10]) -> ([10]);
+            return([0], [10]);
+
+            test_get@0([0]: RangeCheck, [1]: u128) -> (RangeCheck, felt);
+        "})
+        .unwrap();
+    let runner = SierraCasmRunner::new(program, false).expect("Failed setting up runner.");
+    let result = runner
+        .run_function("test_get", &[BigInt::from(index)], &None)
+        .expect("Failed running the function.");
+    let value = extract_matches!(result.value, RunResultValue::Success);
+    assert_eq!(value.len(), 1);
+    i64::try_from(&value[0]).unwrap()
+}
+
+#[test]
+fn run_withdraw_gas_all() {
+    let program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type RangeCheck = RangeCheck;
+            type GasBuiltin = GasBuiltin;
+            type BuiltinCosts = BuiltinCosts;
+            type felt = felt;
+
+            libfunc withdraw_gas_all = withdraw_gas_all;
+            libfunc branch_align = branch_align;
+            libfunc felt_const_0 = felt_const<0>;
+            libfunc store_temp_rc = store_temp<RangeCheck>;
+            libfunc store_temp_gb = store_temp<GasBuiltin>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc call_mock = function_call<user@mock_call>;
+
+            withdraw_gas_all([0], [1], [2]) { fallthrough([0], [1], [3]) 8([0], [1]) };
+            branch_align() -> ();
+            store_temp_felt([3]) -> ([3]);
+            call_mock([3]) -> ([3]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_felt([3]) -> ([3]);
+            return([0], [1], [3]);
+            branch_align() -> ();
+            felt_const_0() -> ([3]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_felt([3]) -> ([3]);
+            return([0], [1], [3]);
+            store_temp_felt([0]) -> ([0]);
+            return([0]);
+
+            withdraw_gas_all_test@0([0]: RangeCheck, [1]: GasBuiltin, [2]: BuiltinCosts)
+                -> (RangeCheck, GasBuiltin, felt);
+            mock_call@14([0]: felt) -> (felt);
+        "})
+        .unwrap();
+    let available_gas = 10000;
+    let runner = SierraCasmRunner::new(program, true).expect("Failed setting up runner.");
+    let result = runner
+        .run_function(
+            "withdraw_gas_all_test",
+            // The builtin costs table is not accessed, as no builtins are used.
+            &[BigInt::from(0)],
+            &Some(available_gas),
+        )
+        .expect("Failed running the function.");
+    // The whole counter is drained, and the excess is forwarded through the mock call.
+    assert_eq!(result.gas_counter, Some(BigInt::from(0)));
+    let [excess] = &extract_matches!(result.value, RunResultValue::Success)[..] else {
+        panic!("Expected a single return value.");
+    };
+    assert!(*excess > BigInt::from(0) && *excess < BigInt::from(available_gas));
+}
+
+#[test]
+fn run_inserted_gas_withdrawals() {
+    // Nested loops counting every number below the argument down to zero, without withdrawing gas.
+    let program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type RangeCheck = RangeCheck;
+            type GasBuiltin = GasBuiltin;
+            type felt = felt;
+            type NonZeroFelt = NonZero<felt>;
+            type ArrayFelt = Array<felt>;
+            type PanicResult = Enum<ut@core::PanicResult::<core::felt>, felt, ArrayFelt>;
+
+            libfunc revoke_ap_tracking = revoke_ap_tracking;
+            libfunc felt_jump_nz = felt_jump_nz;
+            libfunc felt_unwrap_nz = unwrap_nz<felt>;
+            libfunc felt_sub_1 = felt_sub<1>;
+            libfunc felt_const_0 = felt_const<0>;
+            libfunc dup_felt = dup<felt>;
+            libfunc enum_init_ok = enum_init<PanicResult, 0>;
+            libfunc store_temp_rc = store_temp<RangeCheck>;
+            libfunc store_temp_gb = store_temp<GasBuiltin>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc store_temp_result = store_temp<PanicResult>;
+            libfunc jump = jump;
+
+            revoke_ap_tracking() -> ();
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_felt([2]) -> ([2]);
+            felt_jump_nz([2]) { fallthrough() 11([2]) };
+            felt_const_0() -> ([4]);
+            enum_init_ok([4]) -> ([4]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_result([4]) -> ([4]);
+            return([0], [1], [4]);
+            felt_unwrap_nz([2]) -> ([2]);
+            felt_sub_1([2]) -> ([2]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_felt([2]) -> ([2]);
+            dup_felt([2]) -> ([2], [3]);
+            store_temp_felt([3]) -> ([3]);
+            felt_jump_nz([3]) { fallthrough() 23([3]) };
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_felt([2]) -> ([2]);
+            jump() { 4() };
+            felt_unwrap_nz([3]) -> ([3]);
+            felt_sub_1([3]) -> ([3]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_felt([2]) -> ([2]);
+            store_temp_felt([3]) -> ([3]);
+            jump() { 18() };
+
+            count_nested@0([0]: RangeCheck, [1]: GasBuiltin, [2]: felt)
+                -> (RangeCheck, GasBuiltin, PanicResult);
+        "})
+        .unwrap();
+    let (program, inserted) =
+        insert_gas_withdrawals(&program).expect("Inserting the gas withdrawals failed.");
+    assert_eq!(inserted.len(), 2);
+    let gas_info = calc_gas_info(&program).expect("Solving the gas equations failed.");
+    let function_cost =
+        gas_info.function_costs[&FunctionId::from("count_nested")][CostTokenType::Step] as usize;
+    let runner = SierraCasmRunner::new(program, true).expect("Failed setting up runner.");
+    let run = |available_gas| {
+        runner
+            .run_function("count_nested", &[BigInt::from(3)], &Some(available_gas))
+            .expect("Failed running the function.")
+            .value
+    };
+    assert_eq!(run(function_cost + 10000), RunResultValue::Success(vec![BigInt::from(0)]));
+    // Only the cost of the function is available, so the first withdrawal fails.
+    assert_eq!(
+        run(function_cost),
+        RunResultValue::Panic(vec![BigInt::from_bytes_be(num_bigint::Sign::Plus, b"OOG")])
+    );
+}
+
+/// Runs `array_len` or `array_len_checked` on a felt array given by its start and end.
+fn run_array_len(checked: bool, start: i64, end: i64) -> Result<RunResultValue, RunnerError> {
+    let program = sierra::ProgramParser::new()
+        .parse(&indoc::formatdoc! {"
+                type RangeCheck = RangeCheck;
+                type felt = felt;
+                type u128 = u128;
+                type ArrayFelt = Array<felt>;
+
+                libfunc array_len = array_len<felt>;
+                libfunc array_len_checked = array_len_checked<felt>;
+                libfunc drop_array = drop<ArrayFelt>;
+                libfunc store_temp_rc = store_temp<RangeCheck>;
+                libfunc store_temp_u128 = store_temp<u128>;
+
+                {}
+                drop_array([1]) -> ();
+                store_temp_rc([0]) -> ([0]);
+                store_temp_u128([2]) -> ([2]);
+                return([0], [2]);
+
+                test_len@0([0]: RangeCheck, [1]: ArrayFelt) -> (RangeCheck, u128);
+            ",
+            if checked {
+                "array_len_checked([0], [1]) -> ([0], [1], [2]);"
+            } else {
+                "array_len([1]) -> ([1], [2]);"
+            }
+        })
+        .unwrap();
+    SierraCasmRunner::new(program, false)
+        .expect("Failed setting up runner.")
+        .run_function("test_len", &[start, end].map(BigInt::from), &None)
+        .map(|result| result.value)
+}
+
+#[test]
+fn run_array_len_checked() {
+    assert_matches!(
+        run_array_len(true, 5, 8),
+        Ok(RunResultValue::Success(values)) if values == [BigInt::from(3)]
+    );
+    // An array whose end is before its start, as produced by corrupted offset bookkeeping.
+    assert_matches!(run_array_len(true, 8, 5), Err(RunnerError::RangeCheckViolation { .. }));
+    // Without the check the length silently wraps around the prime.
+    assert_matches!(
+        run_array_len(false, 8, 5),
+        Ok(RunResultValue::Success(values)) if values[0] > BigInt::from(u128::MAX)
+    );
+}
+
+/// Returns the address `contract_addr_from_felt` converts `value` into as a felt, or 0 if the
+/// conversion fails.
+#[test_case(BigInt::from(5) => BigInt::from(5); "in_range")]
+#[test_case((BigInt::from(1) << 251) - 1 => (BigInt::from(1) << 251) - 1; "max_address")]
+#[test_case(BigInt::from(1) << 251 => BigInt::from(0); "out_of_range")]
+#[test_case(BigInt::from(-1) => BigInt::from(0); "prime_minus_1")]
+fn run_contract_address_try_from_felt(value: BigInt) -> BigInt {
+    let program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type RangeCheck = RangeCheck;
+            type felt = felt;
+            type ContractAddress = ContractAddress;
+
+            libfunc contract_addr_from_felt = contract_addr_from_felt;
+            libfunc contract_addr_to_felt = contract_addr_to_felt;
+            libfunc branch_align = branch_align;
+            libfunc felt_const_0 = felt_const<0>;
+            libfunc store_temp_rc = store_temp<RangeCheck>;
+            libfunc store_temp_felt = store_temp<felt>;
+
+            contract_addr_from_felt([0], [1]) { fallthrough([0], [2]) 6([0]) };
+            branch_align() -> ();
+            contract_addr_to_felt([2]) -> ([3]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_felt([3]) -> ([3]);
+            return([0], [3]);
+            branch_align() -> ();
+            felt_const_0() -> ([3]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_felt([3]) -> ([3]);
+            return([0], [3]);
+
+            test_try_from@0([0]: RangeCheck, [1]: felt) -> (RangeCheck, felt);
+        "})
+        .unwrap();
+    let result = SierraCasmRunner::new(program, false)
+        .expect("Failed setting up runner.")
+        .run_function("test_try_from", &[value], &None)
+        .expect("Failed running the function.");
+    let [address] = &extract_matches!(result.value, RunResultValue::Success)[..] else {
+        panic!("Expected a single return value.");
+    };
+    address.clone()
+}
+
+/// Returns the storage base address `storage_base_from_felt` reduces `value` into.
+#[test_case(BigInt::from(5) => BigInt::from(5); "small")]
+#[test_case((BigInt::from(1) << 251) - 257 => (BigInt::from(1) << 251) - 257; "below_bound")]
+#[test_case((BigInt::from(1) << 251) - 256 => BigInt::from(0); "bound")]
+#[test_case((BigInt::from(1) << 251) - 255 => BigInt::from(1); "above_bound")]
+#[test_case(BigInt::from(-1) => (BigInt::from(17) << 192) + 256; "max_felt")]
+fn run_storage_base_address_from_felt(value: BigInt) -> BigInt {
+    let program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type RangeCheck = RangeCheck;
+            type felt = felt;
+            type StorageBaseAddress = StorageBaseAddress;
+
+            libfunc storage_base_from_felt = storage_base_from_felt;
+            libfunc store_temp_rc = store_temp<RangeCheck>;
+            libfunc store_temp_base = store_temp<StorageBaseAddress>;
+
+            storage_base_from_felt([0], [1]) -> ([0], [2]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_base([2]) -> ([2]);
+            return([0], [2]);
+
+            test_from_felt@0([0]: RangeCheck, [1]: felt) -> (RangeCheck, StorageBaseAddress);
+        "})
+        .unwrap();
+    let result = SierraCasmRunner::new(program, false)
+        .expect("Failed setting up runner.")
+        .run_function("test_from_felt", &[value], &None)
+        .expect("Failed running the function.");
+    let [address] = &extract_matches!(result.value, RunResultValue::Success)[..] else {
+        panic!("Expected a single return value.");
+    };
+    address.clone()
+}
+
+#[test_case(100 => 1; "enough_gas")]
+#[test_case(99 => 0; "not_enough_gas")]
+fn run_gas_reserve(initial_gas: usize) -> i64 {
+    let program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type RangeCheck = RangeCheck;
+            type GasBuiltin = GasBuiltin;
+            type GasReserve = GasReserve;
+            type felt = felt;
+
+            libfunc gas_reserve_create = gas_reserve_create<100>;
+            libfunc gas_reserve_release = gas_reserve_release;
+            libfunc branch_align = branch_align;
+            libfunc felt_const_0 = felt_const<0>;
+            libfunc felt_const_1 = felt_const<1>;
+            libfunc store_temp_rc = store_temp<RangeCheck>;
+            libfunc store_temp_gb = store_temp<GasBuiltin>;
+            libfunc store_temp_felt = store_temp<felt>;
+
+            gas_reserve_create([0], [1]) { fallthrough([0], [1], [2]) 8([0], [1]) };
+            branch_align() -> ();
+            gas_reserve_release([1], [2]) -> ([1]);
+            felt_const_1() -> ([3]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_felt([3]) -> ([3]);
+            return([0], [1], [3]);
+            branch_align() -> ();
+            felt_const_0() -> ([3]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_felt([3]) -> ([3]);
+            return([0], [1], [3]);
+
+            test_reserve@0([0]: RangeCheck, [1]: GasBuiltin) -> (RangeCheck, GasBuiltin, felt);
+        "})
+        .unwrap();
+    let gas_info = calc_gas_info(&program).expect("Solving the gas equations failed.");
+    let function_cost =
+        gas_info.function_costs[&FunctionId::from("test_reserve")][CostTokenType::Step] as usize;
+    let runner = SierraCasmRunner::new(program, true).expect("Failed setting up runner.");
+    let result = runner
+        .run_function("test_reserve", &[], &Some(function_cost + initial_gas))
+        .expect("Failed running the function.");
+    // Whether or not the reserve was created, all the gas is back in the counter.
+    assert_eq!(result.gas_counter, Some(BigInt::from(initial_gas)));
+    let [value] = &extract_matches!(result.value, RunResultValue::Success)[..] else {
+        panic!("Expected a single return value.");
+    };
+    value.try_into().unwrap()
+}
+
+#[test_case(false; "unchecked_storage_addresses")]
+#[test_case(true; "checked_storage_addresses")]
+fn run_contract_entry_point(check_storage_addresses: bool) {
+    // Pop the "/tests" suffix.
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).parent().unwrap().to_owned();
+    path.extend(["crates", "starknet", "test_data", "test_contract.cairo"]);
+    let contract_class = compile_path_with_config(
+        &path,
+        true,
+        &ContractCompilationConfig { check_storage_addresses, ..Default::default() },
+    )
+    .expect("Compilation failed.");
+    let casm_class =
+        CasmContractClass::from_contract_class(contract_class).expect("Compilation failed.");
+    let selector = starknet_keccak(b"test");
+    let available_gas = 1000000;
+    let run = |storage| {
+        run_entry_point(
+            &casm_class,
+            &selector,
+            &[5, 6, 7].map(BigInt::from),
+            EntryPointRunConfig { available_gas, storage },
+        )
+        .expect("Failed running the entry point.")
+    };
+
+    // `test` returns the value of the `ref` argument, and the stored value plus one, which it
+    // also stores.
+    let first = run(HashMap::new());
+    assert_eq!(first.value, RunResultValue::Success([5, 1].map(BigInt::from).to_vec()));
+    assert_eq!(first.storage.values().collect::<Vec<_>>(), vec![&BigInt::from(1)]);
+    let second = run(first.storage);
+    assert_eq!(second.value, RunResultValue::Success([5, 2].map(BigInt::from).to_vec()));
+    assert_eq!(second.storage.values().collect::<Vec<_>>(), vec![&BigInt::from(2)]);
+
+    // Both runs take the same path, so they use the same amount of gas.
+    let gas_counter = first.gas_counter.expect("The entry point uses the gas builtin.");
+    assert!(gas_counter > BigInt::from(0) && gas_counter < BigInt::from(available_gas));
+    assert_eq!(second.gas_counter, Some(gas_counter));
+}
+
+#[test]
+fn run_test_env_snapshot() {
+    // Pop the "/tests" suffix.
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).parent().unwrap().to_owned();
+    path.extend(["crates", "starknet", "test_data", "test_contract.cairo"]);
+    let contract_class =
+        compile_path_with_config(&path, true, &Default::default()).expect("Compilation failed.");
+    let casm_class = Rc::new(
+        CasmContractClass::from_contract_class(contract_class).expect("Compilation failed."),
+    );
+    let selector = starknet_keccak(b"test");
+    // `test` returns the value of the `ref` argument, and the stored value plus one, which it
+    // also stores.
+    let invoke = |env: &mut TestEnv, address: &BigInt| {
+        env.invoke(address, &selector, &[5, 6, 7].map(BigInt::from), 1000000)
+            .expect("Failed running the entry point.")
+            .value
+    };
+    let n_setup_invocations = 5;
+    let setup = || {
+        let mut env = TestEnv::default();
+        let address = env.deploy(casm_class.clone());
+        for _ in 0..n_setup_invocations {
+            invoke(&mut env, &address);
+        }
+        (env, address)
+    };
+    let expected_case_value =
+        RunResultValue::Success([5, n_setup_invocations + 1].map(BigInt::from).to_vec());
+
+    // Both cases run from the same snapshot, so neither observes the changes of the other.
+    let (mut env, address) = setup();
+    let snapshot = env.snapshot();
+    assert_eq!(invoke(&mut env, &address), expected_case_value);
+    assert_eq!(env.state().nonce(&address), BigInt::from(n_setup_invocations + 1));
+    env.restore(&snapshot);
+    assert_eq!(env.state().nonce(&address), BigInt::from(n_setup_invocations));
+    assert_eq!(invoke(&mut env, &address), expected_case_value);
+
+    // Restoring the snapshot discards the changes of the case, leaving the same state as repeating
+    // the setup would.
+    env.restore(&snapshot);
+    assert_eq!(env.state(), setup().0.state());
+}
+
+#[test]
+fn run_checked_storage_read_entry_point() {
+    // An entry point reading the storage address given as its calldata. Such an address can not be
+    // created out of range by Cairo code, so the program is written in Sierra.
+    let sierra_program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type RangeCheck = RangeCheck;
+            type GasBuiltin = GasBuiltin;
+            type System = System;
+            type felt = felt;
+            type u128 = u128;
+            type StorageAddress = StorageAddress;
+            type ArrayStorageAddress = Array<StorageAddress>;
+            type ArrayFelt = Array<felt>;
+            type PanicResult = Enum<ut@core::PanicResult::<core::array::Array::<core::felt>>,
+                                    ArrayFelt, ArrayFelt>;
+
+            libfunc u128_const_0 = u128_const<0>;
+            libfunc store_temp_u128 = store_temp<u128>;
+            libfunc array_at_address = array_at<StorageAddress>;
+            libfunc branch_align = branch_align;
+            libfunc drop_addresses = drop<ArrayStorageAddress>;
+            libfunc store_temp_rc = store_temp<RangeCheck>;
+            libfunc store_temp_gb = store_temp<GasBuiltin>;
+            libfunc store_temp_system = store_temp<System>;
+            libfunc store_temp_address = store_temp<StorageAddress>;
+            libfunc felt_const_0 = felt_const<0>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc storage_read = storage_read_checked;
+            libfunc drop_felt = drop<felt>;
+            libfunc array_new_felt = array_new<felt>;
+            libfunc array_append_felt = array_append<felt>;
+            libfunc panic_result_ok = enum_init<PanicResult, 0>;
+            libfunc panic_result_err = enum_init<PanicResult, 1>;
+            libfunc store_temp_panic_result = store_temp<PanicResult>;
+
+            u128_const_0() -> ([4]);
+            store_temp_u128([4]) -> ([4]);
+            array_at_address([0], [3], [4]) { fallthrough([0], [3], [5]) 28([0], [3]) };
+            branch_align() -> ();
+            drop_addresses([3]) -> ();
+            store_temp_rc([0]) -> ([0]);
+            store_temp_address([5]) -> ([5]);
+            felt_const_0() -> ([9]);
+            store_temp_felt([9]) -> ([9]);
+            storage_read([0], [1], [2], [9], [5]) {
+                fallthrough([0], [1], [2], [6]) 19([0], [1], [2], [6])
+            };
+            branch_align() -> ();
+            drop_felt([6]) -> ();
+            array_new_felt() -> ([7]);
+            panic_result_ok([7]) -> ([8]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_system([2]) -> ([2]);
+            store_temp_panic_result([8]) -> ([8]);
+            return([0], [1], [2], [8]);
+            branch_align() -> ();
+            array_new_felt() -> ([7]);
+            array_append_felt([7], [6]) -> ([7]);
+            panic_result_err([7]) -> ([8]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_system([2]) -> ([2]);
+            store_temp_panic_result([8]) -> ([8]);
+            return([0], [1], [2], [8]);
+            branch_align() -> ();
+            drop_addresses([3]) -> ();
+            array_new_felt() -> ([4]);
+            panic_result_err([4]) -> ([5]);
+            store_temp_rc([0]) -> ([0]);
+            store_temp_gb([1]) -> ([1]);
+            store_temp_system([2]) -> ([2]);
+            store_temp_panic_result([5]) -> ([5]);
+            return([0], [1], [2], [5]);
+
+            read_storage@0(
+                [0]: RangeCheck, [1]: GasBuiltin, [2]: System, [3]: ArrayStorageAddress
+            ) -> (RangeCheck, GasBuiltin, System, PanicResult);
+        "})
+        .unwrap();
+    let selector = starknet_keccak(b"read_storage");
+    let casm_class = CasmContractClass::from_contract_class(ContractClass {
+        sierra_program,
+        entry_points_by_type: ContractEntryPoints {
+            external: vec![ContractEntryPoint { selector: selector.clone(), function_idx: 0 }],
+            ..Default::default()
+        },
+        abi: Default::default(),
+        storage_layout: Default::default(),
+        referenced_constants: Default::default(),
+        unchecked_storage_access: Default::default(),
+        error_codes: Default::default(),
+    })
+    .expect("Compilation failed.");
+    let run = |address: BigInt| {
+        run_entry_point(&casm_class, &selector, &[address], EntryPointRunConfig::default())
+    };
+
+    // The largest valid storage address is `2**251 - 257`.
+    let bound = (BigInt::from(1) << 251) - 256;
+    assert_eq!(
+        run(bound.clone() - 1).expect("Failed running the entry point.").value,
+        RunResultValue::Success(vec![])
+    );
+    assert_matches!(
+        run(bound),
+        Err(RunnerError::RangeCheckViolation { value }) if value == BigInt::from(1) << 128
+    );
+}
+
+#[test]
+fn run_storage_read_multi_entry_point() {
+    // An entry point reading a 3-cell struct from storage, and returning its members - or panicking
+    // with the revert reason if a read fails.
+    let sierra_program = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type GasBuiltin = GasBuiltin;
+            type System = System;
+            type felt = felt;
+            type StorageAddress = StorageAddress;
+            type ArrayFelt = Array<felt>;
+            type Triple = Struct<ut@Triple, felt, felt, felt>;
+            type PanicResult = Enum<ut@core::PanicResult::<core::array::Array::<core::felt>>,
+                                    ArrayFelt, ArrayFelt>;
+
+            libfunc drop_calldata = drop<ArrayFelt>;
+            libfunc address_const = storage_address_const<1000>;
+            libfunc store_temp_address = store_temp<StorageAddress>;
+            libfunc felt_const_0 = felt_const<0>;
+            libfunc store_temp_felt = store_temp<felt>;
+            libfunc read_triple = storage_read_multi<Triple>;
+            libfunc triple_deconstruct = struct_deconstruct<Triple>;
+            libfunc array_new_felt = array_new<felt>;
+            libfunc array_append_felt = array_append<felt>;
+            libfunc panic_result_ok = enum_init<PanicResult, 0>;
+            libfunc panic_result_err = enum_init<PanicResult, 1>;
+            libfunc drop_felt = drop<felt>;
+            libfunc store_temp_gb = store_temp<GasBuiltin>;
+            libfunc store_temp_system = store_temp<System>;
+            libfunc store_temp_panic_result = store_temp<PanicResult>;
+
+            drop_calldata([2]) -> ();
+            address_const() -> ([3]);
+            store_temp_address([3]) -> ([3]);
+            felt_const_0() -> ([10]);
+            store_temp_felt([10]) -> ([10]);
+            read_triple([0], [1], [10], [3]) {
+                fallthrough([0], [1], [4]) 16([0], [1], [11], [12])
+            };
+            triple_deconstruct([4]) -> ([5], [6], [7]);
+            array_new_felt() -> ([8]);
+            array_append_felt([8], [5]) -> ([8]);
+            array_append_felt([8], [6]) -> ([8]);
+            array_append_felt([8], [7]) -> ([8]);
+            panic_result_ok([8]) -> ([9]);
+            store_temp_gb([0]) -> ([0]);
+            store_temp_system([1]) -> ([1]);
+            store_temp_panic_result([9]) -> ([9]);
+            return([0], [1], [9]);
+            drop_felt([11]) -> ();
+            array_new_felt() -> ([8]);
+            array_append_felt([8], [12]) -> ([8]);
+            panic_result_err([8]) -> ([9]);
+            store_temp_gb([0]) -> ([0]);
+            store_temp_system([1]) -> ([1]);
+            store_temp_panic_result([9]) -> ([9]);
+            return([0], [1], [9]);
+
+            read_triple@0([0]: GasBuiltin, [1]: System, [2]: ArrayFelt)
+                -> (GasBuiltin, System, PanicResult);
+        "})
+        .unwrap();
+    let selector = starknet_keccak(b"read_triple");
+    let casm_class = CasmContractClass::from_contract_class(ContractClass {
+        sierra_program,
+        entry_points_by_type: ContractEntryPoints {
+            external: vec![ContractEntryPoint { selector: selector.clone(), function_idx: 0 }],
+            ..Default::default()
+        },
+        abi: Default::default(),
+        storage_layout: Default::default(),
+        referenced_constants: Default::default(),
+        unchecked_storage_access: Default::default(),
+        error_codes: Default::default(),
+    })
+    .expect("Compilation failed.");
+    let storage = (1000..1003).map(|address| (BigInt::from(address), BigInt::from(address - 993)));
+    let result = run_entry_point(
+        &casm_class,
+        &selector,
+        &[],
+        EntryPointRunConfig { storage: storage.collect(), ..Default::default() },
+    )
+    .expect("Failed running the entry point.");
+    assert_eq!(
+        result.value,
+        RunResultValue::Success(vec![BigInt::from(7), BigInt::from(8), BigInt::from(9)])
+    );
+}
+
+#[test]
+fn run_storage_writes_entry_point() {
+    // Five consecutive storage writes, where all the writes but the first read the selector from
+    // the cell the previous write stored it in - compared with the same writes with the ap
+    // tracking revoked before them, where every write stores the selector.
+    let selector = starknet_keccak(b"store_writes");
+    let compile_and_run = |revoke_ap_tracking| {
+        let sierra_program = sierra::ProgramParser::new()
+            .parse(&storage_writes_program(5, revoke_ap_tracking))
+            .unwrap();
+        let casm_class = CasmContractClass::from_contract_class(ContractClass {
+            sierra_program,
+            entry_points_by_type: ContractEntryPoints {
+                external: vec![ContractEntryPoint { selector: selector.clone(), function_idx: 0 }],
+                ..Default::default()
+            },
+            abi: Default::default(),
+            storage_layout: Default::default(),
+            referenced_constants: Default::default(),
+            unchecked_storage_access: Default::default(),
+            error_codes: Default::default(),
+        })
+        .expect("Compilation failed.");
+        let result = run_entry_point(&casm_class, &selector, &[], EntryPointRunConfig::default())
+            .expect("Failed running the entry point.");
+        (casm_class.bytecode.len(), result)
+    };
+    let (bytecode_len, result) = compile_and_run(false);
+    let (full_bytecode_len, full_result) = compile_and_run(true);
+    // Each of the 4 selectors that are not stored again saves an instruction of 2 words.
+    assert_eq!(full_bytecode_len - bytecode_len, 8);
+    assert_eq!(result.value, RunResultValue::Success(vec![]));
+    assert_eq!(
+        result.storage,
+        (1..=5).map(|i| (BigInt::from(1000 + i), BigInt::from(i))).collect()
+    );
+    assert_eq!(result.value, full_result.value);
+    assert_eq!(result.storage, full_result.storage);
+    assert_eq!(result.gas_counter, full_result.gas_counter);
+}
+
+#[test]
+fn run_emit_event_entry_point() {
+    let db = &SemanticDatabaseForTesting::default();
+    let event = EventDefinition::new(
+        db,
+        "Transfer".into(),
+        vec![
+            EventField { name: "amount".into(), ty: core_felt_ty(db), is_key: false },
+            EventField {
+                name: "to".into(),
+                ty: get_core_ty_by_name(db, "u128".into(), vec![]),
+                is_key: true,
+            },
+        ],
+    )
+    .unwrap();
+    let mut sierra_program = event.emit_function(db).expect("Generating the emit function failed.");
+    // An entry point emitting the event with constant fields, and returning the revert reason.
+    // Written in Sierra, as the generated function is not callable from Cairo code.
+    let wrapper = sierra::ProgramParser::new()
+        .parse(indoc! {"
+            type PanicResult = Enum<ut@core::PanicResult::<core::array::Array::<core::felt>>,
+                                    ArrayFelt, ArrayFelt>;
+
+            libfunc drop_calldata = drop<ArrayFelt>;
+            libfunc felt_const_7 = felt_const<7>;
+            libfunc u128_const_3 = u128_const<3>;
+            libfunc store_temp_u128 = store_temp<u128>;
+            libfunc call_emit = function_call<user@emit_Transfer>;
+            libfunc panic_result_ok = enum_init<PanicResult, 0>;
+            libfunc store_temp_panic_result = store_temp<PanicResult>;
+
+            drop_calldata([2]) -> ();
+            felt_const_7() -> ([3]);
+            u128_const_3() -> ([4]);
+            store_temp_gb([0]) -> ([0]);
+            store_temp_system([1]) -> ([1]);
+            store_temp_felt([3]) -> ([3]);
+            store_temp_u128([4]) -> ([4]);
+            call_emit([0], [1], [3], [4]) -> ([0], [1], [5]);
+            array_new_felt() -> ([6]);
+            array_append_felt([6], [5]) -> ([6]);
+            panic_result_ok([6]) -> ([7]);
+            store_temp_gb([0]) -> ([0]);
+            store_temp_system([1]) -> ([1]);
+            store_temp_panic_result([7]) -> ([7]);
+            return([0], [1], [7]);
+
+            emit_transfer@0([0]: GasBuiltin, [1]: System, [2]: ArrayFelt)
+                -> (GasBuiltin, System, PanicResult);
+        "})
+        .unwrap();
+    // The wrapper reuses the declarations of the generated program, such as its `felt`, `u128` and
+    // `ArrayFelt` types, and follows its statements.
+    let entry_point = sierra_program.statements.len();
+    sierra_program.type_declarations.extend(wrapper.type_declarations);
+    sierra_program.libfunc_declarations.extend(wrapper.libfunc_declarations);
+    sierra_program.statements.extend(wrapper.statements);
+    let function_idx = sierra_program.funcs.len();
+    sierra_program.funcs.extend(wrapper.funcs.into_iter().map(|mut function| {
+        function.entry_point = StatementIdx(entry_point);
+        function
+    }));
+
+    let selector = starknet_keccak(b"emit_transfer");
+    let casm_class = CasmContractClass::from_contract_class(ContractClass {
+        sierra_program,
+        entry_points_by_type: ContractEntryPoints {
+            external: vec![ContractEntryPoint { selector: selector.clone(), function_idx }],
+            ..Default::default()
+        },
+        abi: Default::default(),
+        storage_layout: Default::default(),
+        referenced_constants: Default::default(),
+        unchecked_storage_access: Default::default(),
+        error_codes: Default::default(),
+    })
+    .expect("Compilation failed.");
+    let result = run_entry_point(&casm_class, &selector, &[], EntryPointRunConfig::default())
+        .expect("Failed running the entry point.");
+    assert_eq!(result.value, RunResultValue::Success(vec![BigInt::from(0)]));
+
+    let (keys, data) =
+        event.layout(vec![vec![BigUint::from(7u32)], vec![BigUint::from(3u32)]]).unwrap();
+    let to_felts = |values: Vec<BigUint>| values.into_iter().map(BigInt::from).collect();
+    assert_eq!(result.events, vec![EmittedEvent { keys: to_felts(keys), data: to_felts(data) }]);
+}